@@ -0,0 +1,409 @@
+//! Completion: the names that can go at a cursor.
+
+use strata_ast::ast::{Block, Expr, Item, Module, Stmt};
+use strata_ast::span::{FileId, Span};
+use strata_parse::parse_str;
+use strata_types::adt::{AdtKind, VariantFields};
+use strata_types::infer::Ty;
+use strata_types::resolve::{scope_at, DefId};
+use strata_types::{TypeChecker, TypedItemKind, ALL_EFFECTS};
+
+/// Stands in for an empty partial word when the source doesn't parse
+/// without one (`let x = |;`)
+const PLACEHOLDER: &str = "__strata_completion";
+
+/// What a completion names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// A parameter or pattern binding
+    Local,
+    Function,
+    Extern,
+    /// A top-level `let`
+    Global,
+    Const,
+    /// An enum variant or tuple struct constructor
+    Constructor,
+    /// A struct or enum
+    Type,
+    Field,
+    Effect,
+}
+
+/// A name to offer at the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// The name's type when the checker resolved one (a function's includes
+    /// its effect row), a variant's fields, or a constructor's type
+    pub detail: Option<String>,
+}
+
+impl Completion {
+    fn new(label: impl Into<String>, kind: CompletionKind, detail: Option<String>) -> Self {
+        Self {
+            label: label.into(),
+            kind,
+            detail,
+        }
+    }
+}
+
+/// What the text before the partial word asks for
+enum Context<'a> {
+    /// A name in scope
+    Name,
+    /// A field of the dotted chain before `.`: `p.`, `line.start.`
+    Field(Vec<&'a str>),
+    /// A variant of the enum before `::`
+    Variant(&'a str),
+    /// An effect inside `& { }`
+    Effect,
+}
+
+/// Completions for the partial word ending at byte `offset` of `src`:
+/// names in scope there, a struct's or record's fields after `.`, an
+/// enum's variants after `Enum::`, or effect names inside `& { }`.
+///
+/// Code being edited often doesn't parse, so when `src` doesn't, the member
+/// access being typed is dropped (or a placeholder name stands in for an
+/// empty word) and that is tried instead. Types come from the items that
+/// check; a name the checker didn't reach has no detail.
+pub fn completions(src: &str, offset: usize) -> Vec<Completion> {
+    let mut offset = offset.min(src.len());
+    while !src.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let start = offset - word_len_before(&src[..offset]);
+    let prefix = &src[start..offset];
+    let before = &src[..start];
+
+    let (context, cut) = match before.strip_suffix("::") {
+        Some(rest) => match ident_before(rest) {
+            "" => return Vec::new(),
+            adt => (Context::Variant(adt), rest.len()),
+        },
+        None => match before.strip_suffix('.') {
+            Some(rest) => match receiver_chain(rest) {
+                Some(chain) => (Context::Field(chain), rest.len()),
+                None => return Vec::new(),
+            },
+            None if in_effect_row(before) => (Context::Effect, start),
+            None => (Context::Name, start),
+        },
+    };
+    if let Context::Effect = context {
+        return ALL_EFFECTS
+            .iter()
+            .map(|effect| format!("{:?}", effect))
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| Completion::new(name, CompletionKind::Effect, None))
+            .collect();
+    }
+
+    let Some(module) = parse_edited(src, cut, offset) else {
+        return Vec::new();
+    };
+    // Code being edited rarely checks; the items before the error still
+    // have their types
+    let mut checker = TypeChecker::new();
+    let _ = checker.check_module(&module);
+    // Nor does the function being edited, so its bindings are typed with
+    // the function stopped at the cursor
+    let mut locals = TypeChecker::new();
+    let _ = locals.check_module(&stopped_at(&module, cut as u32));
+    let types = Types {
+        module: &module,
+        checker: &checker,
+        locals: &locals,
+    };
+    let scope = scope_at(&module, FileId::default(), cut as u32);
+
+    let found = match context {
+        Context::Name => scope
+            .into_iter()
+            .filter(|(name, _)| name != PLACEHOLDER)
+            .map(|(name, def)| types.completion(name, &def))
+            .collect(),
+        Context::Field(chain) => {
+            let (first, rest) = chain.split_first().expect("chain is never empty");
+            let receiver = scope
+                .iter()
+                .find(|(name, _)| name == first)
+                .and_then(|(_, def)| types.of_def(first, def));
+            let receiver = rest
+                .iter()
+                .try_fold(receiver, |ty, field| {
+                    let fields = types.fields(&ty?);
+                    Some(
+                        fields
+                            .into_iter()
+                            .find(|(name, _)| name == field)
+                            .map(|(_, ty)| ty),
+                    )
+                })
+                .flatten();
+            receiver.map_or_else(Vec::new, |ty| {
+                types
+                    .fields(&ty)
+                    .into_iter()
+                    .map(|(name, ty)| {
+                        Completion::new(name, CompletionKind::Field, Some(ty.to_string()))
+                    })
+                    .collect()
+            })
+        }
+        Context::Variant(adt) => types.variants(adt),
+        Context::Effect => unreachable!("answered above"),
+    };
+    found
+        .into_iter()
+        .filter(|c: &Completion| c.label.starts_with(prefix))
+        .collect()
+}
+
+/// `src` parsed as it stands, or else with `src[cut..offset]` (the partial
+/// word and any `.`/`::` before it) dropped or, if that's empty, replaced
+/// by a placeholder name
+fn parse_edited(src: &str, cut: usize, offset: usize) -> Option<Module> {
+    if let Ok(module) = parse_str("<completion>", src) {
+        return Some(module);
+    }
+    let filler = if cut == offset { PLACEHOLDER } else { "" };
+    let edited = format!("{}{}{}", &src[..cut], filler, &src[offset..]);
+    parse_str("<completion>", &edited).ok()
+}
+
+/// `module` with the function containing `offset` ending there, and its
+/// return type left to inference, so the bindings before `offset` check
+/// without the code after it
+fn stopped_at(module: &Module, offset: u32) -> Module {
+    let mut module = module.clone();
+    for item in &mut module.items {
+        if let Item::Fn(decl) = item {
+            if contains(decl.body.span, offset) {
+                decl.ret_ty = None;
+                stop_block(&mut decl.body, offset);
+            }
+        }
+    }
+    module
+}
+
+/// Cut `block` at `offset`: the statements before it, then the block
+/// containing it (itself cut) if there is one
+fn stop_block(block: &mut Block, offset: u32) {
+    let mut inner = None;
+    for stmt in &block.stmts {
+        if stmt.span().end <= offset {
+            continue;
+        }
+        inner = match stmt {
+            Stmt::Let { value: expr, .. } | Stmt::Expr { expr, .. } => block_at(expr, offset),
+            Stmt::Return {
+                value: Some(expr), ..
+            } => block_at(expr, offset),
+            _ => None,
+        };
+        break;
+    }
+    if inner.is_none() {
+        inner = block
+            .tail
+            .as_deref()
+            .and_then(|tail| block_at(tail, offset));
+    }
+    let inner = inner.cloned().map(|mut inner| {
+        stop_block(&mut inner, offset);
+        let span = inner.span;
+        Stmt::Expr {
+            expr: Expr::Block(inner),
+            span,
+        }
+    });
+    block.stmts.retain(|stmt| stmt.span().end <= offset);
+    block.stmts.extend(inner);
+    block.tail = None;
+}
+
+/// The block `expr` runs that contains `offset`, through `if`s, `while`s,
+/// and nested blocks
+fn block_at(expr: &Expr, offset: u32) -> Option<&Block> {
+    match expr {
+        Expr::Block(block) | Expr::While { body: block, .. } if contains(block.span, offset) => {
+            Some(block)
+        }
+        Expr::If { then_, else_, .. } => match contains(then_.span, offset) {
+            true => Some(then_),
+            false => block_at(else_.as_deref()?, offset),
+        },
+        _ => None,
+    }
+}
+
+fn contains(span: Span, offset: u32) -> bool {
+    span.start <= offset && offset <= span.end
+}
+
+/// The checker's view of a module, for completion details
+struct Types<'a> {
+    module: &'a Module,
+    checker: &'a TypeChecker,
+    /// Checked with the function being edited stopped at the cursor
+    locals: &'a TypeChecker,
+}
+
+impl Types<'_> {
+    fn completion(&self, name: String, def: &DefId) -> Completion {
+        let kind = match def {
+            DefId::Local(_) => CompletionKind::Local,
+            DefId::Fn(_) => CompletionKind::Function,
+            DefId::Extern(_) => CompletionKind::Extern,
+            DefId::Global(_) => CompletionKind::Global,
+            DefId::Const(_) => CompletionKind::Const,
+            DefId::Ctor { .. } => CompletionKind::Constructor,
+            DefId::Type(_) => CompletionKind::Type,
+        };
+        let detail = match def {
+            DefId::Ctor { adt, .. } => Some(adt.clone()),
+            DefId::Type(_) => None,
+            def => self.of_def(&name, def).map(|ty| ty.to_string()),
+        };
+        Completion::new(name, kind, detail)
+    }
+
+    /// The resolved type of `name`, defined by `def`
+    fn of_def(&self, name: &str, def: &DefId) -> Option<Ty> {
+        let kind = match def {
+            DefId::Local(span) => return self.of_local(*span),
+            DefId::Fn(_) => TypedItemKind::Fn,
+            DefId::Extern(_) => TypedItemKind::ExternFn,
+            DefId::Global(_) => TypedItemKind::Let,
+            DefId::Const(_) => TypedItemKind::Const,
+            DefId::Ctor { .. } | DefId::Type(_) => return None,
+        };
+        self.checker
+            .typed_items()
+            .iter()
+            .find(|item| item.kind == kind && item.name == name)
+            .map(|item| item.ty.clone())
+    }
+
+    /// The type of the `let` binding or parameter named at `span`
+    fn of_local(&self, span: Span) -> Option<Ty> {
+        let items = self.locals.typed_items();
+        if let Some(local) = items
+            .iter()
+            .flat_map(|item| &item.locals)
+            .find(|local| local.span == span)
+        {
+            return Some(local.ty.clone());
+        }
+        self.module.items.iter().find_map(|item| {
+            let Item::Fn(decl) = item else {
+                return None;
+            };
+            let i = decl.params.iter().position(|p| p.name.span == span)?;
+            let checked = items
+                .iter()
+                .find(|item| item.kind == TypedItemKind::Fn && item.name == decl.name.text);
+            match checked.map(|item| &item.ty) {
+                Some(Ty::Arrow(params, _, _)) => params.get(i).cloned(),
+                _ => {
+                    let ty = decl.params[i].ty.as_ref()?;
+                    self.checker.ty_from_type_expr(ty).ok()
+                }
+            }
+        })
+    }
+
+    /// Fields of a struct or record type, or a reference to one
+    fn fields(&self, ty: &Ty) -> Vec<(String, Ty)> {
+        match ty {
+            Ty::Ref(inner) => self.fields(inner),
+            Ty::Record(fields, _) => fields.iter().map(|(n, t)| (n.clone(), t.clone())).collect(),
+            Ty::Adt { name, .. } => match self.checker.adt_registry().get(name).map(|a| &a.kind) {
+                Some(AdtKind::Struct(fields)) => fields
+                    .iter()
+                    .map(|f| (f.name.clone(), f.ty.clone()))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Variants of the enum `adt`, with their field types
+    fn variants(&self, adt: &str) -> Vec<Completion> {
+        let Some(AdtKind::Enum(variants)) = self.checker.adt_registry().get(adt).map(|a| &a.kind)
+        else {
+            return Vec::new();
+        };
+        variants
+            .iter()
+            .map(|v| {
+                let detail = match &v.fields {
+                    VariantFields::Unit => None,
+                    VariantFields::Tuple(tys) => {
+                        let tys: Vec<String> = tys.iter().map(Ty::to_string).collect();
+                        Some(format!("({})", tys.join(", ")))
+                    }
+                };
+                Completion::new(v.name.clone(), CompletionKind::Constructor, detail)
+            })
+            .collect()
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Length in bytes of the identifier characters ending `text`
+fn word_len_before(text: &str) -> usize {
+    text.chars()
+        .rev()
+        .take_while(|c| is_word(*c))
+        .map(char::len_utf8)
+        .sum()
+}
+
+/// The identifier ending `text`, or empty
+fn ident_before(text: &str) -> &str {
+    let word = &text[text.len() - word_len_before(text)..];
+    match word.chars().next() {
+        Some(c) if !c.is_ascii_digit() => word,
+        _ => "",
+    }
+}
+
+/// The names of a receiver `a.b.c` ending `text`, outermost first; `None`
+/// when it isn't one (`1.`, `f().`)
+fn receiver_chain(mut text: &str) -> Option<Vec<&str>> {
+    let mut chain = Vec::new();
+    loop {
+        let name = ident_before(text);
+        if name.is_empty() {
+            return None;
+        }
+        chain.push(name);
+        text = &text[..text.len() - name.len()];
+        match text.strip_suffix('.') {
+            Some(rest) => text = rest,
+            None => break,
+        }
+    }
+    chain.reverse();
+    Some(chain)
+}
+
+/// Whether `text` ends inside an effect row: `& {` followed by effect
+/// names and commas
+fn in_effect_row(text: &str) -> bool {
+    let open = text.trim_end_matches(|c: char| is_word(c) || c.is_whitespace() || c == ',');
+    open.strip_suffix('{').is_some_and(|rest| {
+        let rest = rest.trim_end();
+        rest.ends_with('&') && !rest.ends_with("&&")
+    })
+}
@@ -0,0 +1,537 @@
+//! The symbol index: every definition in a set of files, and every
+//! reference to each, for workspace symbols, find-references, and the
+//! dead-code lint.
+//!
+//! Files are indexed one at a time, each with its own resolution; a file
+//! whose source is unchanged since it was indexed isn't reparsed. The
+//! index is saved in a stable binary format, so an editor can start from
+//! the last session's index and reindex only what changed: a `STSX` magic
+//! and a format version, then each file's name, source hash, symbols, and
+//! references. All integers are little-endian; strings are a `u32` length
+//! and UTF-8 bytes.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use strata_ast::ast::{Item, Module};
+use strata_ast::span::{FileId, Span};
+use strata_parse::{parse_str, ParseError};
+use strata_types::resolve::{resolve, DefId};
+
+use crate::occurrence::{occurrences, Target};
+
+const MAGIC: &[u8; 4] = b"STSX";
+
+/// Current index format version.
+const FORMAT_VERSION: u16 = 1;
+
+/// What a symbol defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// A parameter or pattern binding
+    Local,
+    Function,
+    Extern,
+    /// A top-level `let`
+    Global,
+    Const,
+    Struct,
+    Enum,
+    Variant,
+}
+
+impl SymbolKind {
+    const ALL: [SymbolKind; 8] = [
+        SymbolKind::Local,
+        SymbolKind::Function,
+        SymbolKind::Extern,
+        SymbolKind::Global,
+        SymbolKind::Const,
+        SymbolKind::Struct,
+        SymbolKind::Enum,
+        SymbolKind::Variant,
+    ];
+}
+
+/// A definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// What the resolver calls it; its spans are in the symbol's file
+    pub def: DefId,
+    /// The name where it is defined
+    pub span: Span,
+    /// The enum of a variant, or the top-level function a local or nested
+    /// function is in
+    pub container: Option<String>,
+}
+
+/// One file's symbols and references
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FileIndex {
+    name: String,
+    /// Hash of the indexed source; `None` once the file is removed
+    hash: Option<u64>,
+    symbols: Vec<Symbol>,
+    /// Each definition's uses, in source order
+    references: HashMap<DefId, Vec<Span>>,
+}
+
+/// Why index bytes couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexError {
+    /// Not a symbol index
+    BadMagic,
+    /// Written by a different format version
+    UnsupportedVersion(u16),
+    /// The data ends early
+    Truncated,
+    /// Malformed contents
+    Invalid(String),
+}
+
+impl std::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexError::BadMagic => write!(f, "not a Strata symbol index"),
+            IndexError::UnsupportedVersion(v) => write!(
+                f,
+                "symbol index format version {} is not supported (expected {})",
+                v, FORMAT_VERSION
+            ),
+            IndexError::Truncated => write!(f, "symbol index is truncated"),
+            IndexError::Invalid(msg) => write!(f, "invalid symbol index: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+/// Definitions and references across files, by file name. Each file gets
+/// a [`FileId`] when first indexed and keeps it, even once removed, so
+/// spans and [`DefId`]s stay valid as files change.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolIndex {
+    files: Vec<FileIndex>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index `src` as the file `name`, replacing what was indexed for it.
+    /// Returns whether it was reindexed: a source unchanged since it was
+    /// last indexed is left alone. A source that doesn't parse leaves the
+    /// file's previous index in place.
+    pub fn update(&mut self, name: &str, src: &str) -> Result<bool, ParseError> {
+        let hash = fnv1a(src.as_bytes());
+        let file = match self.file_id(name) {
+            Some(file) if self.files[file.0 as usize].hash == Some(hash) => return Ok(false),
+            Some(file) => file,
+            None => {
+                self.files.push(FileIndex {
+                    name: name.to_string(),
+                    ..FileIndex::default()
+                });
+                FileId(self.files.len() as u32 - 1)
+            }
+        };
+        let module = parse_str(name, src)?;
+        let (symbols, references) = index_module(&module, src, file);
+        self.files[file.0 as usize] = FileIndex {
+            name: name.to_string(),
+            hash: Some(hash),
+            symbols,
+            references,
+        };
+        Ok(true)
+    }
+
+    /// Drop the file `name`'s symbols. Returns whether it was indexed.
+    pub fn remove(&mut self, name: &str) -> bool {
+        match self.file_id(name) {
+            Some(file) => {
+                let entry = &mut self.files[file.0 as usize];
+                let indexed = entry.hash.is_some();
+                *entry = FileIndex {
+                    name: entry.name.clone(),
+                    ..FileIndex::default()
+                };
+                indexed
+            }
+            None => false,
+        }
+    }
+
+    /// The id of the file `name`, if it was ever indexed
+    pub fn file_id(&self, name: &str) -> Option<FileId> {
+        let i = self.files.iter().position(|f| f.name == name)?;
+        Some(FileId(i as u32))
+    }
+
+    /// The name of `file`
+    pub fn file_name(&self, file: FileId) -> Option<&str> {
+        self.files.get(file.0 as usize).map(|f| f.name.as_str())
+    }
+
+    /// Every definition, by file then source order
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.files.iter().flat_map(|f| &f.symbols)
+    }
+
+    /// Definitions outside functions whose name contains `query`,
+    /// ignoring case, for workspace-symbol search
+    pub fn workspace_symbols(&self, query: &str) -> Vec<&Symbol> {
+        let query = query.to_lowercase();
+        self.symbols()
+            .filter(|s| s.container.is_none() || s.kind == SymbolKind::Variant)
+            .filter(|s| s.kind != SymbolKind::Local)
+            .filter(|s| s.name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// The uses of `def` in `file`, in source order, leaving out its
+    /// definition
+    pub fn references(&self, file: FileId, def: &DefId) -> &[Span] {
+        self.files
+            .get(file.0 as usize)
+            .and_then(|f| f.references.get(def))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The symbol defined or used at byte `offset` of `file`
+    pub fn symbol_at(&self, file: FileId, offset: u32) -> Option<&Symbol> {
+        let index = self.files.get(file.0 as usize)?;
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        if let Some(symbol) = index.symbols.iter().find(|s| contains(&s.span)) {
+            return Some(symbol);
+        }
+        let (def, _) = index
+            .references
+            .iter()
+            .filter_map(|(def, spans)| Some((def, spans.iter().find(|s| contains(s))?)))
+            .min_by_key(|(_, span)| span.len())?;
+        index.symbols.iter().find(|s| s.def == *def)
+    }
+
+    /// Write the index to `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    /// Read an index written by [`SymbolIndex::save`].
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_bytes(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Encode in the stable binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        put_u32(&mut out, self.files.len());
+        for file in &self.files {
+            put_str(&mut out, &file.name);
+            match file.hash {
+                Some(hash) => {
+                    out.push(1);
+                    out.extend_from_slice(&hash.to_le_bytes());
+                }
+                None => out.push(0),
+            }
+            put_u32(&mut out, file.symbols.len());
+            for symbol in &file.symbols {
+                put_str(&mut out, &symbol.name);
+                let kind = SymbolKind::ALL.iter().position(|k| *k == symbol.kind);
+                out.push(kind.expect("every kind is listed") as u8);
+                put_def(&mut out, &symbol.def);
+                put_span(&mut out, symbol.span);
+                put_opt_str(&mut out, symbol.container.as_deref());
+            }
+            // By first use, so the bytes don't depend on hash order
+            let mut references: Vec<(&DefId, &Vec<Span>)> = file.references.iter().collect();
+            references.sort_by_key(|(_, spans)| spans.first().map(|s| s.start));
+            put_u32(&mut out, references.len());
+            for (def, spans) in references {
+                put_def(&mut out, def);
+                put_u32(&mut out, spans.len());
+                for span in spans {
+                    put_span(&mut out, *span);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode bytes written by [`SymbolIndex::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, IndexError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.take(4)? != MAGIC {
+            return Err(IndexError::BadMagic);
+        }
+        let version = u16::from_le_bytes([r.u8()?, r.u8()?]);
+        if version != FORMAT_VERSION {
+            return Err(IndexError::UnsupportedVersion(version));
+        }
+        let mut files = Vec::new();
+        for i in 0..r.u32()? {
+            let file = FileId(i);
+            let name = r.str()?;
+            let hash = match r.u8()? {
+                0 => None,
+                1 => Some(r.u64()?),
+                tag => return Err(IndexError::Invalid(format!("bad hash tag {}", tag))),
+            };
+            let mut symbols = Vec::new();
+            for _ in 0..r.u32()? {
+                let name = r.str()?;
+                let kind = *SymbolKind::ALL
+                    .get(r.u8()? as usize)
+                    .ok_or_else(|| IndexError::Invalid("bad symbol kind".to_string()))?;
+                symbols.push(Symbol {
+                    name,
+                    kind,
+                    def: r.def(file)?,
+                    span: r.span(file)?,
+                    container: r.opt_str()?,
+                });
+            }
+            let mut references = HashMap::new();
+            for _ in 0..r.u32()? {
+                let def = r.def(file)?;
+                let spans = (0..r.u32()?)
+                    .map(|_| r.span(file))
+                    .collect::<Result<_, _>>()?;
+                references.insert(def, spans);
+            }
+            files.push(FileIndex {
+                name,
+                hash,
+                symbols,
+                references,
+            });
+        }
+        if r.pos != bytes.len() {
+            return Err(IndexError::Invalid("trailing data".to_string()));
+        }
+        Ok(SymbolIndex { files })
+    }
+}
+
+/// The symbols `module` defines and the references to them, with spans
+/// moved into `file`
+fn index_module(
+    module: &Module,
+    src: &str,
+    file: FileId,
+) -> (Vec<Symbol>, HashMap<DefId, Vec<Span>>) {
+    let resolution = resolve(module);
+    let mut symbols = Vec::new();
+    let mut references: HashMap<DefId, Vec<Span>> = HashMap::new();
+    for occ in occurrences(module, &resolution, src) {
+        let Some(def) = occ.target.def_id() else {
+            continue;
+        };
+        let def = in_file(def, file);
+        let span = Span { file, ..occ.span };
+        if !occ.definition {
+            references.entry(def).or_default().push(span);
+            continue;
+        }
+        let (kind, container) = match (&occ.target, &def) {
+            (Target::Variant { adt, .. }, _) => (SymbolKind::Variant, Some(adt.clone())),
+            (Target::Type(adt), _) => {
+                let is_enum = module
+                    .items
+                    .iter()
+                    .any(|item| matches!(item, Item::Enum(e) if e.name.text == *adt));
+                match is_enum {
+                    true => (SymbolKind::Enum, None),
+                    false => (SymbolKind::Struct, None),
+                }
+            }
+            (_, DefId::Local(_)) => (SymbolKind::Local, enclosing_fn(module, occ.span)),
+            (_, DefId::Fn(_)) => (SymbolKind::Function, enclosing_fn(module, occ.span)),
+            (_, DefId::Extern(_)) => (SymbolKind::Extern, None),
+            (_, DefId::Global(_)) => (SymbolKind::Global, None),
+            (_, DefId::Const(_)) => (SymbolKind::Const, None),
+            (_, DefId::Ctor { .. } | DefId::Type(_)) => continue,
+        };
+        symbols.push(Symbol {
+            name: src[occ.span.start as usize..occ.span.end as usize].to_string(),
+            kind,
+            def,
+            span,
+            container,
+        });
+    }
+    symbols.sort_by_key(|s| s.span.start);
+    symbols.dedup_by(|a, b| a.span == b.span);
+    for spans in references.values_mut() {
+        spans.sort_by_key(|s| s.start);
+        spans.dedup();
+    }
+    (symbols, references)
+}
+
+/// The top-level function whose body holds `span`
+fn enclosing_fn(module: &Module, span: Span) -> Option<String> {
+    module.items.iter().find_map(|item| match item {
+        Item::Fn(decl) if decl.name.span != span && decl.span.start <= span.start => {
+            (span.end <= decl.span.end).then(|| decl.name.text.clone())
+        }
+        _ => None,
+    })
+}
+
+/// `def` with its span in `file`
+fn in_file(def: DefId, file: FileId) -> DefId {
+    let moved = |span: Span| Span { file, ..span };
+    match def {
+        DefId::Local(span) => DefId::Local(moved(span)),
+        DefId::Fn(span) => DefId::Fn(moved(span)),
+        DefId::Extern(span) => DefId::Extern(moved(span)),
+        DefId::Global(span) => DefId::Global(moved(span)),
+        DefId::Const(span) => DefId::Const(moved(span)),
+        def @ (DefId::Ctor { .. } | DefId::Type(_)) => def,
+    }
+}
+
+/// 64-bit FNV-1a: stable across runs and platforms, unlike std's hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn put_u32(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_opt_str(out: &mut Vec<u8>, s: Option<&str>) {
+    match s {
+        Some(s) => {
+            out.push(1);
+            put_str(out, s);
+        }
+        None => out.push(0),
+    }
+}
+
+/// A span's offsets; its file is the one being written
+fn put_span(out: &mut Vec<u8>, span: Span) {
+    out.extend_from_slice(&span.start.to_le_bytes());
+    out.extend_from_slice(&span.end.to_le_bytes());
+}
+
+fn put_def(out: &mut Vec<u8>, def: &DefId) {
+    match def {
+        DefId::Local(span) => {
+            out.push(0);
+            put_span(out, *span);
+        }
+        DefId::Fn(span) => {
+            out.push(1);
+            put_span(out, *span);
+        }
+        DefId::Extern(span) => {
+            out.push(2);
+            put_span(out, *span);
+        }
+        DefId::Global(span) => {
+            out.push(3);
+            put_span(out, *span);
+        }
+        DefId::Const(span) => {
+            out.push(4);
+            put_span(out, *span);
+        }
+        DefId::Ctor { adt, variant } => {
+            out.push(5);
+            put_str(out, adt);
+            put_opt_str(out, variant.as_deref());
+        }
+        DefId::Type(name) => {
+            out.push(6);
+            put_str(out, name);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], IndexError> {
+        let end = self.pos.checked_add(n).ok_or(IndexError::Truncated)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(IndexError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, IndexError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, IndexError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, IndexError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn str(&mut self) -> Result<String, IndexError> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| IndexError::Invalid("string is not UTF-8".to_string()))
+    }
+
+    fn opt_str(&mut self) -> Result<Option<String>, IndexError> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.str()?)),
+            tag => Err(IndexError::Invalid(format!("bad option tag {}", tag))),
+        }
+    }
+
+    fn span(&mut self, file: FileId) -> Result<Span, IndexError> {
+        let (start, end) = (self.u32()?, self.u32()?);
+        if start > end {
+            return Err(IndexError::Invalid(
+                "span ends before it starts".to_string(),
+            ));
+        }
+        Ok(Span { start, end, file })
+    }
+
+    fn def(&mut self, file: FileId) -> Result<DefId, IndexError> {
+        Ok(match self.u8()? {
+            0 => DefId::Local(self.span(file)?),
+            1 => DefId::Fn(self.span(file)?),
+            2 => DefId::Extern(self.span(file)?),
+            3 => DefId::Global(self.span(file)?),
+            4 => DefId::Const(self.span(file)?),
+            5 => DefId::Ctor {
+                adt: self.str()?,
+                variant: self.opt_str()?,
+            },
+            6 => DefId::Type(self.str()?),
+            tag => return Err(IndexError::Invalid(format!("bad definition tag {}", tag))),
+        })
+    }
+}
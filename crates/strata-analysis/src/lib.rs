@@ -0,0 +1,17 @@
+//! Editor queries over Strata source, for the language server: what can
+//! go at a cursor, answered from the resolver's scopes and the checker's
+//! resolved types, the edits that rename a name everywhere it's used, and
+//! an index of definitions and their references across files.
+
+#![forbid(unsafe_code)]
+#![deny(unused_must_use)]
+#![warn(clippy::dbg_macro, clippy::todo, clippy::unimplemented)]
+
+mod completion;
+mod index;
+mod occurrence;
+mod rename;
+
+pub use completion::{completions, Completion, CompletionKind};
+pub use index::{IndexError, Symbol, SymbolIndex, SymbolKind};
+pub use rename::{rename, RenameError, TextEdit};
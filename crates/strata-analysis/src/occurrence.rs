@@ -0,0 +1,394 @@
+//! Occurrences: every place a module writes a name, tied to what it names.
+//! Shared by rename and the symbol index.
+
+use std::collections::HashMap;
+
+use strata_ast::ast::{Block, Expr, FnDecl, Ident, Item, Module, Pat, Path, Stmt};
+use strata_ast::span::Span;
+use strata_types::resolve::{DefId, Resolution};
+
+/// What a name names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// A local, function, extern, global, or const
+    Value(DefId),
+    /// A struct or enum, with a tuple struct's constructor
+    Type(String),
+    Variant {
+        adt: String,
+        variant: String,
+    },
+    /// A named struct field
+    Field {
+        adt: String,
+        field: String,
+    },
+}
+
+/// How an occurrence is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Form {
+    Name,
+    /// A binding or value written as shorthand for the field it names
+    /// (`Point { x }`): renamed, it becomes `x: new`
+    ShorthandValue,
+    /// A field written as shorthand for its binding or value: renamed, it
+    /// becomes `new: x`
+    ShorthandField,
+}
+
+/// One place a name is written, by the span of just the name
+#[derive(Debug, Clone)]
+pub(crate) struct Occurrence {
+    pub(crate) span: Span,
+    pub(crate) target: Target,
+    pub(crate) form: Form,
+    /// Where the name is defined, rather than used
+    pub(crate) definition: bool,
+}
+
+impl Target {
+    /// What the resolver calls the target; fields have no [`DefId`]
+    pub(crate) fn def_id(&self) -> Option<DefId> {
+        match self {
+            Target::Value(def) => Some(def.clone()),
+            Target::Type(adt) => Some(DefId::Type(adt.clone())),
+            Target::Variant { adt, variant } => Some(DefId::Ctor {
+                adt: adt.clone(),
+                variant: Some(variant.clone()),
+            }),
+            Target::Field { .. } => None,
+        }
+    }
+}
+
+/// Every name written in `module`: definitions and fields from
+/// the AST, uses from `resolution`
+pub(crate) fn occurrences(module: &Module, resolution: &Resolution, src: &str) -> Vec<Occurrence> {
+    let mut walk = Walk {
+        resolution,
+        found: Vec::new(),
+        shorthand: HashMap::new(),
+    };
+    walk.module(module);
+    let Walk {
+        mut found,
+        shorthand,
+        ..
+    } = walk;
+
+    for (span, name, def) in resolution.iter() {
+        let value_form = match shorthand.contains_key(&span) {
+            true => Form::ShorthandValue,
+            false => Form::Name,
+        };
+        match def {
+            DefId::Local(_)
+            | DefId::Fn(_)
+            | DefId::Extern(_)
+            | DefId::Global(_)
+            | DefId::Const(_) => found.push(Occurrence {
+                span,
+                target: Target::Value(def.clone()),
+                form: value_form,
+                definition: false,
+            }),
+            DefId::Type(adt) => {
+                found.extend(segment(src, span, adt, true).map(|span| Occurrence {
+                    span,
+                    target: Target::Type(adt.clone()),
+                    form: Form::Name,
+                    definition: false,
+                }))
+            }
+            DefId::Ctor { adt, variant: None } => found.push(Occurrence {
+                span,
+                target: Target::Type(adt.clone()),
+                form: Form::Name,
+                definition: false,
+            }),
+            DefId::Ctor {
+                adt,
+                variant: Some(variant),
+            } => {
+                let variant_target = Target::Variant {
+                    adt: adt.clone(),
+                    variant: variant.clone(),
+                };
+                if !name.contains("::") {
+                    // An imported variant, used by its own name
+                    found.push(Occurrence {
+                        span,
+                        target: variant_target,
+                        form: value_form,
+                        definition: false,
+                    });
+                    continue;
+                }
+                found.extend(segment(src, span, adt, true).map(|span| Occurrence {
+                    span,
+                    target: Target::Type(adt.clone()),
+                    form: Form::Name,
+                    definition: false,
+                }));
+                found.extend(segment(src, span, variant, false).map(|span| Occurrence {
+                    span,
+                    target: variant_target.clone(),
+                    form: Form::Name,
+                    definition: false,
+                }));
+            }
+        }
+    }
+    found
+}
+
+/// The span of `name` at the start (or end) of the path or type written
+/// at `span`, if it is written there
+fn segment(src: &str, span: Span, name: &str, first: bool) -> Option<Span> {
+    let len = name.len() as u32;
+    let (start, end) = match first {
+        true => (span.start, span.start + len),
+        false => (span.end.checked_sub(len)?, span.end),
+    };
+    (src.get(start as usize..end as usize) == Some(name)).then_some(Span { start, end, ..span })
+}
+
+/// Collects the definitions and fields the resolution doesn't record
+struct Walk<'a> {
+    resolution: &'a Resolution,
+    found: Vec<Occurrence>,
+    /// Spans of names written once for both a field and its binding or
+    /// value, to the field
+    shorthand: HashMap<Span, String>,
+}
+
+impl Walk<'_> {
+    fn push(&mut self, ident: &Ident, target: Target, definition: bool) {
+        let form = match self.shorthand.contains_key(&ident.span) {
+            true if matches!(target, Target::Field { .. }) => Form::ShorthandField,
+            true => Form::ShorthandValue,
+            false => Form::Name,
+        };
+        self.found.push(Occurrence {
+            span: ident.span,
+            target,
+            form,
+            definition,
+        });
+    }
+
+    fn define(&mut self, ident: &Ident, def: DefId) {
+        self.push(ident, Target::Value(def), true);
+    }
+
+    /// The struct `path` names, if the module defines it
+    fn struct_at(&self, path: &Path) -> Option<String> {
+        let [name] = path.segments.as_slice() else {
+            return None;
+        };
+        match self
+            .resolution
+            .path(path)
+            .or_else(|| self.resolution.ident(name))
+        {
+            Some(DefId::Type(adt)) => Some(adt.clone()),
+            _ => None,
+        }
+    }
+
+    fn field(&mut self, adt: Option<&str>, name: &Ident, shorthand: bool, definition: bool) {
+        if shorthand {
+            self.shorthand.insert(name.span, name.text.clone());
+        }
+        if let Some(adt) = adt {
+            let target = Target::Field {
+                adt: adt.to_string(),
+                field: name.text.clone(),
+            };
+            self.push(name, target, definition);
+        }
+    }
+
+    fn module(&mut self, module: &Module) {
+        for item in &module.items {
+            match item {
+                Item::Let(decl) => {
+                    self.expr(&decl.value);
+                    self.define(&decl.name, DefId::Global(decl.name.span));
+                }
+                Item::Const(decl) => {
+                    self.expr(&decl.value);
+                    self.define(&decl.name, DefId::Const(decl.name.span));
+                }
+                Item::Fn(decl) => self.fn_decl(decl),
+                Item::ExternFn(decl) => self.define(&decl.name, DefId::Extern(decl.name.span)),
+                Item::Struct(def) => {
+                    self.push(&def.name, Target::Type(def.name.text.clone()), true);
+                    if !def.tuple {
+                        for field in &def.fields {
+                            self.field(Some(&def.name.text), &field.name, false, true);
+                        }
+                    }
+                }
+                Item::Enum(def) => {
+                    self.push(&def.name, Target::Type(def.name.text.clone()), true);
+                    for variant in &def.variants {
+                        let target = Target::Variant {
+                            adt: def.name.text.clone(),
+                            variant: variant.name.text.clone(),
+                        };
+                        self.push(&variant.name, target, true);
+                    }
+                }
+                Item::Use(decl) => {
+                    self.push(
+                        &decl.enum_name,
+                        Target::Type(decl.enum_name.text.clone()),
+                        false,
+                    );
+                    if let Some(variant) = &decl.variant {
+                        let target = Target::Variant {
+                            adt: decl.enum_name.text.clone(),
+                            variant: variant.text.clone(),
+                        };
+                        self.push(variant, target, false);
+                    }
+                }
+            }
+        }
+    }
+
+    fn fn_decl(&mut self, decl: &FnDecl) {
+        self.define(&decl.name, DefId::Fn(decl.name.span));
+        for param in &decl.params {
+            self.define(&param.name, DefId::Local(param.name.span));
+        }
+        self.block(&decl.body);
+    }
+
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let { pat, value, .. } => {
+                    self.expr(value);
+                    self.pattern(pat);
+                }
+                Stmt::Declare { name, .. } => self.define(name, DefId::Local(name.span)),
+                Stmt::Assign { value, .. } => self.expr(value),
+                Stmt::Expr { expr, .. } => self.expr(expr),
+                Stmt::Return { value, .. } => {
+                    if let Some(value) = value {
+                        self.expr(value);
+                    }
+                }
+                Stmt::Fn(decl) => self.fn_decl(decl),
+            }
+        }
+        if let Some(tail) = &block.tail {
+            self.expr(tail);
+        }
+    }
+
+    /// Bindings and struct fields in `pat`
+    fn pattern(&mut self, pat: &Pat) {
+        match pat {
+            // Otherwise a const or an imported variant, which is a use
+            Pat::Ident(ident) if self.resolution.ident(ident).is_none() => {
+                self.define(ident, DefId::Local(ident.span))
+            }
+            Pat::Ident(_) | Pat::Wildcard(_) | Pat::Literal(..) | Pat::Range { .. } => {}
+            Pat::StrPrefix { rest, .. } => self.pattern(rest),
+            Pat::Tuple(pats, _) | Pat::Variant { fields: pats, .. } => {
+                for p in pats {
+                    self.pattern(p);
+                }
+            }
+            Pat::Struct { path, fields, .. } => {
+                let adt = self.struct_at(path);
+                for f in fields {
+                    let shorthand = matches!(&f.pat, Pat::Ident(i) if i.span == f.name.span);
+                    self.field(adt.as_deref(), &f.name, shorthand, false);
+                    self.pattern(&f.pat);
+                }
+            }
+            Pat::Record { fields, .. } => {
+                for f in fields {
+                    let shorthand = matches!(&f.pat, Pat::Ident(i) if i.span == f.name.span);
+                    self.field(None, &f.name, shorthand, false);
+                    self.pattern(&f.pat);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+            Expr::Paren { inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::Borrow(inner, _)
+            | Expr::Field { expr: inner, .. } => self.expr(inner),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Block(block) | Expr::Spawn { body: block, .. } => self.block(block),
+            Expr::If {
+                cond, then_, else_, ..
+            } => {
+                self.expr(cond);
+                self.block(then_);
+                if let Some(e) = else_ {
+                    self.expr(e);
+                }
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            Expr::For {
+                pat, iter, body, ..
+            } => {
+                self.expr(iter);
+                self.pattern(pat);
+                self.block(body);
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.pattern(&arm.pat);
+                    self.expr(&arm.body);
+                }
+            }
+            Expr::Tuple { elems, .. } => {
+                for e in elems {
+                    self.expr(e);
+                }
+            }
+            Expr::StructExpr { path, fields, .. } => {
+                let adt = self.struct_at(path);
+                for f in fields {
+                    let shorthand = matches!(&f.value, Expr::Var(v) if v.span == f.name.span);
+                    self.field(adt.as_deref(), &f.name, shorthand, false);
+                    self.expr(&f.value);
+                }
+            }
+            Expr::Record { fields, .. } => {
+                for f in fields {
+                    let shorthand = matches!(&f.value, Expr::Var(v) if v.span == f.name.span);
+                    self.field(None, &f.name, shorthand, false);
+                    self.expr(&f.value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Rename: every edit that gives a name a new one.
+
+use std::fmt;
+
+use strata_ast::ast::{Item, Module};
+use strata_ast::span::Span;
+use strata_parse::{parse_str, semantic_tokens, ParseError, TokenClass};
+use strata_types::resolve::{resolve, scope_at, Resolution};
+
+use crate::occurrence::{occurrences, Form, Occurrence, Target};
+
+/// Replace the source at `span` with `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub span: Span,
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// Apply non-overlapping `edits` to `src`.
+    pub fn apply_all(src: &str, edits: &[TextEdit]) -> String {
+        let mut edits: Vec<&TextEdit> = edits.iter().collect();
+        edits.sort_by_key(|e| e.span.start);
+        let mut out = String::with_capacity(src.len());
+        let mut pos = 0;
+        for edit in edits {
+            out.push_str(&src[pos..edit.span.start as usize]);
+            out.push_str(&edit.new_text);
+            pos = edit.span.end as usize;
+        }
+        out.push_str(&src[pos..]);
+        out
+    }
+}
+
+/// Why a rename can't be done.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameError {
+    Parse(ParseError),
+    /// No renamable name at the offset
+    NothingToRename,
+    /// The name is defined by the prelude, not the source
+    Builtin {
+        name: String,
+    },
+    /// The new name isn't an identifier, or is a keyword
+    InvalidName {
+        name: String,
+    },
+    /// The new name is already bound where the renamed name is used or
+    /// defined (at `span`), or the renamed name would capture a use of it
+    Conflict {
+        name: String,
+        span: Span,
+    },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::Parse(e) => write!(f, "{}", e),
+            RenameError::NothingToRename => write!(f, "no name to rename here"),
+            RenameError::Builtin { name } => write!(f, "'{}' is built in", name),
+            RenameError::InvalidName { name } => write!(f, "'{}' is not a valid name", name),
+            RenameError::Conflict { name, .. } => write!(f, "'{}' is already defined", name),
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+/// The edits that rename the name at byte `offset` of `src` to `new_name`,
+/// in source order: its definition and every use the resolver ties to it.
+///
+/// Locals, functions, externs, globals, consts, structs and enums, enum
+/// variants, and struct fields can be renamed. A type's uses include the
+/// `Enum::` of its variant paths and its `use` items; a field's include
+/// struct literals and patterns, where shorthand `x` becomes `x: new`
+/// (record fields are structural, so aren't renamed). Fails with a
+/// [`RenameError::Conflict`] if `new_name` is already bound in a scope
+/// the renamed name reaches, or the renamed name would shadow a use of it.
+pub fn rename(src: &str, offset: usize, new_name: &str) -> Result<Vec<TextEdit>, RenameError> {
+    let module = parse_str("<rename>", src).map_err(RenameError::Parse)?;
+    if !is_identifier(new_name) {
+        return Err(RenameError::InvalidName {
+            name: new_name.to_string(),
+        });
+    }
+    let resolution = resolve(&module);
+    let occurrences = occurrences(&module, &resolution, src);
+
+    // The innermost name at the offset; a shorthand field is renamed as
+    // the binding or value it stands for
+    let offset = offset as u32;
+    let target = occurrences
+        .iter()
+        .filter(|occ| occ.span.start <= offset && offset <= occ.span.end)
+        .min_by_key(|occ| {
+            (
+                occ.span.end - occ.span.start,
+                occ.form == Form::ShorthandField,
+            )
+        })
+        .map(|occ| occ.target.clone())
+        .ok_or(RenameError::NothingToRename)?;
+    if let Target::Type(adt) | Target::Variant { adt, .. } = &target {
+        if !defines_type(&module, adt) {
+            return Err(RenameError::Builtin { name: adt.clone() });
+        }
+    }
+    let renamed: Vec<&Occurrence> = occurrences
+        .iter()
+        .filter(|occ| occ.target == target)
+        .collect();
+    let old_name = &src[renamed[0].span.start as usize..renamed[0].span.end as usize];
+    if old_name == new_name {
+        return Ok(Vec::new());
+    }
+    check_conflicts(&module, &resolution, &target, &renamed, old_name, new_name)?;
+
+    let mut edits: Vec<TextEdit> = renamed
+        .iter()
+        .map(|occ| TextEdit {
+            span: occ.span,
+            new_text: match occ.form {
+                Form::Name => new_name.to_string(),
+                Form::ShorthandValue => format!("{}: {}", old_name, new_name),
+                Form::ShorthandField => format!("{}: {}", new_name, old_name),
+            },
+        })
+        .collect();
+    edits.sort_by_key(|e| (e.span.start, e.span.end));
+    edits.dedup();
+    Ok(edits)
+}
+
+/// Whether `name` can name something: an identifier that isn't a keyword
+/// or `_`
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    let starts = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_');
+    starts
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && name != "_"
+        && !semantic_tokens(name)
+            .iter()
+            .any(|(_, class)| *class == TokenClass::Keyword)
+}
+
+fn defines_type(module: &Module, name: &str) -> bool {
+    module.items.iter().any(|item| match item {
+        Item::Struct(def) => def.name.text == name,
+        Item::Enum(def) => def.name.text == name,
+        _ => false,
+    })
+}
+
+/// Fail if renaming `renamed` to `new_name` would change what a name
+/// refers to
+fn check_conflicts(
+    module: &Module,
+    resolution: &Resolution,
+    target: &Target,
+    renamed: &[&Occurrence],
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), RenameError> {
+    let conflict = |span: Span| {
+        Err(RenameError::Conflict {
+            name: new_name.to_string(),
+            span,
+        })
+    };
+    match target {
+        Target::Value(def) => {
+            // Where the name is written, `new_name` must not already be
+            // something else
+            for occ in renamed {
+                let scope = scope_at(module, occ.span.file, occ.span.start);
+                if scope.iter().any(|(n, d)| n == new_name && d != def) {
+                    return conflict(occ.span);
+                }
+            }
+            // Where `new_name` is used, the renamed name mustn't be visible
+            // to capture it
+            let uses = resolution
+                .iter()
+                .filter(|(_, name, d)| *name == new_name && *d != def);
+            for (span, _, _) in uses {
+                let scope = scope_at(module, span.file, span.start);
+                if scope.iter().any(|(n, d)| n == old_name && d == def) {
+                    return conflict(span);
+                }
+            }
+        }
+        Target::Type(_) => {
+            // A tuple struct's name is also its constructor's
+            let taken = defines_type(module, new_name)
+                || (is_tuple_struct(module, old_name) && defines_value(module, new_name));
+            if taken {
+                return conflict(renamed[0].span);
+            }
+        }
+        Target::Variant { adt, .. } => {
+            let taken = module.items.iter().any(|item| match item {
+                Item::Enum(def) if def.name.text == *adt => {
+                    def.variants.iter().any(|v| v.name.text == new_name)
+                }
+                _ => false,
+            });
+            if taken {
+                return conflict(renamed[0].span);
+            }
+        }
+        Target::Field { adt, .. } => {
+            let taken = module.items.iter().any(|item| match item {
+                Item::Struct(def) if def.name.text == *adt => {
+                    def.fields.iter().any(|f| f.name.text == new_name)
+                }
+                _ => false,
+            });
+            if taken {
+                return conflict(renamed[0].span);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a module-level value (function, extern, global, const, or
+/// tuple struct constructor) is called `name`
+fn defines_value(module: &Module, name: &str) -> bool {
+    module.items.iter().any(|item| match item {
+        Item::Fn(decl) => decl.name.text == name,
+        Item::ExternFn(decl) => decl.name.text == name,
+        Item::Let(decl) => decl.name.text == name,
+        Item::Const(decl) => decl.name.text == name,
+        Item::Struct(def) => def.tuple && def.name.text == name,
+        Item::Enum(_) | Item::Use(_) => false,
+    })
+}
+
+fn is_tuple_struct(module: &Module, name: &str) -> bool {
+    module
+        .items
+        .iter()
+        .any(|item| matches!(item, Item::Struct(def) if def.tuple && def.name.text == name))
+}
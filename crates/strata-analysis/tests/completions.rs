@@ -0,0 +1,144 @@
+//! Tests for `strata_analysis::completions`.
+
+use strata_analysis::{completions, Completion, CompletionKind};
+
+/// Completions at the `|` in `src`
+fn complete(src: &str) -> Vec<Completion> {
+    let offset = src.find('|').expect("no cursor");
+    let src = src.replacen('|', "", 1);
+    completions(&src, offset)
+}
+
+fn labels(src: &str) -> Vec<String> {
+    complete(src).into_iter().map(|c| c.label).collect()
+}
+
+#[test]
+fn names_in_scope_innermost_first() {
+    let src = "fn helper() -> Int { 1 }\n\
+               fn main() -> Int {\n\
+                   let total = 1;\n\
+                   let tally = 2;\n\
+                   t|\n\
+               }";
+    assert_eq!(labels(src), vec!["tally", "total"]);
+
+    let all = labels(&src.replace("t|", "|"));
+    assert_eq!(all[..2], ["tally", "total"]);
+    assert!(all.contains(&"helper".to_string()));
+    assert!(all.contains(&"main".to_string()));
+}
+
+#[test]
+fn later_bindings_and_other_functions_locals_are_out_of_scope() {
+    let src = "fn other() -> Int { let hidden = 1; hidden }\n\
+               fn main() -> Int {\n\
+                   let before = 1;\n\
+                   let x = |;\n\
+                   let after = 2;\n\
+                   x\n\
+               }";
+    let names = labels(src);
+    assert!(names.contains(&"before".to_string()), "{:?}", names);
+    assert!(!names.contains(&"after".to_string()), "{:?}", names);
+    assert!(!names.contains(&"hidden".to_string()), "{:?}", names);
+    assert!(!names.iter().any(|n| n.starts_with("__")), "{:?}", names);
+}
+
+#[test]
+fn details_carry_types_and_effect_rows() {
+    let src = "extern fn fetch(net: &NetCap, url: String) -> String & {Net};\n\
+               fn double(n: Int) -> Int { n * 2 }\n\
+               fn main() -> Int { let count = 3; d| }";
+    let found = complete(src);
+    assert_eq!(
+        found,
+        vec![Completion {
+            label: "double".to_string(),
+            kind: CompletionKind::Function,
+            detail: found[0].detail.clone(),
+        }]
+    );
+    assert!(found[0]
+        .detail
+        .as_deref()
+        .unwrap()
+        .starts_with("Int -> Int"));
+
+    let found = complete(&src.replace("d|", "f|"));
+    assert_eq!(found[0].kind, CompletionKind::Extern);
+    assert_eq!(
+        found[0].detail.as_deref(),
+        Some("(&NetCap, String) -> String & {Net}")
+    );
+
+    let found = complete(&src.replace("d|", "c|"));
+    assert_eq!(found[0].kind, CompletionKind::Local);
+    assert_eq!(found[0].detail.as_deref(), Some("Int"));
+}
+
+#[test]
+fn fields_after_dot() {
+    let src = "struct Point { x: Int, y: Int }\n\
+               struct Line { start: Point, end: Point }\n\
+               fn len(line: Line) -> Int {\n\
+                   let size = { width: 3, height: 4 };\n\
+                   size.|\n\
+               }";
+    let found = complete(src);
+    assert_eq!(labels(src), vec!["height", "width"]);
+    assert!(found.iter().all(|c| c.kind == CompletionKind::Field));
+    assert_eq!(found[0].detail.as_deref(), Some("Int"));
+
+    // A struct parameter's fields, through a chain, with a partial name
+    assert_eq!(
+        labels(&src.replace("size.|", "line.|")),
+        vec!["start", "end"]
+    );
+    assert_eq!(labels(&src.replace("size.|", "line.end.y|")), vec!["y"]);
+    assert!(labels(&src.replace("size.|", "1.|")).is_empty());
+}
+
+#[test]
+fn enum_variants_after_path() {
+    let src = "enum Shape { Circle(Int), Square(Int), Empty }\n\
+               fn main() -> Int { let s = Shape::|; 0 }";
+    let found = complete(src);
+    assert_eq!(labels(src), vec!["Circle", "Square", "Empty"]);
+    assert_eq!(found[0].detail.as_deref(), Some("(Int)"));
+    assert_eq!(found[2].detail, None);
+    assert_eq!(
+        labels(&src.replace("Shape::|", "Shape::S|")),
+        vec!["Square"]
+    );
+    assert_eq!(
+        labels(&src.replace("Shape::|", "Option::|")),
+        vec!["Some", "None"]
+    );
+}
+
+#[test]
+fn effect_names_inside_effect_rows() {
+    let src = "fn f() -> Int & {Fs, N|} { 1 }";
+    let found = complete(src);
+    assert_eq!(labels(src), vec!["Net"]);
+    assert_eq!(found[0].kind, CompletionKind::Effect);
+    assert_eq!(
+        labels("fn f() & {|"),
+        vec!["Fs", "Net", "Time", "Rand", "Ai", "Console"]
+    );
+}
+
+#[test]
+fn match_arm_bindings_and_imported_variants() {
+    let src = "enum Level { Low, High }\n\
+               use Level::*;\n\
+               fn f(o: Option<Int>) -> Int {\n\
+                   match o { Some(value) => v|, None => 0 }\n\
+               }";
+    assert_eq!(labels(src), vec!["value"]);
+    let found = complete(&src.replace("v|", "Hi|"));
+    assert_eq!(found[0].label, "High");
+    assert_eq!(found[0].kind, CompletionKind::Constructor);
+    assert_eq!(found[0].detail.as_deref(), Some("Level"));
+}
@@ -0,0 +1,151 @@
+//! Tests for `strata_analysis::SymbolIndex`.
+
+use strata_analysis::{IndexError, SymbolIndex, SymbolKind};
+use strata_types::resolve::DefId;
+
+const SHAPES: &str = "enum Shape { Circle(Int), Square(Int) }\n\
+                      const SIDES: Int = 4;\n\
+                      fn area(s: Shape) -> Int {\n\
+                          let scale = 3;\n\
+                          match s { Shape::Circle(r) => r * r * scale, Shape::Square(w) => w * w }\n\
+                      }\n\
+                      fn unused() -> Int { SIDES }\n\
+                      fn main() -> Int { area(Shape::Square(SIDES)) + area(Shape::Circle(1)) }";
+
+fn index() -> SymbolIndex {
+    let mut index = SymbolIndex::new();
+    assert!(index.update("shapes.strata", SHAPES).unwrap());
+    index
+}
+
+#[test]
+fn lists_definitions_with_kinds_and_containers() {
+    let index = index();
+    let symbols: Vec<(&str, SymbolKind, Option<&str>)> = index
+        .symbols()
+        .map(|s| (s.name.as_str(), s.kind, s.container.as_deref()))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            ("Shape", SymbolKind::Enum, None),
+            ("Circle", SymbolKind::Variant, Some("Shape")),
+            ("Square", SymbolKind::Variant, Some("Shape")),
+            ("SIDES", SymbolKind::Const, None),
+            ("area", SymbolKind::Function, None),
+            ("s", SymbolKind::Local, Some("area")),
+            ("scale", SymbolKind::Local, Some("area")),
+            ("r", SymbolKind::Local, Some("area")),
+            ("w", SymbolKind::Local, Some("area")),
+            ("unused", SymbolKind::Function, None),
+            ("main", SymbolKind::Function, None),
+        ]
+    );
+    let area = index.symbols().find(|s| s.name == "area").unwrap();
+    assert_eq!(
+        &SHAPES[area.span.start as usize..area.span.end as usize],
+        "area"
+    );
+
+    let found: Vec<&str> = index
+        .workspace_symbols("S")
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(found, vec!["Shape", "Square", "SIDES", "unused"]);
+}
+
+#[test]
+fn finds_references_by_definition() {
+    let index = index();
+    let file = index.file_id("shapes.strata").unwrap();
+    let text = |def: &DefId| -> Vec<&str> {
+        index
+            .references(file, def)
+            .iter()
+            .map(|span| &SHAPES[span.start as usize..span.end as usize])
+            .collect()
+    };
+    let area = index.symbols().find(|s| s.name == "area").unwrap();
+    assert_eq!(text(&area.def), vec!["area", "area"]);
+    let square = DefId::Ctor {
+        adt: "Shape".to_string(),
+        variant: Some("Square".to_string()),
+    };
+    assert_eq!(text(&square), vec!["Square", "Square"]);
+    assert_eq!(text(&DefId::Type("Shape".to_string())).len(), 5);
+
+    // Dead code: functions nothing calls
+    let uncalled: Vec<&str> = index
+        .symbols()
+        .filter(|s| s.kind == SymbolKind::Function && s.name != "main")
+        .filter(|s| index.references(file, &s.def).is_empty())
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(uncalled, vec!["unused"]);
+
+    // From a use back to its definition
+    let use_of_scale = SHAPES.rfind("scale").unwrap() as u32;
+    let symbol = index.symbol_at(file, use_of_scale + 1).unwrap();
+    assert_eq!(
+        (symbol.name.as_str(), symbol.kind),
+        ("scale", SymbolKind::Local)
+    );
+}
+
+#[test]
+fn reindexes_only_changed_files() {
+    let mut index = index();
+    index
+        .update("util.strata", "fn twice(x: Int) -> Int { x * 2 }")
+        .unwrap();
+    assert!(!index.update("shapes.strata", SHAPES).unwrap());
+
+    let util = index.file_id("util.strata").unwrap();
+    assert_eq!(index.file_name(util), Some("util.strata"));
+    let twice = index.symbols().find(|s| s.name == "twice").unwrap();
+    assert_eq!(twice.span.file, util);
+
+    assert!(index
+        .update("util.strata", "fn thrice(x: Int) -> Int { x * 3 }")
+        .unwrap());
+    assert!(index.symbols().all(|s| s.name != "twice"));
+    // A file that doesn't parse keeps its last index
+    assert!(index.update("util.strata", "fn thrice(").is_err());
+    assert!(index.symbols().any(|s| s.name == "thrice"));
+
+    assert!(index.remove("util.strata"));
+    assert!(index.symbols().all(|s| s.name != "thrice"));
+    assert_eq!(index.file_id("util.strata"), Some(util));
+}
+
+#[test]
+fn round_trips_through_disk() {
+    let mut index = index();
+    index
+        .update("util.strata", "fn twice(x: Int) -> Int { x * 2 }")
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("symbols.idx");
+    index.save(&path).unwrap();
+    let loaded = SymbolIndex::load(&path).unwrap();
+    assert_eq!(loaded, index);
+    assert_eq!(loaded.to_bytes(), index.to_bytes());
+
+    // Starting from the saved index, an unchanged file isn't reparsed
+    let mut warm = loaded;
+    assert!(!warm.update("shapes.strata", SHAPES).unwrap());
+
+    let bytes = index.to_bytes();
+    assert_eq!(SymbolIndex::from_bytes(b"nope"), Err(IndexError::BadMagic));
+    assert_eq!(
+        SymbolIndex::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(IndexError::Truncated)
+    );
+    let mut newer = bytes.clone();
+    newer[4] = 99;
+    assert_eq!(
+        SymbolIndex::from_bytes(&newer),
+        Err(IndexError::UnsupportedVersion(99))
+    );
+}
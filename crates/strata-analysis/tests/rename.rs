@@ -0,0 +1,153 @@
+//! Tests for `strata_analysis::rename`.
+
+use strata_analysis::{rename, RenameError, TextEdit};
+
+/// `src` with the name at the `|` renamed to `new_name`
+fn renamed(src: &str, new_name: &str) -> Result<String, RenameError> {
+    let offset = src.find('|').expect("no cursor");
+    let src = src.replacen('|', "", 1);
+    let edits = rename(&src, offset, new_name)?;
+    Ok(TextEdit::apply_all(&src, &edits))
+}
+
+#[test]
+fn renames_a_local_and_only_its_uses() {
+    let src = "fn f(n: Int) -> Int {\n\
+                   let to|tal = n + 1;\n\
+                   let other = { let total = 2; total };\n\
+                   total * other\n\
+               }";
+    assert_eq!(
+        renamed(src, "sum").unwrap(),
+        "fn f(n: Int) -> Int {\n\
+             let sum = n + 1;\n\
+             let other = { let total = 2; total };\n\
+             sum * other\n\
+         }"
+    );
+    // From a use, the same edits
+    let from_use = src
+        .replace("to|tal", "total")
+        .replace("total * ", "t|otal * ");
+    assert_eq!(renamed(&from_use, "sum"), renamed(src, "sum"));
+}
+
+#[test]
+fn renames_a_function_across_items() {
+    let src = "fn he|lper(x: Int) -> Int { x }\n\
+               const LIMIT: Int = 3;\n\
+               fn main() -> Int { helper(LIMIT) + helper(1) }";
+    assert_eq!(
+        renamed(src, "assist").unwrap(),
+        "fn assist(x: Int) -> Int { x }\n\
+         const LIMIT: Int = 3;\n\
+         fn main() -> Int { assist(LIMIT) + assist(1) }"
+    );
+}
+
+#[test]
+fn renames_types_variants_and_imports() {
+    let src = "enum Sha|pe { Circle(Int), Square(Int) }\n\
+               use Shape::Square;\n\
+               fn area(s: Shape) -> Int {\n\
+                   match s { Shape::Circle(r) => r * r * 3, Square(w) => w * w }\n\
+               }";
+    assert_eq!(
+        renamed(src, "Figure").unwrap(),
+        "enum Figure { Circle(Int), Square(Int) }\n\
+         use Figure::Square;\n\
+         fn area(s: Figure) -> Int {\n\
+             match s { Figure::Circle(r) => r * r * 3, Square(w) => w * w }\n\
+         }"
+    );
+    let variant = src
+        .replace("Sha|pe", "Shape")
+        .replace("Square(w)", "Squ|are(w)");
+    assert_eq!(
+        renamed(&variant, "Box").unwrap(),
+        "enum Shape { Circle(Int), Box(Int) }\n\
+         use Shape::Box;\n\
+         fn area(s: Shape) -> Int {\n\
+             match s { Shape::Circle(r) => r * r * 3, Box(w) => w * w }\n\
+         }"
+    );
+}
+
+#[test]
+fn renames_struct_fields_through_shorthand() {
+    let src = "struct Point { x: Int, y: Int }\n\
+               fn make(x: Int) -> Point { Point { x, y: 0 } }\n\
+               fn sum(p: Point) -> Int { let Point { x, y: b } = p; x + b }\n\
+               fn main() -> Int { let p = Point { |x: 1, y: 2 }; sum(p) }";
+    assert_eq!(
+        renamed(src, "horiz").unwrap(),
+        "struct Point { horiz: Int, y: Int }\n\
+         fn make(x: Int) -> Point { Point { horiz: x, y: 0 } }\n\
+         fn sum(p: Point) -> Int { let Point { horiz: x, y: b } = p; x + b }\n\
+         fn main() -> Int { let p = Point { horiz: 1, y: 2 }; sum(p) }"
+    );
+    // The binding a shorthand field introduces keeps the field's name
+    let binding = src.replace("|x: 1", "x: 1").replace("x + b", "|x + b");
+    assert_eq!(
+        renamed(&binding, "a").unwrap(),
+        "struct Point { x: Int, y: Int }\n\
+         fn make(x: Int) -> Point { Point { x, y: 0 } }\n\
+         fn sum(p: Point) -> Int { let Point { x: a, y: b } = p; a + b }\n\
+         fn main() -> Int { let p = Point { x: 1, y: 2 }; sum(p) }"
+    );
+}
+
+#[test]
+fn conflicts_with_names_in_scope() {
+    let src = "fn f() -> Int {\n\
+                   let a = 1;\n\
+                   let |b = 2;\n\
+                   a + b\n\
+               }";
+    assert!(matches!(
+        renamed(src, "a"),
+        Err(RenameError::Conflict { name, .. }) if name == "a"
+    ));
+    // Renaming a parameter would capture the global it shadows
+    let capture = "let scale = 10;\nfn f(|n: Int) -> Int { n * scale }";
+    assert!(matches!(
+        renamed(capture, "scale"),
+        Err(RenameError::Conflict { .. })
+    ));
+    let sibling = "enum Level { |Low, High }";
+    assert!(matches!(
+        renamed(sibling, "High"),
+        Err(RenameError::Conflict { .. })
+    ));
+    let field = "struct P { |x: Int, y: Int }";
+    assert!(matches!(
+        renamed(field, "y"),
+        Err(RenameError::Conflict { .. })
+    ));
+    // In a separate scope the name is free
+    let separate = "fn g() -> Int { let a = 1; a }\nfn f() -> Int { let |b = 2; b }";
+    assert!(renamed(separate, "a").is_ok());
+}
+
+#[test]
+fn rejects_invalid_names_builtins_and_empty_positions() {
+    let src = "fn f(|x: Int) -> Int { x }";
+    for bad in ["", "1x", "match", "_", "a-b"] {
+        assert!(
+            matches!(renamed(src, bad), Err(RenameError::InvalidName { .. })),
+            "{:?} accepted",
+            bad
+        );
+    }
+    let prelude = "fn f() -> Option<Int> { Option::So|me(1) }";
+    assert!(matches!(
+        renamed(prelude, "Just"),
+        Err(RenameError::Builtin { name }) if name == "Option"
+    ));
+    let nothing = "fn f() -> Int { 1 |+ 2 }";
+    assert_eq!(renamed(nothing, "y"), Err(RenameError::NothingToRename));
+    assert!(matches!(
+        renamed("fn f( |", "y"),
+        Err(RenameError::Parse(_))
+    ));
+}
@@ -1,10 +1,273 @@
 pub mod span {
     use serde::Serialize;
 
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    /// Which file of a [`SourceDb`] a span points into. The default id is
+    /// the first file added, so single-file tools never have to name it.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+    pub struct FileId(pub u32);
+
+    impl FileId {
+        pub fn is_default(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    /// Byte range `start..end` of one source file.
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
     pub struct Span {
         pub start: u32,
         pub end: u32,
+        #[serde(skip_serializing_if = "FileId::is_default")]
+        pub file: FileId,
+    }
+
+    /// Longest source, in bytes, whose offsets fit in a [`Span`]. Parsers
+    /// reject longer input up front rather than let offsets wrap.
+    pub const MAX_SOURCE_LEN: usize = u32::MAX as usize;
+
+    impl Span {
+        /// `start..end` in `file`, or `None` if an offset is past
+        /// [`MAX_SOURCE_LEN`] or `start > end`.
+        pub fn checked(file: FileId, start: usize, end: usize) -> Option<Span> {
+            let start = u32::try_from(start).ok()?;
+            let end = u32::try_from(end).ok()?;
+            (start <= end).then_some(Span { start, end, file })
+        }
+
+        /// `start..end` in `file`, with offsets past [`MAX_SOURCE_LEN`]
+        /// clamped to it and `end` raised to `start`. For code whose input
+        /// was already length-checked: clamping can't happen there, and if a
+        /// refactor makes it reachable the span is wrong but never wraps.
+        pub fn clamped(file: FileId, start: usize, end: usize) -> Span {
+            let clamp = |offset: usize| u32::try_from(offset).unwrap_or(u32::MAX);
+            let start = clamp(start);
+            Span {
+                start,
+                end: clamp(end).max(start),
+                file,
+            }
+        }
+
+        /// Length in bytes.
+        pub fn len(&self) -> u32 {
+            self.end.saturating_sub(self.start)
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+    }
+
+    // The file is left out for the default file, so single-file messages
+    // read `Span { start: 4, end: 9 }`.
+    impl std::fmt::Debug for Span {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let mut s = f.debug_struct("Span");
+            s.field("start", &self.start).field("end", &self.end);
+            if !self.file.is_default() {
+                s.field("file", &self.file.0);
+            }
+            s.finish()
+        }
+    }
+
+    /// Maps byte offsets to 1-based line/column positions.
+    #[derive(Debug, Clone)]
+    pub struct LineIndex {
+        src: String,
+        /// Byte offset of the start of each line
+        line_starts: Vec<usize>,
+    }
+
+    impl LineIndex {
+        pub fn new(src: &str) -> Self {
+            let mut line_starts = vec![0];
+            line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+            Self {
+                src: src.to_string(),
+                line_starts,
+            }
+        }
+
+        /// The indexed source text.
+        pub fn source(&self) -> &str {
+            &self.src
+        }
+
+        /// Number of lines in the source.
+        pub fn line_count(&self) -> usize {
+            self.line_starts.len()
+        }
+
+        /// Text of 1-based line `line`, without its line ending.
+        pub fn line_text(&self, line: usize) -> Option<&str> {
+            let start = *self.line_starts.get(line.checked_sub(1)?)?;
+            let end = self
+                .line_starts
+                .get(line)
+                .map_or(self.src.len(), |&next| next - 1);
+            Some(self.src[start..end].trim_end_matches('\r'))
+        }
+
+        /// 1-based `(line, column)` of a byte offset. Columns count characters,
+        /// not bytes, so multi-byte text does not skew them.
+        pub fn line_col(&self, offset: u32) -> (usize, usize) {
+            let offset = (offset as usize).min(self.src.len());
+            let line = self.line_starts.partition_point(|&s| s <= offset) - 1;
+            let start = self.line_starts[line];
+            let col = self
+                .src
+                .get(start..offset)
+                .map_or(offset - start, |s| s.chars().count());
+            (line + 1, col + 1)
+        }
+
+        /// Render a span as `line:col-line:col`.
+        pub fn range(&self, span: Span) -> String {
+            let (l1, c1) = self.line_col(span.start);
+            let (l2, c2) = self.line_col(span.end);
+            format!("{}:{}-{}:{}", l1, c1, l2, c2)
+        }
+    }
+
+    /// The source files of one program, each named and line-indexed, so a
+    /// span from any of them renders as `name:line:col`.
+    #[derive(Debug, Clone, Default)]
+    pub struct SourceDb {
+        files: Vec<(String, LineIndex)>,
+    }
+
+    impl SourceDb {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Add a file; ids are handed out in order from the default id.
+        pub fn add(&mut self, name: impl Into<String>, src: &str) -> FileId {
+            self.files.push((name.into(), LineIndex::new(src)));
+            FileId(self.files.len() as u32 - 1)
+        }
+
+        /// Display name of `file`, or `<unknown>` for an id from elsewhere.
+        pub fn name(&self, file: FileId) -> &str {
+            self.files
+                .get(file.0 as usize)
+                .map_or("<unknown>", |(name, _)| name)
+        }
+
+        /// Source text of `file`.
+        pub fn source(&self, file: FileId) -> Option<&str> {
+            self.index(file).map(LineIndex::source)
+        }
+
+        /// Line index of `file`.
+        pub fn index(&self, file: FileId) -> Option<&LineIndex> {
+            self.files.get(file.0 as usize).map(|(_, index)| index)
+        }
+
+        /// Render where `span` starts as `name:line:col`.
+        pub fn location(&self, span: Span) -> String {
+            match self.index(span.file) {
+                Some(index) => {
+                    let (line, col) = index.line_col(span.start);
+                    format!("{}:{}:{}", self.name(span.file), line, col)
+                }
+                None => format!("{}:?", self.name(span.file)),
+            }
+        }
+    }
+}
+
+pub mod diag {
+    use super::span::Span;
+    use serde::Serialize;
+
+    /// Severity of a [`Diagnostic`].
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Severity {
+        Error,
+        Warning,
+    }
+
+    /// Tool-facing report of a problem in source: a stable machine-readable
+    /// code, a human message, the span it applies to, and any suggested
+    /// [`Fix`]es.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+    pub struct Diagnostic {
+        pub code: &'static str,
+        pub severity: Severity,
+        pub message: String,
+        pub span: Span,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub fixes: Vec<Fix>,
+    }
+
+    impl Diagnostic {
+        pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+            Self {
+                code,
+                severity: Severity::Error,
+                message: message.into(),
+                span,
+                fixes: Vec::new(),
+            }
+        }
+
+        /// Attach suggested fixes.
+        pub fn with_fixes(mut self, fixes: impl IntoIterator<Item = Fix>) -> Self {
+            self.fixes.extend(fixes);
+            self
+        }
+    }
+
+    /// Whether a [`Fix`] can be applied without human review.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Applicability {
+        /// The edit is exactly what is needed; tools may apply it unattended.
+        MachineApplicable,
+        /// The edit contains placeholder code the user must fill in.
+        HasPlaceholders,
+    }
+
+    /// A suggested edit: replace the source at `span` with `replacement`.
+    /// An empty span is an insertion.
+    #[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+    pub struct Fix {
+        /// What the edit does, e.g. "add `Time` to the effect annotation"
+        pub message: String,
+        pub span: Span,
+        pub replacement: String,
+        pub applicability: Applicability,
+    }
+
+    impl Fix {
+        /// Apply `fixes` to `src`. Fixes that overlap one already applied
+        /// (in source order) are skipped. Returns the new source and the
+        /// number of fixes applied.
+        pub fn apply_all<'a>(
+            src: &str,
+            fixes: impl IntoIterator<Item = &'a Fix>,
+        ) -> (String, usize) {
+            let mut fixes: Vec<&Fix> = fixes.into_iter().collect();
+            fixes.sort_by_key(|f| (f.span.start, f.span.end));
+            let mut out = String::with_capacity(src.len());
+            let mut pos = 0;
+            let mut applied = 0;
+            for fix in fixes {
+                let (start, end) = (fix.span.start as usize, fix.span.end as usize);
+                if start < pos || end > src.len() || start > end {
+                    continue;
+                }
+                out.push_str(&src[pos..start]);
+                out.push_str(&fix.replacement);
+                pos = end;
+                applied += 1;
+            }
+            out.push_str(&src[pos..]);
+            (out, applied)
+        }
     }
 }
 
@@ -12,35 +275,137 @@ pub mod ast {
     use super::span::Span;
     use serde::Serialize;
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub struct Module {
         pub items: Vec<Item>,
         pub span: Span,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub enum Item {
         Let(LetDecl),
+        Const(ConstDecl),
         Fn(FnDecl),
         Struct(StructDef),
         Enum(EnumDef),
         ExternFn(ExternFnDecl),
+        Use(UseDecl),
     }
 
-    /// Struct definition: `struct Point<T> { x: T, y: T }`
+    impl Item {
+        /// The `#[...]` attributes written before the item
+        pub fn attrs(&self) -> &[Attribute] {
+            match self {
+                Item::Let(d) => &d.attrs,
+                Item::Const(d) => &d.attrs,
+                Item::Fn(d) => &d.attrs,
+                Item::Struct(d) => &d.attrs,
+                Item::Enum(d) => &d.attrs,
+                Item::ExternFn(d) => &d.attrs,
+                Item::Use(d) => &d.attrs,
+            }
+        }
+
+        /// Mutable access to the item's attributes
+        pub fn attrs_mut(&mut self) -> &mut Vec<Attribute> {
+            match self {
+                Item::Let(d) => &mut d.attrs,
+                Item::Const(d) => &mut d.attrs,
+                Item::Fn(d) => &mut d.attrs,
+                Item::Struct(d) => &mut d.attrs,
+                Item::Enum(d) => &mut d.attrs,
+                Item::ExternFn(d) => &mut d.attrs,
+                Item::Use(d) => &mut d.attrs,
+            }
+        }
+    }
+
+    /// Item attribute: `#[test]`, `#[allow(shadowed_binding)]`,
+    /// `#[deprecated("use area")]`, or `#[cfg(feature = "net")]`
+    #[derive(Debug, Clone, Serialize)]
+    pub struct Attribute {
+        pub name: Ident,
+        /// The parenthesized arguments; empty for `#[name]`
+        pub args: Vec<AttrArg>,
+        /// From `#` through `]`
+        pub span: Span,
+    }
+
+    /// One argument of an [`Attribute`], or of a nested list inside one
+    #[derive(Debug, Clone, Serialize)]
+    pub enum AttrArg {
+        /// A bare name: `shadowed_binding`
+        Word(Ident),
+        /// A string literal: `"use area"`
+        Str { value: String, span: Span },
+        /// `name = "value"`: `feature = "net"`
+        NameValue {
+            name: Ident,
+            value: String,
+            span: Span,
+        },
+        /// `name(args)`: `not(feature = "net")`
+        List {
+            name: Ident,
+            args: Vec<AttrArg>,
+            span: Span,
+        },
+    }
+
+    impl AttrArg {
+        pub fn span(&self) -> Span {
+            match self {
+                AttrArg::Word(ident) => ident.span,
+                AttrArg::Str { span, .. }
+                | AttrArg::NameValue { span, .. }
+                | AttrArg::List { span, .. } => *span,
+            }
+        }
+    }
+
+    /// Variant import: `use Shape::*;` or `use Shape::Circle;`, after which
+    /// the imported variants may be written without the `Shape::` prefix
+    #[derive(Debug, Clone, Serialize)]
+    pub struct UseDecl {
+        pub enum_name: Ident,
+        /// The imported variant; `None` for `*`, every variant
+        pub variant: Option<Ident>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
+        pub span: Span,
+    }
+
+    /// Struct definition: `struct Point<T> { x: T, y: T }`, or a tuple
+    /// struct `struct UserId(Int);` whose fields are named `0`, `1`, ...
     #[derive(Debug, Clone, Serialize)]
     pub struct StructDef {
         pub name: Ident,
         pub type_params: Vec<Ident>,
         pub fields: Vec<Field>,
+        /// Declared `linear struct`: may hold capabilities and is single-use
+        pub linear: bool,
+        /// Declared with positional fields: built by calling `UserId(1)`
+        /// and matched by `UserId(n)`
+        pub tuple: bool,
+        /// Declared `pub`: visible to other modules
+        pub public: bool,
+        /// Declared `transparent struct`: a one-field tuple struct that
+        /// extern fns see as the value it wraps
+        pub transparent: bool,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
         pub span: Span,
     }
 
-    /// Field in a struct: `name: Type`
+    /// Field in a struct or record type: `name: Type`
     #[derive(Debug, Clone, Serialize)]
     pub struct Field {
         pub name: Ident,
         pub ty: TypeExpr,
+        /// Default value of a named struct field: `retries: Int = 3`, which
+        /// a struct literal may then leave off
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub default: Option<Expr>,
         pub span: Span,
     }
 
@@ -50,6 +415,12 @@ pub mod ast {
         pub name: Ident,
         pub type_params: Vec<Ident>,
         pub variants: Vec<Variant>,
+        /// Declared `linear enum`: may hold capabilities and is single-use
+        pub linear: bool,
+        /// Declared `pub`: visible to other modules
+        pub public: bool,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
         pub span: Span,
     }
 
@@ -58,6 +429,8 @@ pub mod ast {
     pub struct Variant {
         pub name: Ident,
         pub fields: VariantFields,
+        /// Explicit discriminant: `Red = 1`
+        pub discriminant: Option<(i64, Span)>,
         pub span: Span,
     }
 
@@ -70,40 +443,75 @@ pub mod ast {
         Tuple(Vec<TypeExpr>),
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub struct FnDecl {
         pub name: Ident,
         pub params: Vec<Param>,
         pub ret_ty: Option<TypeExpr>,
         /// Effect annotation: `& { Fs, Net }`. None means unannotated (inferred).
         pub effects: Option<Vec<Ident>>,
+        /// Declared `pub fn`: visible to other modules
+        pub public: bool,
+        /// Declared `bench fn`: a benchmark `strata bench` runs
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        pub bench: bool,
+        /// Declared `pure fn`: the checker verifies it performs no effects
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        pub pure: bool,
         pub body: Block,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
         pub span: Span,
     }
 
     /// Extern function declaration: `extern fn read(path: String) -> String & {Fs};`
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub struct ExternFnDecl {
         pub name: Ident,
         pub params: Vec<Param>,
         pub ret_ty: Option<TypeExpr>,
         /// Effect annotation: `& { Fs, Net }`. None means pure.
         pub effects: Option<Vec<Ident>>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
         pub span: Span,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub struct Param {
         pub name: Ident,
         pub ty: Option<TypeExpr>,
+        /// Default value: `timeout: Int = 30`. Only `extern fn` parameters
+        /// may have one; a call may leave them off.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub default: Option<Expr>,
+        /// Declared `args: ...Show`: the last parameter of an `extern fn`,
+        /// taking any number of arguments, each fitting the type after the
+        /// `...` (`Show` for any value `show` accepts)
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        pub variadic: bool,
         pub span: Span,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Clone, Serialize)]
     pub struct LetDecl {
         pub name: Ident,
         pub ty: Option<TypeExpr>,
         pub value: Expr,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
+        pub span: Span,
+    }
+
+    /// Constant declaration: `const MAX: Int = 100;`. The type is required;
+    /// the value is evaluated at check time.
+    #[derive(Debug, Clone, Serialize)]
+    pub struct ConstDecl {
+        pub name: Ident,
+        pub ty: TypeExpr,
+        pub value: Expr,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub attrs: Vec<Attribute>,
         pub span: Span,
     }
 
@@ -133,7 +541,9 @@ pub mod ast {
         },
         /// Tuple type: `(A, B, C)`
         Tuple(Vec<TypeExpr>, Span),
-        /// Reference type: `&T` (only allowed in extern fn params for capability borrowing)
+        /// Record type: `{x: Int, y: Int}`, matched structurally
+        Record(Vec<Field>, Span),
+        /// Reference type: `&T` (only allowed as a function parameter type, for borrowing)
         Ref(Box<TypeExpr>, Span),
     }
 
@@ -145,6 +555,7 @@ pub mod ast {
                 TypeExpr::Arrow { span, .. } => *span,
                 TypeExpr::App { span, .. } => *span,
                 TypeExpr::Tuple(_, span) => *span,
+                TypeExpr::Record(_, span) => *span,
                 TypeExpr::Ref(_, span) => *span,
             }
         }
@@ -162,6 +573,13 @@ pub mod ast {
             value: Expr,
             span: Span,
         },
+        /// Declaration without a value: `let mut x: T;`
+        /// Always mutable; every path must assign `x` before it is read
+        Declare {
+            name: Ident,
+            ty: TypeExpr,
+            span: Span,
+        },
         /// Assignment: `x = e;`
         Assign {
             target: Ident,
@@ -172,6 +590,25 @@ pub mod ast {
         Expr { expr: Expr, span: Span },
         /// Return statement: `return e;` or `return;`
         Return { value: Option<Expr>, span: Span },
+        /// Nested function: `fn helper(x: Int) -> Int { ... }`, visible in
+        /// its own body and in the rest of the enclosing block. Adjacent
+        /// nested functions form a group that can call each other; see
+        /// [`Block::fn_group_at`]
+        Fn(Box<FnDecl>),
+    }
+
+    impl Stmt {
+        /// Get the span of this statement
+        pub fn span(&self) -> Span {
+            match self {
+                Stmt::Let { span, .. }
+                | Stmt::Declare { span, .. }
+                | Stmt::Assign { span, .. }
+                | Stmt::Expr { span, .. }
+                | Stmt::Return { span, .. } => *span,
+                Stmt::Fn(decl) => decl.span,
+            }
+        }
     }
 
     /// Block expression: `{ stmt; stmt; expr }`
@@ -184,6 +621,25 @@ pub mod ast {
         pub span: Span,
     }
 
+    impl Block {
+        /// The nested functions declared together from statement `i`: the
+        /// run of adjacent `fn` statements it starts, all visible in each
+        /// other's bodies. Empty unless statement `i` starts such a run.
+        pub fn fn_group_at(&self, i: usize) -> Vec<&FnDecl> {
+            let is_fn = |s: &Stmt| matches!(s, Stmt::Fn(_));
+            if i > 0 && self.stmts.get(i - 1).is_some_and(is_fn) {
+                return Vec::new();
+            }
+            self.stmts[i..]
+                .iter()
+                .map_while(|s| match s {
+                    Stmt::Fn(decl) => Some(decl.as_ref()),
+                    _ => None,
+                })
+                .collect()
+        }
+    }
+
     /// Qualified path: `Option::Some`, `Result::Ok`
     #[derive(Debug, Clone, Serialize)]
     pub struct Path {
@@ -220,6 +676,21 @@ pub mod ast {
         Ident(Ident),
         /// Literal pattern: `0`, `true`, `"hello"`
         Literal(Lit, Span),
+        /// Integer range pattern: `1..=9`, `0..10`, `100..`, `..=-1`
+        Range {
+            lo: Option<i64>,
+            hi: Option<i64>,
+            /// `..=` (true) or `..` (false); only meaningful with an upper bound
+            inclusive: bool,
+            span: Span,
+        },
+        /// String prefix pattern: `"get " + rest` matches a string starting
+        /// with `prefix`, matching the remainder against `rest`
+        StrPrefix {
+            prefix: String,
+            rest: Box<Pat>,
+            span: Span,
+        },
         /// Tuple pattern: `(a, b)`
         Tuple(Vec<Pat>, Span),
         /// Struct pattern: `Point { x, y: 0 }`
@@ -228,6 +699,14 @@ pub mod ast {
             fields: Vec<PatField>,
             span: Span,
         },
+        /// Record pattern: `{ x, y: 0 }` matches a record with exactly these
+        /// fields; `{ x, .. }` one with at least these
+        Record {
+            fields: Vec<PatField>,
+            /// Ends in `..`
+            rest: bool,
+            span: Span,
+        },
         /// Variant pattern: `Option::Some(x)`, `Option::None`
         Variant {
             path: Path,
@@ -243,11 +722,32 @@ pub mod ast {
                 Pat::Wildcard(span) => *span,
                 Pat::Ident(ident) => ident.span,
                 Pat::Literal(_, span) => *span,
+                Pat::Range { span, .. } => *span,
+                Pat::StrPrefix { span, .. } => *span,
                 Pat::Tuple(_, span) => *span,
                 Pat::Struct { span, .. } => *span,
+                Pat::Record { span, .. } => *span,
                 Pat::Variant { span, .. } => *span,
             }
         }
+
+        /// Inclusive `(lo, hi)` bounds of a range pattern.
+        /// Returns None for other patterns and for ranges that match nothing.
+        pub fn range_bounds(&self) -> Option<(i64, i64)> {
+            let Pat::Range {
+                lo, hi, inclusive, ..
+            } = self
+            else {
+                return None;
+            };
+            let lo = lo.unwrap_or(i64::MIN);
+            let hi = match hi {
+                Some(hi) if *inclusive => *hi,
+                Some(hi) => hi.checked_sub(1)?,
+                None => i64::MAX,
+            };
+            (lo <= hi).then_some((lo, hi))
+        }
     }
 
     /// Field in a struct pattern: `x` or `x: pat`
@@ -278,6 +778,12 @@ pub mod ast {
         Call {
             callee: Box<Expr>,
             args: Vec<Expr>,
+            /// The name each argument is passed by (`None` for a positional
+            /// one) when any is: `draw(x: 10, y: 20)`. Empty when all are
+            /// positional. Named-argument expansion puts the arguments in
+            /// parameter order, keeping each one's name for diagnostics.
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            names: Vec<Option<Ident>>,
             span: Span,
         },
         Binary {
@@ -305,6 +811,20 @@ pub mod ast {
             body: Block,
             span: Span,
         },
+        /// For loop: `for pat in iter { ... }` runs the body on each item
+        /// of an `Iter`, bound to the (irrefutable) pattern
+        For {
+            pat: Pat,
+            iter: Box<Expr>,
+            body: Block,
+            span: Span,
+        },
+        /// Task: `spawn { ... }` runs the block alongside the code after
+        /// it, giving a handle that `join` waits on for the block's value
+        Spawn {
+            body: Block,
+            span: Span,
+        },
         /// Match expression: `match expr { pat => body, ... }`
         Match {
             scrutinee: Box<Expr>,
@@ -322,14 +842,30 @@ pub mod ast {
             fields: Vec<FieldInit>,
             span: Span,
         },
+        /// Record construction: `{ x: 1, y: 2 }`
+        Record {
+            fields: Vec<FieldInit>,
+            span: Span,
+        },
+        /// Record field access: `r.x`
+        Field {
+            expr: Box<Expr>,
+            field: Ident,
+            span: Span,
+        },
         /// Variant construction: `Option::Some(x)`
         /// (Handled as Call on a path, but explicit for clarity in some cases)
         PathExpr(Path),
         /// Borrow expression: `&expr` (produces a reference type)
         Borrow(Box<Expr>, Span),
+        /// Hole: `_` as a call argument, leaving it to be passed later.
+        /// `add(1, _)` applies `add` partially, giving a function of the
+        /// holes' parameters in order
+        Hole(Span),
     }
 
-    /// Field initialization in struct expression: `x: expr` or `x` (shorthand)
+    /// Field initialization in struct or record expression: `x: expr` or `x`
+    /// (shorthand)
     #[derive(Debug, Clone, Serialize)]
     pub struct FieldInit {
         pub name: Ident,
@@ -350,13 +886,23 @@ pub mod ast {
                 Expr::Block(block) => block.span,
                 Expr::If { span, .. } => *span,
                 Expr::While { span, .. } => *span,
+                Expr::For { span, .. } => *span,
+                Expr::Spawn { span, .. } => *span,
                 Expr::Match { span, .. } => *span,
                 Expr::Tuple { span, .. } => *span,
                 Expr::StructExpr { span, .. } => *span,
+                Expr::Record { span, .. } => *span,
+                Expr::Field { span, .. } => *span,
                 Expr::PathExpr(path) => path.span,
                 Expr::Borrow(_, span) => *span,
+                Expr::Hole(span) => *span,
             }
         }
+
+        /// Whether this is a call with a `_` argument: a partial application
+        pub fn is_partial_call(&self) -> bool {
+            matches!(self, Expr::Call { args, .. } if args.iter().any(|a| matches!(a, Expr::Hole(_))))
+        }
     }
 
     #[derive(Debug, Clone, Copy, Serialize)]
@@ -377,7 +923,7 @@ pub mod ast {
         Nil,
     }
 
-    #[derive(Debug, Clone, Copy, Serialize)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
     pub enum BinOp {
         // logical
         Or,
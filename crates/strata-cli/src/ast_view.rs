@@ -0,0 +1,465 @@
+//! Compact tree view of a parsed module for `strata ast`.
+//!
+//! Each line is one node: its kind, the fields that identify it (names,
+//! operators, literal values, annotated types), and its source range as
+//! `line:col-line:col`. Lines and columns are 1-based; the end position is
+//! just past the node's last character. Children are indented two spaces
+//! under their parent.
+
+use strata_ast::ast::{
+    AttrArg, BinOp, Block, Expr, FnDecl, Item, Lit, MatchArm, Module, Param, Pat, Stmt, TypeExpr,
+    UnOp, VariantFields,
+};
+pub use strata_ast::span::LineIndex;
+use strata_ast::span::Span;
+use strata_parse::escape_str;
+
+/// A node of the rendered tree.
+struct Node {
+    label: String,
+    span: Span,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: impl Into<String>, span: Span) -> Self {
+        Self {
+            label: label.into(),
+            span,
+            children: Vec::new(),
+        }
+    }
+
+    fn with(mut self, children: impl IntoIterator<Item = Node>) -> Self {
+        self.children.extend(children);
+        self
+    }
+}
+
+/// Render `module` as a tree. `max_depth` limits how many levels below the
+/// module are shown (`Some(0)` prints only the module line); a node whose
+/// children are cut off says how many were elided.
+pub fn render(module: &Module, src: &str, max_depth: Option<usize>) -> String {
+    let index = LineIndex::new(src);
+    let root = Node::new("Module", module.span).with(module.items.iter().map(item_node));
+    let mut out = String::new();
+    write_node(&mut out, &root, &index, 0, max_depth);
+    out
+}
+
+fn write_node(out: &mut String, node: &Node, index: &LineIndex, depth: usize, max: Option<usize>) {
+    out.push_str(&"  ".repeat(depth));
+    out.push_str(&node.label);
+    out.push_str(" @ ");
+    out.push_str(&index.range(node.span));
+    let cut = max.is_some_and(|m| depth >= m);
+    if cut && !node.children.is_empty() {
+        let n = node.children.len();
+        out.push_str(&format!(
+            " ({} child{} elided)",
+            n,
+            if n == 1 { "" } else { "ren" }
+        ));
+    }
+    out.push('\n');
+    if !cut {
+        for child in &node.children {
+            write_node(out, child, index, depth + 1, max);
+        }
+    }
+}
+
+// ======= items =======
+
+fn item_node(item: &Item) -> Node {
+    let mut node = match item {
+        Item::Let(d) => Node::new(
+            format!("Let {}{}", d.name.text, annotation(d.ty.as_ref())),
+            d.span,
+        )
+        .with([expr_node(&d.value)]),
+        Item::Const(d) => Node::new(
+            format!("Const {}{}", d.name.text, annotation(Some(&d.ty))),
+            d.span,
+        )
+        .with([expr_node(&d.value)]),
+        Item::Fn(d) => fn_node(d),
+        Item::ExternFn(d) => Node::new(
+            format!(
+                "ExternFn {}{}{}",
+                d.name.text,
+                returns(d.ret_ty.as_ref()),
+                effects(d.effects.as_deref())
+            ),
+            d.span,
+        )
+        .with(d.params.iter().map(param_node)),
+        Item::Struct(d) => Node::new(
+            format!(
+                "{}{}{}{}Struct {}{}",
+                if d.public { "Pub" } else { "" },
+                if d.linear { "Linear" } else { "" },
+                if d.transparent { "Transparent" } else { "" },
+                if d.tuple { "Tuple" } else { "" },
+                d.name.text,
+                type_params(&d.type_params)
+            ),
+            d.span,
+        )
+        .with(d.fields.iter().map(|f| {
+            Node::new(
+                format!("Field {}: {}", f.name.text, type_str(&f.ty)),
+                f.span,
+            )
+            .with(f.default.iter().map(expr_node))
+        })),
+        Item::Enum(d) => Node::new(
+            format!(
+                "{}{}Enum {}{}",
+                if d.public { "Pub" } else { "" },
+                if d.linear { "Linear" } else { "" },
+                d.name.text,
+                type_params(&d.type_params)
+            ),
+            d.span,
+        )
+        .with(d.variants.iter().map(|v| {
+            let fields = match &v.fields {
+                VariantFields::Unit => String::new(),
+                VariantFields::Tuple(tys) => format!("({})", type_list(tys)),
+            };
+            Node::new(format!("Variant {}{}", v.name.text, fields), v.span)
+        })),
+        Item::Use(d) => Node::new(
+            format!(
+                "Use {}::{}",
+                d.enum_name.text,
+                d.variant.as_ref().map_or("*", |v| v.text.as_str())
+            ),
+            d.span,
+        ),
+    };
+    // Attributes come first, in the order they were written
+    let attrs = item.attrs().iter().map(|a| {
+        Node::new(
+            format!("Attr {}{}", a.name.text, attr_args(&a.args)),
+            a.span,
+        )
+    });
+    node.children.splice(0..0, attrs);
+    node
+}
+
+/// `(arg, ..)` as written, or nothing for an attribute without arguments
+fn attr_args(args: &[AttrArg]) -> String {
+    if args.is_empty() {
+        return String::new();
+    }
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            AttrArg::Word(name) => name.text.clone(),
+            AttrArg::Str { value, .. } => format!("\"{}\"", escape_str(value)),
+            AttrArg::NameValue { name, value, .. } => {
+                format!("{} = \"{}\"", name.text, escape_str(value))
+            }
+            AttrArg::List { name, args, .. } => {
+                format!("{}{}", name.text, attr_args(args))
+            }
+        })
+        .collect();
+    format!("({})", args.join(", "))
+}
+
+fn fn_node(d: &FnDecl) -> Node {
+    Node::new(
+        format!(
+            "{}Fn {}{}{}",
+            if d.public { "Pub" } else { "" },
+            d.name.text,
+            returns(d.ret_ty.as_ref()),
+            effects(d.effects.as_deref())
+        ),
+        d.span,
+    )
+    .with(d.params.iter().map(param_node))
+    .with([block_node(&d.body)])
+}
+
+fn param_node(p: &Param) -> Node {
+    Node::new(
+        match (&p.ty, p.variadic) {
+            (Some(ty), true) => format!("Param {}: ...{}", p.name.text, type_str(ty)),
+            (ty, _) => format!("Param {}{}", p.name.text, annotation(ty.as_ref())),
+        },
+        p.span,
+    )
+    .with(p.default.iter().map(expr_node))
+}
+
+// ======= blocks and statements =======
+
+fn block_node(b: &Block) -> Node {
+    Node::new("Block", b.span)
+        .with(b.stmts.iter().map(stmt_node))
+        .with(b.tail.as_deref().map(expr_node))
+}
+
+fn stmt_node(s: &Stmt) -> Node {
+    match s {
+        Stmt::Let {
+            mutable,
+            pat,
+            ty,
+            value,
+            span,
+        } => {
+            let kw = if *mutable { "Let mut" } else { "Let" };
+            let ann = annotation(ty.as_ref());
+            // A plain binding is shown inline like a top-level let
+            match pat {
+                Pat::Ident(id) => {
+                    Node::new(format!("{} {}{}", kw, id.text, ann), *span).with([expr_node(value)])
+                }
+                _ => Node::new(format!("{}{}", kw, ann), *span)
+                    .with([pat_node(pat), expr_node(value)]),
+            }
+        }
+        Stmt::Declare { name, ty, span } => Node::new(
+            format!("Declare mut {}{}", name.text, annotation(Some(ty))),
+            *span,
+        ),
+        Stmt::Assign {
+            target,
+            value,
+            span,
+        } => Node::new(format!("Assign {}", target.text), *span).with([expr_node(value)]),
+        Stmt::Expr { expr, span } => Node::new("ExprStmt", *span).with([expr_node(expr)]),
+        Stmt::Fn(d) => fn_node(d),
+        Stmt::Return { value, span } => {
+            Node::new("Return", *span).with(value.as_ref().map(expr_node))
+        }
+    }
+}
+
+// ======= expressions =======
+
+fn expr_node(e: &Expr) -> Node {
+    match e {
+        Expr::Lit(lit, span) => Node::new(format!("Lit {}", lit_str(lit)), *span),
+        Expr::Var(id) => Node::new(format!("Var {}", id.text), id.span),
+        Expr::Hole(span) => Node::new("Hole", *span),
+        Expr::Unary { op, expr, span } => {
+            let op = match op {
+                UnOp::Not => "!",
+                UnOp::Neg => "-",
+            };
+            Node::new(format!("Unary {}", op), *span).with([expr_node(expr)])
+        }
+        Expr::Binary { lhs, op, rhs, span } => {
+            Node::new(format!("Binary {}", binop_str(*op)), *span)
+                .with([expr_node(lhs), expr_node(rhs)])
+        }
+        Expr::Call {
+            callee, args, span, ..
+        } => {
+            let label = match callee.as_ref() {
+                Expr::Var(id) => format!("Call {}", id.text),
+                Expr::PathExpr(p) => format!("Call {}", p.as_str()),
+                _ => "Call".to_string(),
+            };
+            let node = Node::new(label, *span);
+            match callee.as_ref() {
+                Expr::Var(_) | Expr::PathExpr(_) => node,
+                other => node.with([expr_node(other)]),
+            }
+            .with(args.iter().map(expr_node))
+        }
+        Expr::Paren { inner, span } => Node::new("Paren", *span).with([expr_node(inner)]),
+        Expr::Block(b) => block_node(b),
+        Expr::If {
+            cond,
+            then_,
+            else_,
+            span,
+        } => Node::new("If", *span)
+            .with([expr_node(cond), block_node(then_)])
+            .with(else_.as_deref().map(expr_node)),
+        Expr::For {
+            pat,
+            iter,
+            body,
+            span,
+        } => Node::new("For", *span).with([pat_node(pat), expr_node(iter), block_node(body)]),
+        Expr::Spawn { body, span } => Node::new("Spawn", *span).with([block_node(body)]),
+        Expr::While { cond, body, span } => {
+            Node::new("While", *span).with([expr_node(cond), block_node(body)])
+        }
+        Expr::Match {
+            scrutinee,
+            arms,
+            span,
+        } => Node::new("Match", *span)
+            .with([expr_node(scrutinee)])
+            .with(arms.iter().map(arm_node)),
+        Expr::Tuple { elems, span } => {
+            Node::new(format!("Tuple ({})", elems.len()), *span).with(elems.iter().map(expr_node))
+        }
+        Expr::StructExpr { path, fields, span } => {
+            Node::new(format!("StructExpr {}", path.as_str()), *span).with(fields.iter().map(|f| {
+                Node::new(format!("FieldInit {}", f.name.text), f.span).with([expr_node(&f.value)])
+            }))
+        }
+        Expr::Record { fields, span } => Node::new(format!("Record ({})", fields.len()), *span)
+            .with(fields.iter().map(|f| {
+                Node::new(format!("FieldInit {}", f.name.text), f.span).with([expr_node(&f.value)])
+            })),
+        Expr::Field { expr, field, span } => {
+            Node::new(format!("Field {}", field.text), *span).with([expr_node(expr)])
+        }
+        Expr::PathExpr(p) => Node::new(format!("Path {}", p.as_str()), p.span),
+        Expr::Borrow(inner, span) => Node::new("Borrow", *span).with([expr_node(inner)]),
+    }
+}
+
+fn arm_node(arm: &MatchArm) -> Node {
+    Node::new("Arm", arm.span).with([pat_node(&arm.pat), expr_node(&arm.body)])
+}
+
+// ======= patterns =======
+
+fn pat_node(p: &Pat) -> Node {
+    match p {
+        Pat::Wildcard(span) => Node::new("PatWildcard", *span),
+        Pat::Ident(id) => Node::new(format!("PatIdent {}", id.text), id.span),
+        Pat::Literal(lit, span) => Node::new(format!("PatLit {}", lit_str(lit)), *span),
+        Pat::Range {
+            lo,
+            hi,
+            inclusive,
+            span,
+        } => {
+            let bound = |b: &Option<i64>| b.map(|v| v.to_string()).unwrap_or_default();
+            let op = if *inclusive { "..=" } else { ".." };
+            Node::new(format!("PatRange {}{}{}", bound(lo), op, bound(hi)), *span)
+        }
+        Pat::StrPrefix { prefix, rest, span } => {
+            Node::new(format!("PatStrPrefix \"{}\"", escape_str(prefix)), *span)
+                .with([pat_node(rest)])
+        }
+        Pat::Tuple(elems, span) => {
+            Node::new(format!("PatTuple ({})", elems.len()), *span).with(elems.iter().map(pat_node))
+        }
+        Pat::Struct { path, fields, span } => {
+            Node::new(format!("PatStruct {}", path.as_str()), *span).with(fields.iter().map(|f| {
+                Node::new(format!("PatField {}", f.name.text), f.span).with([pat_node(&f.pat)])
+            }))
+        }
+        Pat::Record { fields, rest, span } => {
+            let rest = if *rest { " .." } else { "" };
+            Node::new(format!("PatRecord ({}){}", fields.len(), rest), *span).with(
+                fields.iter().map(|f| {
+                    Node::new(format!("PatField {}", f.name.text), f.span).with([pat_node(&f.pat)])
+                }),
+            )
+        }
+        Pat::Variant { path, fields, span } => {
+            Node::new(format!("PatVariant {}", path.as_str()), *span)
+                .with(fields.iter().map(pat_node))
+        }
+    }
+}
+
+// ======= field formatting =======
+
+fn lit_str(lit: &Lit) -> String {
+    match lit {
+        Lit::Int(i) => i.to_string(),
+        Lit::Float(f) => format!("{:?}", f),
+        Lit::Str(s) => format!("\"{}\"", escape_str(s)),
+        Lit::Bool(b) => b.to_string(),
+        Lit::Nil => "nil".to_string(),
+    }
+}
+
+pub(crate) fn binop_str(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Or => "||",
+        BinOp::And => "&&",
+        BinOp::Eq => "==",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Le => "<=",
+        BinOp::Gt => ">",
+        BinOp::Ge => ">=",
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+    }
+}
+
+/// Source-like rendering of a type annotation.
+pub fn type_str(ty: &TypeExpr) -> String {
+    let path = |segs: &[strata_ast::ast::Ident]| {
+        segs.iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("::")
+    };
+    match ty {
+        TypeExpr::Path(segs, _) => path(segs),
+        TypeExpr::App { base, args, .. } => format!("{}<{}>", path(base), type_list(args)),
+        TypeExpr::Tuple(elems, _) => format!("({})", type_list(elems)),
+        TypeExpr::Ref(inner, _) => format!("&{}", type_str(inner)),
+        TypeExpr::Record(fields, _) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|f| format!("{}: {}", f.name.text, type_str(&f.ty)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        TypeExpr::Arrow {
+            params,
+            ret,
+            effects: effs,
+            ..
+        } => format!(
+            "fn({}) -> {}{}",
+            type_list(params),
+            type_str(ret),
+            effects(effs.as_deref())
+        ),
+    }
+}
+
+fn type_list(tys: &[TypeExpr]) -> String {
+    tys.iter().map(type_str).collect::<Vec<_>>().join(", ")
+}
+
+fn type_params(params: &[strata_ast::ast::Ident]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+    let names: Vec<_> = params.iter().map(|p| p.text.as_str()).collect();
+    format!("<{}>", names.join(", "))
+}
+
+fn annotation(ty: Option<&TypeExpr>) -> String {
+    ty.map(|t| format!(": {}", type_str(t))).unwrap_or_default()
+}
+
+fn returns(ty: Option<&TypeExpr>) -> String {
+    ty.map(|t| format!(" -> {}", type_str(t)))
+        .unwrap_or_default()
+}
+
+fn effects(effs: Option<&[strata_ast::ast::Ident]>) -> String {
+    match effs {
+        Some(effs) => {
+            let names: Vec<_> = effs.iter().map(|e| e.text.as_str()).collect();
+            format!(" & {{{}}}", names.join(", "))
+        }
+        None => String::new(),
+    }
+}
@@ -0,0 +1,239 @@
+//! Audit log of capability use, separate from the effect trace.
+//!
+//! The trace records every value for replay; the audit log records only
+//! which host functions ran, under which capability, and when, one
+//! greppable line per event:
+//!
+//! ```text
+//! 2026-01-01T00:00:00.000Z run=81f2-1767225600000 event=start program="job.strata"
+//! 2026-01-01T00:00:00.004Z run=81f2-1767225600000 event=call fn=read_file cap=FsCap access=borrow effect=Fs status=ok
+//! 2026-01-01T00:00:00.005Z run=81f2-1767225600000 event=end status=success
+//! ```
+//!
+//! A file log rotates by size: once it reaches `max_bytes`, `audit.log`
+//! becomes `audit.log.1` (shifting older files up, dropping the oldest
+//! past `keep`) and a fresh file is started.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::host::{now_iso8601, HostError};
+
+/// When a file audit log rotates, and how many old files it keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+impl Default for Rotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            keep: 3,
+        }
+    }
+}
+
+enum Sink {
+    File {
+        path: PathBuf,
+        file: File,
+        size: u64,
+        rotation: Rotation,
+    },
+    Writer(Box<dyn Write + Send>),
+}
+
+/// Appends capability events for one run.
+pub struct AuditLog {
+    sink: Sink,
+    run: String,
+    program: String,
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("run", &self.run)
+            .field("program", &self.program)
+            .finish()
+    }
+}
+
+impl AuditLog {
+    /// Append to the log file at `path` (creating it and its directory),
+    /// rotating it per `rotation`. `program` labels the run.
+    pub fn open(path: &Path, rotation: Rotation, program: &str) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self::new(
+            Sink::File {
+                path: path.to_path_buf(),
+                file,
+                size,
+                rotation,
+            },
+            program,
+        ))
+    }
+
+    /// Write to `writer`, without rotation.
+    pub fn from_writer(writer: Box<dyn Write + Send>, program: &str) -> Self {
+        Self::new(Sink::Writer(writer), program)
+    }
+
+    fn new(sink: Sink, program: &str) -> Self {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        Self {
+            sink,
+            run: format!("{:x}-{}", std::process::id(), millis),
+            program: program.to_string(),
+        }
+    }
+
+    /// Record the start of the run.
+    pub fn start(&mut self) -> Result<(), HostError> {
+        let line = format!("event=start program={:?}", self.program);
+        self.write(&line)
+    }
+
+    /// Record a host call made under capability `cap`. `status` is `ok`,
+    /// `error`, or `denied`.
+    pub fn call(
+        &mut self,
+        name: &str,
+        cap: &str,
+        access: &str,
+        effect: &str,
+        status: &str,
+    ) -> Result<(), HostError> {
+        let line = format!(
+            "event=call fn={} cap={} access={} effect={} status={}",
+            name, cap, access, effect, status
+        );
+        self.write(&line)
+    }
+
+    /// Record the end of the run: `success` or `error`.
+    pub fn end(&mut self, status: &str) -> Result<(), HostError> {
+        self.write(&format!("event=end status={}", status))
+    }
+
+    fn write(&mut self, event: &str) -> Result<(), HostError> {
+        let line = format!("{} run={} {}\n", now_iso8601(), self.run, event);
+        let err = |e: std::io::Error| HostError::AuditWriteError(e.to_string());
+        match &mut self.sink {
+            Sink::Writer(w) => w.write_all(line.as_bytes()).map_err(err),
+            Sink::File {
+                path,
+                file,
+                size,
+                rotation,
+            } => {
+                if *size > 0 && *size + line.len() as u64 > rotation.max_bytes {
+                    rotate(path, rotation.keep).map_err(err)?;
+                    *file = OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&*path)
+                        .map_err(err)?;
+                    *size = 0;
+                }
+                file.write_all(line.as_bytes()).map_err(err)?;
+                *size += line.len() as u64;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Shift `path.N` to `path.N+1` (dropping `path.keep`), then `path` to
+/// `path.1`. With `keep` 0 the current file is simply removed.
+fn rotate(path: &Path, keep: usize) -> std::io::Result<()> {
+    let numbered = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    if keep == 0 {
+        return std::fs::remove_file(path);
+    }
+    for n in (1..keep).rev() {
+        let from = numbered(n);
+        if from.exists() {
+            std::fs::rename(&from, numbered(n + 1))?;
+        }
+    }
+    std::fs::rename(path, numbered(1))
+}
+
+/// Where the audit log goes when `strata run` isn't told: `$STRATA_AUDIT_LOG`,
+/// else `$XDG_STATE_HOME/strata/audit.log`, else
+/// `~/.local/state/strata/audit.log`.
+pub fn default_path() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(path) = var("STRATA_AUDIT_LOG") {
+        return Some(PathBuf::from(path));
+    }
+    let state = var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+    Some(state.join("strata/audit.log"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_share_the_run_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs/audit.log");
+        let mut log = AuditLog::open(&path, Rotation::default(), "job.strata").unwrap();
+        log.start().unwrap();
+        log.call("now", "TimeCap", "borrow", "Time", "ok").unwrap();
+        log.end("success").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        let run = format!("run={} ", log.run);
+        assert!(lines.iter().all(|l| l.contains(&run)), "{}", content);
+        assert!(lines[0].ends_with("event=start program=\"job.strata\""));
+        assert!(
+            lines[1].ends_with("event=call fn=now cap=TimeCap access=borrow effect=Time status=ok")
+        );
+        assert!(lines[2].ends_with("event=end status=success"));
+    }
+
+    #[test]
+    fn file_rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let rotation = Rotation {
+            max_bytes: 150,
+            keep: 2,
+        };
+        let mut log = AuditLog::open(&path, rotation, "p").unwrap();
+        for _ in 0..8 {
+            log.call("now", "TimeCap", "borrow", "Time", "ok").unwrap();
+        }
+
+        let rotated = |n: usize| dir.path().join(format!("audit.log.{}", n));
+        assert!(rotated(1).exists());
+        assert!(rotated(2).exists());
+        assert!(!rotated(3).exists());
+        for file in [path.clone(), rotated(1), rotated(2)] {
+            let len = std::fs::metadata(&file).unwrap().len();
+            assert!(len <= 150, "{} is {} bytes", file.display(), len);
+        }
+    }
+}
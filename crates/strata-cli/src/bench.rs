@@ -0,0 +1,126 @@
+//! Benchmark runner for `strata bench`.
+//!
+//! Each `bench fn name() { ... }` is called `warmup` times untimed, then
+//! `iters` times under the clock. A result reports wall time per call and
+//! evaluator steps (expressions evaluated) per call: steps don't depend on
+//! the machine or its load, so they compare across runs where nanoseconds
+//! can't. A benchmark takes no parameters, so it is handed no capabilities
+//! and the checker keeps it pure; host calls are refused at dispatch as
+//! well, so a benchmark measures the same work every run.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use strata_ast::ast::{Expr, FnDecl, Item, Module};
+
+use crate::eval::{self, Result};
+use crate::host::EffectPolicy;
+use crate::stats::StatsRecorder;
+
+/// How `strata bench` runs a module's benchmarks.
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// Untimed calls before measuring
+    pub warmup: u32,
+    /// Timed calls
+    pub iters: u32,
+    /// Run only benchmarks whose name contains this
+    pub filter: Option<String>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            warmup: 10,
+            iters: 100,
+            filter: None,
+        }
+    }
+}
+
+/// What one benchmark measured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    /// Timed calls
+    pub iters: u32,
+    /// Wall time of all timed calls
+    pub elapsed: Duration,
+    /// Evaluator steps of all timed calls
+    pub steps: u64,
+}
+
+impl BenchResult {
+    /// Mean wall time per call, in nanoseconds
+    pub fn ns_per_iter(&self) -> u128 {
+        self.elapsed.as_nanos() / u128::from(self.iters.max(1))
+    }
+
+    /// Evaluator steps per call
+    pub fn steps_per_iter(&self) -> u64 {
+        self.steps / u64::from(self.iters.max(1))
+    }
+
+    /// `bench name ... N ns/iter (S steps/iter)`
+    pub fn report(&self) -> String {
+        format!(
+            "bench {} ... {} ns/iter ({} steps/iter)",
+            self.name,
+            self.ns_per_iter(),
+            self.steps_per_iter()
+        )
+    }
+}
+
+/// The module's benchmarks that `filter` selects, in source order
+pub fn benches<'m>(m: &'m Module, filter: Option<&str>) -> Vec<&'m FnDecl> {
+    m.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(decl) if decl.bench => Some(decl),
+            _ => None,
+        })
+        .filter(|decl| filter.is_none_or(|f| decl.name.text.contains(f)))
+        .collect()
+}
+
+/// Run the benchmarks of the checked module `m` as `opts` says, passing
+/// each one's outcome to `report` as it finishes. A benchmark that fails
+/// doesn't stop the others; an error setting the module up does.
+pub fn run_benches(
+    m: &Module,
+    opts: &BenchOptions,
+    mut report: impl FnMut(&FnDecl, Result<BenchResult>),
+) -> Result<()> {
+    let stats = Arc::new(StatsRecorder::new());
+    let mut env = eval::module_env(m, EffectPolicy::allow_only(&[]), |env| {
+        env.with_stats(stats.clone())
+    })?;
+    for decl in benches(m, opts.filter.as_deref()) {
+        // The benchmark is called by name, as `name()`
+        let call = Expr::Call {
+            callee: Box::new(Expr::Var(decl.name.clone())),
+            args: Vec::new(),
+            names: Vec::new(),
+            span: decl.name.span,
+        };
+        let mut measure = || -> Result<BenchResult> {
+            for _ in 0..opts.warmup {
+                eval::eval_expr(&mut env, &call)?;
+            }
+            let steps = stats.snapshot().exprs;
+            let started = Instant::now();
+            for _ in 0..opts.iters {
+                eval::eval_expr(&mut env, &call)?;
+            }
+            Ok(BenchResult {
+                name: decl.name.text.clone(),
+                iters: opts.iters,
+                elapsed: started.elapsed(),
+                steps: stats.snapshot().exprs - steps,
+            })
+        };
+        report(decl, measure());
+    }
+    Ok(())
+}
@@ -0,0 +1,77 @@
+//! `strata-kernel`: a Jupyter kernel for Strata, so notebooks can run
+//! cells and show the types and effects of what they define.
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use strata_cli::jupyter::{self, ConnectionInfo};
+
+#[derive(Parser, Debug)]
+#[command(name = "strata-kernel")]
+#[command(about = "Jupyter kernel for Strata")]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Serve the kernel on the sockets a connection file names (what
+    /// Jupyter runs)
+    Run {
+        /// Connection file written by the frontend
+        connection_file: String,
+    },
+    /// Write a kernelspec so Jupyter can start this kernel
+    Install {
+        /// Kernels directory to write `strata/kernel.json` into [default:
+        /// the user's Jupyter data directory]
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    match Cli::parse().command {
+        Commands::Run { connection_file } => {
+            let text = std::fs::read_to_string(&connection_file)
+                .map_err(|e| format!("Failed to read {}: {}", connection_file, e))?;
+            let info: ConnectionInfo = serde_json::from_str(&text)
+                .map_err(|e| format!("Invalid connection file {}: {}", connection_file, e))?;
+            jupyter::serve(&info)?;
+        }
+        Commands::Install { dir } => {
+            let dir = match dir {
+                Some(dir) => dir,
+                None => user_kernels_dir()
+                    .ok_or("Cannot find the Jupyter data directory; pass --dir")?,
+            }
+            .join("strata");
+            let exe = std::env::current_exe()?;
+            let spec = jupyter::kernel_spec(&exe.to_string_lossy());
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join("kernel.json");
+            std::fs::write(&path, serde_json::to_string_pretty(&spec)? + "\n")?;
+            println!("Installed kernelspec in {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// `kernels` in the user's Jupyter data directory: `$JUPYTER_DATA_DIR`,
+/// else the platform default
+fn user_kernels_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("JUPYTER_DATA_DIR") {
+        return Some(PathBuf::from(dir).join("kernels"));
+    }
+    let data = if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?).join("jupyter")
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Jupyter")
+    } else {
+        match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir).join("jupyter"),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share/jupyter"),
+        }
+    };
+    Some(data.join("kernels"))
+}
@@ -0,0 +1,446 @@
+//! Semantic AST hashing and the on-disk check cache for `strata check`.
+//!
+//! [`semantic_hash`] digests a parsed module while ignoring every
+//! [`Span`](strata_ast::span::Span), so edits that only move code around
+//! (whitespace, comments, blank lines) hash the same. It walks the AST's
+//! serde serialization: each value is tagged with its kind, strings are
+//! length-prefixed, and compound values are bracketed, so distinct trees
+//! can't produce the same byte stream.
+//!
+//! [`CheckCache`] remembers which hashes type-checked cleanly, under
+//! `.strata-cache/check/` beside the source. Only clean results are cached:
+//! errors and warnings carry spans, which a whitespace edit would leave
+//! stale. The cache records the compiler version that wrote it and is
+//! cleared when a different version opens it.
+
+use std::path::{Path, PathBuf};
+
+use serde::ser::{self, Serialize};
+use sha2::{Digest, Sha256};
+use strata_ast::ast::Module;
+
+/// Version stamped on the cache; entries from another version are dropped.
+const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Hex SHA-256 of `module` with spans left out.
+pub fn semantic_hash(module: &Module) -> String {
+    let mut hasher = SpanlessHasher(Sha256::new());
+    module
+        .serialize(&mut hasher)
+        .expect("hashing a module cannot fail");
+    format!("{:x}", hasher.0.finalize())
+}
+
+/// Clean check results of a project, by semantic hash.
+#[derive(Debug)]
+pub struct CheckCache {
+    dir: PathBuf,
+}
+
+impl CheckCache {
+    /// Open (creating if needed) the cache in `root/.strata-cache`,
+    /// clearing it if another compiler version wrote it.
+    pub fn open(root: &Path) -> std::io::Result<Self> {
+        let base = root.join(".strata-cache");
+        let dir = base.join("check");
+        let version_file = base.join("version");
+        let version = std::fs::read_to_string(&version_file).unwrap_or_default();
+        if version.trim() != COMPILER_VERSION && dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(&version_file, COMPILER_VERSION)?;
+        Ok(Self { dir })
+    }
+
+    /// Whether a module with this hash already checked cleanly.
+    pub fn is_clean(&self, hash: &str) -> bool {
+        self.dir.join(hash).exists()
+    }
+
+    /// Record that a module with this hash checks cleanly.
+    pub fn mark_clean(&self, hash: &str) -> std::io::Result<()> {
+        std::fs::write(self.dir.join(hash), "")
+    }
+}
+
+/// A serde serializer that feeds everything but spans into a digest.
+struct SpanlessHasher(Sha256);
+
+impl SpanlessHasher {
+    fn tag(&mut self, tag: u8) {
+        self.0.update([tag]);
+    }
+
+    fn bytes(&mut self, tag: u8, bytes: &[u8]) {
+        self.tag(tag);
+        self.0.update((bytes.len() as u64).to_le_bytes());
+        self.0.update(bytes);
+    }
+
+    fn name(&mut self, name: &str) {
+        self.bytes(b'n', name.as_bytes());
+    }
+}
+
+/// Fields and elements of a compound value; a `Span`'s are skipped.
+struct Compound<'a> {
+    hasher: &'a mut SpanlessHasher,
+    skip: bool,
+}
+
+impl Compound<'_> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), std::fmt::Error> {
+        if self.skip {
+            return Ok(());
+        }
+        value.serialize(&mut *self.hasher)
+    }
+
+    fn field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), std::fmt::Error> {
+        if self.skip {
+            return Ok(());
+        }
+        self.hasher.name(key);
+        value.serialize(&mut *self.hasher)
+    }
+
+    fn end(self) -> Result<(), std::fmt::Error> {
+        if !self.skip {
+            self.hasher.tag(b')');
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut SpanlessHasher {
+    type Ok = ();
+    type Error = std::fmt::Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Self::Error> {
+        self.tag(if v { b'T' } else { b'F' });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Self::Error> {
+        self.bytes(b'i', &v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Self::Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Self::Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Self::Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Self::Error> {
+        self.bytes(b'u', &v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Self::Error> {
+        self.bytes(b'f', &v.to_bits().to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Self::Error> {
+        self.bytes(b'c', v.encode_utf8(&mut [0; 4]).as_bytes());
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Self::Error> {
+        self.bytes(b's', v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Self::Error> {
+        self.bytes(b'b', v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.tag(b'N');
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Self::Error> {
+        self.tag(b'S');
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        self.tag(b'U');
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Self::Error> {
+        self.name(name);
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Self::Error> {
+        self.name(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.name(name);
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.name(variant);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Self::Error> {
+        self.tag(b'[');
+        Ok(Compound {
+            hasher: self,
+            skip: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Self::Error> {
+        self.name(name);
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Self::Error> {
+        self.name(variant);
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Self::Error> {
+        self.tag(b'{');
+        Ok(Compound {
+            hasher: self,
+            skip: false,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Self::Error> {
+        let skip = name == "Span";
+        if !skip {
+            self.name(name);
+            self.tag(b'{');
+        }
+        Ok(Compound { hasher: self, skip })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Self::Error> {
+        self.name(variant);
+        self.tag(b'{');
+        Ok(Compound {
+            hasher: self,
+            skip: false,
+        })
+    }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        self.element(key)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = std::fmt::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Compound::end(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(src: &str) -> String {
+        semantic_hash(&strata_parse::parse_str("<test>", src).unwrap())
+    }
+
+    #[test]
+    fn hash_ignores_layout_but_not_meaning() {
+        let base = hash("fn f(x: Int) -> Int { x + 1 }");
+        assert_eq!(
+            base,
+            hash("// add one\nfn f(x: Int) -> Int {\n    x + 1\n}\n")
+        );
+        assert_ne!(base, hash("fn f(x: Int) -> Int { x + 2 }"));
+        assert_ne!(base, hash("fn f(y: Int) -> Int { y + 1 }"));
+        assert_ne!(base, hash("fn f(x: Int) -> Int { x - 1 }"));
+    }
+
+    #[test]
+    fn cache_is_cleared_for_another_compiler_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = CheckCache::open(dir.path()).unwrap();
+        cache.mark_clean("abc").unwrap();
+        assert!(CheckCache::open(dir.path()).unwrap().is_clean("abc"));
+
+        std::fs::write(dir.path().join(".strata-cache/version"), "0.0.0-old").unwrap();
+        assert!(!CheckCache::open(dir.path()).unwrap().is_clean("abc"));
+    }
+}
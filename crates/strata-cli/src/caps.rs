@@ -0,0 +1,328 @@
+//! Capability grants from a `--caps caps.toml` file.
+//!
+//! By default `main()` receives every capability its parameters ask for.
+//! A caps file lets the operator grant only some of them, each with
+//! settings that attenuate what the host functions behind it may do:
+//!
+//! ```toml
+//! [fs]
+//! root = "/srv/data"        # paths resolve under root; escaping it is refused
+//!
+//! [net]
+//! allow = ["example.com"]   # hosts net host functions may reach
+//!
+//! [time]
+//! fixed = "1700000000.000"  # now() always returns this instant
+//!
+//! [rand]
+//! ```
+//!
+//! A capability without a table is not granted. Each kind is injected at
+//! most once per run and cannot be forged, so a kind's settings are the
+//! settings of the one runtime `Cap` value that grants it.
+//!
+//! The file is read with a small TOML subset: `[table]` headers and
+//! `key = value` lines, where a value is a string or an array of strings.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use strata_types::{CapKind, Effect};
+
+use crate::host::{EffectPolicy, HostError};
+
+/// Error in a caps file, with its 1-based line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapsError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for CapsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CapsError {}
+
+/// Which capabilities a run grants, and how each is attenuated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapsConfig {
+    /// Granted capabilities; `None` grants all of them (no caps file)
+    granted: Option<HashSet<CapKind>>,
+    /// `[fs] root`: directory file paths are confined to
+    pub fs_root: Option<PathBuf>,
+    /// `[net] allow`: hosts net host functions may reach
+    pub net_allow: Option<Vec<String>>,
+    /// `[time] fixed`: the instant `now()` returns
+    pub fixed_time: Option<String>,
+}
+
+/// A parsed `key = value` right-hand side.
+enum TomlValue {
+    Str(String),
+    Array(Vec<String>),
+}
+
+impl CapsConfig {
+    /// Parse a caps file.
+    pub fn from_toml(src: &str) -> Result<Self, CapsError> {
+        let mut config = CapsConfig {
+            granted: Some(HashSet::new()),
+            ..CapsConfig::default()
+        };
+        let mut table: Option<CapKind> = None;
+        for (i, raw) in src.lines().enumerate() {
+            let line = i + 1;
+            let err = |message: String| CapsError { line, message };
+            let text = strip_comment(raw).trim();
+            if text.is_empty() {
+                continue;
+            }
+            if let Some(name) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+                let kind = table_kind(name.trim())
+                    .ok_or_else(|| err(format!("unknown capability table [{}]", name.trim())))?;
+                if !config.grant(kind) {
+                    return Err(err(format!("duplicate table [{}]", name.trim())));
+                }
+                table = Some(kind);
+                continue;
+            }
+            let (key, value) = text.split_once('=').ok_or_else(|| {
+                err(format!(
+                    "expected `[table]` or `key = value`, found `{}`",
+                    text
+                ))
+            })?;
+            let key = key.trim();
+            let value = parse_value(value.trim()).map_err(err)?;
+            let kind =
+                table.ok_or_else(|| err(format!("`{}` must be inside a capability table", key)))?;
+            match (kind, key, value) {
+                (CapKind::Fs, "root", TomlValue::Str(root)) => {
+                    config.fs_root = Some(PathBuf::from(root))
+                }
+                (CapKind::Net, "allow", TomlValue::Array(hosts)) => config.net_allow = Some(hosts),
+                (CapKind::Time, "fixed", TomlValue::Str(instant)) => {
+                    config.fixed_time = Some(instant)
+                }
+                (CapKind::Fs, "root", _)
+                | (CapKind::Net, "allow", _)
+                | (CapKind::Time, "fixed", _) => {
+                    return Err(err(format!("`{}` has the wrong type", key)))
+                }
+                (kind, key, _) => {
+                    return Err(err(format!(
+                        "unknown setting `{}` for {}",
+                        key,
+                        kind.type_name()
+                    )))
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    /// Read and parse a caps file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let src = std::fs::read_to_string(path)?;
+        Ok(Self::from_toml(&src)?)
+    }
+
+    /// Mark `kind` granted; false if it already was.
+    fn grant(&mut self, kind: CapKind) -> bool {
+        self.granted.get_or_insert_with(HashSet::new).insert(kind)
+    }
+
+    /// Whether `kind` may be injected into `main`.
+    pub fn grants(&self, kind: CapKind) -> bool {
+        self.granted.as_ref().is_none_or(|g| g.contains(&kind))
+    }
+
+    /// The effect policy denying every effect whose capability isn't granted.
+    pub fn policy(&self) -> EffectPolicy {
+        match &self.granted {
+            None => EffectPolicy::default(),
+            Some(granted) => {
+                let allowed: Vec<Effect> = granted.iter().map(|k| k.gates_effect()).collect();
+                EffectPolicy::allow_only(&allowed)
+            }
+        }
+    }
+
+    /// Resolve a file path under `[fs] root`. Relative paths are joined to
+    /// the root; absolute ones must lie within it. `..` is refused, since
+    /// it could climb out. The check is lexical, so a symlink under the
+    /// root can still lead outside it.
+    pub fn resolve_path(&self, path: &str) -> Result<PathBuf, HostError> {
+        let Some(root) = &self.fs_root else {
+            return Ok(PathBuf::from(path));
+        };
+        let outside = || HostError::PolicyDenied(format!("path '{}' is outside the fs root", path));
+        let path = Path::new(path);
+        let rel = if path.is_absolute() {
+            path.strip_prefix(root).map_err(|_| outside())?
+        } else {
+            path
+        };
+        if rel
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(outside());
+        }
+        Ok(root.join(rel))
+    }
+
+    /// Whether net host functions may reach `host` under `[net] allow`.
+    pub fn allows_host(&self, host: &str) -> bool {
+        self.net_allow
+            .as_ref()
+            .is_none_or(|allow| allow.iter().any(|h| h == host))
+    }
+}
+
+/// The capability named by a table header.
+fn table_kind(name: &str) -> Option<CapKind> {
+    match name {
+        "fs" => Some(CapKind::Fs),
+        "net" => Some(CapKind::Net),
+        "time" => Some(CapKind::Time),
+        "rand" => Some(CapKind::Rand),
+        "ai" => Some(CapKind::Ai),
+        "console" => Some(CapKind::Console),
+        _ => None,
+    }
+}
+
+/// `line` up to a `#` that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_str = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_str => escaped = true,
+            '"' => in_str = !in_str,
+            '#' if !in_str => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_value(text: &str) -> Result<TomlValue, String> {
+    if let Some(items) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        let mut strings = Vec::new();
+        let mut rest = items.trim();
+        while !rest.is_empty() {
+            let (s, after) = parse_string(rest)?;
+            strings.push(s);
+            rest = after.trim_start();
+            rest = match rest.strip_prefix(',') {
+                Some(after_comma) => after_comma.trim_start(),
+                None if rest.is_empty() => rest,
+                None => return Err(format!("expected `,` in array, found `{}`", rest)),
+            };
+        }
+        return Ok(TomlValue::Array(strings));
+    }
+    if text.starts_with('"') {
+        let (s, rest) = parse_string(text)?;
+        if !rest.trim().is_empty() {
+            return Err(format!("unexpected `{}` after string", rest.trim()));
+        }
+        return Ok(TomlValue::Str(s));
+    }
+    Err(format!("unsupported value `{}`", text))
+}
+
+/// A leading basic string of `text` and what follows it.
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let body = text
+        .strip_prefix('"')
+        .ok_or_else(|| format!("expected a string, found `{}`", text))?;
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((out, &body[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, '"')) => out.push('"'),
+                Some((_, '\\')) => out.push('\\'),
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 't')) => out.push('\t'),
+                _ => return Err("unsupported escape in string".to_string()),
+            },
+            c => out.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_grant_capabilities_with_settings() {
+        let config = CapsConfig::from_toml(
+            r#"
+            # operator grants
+            [fs]
+            root = "/srv/data" # confined
+            [net]
+            allow = ["example.com", "api.example.com"]
+            [time]
+            fixed = "1700000000.000"
+            "#,
+        )
+        .unwrap();
+        assert!(config.grants(CapKind::Fs));
+        assert!(config.grants(CapKind::Time));
+        assert!(!config.grants(CapKind::Rand));
+        assert_eq!(config.fs_root, Some(PathBuf::from("/srv/data")));
+        assert!(config.allows_host("api.example.com"));
+        assert!(!config.allows_host("evil.example"));
+        assert_eq!(config.fixed_time.as_deref(), Some("1700000000.000"));
+        assert!(config.policy().is_denied(Effect::Rand));
+        assert!(!config.policy().is_denied(Effect::Net));
+    }
+
+    #[test]
+    fn no_caps_file_grants_everything() {
+        let config = CapsConfig::default();
+        assert!(config.grants(CapKind::Ai));
+        assert_eq!(config.policy(), EffectPolicy::default());
+        assert_eq!(config.resolve_path("../x").unwrap(), PathBuf::from("../x"));
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let err = CapsConfig::from_toml("[fs]\nroot = [\"a\"]\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        let err = CapsConfig::from_toml("[disk]\n").unwrap_err();
+        assert!(err.message.contains("[disk]"), "{}", err);
+        let err = CapsConfig::from_toml("[time]\nspeed = \"2\"\n").unwrap_err();
+        assert!(err.message.contains("speed"), "{}", err);
+        assert!(CapsConfig::from_toml("root = \"/\"\n").is_err());
+        assert!(CapsConfig::from_toml("[fs]\n[fs]\n").is_err());
+    }
+
+    #[test]
+    fn fs_root_confines_paths() {
+        let config = CapsConfig::from_toml("[fs]\nroot = \"/srv/data\"\n").unwrap();
+        assert_eq!(
+            config.resolve_path("a/b.txt").unwrap(),
+            PathBuf::from("/srv/data/a/b.txt")
+        );
+        assert_eq!(
+            config.resolve_path("/srv/data/c.txt").unwrap(),
+            PathBuf::from("/srv/data/c.txt")
+        );
+        assert!(config.resolve_path("../etc/passwd").is_err());
+        assert!(config.resolve_path("/etc/passwd").is_err());
+        assert!(config.resolve_path("/srv/data/../secret").is_err());
+    }
+}
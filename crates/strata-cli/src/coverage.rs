@@ -0,0 +1,444 @@
+//! Statement and branch coverage for `strata test --coverage`.
+//!
+//! The points a run can reach are read off the syntax tree: every
+//! statement and block tail, and every way out of an `if` (its `then`
+//! block, and its `else` whether written or not) or a `match` (each arm).
+//! A point is named by the span of its node, which is unique within a
+//! parsed program, and rendered through the program's [`SourceDb`] as a
+//! line. An environment given a [`Coverage`] (via `Env::with_coverage`)
+//! counts each point it reaches; [`Coverage::report`] lines the counts up
+//! with the points, including the ones never reached.
+//!
+//! `#[test]` and `bench` functions are left out of the report: they are
+//! what does the covering.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+use strata_ast::ast::{Block, Expr, Item, Module, Stmt};
+use strata_ast::span::{FileId, SourceDb, Span};
+
+use crate::emit::escape_html;
+
+/// Counts the points a run reaches.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    stmts: Mutex<HashMap<Span, u64>>,
+    /// Keyed by the `if` or `match` and the branch's index: an `if`'s
+    /// `then` is 0 and its `else` 1, a `match` arm its position
+    branches: Mutex<HashMap<(Span, usize), u64>>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn stmt(&self, span: Span) {
+        *self.stmts.lock().unwrap().entry(span).or_default() += 1;
+    }
+
+    pub(crate) fn branch(&self, at: Span, index: usize) {
+        *self
+            .branches
+            .lock()
+            .unwrap()
+            .entry((at, index))
+            .or_default() += 1;
+    }
+
+    /// The counts so far for every point of `m`, whose sources are in `db`
+    pub fn report(&self, m: &Module, db: &SourceDb) -> CoverageReport {
+        let mut points = Points::default();
+        for item in &m.items {
+            if let Item::Fn(decl) = item {
+                if !decl.bench && !decl.attrs.iter().any(|a| a.name.text == "test") {
+                    points.block(&decl.body);
+                }
+            }
+        }
+
+        let line = |span: Span| db.index(span.file).map_or(0, |i| i.line_col(span.start).0);
+        let stmts = self.stmts.lock().unwrap();
+        let taken = self.branches.lock().unwrap();
+        let mut lines: BTreeMap<(FileId, usize), u64> = BTreeMap::new();
+        let mut hit_line = |span: Span, hits: u64| {
+            let count = lines.entry((span.file, line(span))).or_default();
+            *count = (*count).max(hits);
+        };
+        for span in points.stmts {
+            hit_line(span, stmts.get(&span).copied().unwrap_or(0));
+        }
+        // A branch's target counts as reached when the branch is taken, so
+        // a match arm's line is covered even if its body isn't a block
+        for &(at, index, target) in &points.branches {
+            if let Some(target) = target {
+                hit_line(target, taken.get(&(at, index)).copied().unwrap_or(0));
+            }
+        }
+
+        let mut branches: Vec<BranchHits> = points
+            .branches
+            .iter()
+            .map(|&(at, index, _)| BranchHits {
+                file: at.file,
+                line: line(at),
+                block: at.start,
+                branch: index,
+                hits: taken.get(&(at, index)).copied().unwrap_or(0),
+            })
+            .collect();
+        branches.sort_by_key(|b| (b.file, b.line, b.block, b.branch));
+
+        CoverageReport {
+            lines: lines
+                .into_iter()
+                .map(|((file, line), hits)| LineHits { file, line, hits })
+                .collect(),
+            branches,
+        }
+    }
+}
+
+/// The points of a module, in source order
+#[derive(Default)]
+struct Points {
+    stmts: Vec<Span>,
+    /// The `if` or `match`, the branch's index, and the code it runs; an
+    /// `if` without `else` runs nothing when its condition is false
+    branches: Vec<(Span, usize, Option<Span>)>,
+}
+
+impl Points {
+    fn block(&mut self, block: &Block) {
+        for stmt in &block.stmts {
+            match stmt {
+                Stmt::Let { value, .. } | Stmt::Assign { value, .. } => {
+                    self.stmts.push(stmt.span());
+                    self.expr(value);
+                }
+                Stmt::Expr { expr, .. } => {
+                    self.stmts.push(stmt.span());
+                    self.expr(expr);
+                }
+                Stmt::Return { value, .. } => {
+                    self.stmts.push(stmt.span());
+                    if let Some(value) = value {
+                        self.expr(value);
+                    }
+                }
+                Stmt::Declare { .. } => {}
+                // A nested function runs only when called
+                Stmt::Fn(decl) => self.block(&decl.body),
+            }
+        }
+        if let Some(tail) = &block.tail {
+            self.stmts.push(tail.span());
+            self.expr(tail);
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+            Expr::Unary { expr, .. } | Expr::Paren { inner: expr, .. } | Expr::Borrow(expr, _) => {
+                self.expr(expr)
+            }
+            Expr::Field { expr, .. } => self.expr(expr),
+            Expr::Call { callee, args, .. } => {
+                self.expr(callee);
+                args.iter().for_each(|a| self.expr(a));
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Block(block) | Expr::Spawn { body: block, .. } => self.block(block),
+            Expr::If {
+                cond,
+                then_,
+                else_,
+                span,
+            } => {
+                self.expr(cond);
+                self.branches.push((*span, 0, Some(then_.span)));
+                self.block(then_);
+                self.branches
+                    .push((*span, 1, else_.as_ref().map(|e| e.span())));
+                if let Some(else_) = else_ {
+                    self.expr(else_);
+                }
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            Expr::For { iter, body, .. } => {
+                self.expr(iter);
+                self.block(body);
+            }
+            Expr::Match {
+                scrutinee,
+                arms,
+                span,
+            } => {
+                self.expr(scrutinee);
+                for (i, arm) in arms.iter().enumerate() {
+                    self.branches.push((*span, i, Some(arm.body.span())));
+                    self.expr(&arm.body);
+                }
+            }
+            Expr::Tuple { elems, .. } => elems.iter().for_each(|e| self.expr(e)),
+            Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+                fields.iter().for_each(|f| self.expr(&f.value))
+            }
+        }
+    }
+}
+
+/// How often a run reached each line and branch of a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// Lines starting a statement, in order
+    pub lines: Vec<LineHits>,
+    /// Branches of each `if` and `match`, in order
+    pub branches: Vec<BranchHits>,
+}
+
+/// Times a run reached the statements starting on one line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineHits {
+    pub file: FileId,
+    /// 1-based
+    pub line: usize,
+    pub hits: u64,
+}
+
+/// Times a run took one branch of an `if` or `match`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BranchHits {
+    pub file: FileId,
+    /// 1-based line of the `if` or `match`
+    pub line: usize,
+    /// The `if` or `match`, by its byte offset
+    pub block: u32,
+    /// Which of its branches
+    pub branch: usize,
+    pub hits: u64,
+}
+
+impl CoverageReport {
+    /// `lines: 9/12 (75.0%), branches: 3/4 (75.0%)`
+    pub fn summary(&self) -> String {
+        let lines_hit = self.lines.iter().filter(|l| l.hits > 0).count();
+        let branches_hit = self.branches.iter().filter(|b| b.hits > 0).count();
+        format!(
+            "lines: {}, branches: {}",
+            ratio(lines_hit, self.lines.len()),
+            ratio(branches_hit, self.branches.len())
+        )
+    }
+
+    /// The report in lcov's tracefile format, one record per file
+    pub fn lcov(&self, db: &SourceDb) -> String {
+        let mut out = String::from("TN:\n");
+        for file in self.files() {
+            let lines: Vec<_> = self.lines.iter().filter(|l| l.file == file).collect();
+            let branches: Vec<_> = self.branches.iter().filter(|b| b.file == file).collect();
+            writeln!(out, "SF:{}", db.name(file)).unwrap();
+            for l in &lines {
+                writeln!(out, "DA:{},{}", l.line, l.hits).unwrap();
+            }
+            writeln!(out, "LF:{}", lines.len()).unwrap();
+            writeln!(out, "LH:{}", lines.iter().filter(|l| l.hits > 0).count()).unwrap();
+            for b in &branches {
+                writeln!(out, "BRDA:{},{},{},{}", b.line, b.block, b.branch, b.hits).unwrap();
+            }
+            writeln!(out, "BRF:{}", branches.len()).unwrap();
+            writeln!(
+                out,
+                "BRH:{}",
+                branches.iter().filter(|b| b.hits > 0).count()
+            )
+            .unwrap();
+            out.push_str("end_of_record\n");
+        }
+        out
+    }
+
+    /// Standalone HTML page listing each file's source, with covered lines
+    /// green, lines never reached red, and lines with a branch never taken
+    /// yellow
+    pub fn html(&self, db: &SourceDb) -> String {
+        let mut body = String::new();
+        for file in self.files() {
+            let Some(index) = db.index(file) else {
+                continue;
+            };
+            writeln!(
+                body,
+                "<h2>{}</h2>\n<p>{}</p>\n<pre class=\"strata\"><code>",
+                escape_html(db.name(file)),
+                escape_html(&self.only(file).summary())
+            )
+            .unwrap();
+            for line in 1..=index.line_count() {
+                let hits = self.lines.iter().find(|l| l.file == file && l.line == line);
+                let missed_branch = self
+                    .branches
+                    .iter()
+                    .any(|b| b.file == file && b.line == line && b.hits == 0);
+                let class = match hits {
+                    None => "",
+                    Some(l) if l.hits == 0 => " class=\"cov-missed\"",
+                    Some(_) if missed_branch => " class=\"cov-partial\"",
+                    Some(_) => " class=\"cov-hit\"",
+                };
+                let count = hits.map_or(String::new(), |l| l.hits.to_string());
+                writeln!(
+                    body,
+                    "<span{}><span class=\"cov-count\">{:>6}</span> {}</span>",
+                    class,
+                    count,
+                    escape_html(index.line_text(line).unwrap_or(""))
+                )
+                .unwrap();
+            }
+            body.push_str("</code></pre>\n");
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Coverage</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            HTML_STYLE, body
+        )
+    }
+
+    /// The files with points, in order
+    fn files(&self) -> Vec<FileId> {
+        let mut files: Vec<FileId> = self.lines.iter().map(|l| l.file).collect();
+        files.extend(self.branches.iter().map(|b| b.file));
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// The part of the report in `file`
+    fn only(&self, file: FileId) -> CoverageReport {
+        CoverageReport {
+            lines: self
+                .lines
+                .iter()
+                .filter(|l| l.file == file)
+                .copied()
+                .collect(),
+            branches: self
+                .branches
+                .iter()
+                .filter(|b| b.file == file)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+const HTML_STYLE: &str = "\
+pre.strata { background: #fafafa; color: #222; padding: 1em; }
+.cov-count { color: #a0a1a7; }
+.cov-hit { background: #e6ffed; }
+.cov-missed { background: #ffeef0; }
+.cov-partial { background: #fff5b1; }
+";
+
+/// `3/4 (75.0%)`, or `0/0` when there is nothing to cover
+fn ratio(hit: usize, total: usize) -> String {
+    match total {
+        0 => "0/0".to_string(),
+        _ => format!(
+            "{}/{} ({:.1}%)",
+            hit,
+            total,
+            hit as f64 * 100.0 / total as f64
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_runner::{run_tests, TestOptions};
+    use std::sync::Arc;
+    use strata_parse::parse_source;
+
+    const SRC: &str = "\
+fn sign(n: Int) -> Int {
+    if n < 0 { 0 - 1 } else { 1 }
+}
+fn pick(n: Int) -> Int {
+    match n {
+        0 => 10,
+        _ => 20,
+    }
+}
+fn never() -> Int {
+    let x = 1;
+    x
+}
+#[test]
+fn sign_of_two() -> Bool { sign(2) == 1 && pick(0) == 10 }
+";
+
+    /// Run `SRC`'s tests, returning the coverage report
+    fn covered() -> (CoverageReport, SourceDb) {
+        let mut db = SourceDb::new();
+        let file = db.add("c.strata", SRC);
+        let module = parse_source(&db, file).expect("parses");
+        let coverage = Arc::new(Coverage::new());
+        let opts = TestOptions {
+            filter: None,
+            coverage: Some(coverage.clone()),
+            ..TestOptions::default()
+        };
+        run_tests(&module, &opts, |_, outcome| assert!(outcome.passed())).unwrap();
+        (coverage.report(&module, &db), db)
+    }
+
+    #[test]
+    fn lines_and_branches_reached_by_tests() {
+        let (report, _) = covered();
+        let lines: Vec<(usize, u64)> = report.lines.iter().map(|l| (l.line, l.hits)).collect();
+        // The test's own line isn't a point; `never` is never reached
+        assert_eq!(lines, [(2, 1), (5, 1), (6, 1), (7, 0), (11, 0), (12, 0)]);
+        let branches: Vec<(usize, usize, u64)> = report
+            .branches
+            .iter()
+            .map(|b| (b.line, b.branch, b.hits))
+            .collect();
+        assert_eq!(branches, [(2, 0, 0), (2, 1, 1), (5, 0, 1), (5, 1, 0)]);
+        assert_eq!(
+            report.summary(),
+            "lines: 3/6 (50.0%), branches: 2/4 (50.0%)"
+        );
+    }
+
+    #[test]
+    fn lcov_records_lines_and_branches() {
+        let (report, db) = covered();
+        let lcov = report.lcov(&db);
+        assert!(lcov.starts_with("TN:\nSF:c.strata\nDA:2,1\n"), "{lcov}");
+        assert!(lcov.contains("\nLF:6\nLH:3\n"), "{lcov}");
+        assert!(lcov.contains("\nBRF:4\nBRH:2\nend_of_record\n"), "{lcov}");
+    }
+
+    #[test]
+    fn html_marks_missed_and_partial_lines() {
+        let (report, db) = covered();
+        let html = report.html(&db);
+        assert!(html.contains("<span class=\"cov-partial\">"), "{html}");
+        assert!(
+            html.contains("<span class=\"cov-missed\"><span class=\"cov-count\">     0</span>     let x = 1;</span>"),
+            "{html}"
+        );
+        assert!(html.contains("<span class=\"cov-hit\">"), "{html}");
+    }
+}
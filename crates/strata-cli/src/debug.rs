@@ -0,0 +1,470 @@
+//! Interactive step debugger for `strata debug`.
+//!
+//! The evaluator calls [`Debugger::on_stmt`] before each statement (and
+//! each block's tail expression) and [`Debugger::enter`] /
+//! [`Debugger::exit`] around each Strata function call. The debugger stops
+//! at the first statement of `main` (unless told to
+//! [`Debugger::run_to_breakpoint`]), at breakpoints, and after a step, and
+//! then reads commands until told to resume:
+//!
+//! ```text
+//! break fib | break 12 | break prog.strata:12   set a breakpoint
+//! delete 2                                      remove breakpoint 2
+//! step                                          stop at the next statement
+//! next                                          ... not inside calls it makes
+//! continue                                      run to the next breakpoint
+//! locals | print n                              show bindings
+//! where                                         show the call stack
+//! quit                                          end the run
+//! ```
+//!
+//! Steps and line breakpoints stop when execution arrives at a statement
+//! on a new line, so a line holding several statements stops once. A
+//! function breakpoint stops at the first statement of each call. An empty
+//! command repeats the previous one.
+
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use strata_ast::span::Span;
+
+use crate::ast_view::LineIndex;
+use crate::eval::{Env, Result, RuntimeErrorKind, Value};
+use crate::pretty::{pretty, PrettyLimits};
+
+/// Where to stop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// The first statement of each call to this function
+    Fn(String),
+    /// Any statement starting on this 1-based line
+    Line(usize),
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Breakpoint::Fn(name) => write!(f, "fn {}", name),
+            Breakpoint::Line(line) => write!(f, "line {}", line),
+        }
+    }
+}
+
+/// When to stop next, besides breakpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// At the next statement
+    Step,
+    /// At the next statement at most this many calls deep
+    Next(usize),
+    /// Only at breakpoints
+    Continue,
+}
+
+#[derive(Debug)]
+struct Frame {
+    name: String,
+    line: Option<usize>,
+}
+
+/// Debugger state for one run, reading commands from `input`.
+pub struct Debugger {
+    file: String,
+    index: LineIndex,
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_id: usize,
+    stack: Vec<Frame>,
+    mode: Mode,
+    /// A function breakpoint was entered; stop at its first statement
+    pending: Option<usize>,
+    /// Depth and line of the last statement, so a line breakpoint fires
+    /// once per arrival rather than for every statement on the line
+    last: Option<(usize, usize)>,
+    last_command: String,
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+    /// How much of a value `locals` and `print` show
+    limits: PrettyLimits,
+}
+
+impl std::fmt::Debug for Debugger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debugger")
+            .field("file", &self.file)
+            .field("breakpoints", &self.breakpoints)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl Debugger {
+    /// Debug the program `file` with source `src`.
+    pub fn new(
+        file: &str,
+        src: &str,
+        input: Box<dyn BufRead + Send>,
+        output: Box<dyn Write + Send>,
+    ) -> Self {
+        Self {
+            file: file.to_string(),
+            index: LineIndex::new(src),
+            breakpoints: Vec::new(),
+            next_id: 1,
+            stack: Vec::new(),
+            mode: Mode::Step,
+            pending: None,
+            last: None,
+            last_command: String::new(),
+            input,
+            output,
+            limits: PrettyLimits::default(),
+        }
+    }
+
+    /// Show values within `limits` rather than the defaults.
+    pub fn with_print_limits(mut self, limits: PrettyLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Add a breakpoint from `fn_name`, `line`, or `file:line`, returning
+    /// its number.
+    pub fn add_breakpoint(&mut self, spec: &str) -> Result<usize, String> {
+        let bp = self.parse_breakpoint(spec)?;
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push((id, bp));
+        Ok(id)
+    }
+
+    fn parse_breakpoint(&self, spec: &str) -> Result<Breakpoint, String> {
+        let (file, line) = match spec.rsplit_once(':') {
+            Some((file, line)) => (Some(file), line),
+            None => (None, spec),
+        };
+        if let Some(file) = file {
+            let matches =
+                file == self.file || Path::new(&self.file).file_name() == Some(file.as_ref());
+            if !matches {
+                return Err(format!("no source file named '{}'", file));
+            }
+        }
+        match line.parse::<usize>() {
+            Ok(n) if (1..=self.index.line_count()).contains(&n) => Ok(Breakpoint::Line(n)),
+            Ok(n) => Err(format!("line {} is outside {}", n, self.file)),
+            Err(_) if file.is_none() && is_ident(spec) => Ok(Breakpoint::Fn(spec.to_string())),
+            Err(_) => Err(format!(
+                "expected a function name or line, found '{}'",
+                spec
+            )),
+        }
+    }
+
+    /// Don't stop at the start of `main`; run until a breakpoint.
+    pub fn run_to_breakpoint(&mut self) {
+        self.mode = Mode::Continue;
+    }
+
+    /// A call to Strata function `name` begins.
+    pub fn enter(&mut self, name: &str) {
+        self.stack.push(Frame {
+            name: name.to_string(),
+            line: None,
+        });
+        self.pending = self
+            .breakpoints
+            .iter()
+            .find(|(_, bp)| matches!(bp, Breakpoint::Fn(f) if f == name))
+            .map(|(id, _)| *id);
+    }
+
+    /// The innermost call returns.
+    pub fn exit(&mut self) {
+        self.stack.pop();
+        self.pending = None;
+    }
+
+    /// A statement at `span` is about to run in `env`. Stops if a
+    /// breakpoint or step says so, failing with [`RuntimeErrorKind::Quit`]
+    /// if the user quits.
+    pub fn on_stmt(&mut self, span: Span, env: &Env) -> Result<()> {
+        let (line, _) = self.index.line_col(span.start);
+        let depth = self.stack.len();
+        if let Some(frame) = self.stack.last_mut() {
+            frame.line = Some(line);
+        }
+        let arrived = self.last != Some((depth, line));
+        self.last = Some((depth, line));
+
+        let hit = self.pending.take().or_else(|| {
+            self.breakpoints
+                .iter()
+                .find(|(_, bp)| arrived && *bp == Breakpoint::Line(line))
+                .map(|(id, _)| *id)
+        });
+        let stepped = arrived
+            && match self.mode {
+                Mode::Step => true,
+                Mode::Next(d) => depth <= d,
+                Mode::Continue => false,
+            };
+        if hit.is_none() && !stepped {
+            return Ok(());
+        }
+
+        if let Some(id) = hit {
+            write!(self.output, "Breakpoint {}, ", id)?;
+        }
+        let location = format!("{} at {}:{}", self.frame_name(), self.file, line);
+        writeln!(self.output, "{}", location)?;
+        self.show_line(line)?;
+        self.prompt(env)
+    }
+
+    fn frame_name(&self) -> &str {
+        self.stack.last().map_or("<top level>", |f| f.name.as_str())
+    }
+
+    fn show_line(&mut self, line: usize) -> Result<()> {
+        let text = self.index.line_text(line).unwrap_or_default();
+        writeln!(self.output, "{:>5} | {}", line, text)?;
+        Ok(())
+    }
+
+    /// Read and run commands until one resumes the program.
+    fn prompt(&mut self, env: &Env) -> Result<()> {
+        loop {
+            write!(self.output, "(strata-debug) ")?;
+            self.output.flush()?;
+            let mut input = String::new();
+            if self.input.read_line(&mut input)? == 0 {
+                writeln!(self.output)?;
+                return Err(RuntimeErrorKind::Quit.into());
+            }
+            let input = input.trim();
+            if !input.is_empty() {
+                self.last_command = input.to_string();
+            }
+            let command = self.last_command.clone();
+            let (cmd, arg) = match command.split_once(char::is_whitespace) {
+                Some((cmd, arg)) => (cmd, arg.trim()),
+                None => (command.as_str(), ""),
+            };
+
+            match cmd {
+                "s" | "step" => {
+                    self.mode = Mode::Step;
+                    return Ok(());
+                }
+                "n" | "next" => {
+                    self.mode = Mode::Next(self.stack.len());
+                    return Ok(());
+                }
+                "c" | "continue" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "q" | "quit" => return Err(RuntimeErrorKind::Quit.into()),
+                "b" | "break" => {
+                    let spec = match (arg, self.last) {
+                        ("", Some((_, line))) => line.to_string(),
+                        _ => arg.to_string(),
+                    };
+                    match self.add_breakpoint(&spec) {
+                        Ok(id) => {
+                            let bp = &self.breakpoints[self.breakpoints.len() - 1].1;
+                            writeln!(self.output, "Breakpoint {} at {}", id, bp)?;
+                        }
+                        Err(e) => writeln!(self.output, "Error: {}", e)?,
+                    }
+                }
+                "d" | "delete" => match arg.parse::<usize>() {
+                    Ok(id) if self.breakpoints.iter().any(|(b, _)| *b == id) => {
+                        self.breakpoints.retain(|(b, _)| *b != id);
+                        writeln!(self.output, "Deleted breakpoint {}", id)?;
+                    }
+                    _ => writeln!(self.output, "Error: no breakpoint '{}'", arg)?,
+                },
+                "l" | "locals" => {
+                    let locals = env.locals();
+                    if locals.is_empty() {
+                        writeln!(self.output, "No locals.")?;
+                    }
+                    for (name, value) in locals {
+                        let shown = self.describe(value);
+                        writeln!(self.output, "{} = {}", name, shown)?;
+                    }
+                }
+                "p" | "print" => match env.get(arg) {
+                    Some(value) => {
+                        let shown = self.describe(value);
+                        writeln!(self.output, "{} = {}", arg, shown)?;
+                    }
+                    None => writeln!(self.output, "Error: no variable '{}'", arg)?,
+                },
+                "bt" | "where" => {
+                    for (i, frame) in self.stack.iter().rev().enumerate() {
+                        match frame.line {
+                            Some(line) => writeln!(
+                                self.output,
+                                "#{} {} at {}:{}",
+                                i, frame.name, self.file, line
+                            )?,
+                            None => writeln!(self.output, "#{} {}", i, frame.name)?,
+                        }
+                    }
+                }
+                "h" | "help" => writeln!(self.output, "{}", HELP)?,
+                _ => writeln!(self.output, "Unknown command '{}'. Try 'help'.", command)?,
+            }
+        }
+    }
+
+    /// A value as `locals` and `print` show it: a moved affine value says
+    /// where it was moved rather than looking like a live one.
+    fn describe(&self, value: &Value) -> String {
+        match value {
+            Value::Consumed { moved_at, .. } => {
+                let (line, col) = self.index.line_col(moved_at.start);
+                format!("<moved at {}:{}>", line, col)
+            }
+            value => pretty(value, &self.limits),
+        }
+    }
+}
+
+const HELP: &str = "\
+break FN | LINE | FILE:LINE   set a breakpoint (here, with no argument)
+delete N                     remove breakpoint N
+step (s)                     run to the next statement
+next (n)                     run to the next statement in this function
+continue (c)                 run to the next breakpoint
+locals (l)                   show local variables
+print NAME (p)               show a variable
+where (bt)                   show the call stack
+quit (q)                     end the run";
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::{run_module_with, RunOptions};
+    use std::sync::{Arc, Mutex};
+
+    /// Output shared with the test after the debugger is moved into the run
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn debug(src: &str, commands: &str) -> (Result<Value>, String) {
+        let module = strata_parse::parse_str("prog.strata", src).unwrap();
+        let out = Shared::default();
+        let input = Box::new(std::io::Cursor::new(commands.to_string()));
+        let debugger = Debugger::new("prog.strata", src, input, Box::new(out.clone()));
+        let opts = RunOptions {
+            debugger: Some(Arc::new(Mutex::new(debugger))),
+            ..RunOptions::default()
+        };
+        let result = run_module_with(&module, opts);
+        let output = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        (result, output)
+    }
+
+    const SRC: &str = "fn double(n: Int) -> Int {
+    let twice = n * 2;
+    twice
+}
+
+fn main() -> Int {
+    let a = double(3);
+    let b = double(a);
+    b
+}
+";
+
+    #[test]
+    fn breakpoints_stop_and_show_locals() {
+        let (result, out) = debug(SRC, "break double\ncontinue\nwhere\nlocals\ndelete 1\nc\n");
+        assert!(matches!(result, Ok(Value::Int(12))), "{:?}", result);
+        assert!(
+            out.starts_with("main at prog.strata:7\n    7 |     let a"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("Breakpoint 1, double at prog.strata:2"),
+            "{}",
+            out
+        );
+        assert!(
+            out.contains("#0 double at prog.strata:2\n#1 main at prog.strata:7"),
+            "{}",
+            out
+        );
+        assert!(out.contains("n = 3\n"), "{}", out);
+        // Deleted before the second call
+        assert_eq!(out.matches("Breakpoint 1,").count(), 1, "{}", out);
+    }
+
+    #[test]
+    fn next_steps_over_calls_and_step_into_them() {
+        let (_, out) = debug(SRC, "next\nstep\nstep\nstep\nq\n");
+        let stops: Vec<&str> = out
+            .lines()
+            .filter(|l| l.contains(" at prog.strata:"))
+            .map(|l| l.trim_start_matches("(strata-debug) "))
+            .collect();
+        assert_eq!(
+            stops,
+            [
+                "main at prog.strata:7",
+                "main at prog.strata:8",
+                "double at prog.strata:2",
+                "double at prog.strata:3",
+                "main at prog.strata:9",
+            ]
+        );
+    }
+
+    #[test]
+    fn moved_values_are_flagged_and_quit_ends_the_run() {
+        let src = "fn main(t: TimeCap) -> Int & {Time} {
+    drop(t);
+    0
+}
+";
+        let (result, out) = debug(src, "step\nlocals\nquit\n");
+        assert!(out.contains("t = <moved at 2:10>"), "{}", out);
+        assert_eq!(result.unwrap_err().kind, RuntimeErrorKind::Quit);
+    }
+
+    #[test]
+    fn breakpoint_specs() {
+        let mut d = Debugger::new(
+            "dir/prog.strata",
+            SRC,
+            Box::new(std::io::empty()),
+            Box::new(std::io::sink()),
+        );
+        assert_eq!(d.add_breakpoint("double"), Ok(1));
+        assert_eq!(d.add_breakpoint("prog.strata:8"), Ok(2));
+        assert_eq!(d.add_breakpoint("dir/prog.strata:8"), Ok(3));
+        assert_eq!(d.breakpoints[1].1, Breakpoint::Line(8));
+        assert!(d.add_breakpoint("other.strata:8").is_err());
+        assert!(d.add_breakpoint("99").is_err());
+        assert!(d.add_breakpoint("1x").is_err());
+    }
+}
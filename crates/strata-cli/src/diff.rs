@@ -0,0 +1,290 @@
+//! Item-level semantic diff for `strata diff`.
+//!
+//! Two checked modules are compared by what they declare, not by their
+//! text: each top-level function, `extern fn`, `let`, and `const` by its
+//! checked type (with its inferred effect row), and each struct and enum by
+//! its visibility and type parameters and then field by field or variant by
+//! variant. Reformatting, reordering items, or editing a function body
+//! without changing its type is no change at all.
+
+use std::collections::BTreeMap;
+
+use strata_ast::ast::{Item, Module};
+use strata_types::adt::{AdtDef, AdtKind, VariantFields};
+use strata_types::infer::subst::Subst;
+use strata_types::infer::ty::renumbered;
+use strata_types::infer::{Ty, TypeVarId};
+use strata_types::{TypeChecker, TypedItemKind};
+
+/// What a change is to: a top-level item, or a field or variant of one.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Key {
+    pub item: String,
+    /// `fn`, `extern fn`, `let`, `const`, `struct`, or `enum`
+    pub kind: &'static str,
+    /// `field x` or `variant Some`, for a member of a struct or enum
+    pub member: Option<String>,
+}
+
+/// One difference between two modules. Each side is shown as
+/// [`describe`] writes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added {
+        key: Key,
+        new: String,
+    },
+    Removed {
+        key: Key,
+        old: String,
+    },
+    /// Declared in both, differently
+    Changed {
+        key: Key,
+        old: String,
+        new: String,
+    },
+}
+
+impl Change {
+    pub fn key(&self) -> &Key {
+        match self {
+            Change::Added { key, .. }
+            | Change::Removed { key, .. }
+            | Change::Changed { key, .. } => key,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // A member is shown under its item: `+ struct Point: field z: Int`
+        let key = self.key();
+        let under = match &key.member {
+            Some(_) => format!("{} {}: ", key.kind, key.item),
+            None => String::new(),
+        };
+        match self {
+            Change::Added { new, .. } => write!(f, "+ {}{}", under, new),
+            Change::Removed { old, .. } => write!(f, "- {}{}", under, old),
+            Change::Changed { old, new, .. } => write!(f, "~ {}{} => {}", under, old, new),
+        }
+    }
+}
+
+/// The item-level changes from the checked module `old` to the checked
+/// module `new`, by item name. The fields and variants of an item added or
+/// removed as a whole aren't listed separately.
+pub fn diff(
+    old: &Module,
+    old_checker: &TypeChecker,
+    new: &Module,
+    new_checker: &TypeChecker,
+) -> Vec<Change> {
+    let old = describe(old, old_checker);
+    let mut new = describe(new, new_checker);
+    let mut changes = Vec::new();
+    for (key, old_text) in old {
+        match new.remove(&key) {
+            Some(new_text) if new_text == old_text => {}
+            Some(new_text) => changes.push(Change::Changed {
+                key,
+                old: old_text,
+                new: new_text,
+            }),
+            None => changes.push(Change::Removed { key, old: old_text }),
+        }
+    }
+    changes.extend(
+        new.into_iter()
+            .map(|(key, new_text)| Change::Added { key, new: new_text }),
+    );
+    changes.sort_by(|a, b| a.key().cmp(b.key()));
+    // A whole item added or removed stands for its members
+    let whole: Vec<Key> = changes
+        .iter()
+        .filter(|c| !matches!(c, Change::Changed { .. }) && c.key().member.is_none())
+        .map(|c| c.key().clone())
+        .collect();
+    changes.retain(|c| {
+        let key = c.key();
+        key.member.is_none()
+            || !whole
+                .iter()
+                .any(|item| item.item == key.item && item.kind == key.kind)
+    });
+    changes
+}
+
+/// Everything `diff` compares in the checked module `m`: the items, and
+/// the fields and variants of its structs and enums, each written out as
+/// `pub fn load: String -> String & {Fs}`, `pub struct Pair<A, B>`,
+/// `field first: A`, or `variant Some(T)`
+pub fn describe(m: &Module, checker: &TypeChecker) -> BTreeMap<Key, String> {
+    let mut described = BTreeMap::new();
+    let mut add = |item: &str, kind: &'static str, member: Option<String>, text: String| {
+        let key = Key {
+            item: item.to_string(),
+            kind,
+            member,
+        };
+        described.insert(key, text);
+    };
+    for typed in checker.typed_items() {
+        let kind = match typed.kind {
+            TypedItemKind::Fn => "fn",
+            TypedItemKind::ExternFn => "extern fn",
+            TypedItemKind::Let => "let",
+            TypedItemKind::Const => "const",
+        };
+        let public = m.items.iter().any(
+            |item| matches!(item, Item::Fn(decl) if decl.public && decl.name.text == typed.name),
+        );
+        let text = format!(
+            "{}{} {}: {}",
+            if public { "pub " } else { "" },
+            kind,
+            typed.name,
+            renumbered(&typed.ty)
+        );
+        add(&typed.name, kind, None, text);
+    }
+    for item in &m.items {
+        let (name, public, kind) = match item {
+            Item::Struct(def) => (&def.name.text, def.public, "struct"),
+            Item::Enum(def) => (&def.name.text, def.public, "enum"),
+            _ => continue,
+        };
+        let Some(def) = checker.adt_registry().get(name) else {
+            continue;
+        };
+        let params = match def.type_params.as_slice() {
+            [] => String::new(),
+            params => format!("<{}>", params.join(", ")),
+        };
+        let text = format!(
+            "{}{}{} {}{}",
+            if public { "pub " } else { "" },
+            if def.linear { "linear " } else { "" },
+            kind,
+            name,
+            params
+        );
+        add(name, kind, None, text);
+        match &def.kind {
+            AdtKind::Struct(fields) => {
+                for field in fields {
+                    let member = format!("field {}", field.name);
+                    let text = format!("{}: {}", member, written(def, &field.ty));
+                    add(name, kind, Some(member), text);
+                }
+            }
+            AdtKind::Enum(variants) => {
+                for variant in variants {
+                    let member = format!("variant {}", variant.name);
+                    let text = match &variant.fields {
+                        // A unit-only enum's variants are numbered, and
+                        // casts see the numbers
+                        VariantFields::Unit if def.is_unit_only() => {
+                            format!("{} = {}", member, variant.discriminant)
+                        }
+                        VariantFields::Unit => member.clone(),
+                        VariantFields::Tuple(tys) => {
+                            let tys: Vec<String> = tys.iter().map(|t| written(def, t)).collect();
+                            format!("{}({})", member, tys.join(", "))
+                        }
+                    };
+                    add(name, kind, Some(member), text);
+                }
+            }
+        }
+    }
+    described
+}
+
+/// A field type of `def`, with its type parameters by name
+fn written(def: &AdtDef, ty: &Ty) -> String {
+    let mut params = Subst::new();
+    for (i, param) in def.type_params.iter().enumerate() {
+        let named = Ty::Adt {
+            name: param.clone(),
+            args: Vec::new(),
+        };
+        params.insert(TypeVarId(i as u32), named);
+    }
+    params.apply(ty).unwrap_or_else(|_| ty.clone()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strata_parse::parse_str;
+
+    fn changes(old: &str, new: &str) -> Vec<String> {
+        let check = |src: &str| {
+            let m = parse_str("<mem>", src).unwrap();
+            let mut checker = TypeChecker::new();
+            checker.check_module(&m).unwrap();
+            (m, checker)
+        };
+        let (old, old_checker) = check(old);
+        let (new, new_checker) = check(new);
+        diff(&old, &old_checker, &new, &new_checker)
+            .iter()
+            .map(Change::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn bodies_and_layout_are_not_changes() {
+        let old = "fn twice(n: Int) -> Int { n * 2 }\nfn id(x) { x }\n";
+        let new = "fn id(y) {\n    y\n}\n\nfn twice(n: Int) -> Int { n + n }\n";
+        assert!(changes(old, new).is_empty());
+    }
+
+    #[test]
+    fn functions_added_removed_and_retyped() {
+        let old = "\
+extern fn read(fs: FsCap, path: String) -> String & {Fs};
+pub fn load(fs: FsCap, p: String) -> String & {Fs} { read(fs, p) }
+fn gone() -> Int { 1 }
+";
+        let new = "\
+extern fn read(fs: FsCap, path: String) -> String & {Fs};
+extern fn fetch(net: NetCap, url: String) -> String & {Net};
+pub fn load(fs: FsCap, net: NetCap, p: String) -> String & {Fs, Net} { fetch(net, read(fs, p)) }
+";
+        assert_eq!(
+            changes(old, new),
+            [
+                "+ extern fn fetch: (NetCap, String) -> String & {Net}",
+                "- fn gone: () -> Int & {e0}",
+                "~ pub fn load: (FsCap, String) -> String & {Fs} \
+                 => pub fn load: (FsCap, NetCap, String) -> String & {Fs, Net}",
+            ]
+        );
+    }
+
+    #[test]
+    fn adt_members_are_compared_one_by_one() {
+        let old = "\
+pub struct Pair<A, B> { first: A, second: B }
+enum Shape { Circle(Float), Square(Float) }
+struct Gone { x: Int }
+";
+        let new = "\
+pub struct Pair<A, B> { first: A, second: Int, third: Bool }
+pub enum Shape { Circle(Float), Square(Float), Tri(Float, Float, Float) }
+";
+        assert_eq!(
+            changes(old, new),
+            [
+                "- struct Gone",
+                "~ struct Pair: field second: B => field second: Int",
+                "+ struct Pair: field third: Bool",
+                "~ enum Shape => pub enum Shape",
+                "+ enum Shape: variant Tri(Float, Float, Float)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Outputs for `strata parse --emit`: typed JSON and highlighted HTML.
+
+use serde_json::{json, Value};
+use strata_ast::ast::Module;
+use strata_parse::{semantic_token_modifiers, semantic_tokens, TokenClass};
+use strata_types::infer::Ty;
+use strata_types::{EffectRow, TypedItem, TypedItemKind};
+
+/// The checked module as JSON: the syntax tree under `"module"` and, under
+/// `"items"`, the resolved type of every top-level `fn`, `extern fn`, and
+/// `let`, with function effect rows and the types of local `let` bindings.
+///
+/// Types are rendered in the checker's notation (`(Int, String) -> Unit`),
+/// with unsolved type variables as `tN`.
+pub fn typed_json(module: &Module, items: &[TypedItem]) -> Result<Value, serde_json::Error> {
+    Ok(json!({
+        "module": serde_json::to_value(module)?,
+        "items": items.iter().map(typed_item_json).collect::<Vec<_>>(),
+    }))
+}
+
+fn typed_item_json(item: &TypedItem) -> Value {
+    let kind = match item.kind {
+        TypedItemKind::Fn => "fn",
+        TypedItemKind::ExternFn => "extern_fn",
+        TypedItemKind::Let => "let",
+        TypedItemKind::Const => "const",
+    };
+    let mut out = json!({
+        "name": item.name,
+        "kind": kind,
+        "span": item.span,
+        "type": item.ty.to_string(),
+        "locals": item.locals.iter().map(|l| json!({
+            "name": l.name,
+            "span": l.span,
+            "type": l.ty.to_string(),
+        })).collect::<Vec<_>>(),
+    });
+    if let Ty::Arrow(params, ret, eff) = &item.ty {
+        out["params"] = params.iter().map(|p| p.to_string()).collect();
+        out["ret"] = ret.to_string().into();
+        out["effects"] = effects_json(eff);
+    }
+    out
+}
+
+/// `{"concrete": ["Fs", ...], "open": bool}`; an open row may still gain
+/// effects through its tail variable.
+fn effects_json(row: &EffectRow) -> Value {
+    json!({
+        "concrete": row.iter().map(|e| format!("{:?}", e)).collect::<Vec<_>>(),
+        "open": !row.is_closed(),
+    })
+}
+
+/// Stylesheet for [`html`]: one rule per token class, then one per
+/// modifier.
+const HTML_STYLE: &str = "\
+pre.strata { background: #fafafa; color: #222; padding: 1em; }
+.tok-keyword { color: #a626a4; font-weight: bold; }
+.tok-type { color: #c18401; }
+.tok-capability { color: #e45649; font-weight: bold; }
+.tok-effect { color: #e45649; font-style: italic; }
+.tok-function { color: #4078f2; }
+.tok-enumMember { color: #986801; }
+.tok-number { color: #986801; }
+.tok-string { color: #50a14f; }
+.tok-comment { color: #a0a1a7; font-style: italic; }
+.tok-deprecated { text-decoration: line-through; }
+";
+
+/// Standalone HTML page showing `src` highlighted with
+/// [`semantic_tokens`]. Each classified span is wrapped in
+/// `<span class="tok-{class}">`, plus `tok-{modifier}` for each modifier
+/// it carries; unmodified variables are left unwrapped.
+pub fn html(title: &str, src: &str) -> String {
+    let modifiers = semantic_token_modifiers(src);
+    let mut body = String::new();
+    let mut pos = 0;
+    for (span, class) in semantic_tokens(src) {
+        let (start, end) = (span.start as usize, span.end as usize);
+        let modified: Vec<_> = modifiers.iter().filter(|(s, _)| *s == span).collect();
+        if (class == TokenClass::Variable && modified.is_empty()) || start < pos {
+            continue;
+        }
+        let mut classes = format!("tok-{}", class.name());
+        for (_, modifier) in modified {
+            classes.push_str(&format!(" tok-{}", modifier.name()));
+        }
+        body.push_str(&escape_html(&src[pos..start]));
+        body.push_str(&format!(
+            "<span class=\"{}\">{}</span>",
+            classes,
+            escape_html(&src[start..end])
+        ));
+        pos = end;
+    }
+    body.push_str(&escape_html(&src[pos..]));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}</style>\n</head>\n<body>\n<pre class=\"strata\"><code>{}</code></pre>\n\
+         </body>\n</html>\n",
+        escape_html(title),
+        HTML_STYLE,
+        body
+    )
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,272 @@
+//! Typed evaluator errors.
+//!
+//! Every way a run can fail is a [`RuntimeErrorKind`], so embedders can tell
+//! a division by zero from an undefined variable or a reused capability
+//! without matching on message text. A [`RuntimeError`] adds where it was
+//! raised and the functions active at the time.
+
+use std::fmt;
+use strata_ast::diag::Diagnostic;
+use strata_ast::span::Span;
+
+/// Result of evaluation
+pub type Result<T, E = RuntimeError> = std::result::Result<T, E>;
+
+/// What went wrong
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RuntimeErrorKind {
+    /// A name with no binding in scope
+    UndefinedVariable { name: String },
+    /// Assignment to a binding not declared `mut`
+    AssignToImmutable { name: String },
+    /// Integer division by zero
+    DivisionByZero,
+    /// Integer arithmetic whose result doesn't fit in an `Int`
+    IntegerOverflow { op: &'static str },
+    /// An operation given a value of the wrong kind
+    TypeMismatch { message: String },
+    /// A function called with the wrong number of arguments
+    ArityMismatch { expected: usize, got: usize },
+    /// Calls nested deeper than the evaluator allows
+    CallDepthExceeded { limit: u32 },
+    /// More expressions evaluated than the run's step limit allows
+    StepLimitExceeded { limit: u64 },
+    /// `todo()` ran: the code there has not been written yet
+    Todo,
+    /// `unreachable()` ran: the program reached code it claimed could not run
+    Unreachable,
+    /// A capability used after being moved at `moved_at`
+    CapabilityReused { name: String, moved_at: Span },
+    /// An irrefutable pattern didn't match
+    PatternMismatch,
+    /// No `match` arm matched the value
+    NonExhaustiveMatch { value: String },
+    /// One pattern bound a name twice
+    DuplicateBinding { name: String },
+    /// `==` or an ordering applied to values that have none
+    NotComparable { message: String },
+    /// A task joined a second time
+    TaskAlreadyJoined,
+    /// A host function failed, or policy or an observer refused it
+    Host { function: String, message: String },
+    /// A replayed run diverged from its trace
+    Replay { message: String },
+    /// A signed or encrypted trace replayed without checking or opening it
+    SealedTrace { message: String },
+    /// The run couldn't start: no `main`, an ungranted capability, a bad const
+    Setup { message: String },
+    /// Writing the trace, audit log, or debugger output, or starting a
+    /// thread, failed
+    Io { message: String },
+    /// The user quit the debugger
+    Quit,
+    /// The evaluator broke one of its own invariants
+    Internal { message: String },
+}
+
+impl RuntimeErrorKind {
+    /// The stable error code, for the kinds that have one
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            RuntimeErrorKind::Todo => Some("RUN-TODO"),
+            RuntimeErrorKind::Unreachable => Some("RUN-UNREACHABLE"),
+            RuntimeErrorKind::CapabilityReused { .. } => Some("CAP-MOVE-RUNTIME"),
+            RuntimeErrorKind::IntegerOverflow { .. } => Some("RUN-INT-OVERFLOW"),
+            _ => None,
+        }
+    }
+
+    /// Whether this is one of the evaluator's defense-in-depth failures
+    /// (irrefutable pattern not matching, capability used twice, no match
+    /// arm taken, duplicate binding, ...): a soundness bug in the type
+    /// checker rather than an error in the program.
+    pub fn is_checker_escape(&self) -> bool {
+        matches!(
+            self,
+            RuntimeErrorKind::CapabilityReused { .. }
+                | RuntimeErrorKind::PatternMismatch
+                | RuntimeErrorKind::NonExhaustiveMatch { .. }
+                | RuntimeErrorKind::DuplicateBinding { .. }
+                | RuntimeErrorKind::NotComparable { .. }
+                | RuntimeErrorKind::TaskAlreadyJoined
+        )
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::UndefinedVariable { name } => {
+                write!(f, "undefined variable `{}`", name)
+            }
+            RuntimeErrorKind::AssignToImmutable { name } => {
+                write!(f, "cannot assign to immutable variable `{}`", name)
+            }
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::IntegerOverflow { op } => write!(f, "integer overflow in `{}`", op),
+            RuntimeErrorKind::TypeMismatch { message }
+            | RuntimeErrorKind::NotComparable { message }
+            | RuntimeErrorKind::Replay { message }
+            | RuntimeErrorKind::SealedTrace { message }
+            | RuntimeErrorKind::Setup { message }
+            | RuntimeErrorKind::Io { message } => write!(f, "{}", message),
+            RuntimeErrorKind::ArityMismatch { expected, got } => {
+                write!(f, "function expects {} arguments, got {}", expected, got)
+            }
+            RuntimeErrorKind::CallDepthExceeded { limit } => {
+                write!(f, "maximum call depth exceeded (limit: {} calls)", limit)
+            }
+            RuntimeErrorKind::StepLimitExceeded { limit } => {
+                write!(f, "step limit exceeded (limit: {} steps)", limit)
+            }
+            RuntimeErrorKind::Todo => write!(f, "not yet implemented: reached `todo()`"),
+            RuntimeErrorKind::Unreachable => {
+                write!(f, "entered unreachable code: reached `unreachable()`")
+            }
+            RuntimeErrorKind::CapabilityReused { name, .. } => {
+                write!(f, "capability '{}' has already been used", name)
+            }
+            RuntimeErrorKind::PatternMismatch => {
+                write!(f, "pattern match failed (should be caught by type checker)")
+            }
+            RuntimeErrorKind::NonExhaustiveMatch { value } => {
+                write!(
+                    f,
+                    "non-exhaustive match: no pattern matched value {}",
+                    value
+                )
+            }
+            RuntimeErrorKind::DuplicateBinding { name } => write!(
+                f,
+                "duplicate binding '{}' in pattern (should be caught by type checker)",
+                name
+            ),
+            RuntimeErrorKind::TaskAlreadyJoined => {
+                write!(f, "task already joined (should be caught by type checker)")
+            }
+            RuntimeErrorKind::Host { function, message } => {
+                write!(f, "host function '{}': {}", function, message)
+            }
+            RuntimeErrorKind::Quit => write!(f, "debugging session ended"),
+            RuntimeErrorKind::Internal { message } => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+/// A failed run: what went wrong, where, and in which calls
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    /// The innermost expression being evaluated, if the error came from the
+    /// program rather than the run's setup
+    pub span: Option<Span>,
+    /// Names of the Strata functions active, innermost first
+    pub stack: Vec<String>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind) -> Self {
+        Self {
+            kind,
+            span: None,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Locate the error at `span`, unless it already has a location.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Note that the error unwound out of a call to `function`.
+    pub fn in_fn(mut self, function: impl Into<String>) -> Self {
+        self.stack.push(function.into());
+        self
+    }
+
+    /// The error as a tool-facing [`Diagnostic`], located at the failing
+    /// expression. Kinds without a code of their own use `RUN-ERROR`.
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let code = self.kind.code().unwrap_or("RUN-ERROR");
+        Diagnostic::error(code, self.kind.to_string(), self.span.unwrap_or_default())
+    }
+
+    /// Other places the error refers to, each with what happened there:
+    /// where a reused capability was moved.
+    pub fn notes(&self) -> Vec<(String, Span)> {
+        match &self.kind {
+            RuntimeErrorKind::CapabilityReused { moved_at, .. } => {
+                vec![("permission was transferred here".to_string(), *moved_at)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// See [`RuntimeErrorKind::is_checker_escape`].
+    pub fn is_checker_escape(&self) -> bool {
+        self.kind.is_checker_escape()
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<std::io::Error> for RuntimeError {
+    fn from(e: std::io::Error) -> Self {
+        Self::new(RuntimeErrorKind::Io {
+            message: e.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(code) = self.kind.code() else {
+            return write!(f, "{}", self.kind);
+        };
+        match &self.kind {
+            RuntimeErrorKind::CapabilityReused { .. } => write!(
+                f,
+                "error[{}]: {}\n\
+                 \n  \
+                 note: this should have been rejected at compile time. This is a Strata bug.\n  \
+                 Please report at: https://github.com/strata-lang/strata/issues\n  \
+                 Include your source file and `strata --version` output.",
+                code, self.kind,
+            ),
+            _ => write!(f, "error[{}]: {}", code, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_location_and_every_frame_are_kept() {
+        let inner = Span {
+            start: 4,
+            end: 9,
+            ..Span::default()
+        };
+        let err = RuntimeError::new(RuntimeErrorKind::Todo)
+            .at(inner)
+            .at(Span::default())
+            .in_fn("helper")
+            .in_fn("main");
+        assert_eq!(err.span, Some(inner));
+        assert_eq!(err.stack, ["helper", "main"]);
+        assert_eq!(
+            err.to_string(),
+            "error[RUN-TODO]: not yet implemented: reached `todo()`"
+        );
+    }
+}
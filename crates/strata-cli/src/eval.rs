@@ -3,23 +3,62 @@
 //! Implements a tree-walking interpreter with proper scoping,
 //! closures, and control flow (return, break, continue).
 
-use anyhow::{bail, Result};
+use std::borrow::Cow;
 use std::cell::Cell;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use strata_ast::ast::{
-    BinOp, Block, Expr, FieldInit, Lit, MatchArm, Module, Pat, Path, Stmt, UnOp,
+    BinOp, Block, Expr, FieldInit, FnDecl, Lit, MatchArm, Module, Pat, Path, Stmt, UnOp,
 };
 use strata_ast::span::Span;
+use strata_types::consts::{ConstTable, ConstValue};
+use strata_types::resolve::{self, DefId, Resolution};
 use strata_types::CapKind;
 
+use crate::audit::AuditLog;
+use crate::caps::CapsConfig;
+use crate::coverage::Coverage;
+use crate::debug::Debugger;
+pub use crate::error::{Result, RuntimeError, RuntimeErrorKind};
 use crate::host::{
-    ExternFnMeta, HostRegistry, ParamKind, ReplayError, TraceEmitter, TraceReplayer, TraceValue,
+    EffectPolicy, ExternFnMeta, HostRegistry, ParamKind, RedactMode, ReplayError, TraceEmitter,
+    TraceMeta, TraceReplayer, TraceValue,
 };
+use crate::profile::Profiler;
+use crate::seal::SealKeys;
+use crate::stats::StatsRecorder;
+
+/// Fail with a [`RuntimeErrorKind::TypeMismatch`], the message formatted
+/// as by `format!`; `mistyped!(at span; ...)` locates it at the operand at
+/// fault rather than the enclosing expression
+macro_rules! mistyped {
+    (at $span:expr; $($arg:tt)*) => {
+        return Err(RuntimeError::new(RuntimeErrorKind::TypeMismatch {
+            message: format!($($arg)*),
+        })
+        .at($span))
+    };
+    ($($arg:tt)*) => {
+        return Err(RuntimeErrorKind::TypeMismatch { message: format!($($arg)*) }.into())
+    };
+}
 
 /// Maximum call depth to prevent stack overflow from deep recursion
 const MAX_CALL_DEPTH: u32 = 1000;
 
+/// Most `par` branches and `spawn` tasks running on threads of their own at
+/// once; past this, `par` runs its branches one after the other and a
+/// spawned task waits to run until it is joined
+const MAX_TASK_THREADS: usize = 64;
+
+/// Stack size of a branch's or task's thread, the usual main thread's size
+const TASK_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// `par` branches and `spawn` tasks currently running on threads of their own
+static TASK_THREADS: AtomicUsize = AtomicUsize::new(0);
+
 thread_local! {
     /// Current call depth (thread-local for safety)
     static CALL_DEPTH: Cell<u32> = const { Cell::new(0) };
@@ -38,9 +77,20 @@ pub enum Value {
         params: Vec<String>,
         body: Block,
         env: Env,
+        /// The nested functions this one was declared with, if it is one
+        local: Option<Arc<LocalFns>>,
+    },
+    /// Partial application: `add(1, _)`, the function applied and its
+    /// arguments, `None` for each hole a call fills in order
+    Partial {
+        callee: Box<Value>,
+        args: Vec<Option<Value>>,
     },
-    /// Tuple value: (a, b, c)
-    Tuple(Vec<Value>),
+    /// Tuple value: (a, b, c). Shared, so copying a wide tuple out of a
+    /// binding bumps a count rather than copying every element
+    Tuple(Arc<[Value]>),
+    /// Record value: {x: 1, y: 2}, fields sorted by name. Shared like tuples
+    Record(Arc<BTreeMap<String, Value>>),
     /// Struct value: Point { x: 1, y: 2 }
     Struct {
         name: String,
@@ -56,6 +106,10 @@ pub enum Value {
     Cap(CapKind),
     /// Host function reference (extern fn name)
     HostFn(String),
+    /// Task started by `spawn`, waited for by `join`
+    Task(TaskHandle),
+    /// Lazy sequence from `iter` and the adapters over it
+    Iter(Seq),
     /// Tombstone: affine value already moved. Runtime defense-in-depth.
     Consumed {
         var_name: String,
@@ -69,9 +123,13 @@ impl std::fmt::Display for Value {
             Value::Int(v) => write!(f, "{v}"),
             Value::Float(v) => write!(f, "{v}"),
             Value::Bool(v) => write!(f, "{v}"),
-            Value::Str(s) => write!(f, "\"{s}\""),
+            Value::Str(s) => write!(f, "\"{}\"", strata_parse::escape_str(s)),
             Value::Unit => write!(f, "()"),
             Value::Closure { params, .. } => write!(f, "<fn({})>", params.join(", ")),
+            Value::Partial { args, .. } => {
+                let holes = args.iter().filter(|a| a.is_none()).count();
+                write!(f, "<fn({})>", vec!["_"; holes].join(", "))
+            }
             Value::Tuple(elems) => {
                 write!(f, "(")?;
                 for (i, elem) in elems.iter().enumerate() {
@@ -82,7 +140,27 @@ impl std::fmt::Display for Value {
                 }
                 write!(f, ")")
             }
+            Value::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, "}}")
+            }
             Value::Struct { name, fields } => {
+                if let Some(fields) = tuple_struct_fields(fields) {
+                    write!(f, "{}(", name)?;
+                    for (i, field) in fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", field)?;
+                    }
+                    return write!(f, ")");
+                }
                 write!(f, "{} {{ ", name)?;
                 let mut first = true;
                 // Sort fields for deterministic output
@@ -117,24 +195,49 @@ impl std::fmt::Display for Value {
             }
             Value::Cap(kind) => write!(f, "<cap:{}>", kind.type_name()),
             Value::HostFn(name) => write!(f, "<host_fn:{}>", name),
+            Value::Task(_) => write!(f, "<task>"),
+            Value::Iter(_) => write!(f, "<iter>"),
             Value::Consumed { var_name, .. } => write!(f, "<consumed:{}>", var_name),
         }
     }
 }
 
+/// A tuple struct's fields in order. Its fields are named `0`, `1`, ...,
+/// which no named field can be
+pub(crate) fn tuple_struct_fields(fields: &HashMap<String, Value>) -> Option<Vec<&Value>> {
+    if !fields.contains_key("0") {
+        return None;
+    }
+    (0..fields.len())
+        .map(|i| fields.get(&i.to_string()))
+        .collect()
+}
+
 impl Value {
+    /// Affinity with no `linear` ADTs in scope.
+    #[cfg(test)]
+    fn is_affine(&self) -> bool {
+        self.is_affine_in(&HashSet::new())
+    }
+
     /// Returns true if this value has affine semantics (single-use).
     ///
-    /// A value is affine if it IS a capability or CONTAINS one.
-    /// This mirrors Ty::kind() propagation at the type level — the same
-    /// principle that caught the v0.0.10.1 kind propagation bypass now
+    /// A value is affine if it IS a capability or CONTAINS one, or if it is
+    /// a value of one of the `linear` ADTs (even one holding no capability).
+    /// This mirrors AdtRegistry::kind() propagation at the type level — the
+    /// same principle that caught the v0.0.10.1 kind propagation bypass now
     /// operates at the runtime value level as defense-in-depth.
-    fn is_affine(&self) -> bool {
+    fn is_affine_in(&self, linear: &HashSet<String>) -> bool {
         match self {
-            Value::Cap(_) => true,
-            Value::Tuple(elems) => elems.iter().any(|v| v.is_affine()),
-            Value::Struct { fields, .. } => fields.values().any(|v| v.is_affine()),
-            Value::Variant { fields, .. } => fields.iter().any(|v| v.is_affine()),
+            Value::Cap(_) | Value::Task(_) => true,
+            Value::Tuple(elems) => elems.iter().any(|v| v.is_affine_in(linear)),
+            Value::Record(fields) => fields.values().any(|v| v.is_affine_in(linear)),
+            Value::Struct { name, fields } => {
+                linear.contains(name) || fields.values().any(|v| v.is_affine_in(linear))
+            }
+            Value::Variant {
+                enum_name, fields, ..
+            } => linear.contains(enum_name) || fields.iter().any(|v| v.is_affine_in(linear)),
             // Closures: NOT affine in v0.1. All closures are module-level function
             // definitions. The static move checker bans closure capture of affine
             // values. Making closures affine here would break every function call.
@@ -144,6 +247,201 @@ impl Value {
     }
 }
 
+/// Nested functions declared together in a block. Each call to one binds
+/// them all afresh, so they can call themselves and each other without a
+/// closure having to contain itself.
+#[derive(Debug)]
+pub struct LocalFns {
+    /// Name, parameters and body of each function
+    fns: Vec<(String, Vec<String>, Block)>,
+    /// The environment they were declared in
+    env: Env,
+}
+
+impl LocalFns {
+    /// Closures for the functions, by name
+    fn closures(self: &Arc<Self>) -> Vec<(String, Value)> {
+        self.fns
+            .iter()
+            .map(|(name, params, body)| {
+                let closure = Value::Closure {
+                    params: params.clone(),
+                    body: body.clone(),
+                    env: self.env.clone(),
+                    local: Some(Arc::clone(self)),
+                };
+                (name.clone(), closure)
+            })
+            .collect()
+    }
+}
+
+/// A lazy sequence: `iter(first, step)` and the adapters over it. Nothing
+/// runs until `next` or a `for` loop asks for an item, and then only what
+/// that item takes.
+#[derive(Debug, Clone)]
+pub enum Seq {
+    /// `first`, then the items after it
+    Start { first: Box<Value>, step: SeqFn },
+    /// The item `step(prev)` gives, if any, and those after it
+    After { prev: Box<Value>, step: SeqFn },
+    /// `f` of each item of `inner`
+    Map { inner: Box<Seq>, f: SeqFn },
+    /// The items of `inner` that `pred` holds for
+    Filter { inner: Box<Seq>, pred: SeqFn },
+    /// The first `left` items of `inner`
+    Take { inner: Box<Seq>, left: i64 },
+}
+
+/// A function a sequence calls, with the argument expression it was
+/// passed as, which names it to the profiler and observers
+#[derive(Debug, Clone)]
+pub struct SeqFn {
+    callee: Box<Expr>,
+    func: Box<Value>,
+}
+
+impl SeqFn {
+    fn call(&self, env: &mut Env, arg: Value) -> Result<Value> {
+        let cf = call_value(env, &self.callee, (*self.func).clone(), vec![(arg, false)])?;
+        Ok(cf.into_value())
+    }
+}
+
+impl Seq {
+    /// The first item and the rest of the sequence, or `None` if it is
+    /// empty
+    fn next(self, env: &mut Env) -> Result<Option<(Value, Seq)>> {
+        Ok(match self {
+            Seq::Start { first, step } => {
+                Some(((*first).clone(), Seq::After { prev: first, step }))
+            }
+            Seq::After { prev, step } => match step.call(env, *prev)? {
+                Value::Variant {
+                    variant_name,
+                    mut fields,
+                    ..
+                } if variant_name == "Some" && fields.len() == 1 => {
+                    let item = fields.remove(0);
+                    let rest = Seq::After {
+                        prev: Box::new(item.clone()),
+                        step,
+                    };
+                    Some((item, rest))
+                }
+                Value::Variant { variant_name, .. } if variant_name == "None" => None,
+                other => {
+                    mistyped!(at step.callee.span(); "iter() step must give an Option, got {}", other)
+                }
+            },
+            Seq::Map { inner, f } => match inner.next(env)? {
+                Some((item, rest)) => {
+                    let rest = Seq::Map {
+                        inner: Box::new(rest),
+                        f: f.clone(),
+                    };
+                    Some((f.call(env, item)?, rest))
+                }
+                None => None,
+            },
+            Seq::Filter { inner, pred } => {
+                let mut seq = *inner;
+                loop {
+                    let Some((item, rest)) = seq.next(env)? else {
+                        break None;
+                    };
+                    match pred.call(env, item.clone())? {
+                        Value::Bool(true) => {
+                            let rest = Seq::Filter {
+                                inner: Box::new(rest),
+                                pred,
+                            };
+                            break Some((item, rest));
+                        }
+                        Value::Bool(false) => seq = rest,
+                        other => {
+                            mistyped!(at pred.callee.span(); "filter() predicate must give a Bool, got {}", other)
+                        }
+                    }
+                }
+            }
+            Seq::Take { left, .. } if left <= 0 => None,
+            Seq::Take { inner, left } => inner.next(env)?.map(|(item, rest)| {
+                let rest = Seq::Take {
+                    inner: Box::new(rest),
+                    left: left - 1,
+                };
+                (item, rest)
+            }),
+        })
+    }
+}
+
+/// A task started by `spawn`. The `Value::Task` and the run's list of tasks
+/// to finish before it ends share it; whichever gets to it first takes the
+/// result.
+#[derive(Debug, Clone)]
+pub struct TaskHandle(Arc<Mutex<Option<TaskState>>>);
+
+#[derive(Debug)]
+enum TaskState {
+    /// Running on a thread of its own
+    Running(std::thread::JoinHandle<Result<Value>>),
+    /// Not started; runs on the joining thread when it is joined
+    Deferred { env: Box<Env>, body: Block },
+}
+
+impl TaskHandle {
+    /// Wait for the task to finish and take its result, or `None` if it was
+    /// already joined.
+    fn join(&self) -> Option<Result<Value>> {
+        let state = self.0.lock().unwrap().take()?;
+        Some(match state {
+            TaskState::Running(handle) => handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            TaskState::Deferred { mut env, body } => run_task(&mut env, &body),
+        })
+    }
+}
+
+/// The task code runs in: which one it is, for tracing, and how to name the
+/// tasks it spawns.
+#[derive(Debug, Default)]
+struct TaskScope {
+    /// `None` for `main`; otherwise `"2"` for the second task `main`
+    /// spawned, `"2.1"` for the first task that one spawned, and so on
+    id: Option<String>,
+    /// Prefix of the ids of tasks spawned here
+    prefix: String,
+    /// Tasks spawned here so far
+    spawned: AtomicU64,
+}
+
+impl TaskScope {
+    /// Scope of the next task spawned here
+    fn child(&self) -> TaskScope {
+        let n = self.spawned.fetch_add(1, Ordering::SeqCst) + 1;
+        let id = format!("{}{}", self.prefix, n);
+        TaskScope {
+            prefix: format!("{}.", id),
+            id: Some(id),
+            spawned: AtomicU64::new(0),
+        }
+    }
+
+    /// Scope of branch `i` of a `par`: the same task, numbering the tasks
+    /// it spawns apart from the other branch's so the ids don't depend on
+    /// how the branches interleave
+    fn branch(&self, i: usize) -> TaskScope {
+        TaskScope {
+            id: self.id.clone(),
+            prefix: format!("{}p{}.", self.prefix, i),
+            spawned: AtomicU64::new(0),
+        }
+    }
+}
+
 /// Control flow for evaluation
 ///
 /// Used to propagate returns through blocks and function calls.
@@ -181,26 +479,79 @@ impl ControlFlow {
 struct Binding {
     value: Value,
     mutable: bool,
+    /// Bound from a borrowed argument (`&x`): reads never tombstone it.
+    borrowed: bool,
 }
 
 /// Environment with lexical scoping
 ///
 /// Uses a stack of scopes for proper variable shadowing and block scoping.
+/// Scopes are shared between clones (closures capture their environment by
+/// cloning it) and copied only when a clone changes one.
 #[derive(Debug, Clone)]
 pub struct Env {
-    scopes: Vec<HashMap<String, Binding>>,
+    scopes: Vec<Arc<HashMap<String, Binding>>>,
     host_registry: Option<Arc<HostRegistry>>,
     tracer: Option<Arc<Mutex<TraceEmitter>>>,
     replayer: Option<Arc<Mutex<TraceReplayer>>>,
+    /// Names of ADTs declared `linear`; their values are always single-use
+    linear_types: Arc<HashSet<String>>,
+    /// What the module's names refer to: which calls build a tuple struct,
+    /// which paths are variants
+    resolution: Arc<Resolution>,
+    /// Module consts; a pattern naming one matches its value
+    consts: Arc<ConstTable>,
+    /// Default values of each struct's defaulted fields, for struct
+    /// literals that leave them off
+    field_defaults: Arc<HashMap<String, Vec<(String, Value)>>>,
+    /// Variant discriminants of each enum, in declaration order
+    discriminants: Arc<HashMap<String, Vec<(String, i64)>>>,
+    /// Command-line arguments read by the `arg_count()`/`arg(i)` builtins
+    args: Arc<Vec<String>>,
+    /// Embedder hooks notified of calls
+    observer: Option<Arc<dyn RuntimeObserver>>,
+    /// Per-function timings, for `--profile`
+    profiler: Option<Arc<Mutex<Profiler>>>,
+    /// Interactive debugger, for `strata debug`
+    debugger: Option<Arc<Mutex<Debugger>>>,
+    /// The task this code runs in
+    task: Arc<TaskScope>,
+    /// Every task spawned in the run, finished before the run ends
+    tasks: Arc<Mutex<Vec<TaskHandle>>>,
+    /// Tracer of spawned tasks; unlike `tracer`, never a `par` branch's
+    /// fork, which a task can outlive
+    task_tracer: Option<Arc<Mutex<TraceEmitter>>>,
+    /// Evaluator counters, for `--stats`
+    stats: Option<Arc<StatsRecorder>>,
+    /// Statements and branches reached, for `strata test --coverage`
+    coverage: Option<Arc<Coverage>>,
+    /// Expressions left to evaluate before the run fails, shared by every
+    /// clone, and the limit they started from
+    steps: Option<(Arc<AtomicU64>, u64)>,
 }
 
 impl Default for Env {
     fn default() -> Self {
         Self {
-            scopes: vec![HashMap::new()],
+            scopes: vec![Arc::default()],
             host_registry: None,
             tracer: None,
             replayer: None,
+            linear_types: Arc::default(),
+            resolution: Arc::default(),
+            consts: Arc::default(),
+            field_defaults: Arc::default(),
+            discriminants: Arc::default(),
+            args: Arc::default(),
+            observer: None,
+            profiler: None,
+            debugger: None,
+            task: Arc::default(),
+            tasks: Arc::default(),
+            task_tracer: None,
+            stats: None,
+            coverage: None,
+            steps: None,
         }
     }
 }
@@ -214,15 +565,31 @@ impl Env {
     /// Create a new environment with a host function registry
     pub fn with_host_registry(registry: Arc<HostRegistry>) -> Self {
         Self {
-            scopes: vec![HashMap::new()],
+            scopes: vec![Arc::default()],
             host_registry: Some(registry),
             tracer: None,
             replayer: None,
+            linear_types: Arc::default(),
+            resolution: Arc::default(),
+            consts: Arc::default(),
+            field_defaults: Arc::default(),
+            discriminants: Arc::default(),
+            args: Arc::default(),
+            observer: None,
+            profiler: None,
+            debugger: None,
+            task: Arc::default(),
+            tasks: Arc::default(),
+            task_tracer: None,
+            stats: None,
+            coverage: None,
+            steps: None,
         }
     }
 
     /// Attach a trace emitter to this environment.
     pub fn with_tracer(mut self, tracer: Arc<Mutex<TraceEmitter>>) -> Self {
+        self.task_tracer = Some(tracer.clone());
         self.tracer = Some(tracer);
         self
     }
@@ -233,17 +600,213 @@ impl Env {
         self
     }
 
+    /// Install hooks notified of calls as the program runs.
+    pub fn with_observer(mut self, observer: Arc<dyn RuntimeObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn profile_enter(&self, name: &str, host: bool) {
+        if let Some(profiler) = &self.profiler {
+            profiler.lock().unwrap().enter(name, host);
+        }
+    }
+
+    fn profile_exit(&self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.lock().unwrap().exit();
+        }
+    }
+
+    /// Time every function call with `profiler`.
+    pub fn with_profiler(mut self, profiler: Arc<Mutex<Profiler>>) -> Self {
+        self.profiler = Some(profiler);
+        self
+    }
+
+    /// Count evaluator work into `stats`.
+    pub fn with_stats(mut self, stats: Arc<StatsRecorder>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Run `f` on the stats recorder, if there is one.
+    fn count(&self, f: impl FnOnce(&StatsRecorder)) {
+        if let Some(stats) = &self.stats {
+            f(stats);
+        }
+    }
+
+    /// Count the statements and branches reached into `coverage`.
+    pub fn with_coverage(mut self, coverage: Arc<Coverage>) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Run `f` on the coverage counts, if there are any.
+    fn cover(&self, f: impl FnOnce(&Coverage)) {
+        if let Some(coverage) = &self.coverage {
+            f(coverage);
+        }
+    }
+
+    /// Fail the run once it has evaluated `limit` expressions, so a
+    /// program that may not terminate (a mutant, say) can't hang its caller.
+    pub fn with_step_limit(mut self, limit: u64) -> Self {
+        self.steps = Some((Arc::new(AtomicU64::new(limit)), limit));
+        self
+    }
+
+    /// Spend one step of the step limit, if there is one.
+    fn step(&self) -> Result<()> {
+        let Some((left, limit)) = &self.steps else {
+            return Ok(());
+        };
+        let spent = left.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        match spent {
+            Ok(_) => Ok(()),
+            Err(_) => Err(RuntimeErrorKind::StepLimitExceeded { limit: *limit }.into()),
+        }
+    }
+
+    /// Stop at breakpoints and steps under `debugger`.
+    pub fn with_debugger(mut self, debugger: Arc<Mutex<Debugger>>) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
+
+    fn debug_enter(&self, name: &str) {
+        if let Some(debugger) = &self.debugger {
+            debugger.lock().unwrap().enter(name);
+        }
+    }
+
+    fn debug_exit(&self) {
+        if let Some(debugger) = &self.debugger {
+            debugger.lock().unwrap().exit();
+        }
+    }
+
+    /// Let the debugger stop before the statement or expression at `span`.
+    fn debug_stmt(&self, span: Span) -> Result<()> {
+        match &self.debugger {
+            Some(debugger) => debugger.lock().unwrap().on_stmt(span, self),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the command-line arguments visible to the program.
+    pub fn with_args(mut self, args: &[String]) -> Self {
+        self.args = Arc::new(args.to_vec());
+        self
+    }
+
+    /// Record the module's `linear struct`/`linear enum` names.
+    pub fn with_linear_types(mut self, m: &Module) -> Self {
+        use strata_ast::ast::Item;
+        let names = m
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(def) if def.linear => Some(def.name.text.clone()),
+                Item::Enum(def) if def.linear => Some(def.name.text.clone()),
+                _ => None,
+            })
+            .collect();
+        self.linear_types = Arc::new(names);
+        self
+    }
+
+    /// Resolve the module's names.
+    pub fn with_resolution(mut self, m: &Module) -> Self {
+        self.resolution = Arc::new(resolve::resolve(m));
+        self
+    }
+
+    /// Record the discriminant of every enum variant.
+    pub fn with_discriminants(mut self, m: &Module) -> Self {
+        use strata_ast::ast::Item;
+        let table = m
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Enum(def) => {
+                    let values = strata_types::adt::enum_discriminants(def).ok()?;
+                    let variants = def.variants.iter().map(|v| v.name.text.clone());
+                    Some((def.name.text.clone(), variants.zip(values).collect()))
+                }
+                _ => None,
+            })
+            .collect();
+        self.discriminants = Arc::new(table);
+        self
+    }
+
+    /// Evaluate the module's consts and define them as immutable globals,
+    /// and its struct fields' default values.
+    pub fn with_consts(mut self, m: &Module) -> Result<Self> {
+        use strata_ast::ast::Item;
+        let consts = strata_types::consts::evaluate(m).map_err(|e| RuntimeErrorKind::Setup {
+            message: format!("const evaluation failed: {:?}", e),
+        })?;
+        for (name, value) in &consts {
+            self.define(name.clone(), const_value(value), false);
+        }
+        self.consts = Arc::new(consts);
+
+        let mut field_defaults = HashMap::new();
+        for item in &m.items {
+            let Item::Struct(def) = item else { continue };
+            if def.fields.iter().all(|f| f.default.is_none()) {
+                continue;
+            }
+            let values =
+                strata_types::consts::evaluate_field_defaults(m, &def.fields).map_err(|e| {
+                    RuntimeErrorKind::Setup {
+                        message: format!("field default evaluation failed: {:?}", e),
+                    }
+                })?;
+            let defaults = def
+                .fields
+                .iter()
+                .zip(values)
+                .filter_map(|(field, value)| Some((field.name.text.clone(), const_value(&value?))))
+                .collect();
+            field_defaults.insert(def.name.text.clone(), defaults);
+        }
+        self.field_defaults = Arc::new(field_defaults);
+        Ok(self)
+    }
+
+    /// The discriminant of `enum_name::variant_name`
+    fn discriminant_of(&self, enum_name: &str, variant_name: &str) -> Option<i64> {
+        self.discriminants
+            .get(enum_name)?
+            .iter()
+            .find(|(name, _)| name == variant_name)
+            .map(|(_, d)| *d)
+    }
+
+    /// Whether reading `value` must consume it
+    fn is_affine_value(&self, value: &Value) -> bool {
+        value.is_affine_in(&self.linear_types)
+    }
+
     /// Push a new scope onto the stack
     pub fn push_scope(&mut self) {
-        self.scopes.push(HashMap::new());
+        self.scopes.push(Arc::default());
+        self.count(|s| s.env_depth(self.scopes.len()));
     }
 
     /// Pop the current scope off the stack
     ///
     /// Returns an error if attempting to pop the global scope.
-    pub fn pop_scope(&mut self) -> anyhow::Result<()> {
+    pub fn pop_scope(&mut self) -> Result<()> {
         if self.scopes.len() <= 1 {
-            anyhow::bail!("internal error: attempted to pop global scope");
+            return Err(RuntimeErrorKind::Internal {
+                message: "attempted to pop global scope".to_string(),
+            }
+            .into());
         }
         self.scopes.pop();
         Ok(())
@@ -263,8 +826,60 @@ impl Env {
     /// Define a new variable in the current scope
     pub fn define(&mut self, name: String, value: Value, mutable: bool) {
         if let Some(scope) = self.scopes.last_mut() {
-            scope.insert(name, Binding { value, mutable });
+            Arc::make_mut(scope).insert(
+                name,
+                Binding {
+                    value,
+                    mutable,
+                    borrowed: false,
+                },
+            );
+        }
+    }
+
+    /// Define an immutable binding for a borrowed function argument.
+    /// Reads of a borrowed binding never consume the underlying value.
+    pub fn define_borrowed(&mut self, name: String, value: Value) {
+        if let Some(scope) = self.scopes.last_mut() {
+            Arc::make_mut(scope).insert(
+                name,
+                Binding {
+                    value,
+                    mutable: false,
+                    borrowed: true,
+                },
+            );
+        }
+    }
+
+    /// Whether the visible binding for `name` is a borrow
+    fn is_borrowed(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .is_some_and(|b| b.borrowed)
+    }
+
+    /// Variables bound outside the global scope and visible here, innermost
+    /// first, leaving out functions and shadowed bindings.
+    pub fn locals(&self) -> Vec<(&str, &Value)> {
+        let mut seen = HashSet::new();
+        let mut locals = Vec::new();
+        for scope in self.scopes.iter().skip(1).rev() {
+            let mut names: Vec<_> = scope.iter().collect();
+            names.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, binding) in names {
+                let is_fn = matches!(
+                    binding.value,
+                    Value::Closure { .. } | Value::Partial { .. } | Value::HostFn(_)
+                );
+                if seen.insert(name.as_str()) && !is_fn {
+                    locals.push((name.as_str(), &binding.value));
+                }
+            }
         }
+        locals
     }
 
     /// Look up a variable by name, searching from innermost to outermost scope
@@ -282,7 +897,7 @@ impl Env {
     /// This prevents the scope-pop resurrection exploit.
     pub fn move_out(&mut self, name: &str, span: Span) -> Option<Value> {
         for scope in self.scopes.iter_mut().rev() {
-            if let Some(binding) = scope.get_mut(name) {
+            if let Some(binding) = binding_mut(scope, name) {
                 let val = std::mem::replace(
                     &mut binding.value,
                     Value::Consumed {
@@ -299,23 +914,180 @@ impl Env {
     /// Set a variable's value, respecting mutability
     pub fn set(&mut self, name: &str, value: Value) -> Result<()> {
         for scope in self.scopes.iter_mut().rev() {
-            if let Some(binding) = scope.get_mut(name) {
+            if let Some(binding) = binding_mut(scope, name) {
                 if !binding.mutable {
-                    bail!("cannot assign to immutable variable `{}`", name);
+                    return Err(RuntimeErrorKind::AssignToImmutable {
+                        name: name.to_string(),
+                    }
+                    .into());
                 }
                 binding.value = value;
                 return Ok(());
             }
         }
-        bail!("undefined variable `{}`", name)
+        Err(RuntimeErrorKind::UndefinedVariable {
+            name: name.to_string(),
+        }
+        .into())
+    }
+
+    /// Save the variables in scope, for a notebook-style frontend to return
+    /// to with [`restore`](Self::restore) after evaluating a cell
+    /// speculatively. Cheap: the snapshot shares every scope with the
+    /// environment until the environment next changes it.
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot {
+            scopes: self.scopes.clone(),
+        }
+    }
+
+    /// Return to the variables saved in `snapshot`, forgetting bindings made
+    /// and undoing assignments since. A capability, task, or linear value
+    /// comes back only if its variable still holds a live one: what it was
+    /// moved out for can't be undone, so rolling back must not allow using
+    /// it again.
+    pub fn restore(&mut self, snapshot: &EnvSnapshot) {
+        let mut scopes = snapshot.scopes.clone();
+        for (depth, saved) in scopes.iter_mut().enumerate() {
+            let current = self.scopes.get(depth);
+            if current.is_some_and(|current| Arc::ptr_eq(saved, current)) {
+                continue;
+            }
+            let moved: Vec<(String, Value)> = saved
+                .iter()
+                .filter(|(_, binding)| self.is_affine_value(&binding.value))
+                .filter_map(|(name, _)| {
+                    let now = current.and_then(|scope| scope.get(name));
+                    match now.map(|b| &b.value) {
+                        Some(tombstone @ Value::Consumed { .. }) => {
+                            Some((name.clone(), tombstone.clone()))
+                        }
+                        Some(value) if self.is_affine_value(value) => None,
+                        // Moved, then shadowed or out of scope
+                        _ => Some((
+                            name.clone(),
+                            Value::Consumed {
+                                var_name: name.clone(),
+                                moved_at: Span::default(),
+                            },
+                        )),
+                    }
+                })
+                .collect();
+            for (name, tombstone) in moved {
+                if let Some(binding) = binding_mut(saved, &name) {
+                    binding.value = tombstone;
+                }
+            }
+        }
+        self.scopes = scopes;
+    }
+}
+
+/// Variables of an [`Env`], saved by [`Env::snapshot`].
+#[derive(Debug, Clone)]
+pub struct EnvSnapshot {
+    scopes: Vec<Arc<HashMap<String, Binding>>>,
+}
+
+/// `name`'s binding in `scope`, copying the scope first if it is shared.
+/// A scope without the name is left shared.
+fn binding_mut<'a>(
+    scope: &'a mut Arc<HashMap<String, Binding>>,
+    name: &str,
+) -> Option<&'a mut Binding> {
+    if !scope.contains_key(name) {
+        return None;
+    }
+    Arc::make_mut(scope).get_mut(name)
+}
+
+/// Add `m`'s extern fns, functions, and `let` globals to `env`'s current
+/// scope, as a notebook cell adds to the cells before it. Functions see
+/// each other and everything `env` already holds; `let`s are evaluated in
+/// order. The caller sets `env` up for `m` beforehand.
+pub fn define_items(env: &mut Env, m: &Module) -> Result<()> {
+    use strata_ast::ast::Item;
+
+    for item in &m.items {
+        if let Item::ExternFn(decl) = item {
+            env.define(
+                decl.name.text.clone(),
+                Value::HostFn(decl.name.text.clone()),
+                false,
+            );
+        }
+    }
+
+    let fn_decls: Vec<_> = m
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(decl) => Some(decl),
+            _ => None,
+        })
+        .collect();
+    for decl in &fn_decls {
+        env.define(decl.name.text.clone(), Value::Unit, true);
+    }
+    // Twice, so each closure captures the others rather than placeholders
+    for _ in 0..2 {
+        for decl in &fn_decls {
+            let closure = Value::Closure {
+                params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
+                body: decl.body.clone(),
+                env: env.clone(),
+                local: None,
+            };
+            env.set(&decl.name.text, closure).ok();
+        }
+    }
+
+    for item in &m.items {
+        if let Item::Let(ld) = item {
+            let v = eval_expr(env, &ld.value)?.into_value();
+            env.define(ld.name.text.clone(), v, false);
+        }
     }
+    Ok(())
+}
+
+/// An environment holding `m`'s items, for calling its functions one at a
+/// time as `strata bench` and `strata test` do. Host calls are refused as
+/// `policy` says. `instrument` adds counters and limits (`with_stats`,
+/// `with_coverage`, ...) before the items are defined, so that calls into
+/// them see these too.
+pub fn module_env(
+    m: &Module,
+    policy: EffectPolicy,
+    instrument: impl FnOnce(Env) -> Env,
+) -> Result<Env> {
+    let expanded = expand_module(m)?;
+    let m: &Module = &expanded;
+    let mut registry = HostRegistry::new().with_policy(policy);
+    register_extern_metas(&mut registry, m);
+    let mut env = Env::with_host_registry(Arc::new(registry))
+        .with_linear_types(m)
+        .with_resolution(m)
+        .with_discriminants(m)
+        .with_consts(m)?;
+    env = instrument(env);
+    define_items(&mut env, m)?;
+    Ok(env)
 }
 
 /// Evaluate an entire module
 pub fn eval_module(m: &Module) -> Result<()> {
     use strata_ast::ast::Item;
 
-    let mut env = Env::new();
+    let expanded = expand_module(m)?;
+    let m: &Module = &expanded;
+
+    let mut env = Env::new()
+        .with_linear_types(m)
+        .with_resolution(m)
+        .with_discriminants(m)
+        .with_consts(m)?;
 
     // Collect function declarations
     let fn_decls: Vec<_> = m
@@ -357,6 +1129,7 @@ pub fn eval_module(m: &Module) -> Result<()> {
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -368,6 +1141,7 @@ pub fn eval_module(m: &Module) -> Result<()> {
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -418,68 +1192,162 @@ fn extract_cap_type_name(ty: &strata_ast::ast::TypeExpr) -> Option<String> {
 /// This is the primary entry point for programs that use capabilities.
 /// No trace output is produced.
 pub fn run_module(m: &Module) -> Result<Value> {
-    run_module_inner(m, None, false)
+    run_module_with(m, RunOptions::default())
 }
 
 /// Run a module with host function dispatch, capability injection, and
 /// JSONL trace output written to the provided writer.
 /// Values > 1KB are hashed (not suitable for replay).
 pub fn run_module_traced(m: &Module, writer: Box<dyn std::io::Write + Send>) -> Result<Value> {
-    run_module_inner(m, Some(writer), false)
+    run_module_with(
+        m,
+        RunOptions {
+            trace: Some(writer),
+            ..RunOptions::default()
+        },
+    )
 }
 
 /// Run a module with full trace output (all values recorded, no hashing).
 /// The resulting trace is suitable for deterministic replay.
 pub fn run_module_traced_full(m: &Module, writer: Box<dyn std::io::Write + Send>) -> Result<Value> {
-    run_module_inner(m, Some(writer), true)
+    run_module_with(
+        m,
+        RunOptions {
+            trace: Some(writer),
+            full_values: true,
+            ..RunOptions::default()
+        },
+    )
 }
 
-fn run_module_inner(
-    m: &Module,
-    trace_writer: Option<Box<dyn std::io::Write + Send>>,
-    full_values: bool,
-) -> Result<Value> {
-    use strata_ast::ast::Item;
+/// Hooks an embedder installs (via [`RunOptions::observer`]) to watch a run
+/// for metrics, logging, or policy, without changing the evaluator. Every
+/// method defaults to doing nothing.
+pub trait RuntimeObserver: Send + Sync {
+    /// A Strata function is about to run with `args`.
+    fn on_call(&self, _name: &str, _args: &[Value]) {}
+
+    /// A host function is about to run with `args` (capabilities included).
+    /// Returning `Err` refuses the call, which then fails with that message
+    /// without being dispatched or traced.
+    fn on_host_call(&self, _name: &str, _args: &[Value]) -> Result<(), String> {
+        Ok(())
+    }
 
-    let mut registry = HostRegistry::new();
+    /// The run failed with `error`.
+    fn on_error(&self, _error: &RuntimeError) {}
+}
 
-    // Build ExternFnMeta from extern fn declarations and register host fn refs
-    for item in &m.items {
-        if let Item::ExternFn(decl) = item {
-            let mut params = Vec::new();
-            for param in &decl.params {
-                if let Some(ty_expr) = &param.ty {
-                    let (is_ref, cap_name) = extract_cap_info(ty_expr);
-                    if let Some(name) = cap_name {
-                        if let Some(kind) = CapKind::from_name(&name) {
-                            params.push(ParamKind::Cap {
-                                kind,
-                                borrowed: is_ref,
-                            });
-                            continue;
-                        }
-                    }
-                }
-                params.push(ParamKind::Data {
-                    name: param.name.text.clone(),
-                });
-            }
-            registry.register_extern_meta(&decl.name.text, ExternFnMeta { params });
-        }
+impl std::fmt::Debug for dyn RuntimeObserver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RuntimeObserver")
     }
+}
+
+/// How [`run_module_with`] runs a program.
+#[derive(Default)]
+pub struct RunOptions {
+    /// Command-line arguments, recorded in the trace header
+    pub args: Vec<String>,
+    /// Effects refused at host dispatch
+    pub policy: EffectPolicy,
+    /// Capabilities granted to `main`, and their settings
+    pub caps: CapsConfig,
+    /// Where to record capability-gated host calls, if anywhere
+    pub audit: Option<AuditLog>,
+    /// Where to write the JSONL effect trace, if anywhere
+    pub trace: Option<Box<dyn std::io::Write + Send>>,
+    /// Record all values in the trace (replay-capable) rather than hashing
+    /// those > 1KB
+    pub full_values: bool,
+    /// What the trace header records the run was made from
+    pub trace_meta: TraceMeta,
+    /// Keys to sign and encrypt the trace with
+    pub trace_seal: SealKeys,
+    /// Hooks notified of calls and failure
+    pub observer: Option<Arc<dyn RuntimeObserver>>,
+    /// Where to accumulate per-function timings, if anywhere
+    pub profiler: Option<Arc<Mutex<Profiler>>>,
+    /// Debugger to stop at breakpoints and steps, if any
+    pub debugger: Option<Arc<Mutex<Debugger>>>,
+    /// Where to count evaluator work, if anywhere
+    pub stats: Option<Arc<StatsRecorder>>,
+}
+
+/// Run a module with host function dispatch and main() capability
+/// injection, as configured by `opts`.
+pub fn run_module_with(m: &Module, opts: RunOptions) -> Result<Value> {
+    let observer = opts.observer.clone();
+    let result = run_module_inner(m, opts);
+    if let (Err(e), Some(observer)) = (&result, &observer) {
+        observer.on_error(e);
+    }
+    result
+}
+
+fn run_module_inner(m: &Module, opts: RunOptions) -> Result<Value> {
+    use strata_ast::ast::Item;
+
+    let RunOptions {
+        args,
+        policy,
+        caps,
+        audit,
+        trace,
+        full_values,
+        trace_meta,
+        trace_seal,
+        observer,
+        profiler,
+        debugger,
+        stats,
+    } = opts;
+    let started = Instant::now();
+    let expanded = expand_module(m)?;
+    let m: &Module = &expanded;
+
+    // Capabilities the caps config doesn't grant are denied at dispatch too
+    let mut registry = HostRegistry::new()
+        .with_policy(policy.union(caps.policy()))
+        .with_caps(caps.clone());
+    let audit = audit.map(|log| Arc::new(Mutex::new(log)));
+    if let Some(audit) = &audit {
+        registry = registry.with_audit_log(audit.clone());
+    }
+
+    // Build ExternFnMeta from extern fn declarations and register host fn refs
+    register_extern_metas(&mut registry, m);
 
     let registry = Arc::new(registry);
 
-    let tracer = trace_writer
-        .map(|w| TraceEmitter::new(w, full_values))
+    let tracer = trace
+        .map(|w| TraceEmitter::new(w, full_values, &args, &trace_meta, trace_seal))
         .transpose()
-        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .map_err(io_error)?
         .map(|t| Arc::new(Mutex::new(t)));
 
-    let mut env = Env::with_host_registry(registry);
+    let mut env = Env::with_host_registry(registry)
+        .with_args(&args)
+        .with_linear_types(m)
+        .with_resolution(m)
+        .with_discriminants(m)
+        .with_consts(m)?;
     if let Some(t) = tracer {
         env = env.with_tracer(t);
     }
+    if let Some(observer) = observer {
+        env = env.with_observer(observer);
+    }
+    if let Some(profiler) = profiler {
+        env = env.with_profiler(profiler);
+    }
+    if let Some(debugger) = debugger {
+        env = env.with_debugger(debugger);
+    }
+    if let Some(stats) = &stats {
+        env = env.with_stats(stats.clone());
+    }
 
     // Register extern fns as host function references
     for item in &m.items {
@@ -516,6 +1384,7 @@ fn run_module_inner(
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -526,6 +1395,7 @@ fn run_module_inner(
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -563,24 +1433,42 @@ fn run_module_inner(
         if let Some(ty_expr) = &param.ty {
             if let Some(name) = extract_cap_type_name(ty_expr) {
                 if let Some(kind) = CapKind::from_name(&name) {
+                    if !caps.grants(kind) {
+                        let kind = RuntimeErrorKind::Setup {
+                            message: format!(
+                                "main requires {}, which the caps config does not grant",
+                                name
+                            ),
+                        };
+                        return Err(RuntimeError::new(kind).at(ty_expr.span()));
+                    }
                     cap_args.push(Value::Cap(kind));
                 }
             }
         }
     }
 
+    if let Some(audit) = &audit {
+        audit.lock().unwrap().start().map_err(io_error)?;
+    }
+
     // Call main with cap args
     let main_val = env
         .get("main")
-        .ok_or_else(|| anyhow::anyhow!("main function not found"))?
+        .ok_or_else(|| setup_error("main function not found"))?
         .clone();
 
     if let Value::Closure {
         params,
         body,
         env: closure_env,
+        ..
     } = main_val
     {
+        if let Some(observer) = &env.observer {
+            observer.on_call("main", &cap_args);
+        }
+        env.count(StatsRecorder::call);
         let mut call_env = closure_env;
         call_env.push_scope();
 
@@ -589,24 +1477,40 @@ fn run_module_inner(
             call_env.define(param.clone(), value, false);
         }
 
-        let result = eval_block(&mut call_env, &body);
+        env.profile_enter("main", false);
+        env.debug_enter("main");
+        let result = eval_block(&mut call_env, &body).map_err(|e| e.in_fn("main"));
+        env.debug_exit();
+        env.profile_exit();
         call_env.pop_scope()?;
 
+        // The run ends once every task has, failing if main or a task did
+        let tasks = finish_tasks(&env);
+        let result = result.and_then(|cf| tasks.map(|()| cf));
+        env.count(|s| s.finish(started.elapsed()));
+
         // Finalize the trace (write footer) regardless of success/error.
         // If program succeeded but finalize fails, propagate the write error.
         // If program already errored, prioritize the program error.
+        let result = result.map(ControlFlow::into_value);
         let program_status = if result.is_ok() { "success" } else { "error" };
         if let Some(tracer) = &env.tracer {
             let mut t = tracer.lock().unwrap();
-            let fin = t.finalize(program_status);
+            let fin = t.finalize(program_status, result.as_ref().ok());
+            if result.is_ok() {
+                fin.map_err(io_error)?;
+            }
+        }
+        if let Some(audit) = &audit {
+            let fin = audit.lock().unwrap().end(program_status);
             if result.is_ok() {
-                fin.map_err(|e| anyhow::anyhow!("{}", e))?;
+                fin.map_err(io_error)?;
             }
         }
 
-        Ok(result?.into_value())
+        result
     } else {
-        bail!("main is not a function")
+        Err(setup_error("main is not a function"))
     }
 }
 
@@ -625,43 +1529,113 @@ fn extract_cap_info(ty: &strata_ast::ast::TypeExpr) -> (bool, Option<String>) {
     }
 }
 
-/// Run a module in replay mode, substituting recorded trace outputs
-/// instead of calling real host functions.
-pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
+/// The runtime value of a const (or parameter default)
+fn const_value(value: &ConstValue) -> Value {
+    match value {
+        ConstValue::Int(n) => Value::Int(*n),
+        ConstValue::Float(x) => Value::Float(*x),
+        ConstValue::Bool(b) => Value::Bool(*b),
+        ConstValue::Str(s) => Value::Str(s.clone()),
+    }
+}
+
+/// Register position-aware metadata for each of the module's extern fns,
+/// from their declared parameter and return types and `#[redact]` attributes
+fn register_extern_metas(registry: &mut HostRegistry, m: &Module) {
     use strata_ast::ast::Item;
 
-    let replayer = TraceReplayer::from_jsonl(trace_jsonl).map_err(|e| anyhow::anyhow!("{}", e))?;
-    let replayer = Arc::new(Mutex::new(replayer));
+    let transparent: HashSet<&str> = m
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(def) if def.transparent => Some(def.name.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let names_transparent = |ty: &strata_ast::ast::TypeExpr| match extract_cap_info(ty) {
+        (false, Some(name)) if transparent.contains(name.as_str()) => Some(name),
+        _ => None,
+    };
 
-    // We still need a registry for ExternFnMeta (position-aware input building),
-    // but we won't call any real host functions.
-    let mut registry = HostRegistry::new();
     for item in &m.items {
-        if let Item::ExternFn(decl) = item {
-            let mut params = Vec::new();
-            for param in &decl.params {
-                if let Some(ty_expr) = &param.ty {
-                    let (is_ref, cap_name) = extract_cap_info(ty_expr);
-                    if let Some(name) = cap_name {
-                        if let Some(kind) = CapKind::from_name(&name) {
-                            params.push(ParamKind::Cap {
-                                kind,
-                                borrowed: is_ref,
-                            });
-                            continue;
-                        }
+        let Item::ExternFn(decl) = item else {
+            continue;
+        };
+        let redaction = strata_types::attrs::redaction(decl);
+        let mode = match redaction.as_ref().is_some_and(|r| r.mask) {
+            true => RedactMode::Mask,
+            false => RedactMode::Hash,
+        };
+        let mut params = Vec::new();
+        for param in &decl.params {
+            if let Some(ty_expr) = &param.ty {
+                let (is_ref, cap_name) = extract_cap_info(ty_expr);
+                if let Some(name) = cap_name {
+                    if let Some(kind) = CapKind::from_name(&name) {
+                        params.push(ParamKind::Cap {
+                            kind,
+                            borrowed: is_ref,
+                        });
+                        continue;
                     }
                 }
-                params.push(ParamKind::Data {
-                    name: param.name.text.clone(),
-                });
             }
-            registry.register_extern_meta(&decl.name.text, ExternFnMeta { params });
+            let redacted = redaction
+                .as_ref()
+                .is_some_and(|r| r.params.contains(&param.name.text));
+            params.push(ParamKind::Data {
+                name: param.name.text.clone(),
+                transparent: param.ty.as_ref().and_then(names_transparent).is_some(),
+                redact: redacted.then_some(mode),
+                variadic: param.variadic,
+            });
         }
+        let meta = ExternFnMeta {
+            params,
+            returns_transparent: decl.ret_ty.as_ref().and_then(names_transparent),
+            redact_output: redaction.as_ref().is_some_and(|r| r.output).then_some(mode),
+            // The checker has evaluated the defaults already
+            defaults: strata_types::consts::evaluate_defaults(m, &decl.params)
+                .unwrap_or_default()
+                .iter()
+                .flatten()
+                .map(const_value)
+                .collect(),
+        };
+        registry.register_extern_meta(&decl.name.text, meta);
     }
+}
+
+/// Run a module in replay mode, substituting recorded trace outputs
+/// instead of calling real host functions.
+pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
+    let replayer = TraceReplayer::from_jsonl(trace_jsonl).map_err(replay_error)?;
+    run_module_replay_from(m, Arc::new(Mutex::new(replayer)))
+}
+
+/// Run a module in replay mode against `replayer`, which may be reading
+/// its trace as replay goes. The caller keeps it to see what was replayed.
+pub fn run_module_replay_from(m: &Module, replayer: Arc<Mutex<TraceReplayer>>) -> Result<Value> {
+    use strata_ast::ast::Item;
+
+    let expanded = expand_module(m)?;
+    let m: &Module = &expanded;
+
+    let args = replayer.lock().unwrap().args().to_vec();
+
+    // We still need a registry for ExternFnMeta (position-aware input building),
+    // but we won't call any real host functions.
+    let mut registry = HostRegistry::new();
+    register_extern_metas(&mut registry, m);
     let registry = Arc::new(registry);
 
-    let mut env = Env::with_host_registry(registry).with_replayer(replayer.clone());
+    let mut env = Env::with_host_registry(registry)
+        .with_replayer(replayer.clone())
+        .with_args(&args)
+        .with_linear_types(m)
+        .with_resolution(m)
+        .with_discriminants(m)
+        .with_consts(m)?;
 
     // Register extern fns as host function references
     for item in &m.items {
@@ -695,6 +1669,7 @@ pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -703,6 +1678,7 @@ pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
             params: decl.params.iter().map(|p| p.name.text.clone()).collect(),
             body: decl.body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set(&decl.name.text, closure).ok();
     }
@@ -746,13 +1722,14 @@ pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
 
     let main_val = env
         .get("main")
-        .ok_or_else(|| anyhow::anyhow!("main function not found"))?
+        .ok_or_else(|| setup_error("main function not found"))?
         .clone();
 
     let result = if let Value::Closure {
         params,
         body,
         env: closure_env,
+        ..
     } = main_val
     {
         let mut call_env = closure_env;
@@ -760,16 +1737,19 @@ pub fn run_module_replay(m: &Module, trace_jsonl: &str) -> Result<Value> {
         for (param, value) in params.iter().zip(cap_args) {
             call_env.define(param.clone(), value, false);
         }
-        let result = eval_block(&mut call_env, &body)?;
+        let result = eval_block(&mut call_env, &body).map_err(|e| e.in_fn("main"));
+        let tasks = finish_tasks(&env);
+        let result = result?;
+        tasks?;
         call_env.pop_scope()?;
         result.into_value()
     } else {
-        bail!("main is not a function")
+        return Err(setup_error("main is not a function"));
     };
 
     // Verify all trace entries were consumed
-    let r = replayer.lock().unwrap();
-    r.verify_complete().map_err(|e| anyhow::anyhow!("{}", e))?;
+    let mut r = replayer.lock().unwrap();
+    r.verify_complete().map_err(replay_error)?;
 
     Ok(result)
 }
@@ -785,9 +1765,13 @@ fn build_replay_inputs(
         if let Some(meta) = registry.get_extern_meta(name) {
             let mut inputs = std::collections::BTreeMap::new();
             for (i, param) in meta.params.iter().enumerate() {
-                if let ParamKind::Data { name } = param {
+                if let ParamKind::Data { name, variadic, .. } = param {
                     if let Some(val) = all_args.get(i) {
-                        inputs.insert(name.clone(), TraceValue::from_value(val));
+                        let tv = match variadic {
+                            true => TraceValue::from_variadic(val),
+                            false => TraceValue::from_value(val),
+                        };
+                        inputs.insert(name.clone(), tv);
                     }
                 }
             }
@@ -806,32 +1790,34 @@ fn check_not_consumed(val: &Value, _var_name: &str, use_span: Span) -> Result<()
         moved_at,
     } = val
     {
-        bail!(
-            "error[CAP-MOVE-RUNTIME]: capability '{}' has already been used\n  \
-             -> used at: {}:{}\n  \
-             -> previously transferred at: {}:{}\n\
-             \n  \
-             note: this should have been rejected at compile time. This is a Strata bug.\n  \
-             Please report at: https://github.com/strata-lang/strata/issues\n  \
-             Include your source file and `strata --version` output.",
-            orig_name,
-            use_span.start,
-            use_span.end,
-            moved_at.start,
-            moved_at.end,
-        );
+        let kind = RuntimeErrorKind::CapabilityReused {
+            name: orig_name.clone(),
+            moved_at: *moved_at,
+        };
+        return Err(RuntimeError::new(kind).at(use_span));
     }
     Ok(())
 }
 
-/// Evaluate an expression
+/// Evaluate an expression. An error raised here without a location of
+/// its own is located at `expr`, so errors point at the innermost
+/// expression that failed.
 pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
+    env.count(StatsRecorder::expr);
+    env.step().map_err(|e| e.at(expr.span()))?;
+    eval_expr_inner(env, expr).map_err(|e| e.at(expr.span()))
+}
+
+fn eval_expr_inner(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
     match expr {
         // Literals
         Expr::Lit(Lit::Int(v), _) => Ok(ControlFlow::Value(Value::Int(*v))),
         Expr::Lit(Lit::Float(v), _) => Ok(ControlFlow::Value(Value::Float(*v))),
         Expr::Lit(Lit::Bool(b), _) => Ok(ControlFlow::Value(Value::Bool(*b))),
-        Expr::Lit(Lit::Str(s), _) => Ok(ControlFlow::Value(Value::Str(s.clone()))),
+        Expr::Lit(Lit::Str(s), _) => {
+            env.count(StatsRecorder::value);
+            Ok(ControlFlow::Value(Value::Str(s.clone())))
+        }
         Expr::Lit(Lit::Nil, _) => Ok(ControlFlow::Value(Value::Unit)),
 
         // Variable lookup — affine values are destructively read (tombstoned)
@@ -840,18 +1826,15 @@ pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
             let is_affine = match env.get(&id.text) {
                 Some(v) => {
                     check_not_consumed(v, &id.text, id.span)?;
-                    v.is_affine()
+                    env.is_affine_value(v) && !env.is_borrowed(&id.text)
                 }
-                None => bail!("undefined variable `{}`", id.text),
+                None => return Err(undefined(&id.text)),
             };
             if is_affine {
                 // Destructive read: take value out, leave tombstone
-                let val = env.move_out(&id.text, id.span).ok_or_else(|| {
-                    anyhow::anyhow!(
-                        "internal error: move_out failed for `{}` — binding not found in any scope",
-                        id.text
-                    )
-                })?;
+                let val = env
+                    .move_out(&id.text, id.span)
+                    .ok_or_else(|| move_out_failed(&id.text))?;
                 Ok(ControlFlow::Value(val))
             } else {
                 Ok(ControlFlow::Value(env.get(&id.text).unwrap().clone()))
@@ -870,10 +1853,10 @@ pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
             let v = cf.into_value();
             match (op, v) {
                 (UnOp::Not, Value::Bool(b)) => Ok(ControlFlow::Value(Value::Bool(!b))),
-                (UnOp::Neg, Value::Int(i)) => Ok(ControlFlow::Value(Value::Int(-i))),
+                (UnOp::Neg, Value::Int(i)) => int_neg(i).map(ControlFlow::Value),
                 (UnOp::Neg, Value::Float(f)) => Ok(ControlFlow::Value(Value::Float(-f))),
-                (UnOp::Not, _) => bail!("`!` expects Bool"),
-                (UnOp::Neg, _) => bail!("unary `-` expects Int or Float"),
+                (UnOp::Not, _) => mistyped!(at expr.span(); "`!` expects Bool"),
+                (UnOp::Neg, _) => mistyped!(at expr.span(); "unary `-` expects Int or Float"),
             }
         }
 
@@ -883,21 +1866,37 @@ pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
         // Function call
         Expr::Call { callee, args, .. } => eval_call(env, callee, args),
 
+        // Holes are call arguments, which a partial application takes in
+        Expr::Hole(span) => mistyped!(at *span; "`_` outside a call's arguments"),
+
         // Block expression
         Expr::Block(block) => eval_block(env, block),
 
+        // Task
+        Expr::Spawn { body, .. } => eval_spawn(env, body),
+
         // If expression
         Expr::If {
-            cond, then_, else_, ..
-        } => eval_if(env, cond, then_, else_.as_deref()),
+            cond,
+            then_,
+            else_,
+            span,
+        } => eval_if(env, cond, then_, else_.as_deref(), *span),
 
         // While loop
         Expr::While { cond, body, .. } => eval_while(env, cond, body),
 
+        // For loop
+        Expr::For {
+            pat, iter, body, ..
+        } => eval_for(env, pat, iter, body),
+
         // Match expression
         Expr::Match {
-            scrutinee, arms, ..
-        } => eval_match(env, scrutinee, arms),
+            scrutinee,
+            arms,
+            span,
+        } => eval_match(env, scrutinee, arms, *span),
 
         // Tuple expression
         Expr::Tuple { elems, .. } => eval_tuple(env, elems),
@@ -905,6 +1904,24 @@ pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
         // Struct expression
         Expr::StructExpr { path, fields, .. } => eval_struct_expr(env, path, fields),
 
+        // Record expression and field access
+        Expr::Record { fields, .. } => eval_record(env, fields),
+        Expr::Field { expr, field, .. } => {
+            let cf = eval_expr(env, expr)?;
+            if cf.is_return() {
+                return Ok(cf);
+            }
+            match cf.into_value() {
+                Value::Record(fields) => match fields.get(&field.text) {
+                    Some(value) => Ok(ControlFlow::Value(value.clone())),
+                    None => mistyped!(at field.span; "record has no field `{}`", field.text),
+                },
+                other => {
+                    mistyped!(at field.span; "cannot read field `{}` of {}", field.text, other)
+                }
+            }
+        }
+
         // Path expression (enum constructor)
         Expr::PathExpr(path) => eval_path_expr(env, path),
 
@@ -917,13 +1934,35 @@ pub fn eval_expr(env: &mut Env, expr: &Expr) -> Result<ControlFlow> {
                     check_not_consumed(v, &id.text, id.span)?;
                     Ok(ControlFlow::Value(v.clone()))
                 }
-                None => bail!("undefined variable `{}`", id.text),
+                None => Err(undefined(&id.text)),
             },
             _ => eval_expr(env, inner),
         },
     }
 }
 
+/// `a op b` for one of the arithmetic operators, failing where the result
+/// leaves the `Int` range rather than wrapping
+fn int_arith(op: &BinOp, a: i64, b: i64) -> Result<Value> {
+    let (result, name) = match op {
+        BinOp::Add => (a.checked_add(b), "+"),
+        BinOp::Sub => (a.checked_sub(b), "-"),
+        BinOp::Mul => (a.checked_mul(b), "*"),
+        _ if b == 0 => return Err(RuntimeErrorKind::DivisionByZero.into()),
+        _ => (a.checked_div(b), "/"),
+    };
+    result
+        .map(Value::Int)
+        .ok_or_else(|| RuntimeErrorKind::IntegerOverflow { op: name }.into())
+}
+
+/// `-i`, failing for the one `Int` whose negation is out of range
+fn int_neg(i: i64) -> Result<Value> {
+    i.checked_neg()
+        .map(Value::Int)
+        .ok_or_else(|| RuntimeErrorKind::IntegerOverflow { op: "-" }.into())
+}
+
 /// Evaluate a binary operation
 fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<ControlFlow> {
     use BinOp::*;
@@ -944,10 +1983,10 @@ fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<Cont
                     }
                     match cf.into_value() {
                         Value::Bool(b) => return Ok(ControlFlow::Value(Value::Bool(b))),
-                        _ => bail!("&& expects Bool"),
+                        _ => mistyped!(at rhs.span(); "&& expects Bool"),
                     }
                 }
-                _ => bail!("&& expects Bool"),
+                _ => mistyped!(at lhs.span(); "&& expects Bool"),
             }
         }
         Or => {
@@ -964,10 +2003,10 @@ fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<Cont
                     }
                     match cf.into_value() {
                         Value::Bool(b) => return Ok(ControlFlow::Value(Value::Bool(b))),
-                        _ => bail!("|| expects Bool"),
+                        _ => mistyped!(at rhs.span(); "|| expects Bool"),
                     }
                 }
-                _ => bail!("|| expects Bool"),
+                _ => mistyped!(at lhs.span(); "|| expects Bool"),
             }
         }
         _ => {}
@@ -989,10 +2028,7 @@ fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<Cont
     match op {
         Add | Sub | Mul | Div => {
             let result = match (l, r, op) {
-                (Value::Int(a), Value::Int(b), Add) => Value::Int(a + b),
-                (Value::Int(a), Value::Int(b), Sub) => Value::Int(a - b),
-                (Value::Int(a), Value::Int(b), Mul) => Value::Int(a * b),
-                (Value::Int(a), Value::Int(b), Div) => Value::Int(a / b),
+                (Value::Int(a), Value::Int(b), _) => int_arith(op, a, b)?,
 
                 (Value::Int(a), Value::Float(b), Add) => Value::Float((a as f64) + b),
                 (Value::Int(a), Value::Float(b), Sub) => Value::Float((a as f64) - b),
@@ -1009,49 +2045,24 @@ fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<Cont
                 (Value::Float(a), Value::Float(b), Mul) => Value::Float(a * b),
                 (Value::Float(a), Value::Float(b), Div) => Value::Float(a / b),
 
-                _ => bail!("arithmetic expects Int/Float"),
+                _ => mistyped!("arithmetic expects Int/Float"),
             };
             Ok(ControlFlow::Value(result))
         }
 
         Lt | Le | Gt | Ge => {
-            let result = match (l, r, op) {
-                (Value::Int(a), Value::Int(b), Lt) => a < b,
-                (Value::Int(a), Value::Int(b), Le) => a <= b,
-                (Value::Int(a), Value::Int(b), Gt) => a > b,
-                (Value::Int(a), Value::Int(b), Ge) => a >= b,
-
-                (Value::Float(a), Value::Float(b), Lt) => a < b,
-                (Value::Float(a), Value::Float(b), Le) => a <= b,
-                (Value::Float(a), Value::Float(b), Gt) => a > b,
-                (Value::Float(a), Value::Float(b), Ge) => a >= b,
-
-                (Value::Int(a), Value::Float(b), Lt) => (a as f64) < b,
-                (Value::Int(a), Value::Float(b), Le) => (a as f64) <= b,
-                (Value::Int(a), Value::Float(b), Gt) => (a as f64) > b,
-                (Value::Int(a), Value::Float(b), Ge) => (a as f64) >= b,
-
-                (Value::Float(a), Value::Int(b), Lt) => a < (b as f64),
-                (Value::Float(a), Value::Int(b), Le) => a <= (b as f64),
-                (Value::Float(a), Value::Int(b), Gt) => a > (b as f64),
-                (Value::Float(a), Value::Int(b), Ge) => a >= (b as f64),
-
-                _ => bail!("relational ops expect numbers"),
-            };
+            // Unordered (a NaN somewhere) makes every comparison false
+            let result = compare_values(&l, &r)?.is_some_and(|o| match op {
+                Lt => o.is_lt(),
+                Le => o.is_le(),
+                Gt => o.is_gt(),
+                _ => o.is_ge(),
+            });
             Ok(ControlFlow::Value(Value::Bool(result)))
         }
 
         Eq | Ne => {
-            let eq = match (l, r) {
-                (Value::Int(a), Value::Int(b)) => a == b,
-                (Value::Float(a), Value::Float(b)) => a == b,
-                (Value::Int(a), Value::Float(b)) => (a as f64) == b,
-                (Value::Float(a), Value::Int(b)) => a == (b as f64),
-                (Value::Bool(a), Value::Bool(b)) => a == b,
-                (Value::Str(a), Value::Str(b)) => a == b,
-                (Value::Unit, Value::Unit) => true,
-                _ => false,
-            };
+            let eq = values_equal(&l, &r)?;
             Ok(ControlFlow::Value(Value::Bool(if matches!(op, Eq) {
                 eq
             } else {
@@ -1063,12 +2074,151 @@ fn eval_binary(env: &mut Env, op: &BinOp, lhs: &Expr, rhs: &Expr) -> Result<Cont
     }
 }
 
+/// Structural equality for `==`: primitives by value (Int and Float
+/// compare numerically), tuples element by element, structs and records
+/// field by field, and enum values by variant and fields.
+fn values_equal(a: &Value, b: &Value) -> Result<bool> {
+    Ok(match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::Int(a), Value::Float(b)) => (*a as f64) == *b,
+        (Value::Float(a), Value::Int(b)) => *a == (*b as f64),
+        (Value::Bool(a), Value::Bool(b)) => a == b,
+        (Value::Str(a), Value::Str(b)) => a == b,
+        (Value::Unit, Value::Unit) => true,
+        (Value::Tuple(a), Value::Tuple(b)) => all_equal(a, b)?,
+        (Value::Record(a), Value::Record(b)) => {
+            if !a.keys().eq(b.keys()) {
+                return Ok(false);
+            }
+            for (a, b) in a.values().zip(b.values()) {
+                if !values_equal(a, b)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        (
+            Value::Struct { name, fields },
+            Value::Struct {
+                name: other_name,
+                fields: other_fields,
+            },
+        ) => {
+            if name != other_name || fields.len() != other_fields.len() {
+                return Ok(false);
+            }
+            for (field, value) in fields {
+                match other_fields.get(field) {
+                    Some(other) if values_equal(value, other)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            true
+        }
+        (
+            Value::Variant {
+                enum_name,
+                variant_name,
+                fields,
+            },
+            Value::Variant {
+                enum_name: other_enum,
+                variant_name: other_variant,
+                fields: other_fields,
+            },
+        ) => {
+            enum_name == other_enum
+                && variant_name == other_variant
+                && all_equal(fields, other_fields)?
+        }
+        (
+            Value::Closure { .. }
+            | Value::Partial { .. }
+            | Value::Cap(_)
+            | Value::HostFn(_)
+            | Value::Task(_)
+            | Value::Iter(_)
+            | Value::Consumed { .. },
+            _,
+        )
+        | (
+            _,
+            Value::Closure { .. }
+            | Value::Partial { .. }
+            | Value::Cap(_)
+            | Value::HostFn(_)
+            | Value::Task(_)
+            | Value::Iter(_)
+            | Value::Consumed { .. },
+        ) => {
+            return Err(RuntimeErrorKind::NotComparable {
+                message: format!(
+                    "cannot compare {} and {} with `==` (should be caught by type checker)",
+                    a, b
+                ),
+            }
+            .into())
+        }
+        _ => false,
+    })
+}
+
+fn all_equal(a: &[Value], b: &[Value]) -> Result<bool> {
+    if a.len() != b.len() {
+        return Ok(false);
+    }
+    for (a, b) in a.iter().zip(b) {
+        if !values_equal(a, b)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Ordering for `<` and friends: numbers numerically, strings
+/// lexicographically, and tuples element by element. `None` when the
+/// values are unordered (a NaN).
+fn compare_values(a: &Value, b: &Value) -> Result<Option<std::cmp::Ordering>> {
+    Ok(match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Tuple(a), Value::Tuple(b)) if a.len() == b.len() => {
+            for (a, b) in a.iter().zip(b.iter()) {
+                match compare_values(a, b)? {
+                    Some(std::cmp::Ordering::Equal) => {}
+                    other => return Ok(other),
+                }
+            }
+            Some(std::cmp::Ordering::Equal)
+        }
+        _ => {
+            return Err(RuntimeErrorKind::NotComparable {
+                message: format!(
+                    "cannot order {} and {} (should be caught by type checker)",
+                    a, b
+                ),
+            }
+            .into())
+        }
+    })
+}
+
 /// Evaluate a block expression
 pub fn eval_block(env: &mut Env, block: &Block) -> Result<ControlFlow> {
     env.with_scope(|env| {
-        // Evaluate each statement
-        for stmt in &block.stmts {
-            let cf = eval_stmt(env, stmt)?;
+        // Evaluate each statement, defining each group of nested functions
+        // together where it starts
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            define_local_fns(env, &block.fn_group_at(i));
+            if !matches!(stmt, Stmt::Fn(_)) {
+                env.debug_stmt(stmt.span())?;
+                env.cover(|c| c.stmt(stmt.span()));
+            }
+            let cf = eval_stmt(env, stmt).map_err(|e| e.at(stmt.span()))?;
             // Propagate returns early
             if cf.is_return() {
                 return Ok(cf);
@@ -1077,6 +2227,8 @@ pub fn eval_block(env: &mut Env, block: &Block) -> Result<ControlFlow> {
 
         // Evaluate tail expression if present
         if let Some(ref tail) = block.tail {
+            env.debug_stmt(tail.span())?;
+            env.cover(|c| c.stmt(tail.span()));
             eval_expr(env, tail)
         } else {
             Ok(ControlFlow::Value(Value::Unit))
@@ -1084,6 +2236,31 @@ pub fn eval_block(env: &mut Env, block: &Block) -> Result<ControlFlow> {
     })
 }
 
+/// Define a group of nested functions, capturing the variables in scope
+/// here by value
+fn define_local_fns(env: &mut Env, group: &[&FnDecl]) {
+    if group.is_empty() {
+        return;
+    }
+    let local = Arc::new(LocalFns {
+        fns: group
+            .iter()
+            .map(|decl| {
+                (
+                    decl.name.text.clone(),
+                    decl.params.iter().map(|p| p.name.text.clone()).collect(),
+                    decl.body.clone(),
+                )
+            })
+            .collect(),
+        env: env.clone(),
+    });
+    for (name, closure) in local.closures() {
+        env.count(StatsRecorder::value);
+        env.define(name, closure, false);
+    }
+}
+
 /// Evaluate a statement
 fn eval_stmt(env: &mut Env, stmt: &Stmt) -> Result<ControlFlow> {
     match stmt {
@@ -1101,12 +2278,12 @@ fn eval_stmt(env: &mut Env, stmt: &Stmt) -> Result<ControlFlow> {
 
             // Match pattern against value to get bindings
             // Pattern should always match (irrefutability checked by type checker)
-            let bindings = match_pattern(pat, &v).ok_or_else(|| {
-                anyhow::anyhow!("pattern match failed (should be caught by type checker)")
+            let bindings = match_pattern(pat, &v, &env.consts).ok_or_else(|| {
+                RuntimeError::new(RuntimeErrorKind::PatternMismatch).at(pat.span())
             })?;
 
             // Check for duplicate bindings (defensive - type checker should catch this)
-            check_duplicate_bindings(&bindings)?;
+            check_duplicate_bindings(&bindings, pat.span())?;
 
             // Define all bindings with the same mutability
             for (name, val) in bindings {
@@ -1116,13 +2293,24 @@ fn eval_stmt(env: &mut Env, stmt: &Stmt) -> Result<ControlFlow> {
             Ok(ControlFlow::Value(Value::Unit))
         }
 
+        // The body captures the variables in scope here by value
+        // Defined with its group by `eval_block`
+        Stmt::Fn(_) => Ok(ControlFlow::Value(Value::Unit)),
+
+        // The checker rejects reads before the first assignment, so the
+        // placeholder value is never observed
+        Stmt::Declare { name, .. } => {
+            env.define(name.text.clone(), Value::Unit, true);
+            Ok(ControlFlow::Value(Value::Unit))
+        }
+
         Stmt::Assign { target, value, .. } => {
             let cf = eval_expr(env, value)?;
             if cf.is_return() {
                 return Ok(cf);
             }
             let v = cf.into_value();
-            env.set(&target.text, v)?;
+            env.set(&target.text, v).map_err(|e| e.at(target.span))?;
             Ok(ControlFlow::Value(Value::Unit))
         }
 
@@ -1152,7 +2340,13 @@ fn eval_stmt(env: &mut Env, stmt: &Stmt) -> Result<ControlFlow> {
 }
 
 /// Evaluate an if expression
-fn eval_if(env: &mut Env, cond: &Expr, then_: &Block, else_: Option<&Expr>) -> Result<ControlFlow> {
+fn eval_if(
+    env: &mut Env,
+    cond: &Expr,
+    then_: &Block,
+    else_: Option<&Expr>,
+    span: Span,
+) -> Result<ControlFlow> {
     // Evaluate condition
     let cf = eval_expr(env, cond)?;
     if cf.is_return() {
@@ -1161,9 +2355,10 @@ fn eval_if(env: &mut Env, cond: &Expr, then_: &Block, else_: Option<&Expr>) -> R
 
     let cond_val = match cf.into_value() {
         Value::Bool(b) => b,
-        _ => bail!("if condition must be Bool"),
+        _ => mistyped!(at cond.span(); "if condition must be Bool"),
     };
 
+    env.cover(|c| c.branch(span, usize::from(!cond_val)));
     if cond_val {
         eval_block(env, then_)
     } else if let Some(else_expr) = else_ {
@@ -1184,7 +2379,7 @@ fn eval_while(env: &mut Env, cond: &Expr, body: &Block) -> Result<ControlFlow> {
 
         let cond_val = match cf.into_value() {
             Value::Bool(b) => b,
-            _ => bail!("while condition must be Bool"),
+            _ => mistyped!(at cond.span(); "while condition must be Bool"),
         };
 
         if !cond_val {
@@ -1210,6 +2405,98 @@ fn eval_while(env: &mut Env, cond: &Expr, body: &Block) -> Result<ControlFlow> {
     Ok(ControlFlow::Value(Value::Unit))
 }
 
+/// Evaluate a for loop: the body runs once per item of the sequence, in a
+/// scope of its own with the item bound to the pattern
+fn eval_for(env: &mut Env, pat: &Pat, iter: &Expr, body: &Block) -> Result<ControlFlow> {
+    let cf = eval_expr(env, iter)?;
+    if cf.is_return() {
+        return Ok(cf);
+    }
+    let mut seq = match cf.into_value() {
+        Value::Iter(seq) => seq,
+        other => mistyped!(at iter.span(); "for loop expects an iterator, got {}", other),
+    };
+
+    while let Some((item, rest)) = seq.next(env)? {
+        seq = rest;
+        let bindings = match_pattern(pat, &item, &env.consts)
+            .ok_or_else(|| RuntimeError::new(RuntimeErrorKind::PatternMismatch).at(pat.span()))?;
+        check_duplicate_bindings(&bindings, pat.span())?;
+
+        let cf = env.with_scope(|env| {
+            for (name, val) in bindings {
+                env.define(name, val, false);
+            }
+            eval_block(env, body)
+        })?;
+
+        // Propagate returns
+        if cf.is_return() {
+            return Ok(cf);
+        }
+
+        // Handle break/continue (reserved for future)
+        match cf {
+            ControlFlow::Break => break,
+            ControlFlow::Continue => continue,
+            _ => {}
+        }
+    }
+
+    Ok(ControlFlow::Value(Value::Unit))
+}
+
+/// Evaluate `iter`, `next`, `map`, `filter` or `take` on its argument
+/// values; `args` are the argument expressions, naming function arguments
+fn eval_seq_builtin(env: &mut Env, name: &str, args: &[Expr], values: Vec<Value>) -> Result<Value> {
+    let seq_fn = |i: usize, func: Value| SeqFn {
+        callee: Box::new(args[i].clone()),
+        func: Box::new(func),
+    };
+    let mut values = values.into_iter();
+    let (Some(first), second) = (values.next(), values.next()) else {
+        mistyped!("{}() expects arguments", name);
+    };
+    if let ("iter", Some(step)) = (name, &second) {
+        return Ok(Value::Iter(Seq::Start {
+            first: Box::new(first),
+            step: seq_fn(1, step.clone()),
+        }));
+    }
+    let Value::Iter(seq) = first else {
+        mistyped!(at args[0].span(); "{}() expects an iterator, got {}", name, first);
+    };
+    Ok(match (name, second) {
+        ("next", None) => {
+            let (variant_name, fields) = match seq.next(env)? {
+                Some((item, rest)) => (
+                    "Some",
+                    vec![Value::Tuple(Arc::new([item, Value::Iter(rest)]))],
+                ),
+                None => ("None", vec![]),
+            };
+            Value::Variant {
+                enum_name: "Option".to_string(),
+                variant_name: variant_name.to_string(),
+                fields,
+            }
+        }
+        ("map", Some(f)) => Value::Iter(Seq::Map {
+            inner: Box::new(seq),
+            f: seq_fn(1, f),
+        }),
+        ("filter", Some(pred)) => Value::Iter(Seq::Filter {
+            inner: Box::new(seq),
+            pred: seq_fn(1, pred),
+        }),
+        ("take", Some(Value::Int(n))) => Value::Iter(Seq::Take {
+            inner: Box::new(seq),
+            left: n,
+        }),
+        _ => mistyped!("{}() got arguments of the wrong kind", name),
+    })
+}
+
 /// Evaluate a function call
 fn eval_call(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<ControlFlow> {
     // Security: Check call depth limit
@@ -1221,10 +2508,10 @@ fn eval_call(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<ControlFlow>
 
     if depth > MAX_CALL_DEPTH {
         CALL_DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
-        bail!(
-            "maximum call depth exceeded (limit: {} calls)",
-            MAX_CALL_DEPTH
-        );
+        return Err(RuntimeErrorKind::CallDepthExceeded {
+            limit: MAX_CALL_DEPTH,
+        }
+        .into());
     }
 
     // Ensure we decrement depth even on error/return
@@ -1237,6 +2524,194 @@ fn eval_call(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<ControlFlow>
 
 /// Inner implementation of eval_call (without depth tracking)
 fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<ControlFlow> {
+    if args.iter().any(|arg| matches!(arg, Expr::Hole(_))) {
+        return eval_partial(env, callee, args);
+    }
+
+    // Intrinsic `drop(x)`: evaluating the argument consumes (tombstones) it
+    if let Expr::Var(id) = callee {
+        if id.text == "drop" && env.get("drop").is_none() {
+            for arg in args {
+                let cf = eval_expr(env, arg)?;
+                if cf.is_return() {
+                    return Ok(cf);
+                }
+            }
+            return Ok(ControlFlow::Value(Value::Unit));
+        }
+        // Intrinsic `discriminant(x)`: the tag of an enum value
+        if id.text == "discriminant" && env.get("discriminant").is_none() && args.len() == 1 {
+            let cf = eval_expr(env, &args[0])?;
+            if cf.is_return() {
+                return Ok(cf);
+            }
+            return match cf.into_value() {
+                Value::Variant {
+                    enum_name,
+                    variant_name,
+                    ..
+                } => env
+                    .discriminant_of(&enum_name, &variant_name)
+                    .map(|d| ControlFlow::Value(Value::Int(d)))
+                    .ok_or_else(|| {
+                        RuntimeErrorKind::Internal {
+                            message: format!("unknown variant {}::{}", enum_name, variant_name),
+                        }
+                        .into()
+                    }),
+                other => {
+                    mistyped!(at args[0].span(); "discriminant() expects an enum value, got {}", other)
+                }
+            };
+        }
+        // Intrinsic `show(x)`: the value as Display renders it
+        if id.text == "show" && env.get("show").is_none() && args.len() == 1 {
+            let cf = eval_expr(env, &args[0])?;
+            if cf.is_return() {
+                return Ok(cf);
+            }
+            env.count(StatsRecorder::value);
+            return Ok(ControlFlow::Value(Value::Str(cf.into_value().to_string())));
+        }
+        // Intrinsic `par(f, x, g, y)`: `(f(x), g(y))`, the calls running at once
+        if id.text == "par" && env.get("par").is_none() && args.len() == 4 {
+            let mut values = Vec::new();
+            for arg in args {
+                let borrowed = match arg {
+                    Expr::Borrow(..) => true,
+                    Expr::Var(id) => env.is_borrowed(&id.text),
+                    _ => false,
+                };
+                let cf = eval_expr(env, arg)?;
+                if cf.is_return() {
+                    return Ok(cf);
+                }
+                values.push((cf.into_value(), borrowed));
+            }
+            let [(f, _), x, (g, _), y] = <[_; 4]>::try_from(values).expect("four arguments");
+            return eval_par(env, [(&args[0], f, x), (&args[2], g, y)]);
+        }
+        // Intrinsic `join(t)`: wait for a spawned task and take its result
+        if id.text == "join" && env.get("join").is_none() && args.len() == 1 {
+            let cf = eval_expr(env, &args[0])?;
+            if cf.is_return() {
+                return Ok(cf);
+            }
+            return match cf.into_value() {
+                Value::Task(task) => match task.join() {
+                    Some(result) => result.map(ControlFlow::Value),
+                    None => Err(RuntimeErrorKind::TaskAlreadyJoined.into()),
+                },
+                other => mistyped!(at args[0].span(); "join() expects a task, got {}", other),
+            };
+        }
+        // Sequence builtins: `iter(first, step)`, `next(it)`, and the lazy
+        // adapters `map`, `filter`, `take`
+        let seq_arity = match id.text.as_str() {
+            "iter" | "map" | "filter" | "take" => 2,
+            "next" => 1,
+            _ => 0,
+        };
+        if seq_arity > 0 && args.len() == seq_arity && env.get(&id.text).is_none() {
+            let mut values = Vec::new();
+            for arg in args {
+                let cf = eval_expr(env, arg)?;
+                if cf.is_return() {
+                    return Ok(cf);
+                }
+                values.push(cf.into_value());
+            }
+            return eval_seq_builtin(env, &id.text, args, values).map(ControlFlow::Value);
+        }
+        // Intrinsics `todo()` / `unreachable()`: diverge with the call site
+        let unfinished = match id.text.as_str() {
+            "todo" => Some(RuntimeErrorKind::Todo),
+            "unreachable" => Some(RuntimeErrorKind::Unreachable),
+            _ => None,
+        };
+        if let Some(unfinished) = unfinished {
+            if env.get(&id.text).is_none() {
+                return Err(RuntimeError::new(unfinished).at(id.span));
+            }
+        }
+        // Builtins `arg_count()` / `arg(i)`: the program's command-line arguments
+        if id.text == "arg_count" && env.get("arg_count").is_none() && args.is_empty() {
+            return Ok(ControlFlow::Value(Value::Int(env.args.len() as i64)));
+        }
+        if id.text == "arg" && env.get("arg").is_none() && args.len() == 1 {
+            let cf = eval_expr(env, &args[0])?;
+            if cf.is_return() {
+                return Ok(cf);
+            }
+            let Value::Int(i) = cf.into_value() else {
+                mistyped!(at args[0].span(); "arg() expects an Int");
+            };
+            let found = usize::try_from(i).ok().and_then(|i| env.args.get(i));
+            let (variant_name, fields) = match found {
+                Some(a) => ("Some", vec![Value::Str(a.clone())]),
+                None => ("None", vec![]),
+            };
+            return Ok(ControlFlow::Value(Value::Variant {
+                enum_name: "Option".to_string(),
+                variant_name: variant_name.to_string(),
+                fields,
+            }));
+        }
+    }
+
+    // Tuple struct constructor: UserId(42)
+    if let Expr::Var(id) = callee {
+        if let Some(DefId::Ctor { variant: None, .. }) = env.resolution.ident(id) {
+            let mut fields = HashMap::new();
+            for (i, arg) in args.iter().enumerate() {
+                let cf = eval_expr(env, arg)?;
+                if cf.is_return() {
+                    return Ok(cf);
+                }
+                fields.insert(i.to_string(), cf.into_value());
+            }
+            env.count(StatsRecorder::value);
+            return Ok(ControlFlow::Value(Value::Struct {
+                name: id.text.clone(),
+                fields,
+            }));
+        }
+    }
+
+    // `E::from_discriminant(n)`: `Option::Some` of the variant numbered `n`
+    if let Expr::PathExpr(path) = callee {
+        if let [enum_name, method] = path.segments.as_slice() {
+            if method.text == "from_discriminant" && args.len() == 1 {
+                if let Some(variants) = env.discriminants.get(&enum_name.text).cloned() {
+                    let cf = eval_expr(env, &args[0])?;
+                    if cf.is_return() {
+                        return Ok(cf);
+                    }
+                    let Value::Int(n) = cf.into_value() else {
+                        mistyped!(at args[0].span(); "from_discriminant() expects an Int");
+                    };
+                    let found = variants.iter().find(|(_, d)| *d == n);
+                    let (variant_name, fields) = match found {
+                        Some((name, _)) => (
+                            "Some",
+                            vec![Value::Variant {
+                                enum_name: enum_name.text.clone(),
+                                variant_name: name.clone(),
+                                fields: vec![],
+                            }],
+                        ),
+                        None => ("None", vec![]),
+                    };
+                    return Ok(ControlFlow::Value(Value::Variant {
+                        enum_name: "Option".to_string(),
+                        variant_name: variant_name.to_string(),
+                        fields,
+                    }));
+                }
+            }
+        }
+    }
+
     // Evaluate callee
     let cf = eval_expr(env, callee)?;
     if cf.is_return() {
@@ -1262,6 +2737,7 @@ fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<Contro
                 }
                 field_values.push(cf.into_value());
             }
+            env.count(StatsRecorder::value);
             return Ok(ControlFlow::Value(Value::Variant {
                 enum_name: enum_name.clone(),
                 variant_name: variant_name.clone(),
@@ -1270,65 +2746,185 @@ fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<Contro
         }
     }
 
-    // Handle host function dispatch for extern fns
-    if let Value::HostFn(name) = &callee_val {
-        let mut arg_values = Vec::new();
-        for arg in args {
-            let cf = eval_expr(env, arg)?;
-            if cf.is_return() {
-                return Ok(cf);
+    // Evaluate arguments, noting which are borrows (`&x`, or passing on a
+    // borrowed parameter) so the callee reads them without consuming
+    let mut arg_values = Vec::new();
+    for arg in args {
+        let borrowed = match arg {
+            Expr::Borrow(..) => true,
+            Expr::Var(id) => env.is_borrowed(&id.text),
+            _ => false,
+        };
+        let cf = eval_expr(env, arg)?;
+        if cf.is_return() {
+            return Ok(cf);
+        }
+        arg_values.push((cf.into_value(), borrowed));
+    }
+
+    call_value(env, callee, callee_val, arg_values)
+}
+
+/// Partial application `callee(args)`, some of `args` holes: the callee
+/// and the other arguments, evaluated now, for a call to pass the rest
+fn eval_partial(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<ControlFlow> {
+    let cf = eval_expr(env, callee)?;
+    if cf.is_return() {
+        return Ok(cf);
+    }
+    let callee_val = cf.into_value();
+    let mut bound = Vec::with_capacity(args.len());
+    for arg in args {
+        if let Expr::Hole(_) = arg {
+            bound.push(None);
+            continue;
+        }
+        let cf = eval_expr(env, arg)?;
+        if cf.is_return() {
+            return Ok(cf);
+        }
+        bound.push(Some(cf.into_value()));
+    }
+    env.count(StatsRecorder::value);
+    Ok(ControlFlow::Value(Value::Partial {
+        callee: Box::new(callee_val),
+        args: bound,
+    }))
+}
+
+/// The function a partial application `callee_val` holds, with the
+/// arguments it bound and `arg_values` in its holes. Other values are
+/// returned as they are.
+fn fill_holes(
+    mut callee_val: Value,
+    mut arg_values: Vec<(Value, bool)>,
+) -> Result<(Value, Vec<(Value, bool)>)> {
+    // A partial application of a partial application, and so on
+    while let Value::Partial { callee, args } = callee_val {
+        let holes = args.iter().filter(|a| a.is_none()).count();
+        if arg_values.len() != holes {
+            return Err(RuntimeErrorKind::ArityMismatch {
+                expected: holes,
+                got: arg_values.len(),
             }
-            arg_values.push(cf.into_value());
+            .into());
         }
+        let mut given = arg_values.into_iter();
+        // Bound arguments hold no capability, so none is borrowed
+        arg_values = args
+            .into_iter()
+            .map(|arg| match arg {
+                Some(value) => Some((value, false)),
+                None => given.next(),
+            })
+            .collect::<Option<_>>()
+            .expect("one argument per hole");
+        callee_val = *callee;
+    }
+    Ok((callee_val, arg_values))
+}
+
+/// Call the function value `callee_val`, which `callee` evaluated to, with
+/// evaluated arguments, each marked whether it is borrowed
+fn call_value(
+    env: &mut Env,
+    callee: &Expr,
+    callee_val: Value,
+    arg_values: Vec<(Value, bool)>,
+) -> Result<ControlFlow> {
+    let (callee_val, arg_values) = fill_holes(callee_val, arg_values)?;
+
+    // Handle host function dispatch for extern fns
+    if let Value::HostFn(name) = &callee_val {
+        let arg_values: Vec<Value> = arg_values.into_iter().map(|(v, _)| v).collect();
+        env.count(StatsRecorder::host_call);
+
+        // The host sees through `transparent struct`s, both ways
+        let meta = env
+            .host_registry
+            .as_ref()
+            .and_then(|r| r.get_extern_meta(name))
+            .cloned();
+        let arg_values = match &meta {
+            Some(meta) => meta.unwrap_args(meta.complete_args(arg_values)),
+            None => arg_values,
+        };
+        let wrap = |val: Value| match &meta {
+            Some(meta) => meta.wrap_result(val),
+            None => val,
+        };
 
         // Replay mode: substitute outputs from recorded trace
         if let Some(replayer) = &env.replayer {
             let inputs = build_replay_inputs(env, name, &arg_values);
             let mut r = replayer.lock().unwrap();
-            match r.next(name, &inputs) {
-                Ok(val) => return Ok(ControlFlow::Value(val)),
-                Err(ReplayError::ReplayedError(msg)) => {
-                    bail!("host function '{}': {}", name, msg)
-                }
-                Err(e) => bail!("{}", e),
+            match r.next(env.task.id.as_deref(), name, &inputs) {
+                Ok(val) => return Ok(ControlFlow::Value(wrap(val))),
+                Err(ReplayError::ReplayedError(msg)) => return Err(host_error(name, msg)),
+                Err(e) => return Err(replay_error(e)),
+            }
+        }
+
+        if let Some(observer) = &env.observer {
+            if let Err(msg) = observer.on_host_call(name, &arg_values) {
+                return Err(host_error(name, format!("refused by observer: {}", msg)));
             }
         }
 
         // Live mode: dispatch to real host function
-        let registry = env.host_registry.as_ref().ok_or_else(|| {
-            anyhow::anyhow!("no host registry available for extern fn '{}'", name)
-        })?;
+        let registry = env
+            .host_registry
+            .as_ref()
+            .ok_or_else(|| RuntimeErrorKind::Internal {
+                message: format!("no host registry available for extern fn '{}'", name),
+            })?;
 
         // Single dispatch path: always use position-aware dispatch_traced().
         // TraceEmitter::disabled() handles the no-output case.
+        env.profile_enter(name, true);
+        let task = env.task.id.as_deref();
         let result = if let Some(tracer) = &env.tracer {
-            let mut t = tracer.lock().unwrap();
-            registry.dispatch_traced(name, &arg_values, &mut t)
+            registry.dispatch_traced(name, &arg_values, task, tracer)
         } else {
-            let mut t = TraceEmitter::disabled();
-            registry.dispatch_traced(name, &arg_values, &mut t)
+            let tracer = Mutex::new(TraceEmitter::disabled());
+            registry.dispatch_traced(name, &arg_values, task, &tracer)
         };
+        env.profile_exit();
 
         match result {
-            Ok(val) => return Ok(ControlFlow::Value(val)),
-            Err(e) => bail!("host function '{}': {}", name, e),
+            Ok(val) => return Ok(ControlFlow::Value(wrap(val))),
+            Err(e) => return Err(host_error(name, e.to_string())),
         }
     }
 
     let closure = match callee_val {
-        Value::Closure { params, body, env } => (params, body, env),
-        v => bail!("cannot call non-function value: {}", v),
+        Value::Closure {
+            params,
+            body,
+            env,
+            local,
+        } => (params, body, env, local),
+        v => mistyped!(at callee.span(); "cannot call non-function value: {}", v),
     };
 
-    let (params, body, mut closure_env) = closure;
+    let (params, body, mut closure_env, local) = closure;
+
+    // The call runs in the caller's task, tracing where the caller does
+    closure_env.task = env.task.clone();
+    closure_env.tracer = env.tracer.clone();
 
     // For recursion and mutual recursion support: patch the closure's captured
     // environment with any closures from the calling environment that are
     // placeholders (Unit) or outdated versions in the captured env.
     // This handles self-recursion, forward references, and mutual recursion.
+    // A global scope the closure still shares with the caller is up to date.
     if let Some(calling_scope) = env.scopes.first() {
-        if let Some(closure_scope) = closure_env.scopes.first_mut() {
-            for (name, binding) in calling_scope {
+        if let Some(closure_scope) = closure_env
+            .scopes
+            .first_mut()
+            .filter(|scope| !Arc::ptr_eq(scope, calling_scope))
+        {
+            for (name, binding) in calling_scope.iter() {
                 // Only patch if it's a closure in the calling env
                 if matches!(binding.value, Value::Closure { .. }) {
                     // Check if closure_env has Unit (placeholder) or a different closure
@@ -1337,11 +2933,12 @@ fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<Contro
                         None => true,
                     };
                     if needs_update {
-                        closure_scope.insert(
+                        Arc::make_mut(closure_scope).insert(
                             name.clone(),
                             Binding {
                                 value: binding.value.clone(),
                                 mutable: false,
+                                borrowed: false,
                             },
                         );
                     }
@@ -1351,34 +2948,50 @@ fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<Contro
     }
 
     // Check argument count
-    if args.len() != params.len() {
-        bail!(
-            "function expects {} arguments, got {}",
-            params.len(),
-            args.len()
-        );
+    if arg_values.len() != params.len() {
+        return Err(RuntimeErrorKind::ArityMismatch {
+            expected: params.len(),
+            got: arg_values.len(),
+        }
+        .into());
     }
 
-    // Evaluate arguments
-    let mut arg_values = Vec::new();
-    for arg in args {
-        let cf = eval_expr(env, arg)?;
-        if cf.is_return() {
-            return Ok(cf);
+    if let Some(observer) = &env.observer {
+        let values: Vec<Value> = arg_values.iter().map(|(v, _)| v.clone()).collect();
+        observer.on_call(&callee_name(callee), &values);
+    }
+    env.count(StatsRecorder::call);
+
+    // A nested function sees itself and the functions declared with it
+    if let Some(local) = &local {
+        closure_env.push_scope();
+        for (name, closure) in local.closures() {
+            closure_env.define(name, closure, false);
         }
-        arg_values.push(cf.into_value());
     }
 
     // Set up function environment with captured env
     closure_env.push_scope();
 
     // Bind parameters to arguments
-    for (param, value) in params.iter().zip(arg_values) {
-        closure_env.define(param.clone(), value, false);
+    for (param, (value, borrowed)) in params.iter().zip(arg_values) {
+        if borrowed {
+            closure_env.define_borrowed(param.clone(), value);
+        } else {
+            closure_env.define(param.clone(), value, false);
+        }
     }
 
     // Evaluate body
-    let result = eval_block(&mut closure_env, &body)?;
+    if env.profiler.is_some() || env.debugger.is_some() {
+        let name = callee_name(callee);
+        env.profile_enter(&name, false);
+        env.debug_enter(&name);
+    }
+    let result = eval_block(&mut closure_env, &body);
+    env.debug_exit();
+    env.profile_exit();
+    let result = result.map_err(|e| e.in_fn(callee_name(callee)))?;
 
     closure_env.pop_scope()?;
 
@@ -1386,6 +2999,169 @@ fn eval_call_inner(env: &mut Env, callee: &Expr, args: &[Expr]) -> Result<Contro
     Ok(ControlFlow::Value(result.into_value()))
 }
 
+/// Call each branch's function on its argument, each on a thread of its
+/// own, giving the results as a tuple.
+///
+/// Host calls a branch makes are traced through a fork of the tracer,
+/// joined back in branch order, so the trace reads as if the branches ran
+/// one after the other. Replay, the debugger, and the profiler follow a
+/// single thread of calls, so under any of them (or once `MAX_TASK_THREADS`
+/// branches are running) the branches do run one after the other.
+fn eval_par(env: &mut Env, branches: [(&Expr, Value, (Value, bool)); 2]) -> Result<ControlFlow> {
+    let single_threaded =
+        env.replayer.is_some() || env.debugger.is_some() || env.profiler.is_some();
+    let branch_env = |i: usize| {
+        let mut branch_env = env.clone();
+        branch_env.task = Arc::new(env.task.branch(i));
+        branch_env
+    };
+    if single_threaded || !reserve_task_threads(2) {
+        let mut results = Vec::new();
+        for (i, (callee, func, arg)) in branches.into_iter().enumerate() {
+            let cf = call_value(&mut branch_env(i), callee, func, vec![arg])?;
+            results.push(cf.into_value());
+        }
+        return Ok(ControlFlow::Value(Value::Tuple(results.into())));
+    }
+
+    let depth = CALL_DEPTH.with(|d| d.get());
+    let mut i = 0;
+    let outcomes = std::thread::scope(|scope| {
+        let handles = branches.map(|(callee, func, arg)| {
+            let mut branch_env = branch_env(i);
+            i += 1;
+            let fork = env
+                .tracer
+                .as_ref()
+                .map(|t| Arc::new(Mutex::new(t.lock().unwrap().fork())));
+            branch_env.tracer = fork.clone();
+            let handle = std::thread::Builder::new()
+                .name("strata-par".to_string())
+                .stack_size(TASK_STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    // The branch is as deep in calls as `par` was
+                    CALL_DEPTH.with(|d| d.set(depth));
+                    call_value(&mut branch_env, callee, func, vec![arg])
+                });
+            (handle, fork)
+        });
+        handles.map(|(handle, fork)| {
+            let result = match handle {
+                Ok(handle) => handle.join(),
+                Err(e) => Ok(Err(io_error(format!("par: cannot start a thread: {}", e)))),
+            };
+            (result, fork)
+        })
+    });
+    TASK_THREADS.fetch_sub(2, Ordering::SeqCst);
+
+    let mut results = Vec::new();
+    for (result, fork) in outcomes {
+        // Every branch ran to the end, so each one's host calls are traced
+        // even if the other failed
+        if let (Some(tracer), Some(fork)) = (&env.tracer, fork) {
+            let fork = std::mem::take(&mut *fork.lock().unwrap());
+            tracer.lock().unwrap().join(fork).map_err(io_error)?;
+        }
+        let result = result.unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        results.push(result.map(ControlFlow::into_value));
+    }
+    let results = results.into_iter().collect::<Result<Vec<_>>>()?;
+    Ok(ControlFlow::Value(Value::Tuple(results.into())))
+}
+
+/// Start a task running `body`, on a thread of its own, giving its handle.
+///
+/// The task gets a copy of the environment; the checker has made sure it
+/// uses no mutable variable from it, and that any capability it uses moved
+/// into it. Its host calls are traced tagged with its id. Like `par`, under
+/// replay, the debugger, or the profiler (or once `MAX_TASK_THREADS` threads
+/// are running) the task doesn't start until it is joined.
+fn eval_spawn(env: &mut Env, body: &Block) -> Result<ControlFlow> {
+    let mut task_env = env.clone();
+    task_env.task = Arc::new(env.task.child());
+    task_env.tracer = env.task_tracer.clone();
+
+    let single_threaded =
+        env.replayer.is_some() || env.debugger.is_some() || env.profiler.is_some();
+    let state = if single_threaded || !reserve_task_threads(1) {
+        TaskState::Deferred {
+            env: Box::new(task_env),
+            body: body.clone(),
+        }
+    } else {
+        let depth = CALL_DEPTH.with(|d| d.get());
+        let body = body.clone();
+        let name = format!("strata-task-{}", task_env.task.id.as_deref().unwrap_or(""));
+        let spawned = std::thread::Builder::new()
+            .name(name)
+            .stack_size(TASK_STACK_SIZE)
+            .spawn(move || {
+                // The task is as deep in calls as `spawn` was
+                CALL_DEPTH.with(|d| d.set(depth));
+                let result = run_task(&mut task_env, &body);
+                TASK_THREADS.fetch_sub(1, Ordering::SeqCst);
+                result
+            });
+        match spawned {
+            Ok(handle) => TaskState::Running(handle),
+            Err(e) => {
+                TASK_THREADS.fetch_sub(1, Ordering::SeqCst);
+                return Err(io_error(format!("spawn: cannot start a thread: {}", e)));
+            }
+        }
+    };
+    let task = TaskHandle(Arc::new(Mutex::new(Some(state))));
+    env.tasks.lock().unwrap().push(task.clone());
+    Ok(ControlFlow::Value(Value::Task(task)))
+}
+
+/// Run a task's body to its value; `return` in it ends the task
+fn run_task(env: &mut Env, body: &Block) -> Result<Value> {
+    eval_block(env, body).map(ControlFlow::into_value)
+}
+
+/// Join every task still unjoined when `main` returns, tasks they spawn
+/// included, giving the first one's error.
+fn finish_tasks(env: &Env) -> Result<()> {
+    let mut first_error = None;
+    loop {
+        let pending = std::mem::take(&mut *env.tasks.lock().unwrap());
+        if pending.is_empty() {
+            break;
+        }
+        for task in pending {
+            if let Some(Err(e)) = task.join() {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Claim `n` of the `MAX_TASK_THREADS` branch threads, if that many are free
+fn reserve_task_threads(n: usize) -> bool {
+    TASK_THREADS
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |running| {
+            (running + n <= MAX_TASK_THREADS).then_some(running + n)
+        })
+        .is_ok()
+}
+
+/// How a call site names its callee, for [`RuntimeObserver::on_call`]
+fn callee_name(callee: &Expr) -> String {
+    match callee {
+        Expr::Var(id) => id.text.clone(),
+        Expr::PathExpr(path) => path
+            .segments
+            .iter()
+            .map(|s| s.text.as_str())
+            .collect::<Vec<_>>()
+            .join("::"),
+        _ => "<fn>".to_string(),
+    }
+}
+
 /// Evaluate a tuple expression
 fn eval_tuple(env: &mut Env, elems: &[Expr]) -> Result<ControlFlow> {
     // Empty tuple is unit
@@ -1408,7 +3184,23 @@ fn eval_tuple(env: &mut Env, elems: &[Expr]) -> Result<ControlFlow> {
         values.push(cf.into_value());
     }
 
-    Ok(ControlFlow::Value(Value::Tuple(values)))
+    env.count(StatsRecorder::value);
+    Ok(ControlFlow::Value(Value::Tuple(values.into())))
+}
+
+/// Evaluate a record expression
+fn eval_record(env: &mut Env, fields: &[FieldInit]) -> Result<ControlFlow> {
+    let mut values = BTreeMap::new();
+    for field in fields {
+        let cf = eval_expr(env, &field.value)?;
+        if cf.is_return() {
+            return Ok(cf);
+        }
+        values.insert(field.name.text.clone(), cf.into_value());
+    }
+
+    env.count(StatsRecorder::value);
+    Ok(ControlFlow::Value(Value::Record(Arc::new(values))))
 }
 
 /// Evaluate a struct expression
@@ -1423,7 +3215,16 @@ fn eval_struct_expr(env: &mut Env, path: &Path, fields: &[FieldInit]) -> Result<
         }
         field_values.insert(field.name.text.clone(), cf.into_value());
     }
+    // Defaulted fields the literal leaves off
+    if let Some(defaults) = env.field_defaults.get(&struct_name) {
+        for (name, value) in defaults {
+            if !field_values.contains_key(name) {
+                field_values.insert(name.clone(), value.clone());
+            }
+        }
+    }
 
+    env.count(StatsRecorder::value);
     Ok(ControlFlow::Value(Value::Struct {
         name: struct_name,
         fields: field_values,
@@ -1432,48 +3233,49 @@ fn eval_struct_expr(env: &mut Env, path: &Path, fields: &[FieldInit]) -> Result<
 
 /// Evaluate a path expression (enum constructor)
 fn eval_path_expr(env: &mut Env, path: &Path) -> Result<ControlFlow> {
-    let segments = &path.segments;
-
-    if segments.len() == 2 {
-        // Enum::Variant format - unit constructor
-        let enum_name = segments[0].text.clone();
-        let variant_name = segments[1].text.clone();
+    // A variant: a unit value, or a constructor once called
+    if let Some(DefId::Ctor {
+        adt,
+        variant: Some(variant),
+    }) = env.resolution.path(path)
+    {
         return Ok(ControlFlow::Value(Value::Variant {
-            enum_name,
-            variant_name,
+            enum_name: adt.clone(),
+            variant_name: variant.clone(),
             fields: vec![],
         }));
     }
 
-    // Single segment - look up in environment (might be a function or variable)
-    // Same affine treatment as Expr::Var
-    if segments.len() == 1 {
-        let seg = &segments[0];
+    // Anything else names a value in the environment, with the same affine
+    // treatment as Expr::Var
+    if let [seg] = path.segments.as_slice() {
         let is_affine = match env.get(&seg.text) {
             Some(v) => {
                 check_not_consumed(v, &seg.text, seg.span)?;
-                v.is_affine()
+                env.is_affine_value(v)
             }
-            None => bail!("undefined: {}", seg.text),
+            None => return Err(undefined(&seg.text)),
         };
         if is_affine {
-            let val = env.move_out(&seg.text, seg.span).ok_or_else(|| {
-                anyhow::anyhow!(
-                    "internal error: move_out failed for `{}` — binding not found in any scope",
-                    seg.text
-                )
-            })?;
+            let val = env
+                .move_out(&seg.text, seg.span)
+                .ok_or_else(|| move_out_failed(&seg.text))?;
             return Ok(ControlFlow::Value(val));
         } else {
             return Ok(ControlFlow::Value(env.get(&seg.text).unwrap().clone()));
         }
     }
 
-    bail!("invalid path expression: {}", path.as_str())
+    mistyped!("unresolved path: {}", path.as_str())
 }
 
 /// Evaluate a match expression
-fn eval_match(env: &mut Env, scrutinee: &Expr, arms: &[MatchArm]) -> Result<ControlFlow> {
+fn eval_match(
+    env: &mut Env,
+    scrutinee: &Expr,
+    arms: &[MatchArm],
+    span: Span,
+) -> Result<ControlFlow> {
     // Evaluate the scrutinee
     let cf = eval_expr(env, scrutinee)?;
     if cf.is_return() {
@@ -1482,15 +3284,21 @@ fn eval_match(env: &mut Env, scrutinee: &Expr, arms: &[MatchArm]) -> Result<Cont
     let value = cf.into_value();
 
     // Try each arm in order
-    for arm in arms {
-        if let Some(bindings) = match_pattern(&arm.pat, &value) {
+    for (i, arm) in arms.iter().enumerate() {
+        if let Some(bindings) = match_pattern(&arm.pat, &value, &env.consts) {
             // Check for duplicate bindings (defensive - type checker should catch this)
-            check_duplicate_bindings(&bindings)?;
+            check_duplicate_bindings(&bindings, arm.pat.span())?;
+            env.cover(|c| c.branch(span, i));
 
-            // Pattern matched - evaluate arm body with bindings in new scope
+            // Pattern matched - evaluate arm body with bindings in new scope.
+            // `match &x` only borrows `x`, so neither are its bindings owned
+            let borrowed = matches!(scrutinee, Expr::Borrow(..));
             return env.with_scope(|env| {
                 for (name, val) in bindings {
-                    env.define(name, val, false);
+                    match borrowed {
+                        true => env.define_borrowed(name, val),
+                        false => env.define(name, val, false),
+                    }
                 }
                 eval_expr(env, &arm.body)
             });
@@ -1498,22 +3306,35 @@ fn eval_match(env: &mut Env, scrutinee: &Expr, arms: &[MatchArm]) -> Result<Cont
     }
 
     // No arm matched (should be caught by exhaustiveness checking)
-    bail!("non-exhaustive match: no pattern matched value {}", value)
+    let kind = RuntimeErrorKind::NonExhaustiveMatch {
+        value: value.to_string(),
+    };
+    Err(RuntimeError::new(kind).at(scrutinee.span()))
 }
 
-/// Try to match a pattern against a value, returning bindings if successful
-fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
+/// Try to match a pattern against a value, returning bindings if successful.
+/// An identifier naming one of `consts` matches the const's value.
+fn match_pattern(pat: &Pat, value: &Value, consts: &ConstTable) -> Option<Vec<(String, Value)>> {
     match pat {
         Pat::Wildcard(_) => Some(vec![]),
 
-        Pat::Ident(ident) => Some(vec![(ident.text.clone(), value.clone())]),
+        Pat::Ident(ident) => match consts.get(&ident.text) {
+            Some(c) => match_literal(&c.to_lit(), value),
+            None => Some(vec![(ident.text.clone(), value.clone())]),
+        },
+
+        Pat::Literal(lit, _) => match_literal(lit, value),
 
-        Pat::Literal(lit, _) => match (lit, value) {
-            (Lit::Int(n), Value::Int(v)) if *n == *v => Some(vec![]),
-            (Lit::Float(n), Value::Float(v)) if *n == *v => Some(vec![]),
-            (Lit::Bool(b), Value::Bool(v)) if *b == *v => Some(vec![]),
-            (Lit::Str(s), Value::Str(v)) if s == v => Some(vec![]),
-            (Lit::Nil, Value::Unit) => Some(vec![]),
+        Pat::Range { .. } => match (pat.range_bounds(), value) {
+            (Some((lo, hi)), Value::Int(v)) if (lo..=hi).contains(v) => Some(vec![]),
+            _ => None,
+        },
+
+        Pat::StrPrefix { prefix, rest, .. } => match value {
+            Value::Str(s) => {
+                let remainder = s.strip_prefix(prefix.as_str())?;
+                match_pattern(rest, &Value::Str(remainder.to_string()), consts)
+            }
             _ => None,
         },
 
@@ -1532,7 +3353,7 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
                 }
                 let mut bindings = Vec::new();
                 for (pat, val) in pats.iter().zip(values.iter()) {
-                    if let Some(mut sub_bindings) = match_pattern(pat, val) {
+                    if let Some(mut sub_bindings) = match_pattern(pat, val, consts) {
                         bindings.append(&mut sub_bindings);
                     } else {
                         return None;
@@ -1544,6 +3365,19 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
             }
         }
 
+        // Fields the pattern leaves out (with `..`) aren't looked at
+        Pat::Record { fields, .. } => {
+            let Value::Record(values) = value else {
+                return None;
+            };
+            let mut bindings = Vec::new();
+            for pat_field in fields {
+                let field_value = values.get(&pat_field.name.text)?;
+                bindings.append(&mut match_pattern(&pat_field.pat, field_value, consts)?);
+            }
+            Some(bindings)
+        }
+
         Pat::Struct { path, fields, .. } => {
             if let Value::Struct {
                 name,
@@ -1556,7 +3390,9 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
                 let mut bindings = Vec::new();
                 for pat_field in fields {
                     let field_value = value_fields.get(&pat_field.name.text)?;
-                    if let Some(mut sub_bindings) = match_pattern(&pat_field.pat, field_value) {
+                    if let Some(mut sub_bindings) =
+                        match_pattern(&pat_field.pat, field_value, consts)
+                    {
                         bindings.append(&mut sub_bindings);
                     } else {
                         return None;
@@ -1568,6 +3404,26 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
             }
         }
 
+        // A tuple struct pattern: `UserId(n)`
+        Pat::Variant { path, fields, .. } if path.segments.len() == 1 => {
+            let Value::Struct {
+                name,
+                fields: value_fields,
+            } = value
+            else {
+                return None;
+            };
+            if path.segments[0].text != *name || fields.len() != value_fields.len() {
+                return None;
+            }
+            let mut bindings = Vec::new();
+            for (i, pat) in fields.iter().enumerate() {
+                let field_value = value_fields.get(&i.to_string())?;
+                bindings.append(&mut match_pattern(pat, field_value, consts)?);
+            }
+            Some(bindings)
+        }
+
         Pat::Variant { path, fields, .. } => {
             if let Value::Variant {
                 enum_name,
@@ -1590,7 +3446,7 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
                 // Match each field pattern
                 let mut bindings = Vec::new();
                 for (pat, val) in fields.iter().zip(value_fields.iter()) {
-                    if let Some(mut sub_bindings) = match_pattern(pat, val) {
+                    if let Some(mut sub_bindings) = match_pattern(pat, val, consts) {
                         bindings.append(&mut sub_bindings);
                     } else {
                         return None;
@@ -1604,16 +3460,101 @@ fn match_pattern(pat: &Pat, value: &Value) -> Option<Vec<(String, Value)>> {
     }
 }
 
-/// Check for duplicate bindings and return an error if found.
+fn match_literal(lit: &Lit, value: &Value) -> Option<Vec<(String, Value)>> {
+    match (lit, value) {
+        (Lit::Int(n), Value::Int(v)) if *n == *v => Some(vec![]),
+        (Lit::Float(n), Value::Float(v)) if *n == *v => Some(vec![]),
+        (Lit::Bool(b), Value::Bool(v)) if *b == *v => Some(vec![]),
+        (Lit::Str(s), Value::Str(v)) if s == v => Some(vec![]),
+        (Lit::Nil, Value::Unit) => Some(vec![]),
+        _ => None,
+    }
+}
+
+fn undefined(name: &str) -> RuntimeError {
+    RuntimeErrorKind::UndefinedVariable {
+        name: name.to_string(),
+    }
+    .into()
+}
+
+fn move_out_failed(name: &str) -> RuntimeError {
+    RuntimeErrorKind::Internal {
+        message: format!(
+            "move_out failed for `{}` — binding not found in any scope",
+            name
+        ),
+    }
+    .into()
+}
+
+fn host_error(function: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeErrorKind::Host {
+        function: function.to_string(),
+        message: message.into(),
+    }
+    .into()
+}
+
+fn replay_error(e: ReplayError) -> RuntimeError {
+    match e {
+        ReplayError::Sealed(e) => RuntimeErrorKind::SealedTrace {
+            message: e.to_string(),
+        },
+        e => RuntimeErrorKind::Replay {
+            message: e.to_string(),
+        },
+    }
+    .into()
+}
+
+/// `m` with `use`-imported variants written as qualified paths, derived
+/// functions added, and named arguments passed by position
+fn expand_module(m: &Module) -> Result<Cow<'_, Module>> {
+    let m = strata_types::resolve::expand_variant_imports(m)
+        .map_err(|e| setup_error(&e.to_string()))?;
+    let m = match strata_types::derive::expand_derives(&m) {
+        Cow::Owned(expanded) => Cow::Owned(expanded),
+        Cow::Borrowed(_) => m,
+    };
+    match strata_types::named_args::expand_named_args(&m)
+        .map_err(|e| setup_error(&e.to_string()))?
+    {
+        Cow::Owned(expanded) => Ok(Cow::Owned(expanded)),
+        Cow::Borrowed(_) => Ok(m),
+    }
+}
+
+fn setup_error(message: &str) -> RuntimeError {
+    RuntimeErrorKind::Setup {
+        message: message.to_string(),
+    }
+    .into()
+}
+
+fn io_error(e: impl std::fmt::Display) -> RuntimeError {
+    RuntimeErrorKind::Io {
+        message: e.to_string(),
+    }
+    .into()
+}
+
+/// Whether `err` is one of the evaluator's defense-in-depth failures: a
+/// soundness bug in the type checker rather than an error in the program.
+/// See [`RuntimeErrorKind::is_checker_escape`].
+pub fn is_checker_escape(err: &RuntimeError) -> bool {
+    err.is_checker_escape()
+}
+
+/// Check for duplicate bindings made by the pattern at `span` and return
+/// an error if found.
 /// This is a defensive check - the type checker should catch duplicates.
-fn check_duplicate_bindings(bindings: &[(String, Value)]) -> Result<()> {
+fn check_duplicate_bindings(bindings: &[(String, Value)], span: Span) -> Result<()> {
     let mut seen = HashSet::new();
     for (name, _) in bindings {
         if !seen.insert(name) {
-            bail!(
-                "duplicate binding '{}' in pattern (should be caught by type checker)",
-                name
-            );
+            let kind = RuntimeErrorKind::DuplicateBinding { name: name.clone() };
+            return Err(RuntimeError::new(kind).at(span));
         }
     }
     Ok(())
@@ -1626,7 +3567,7 @@ mod tests {
     use strata_ast::span::Span;
 
     fn sp() -> Span {
-        Span { start: 0, end: 0 }
+        Span::default()
     }
 
     fn ident(name: &str) -> Ident {
@@ -1636,6 +3577,23 @@ mod tests {
         }
     }
 
+    /// An environment where the hand-built path `Option::<variant>`
+    /// resolves as it would in a parsed module
+    fn env_resolving_option(variant: &str) -> Env {
+        let mut resolution = Resolution::default();
+        resolution.insert(
+            sp(),
+            format!("Option::{}", variant),
+            DefId::Ctor {
+                adt: "Option".to_string(),
+                variant: Some(variant.to_string()),
+            },
+        );
+        let mut env = Env::new();
+        env.resolution = Arc::new(resolution);
+        env
+    }
+
     #[test]
     fn test_eval_literal_int() {
         let mut env = Env::new();
@@ -1929,6 +3887,7 @@ mod tests {
                 span: sp(),
             },
             env: Env::new(),
+            local: None,
         };
         env.define("add".to_string(), add_closure, false);
 
@@ -1936,6 +3895,7 @@ mod tests {
         let call_expr = Expr::Call {
             callee: Box::new(Expr::Var(ident("add"))),
             args: vec![Expr::Lit(Lit::Int(1), sp()), Expr::Lit(Lit::Int(2), sp())],
+            names: Vec::new(),
             span: sp(),
         };
         let cf = eval_expr(&mut env, &call_expr).unwrap();
@@ -1976,6 +3936,7 @@ mod tests {
                         op: BinOp::Mul,
                         rhs: Box::new(Expr::Call {
                             callee: Box::new(Expr::Var(ident("fact"))),
+                            names: Vec::new(),
                             args: vec![Expr::Binary {
                                 lhs: Box::new(Expr::Var(ident("n"))),
                                 op: BinOp::Sub,
@@ -1998,6 +3959,7 @@ mod tests {
             params: vec!["n".to_string()],
             body: fact_body.clone(),
             env: env.clone(),
+            local: None,
         };
         env.set("fact", fact_closure).unwrap();
 
@@ -2006,6 +3968,7 @@ mod tests {
             params: vec!["n".to_string()],
             body: fact_body,
             env: env.clone(),
+            local: None,
         };
         env.set("fact", fact_closure).unwrap();
 
@@ -2013,6 +3976,7 @@ mod tests {
         let call_expr = Expr::Call {
             callee: Box::new(Expr::Var(ident("fact"))),
             args: vec![Expr::Lit(Lit::Int(5), sp())],
+            names: Vec::new(),
             span: sp(),
         };
         let cf = eval_expr(&mut env, &call_expr).unwrap();
@@ -2120,6 +4084,39 @@ mod tests {
         assert!(matches!(cf, ControlFlow::Value(Value::Bool(true))));
     }
 
+    #[test]
+    fn test_eval_match_range() {
+        // match 7 { ..=-1 => 0, 0..5 => 1, 5..=9 => 2, _ => 3 }
+        use strata_ast::ast::MatchArm;
+        let range = |lo, hi, inclusive, result| MatchArm {
+            pat: Pat::Range {
+                lo,
+                hi,
+                inclusive,
+                span: sp(),
+            },
+            body: Expr::Lit(Lit::Int(result), sp()),
+            span: sp(),
+        };
+        let mut env = Env::new();
+        let expr = Expr::Match {
+            scrutinee: Box::new(Expr::Lit(Lit::Int(7), sp())),
+            arms: vec![
+                range(None, Some(-1), true, 0),
+                range(Some(0), Some(5), false, 1),
+                range(Some(5), Some(9), true, 2),
+                MatchArm {
+                    pat: Pat::Wildcard(sp()),
+                    body: Expr::Lit(Lit::Int(3), sp()),
+                    span: sp(),
+                },
+            ],
+            span: sp(),
+        };
+        let cf = eval_expr(&mut env, &expr).unwrap();
+        assert!(matches!(cf, ControlFlow::Value(Value::Int(2))));
+    }
+
     #[test]
     fn test_eval_match_binding() {
         // match 42 { x => x + 1 }
@@ -2173,7 +4170,7 @@ mod tests {
     fn test_eval_variant_construction() {
         // Option::Some(42)
         use strata_ast::ast::Path;
-        let mut env = Env::new();
+        let mut env = env_resolving_option("Some");
 
         // First construct the path expression for Option::Some
         let path_expr = Expr::PathExpr(Path {
@@ -2185,6 +4182,7 @@ mod tests {
         let expr = Expr::Call {
             callee: Box::new(path_expr),
             args: vec![Expr::Lit(Lit::Int(42), sp())],
+            names: Vec::new(),
             span: sp(),
         };
 
@@ -2208,7 +4206,7 @@ mod tests {
     fn test_eval_unit_variant() {
         // Option::None
         use strata_ast::ast::Path;
-        let mut env = Env::new();
+        let mut env = env_resolving_option("None");
         let expr = Expr::PathExpr(Path {
             segments: vec![ident("Option"), ident("None")],
             span: sp(),
@@ -2233,7 +4231,7 @@ mod tests {
     fn test_eval_match_variant() {
         // match Option::Some(42) { Option::Some(x) => x, Option::None => 0 }
         use strata_ast::ast::{MatchArm, Path};
-        let mut env = Env::new();
+        let mut env = env_resolving_option("Some");
 
         // Build Option::Some(42)
         let scrutinee = Expr::Call {
@@ -2242,6 +4240,7 @@ mod tests {
                 span: sp(),
             })),
             args: vec![Expr::Lit(Lit::Int(42), sp())],
+            names: Vec::new(),
             span: sp(),
         };
 
@@ -2428,6 +4427,47 @@ mod tests {
         assert!(matches!(cf, ControlFlow::Value(Value::Int(6))));
     }
 
+    #[test]
+    fn test_env_restore_rolls_back_variables() {
+        let mut env = Env::new();
+        env.define("x".to_string(), Value::Int(1), true);
+        let saved = env.snapshot();
+
+        // Two speculative branches from the same snapshot
+        for branch in [2, 3] {
+            env.set("x", Value::Int(branch)).unwrap();
+            env.define("y".to_string(), Value::Int(branch), false);
+            env.push_scope();
+            env.define("z".to_string(), Value::Int(branch), false);
+            assert!(matches!(env.get("x"), Some(Value::Int(b)) if *b == branch));
+            env.restore(&saved);
+            assert!(matches!(env.get("x"), Some(Value::Int(1))));
+            assert!(env.get("y").is_none() && env.get("z").is_none());
+        }
+    }
+
+    #[test]
+    fn test_env_restore_keeps_moved_capabilities_moved() {
+        let mut env = Env::new();
+        env.define("fs".to_string(), Value::Cap(CapKind::Fs), false);
+        env.define("net".to_string(), Value::Cap(CapKind::Net), false);
+        env.define("time".to_string(), Value::Cap(CapKind::Time), false);
+        let saved = env.snapshot();
+
+        env.move_out("fs", sp()).unwrap();
+        // Moved into another binding, then shadowed
+        let net = env.move_out("net", sp()).unwrap();
+        env.define("other".to_string(), net, false);
+        env.define("net".to_string(), Value::Int(0), false);
+        env.restore(&saved);
+
+        assert!(matches!(env.get("fs"), Some(Value::Consumed { .. })));
+        assert!(matches!(env.get("net"), Some(Value::Consumed { .. })));
+        assert!(env.get("other").is_none());
+        // Untouched, the capability is still usable
+        assert!(matches!(env.get("time"), Some(Value::Cap(CapKind::Time))));
+    }
+
     // ================================================================
     // Issue 012: Affine Integrity — Runtime tombstone tests
     // ================================================================
@@ -2447,6 +4487,535 @@ mod tests {
         assert!(matches!(val, Value::Consumed { .. }));
     }
 
+    #[test]
+    fn test_drop_intrinsic_tombstones_cap() {
+        let mut env = Env::new();
+        env.define("fs".to_string(), Value::Cap(CapKind::Fs), false);
+
+        let expr = Expr::Call {
+            callee: Box::new(Expr::Var(ident("drop"))),
+            args: vec![Expr::Var(ident("fs"))],
+            names: Vec::new(),
+            span: sp(),
+        };
+        let cf = eval_expr(&mut env, &expr).unwrap();
+        assert!(matches!(cf, ControlFlow::Value(Value::Unit)));
+        assert!(matches!(env.get("fs").unwrap(), Value::Consumed { .. }));
+    }
+
+    #[test]
+    fn test_declared_variable_assigned_in_branches() {
+        let src = "fn pick(c: Bool) -> Int {
+                let mut x: Int;
+                if c { x = 1; } else { x = 2; };
+                x
+            }
+            fn main() -> Int { pick(false) * 10 + pick(true) }";
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Int(21)));
+    }
+
+    #[test]
+    fn test_structural_equality_on_composites() {
+        let src = r#"struct Point { x: Int, y: Int }
+            enum Shape { Dot(Point), Empty }
+            fn main() -> Bool {
+                let p = Point { x: 1, y: 2 };
+                (1, "a") == (1, "a") && (1, "a") != (1, "b")
+                    && p == Point { y: 2, x: 1 } && p != Point { x: 1, y: 3 }
+                    && Shape::Dot(p) == Shape::Dot(Point { x: 1, y: 2 })
+                    && Shape::Dot(p) != Shape::Empty
+                    && Option::Some((1, true)) == Option::Some((1, true))
+                    && Option::Some(1) != Option::None
+            }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Bool(true)), "got {v}");
+    }
+
+    #[test]
+    fn test_tuple_and_string_ordering() {
+        let src = r#"fn main() -> Bool {
+                (1, 9) < (2, 0) && (2, 0) > (1, 9) && (1, "b") >= (1, "a")
+                    && (1, 2) <= (1, 2) && !((1, 2) < (1, 2))
+                    && "apple" < "banana" && !("b" < "a")
+            }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Bool(true)), "got {v}");
+    }
+
+    #[test]
+    fn test_comparing_closures_is_a_checker_escape() {
+        // The checker rejects this; run anyway, the evaluator refuses too
+        let src = "fn f() -> Int { 1 } fn main() -> Bool { f == f }";
+        let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+        assert!(is_checker_escape(&err), "got: {err}");
+    }
+
+    #[test]
+    fn test_show_formats_like_display() {
+        let src = r#"struct Point { y: Int, x: Int }
+            fn main() -> (String, String, String) {
+                (show((1, "a\n", true)), show(Point { y: 2, x: 1 }), show(Option::Some(())))
+            }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert_eq!(
+            v.to_string(),
+            r#"("(1, \"a\\n\", true)", "Point { x: 1, y: 2 }", "Option::Some(())")"#
+        );
+    }
+
+    #[test]
+    fn test_par_returns_both_results() {
+        let src = r#"
+            fn fib(n: Int) -> Int {
+                if n < 2 { n } else { let (a, b) = par(fib, n - 1, fib, n - 2); a + b }
+            }
+            fn label(n: Int) -> String { show(n) }
+            fn main() -> (Int, String) { par(fib, 15, label, 7) }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        // fib(15) nests deeper than MAX_TASK_THREADS allows, so some
+        // branches run in turn, to the same result
+        assert_eq!(v.to_string(), r#"(610, "7")"#);
+    }
+
+    #[test]
+    fn test_for_runs_lazy_sequences() {
+        let src = r#"
+            fn succ(n: Int) -> Option<Int> { Option::Some(n + 1) }
+            fn square(n: Int) -> Int { n * n }
+            fn odd(n: Int) -> Bool { n / 2 * 2 != n }
+            fn fib_step(p: (Int, Int)) -> Option<(Int, Int)> {
+                let (a, b) = p;
+                if a > 20 { Option::None } else { Option::Some((b, a + b)) }
+            }
+            fn first(p: (Int, Int)) -> Int { let (a, _) = p; a }
+            fn main() -> (Int, Int) {
+                // `iter(1, succ)` never ends; `take` stops asking for items
+                let mut odd_squares = 0;
+                for n in take(filter(map(iter(1, succ), square), odd), 3) {
+                    odd_squares = odd_squares + n;
+                };
+                let mut fibs = 0;
+                for f in map(iter((0, 1), fib_step), first) {
+                    fibs = fibs + f;
+                };
+                (odd_squares, fibs)
+            }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        // 1 + 9 + 25, and 0 + 1 + 1 + 2 + 3 + 5 + 8 + 13 + 21
+        assert_eq!(v.to_string(), "(35, 54)");
+    }
+
+    #[test]
+    fn test_next_splits_off_the_first_item() {
+        let src = r#"
+            fn succ(n: Int) -> Option<Int> { if n < 2 { Option::Some(n + 1) } else { Option::None } }
+            fn main() -> (Int, Int, Bool) {
+                match next(iter(1, succ)) {
+                    Option::Some((a, rest)) => match next(rest) {
+                        Option::Some((b, rest)) => match next(rest) {
+                            Option::Some(_) => (a, b, false),
+                            Option::None => (a, b, true),
+                        },
+                        Option::None => (a, 0, false),
+                    },
+                    Option::None => (0, 0, false),
+                }
+            }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert_eq!(v.to_string(), "(1, 2, true)");
+    }
+
+    #[test]
+    fn test_stats_count_calls_and_values() {
+        let src = r#"
+            fn pair(n: Int) -> (Int, String) { (n, show(n)) }
+            fn main() -> Int {
+                let (a, _) = pair(1);
+                let (b, _) = pair(2);
+                a + b
+            }
+        "#;
+        let module = strata_parse::parse_str("<test>", src).unwrap();
+        let stats = Arc::new(StatsRecorder::new());
+        let opts = RunOptions {
+            stats: Some(stats.clone()),
+            ..RunOptions::default()
+        };
+        assert_eq!(run_module_with(&module, opts).unwrap().to_string(), "3");
+        let stats = stats.snapshot();
+        // main and two calls to pair, each building a tuple and a string
+        assert_eq!(stats.calls, 3);
+        assert_eq!(stats.values, 4);
+        assert_eq!(stats.host_calls, 0);
+        assert!(stats.exprs > 10, "{stats:?}");
+        assert!(stats.peak_env_depth >= 3, "{stats:?}");
+    }
+
+    #[test]
+    fn test_wide_tuples_are_shared_not_copied() {
+        // A 64-field row, the default profile's widest tuple, read through
+        // a binding, compared, and taken apart
+        let fields: Vec<String> = (0..64).map(|i| i.to_string()).collect();
+        let names: Vec<String> = (0..64).map(|i| format!("x{i}")).collect();
+        let src = format!(
+            "fn main() -> Int {{ let row = ({}); let copy = row; \
+             let ({}) = copy; if copy == row {{ x0 + x63 }} else {{ 0 }} }}",
+            fields.join(", "),
+            names.join(", "),
+        );
+        let module = strata_parse::parse_str("<test>", &src).unwrap();
+        assert_eq!(run_module(&module).unwrap().to_string(), "63");
+
+        let row = Value::Tuple(fields.iter().map(|_| Value::Int(0)).collect());
+        let (Value::Tuple(a), Value::Tuple(b)) = (&row, &row.clone()) else {
+            unreachable!()
+        };
+        assert!(Arc::ptr_eq(a, b));
+    }
+
+    #[test]
+    fn test_records_build_read_and_match() {
+        let src = r#"
+            fn get_x(r) { r.x }
+            fn classify(p: {x: Int, y: Int}) -> Int {
+                match p {
+                    { x: 0, y } => y,
+                    { x, y: 0 } => x,
+                    { x, .. } => x * 100,
+                }
+            }
+            fn main() -> (Int, Int, Int, Bool, Int) {
+                let p = { y: 4, x: 3 };
+                let q = { x: 10, label: "q" };
+                let { label: l, .. } = q;
+                let same = p == { x: 3, y: 4 } && l == "q";
+                (get_x(p) + get_x(q), classify({ x: 0, y: 7 }), classify(p), same, p.y)
+            }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert_eq!(v.to_string(), "(13, 7, 300, true, 4)");
+
+        let record = Value::Record(Arc::new(
+            [("b", Value::Int(2)), ("a", Value::Str("s".to_string()))]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        ));
+        assert_eq!(record.to_string(), "{a: \"s\", b: 2}");
+        assert!(!record.is_affine());
+    }
+
+    #[test]
+    fn test_tuple_structs_build_and_match() {
+        let src = r#"
+            struct UserId(Int);
+            struct Pair<T>(T, T);
+            fn main() -> (UserId, Int, Bool) {
+                let id = UserId(41);
+                let UserId(n) = id;
+                let total = match Pair(n, 1) {
+                    Pair(0, _) => 0,
+                    Pair(a, b) => a + b,
+                };
+                (UserId(total), n, Pair(1, 2) == Pair(1, 2))
+            }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert_eq!(v.to_string(), "(UserId(42), 41, true)");
+    }
+
+    #[test]
+    fn test_resolution_decides_constructor_calls() {
+        // The parameter hides the constructor, and the variant path is
+        // the user's enum even though it is spelled like the prelude's
+        let src = r#"
+            struct Wrap(Int);
+            enum Option { None, Some(Int) }
+            fn apply(Wrap: fn(Int) -> Int, n: Int) -> Int { Wrap(n) }
+            fn inc(n: Int) -> Int { n + 1 }
+            fn main() -> (Int, Wrap, Option) {
+                (apply(inc, 1), Wrap(3), Option::None)
+            }
+        "#;
+        let m = strata_parse::parse_str("<test>", src).unwrap();
+        let v = run_module(&m).unwrap();
+        assert_eq!(v.to_string(), "(2, Wrap(3), Option::None)");
+    }
+
+    #[test]
+    fn test_imported_variants_construct_and_match() {
+        let src = r#"
+            use Option::*;
+            enum Shape { Dot, Line(Int) }
+            use Shape::Line;
+            fn len(s: Shape) -> Int { match s { Shape::Dot => 0, Line(n) => n } }
+            fn main() -> (Option<Int>, Option<Int>, Int) {
+                let none = match Some(1) { None => 0, Some(n) => n };
+                (Some(len(Line(3))), None, none)
+            }
+        "#;
+        let m = strata_parse::parse_str("<test>", src).unwrap();
+        let v = run_module(&m).unwrap();
+        assert_eq!(v.to_string(), "(Option::Some(3), Option::None, 1)");
+    }
+
+    #[test]
+    fn test_spawn_join_returns_task_results() {
+        let src = r#"
+            fn double(n: Int) -> Int { n * 2 }
+            fn main() -> (Int, Int, Int) {
+                let a = spawn { double(20) };
+                let b = spawn {
+                    let inner = spawn { 1 };
+                    join(inner) + 1
+                };
+                let unjoined = spawn { 3 };
+                let early = spawn { if true { return 5; } else { 6 } };
+                (join(a), join(b), join(early))
+            }
+        "#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert_eq!(v.to_string(), "(40, 2, 5)");
+    }
+
+    #[test]
+    fn test_unjoined_task_failure_fails_the_run() {
+        let src = r#"
+            fn main() -> Int {
+                let t = spawn { todo() };
+                1
+            }
+        "#;
+        let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::Todo);
+    }
+
+    #[test]
+    fn test_task_ids_follow_spawn_order() {
+        let root = TaskScope::default();
+        let first = root.child();
+        let second = root.child();
+        assert_eq!(first.id.as_deref(), Some("1"));
+        assert_eq!(second.id.as_deref(), Some("2"));
+        assert_eq!(second.child().id.as_deref(), Some("2.1"));
+        let branch = first.branch(1);
+        assert_eq!(branch.id.as_deref(), Some("1"));
+        assert_eq!(branch.child().id.as_deref(), Some("1.p1.1"));
+    }
+
+    #[test]
+    fn test_par_reports_the_first_failing_branch() {
+        let src = r#"
+            fn fail(n: Int) -> Int { if n > 0 { todo() } else { unreachable() } }
+            fn main() -> (Int, Int) { par(fail, 0, fail, 1) }
+        "#;
+        let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::Unreachable);
+    }
+
+    #[test]
+    fn test_todo_raises_structured_error_with_span() {
+        let src = "fn main() -> Int { if true { todo() } else { 1 } }";
+        let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::Todo);
+        assert_eq!(
+            err.span,
+            Some(Span {
+                start: 29,
+                end: 33,
+                ..Span::default()
+            })
+        );
+        assert_eq!(err.stack, ["main"]);
+        assert!(err.to_string().contains("RUN-TODO"), "got: {err}");
+    }
+
+    #[test]
+    fn test_runtime_errors_carry_kind_location_and_calls() {
+        let src = "fn ratio(a: Int, b: Int) -> Int { a / b }
+            fn main() -> Int { ratio(1, 0) }";
+        let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::DivisionByZero);
+        // The division itself, not the call to `ratio`
+        let span = err.span.expect("located");
+        assert_eq!(&src[span.start as usize..span.end as usize], "a / b");
+        assert_eq!(err.stack, ["ratio", "main"]);
+        assert!(!is_checker_escape(&err));
+    }
+
+    #[test]
+    fn test_integer_overflow_is_a_runtime_error() {
+        let min = "(-9223372036854775807 - 1)";
+        for (body, op) in [
+            ("9223372036854775807 + 1".to_string(), "+"),
+            (format!("{min} - 1"), "-"),
+            ("4611686018427387904 * 2".to_string(), "*"),
+            (format!("{min} / -1"), "/"),
+            (format!("-{min}"), "-"),
+        ] {
+            let src = format!("fn main() -> Int {{ {body} }}");
+            let err = run_module(&strata_parse::parse_str("<test>", &src).unwrap()).unwrap_err();
+            assert_eq!(err.kind, RuntimeErrorKind::IntegerOverflow { op }, "{body}");
+            assert!(err.span.is_some(), "{body}");
+        }
+
+        let src = "fn main() -> Int { -9223372036854775807 - 1 }";
+        let value = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(value, Value::Int(i64::MIN)));
+    }
+
+    #[test]
+    fn test_runtime_errors_point_at_the_subexpression_at_fault() {
+        // Programs the checker rejects, run anyway
+        let cases = [
+            (
+                "fn main() -> Int { let Option::Some(x) = Option::None; x }",
+                "Option::Some(x)",
+            ),
+            ("fn main() -> Int { if 1 { 2 } else { 3 } }", "1"),
+            ("fn main() -> Bool { true && 2 }", "2"),
+            ("fn main() -> Int { let f = 1; f(2) }", "f"),
+            ("fn main() -> Int { let x = 1; x = 2; x }", "x"),
+        ];
+        for (src, at) in cases {
+            let err = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap_err();
+            let span = err.span.unwrap_or_else(|| panic!("{src}: no span"));
+            assert_eq!(&src[span.start as usize..span.end as usize], at, "{src}");
+        }
+    }
+
+    #[test]
+    fn test_unreachable_only_fails_when_reached() {
+        let src = "enum E { A, B }
+            fn pick(e: E) -> Int { match e { E::A => 1, E::B => unreachable() } }
+            fn main() -> Int { pick(E::A) }";
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Int(1)));
+
+        let src = src.replace("pick(E::A)", "pick(E::B)");
+        let err = run_module(&strata_parse::parse_str("<test>", &src).unwrap()).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::Unreachable);
+        assert!(err.to_string().contains("RUN-UNREACHABLE"), "got: {err}");
+    }
+
+    #[test]
+    fn test_consts_evaluate_and_match_as_literals() {
+        let src = r#"const LIMIT: Int = BASE * 4;
+            const BASE: Int = 25;
+            const NAME: String = "big";
+            fn size(n: Int) -> String { match n { LIMIT => NAME, _ => "small" } }
+            fn main() -> String { size(LIMIT) }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "big"));
+
+        let src = src.replace("size(LIMIT)", "size(BASE)");
+        let v = run_module(&strata_parse::parse_str("<test>", &src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "small"));
+    }
+
+    #[test]
+    fn test_discriminants_round_trip() {
+        let src = r#"enum Color { Red = 1, Green, Blue = 10 }
+            fn code(n: Int) -> Int {
+                match Color::from_discriminant(n) {
+                    Option::Some(c) => discriminant(c),
+                    Option::None => 0 - 1,
+                }
+            }
+            fn main() -> Int { code(2) * 100 + code(10) + code(3) }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Int(209)));
+    }
+
+    #[test]
+    fn test_string_prefix_patterns_bind_the_remainder() {
+        let src = r#"fn route(cmd: String) -> String {
+                match cmd {
+                    "quit" => "bye",
+                    "get " + path => path,
+                    "put " + ("key=" + v) => v,
+                    _ => "unknown",
+                }
+            }
+            fn main() -> String { route("put key=hello") }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "hello"));
+
+        let src = src.replace("put key=hello", "get /index");
+        let v = run_module(&strata_parse::parse_str("<test>", &src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "/index"));
+
+        let src = src.replace("get /index", "put value");
+        let v = run_module(&strata_parse::parse_str("<test>", &src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "unknown"));
+    }
+
+    #[test]
+    fn test_nested_fns_recurse_and_capture_by_value() {
+        let src = r#"fn sum_to(n: Int, step: Int) -> Int {
+                let mut base = 100;
+                fn go(i: Int) -> Int {
+                    if i > n { base } else { i + go(i + step) }
+                }
+                base = 0;
+                go(1)
+            }
+            fn main() -> Int { sum_to(4, 1) }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        // 1 + 2 + 3 + 4, ending on `base` as it was when `go` was declared
+        assert!(matches!(v, Value::Int(110)));
+    }
+
+    #[test]
+    fn test_adjacent_nested_fns_call_each_other() {
+        let src = r#"fn collatz_steps(n: Int) -> Int {
+                fn step(k: Int, acc: Int) -> Int {
+                    if k == 1 { acc } else { halve_or_grow(k, acc + 1) }
+                }
+                fn halve_or_grow(k: Int, acc: Int) -> Int {
+                    if (k / 2) * 2 == k { step(k / 2, acc) } else { step(3 * k + 1, acc) }
+                }
+                step(n, 0)
+            }
+            fn main() -> Int { collatz_steps(6) }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(v, Value::Int(8)));
+    }
+
+    #[test]
+    fn test_string_escapes_evaluate_and_print_consistently() {
+        let src = r#"fn main() -> String { "tab\t\"q\" \u{e9}\n" }"#;
+        let v = run_module(&strata_parse::parse_str("<test>", src).unwrap()).unwrap();
+        assert!(matches!(&v, Value::Str(s) if s == "tab\t\"q\" é\n"));
+        // Printing re-escapes, so the output is the source literal again
+        assert_eq!(v.to_string(), r#""tab\t\"q\" é\n""#);
+
+        let raw = r##"fn main() -> String { r#"C:\dir "x""# }"##;
+        let v = run_module(&strata_parse::parse_str("<test>", raw).unwrap()).unwrap();
+        assert_eq!(v.to_string(), r#""C:\\dir \"x\"""#);
+    }
+
+    #[test]
+    fn test_linear_struct_value_is_affine() {
+        let linear: HashSet<String> = ["Token".to_string()].into_iter().collect();
+        let token = Value::Struct {
+            name: "Token".to_string(),
+            fields: HashMap::new(),
+        };
+        assert!(!token.is_affine());
+        assert!(token.is_affine_in(&linear));
+
+        let m = strata_parse::parse_str("<test>", "linear struct Token { }").unwrap();
+        let mut env = Env::new().with_linear_types(&m);
+        env.define("t".to_string(), token, false);
+        eval_expr(&mut env, &Expr::Var(ident("t"))).unwrap();
+        assert!(matches!(env.get("t").unwrap(), Value::Consumed { .. }));
+    }
+
     #[test]
     fn test_consumed_cap_gives_runtime_error() {
         let mut env = Env::new();
@@ -2477,7 +5046,11 @@ mod tests {
         // First use with identifiable span (becomes the "transferred at" span)
         let id1 = Ident {
             text: "net".to_string(),
-            span: Span { start: 42, end: 45 },
+            span: Span {
+                start: 42,
+                end: 45,
+                ..Span::default()
+            },
         };
         eval_expr(&mut env, &Expr::Var(id1)).unwrap();
 
@@ -2487,18 +5060,25 @@ mod tests {
             span: Span {
                 start: 100,
                 end: 103,
+                ..Span::default()
             },
         };
         let err = eval_expr(&mut env, &Expr::Var(id2)).unwrap_err();
         let msg = err.to_string();
-        // Error should include both spans
-        assert!(
-            msg.contains("previously transferred at: 42:45"),
-            "should include moved_at span, got: {msg}"
+        // The error is at the second use, with a note at the first
+        assert_eq!(err.span.map(|s| (s.start, s.end)), Some((100, 103)));
+        let notes: Vec<_> = err
+            .notes()
+            .into_iter()
+            .map(|(note, span)| (note, span.start, span.end))
+            .collect();
+        assert_eq!(
+            notes,
+            [("permission was transferred here".to_string(), 42, 45)]
         );
         assert!(
-            msg.contains("used at: 100:103"),
-            "should include use_span, got: {msg}"
+            !msg.contains("42"),
+            "message carries no offsets, got: {msg}"
         );
         assert!(
             msg.contains("CAP-MOVE-RUNTIME"),
@@ -2529,6 +5109,46 @@ mod tests {
         assert!(matches!(cf2, ControlFlow::Value(Value::Cap(CapKind::Fs))));
     }
 
+    #[test]
+    fn test_borrowed_match_does_not_tombstone() {
+        let mut env = Env::new();
+        env.define(
+            "t".to_string(),
+            Value::Tuple(Arc::new([Value::Cap(CapKind::Fs), Value::Int(42)])),
+            false,
+        );
+
+        // `match &t { (f, _) => (f, f) }`: the arm's `f` is borrowed too
+        let arm = MatchArm {
+            pat: Pat::Tuple(vec![Pat::Ident(ident("f")), Pat::Wildcard(sp())], sp()),
+            body: Expr::Tuple {
+                elems: vec![Expr::Var(ident("f")), Expr::Var(ident("f"))],
+                span: sp(),
+            },
+            span: sp(),
+        };
+        let match_expr = Expr::Match {
+            scrutinee: Box::new(Expr::Borrow(Box::new(Expr::Var(ident("t"))), sp())),
+            arms: vec![arm],
+            span: sp(),
+        };
+        eval_expr(&mut env, &match_expr).unwrap();
+        assert!(matches!(env.get("t"), Some(Value::Tuple(_))));
+
+        // Matching on `t` itself takes it
+        let Expr::Match { arms, .. } = match_expr else {
+            unreachable!()
+        };
+        let owned = Expr::Match {
+            scrutinee: Box::new(Expr::Var(ident("t"))),
+            arms,
+            span: sp(),
+        };
+        let err = eval_expr(&mut env, &owned).unwrap_err();
+        assert!(err.to_string().contains("CAP-MOVE-RUNTIME"), "{err}");
+        assert!(matches!(env.get("t"), Some(Value::Consumed { .. })));
+    }
+
     #[test]
     fn test_non_affine_values_unaffected() {
         let mut env = Env::new();
@@ -2635,7 +5255,7 @@ mod tests {
         let mut env = Env::new();
         env.define(
             "t".to_string(),
-            Value::Tuple(vec![Value::Cap(CapKind::Fs), Value::Int(42)]),
+            Value::Tuple(Arc::new([Value::Cap(CapKind::Fs), Value::Int(42)])),
             false,
         );
 
@@ -2656,7 +5276,7 @@ mod tests {
         let mut fields = HashMap::new();
         fields.insert(
             "inner".to_string(),
-            Value::Tuple(vec![Value::Cap(CapKind::Net)]),
+            Value::Tuple(Arc::new([Value::Cap(CapKind::Net)])),
         );
         env.define(
             "s".to_string(),
@@ -2682,7 +5302,7 @@ mod tests {
         let mut env = Env::new();
         env.define(
             "t".to_string(),
-            Value::Tuple(vec![Value::Int(1), Value::Str("hello".to_string())]),
+            Value::Tuple(Arc::new([Value::Int(1), Value::Str("hello".to_string())])),
             false,
         );
 
@@ -2698,11 +5318,11 @@ mod tests {
     #[test]
     fn test_is_affine_compound() {
         // Tuple with cap
-        assert!(Value::Tuple(vec![Value::Cap(CapKind::Fs), Value::Int(1)]).is_affine());
+        assert!(Value::Tuple(Arc::new([Value::Cap(CapKind::Fs), Value::Int(1)])).is_affine());
         // Tuple without cap
-        assert!(!Value::Tuple(vec![Value::Int(1), Value::Bool(true)]).is_affine());
+        assert!(!Value::Tuple(Arc::new([Value::Int(1), Value::Bool(true)])).is_affine());
         // Empty tuple
-        assert!(!Value::Tuple(vec![]).is_affine());
+        assert!(!Value::Tuple(Arc::new([])).is_affine());
 
         // Struct with cap in field
         let mut fields = HashMap::new();
@@ -0,0 +1,241 @@
+//! Where repeated runs of a program differ, for `strata run --repeat`.
+//!
+//! The runs' traces are lined up call by call: each task's (and `main`'s)
+//! n-th host call in one run against its n-th call in the others, so how
+//! the tasks happened to interleave isn't a difference. A call every run
+//! made alike that returned different results is a source of
+//! nondeterminism: the clock, randomness, or the world outside the
+//! program. The first call the runs made differently is where they went
+//! separate ways; the task's later calls follow from it and aren't
+//! reported.
+
+use std::collections::BTreeSet;
+use std::io::BufRead;
+
+use crate::host::{ReplayError, TraceEntry, TraceReader, TraceRecord};
+
+/// A host call that didn't go the same way in every run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The task that made the call, `None` for `main`
+    pub task: Option<String>,
+    /// The call's `seq` in the first run that made it
+    pub seq: u64,
+    /// `Time::now`
+    pub call: String,
+    pub kind: DivergenceKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// Every run made the call alike, and it returned `distinct` different
+    /// results (an error counting as a result)
+    Output { distinct: usize },
+    /// Run `run` (from 1) made another call here than the first run, with
+    /// each call written out as `Fs::read_file(path: "a.txt")` (`None` for
+    /// no call)
+    Call {
+        first: Option<String>,
+        run: usize,
+        other: Option<String>,
+    },
+}
+
+impl Divergence {
+    /// What to do about a call returning different results.
+    pub fn hint(&self) -> Option<&'static str> {
+        if !matches!(self.kind, DivergenceKind::Output { .. }) {
+            return None;
+        }
+        Some(match self.call.split("::").next() {
+            Some("Time") => "fix the clock with `[time] fixed = \"...\"` in a --caps file",
+            Some("Rand") => "draw from a seed the program takes as an argument instead",
+            _ => "the world outside the program changed between runs; replay a trace to hold it still",
+        })
+    }
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "seq {}", self.seq)?;
+        if let Some(task) = &self.task {
+            write!(f, " (task {})", task)?;
+        }
+        match &self.kind {
+            DivergenceKind::Output { distinct } => {
+                write!(f, ": {} returned {} different results", self.call, distinct)
+            }
+            DivergenceKind::Call { first, run, other } => {
+                let made = |call: &Option<String>| match call {
+                    Some(call) => format!("called {}", call),
+                    None => "made no call".to_string(),
+                };
+                write!(f, ": run 1 {}; run {} {}", made(first), run, made(other))
+            }
+        }
+    }
+}
+
+/// The effect entries of the trace `reader` reads.
+pub fn read_entries(reader: impl BufRead) -> Result<Vec<TraceEntry>, ReplayError> {
+    let mut entries = Vec::new();
+    for record in TraceReader::new(reader) {
+        if let TraceRecord::Effect(entry) = record? {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// The host calls that went differently across `runs`, each the effect
+/// entries of one run's trace, in the order the first run made them.
+pub fn compare(runs: &[Vec<TraceEntry>]) -> Vec<Divergence> {
+    let mut tasks: Vec<Option<&str>> = Vec::new();
+    for entry in runs.iter().flatten() {
+        if !tasks.contains(&entry.task.as_deref()) {
+            tasks.push(entry.task.as_deref());
+        }
+    }
+
+    let mut divergences = Vec::new();
+    for task in tasks {
+        let calls: Vec<Vec<&TraceEntry>> = runs
+            .iter()
+            .map(|run| run.iter().filter(|e| e.task.as_deref() == task).collect())
+            .collect();
+        let len = calls.iter().map(Vec::len).max().unwrap_or(0);
+        for n in 0..len {
+            let nth: Vec<Option<&TraceEntry>> = calls.iter().map(|c| c.get(n).copied()).collect();
+            let first = nth[0];
+            let differs = nth
+                .iter()
+                .enumerate()
+                .skip(1)
+                .find(|(_, other)| !same_call(first, **other));
+            if let Some((run, other)) = differs {
+                let made = first.or(*other).expect("one of them made a call");
+                divergences.push(Divergence {
+                    task: task.map(str::to_string),
+                    seq: made.seq,
+                    call: call_name(made),
+                    kind: DivergenceKind::Call {
+                        first: first.map(describe),
+                        run: run + 1,
+                        other: other.map(describe),
+                    },
+                });
+                // The task's later calls follow from this one
+                break;
+            }
+            let first = first.expect("every run made the call");
+            let distinct = nth
+                .iter()
+                .flatten()
+                .map(|e| (&e.output.status, &e.output.value_hash))
+                .collect::<BTreeSet<_>>()
+                .len();
+            if distinct > 1 {
+                divergences.push(Divergence {
+                    task: task.map(str::to_string),
+                    seq: first.seq,
+                    call: call_name(first),
+                    kind: DivergenceKind::Output { distinct },
+                });
+            }
+        }
+    }
+    divergences.sort_by_key(|d| d.seq);
+    divergences
+}
+
+fn same_call(a: Option<&TraceEntry>, b: Option<&TraceEntry>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.operation == b.operation && a.inputs == b.inputs,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// `Fs::read_file`
+fn call_name(entry: &TraceEntry) -> String {
+    format!("{}::{}", entry.effect, entry.operation)
+}
+
+/// `Fs::read_file(path: "a.txt")`
+fn describe(entry: &TraceEntry) -> String {
+    let inputs: Vec<String> = entry
+        .inputs
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value.to_value()))
+        .collect();
+    format!("{}({})", call_name(entry), inputs.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trace entry for `effect::operation(path)` returning `output`
+    fn entry(seq: u64, task: Option<&str>, call: &str, path: &str, output: &str) -> String {
+        let (effect, operation) = call.split_once("::").unwrap();
+        let task = task.map_or(String::new(), |t| format!(r#","task":"{}""#, t));
+        format!(
+            r#"{{"record":"effect","seq":{seq},"timestamp":"2026-01-01T00:00:00.000Z","effect":"{effect}","operation":"{operation}","capability":{{"kind":"{effect}Cap","access":"borrow"}},"inputs":{{"path":{{"t":"Str","v":"{path}"}}}},"output":{{"status":"ok","value":{{"t":"Str","v":"{output}"}},"value_hash":"sha256:{output}","value_size":1}},"duration_ms":0,"full_values":true{task}}}"#
+        )
+    }
+
+    fn run(lines: &[String]) -> Vec<TraceEntry> {
+        read_entries(lines.join("\n").as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn differing_results_and_calls_are_found() {
+        let runs = [
+            run(&[
+                entry(0, None, "Time::now", "", "10"),
+                entry(1, None, "Fs::read_file", "a", "x"),
+                entry(2, None, "Fs::read_file", "b", "y"),
+            ]),
+            run(&[
+                entry(0, None, "Time::now", "", "11"),
+                entry(1, None, "Fs::read_file", "a", "x"),
+                entry(2, None, "Fs::write_file", "b", "y"),
+                entry(3, None, "Fs::read_file", "c", "z"),
+            ]),
+        ];
+        let found: Vec<String> = compare(&runs).iter().map(Divergence::to_string).collect();
+        assert_eq!(
+            found,
+            [
+                "seq 0: Time::now returned 2 different results",
+                "seq 2: run 1 called Fs::read_file(path: \"b\"); \
+                 run 2 called Fs::write_file(path: \"b\")",
+            ]
+        );
+        assert!(compare(&runs)[0].hint().unwrap().contains("[time] fixed"));
+    }
+
+    #[test]
+    fn task_interleaving_is_not_a_difference() {
+        let runs = [
+            run(&[
+                entry(0, Some("1"), "Fs::read_file", "a", "x"),
+                entry(1, Some("2"), "Fs::read_file", "b", "y"),
+            ]),
+            run(&[
+                entry(0, Some("2"), "Fs::read_file", "b", "y"),
+                entry(1, Some("1"), "Fs::read_file", "a", "x"),
+            ]),
+        ];
+        assert!(compare(&runs).is_empty());
+
+        let fewer = [
+            run(&[entry(0, None, "Rand::random_int", "", "4")]),
+            run(&[]),
+        ];
+        assert_eq!(
+            compare(&fewer)[0].to_string(),
+            "seq 0: run 1 called Rand::random_int(path: \"\"); run 2 made no call"
+        );
+    }
+}
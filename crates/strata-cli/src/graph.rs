@@ -0,0 +1,341 @@
+//! Graphviz DOT output for `strata graph`.
+//!
+//! - `adt`: struct/enum composition. An edge `A -> B` labeled with a field or
+//!   variant name means a value of `A` holds a `B`. Capabilities held by
+//!   linear types appear as their own nodes.
+//! - `calls`: which top-level functions call which, including externs.
+//! - `effects`: how effects propagate. Each function is labeled with its
+//!   resolved effect row, each extern points at the effects it performs, and
+//!   call edges are labeled with the effects the callee contributes.
+//!
+//! Nodes and edges are emitted in source order so output is deterministic.
+
+use std::collections::HashMap;
+use strata_ast::ast::{Block, Expr, Item, Module, Stmt};
+use strata_types::adt::{is_capability_type, AdtKind, AdtRegistry, VariantFields};
+use strata_types::infer::Ty;
+use strata_types::{EffectRow, TypedItem, TypedItemKind};
+
+/// Call edges between top-level functions, as `(caller, callee)` pairs in
+/// source order without duplicates. Only direct calls by name count; a
+/// function passed as a value is not a call.
+pub fn call_graph(module: &Module) -> Vec<(String, String)> {
+    let fns: Vec<&str> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(d) => Some(d.name.text.as_str()),
+            Item::ExternFn(d) => Some(d.name.text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut edges = Vec::new();
+    for item in &module.items {
+        let Item::Fn(decl) = item else { continue };
+        let mut callees = Vec::new();
+        collect_calls_block(&decl.body, &mut callees);
+        for callee in callees {
+            if fns.contains(&callee.as_str()) {
+                let edge = (decl.name.text.clone(), callee);
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+    }
+    edges
+}
+
+fn collect_calls_block(block: &Block, out: &mut Vec<String>) {
+    for stmt in &block.stmts {
+        match stmt {
+            Stmt::Let { value, .. } | Stmt::Assign { value, .. } => collect_calls(value, out),
+            Stmt::Expr { expr, .. } => collect_calls(expr, out),
+            Stmt::Declare { .. } => {}
+            // A nested function's calls are made on behalf of its enclosing one
+            Stmt::Fn(decl) => collect_calls_block(&decl.body, out),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    collect_calls(value, out);
+                }
+            }
+        }
+    }
+    if let Some(tail) = &block.tail {
+        collect_calls(tail, out);
+    }
+}
+
+fn collect_calls(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::Call { callee, args, .. } => {
+            match callee.as_ref() {
+                Expr::Var(id) => out.push(id.text.clone()),
+                other => collect_calls(other, out),
+            }
+            for arg in args {
+                collect_calls(arg, out);
+            }
+        }
+        Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+        Expr::Unary { expr, .. }
+        | Expr::Paren { inner: expr, .. }
+        | Expr::Borrow(expr, _)
+        | Expr::Field { expr, .. } => collect_calls(expr, out),
+        Expr::Binary { lhs, rhs, .. } => {
+            collect_calls(lhs, out);
+            collect_calls(rhs, out);
+        }
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => collect_calls_block(block, out),
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            collect_calls(cond, out);
+            collect_calls_block(then_, out);
+            if let Some(else_) = else_ {
+                collect_calls(else_, out);
+            }
+        }
+        Expr::While { cond, body, .. }
+        | Expr::For {
+            iter: cond, body, ..
+        } => {
+            collect_calls(cond, out);
+            collect_calls_block(body, out);
+        }
+        Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            collect_calls(scrutinee, out);
+            for arm in arms {
+                collect_calls(&arm.body, out);
+            }
+        }
+        Expr::Tuple { elems, .. } => {
+            for elem in elems {
+                collect_calls(elem, out);
+            }
+        }
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            for field in fields {
+                collect_calls(&field.value, out);
+            }
+        }
+    }
+}
+
+/// DOT for struct/enum composition, using the checked definitions in
+/// `registry`. Built-in tuple and record types are looked through, not drawn.
+pub fn adt_dot(module: &Module, registry: &AdtRegistry) -> String {
+    let mut dot = Dot::new("adt");
+    for item in &module.items {
+        let (name, linear) = match item {
+            Item::Struct(d) => (&d.name.text, d.linear),
+            Item::Enum(d) => (&d.name.text, d.linear),
+            _ => continue,
+        };
+        let Some(def) = registry.get(name) else {
+            continue;
+        };
+        let kind = match def.kind {
+            AdtKind::Struct(_) => "struct",
+            AdtKind::Enum(_) => "enum",
+        };
+        let prefix = if linear { "linear " } else { "" };
+        dot.node(name, &format!("{}{} {}", prefix, kind, name), "box");
+
+        let parts: Vec<(&str, Vec<&Ty>)> = match &def.kind {
+            AdtKind::Struct(fields) => fields
+                .iter()
+                .map(|f| (f.name.as_str(), vec![&f.ty]))
+                .collect(),
+            AdtKind::Enum(variants) => variants
+                .iter()
+                .map(|v| match &v.fields {
+                    VariantFields::Unit => (v.name.as_str(), vec![]),
+                    VariantFields::Tuple(tys) => (v.name.as_str(), tys.iter().collect()),
+                })
+                .collect(),
+        };
+        for (label, tys) in parts {
+            let mut targets = Vec::new();
+            for ty in tys {
+                referenced_types(ty, &mut targets);
+            }
+            for target in targets {
+                if is_capability_type(&target) {
+                    dot.node(&target, &target, "diamond");
+                }
+                dot.edge(name, &target, Some(label));
+            }
+        }
+    }
+    dot.finish()
+}
+
+/// Names of ADTs and capabilities a type mentions, without duplicates.
+fn referenced_types(ty: &Ty, out: &mut Vec<String>) {
+    let mut push = |name: String| {
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    };
+    match ty {
+        Ty::Adt { name, args } => {
+            // Tuples desugar to builtin TupleN structs; show their elements
+            let is_tuple = name
+                .strip_prefix("Tuple")
+                .is_some_and(|n| n.parse::<u8>().is_ok());
+            if !is_tuple {
+                push(name.clone());
+            }
+            for arg in args {
+                referenced_types(arg, out);
+            }
+        }
+        Ty::Cap(kind) => push(kind.type_name().to_string()),
+        Ty::Arrow(params, ret, _) => {
+            for p in params {
+                referenced_types(p, out);
+            }
+            referenced_types(ret, out);
+        }
+        Ty::Tuple(elems) => {
+            for e in elems {
+                referenced_types(e, out);
+            }
+        }
+        Ty::Record(fields, _) => {
+            for f in fields.values() {
+                referenced_types(f, out);
+            }
+        }
+        Ty::List(inner) | Ty::Ref(inner) => referenced_types(inner, out),
+        _ => {}
+    }
+}
+
+/// DOT for the call graph between top-level functions.
+pub fn calls_dot(module: &Module) -> String {
+    let mut dot = Dot::new("calls");
+    for item in &module.items {
+        match item {
+            Item::Fn(d) => dot.node(&d.name.text, &d.name.text, "box"),
+            Item::ExternFn(d) => {
+                dot.node(&d.name.text, &format!("extern {}", d.name.text), "ellipse")
+            }
+            _ => {}
+        }
+    }
+    for (caller, callee) in call_graph(module) {
+        dot.edge(&caller, &callee, None);
+    }
+    dot.finish()
+}
+
+/// DOT for effect propagation, using the resolved function types from the
+/// checker.
+pub fn effects_dot(module: &Module, items: &[TypedItem]) -> String {
+    let rows: HashMap<&str, &EffectRow> = items
+        .iter()
+        .filter_map(|item| match &item.ty {
+            Ty::Arrow(_, _, eff) if item.kind != TypedItemKind::Let => {
+                Some((item.name.as_str(), eff))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut dot = Dot::new("effects");
+    for item in items {
+        let Some(row) = rows.get(item.name.as_str()) else {
+            continue;
+        };
+        let (prefix, shape) = match item.kind {
+            TypedItemKind::ExternFn => ("extern ", "ellipse"),
+            _ => ("", "box"),
+        };
+        dot.node(
+            &item.name,
+            &format!("{}{} & {{{}}}", prefix, item.name, effect_names(row)),
+            shape,
+        );
+        // Externs are where effects enter the program
+        if item.kind == TypedItemKind::ExternFn {
+            for effect in row.iter() {
+                let effect = format!("{:?}", effect);
+                dot.node(&effect, &effect, "diamond");
+                dot.edge(&item.name, &effect, None);
+            }
+        }
+    }
+    for (caller, callee) in call_graph(module) {
+        let Some(row) = rows.get(callee.as_str()) else {
+            continue;
+        };
+        // Pure callees contribute nothing to the caller's row
+        if row.iter().next().is_some() {
+            dot.edge(&caller, &callee, Some(&effect_names(row)));
+        }
+    }
+    dot.finish()
+}
+
+/// Concrete effects of a row, comma-separated. An unresolved tail variable
+/// is left out: it stands for effects no call in the program supplies.
+fn effect_names(row: &EffectRow) -> String {
+    let names: Vec<String> = row.iter().map(|e| format!("{:?}", e)).collect();
+    names.join(", ")
+}
+
+/// Minimal DOT writer that emits each node once.
+struct Dot {
+    out: String,
+    nodes: Vec<String>,
+}
+
+impl Dot {
+    fn new(name: &str) -> Self {
+        Self {
+            out: format!("digraph {} {{\n  rankdir=LR;\n", name),
+            nodes: Vec::new(),
+        }
+    }
+
+    fn node(&mut self, id: &str, label: &str, shape: &str) {
+        if self.nodes.iter().any(|n| n == id) {
+            return;
+        }
+        self.nodes.push(id.to_string());
+        self.out.push_str(&format!(
+            "  {} [label={}, shape={}];\n",
+            quote(id),
+            quote(label),
+            shape
+        ));
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: Option<&str>) {
+        match label {
+            Some(label) => self.out.push_str(&format!(
+                "  {} -> {} [label={}];\n",
+                quote(from),
+                quote(to),
+                quote(label)
+            )),
+            None => self
+                .out
+                .push_str(&format!("  {} -> {};\n", quote(from), quote(to))),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("}\n");
+        self.out
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -5,12 +5,17 @@
 //! trace emission: every host call records effect, operation, capability
 //! access, inputs, output (with SHA-256 hashing), and duration.
 
-use std::collections::{BTreeMap, HashMap};
-use std::io::Write;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{BufRead, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
 
-use strata_types::CapKind;
+use strata_types::infer::Ty;
+use strata_types::{CapKind, Effect, EffectRow};
 
+use crate::audit::AuditLog;
+use crate::caps::CapsConfig;
 use crate::eval::Value;
+use crate::seal::{self, SealError, SealKeys, SealLine, Sealer};
 
 /// Errors from host function execution.
 #[derive(Debug)]
@@ -25,6 +30,10 @@ pub enum HostError {
     RuntimeError(String),
     /// Trace write failure — execution must abort
     TraceWriteError(String),
+    /// Effect refused by the run's `EffectPolicy`
+    PolicyDenied(String),
+    /// Audit log write failure — execution must abort
+    AuditWriteError(String),
 }
 
 impl std::fmt::Display for HostError {
@@ -37,6 +46,10 @@ impl std::fmt::Display for HostError {
             HostError::TraceWriteError(msg) => {
                 write!(f, "trace write error (execution aborted): {}", msg)
             }
+            HostError::PolicyDenied(msg) => write!(f, "denied by effect policy: {}", msg),
+            HostError::AuditWriteError(msg) => {
+                write!(f, "audit log write error (execution aborted): {}", msg)
+            }
         }
     }
 }
@@ -59,6 +72,10 @@ pub enum TraceValue {
     Str(String),
     Bool(bool),
     Unit,
+    /// A value `#[redact]` hides: its hash, or `None` if masked
+    Redacted(Option<String>),
+    /// The arguments a variadic parameter collected
+    List(Vec<TraceValue>),
 }
 
 impl TraceValue {
@@ -77,6 +94,17 @@ impl TraceValue {
         }
     }
 
+    /// Convert the arguments a variadic parameter collected, which reach
+    /// the host packed in a tuple, to a list.
+    pub fn from_variadic(val: &Value) -> Self {
+        match val {
+            Value::Tuple(items) => {
+                TraceValue::List(items.iter().map(TraceValue::from_value).collect())
+            }
+            other => TraceValue::from_value(other),
+        }
+    }
+
     /// Convert a TraceValue back to a runtime Value.
     pub fn to_value(&self) -> Value {
         match self {
@@ -85,6 +113,31 @@ impl TraceValue {
             TraceValue::Str(s) => Value::Str(s.clone()),
             TraceValue::Bool(b) => Value::Bool(*b),
             TraceValue::Unit => Value::Unit,
+            TraceValue::Redacted(_) => Value::Str(self.to_hash_string()),
+            TraceValue::List(items) => Value::Tuple(items.iter().map(Self::to_value).collect()),
+        }
+    }
+
+    /// This value as `#[redact]` records it under `mode`. The hash covers
+    /// the tagged value, so `Str("42")` and `Int(42)` hash differently.
+    pub fn redacted(&self, mode: RedactMode) -> Self {
+        match mode {
+            RedactMode::Hash => {
+                let tagged = serde_json::to_string(self).unwrap_or_default();
+                TraceValue::Redacted(Some(sha256_hex(&tagged)))
+            }
+            RedactMode::Mask => TraceValue::Redacted(None),
+        }
+    }
+
+    /// Whether `actual`, seen on replay, matches this recorded value: it's
+    /// equal, or for a hashed value it has the same hash. A masked value
+    /// matches anything.
+    pub fn matches(&self, actual: &TraceValue) -> bool {
+        match self {
+            TraceValue::Redacted(None) => true,
+            TraceValue::Redacted(Some(_)) => actual.redacted(RedactMode::Hash) == *self,
+            _ => self == actual,
         }
     }
 
@@ -96,6 +149,12 @@ impl TraceValue {
             TraceValue::Str(s) => s.clone(),
             TraceValue::Bool(b) => b.to_string(),
             TraceValue::Unit => "()".to_string(),
+            TraceValue::Redacted(Some(hash)) => hash.clone(),
+            TraceValue::Redacted(None) => "<redacted>".to_string(),
+            TraceValue::List(items) => {
+                let items: Vec<String> = items.iter().map(Self::to_hash_string).collect();
+                format!("[{}]", items.join(", "))
+            }
         }
     }
 }
@@ -115,6 +174,11 @@ pub struct TraceEntry {
     /// Replay requires full_values=true.
     #[serde(default)]
     pub full_values: bool,
+    /// The `spawn`ed task that made the call (`"2"`, `"2.1"`, ...), or
+    /// none for `main`'s own calls. With `seq`, this records how the tasks
+    /// interleaved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
 }
 
 /// Reference to the capability used in a host call.
@@ -163,6 +227,41 @@ pub struct TraceHeader {
     pub schema_version: String,
     pub timestamp: String,
     pub full_values: bool,
+    /// Command-line arguments passed to the program. In audit mode,
+    /// arguments > 1KB are replaced with their SHA-256 hash.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// [`source_hash`] of the program the trace was recorded from
+    #[serde(default)]
+    pub source_hash: Option<String>,
+    /// Version of `strata` that recorded the trace
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    /// Type-checking profile the program ran under
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// The `strata` command line that recorded the trace, up to the
+    /// program's arguments
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+/// What a trace is recorded from, for its header. The compiler version is
+/// filled in by the emitter.
+#[derive(Debug, Clone, Default)]
+pub struct TraceMeta {
+    /// [`source_hash`] of the program's source
+    pub source_hash: Option<String>,
+    /// Type-checking profile the program runs under
+    pub profile: Option<String>,
+    /// The `strata` command line, up to the program's arguments
+    pub flags: Vec<String>,
+}
+
+/// The hash a trace header records of a program's source, as
+/// `sha256:<hex>`.
+pub fn source_hash(source: &str) -> String {
+    sha256_hex(source)
 }
 
 /// Trace footer — last line of the JSONL stream.
@@ -174,6 +273,10 @@ pub struct TraceFooter {
     pub trace_status: String,
     /// "success" or "error".
     pub program_status: String,
+    /// What `main` returned, if the program succeeded. Absent from traces
+    /// recorded before it was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<TraceValue>,
 }
 
 // ---------------------------------------------------------------------------
@@ -192,6 +295,10 @@ pub struct TraceEmitter {
     seq: u64,
     writer: Option<Box<dyn Write + Send>>,
     full_values: bool,
+    /// Entries held back by a [`fork`](Self::fork) until it is joined
+    pending: Option<Vec<TraceEntry>>,
+    /// Encrypts and signs the lines as they're written
+    sealer: Sealer,
 }
 
 impl std::fmt::Debug for TraceEmitter {
@@ -199,6 +306,7 @@ impl std::fmt::Debug for TraceEmitter {
         f.debug_struct("TraceEmitter")
             .field("seq", &self.seq)
             .field("enabled", &self.writer.is_some())
+            .field("forked", &self.pending.is_some())
             .finish()
     }
 }
@@ -215,22 +323,48 @@ impl TraceEmitter {
     /// of size (for replay-capable traces). When false, values > 1KB are
     /// replaced with their SHA-256 hash.
     ///
-    /// Emits a header record immediately. Returns error if header write fails.
-    pub fn new(mut writer: Box<dyn Write + Send>, full_values: bool) -> Result<Self, HostError> {
+    /// Emits a header record (recording the program's `args` and `meta`)
+    /// immediately. Every line is sealed with `seal` (see [`crate::seal`]).
+    /// Returns error if header write fails.
+    pub fn new(
+        mut writer: Box<dyn Write + Send>,
+        full_values: bool,
+        args: &[String],
+        meta: &TraceMeta,
+        seal: SealKeys,
+    ) -> Result<Self, HostError> {
+        let mut sealer = Sealer::new(seal);
+        let args = args
+            .iter()
+            .map(|a| {
+                if full_values || a.len() <= 1024 {
+                    a.clone()
+                } else {
+                    sha256_hex(a)
+                }
+            })
+            .collect();
         // Write header as first record
         let header = TraceRecord::Header(TraceHeader {
             schema_version: TRACE_SCHEMA_VERSION.to_string(),
             timestamp: now_iso8601(),
             full_values,
+            args,
+            source_hash: meta.source_hash.clone(),
+            compiler_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            profile: meta.profile.clone(),
+            flags: meta.flags.clone(),
         });
         let json = serde_json::to_string(&header)
             .map_err(|e| HostError::TraceWriteError(format!("serialize header: {}", e)))?;
-        writeln!(writer, "{}", json)
+        writeln!(writer, "{}", sealer.seal(&json))
             .map_err(|e| HostError::TraceWriteError(format!("write header: {}", e)))?;
         Ok(Self {
             seq: 0,
             writer: Some(writer),
             full_values,
+            pending: None,
+            sealer,
         })
     }
 
@@ -240,9 +374,35 @@ impl TraceEmitter {
             seq: 0,
             writer: None,
             full_values: false,
+            pending: None,
+            sealer: Sealer::default(),
+        }
+    }
+
+    /// An emitter for calls made on another thread. It holds its entries
+    /// until [`join`](Self::join)ed back, so the trace doesn't depend on how
+    /// the threads interleave. Forking a disabled emitter gives another.
+    pub fn fork(&self) -> Self {
+        let enabled = self.writer.is_some() || self.pending.is_some();
+        Self {
+            seq: 0,
+            writer: None,
+            full_values: self.full_values,
+            pending: enabled.then(Vec::new),
+            sealer: Sealer::default(),
         }
     }
 
+    /// Emit the entries held by `fork`, in the order it recorded them,
+    /// numbered after this emitter's own.
+    pub fn join(&mut self, fork: TraceEmitter) -> Result<(), HostError> {
+        for mut entry in fork.pending.unwrap_or_default() {
+            entry.seq = self.next_seq();
+            self.emit(entry)?;
+        }
+        Ok(())
+    }
+
     /// Whether this tracer records full values (no size-based hashing).
     pub fn full_values(&self) -> bool {
         self.full_values
@@ -259,32 +419,45 @@ impl TraceEmitter {
     ///
     /// Returns error if serialization or writing fails — caller must abort.
     pub fn emit(&mut self, entry: TraceEntry) -> Result<(), HostError> {
-        if let Some(ref mut w) = self.writer {
+        if let Some(pending) = &mut self.pending {
+            pending.push(entry);
+        } else if let Some(ref mut w) = self.writer {
             let record = TraceRecord::Effect(entry);
             let json = serde_json::to_string(&record)
                 .map_err(|e| HostError::TraceWriteError(format!("serialize effect: {}", e)))?;
-            writeln!(w, "{}", json)
+            writeln!(w, "{}", self.sealer.seal(&json))
                 .map_err(|e| HostError::TraceWriteError(format!("write effect: {}", e)))?;
         }
         Ok(())
     }
 
-    /// Write the footer record and flush. Call this when the program completes.
+    /// Write the footer record (and, for a signed trace, the signature) and
+    /// flush. Call this when the program completes.
     ///
-    /// `program_status` should be "success" or "error".
+    /// `program_status` should be "success" or "error"; `result` is what
+    /// `main` returned, if it did.
     /// Returns error if serialization, writing, or flushing fails.
-    pub fn finalize(&mut self, program_status: &str) -> Result<(), HostError> {
+    pub fn finalize(
+        &mut self,
+        program_status: &str,
+        result: Option<&Value>,
+    ) -> Result<(), HostError> {
         if let Some(ref mut w) = self.writer {
             let footer = TraceRecord::Footer(TraceFooter {
                 timestamp: now_iso8601(),
                 effect_count: self.seq,
                 trace_status: "complete".to_string(),
                 program_status: program_status.to_string(),
+                result: result.map(TraceValue::from_value),
             });
             let json = serde_json::to_string(&footer)
                 .map_err(|e| HostError::TraceWriteError(format!("serialize footer: {}", e)))?;
-            writeln!(w, "{}", json)
+            writeln!(w, "{}", self.sealer.seal(&json))
                 .map_err(|e| HostError::TraceWriteError(format!("write footer: {}", e)))?;
+            for line in self.sealer.finish() {
+                writeln!(w, "{}", line)
+                    .map_err(|e| HostError::TraceWriteError(format!("write seal: {}", e)))?;
+            }
             w.flush()
                 .map_err(|e| HostError::TraceWriteError(format!("flush trace: {}", e)))?;
         }
@@ -301,14 +474,166 @@ impl TraceEmitter {
 pub enum ParamKind {
     /// Capability parameter — records kind and borrow/consume access.
     Cap { kind: CapKind, borrowed: bool },
-    /// Data parameter — records the param name for trace inputs.
-    Data { name: String },
+    /// Data parameter — records the param name for trace inputs, whether
+    /// its type is a `transparent struct` the host sees through, how
+    /// `#[redact]` hides it in the trace, if it does, and whether it is
+    /// variadic, collecting the rest of the arguments.
+    Data {
+        name: String,
+        transparent: bool,
+        redact: Option<RedactMode>,
+        variadic: bool,
+    },
+}
+
+/// How a value `#[redact]` hides is written to the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactMode {
+    /// Its SHA-256 hash, which replay checks the live value against
+    Hash,
+    /// Nothing but the fact it was there; replay accepts any value
+    Mask,
 }
 
 /// Metadata for an extern fn's parameter list, derived from its type signature.
 #[derive(Debug, Clone)]
 pub struct ExternFnMeta {
     pub params: Vec<ParamKind>,
+    /// The `transparent struct` the return type names, if any; the host
+    /// returns the value it wraps.
+    pub returns_transparent: Option<String>,
+    /// How `#[redact]` hides the returned value (or error) in the trace, if
+    /// it does
+    pub redact_output: Option<RedactMode>,
+    /// The default values of the last parameters, which a call may leave
+    /// off
+    pub defaults: Vec<Value>,
+}
+
+impl ExternFnMeta {
+    /// Append the default values of the parameters a call left off, and
+    /// pack the arguments for a variadic parameter in one tuple, so the
+    /// host and the trace see one argument per parameter.
+    pub fn complete_args(&self, mut args: Vec<Value>) -> Vec<Value> {
+        let required = self.params.len().saturating_sub(self.defaults.len());
+        if let Some(passed) = args.len().checked_sub(required) {
+            args.extend(self.defaults.iter().skip(passed).cloned());
+        }
+        if let Some(ParamKind::Data { variadic: true, .. }) = self.params.last() {
+            let rest = args.split_off((self.params.len() - 1).min(args.len()));
+            args.push(Value::Tuple(rest.into()));
+        }
+        args
+    }
+
+    /// Replace each argument for a `transparent struct` parameter with the
+    /// value it wraps, as the host expects.
+    pub fn unwrap_args(&self, args: Vec<Value>) -> Vec<Value> {
+        args.into_iter()
+            .enumerate()
+            .map(|(i, arg)| match (self.params.get(i), arg) {
+                (
+                    Some(ParamKind::Data {
+                        transparent: true, ..
+                    }),
+                    Value::Struct { mut fields, .. },
+                ) => fields.remove("0").unwrap_or(Value::Unit),
+                (_, arg) => arg,
+            })
+            .collect()
+    }
+
+    /// Wrap a host result in the `transparent struct` the extern fn
+    /// declares it returns.
+    pub fn wrap_result(&self, value: Value) -> Value {
+        match &self.returns_transparent {
+            Some(name) => Value::Struct {
+                name: name.clone(),
+                fields: HashMap::from([("0".to_string(), value)]),
+            },
+            None => value,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// EffectPolicy
+// ---------------------------------------------------------------------------
+
+/// Effects the operator refuses a run (`--deny`, `--allow-only`), even if
+/// the program type-checks with them.
+///
+/// Enforced twice: `check_main()` rejects a program whose `main` needs a
+/// denied effect before it runs, and `HostRegistry` refuses host calls
+/// gated by one as defense in depth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EffectPolicy {
+    denied: EffectRow,
+}
+
+impl EffectPolicy {
+    /// Deny the given effects.
+    pub fn deny(effects: &[Effect]) -> Self {
+        let mut denied = EffectRow::pure();
+        for e in effects {
+            denied.insert(*e);
+        }
+        Self { denied }
+    }
+
+    /// Deny every effect except the given ones.
+    pub fn allow_only(effects: &[Effect]) -> Self {
+        let denied: Vec<Effect> = strata_types::ALL_EFFECTS
+            .iter()
+            .copied()
+            .filter(|e| !effects.contains(e))
+            .collect();
+        Self::deny(&denied)
+    }
+
+    /// Deny the effects either policy denies.
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            denied: self.denied.union(other.denied),
+        }
+    }
+
+    /// Whether `effect` is denied.
+    pub fn is_denied(&self, effect: Effect) -> bool {
+        self.denied.contains(effect)
+    }
+
+    /// Check `main`'s type: none of its capability parameters or declared
+    /// effects may be denied.
+    pub fn check_main(&self, main_ty: &Ty) -> Result<(), HostError> {
+        let Ty::Arrow(params, _, eff) = main_ty else {
+            return Ok(());
+        };
+        for param in params {
+            let kind = match param {
+                Ty::Cap(kind) => *kind,
+                Ty::Ref(inner) => match inner.as_ref() {
+                    Ty::Cap(kind) => *kind,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if self.is_denied(kind.gates_effect()) {
+                return Err(HostError::PolicyDenied(format!(
+                    "main requires {}, which grants {:?}",
+                    kind.type_name(),
+                    kind.gates_effect()
+                )));
+            }
+        }
+        if let Some(effect) = eff.iter().find(|e| self.is_denied(*e)) {
+            return Err(HostError::PolicyDenied(format!(
+                "main performs {:?}",
+                effect
+            )));
+        }
+        Ok(())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -316,12 +641,16 @@ pub struct ExternFnMeta {
 // ---------------------------------------------------------------------------
 
 /// Type alias for host function signatures.
-pub type HostFnImpl = fn(&[Value], &mut TraceEmitter) -> Result<Value, HostError>;
+/// Each receives the run's capability settings, which attenuate what it may do.
+pub type HostFnImpl = fn(&[Value], &CapsConfig) -> Result<Value, HostError>;
 
 /// Registry mapping extern fn names to Rust implementations.
 pub struct HostRegistry {
     functions: HashMap<String, HostFnImpl>,
     extern_meta: HashMap<String, ExternFnMeta>,
+    policy: EffectPolicy,
+    caps: CapsConfig,
+    audit: Option<Arc<Mutex<AuditLog>>>,
 }
 
 impl std::fmt::Debug for HostRegistry {
@@ -330,6 +659,9 @@ impl std::fmt::Debug for HostRegistry {
         f.debug_struct("HostRegistry")
             .field("functions", &names)
             .field("extern_meta_count", &self.extern_meta.len())
+            .field("policy", &self.policy)
+            .field("caps", &self.caps)
+            .field("audit", &self.audit)
             .finish()
     }
 }
@@ -346,14 +678,37 @@ impl HostRegistry {
         let mut reg = Self {
             functions: HashMap::new(),
             extern_meta: HashMap::new(),
+            policy: EffectPolicy::default(),
+            caps: CapsConfig::default(),
+            audit: None,
         };
         reg.register("read_file", host_read_file);
         reg.register("write_file", host_write_file);
         reg.register("now", host_now);
         reg.register("random_int", host_random_int);
+        reg.register("read_stdin", host_read_stdin);
+        reg.register("log", host_log);
         reg
     }
 
+    /// Refuse host calls gated by effects `policy` denies.
+    pub fn with_policy(mut self, policy: EffectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Attenuate host functions by the run's capability settings.
+    pub fn with_caps(mut self, caps: CapsConfig) -> Self {
+        self.caps = caps;
+        self
+    }
+
+    /// Record every capability-gated host call in `audit`.
+    pub fn with_audit_log(mut self, audit: Arc<Mutex<AuditLog>>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
     fn register(&mut self, name: &str, f: HostFnImpl) {
         self.functions.insert(name.to_string(), f);
     }
@@ -370,17 +725,12 @@ impl HostRegistry {
 
     /// Dispatch a host function call by name (data args only).
     /// Used internally by dispatch_traced() after cap/data separation.
-    fn call(
-        &self,
-        name: &str,
-        args: &[Value],
-        tracer: &mut TraceEmitter,
-    ) -> Result<Value, HostError> {
+    fn call(&self, name: &str, args: &[Value]) -> Result<Value, HostError> {
         let f = self
             .functions
             .get(name)
             .ok_or_else(|| HostError::UnknownFunction(name.to_string()))?;
-        f(args, tracer)
+        f(args, &self.caps)
     }
 
     /// Dispatch with trace emission.
@@ -388,17 +738,21 @@ impl HostRegistry {
     /// Walks the extern fn's type signature metadata to determine which
     /// positional args are capabilities vs data, extracts named inputs,
     /// calls the host function with data-only args, and emits a trace entry.
+    /// `task` names the `spawn`ed task making the call, if any. The tracer
+    /// is locked only to emit, so calls from other threads can run meanwhile.
     pub fn dispatch_traced(
         &self,
         name: &str,
         all_args: &[Value],
-        tracer: &mut TraceEmitter,
+        task: Option<&str>,
+        tracer: &Mutex<TraceEmitter>,
     ) -> Result<Value, HostError> {
         let meta = self.extern_meta.get(name);
 
         let mut cap_kind_str = String::new();
         let mut cap_access = String::new();
         let mut effect_str = String::new();
+        let mut denied = None;
         let mut data_args = Vec::new();
         let mut inputs = BTreeMap::new();
 
@@ -415,10 +769,26 @@ impl HostRegistry {
                     cap_kind_str = kind.type_name().to_string();
                     cap_access = if *borrowed { "borrow" } else { "consume" }.to_string();
                     effect_str = format!("{:?}", kind.gates_effect());
+                    if self.policy.is_denied(kind.gates_effect()) {
+                        denied = Some(kind.gates_effect());
+                    }
                 }
-                ParamKind::Data { name } => {
+                ParamKind::Data {
+                    name,
+                    redact,
+                    variadic,
+                    ..
+                } => {
                     if let Some(val) = all_args.get(i) {
-                        inputs.insert(name.clone(), TraceValue::from_value(val));
+                        let tv = match variadic {
+                            true => TraceValue::from_variadic(val),
+                            false => TraceValue::from_value(val),
+                        };
+                        let tv = match redact {
+                            Some(mode) => tv.redacted(*mode),
+                            None => tv,
+                        };
+                        inputs.insert(name.clone(), tv);
                         data_args.push(val.clone());
                     }
                 }
@@ -426,12 +796,43 @@ impl HostRegistry {
         }
 
         let start = std::time::Instant::now();
-        let result = self.call(name, &data_args, tracer);
+        // A denied call is refused, but still traced
+        let result = match denied {
+            Some(effect) => Err(HostError::PolicyDenied(format!(
+                "{} needs {:?}",
+                name, effect
+            ))),
+            None => self.call(name, &data_args),
+        };
         let duration = start.elapsed();
 
+        // The audit log records only capability-gated calls, without values
+        if let (Some(audit), false) = (&self.audit, cap_kind_str.is_empty()) {
+            let status = match (&result, denied) {
+                (_, Some(_)) => "denied",
+                (Ok(_), None) => "ok",
+                (Err(_), None) => "error",
+            };
+            audit
+                .lock()
+                .unwrap()
+                .call(name, &cap_kind_str, &cap_access, &effect_str, status)?;
+        }
+
+        let mut tracer = tracer.lock().unwrap();
         let full = tracer.full_values();
-        let (status, output_value, output_hash, output_size) = match &result {
-            Ok(val) => {
+        let (status, output_value, output_hash, output_size) = match (&result, meta.redact_output) {
+            // A redacted output is recorded as its redaction alone, with no
+            // hash or size of the value itself
+            (Ok(val), Some(mode)) => {
+                let tv = TraceValue::from_value(val).redacted(mode);
+                ("ok", Some(tv.clone()), tv.to_hash_string(), 0)
+            }
+            (Err(e), Some(mode)) => {
+                let tv = TraceValue::Str(e.to_string()).redacted(mode);
+                ("error", Some(tv.clone()), tv.to_hash_string(), 0)
+            }
+            (Ok(val), None) => {
                 let tv = TraceValue::from_value(val);
                 let hash_str = tv.to_hash_string();
                 let hash = sha256_hex(&hash_str);
@@ -439,7 +840,7 @@ impl HostRegistry {
                 let value = if full || size <= 1024 { Some(tv) } else { None };
                 ("ok", value, hash, size)
             }
-            Err(e) => {
+            (Err(e), None) => {
                 let err_str = e.to_string();
                 let hash = sha256_hex(&err_str);
                 let size = err_str.len();
@@ -476,6 +877,7 @@ impl HostRegistry {
             },
             duration_ms: duration.as_millis() as u64,
             full_values: full,
+            task: task.map(str::to_string),
         })?;
 
         result
@@ -510,6 +912,8 @@ pub enum ReplayError {
         seq: u64,
         value_size: usize,
     },
+    /// Output was redacted by `#[redact]`, so there's no value to replay.
+    RedactedOutput { operation: String, seq: u64 },
     /// Trace has entries that were never replayed.
     UnreplayedEffects(usize),
     /// The trace recorded an error; replay returns it.
@@ -518,8 +922,10 @@ pub enum ReplayError {
     UnknownStatus(String),
     /// Trace was recorded in audit mode and cannot be replayed.
     NotReplayable { seq: u64, reason: String },
-    /// JSONL parse error.
+    /// JSONL parse error, at a line numbered from 1.
     ParseError(usize, String),
+    /// A signed or encrypted trace read without opening it.
+    Sealed(seal::SealError),
     /// I/O error reading trace file.
     Io(String),
 }
@@ -559,6 +965,12 @@ impl std::fmt::Display for ReplayError {
                  Re-run with --trace-full to record complete values",
                 operation, seq, value_size
             ),
+            ReplayError::RedactedOutput { operation, seq } => write!(
+                f,
+                "cannot replay: output for '{}' at seq {} was redacted by `#[redact]`, \
+                 so the trace doesn't hold it",
+                operation, seq
+            ),
             ReplayError::UnreplayedEffects(n) => {
                 write!(f, "replay: trace has {} unreplayed entries", n)
             }
@@ -577,69 +989,207 @@ impl std::fmt::Display for ReplayError {
                 write!(f, "replay: parse error at line {}: {}", line, msg)
             }
             ReplayError::Io(msg) => write!(f, "replay: I/O error: {}", msg),
+            ReplayError::Sealed(e) => write!(f, "{}", e),
         }
     }
 }
 
 impl std::error::Error for ReplayError {}
 
+/// Reads a trace's records a line at a time from any `BufRead`, so the
+/// trace never has to be in memory whole. A bare entry (the format from
+/// before traces were versioned) reads as an effect record. Blank lines are
+/// skipped. An encrypted line is an error, and so is the signature ending a
+/// signed trace, which only [`crate::seal::open`] checks, unless the reader
+/// is [`unverified`](Self::unverified).
+#[derive(Debug)]
+pub struct TraceReader<R> {
+    reader: R,
+    /// Skip a signature rather than refusing it
+    unverified: bool,
+    /// Lines read so far
+    line: usize,
+    /// Bytes read so far, so where the next line starts
+    offset: u64,
+    buf: String,
+}
+
+impl<R: BufRead> TraceReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            unverified: false,
+            line: 0,
+            offset: 0,
+            buf: String::new(),
+        }
+    }
+
+    /// Skip the signature ending a signed trace, leaving it unchecked, if
+    /// `unverified`, as `--trace-unverified` asks.
+    pub fn unverified(mut self, unverified: bool) -> Self {
+        self.unverified = unverified;
+        self
+    }
+
+    /// Where the next record starts, in bytes from where reading began.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: BufRead> Iterator for TraceReader<R> {
+    type Item = Result<TraceRecord, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => self.offset += n as u64,
+                Err(e) => return Some(Err(ReplayError::Io(e.to_string()))),
+            }
+            self.line += 1;
+            let line = self.buf.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                continue;
+            }
+            // Try to parse as TraceRecord first (versioned format)
+            if let Ok(record) = serde_json::from_str::<TraceRecord>(line) {
+                return Some(Ok(record));
+            }
+            match seal::line_kind(line) {
+                Some(SealLine::Signature) if self.unverified => continue,
+                Some(SealLine::Signature) => {
+                    return Some(Err(ReplayError::Sealed(SealError::Unverified)))
+                }
+                Some(SealLine::Sealed) => {
+                    return Some(Err(ReplayError::Sealed(SealError::Encrypted)))
+                }
+                None => {}
+            }
+            // Fallback: a bare TraceEntry (pre-versioning format)
+            let entry = serde_json::from_str::<TraceEntry>(line)
+                .map(TraceRecord::Effect)
+                .map_err(|e| ReplayError::ParseError(self.line, e.to_string()));
+            return Some(entry);
+        }
+    }
+}
+
+/// The records of a trace still to be read
+type TraceRecords = Box<dyn Iterator<Item = Result<TraceRecord, ReplayError>> + Send>;
+
 /// Replays a previously recorded trace, substituting recorded outputs
 /// instead of calling real host functions.
-#[derive(Debug)]
+///
+/// A replayer [`from_reader`](Self::from_reader) reads the trace as replay
+/// goes, holding only the entries read ahead of the calls that replay them:
+/// for a program that doesn't fork, one at a time.
 pub struct TraceReplayer {
-    entries: Vec<TraceEntry>,
-    cursor: usize,
-    trace_complete: bool,
+    /// Entries read but not yet replayed, each with its index in the trace
+    pending: VecDeque<(u64, TraceEntry)>,
+    /// The rest of the trace, while it's read as replay goes
+    rest: Option<TraceRecords>,
+    /// Effect entries read so far
+    read: u64,
+    /// Effect entries replayed so far
+    replayed: u64,
+    /// Program arguments recorded in the header
+    args: Vec<String>,
+    /// The header, unless the trace predates them
+    header: Option<TraceHeader>,
+    /// The footer's record of how the program ended, if there was one
+    footer: Option<TraceFooter>,
+}
+
+impl std::fmt::Debug for TraceReplayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TraceReplayer")
+            .field("pending", &self.pending.len())
+            .field("streaming", &self.rest.is_some())
+            .field("read", &self.read)
+            .field("replayed", &self.replayed)
+            .finish()
+    }
 }
 
 impl TraceReplayer {
+    fn empty() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            rest: None,
+            read: 0,
+            replayed: 0,
+            args: Vec::new(),
+            header: None,
+            footer: None,
+        }
+    }
+
     /// Load a trace from JSONL content (one JSON object per line).
     ///
     /// Parses the `TraceRecord` envelope, extracts effect entries, and
     /// validates the header for replay capability.
     pub fn from_jsonl(content: &str) -> Result<Self, ReplayError> {
-        let mut entries = Vec::new();
-        let mut saw_header = false;
-        let mut saw_footer = false;
+        let mut replayer = Self::empty();
+        for record in TraceReader::new(content.as_bytes()) {
+            replayer.accept(record?)?;
+        }
+        Ok(replayer)
+    }
 
-        for (i, line) in content.lines().filter(|l| !l.is_empty()).enumerate() {
-            // Try to parse as TraceRecord first (versioned format)
-            if let Ok(record) = serde_json::from_str::<TraceRecord>(line) {
-                match record {
-                    TraceRecord::Header(h) => {
-                        saw_header = true;
-                        // Reject unknown schema versions
-                        if h.schema_version != TRACE_SCHEMA_VERSION {
-                            return Err(ReplayError::NotReplayable {
-                                seq: 0,
-                                reason: format!(
-                                    "unsupported trace schema version '{}' (expected '{}')",
-                                    h.schema_version, TRACE_SCHEMA_VERSION
-                                ),
-                            });
-                        }
-                        if !h.full_values {
-                            return Err(ReplayError::NotReplayable {
-                                seq: 0,
-                                reason: "trace was recorded with --trace (audit mode). \
-                                         Re-run with --trace-full for replay-capable traces"
-                                    .to_string(),
-                            });
-                        }
-                    }
-                    TraceRecord::Effect(entry) => {
-                        entries.push(entry);
-                    }
-                    TraceRecord::Footer(_) => {
-                        saw_footer = true;
-                    }
-                }
-            } else {
-                // Fallback: try parsing as a bare TraceEntry (pre-versioning format)
-                let entry: TraceEntry = serde_json::from_str(line)
-                    .map_err(|e| ReplayError::ParseError(i, e.to_string()))?;
+    /// A replayer that reads the trace from `reader` as replay goes, so a
+    /// trace of any length replays in bounded memory. The header is read
+    /// (and validated) now, the footer once replay reaches it.
+    pub fn from_reader(reader: impl BufRead + Send + 'static) -> Result<Self, ReplayError> {
+        Self::from_records(TraceReader::new(reader))
+    }
+
+    /// [`from_reader`](Self::from_reader), for a reader set up already
+    /// (say, to skip an unchecked signature).
+    pub fn from_records<R: BufRead + Send + 'static>(
+        mut records: TraceReader<R>,
+    ) -> Result<Self, ReplayError> {
+        let mut replayer = Self::empty();
+        // Legacy traces start with their first entry
+        while replayer.header.is_none() && replayer.pending.is_empty() {
+            match records.next() {
+                Some(record) => replayer.accept(record?)?,
+                None => return Ok(replayer),
+            }
+        }
+        replayer.rest = Some(Box::new(records));
+        Ok(replayer)
+    }
 
-                // Reject audit-mode entries
+    /// Take in the next record of the trace.
+    fn accept(&mut self, record: TraceRecord) -> Result<(), ReplayError> {
+        match record {
+            TraceRecord::Header(h) => {
+                // Reject unknown schema versions
+                if h.schema_version != TRACE_SCHEMA_VERSION {
+                    return Err(ReplayError::NotReplayable {
+                        seq: 0,
+                        reason: format!(
+                            "unsupported trace schema version '{}' (expected '{}')",
+                            h.schema_version, TRACE_SCHEMA_VERSION
+                        ),
+                    });
+                }
+                if !h.full_values {
+                    return Err(ReplayError::NotReplayable {
+                        seq: 0,
+                        reason: "trace was recorded with --trace (audit mode). \
+                                 Re-run with --trace-full for replay-capable traces"
+                            .to_string(),
+                    });
+                }
+                self.args = h.args.clone();
+                self.header = Some(h);
+            }
+            TraceRecord::Effect(entry) => {
+                // Reject audit-mode entries (legacy traces have no header)
                 if !entry.full_values {
                     return Err(ReplayError::NotReplayable {
                         seq: entry.seq,
@@ -648,53 +1198,119 @@ impl TraceReplayer {
                             .to_string(),
                     });
                 }
-                entries.push(entry);
+                self.pending.push_back((self.read, entry));
+                self.read += 1;
             }
+            TraceRecord::Footer(f) => self.footer = Some(f),
         }
+        Ok(())
+    }
 
-        // If we saw a header, this is a versioned trace — good
-        // If not, it's a legacy trace (pre-versioning) — still works
-        let _ = saw_header;
+    /// Read the trace up to its next effect entry, returning whether there
+    /// was one.
+    fn read_entry(&mut self) -> Result<bool, ReplayError> {
+        let read = self.read;
+        while self.read == read {
+            let Some(record) = self.rest.as_mut().and_then(Iterator::next) else {
+                self.rest = None;
+                return Ok(false);
+            };
+            self.accept(record?)?;
+        }
+        Ok(true)
+    }
 
-        Ok(Self {
-            entries,
-            cursor: 0,
-            trace_complete: saw_footer,
-        })
+    /// The program arguments recorded in the trace header (empty for
+    /// legacy traces).
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// The header recording what the trace was recorded from, if the trace
+    /// has one.
+    pub fn header(&self) -> Option<&TraceHeader> {
+        self.header.as_ref()
     }
 
-    /// Replay the next extern call. Validates operation and inputs match
-    /// the trace, then returns the recorded output.
+    /// The footer recording how the program ended, if the trace has one
+    /// (and, for a trace read as replay goes, replay has reached it).
+    pub fn footer(&self) -> Option<&TraceFooter> {
+        self.footer.as_ref()
+    }
+
+    /// How many of the trace's effect entries have been replayed.
+    pub fn replayed(&self) -> u64 {
+        self.replayed
+    }
+
+    /// Replay the next extern call made by `task` (`None` for `main`).
+    /// Validates operation and inputs match the trace, then returns the
+    /// recorded output.
+    ///
+    /// Each task replays its own calls in order, wherever the other tasks'
+    /// calls fell between them, so replay doesn't depend on scheduling.
     pub fn next(
         &mut self,
+        task: Option<&str>,
         operation: &str,
         inputs: &BTreeMap<String, TraceValue>,
     ) -> Result<Value, ReplayError> {
-        let entry = self
-            .entries
-            .get(self.cursor)
-            .ok_or_else(|| ReplayError::UnexpectedEffect(operation.to_string()))?;
+        let mut position = self
+            .pending
+            .iter()
+            .position(|(_, e)| e.task.as_deref() == task);
+        while position.is_none() {
+            if !self.read_entry()? {
+                return Err(ReplayError::UnexpectedEffect(operation.to_string()));
+            }
+            let (_, entry) = self.pending.back().expect("entry just read");
+            position = (entry.task.as_deref() == task).then(|| self.pending.len() - 1);
+        }
+        let position = position.expect("found above");
+        let (seq, entry) = &self.pending[position];
+        let seq = *seq;
 
         if entry.operation != operation {
             return Err(ReplayError::OperationMismatch {
                 expected: entry.operation.clone(),
                 actual: operation.to_string(),
-                seq: self.cursor as u64,
+                seq,
             });
         }
 
-        if entry.inputs != *inputs {
+        let inputs_match = entry.inputs.len() == inputs.len()
+            && entry
+                .inputs
+                .iter()
+                .all(|(name, recorded)| inputs.get(name).is_some_and(|v| recorded.matches(v)));
+        if !inputs_match {
+            // What the program passed for a redacted input stays hidden
+            let actual: BTreeMap<&String, TraceValue> = inputs
+                .iter()
+                .map(|(name, v)| match entry.inputs.get(name) {
+                    Some(TraceValue::Redacted(Some(_))) => (name, v.redacted(RedactMode::Hash)),
+                    Some(TraceValue::Redacted(None)) => (name, v.redacted(RedactMode::Mask)),
+                    _ => (name, v.clone()),
+                })
+                .collect();
             return Err(ReplayError::InputMismatch {
                 operation: operation.to_string(),
-                seq: self.cursor as u64,
+                seq,
                 expected: serde_json::to_value(&entry.inputs).unwrap_or_default(),
-                actual: serde_json::to_value(inputs).unwrap_or_default(),
+                actual: serde_json::to_value(actual).unwrap_or_default(),
             });
         }
 
-        self.cursor += 1;
+        let (_, entry) = self.pending.remove(position).expect("found above");
+        self.replayed += 1;
 
         match entry.output.status.as_str() {
+            "ok" if matches!(entry.output.value, Some(TraceValue::Redacted(_))) => {
+                Err(ReplayError::RedactedOutput {
+                    operation: operation.to_string(),
+                    seq,
+                })
+            }
             "ok" => {
                 let tv = entry
                     .output
@@ -702,7 +1318,7 @@ impl TraceReplayer {
                     .as_ref()
                     .ok_or_else(|| ReplayError::MissingValue {
                         operation: operation.to_string(),
-                        seq: (self.cursor - 1) as u64,
+                        seq,
                         value_size: entry.output.value_size,
                     })?;
                 Ok(tv.to_value())
@@ -720,12 +1336,20 @@ impl TraceReplayer {
         }
     }
 
-    /// Verify that all trace entries were replayed.
-    pub fn verify_complete(&self) -> Result<(), ReplayError> {
-        if self.cursor < self.entries.len() {
-            Err(ReplayError::UnreplayedEffects(
-                self.entries.len() - self.cursor,
-            ))
+    /// Verify that all trace entries were replayed. For a trace read as
+    /// replay goes, this reads the rest of it, counting rather than
+    /// holding the entries left over.
+    pub fn verify_complete(&mut self) -> Result<(), ReplayError> {
+        let mut unreplayed = self.pending.len();
+        for record in self.rest.take().into_iter().flatten() {
+            match record? {
+                TraceRecord::Effect(_) => unreplayed += 1,
+                TraceRecord::Footer(f) => self.footer = Some(f),
+                TraceRecord::Header(_) => {}
+            }
+        }
+        if unreplayed > 0 {
+            Err(ReplayError::UnreplayedEffects(unreplayed))
         } else {
             Ok(())
         }
@@ -734,7 +1358,76 @@ impl TraceReplayer {
     /// Whether the trace included a footer record (indicating clean completion).
     /// A missing footer means the trace may be truncated.
     pub fn is_trace_complete(&self) -> bool {
-        self.trace_complete
+        self.footer.is_some()
+    }
+}
+
+/// Where each effect entry of a trace starts, for reading any one of them
+/// without reading the entries before it. Building an index reads the
+/// trace once but keeps only the offsets, the header, and the footer.
+#[derive(Debug, Default)]
+pub struct TraceIndex {
+    offsets: Vec<u64>,
+    header: Option<TraceHeader>,
+    footer: Option<TraceFooter>,
+}
+
+impl TraceIndex {
+    /// Index the trace `reader` reads.
+    pub fn build(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut records = TraceReader::new(reader);
+        let mut index = Self::default();
+        loop {
+            let offset = records.offset();
+            let Some(record) = records.next() else {
+                return Ok(index);
+            };
+            match record? {
+                TraceRecord::Header(h) => index.header = Some(h),
+                TraceRecord::Effect(_) => index.offsets.push(offset),
+                TraceRecord::Footer(f) => index.footer = Some(f),
+            }
+        }
+    }
+
+    /// How many effect entries the trace has.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn header(&self) -> Option<&TraceHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn footer(&self) -> Option<&TraceFooter> {
+        self.footer.as_ref()
+    }
+
+    /// The effect entry at `index` (from 0, as replay errors number them),
+    /// read from `reader` over the trace this was built from. `None` past
+    /// the last entry.
+    pub fn entry<R: BufRead + Seek>(
+        &self,
+        reader: &mut R,
+        index: usize,
+    ) -> Result<Option<TraceEntry>, ReplayError> {
+        let Some(&offset) = self.offsets.get(index) else {
+            return Ok(None);
+        };
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| ReplayError::Io(e.to_string()))?;
+        match TraceReader::new(reader).next() {
+            Some(Ok(TraceRecord::Effect(entry))) => Ok(Some(entry)),
+            Some(Err(e)) => Err(e),
+            _ => Err(ReplayError::Io(
+                "the trace changed after it was indexed".to_string(),
+            )),
+        }
     }
 }
 
@@ -758,7 +1451,7 @@ fn sha256_hex(data: &str) -> String {
 ///
 /// Uses the standard civil-from-days algorithm to convert epoch seconds
 /// to year-month-day.
-fn now_iso8601() -> String {
+pub(crate) fn now_iso8601() -> String {
     use std::time::SystemTime;
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -794,7 +1487,7 @@ fn now_iso8601() -> String {
 // Host function implementations
 // ---------------------------------------------------------------------------
 
-fn host_read_file(args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, HostError> {
+fn host_read_file(args: &[Value], caps: &CapsConfig) -> Result<Value, HostError> {
     let path = match args.first() {
         Some(Value::Str(s)) => s,
         _ => {
@@ -803,13 +1496,13 @@ fn host_read_file(args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, H
             ))
         }
     };
-    match std::fs::read_to_string(path) {
+    match std::fs::read_to_string(caps.resolve_path(path)?) {
         Ok(content) => Ok(Value::Str(content)),
         Err(e) => Err(HostError::IoError(format!("read_file: {}", e))),
     }
 }
 
-fn host_write_file(args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, HostError> {
+fn host_write_file(args: &[Value], caps: &CapsConfig) -> Result<Value, HostError> {
     let path = match args.first() {
         Some(Value::Str(s)) => s,
         _ => {
@@ -826,13 +1519,16 @@ fn host_write_file(args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value,
             ))
         }
     };
-    match std::fs::write(path, content) {
+    match std::fs::write(caps.resolve_path(path)?, content) {
         Ok(()) => Ok(Value::Unit),
         Err(e) => Err(HostError::IoError(format!("write_file: {}", e))),
     }
 }
 
-fn host_now(_args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, HostError> {
+fn host_now(_args: &[Value], caps: &CapsConfig) -> Result<Value, HostError> {
+    if let Some(instant) = &caps.fixed_time {
+        return Ok(Value::Str(instant.clone()));
+    }
     use std::time::SystemTime;
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -844,7 +1540,7 @@ fn host_now(_args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, HostEr
     )))
 }
 
-fn host_random_int(_args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value, HostError> {
+fn host_random_int(_args: &[Value], _caps: &CapsConfig) -> Result<Value, HostError> {
     use std::time::SystemTime;
     let seed = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -852,3 +1548,48 @@ fn host_random_int(_args: &[Value], _tracer: &mut TraceEmitter) -> Result<Value,
         .subsec_nanos();
     Ok(Value::Int((seed % 1000) as i64))
 }
+
+/// Write a line to standard error: `log(&console, "{} of {}", x, y)`
+/// fills each `{}` with the next argument. Strings go in unquoted.
+fn host_log(args: &[Value], _caps: &CapsConfig) -> Result<Value, HostError> {
+    let (Some(Value::Str(fmt)), Some(Value::Tuple(values))) = (args.first(), args.get(1)) else {
+        return Err(HostError::RuntimeError(
+            "log: expected a format string and its arguments".into(),
+        ));
+    };
+    let pieces: Vec<&str> = fmt.split("{}").collect();
+    if pieces.len() != values.len() + 1 {
+        return Err(HostError::RuntimeError(format!(
+            "log: format has {} placeholders but {} arguments were passed",
+            pieces.len() - 1,
+            values.len()
+        )));
+    }
+    let mut line = pieces[0].to_string();
+    for (value, piece) in values.iter().zip(&pieces[1..]) {
+        match value {
+            Value::Str(s) => line.push_str(s),
+            other => line.push_str(&other.to_string()),
+        }
+        line.push_str(piece);
+    }
+    eprintln!("{}", line);
+    Ok(Value::Unit)
+}
+
+/// Read all of standard input. Input must be piped or redirected: an
+/// interactive terminal is refused rather than blocking on the user.
+fn host_read_stdin(_args: &[Value], _caps: &CapsConfig) -> Result<Value, HostError> {
+    use std::io::{IsTerminal, Read};
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(HostError::IoError(
+            "read_stdin: stdin is a terminal; pipe or redirect input to the program".into(),
+        ));
+    }
+    let mut content = String::new();
+    match stdin.read_to_string(&mut content) {
+        Ok(_) => Ok(Value::Str(content)),
+        Err(e) => Err(HostError::IoError(format!("read_stdin: {}", e))),
+    }
+}
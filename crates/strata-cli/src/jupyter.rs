@@ -0,0 +1,631 @@
+//! The Jupyter messaging protocol for `strata-kernel`, over ZeroMQ.
+//!
+//! The kernel binds the five sockets its connection file names. `shell`
+//! and `control` (ROUTER) take requests, `iopub` (PUB) broadcasts status
+//! and outputs to every subscriber, `hb` (REP) echoes heartbeats, and
+//! `stdin` (ROUTER) is bound but unused, since cells can't read input.
+//! Requests are handled one at a time by a [`Kernel`].
+
+use std::io;
+use std::sync::mpsc;
+use std::thread;
+
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value as Json};
+use sha2::Sha256;
+
+use crate::host::now_iso8601;
+use crate::notebook::{completeness, Completeness, Session};
+use crate::seal::{hex, unhex};
+
+/// Version of the messaging protocol spoken
+pub const PROTOCOL_VERSION: &str = "5.3";
+
+/// Separates a message's routing identities from its signed parts
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+/// How long, in milliseconds, messages still queued when the kernel shuts
+/// down are given to reach a frontend
+const LINGER_MS: i32 = 1000;
+
+/// How often, in milliseconds, the heartbeat thread checks whether the
+/// kernel has shut down
+const HEARTBEAT_POLL_MS: i64 = 100;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A kernel's connection file, written by the frontend that starts it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionInfo {
+    pub ip: String,
+    pub transport: String,
+    pub shell_port: u16,
+    pub iopub_port: u16,
+    pub stdin_port: u16,
+    pub control_port: u16,
+    pub hb_port: u16,
+    /// Signing key; empty disables signing
+    #[serde(default)]
+    pub key: String,
+    #[serde(default = "default_signature_scheme")]
+    pub signature_scheme: String,
+}
+
+fn default_signature_scheme() -> String {
+    "hmac-sha256".to_string()
+}
+
+/// A message that can't be accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// No `<IDS|MSG>` frame, or too few frames after it
+    Malformed,
+    /// The signature doesn't match the connection's key
+    BadSignature,
+    /// A signed part isn't JSON
+    Json(String),
+}
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Malformed => write!(f, "malformed message"),
+            ProtocolError::BadSignature => write!(f, "invalid message signature"),
+            ProtocolError::Json(e) => write!(f, "invalid message JSON: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// A Jupyter message: routing identities and the four signed JSON parts.
+/// Binary buffers after the content are not supported and are dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// Frames before the delimiter: the requester's routing identity on
+    /// `shell`/`control`, the topic on `iopub`
+    pub identities: Vec<Vec<u8>>,
+    pub header: Json,
+    pub parent_header: Json,
+    pub metadata: Json,
+    pub content: Json,
+}
+
+impl Message {
+    /// `header.msg_type`, or empty if missing
+    pub fn msg_type(&self) -> &str {
+        self.header["msg_type"].as_str().unwrap_or_default()
+    }
+
+    /// The message's wire frames, signed with `key`
+    pub fn to_frames(&self, key: &[u8]) -> Vec<Vec<u8>> {
+        let parts: Vec<Vec<u8>> = [
+            &self.header,
+            &self.parent_header,
+            &self.metadata,
+            &self.content,
+        ]
+        .iter()
+        .map(|part| part.to_string().into_bytes())
+        .collect();
+        let mut frames = self.identities.clone();
+        frames.push(DELIMITER.to_vec());
+        frames.push(signature(key, &parts).into_bytes());
+        frames.extend(parts);
+        frames
+    }
+
+    /// Decode wire frames, checking the signature unless `key` is empty
+    pub fn from_frames(frames: &[Vec<u8>], key: &[u8]) -> Result<Self, ProtocolError> {
+        let split = frames
+            .iter()
+            .position(|frame| frame == DELIMITER)
+            .ok_or(ProtocolError::Malformed)?;
+        let signed = frames
+            .get(split + 1..split + 6)
+            .ok_or(ProtocolError::Malformed)?;
+        let parts = &signed[1..];
+        if !key.is_empty() {
+            let claimed = std::str::from_utf8(&signed[0])
+                .ok()
+                .and_then(unhex)
+                .ok_or(ProtocolError::BadSignature)?;
+            mac(key, parts)
+                .verify_slice(&claimed)
+                .map_err(|_| ProtocolError::BadSignature)?;
+        }
+        let json = |bytes: &[u8]| {
+            serde_json::from_slice(bytes).map_err(|e| ProtocolError::Json(e.to_string()))
+        };
+        Ok(Message {
+            identities: frames[..split].to_vec(),
+            header: json(&parts[0])?,
+            parent_header: json(&parts[1])?,
+            metadata: json(&parts[2])?,
+            content: json(&parts[3])?,
+        })
+    }
+}
+
+/// Hex HMAC-SHA256 of `parts` under `key`; empty without a key
+fn signature(key: &[u8], parts: &[Vec<u8>]) -> String {
+    if key.is_empty() {
+        return String::new();
+    }
+    hex(&mac(key, parts).finalize().into_bytes())
+}
+
+/// HMAC-SHA256 of the concatenation of `parts` under `key`, to finalize or
+/// verify
+fn mac(key: &[u8], parts: &[Vec<u8>]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+/// Where a message the kernel sends goes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outgoing {
+    /// To the requester, on the socket the request came in on
+    Reply(Message),
+    /// To every `iopub` subscriber
+    Publish(Message),
+}
+
+/// Answers requests by running them against a notebook [`Session`].
+pub struct Kernel {
+    session: Session,
+    /// Names the kernel's messages and `iopub` topics
+    id: String,
+    /// Messages sent, for unique message ids
+    sent: u64,
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Kernel {
+    pub fn new() -> Self {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        Self {
+            session: Session::new(),
+            id: format!("{:x}-{:x}", std::process::id(), millis),
+            sent: 0,
+        }
+    }
+
+    /// The messages answering `request`, in order: `busy` status, any
+    /// outputs, the reply, and `idle` status. A request of unknown type
+    /// gets no reply.
+    pub fn handle(&mut self, request: &Message) -> Vec<Outgoing> {
+        let mut out = vec![self.publish(request, "status", json!({"execution_state": "busy"}))];
+        let content = &request.content;
+        let reply = match request.msg_type() {
+            "kernel_info_request" => Some(kernel_info()),
+            "execute_request" => Some(self.execute(request, &mut out)),
+            "inspect_request" => Some(self.inspect(content)),
+            "complete_request" => Some(self.complete(content)),
+            "is_complete_request" => Some(is_complete(content)),
+            "comm_info_request" => Some(json!({"status": "ok", "comms": {}})),
+            "history_request" => Some(json!({"status": "ok", "history": []})),
+            "interrupt_request" => Some(json!({"status": "ok"})),
+            "shutdown_request" => Some(json!({
+                "status": "ok",
+                "restart": content["restart"].as_bool().unwrap_or(false),
+            })),
+            _ => None,
+        };
+        if let Some(reply) = reply {
+            let msg_type = request.msg_type().replace("_request", "_reply");
+            let msg = self.message(request, &msg_type, reply, request.identities.clone());
+            out.push(Outgoing::Reply(msg));
+        }
+        out.push(self.publish(request, "status", json!({"execution_state": "idle"})));
+        out
+    }
+
+    /// Run the request's code as the next cell, publishing its input and
+    /// its value or error
+    fn execute(&mut self, request: &Message, out: &mut Vec<Outgoing>) -> Json {
+        let code = request.content["code"].as_str().unwrap_or_default();
+        let silent = request.content["silent"].as_bool().unwrap_or(false);
+        let count = self.session.execution_count() + 1;
+        if !silent {
+            let input = json!({"code": code, "execution_count": count});
+            out.push(self.publish(request, "execute_input", input));
+        }
+        match self.session.execute(code) {
+            Ok(value) => {
+                if let Some(value) = value.filter(|_| !silent) {
+                    let result = json!({
+                        "execution_count": count,
+                        "data": {"text/plain": value.to_string()},
+                        "metadata": {},
+                    });
+                    out.push(self.publish(request, "execute_result", result));
+                }
+                json!({
+                    "status": "ok",
+                    "execution_count": count,
+                    "payload": [],
+                    "user_expressions": {},
+                })
+            }
+            Err(e) => {
+                let error = json!({
+                    "ename": e.name(),
+                    "evalue": e.to_string(),
+                    "traceback": [format!("{}: {}", e.name(), e)],
+                });
+                if !silent {
+                    out.push(self.publish(request, "error", error.clone()));
+                }
+                let mut reply = error;
+                reply["status"] = json!("error");
+                reply["execution_count"] = json!(count);
+                reply
+            }
+        }
+    }
+
+    /// The type of the name at the cursor
+    fn inspect(&self, content: &Json) -> Json {
+        let (code, cursor) = code_and_cursor(content);
+        let (start, end) = word_at(&code, cursor);
+        let name: String = code[start..end].iter().collect();
+        match self.session.inspect(&name) {
+            Some(text) => json!({
+                "status": "ok",
+                "found": true,
+                "data": {"text/plain": text},
+                "metadata": {},
+            }),
+            None => json!({"status": "ok", "found": false, "data": {}, "metadata": {}}),
+        }
+    }
+
+    /// Names completing the word before the cursor
+    fn complete(&self, content: &Json) -> Json {
+        let (code, cursor) = code_and_cursor(content);
+        let (start, _) = word_at(&code, cursor);
+        let prefix: String = code[start..cursor].iter().collect();
+        json!({
+            "status": "ok",
+            "matches": self.session.completions(&prefix),
+            "cursor_start": start,
+            "cursor_end": cursor,
+            "metadata": {},
+        })
+    }
+
+    /// An `iopub` message under the topic `kernel.<id>.<msg_type>`
+    fn publish(&mut self, parent: &Message, msg_type: &str, content: Json) -> Outgoing {
+        let topic = format!("kernel.{}.{}", self.id, msg_type).into_bytes();
+        Outgoing::Publish(self.message(parent, msg_type, content, vec![topic]))
+    }
+
+    fn message(
+        &mut self,
+        parent: &Message,
+        msg_type: &str,
+        content: Json,
+        identities: Vec<Vec<u8>>,
+    ) -> Message {
+        self.sent += 1;
+        Message {
+            identities,
+            header: json!({
+                "msg_id": format!("{}-{}", self.id, self.sent),
+                "session": self.id,
+                "username": "kernel",
+                "date": now_iso8601(),
+                "msg_type": msg_type,
+                "version": PROTOCOL_VERSION,
+            }),
+            parent_header: parent.header.clone(),
+            metadata: json!({}),
+            content,
+        }
+    }
+}
+
+fn kernel_info() -> Json {
+    let version = env!("CARGO_PKG_VERSION");
+    json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "strata",
+        "implementation_version": version,
+        "language_info": {
+            "name": "strata",
+            "version": version,
+            "mimetype": "text/x-strata",
+            "file_extension": ".strata",
+        },
+        "banner": format!("Strata {}", version),
+        "help_links": [],
+    })
+}
+
+fn is_complete(content: &Json) -> Json {
+    match completeness(content["code"].as_str().unwrap_or_default()) {
+        Completeness::Complete => json!({"status": "complete"}),
+        Completeness::Incomplete => json!({"status": "incomplete", "indent": ""}),
+        Completeness::Invalid => json!({"status": "invalid"}),
+    }
+}
+
+/// The request's code as characters, since `cursor_pos` counts them,
+/// and the cursor clamped to it
+fn code_and_cursor(content: &Json) -> (Vec<char>, usize) {
+    let code: Vec<char> = content["code"]
+        .as_str()
+        .unwrap_or_default()
+        .chars()
+        .collect();
+    let cursor = content["cursor_pos"].as_u64().unwrap_or(code.len() as u64);
+    let cursor = (cursor as usize).min(code.len());
+    (code, cursor)
+}
+
+/// Bounds of the identifier around `cursor`
+fn word_at(code: &[char], cursor: usize) -> (usize, usize) {
+    let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+    let start = cursor
+        - code[..cursor]
+            .iter()
+            .rev()
+            .take_while(|c| is_word(c))
+            .count();
+    let end = cursor + code[cursor..].iter().take_while(|c| is_word(c)).count();
+    (start, end)
+}
+
+/// A kernelspec (`kernel.json`) starting the kernel at `exe`.
+pub fn kernel_spec(exe: &str) -> Json {
+    json!({
+        "argv": [exe, "run", "{connection_file}"],
+        "display_name": "Strata",
+        "language": "strata",
+        "interrupt_mode": "message",
+    })
+}
+
+// ---------------------------------------------------------------------------
+// ZeroMQ transport
+// ---------------------------------------------------------------------------
+
+/// Serve a kernel on the sockets `info` names, until a shutdown request.
+pub fn serve(info: &ConnectionInfo) -> io::Result<()> {
+    if info.transport != "tcp" && info.transport != "ipc" {
+        let message = format!("unsupported transport '{}'", info.transport);
+        return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+    }
+    if !info.key.is_empty() && info.signature_scheme != "hmac-sha256" {
+        let message = format!("unsupported signature scheme '{}'", info.signature_scheme);
+        return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+    }
+    let key = info.key.as_bytes();
+
+    let context = zmq::Context::new();
+    let bind = |kind: zmq::SocketType, port: u16| -> io::Result<zmq::Socket> {
+        let socket = context.socket(kind)?;
+        socket.set_linger(LINGER_MS)?;
+        socket.bind(&endpoint(info, port))?;
+        Ok(socket)
+    };
+    let shell = bind(zmq::ROUTER, info.shell_port)?;
+    let control = bind(zmq::ROUTER, info.control_port)?;
+    let _stdin = bind(zmq::ROUTER, info.stdin_port)?;
+    let iopub = bind(zmq::PUB, info.iopub_port)?;
+    let hb = bind(zmq::REP, info.hb_port)?;
+
+    // Heartbeats are answered while a cell runs; dropping `stop` ends them
+    let (stop, stopped) = mpsc::channel::<()>();
+    let heartbeat = thread::spawn(move || echo_heartbeats(&hb, &stopped));
+
+    let mut kernel = Kernel::new();
+    loop {
+        // Control first, so an interrupt or shutdown isn't queued behind
+        // cells waiting on shell
+        let mut items = [
+            control.as_poll_item(zmq::POLLIN),
+            shell.as_poll_item(zmq::POLLIN),
+        ];
+        zmq::poll(&mut items, -1)?;
+        let router = match items.iter().position(zmq::PollItem::is_readable) {
+            Some(0) => &control,
+            Some(_) => &shell,
+            None => continue,
+        };
+        let frames = router.recv_multipart(0)?;
+        let request = match Message::from_frames(&frames, key) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("strata-kernel: dropping message: {}", e);
+                continue;
+            }
+        };
+        for out in kernel.handle(&request) {
+            match out {
+                Outgoing::Reply(msg) => router.send_multipart(msg.to_frames(key), 0)?,
+                Outgoing::Publish(msg) => iopub.send_multipart(msg.to_frames(key), 0)?,
+            }
+        }
+        if request.msg_type() == "shutdown_request" {
+            break;
+        }
+    }
+
+    // The context outlives every socket, so it's dropped last, once the
+    // replies still queued have gone out (or `LINGER_MS` has passed)
+    drop(stop);
+    heartbeat
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))?;
+    Ok(())
+}
+
+/// The address of `port` on the connection's transport: for `ipc`, `ip`
+/// is a path that the port is appended to
+fn endpoint(info: &ConnectionInfo, port: u16) -> String {
+    match info.transport.as_str() {
+        "ipc" => format!("ipc://{}-{}", info.ip, port),
+        _ => format!("tcp://{}:{}", info.ip, port),
+    }
+}
+
+/// Send every message `hb` receives straight back, until `stopped` is
+/// signalled or its sender is dropped
+fn echo_heartbeats(hb: &zmq::Socket, stopped: &mpsc::Receiver<()>) -> io::Result<()> {
+    while let Err(mpsc::TryRecvError::Empty) = stopped.try_recv() {
+        if hb.poll(zmq::POLLIN, HEARTBEAT_POLL_MS)? > 0 {
+            let frames = hb.recv_multipart(0)?;
+            hb.send_multipart(frames, 0)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(msg_type: &str, content: Json) -> Message {
+        Message {
+            identities: vec![b"client".to_vec()],
+            header: json!({"msg_id": "1", "msg_type": msg_type, "session": "s"}),
+            parent_header: json!({}),
+            metadata: json!({}),
+            content,
+        }
+    }
+
+    fn msg_types(out: &[Outgoing]) -> Vec<(&'static str, String)> {
+        out.iter()
+            .map(|o| match o {
+                Outgoing::Reply(m) => ("reply", m.msg_type().to_string()),
+                Outgoing::Publish(m) => ("iopub", m.msg_type().to_string()),
+            })
+            .collect()
+    }
+
+    fn reply(out: &[Outgoing]) -> &Message {
+        out.iter()
+            .find_map(|o| match o {
+                Outgoing::Reply(m) => Some(m),
+                Outgoing::Publish(_) => None,
+            })
+            .expect("no reply")
+    }
+
+    #[test]
+    fn test_signatures_are_hmac_sha256() {
+        // RFC 4231, test cases 2 and 6
+        let parts = [b"what do ya want ".to_vec(), b"for nothing?".to_vec()];
+        assert_eq!(
+            signature(b"Jefe", &parts),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        let parts = [b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec()];
+        assert_eq!(
+            signature(&[0xaa; 131], &parts),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+        assert_eq!(signature(b"", &parts), "");
+    }
+
+    #[test]
+    fn test_message_frames_round_trip_and_reject_bad_signatures() {
+        let msg = request("kernel_info_request", json!({}));
+        let mut frames = msg.to_frames(b"secret");
+        assert_eq!(frames[1], DELIMITER);
+        assert_eq!(Message::from_frames(&frames, b"secret"), Ok(msg));
+
+        let signed = frames[2].clone();
+        frames[2] = b"not hex".to_vec();
+        assert_eq!(
+            Message::from_frames(&frames, b"secret"),
+            Err(ProtocolError::BadSignature)
+        );
+        frames[2] = signed;
+        frames[5] = br#"{"code": "tampered"}"#.to_vec();
+        assert_eq!(
+            Message::from_frames(&frames, b"secret"),
+            Err(ProtocolError::BadSignature)
+        );
+        assert_eq!(
+            Message::from_frames(&frames[2..], b""),
+            Err(ProtocolError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_execute_publishes_input_and_result_between_statuses() {
+        let mut kernel = Kernel::new();
+        let out = kernel.handle(&request("execute_request", json!({"code": "1 + 2"})));
+        assert_eq!(
+            msg_types(&out),
+            vec![
+                ("iopub", "status".to_string()),
+                ("iopub", "execute_input".to_string()),
+                ("iopub", "execute_result".to_string()),
+                ("reply", "execute_reply".to_string()),
+                ("iopub", "status".to_string()),
+            ]
+        );
+        let Outgoing::Publish(result) = &out[2] else {
+            panic!("expected a publish");
+        };
+        assert_eq!(result.content["data"]["text/plain"], "3");
+        assert_eq!(result.parent_header["msg_id"], "1");
+        let reply = reply(&out);
+        assert_eq!(reply.identities, vec![b"client".to_vec()]);
+        assert_eq!(reply.content["status"], "ok");
+        assert_eq!(reply.content["execution_count"], 1);
+    }
+
+    #[test]
+    fn test_execute_error_reply_names_the_failure() {
+        let mut kernel = Kernel::new();
+        let out = kernel.handle(&request("execute_request", json!({"code": "1 + true"})));
+        let reply = reply(&out);
+        assert_eq!(reply.content["status"], "error");
+        assert_eq!(reply.content["ename"], "TypeError");
+        assert!(msg_types(&out).contains(&("iopub", "error".to_string())));
+    }
+
+    #[test]
+    fn test_inspect_and_complete_at_cursor() {
+        let mut kernel = Kernel::new();
+        let code = "fn square(x: Int) -> Int { x * x }";
+        kernel.handle(&request("execute_request", json!({"code": code})));
+
+        let out = kernel.handle(&request(
+            "inspect_request",
+            json!({"code": "square(3)", "cursor_pos": 3, "detail_level": 0}),
+        ));
+        let content = &reply(&out).content;
+        assert_eq!(content["found"], true);
+        let text = content["data"]["text/plain"].as_str().unwrap();
+        assert!(text.starts_with("fn square : Int -> Int"), "got {}", text);
+
+        let out = kernel.handle(&request(
+            "complete_request",
+            json!({"code": "1 + squ", "cursor_pos": 7}),
+        ));
+        let content = &reply(&out).content;
+        assert_eq!(content["matches"], json!(["square"]));
+        assert_eq!(content["cursor_start"], 4);
+        assert_eq!(content["cursor_end"], 7);
+    }
+}
@@ -1,2 +1,24 @@
+pub mod ast_view;
+pub mod audit;
+pub mod bench;
+pub mod cache;
+pub mod caps;
+pub mod coverage;
+pub mod debug;
+pub mod diff;
+pub mod emit;
+pub mod error;
 pub mod eval;
+pub mod flaky;
+pub mod graph;
 pub mod host;
+pub mod jupyter;
+pub mod mutate;
+pub mod notebook;
+pub mod pretty;
+pub mod profile;
+pub mod reduce;
+pub mod seal;
+pub mod stats;
+pub mod test_runner;
+pub mod watch;
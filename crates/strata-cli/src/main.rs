@@ -1,12 +1,53 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand, ValueEnum};
-use strata_ast::ast::Item;
-use strata_parse::parse_str;
-use strata_types::TypeChecker;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ed25519_dalek::SigningKey;
+use std::sync::{Arc, Mutex};
+use strata_ast::ast::{Item, Module};
+use strata_ast::diag::{Applicability, Fix};
+use strata_ast::span::{FileId, SourceDb, Span};
+use strata_cli::audit::{self, AuditLog, Rotation};
+use strata_cli::bench::{self, BenchOptions};
+use strata_cli::cache::{semantic_hash, CheckCache};
+use strata_cli::caps::CapsConfig;
+use strata_cli::coverage::Coverage;
+use strata_cli::debug::Debugger;
+use strata_cli::diff;
+use strata_cli::eval::{RunOptions, RuntimeError, RuntimeErrorKind};
+use strata_cli::flaky;
+use strata_cli::host::{
+    source_hash, EffectPolicy, ReplayError, TraceHeader, TraceIndex, TraceMeta, TraceReader,
+    TraceRecord, TraceReplayer,
+};
+use strata_cli::mutate::{self, MutantOutcome, MutateOptions};
+use strata_cli::pretty::{pretty, PrettyLimits};
+use strata_cli::profile::Profiler;
+use strata_cli::reduce::{self, Predicate, ReduceOptions};
+use strata_cli::seal::{self, OpenKeys, SealKeys};
+use strata_cli::stats::StatsRecorder;
+use strata_cli::test_runner::{self, ReplayOutcome, TestOptions, TestOutcome};
+use strata_cli::watch::{self, Watcher};
+use strata_parse::{
+    parse_script_source_with_options, parse_source_with_options, parse_str, ParseOptions,
+};
+use strata_types::compat;
+use strata_types::infer::Ty;
+use strata_types::interface::Interface;
+use strata_types::{
+    Effect, Lint, LintConfig, LintLevel, Profile, TypeChecker, TypeError, TypedItemKind, Warning,
+};
 
 /// Maximum source file size in bytes (1MB)
 const MAX_SOURCE_SIZE: usize = 1_000_000;
 
+/// Exit code when a program fails while running (or replaying), including
+/// when `main() -> Int` returns a value outside 0-255. A program rejected
+/// before it runs (unreadable, or a parse or type error) exits with 1, and a
+/// command-line usage error with 2.
+const EXIT_RUNTIME_ERROR: i32 = 70;
+
+/// Exit code when a program fails because integer arithmetic overflowed
+const EXIT_INTEGER_OVERFLOW: i32 = 71;
+
 #[derive(Parser, Debug)]
 #[command(name = "strata")]
 #[command(about = "Strata: safe automation with effect types and capability security")]
@@ -29,15 +70,333 @@ enum Commands {
         /// Write replay-capable trace (all values recorded)
         #[arg(long, conflicts_with = "trace")]
         trace_full: Option<String>,
+
+        /// Run the program N times, each traced into --trace-out, and report
+        /// the host calls that went differently between runs
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(2..),
+            requires = "trace_out",
+            conflicts_with_all = ["trace", "trace_full", "profile", "stats"]
+        )]
+        repeat: Option<u32>,
+
+        /// Directory for the traces of --repeat (run-1.jsonl, ...), made if
+        /// it doesn't exist
+        #[arg(long, value_name = "DIR", requires = "repeat")]
+        trace_out: Option<String>,
+
+        /// Run top-level statements in order as the body of `main`, printing
+        /// the value of a final expression
+        #[arg(long)]
+        script: bool,
+
+        /// Refuse these effects (comma-separated), even if the program
+        /// type-checks with them
+        #[arg(long, value_delimiter = ',', value_parser = parse_effect)]
+        deny: Vec<Effect>,
+
+        /// Refuse every effect except these (comma-separated)
+        #[arg(long, value_delimiter = ',', value_parser = parse_effect)]
+        allow_only: Option<Vec<Effect>>,
+
+        /// Grant only the capabilities configured in this TOML file, with
+        /// their settings (fs root, net allowlist, fixed time)
+        #[arg(long)]
+        caps: Option<String>,
+
+        /// Append capability events to this audit log (default:
+        /// $STRATA_AUDIT_LOG, else ~/.local/state/strata/audit.log)
+        #[arg(long)]
+        audit_log: Option<String>,
+
+        /// Don't write the audit log
+        #[arg(long, conflicts_with = "audit_log")]
+        no_audit_log: bool,
+
+        /// Rotate the audit log once it reaches this many bytes
+        #[arg(long, default_value_t = Rotation::default().max_bytes)]
+        audit_log_max_bytes: u64,
+
+        /// Rotated audit log files to keep
+        #[arg(long, default_value_t = Rotation::default().keep)]
+        audit_log_keep: usize,
+
+        /// Time each Strata and host function, printing a report to stderr
+        /// (checking limits, `--profile` on other commands, are
+        /// `--check-profile` here)
+        #[arg(long)]
+        profile: bool,
+
+        /// Also write the profile as folded stacks (for flamegraph tools)
+        #[arg(long, requires = "profile")]
+        profile_folded: Option<String>,
+
+        /// Count evaluator work, printing the totals to stderr
+        #[arg(long)]
+        stats: bool,
+
+        /// Type-check under these limits: strict, default, or permissive
+        /// (`--profile` here is the timing profiler)
+        #[arg(long, default_value_t = Profile::Default, value_parser = parse_profile)]
+        check_profile: Profile,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+
+        #[command(flatten)]
+        print: PrintArgs,
+
+        #[command(flatten)]
+        seal: SealArgs,
+
+        /// Arguments passed to the program, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Type-check a program without running it
+    Check {
+        /// Path to .strata source file
+        file: String,
+
+        /// Check even if the cache says this code already checked cleanly
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Write the interface of the file's `pub` functions, structs, and
+        /// enums here, for `api-check`
+        #[arg(long)]
+        emit_interface: Option<String>,
+
+        /// Print how many constraints each item generated and how many steps
+        /// solving them took, to find what makes checking slow
+        #[arg(long)]
+        verbose_solver: bool,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+
+        #[command(flatten)]
+        lints: LintArgs,
+    },
+
+    /// Compare two interface files written by `check --emit-interface`,
+    /// failing if the new one breaks code written against the old
+    ApiCheck {
+        /// Path to the old interface file
+        old: String,
+
+        /// Path to the new interface file
+        new: String,
+    },
+
+    /// Run a program's `#[test]` functions
+    Test {
+        /// Path to .strata source file
+        file: String,
+
+        /// Run only tests whose name contains this
+        filter: Option<String>,
+
+        /// Record which statements and branches the tests reach, printing
+        /// a summary to stderr
+        #[arg(long)]
+        coverage: bool,
+
+        /// Also write the coverage report to this file
+        #[arg(long, requires = "coverage")]
+        coverage_out: Option<String>,
+
+        /// Format of the coverage report
+        #[arg(long, value_enum, default_value_t = CoverageFormat::Lcov, requires = "coverage_out")]
+        coverage_format: CoverageFormat,
+
+        /// Also replay each trace (`*.jsonl`, recorded with `run
+        /// --trace-full`) in this directory, failing any the program no
+        /// longer reproduces
+        #[arg(long)]
+        replay_dir: Option<String>,
+
+        #[command(flatten)]
+        open: OpenArgs,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// Run a program's tests against small changes to its code, reporting
+    /// the changes no test notices
+    Mutate {
+        /// Path to .strata source file
+        file: String,
+
+        /// Fail a test once it has evaluated this many expressions
+        #[arg(long, default_value_t = MutateOptions::default().step_limit)]
+        step_limit: u64,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// Run a program's `bench fn` benchmarks, reporting time and
+    /// evaluator steps per call
+    Bench {
+        /// Path to .strata source file
+        file: String,
+
+        /// Run only benchmarks whose name contains this
+        filter: Option<String>,
+
+        /// Untimed calls of each benchmark before measuring
+        #[arg(long, default_value_t = BenchOptions::default().warmup)]
+        warmup: u32,
+
+        /// Timed calls of each benchmark
+        #[arg(long, default_value_t = BenchOptions::default().iters)]
+        iters: u32,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// Shrink a program to a smaller one that still fails the same way,
+    /// for a minimal bug report
+    Reduce {
+        /// Path to .strata source file
+        file: String,
+
+        #[command(flatten)]
+        predicate: PredicateArgs,
+
+        /// Write the reduced program here instead of to stdout
+        #[arg(long, short = 'o')]
+        output: Option<String>,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// List the items added, removed, or changed in type between two
+    /// versions of a program
+    Diff {
+        /// Path to the old .strata source file
+        old: String,
+
+        /// Path to the new .strata source file
+        new: String,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// Run a program under the interactive step debugger
+    Debug {
+        /// Path to .strata source file
+        file: String,
+
+        /// Set a breakpoint (function name, line, or file:line) and run to
+        /// it instead of stopping at the start of main
+        #[arg(long = "break", short = 'b')]
+        breakpoints: Vec<String>,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+
+        #[command(flatten)]
+        lints: LintArgs,
+
+        #[command(flatten)]
+        print: PrintArgs,
+
+        /// Arguments passed to the program, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Re-check a program each time it is saved
+    Watch {
+        /// Path to .strata source file
+        file: String,
+
+        /// After a clean check, offer to run main
+        #[arg(long)]
+        run: bool,
+
+        /// Don't clear the screen before each check
+        #[arg(long)]
+        no_clear: bool,
+
+        /// How often to look for changes, in milliseconds
+        #[arg(long, default_value_t = 300)]
+        interval_ms: u64,
+
+        #[command(flatten)]
+        profile: ProfileArgs,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+
+        #[command(flatten)]
+        lints: LintArgs,
+
+        #[command(flatten)]
+        print: PrintArgs,
     },
 
     /// Replay a recorded effect trace
     Replay {
-        /// Path to trace JSONL file
+        /// Path to trace JSONL file (`-` for stdin)
         trace_path: String,
 
         /// Path to .strata source file (omit for trace summary)
         file: Option<String>,
+
+        /// Print only effect entry N (numbered from 0, as replay errors
+        /// number them) instead of the summary
+        #[arg(long, value_name = "N", conflicts_with = "file")]
+        entry: Option<usize>,
+
+        /// Replay even if the program has changed since the trace was
+        /// recorded
+        #[arg(long, requires = "file")]
+        force: bool,
+
+        #[command(flatten)]
+        open: OpenArgs,
+    },
+
+    /// Make a key for signing traces (written with its public key, to
+    /// PATH.pub) or, with `--encryption`, for encrypting them
+    Keygen {
+        /// Where to write the key
+        path: String,
+
+        /// Make a key to encrypt traces with rather than sign them
+        #[arg(long)]
+        encryption: bool,
     },
 
     /// Parse a source file and dump the AST
@@ -48,7 +407,203 @@ enum Commands {
         /// Output format
         #[arg(long, value_enum, default_value_t = Format::Pretty)]
         format: Format,
+
+        /// Emit a checked artifact instead of the syntax tree
+        #[arg(long, value_enum)]
+        emit: Option<Emit>,
+
+        #[command(flatten)]
+        features: FeatureArgs,
+    },
+
+    /// Emit a Graphviz DOT graph of a checked program
+    Graph {
+        /// Path to .strata source file
+        file: String,
+
+        /// Which relationships to draw
+        #[arg(long, value_enum, default_value_t = GraphKind::Calls)]
+        kind: GraphKind,
+
+        #[command(flatten)]
+        features: FeatureArgs,
     },
+
+    /// Print the syntax tree with each node's kind, key fields, and line:col range
+    Ast {
+        /// Path to .strata source file
+        file: String,
+
+        /// Show at most this many levels below the module
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+
+    /// Apply machine-applicable fixes for type errors in place
+    Fix {
+        /// Path to .strata source file
+        file: String,
+
+        /// Instead, write out the inferred parameter types, return types,
+        /// and effect annotations of a program that checks
+        #[arg(long)]
+        annotate: bool,
+
+        /// Print the annotations rather than applying them
+        #[arg(long, requires = "annotate")]
+        dry_run: bool,
+
+        /// Also apply fixes that leave placeholders to fill in, such as the
+        /// `todo()` bodies of missing match arms
+        #[arg(long, conflicts_with = "annotate")]
+        placeholders: bool,
+    },
+}
+
+/// How much of a value to print, for `run`, `watch`, and `debug`
+#[derive(Args, Debug, Clone, Copy)]
+struct PrintArgs {
+    /// Print values nested at most this deep, eliding the rest with `…`
+    #[arg(long, default_value_t = PrettyLimits::default().max_depth)]
+    max_depth: usize,
+
+    /// Print at most this many elements of a tuple, struct, or variant
+    #[arg(long, default_value_t = PrettyLimits::default().max_elems)]
+    max_elems: usize,
+
+    /// Print at most this many characters of a string
+    #[arg(long, default_value_t = PrettyLimits::default().max_string)]
+    max_string: usize,
+}
+
+/// Features for conditional compilation, for commands that parse
+#[derive(Args, Debug, Clone, Copy)]
+struct ProfileArgs {
+    /// Check under these limits: strict, default, or permissive (`run`
+    /// spells this `--check-profile`; its `--profile` is the timing profiler)
+    #[arg(long, default_value_t = Profile::Default, value_parser = parse_profile)]
+    profile: Profile,
+}
+
+#[derive(Args, Debug, Clone)]
+struct FeatureArgs {
+    /// Enable these features (comma-separated) for `#[cfg(feature = "...")]`
+    /// items
+    #[arg(long, value_delimiter = ',')]
+    features: Vec<String>,
+}
+
+/// Lint levels, for commands that report warnings. Items may override
+/// them for themselves with `#[allow(...)]`, `#[warn(...)]`, and
+/// `#[deny(...)]`.
+#[derive(Args, Debug, Clone)]
+struct LintArgs {
+    /// Don't report these lints (comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = parse_lint)]
+    allow: Vec<Lint>,
+
+    /// Report these lints as warnings (comma-separated)
+    #[arg(long, value_delimiter = ',', value_parser = parse_lint)]
+    warn: Vec<Lint>,
+
+    /// Report these lints as errors (comma-separated); wins over
+    /// `--warn` and `--allow`
+    #[arg(long, value_delimiter = ',', value_parser = parse_lint)]
+    deny: Vec<Lint>,
+}
+
+/// Keys to sign and encrypt the trace with, for `run`
+#[derive(Args, Debug, Clone)]
+struct SealArgs {
+    /// Sign the trace with the Ed25519 secret key in this file (made by
+    /// `keygen`)
+    #[arg(long)]
+    trace_sign_key: Option<String>,
+
+    /// Encrypt the trace with the key in this file (made by `keygen
+    /// --encryption`)
+    #[arg(long)]
+    trace_encrypt_key: Option<String>,
+}
+
+/// Keys to open a signed or encrypted trace with, for `replay` and `test`
+#[derive(Args, Debug, Clone)]
+struct OpenArgs {
+    /// Refuse traces not signed by the Ed25519 public key in this file
+    #[arg(long)]
+    trace_verify_key: Option<String>,
+
+    /// Decrypt traces with the key in this file
+    #[arg(long)]
+    trace_decrypt_key: Option<String>,
+
+    /// Read signed traces without checking their signature
+    #[arg(long, conflicts_with = "trace_verify_key")]
+    trace_unverified: bool,
+}
+
+/// What a reduced program must still do, for `reduce`
+#[derive(Args, Debug, Clone)]
+#[group(required = true, multiple = false)]
+struct PredicateArgs {
+    /// Keep programs whose type check panics
+    #[arg(long)]
+    panics: bool,
+
+    /// Keep programs whose type check fails with this error code, like
+    /// TYPE-INFINITE
+    #[arg(long)]
+    error_code: Option<String>,
+
+    /// Keep programs this shell command succeeds on; it is run by `sh -c`
+    /// with the path of the candidate program as $1
+    #[arg(long)]
+    exec: Option<String>,
+}
+
+impl From<PredicateArgs> for Predicate {
+    fn from(args: PredicateArgs) -> Self {
+        match (args.panics, args.error_code, args.exec) {
+            (_, Some(code), _) => Predicate::ErrorCode(code),
+            (_, _, Some(command)) => Predicate::Command(command),
+            _ => Predicate::Panics,
+        }
+    }
+}
+
+impl From<LintArgs> for LintConfig {
+    fn from(args: LintArgs) -> Self {
+        let mut config = LintConfig::new();
+        for (lints, level) in [
+            (args.allow, LintLevel::Allow),
+            (args.warn, LintLevel::Warn),
+            (args.deny, LintLevel::Deny),
+        ] {
+            for lint in lints {
+                config.set(lint, level);
+            }
+        }
+        config
+    }
+}
+
+impl From<FeatureArgs> for ParseOptions {
+    fn from(args: FeatureArgs) -> Self {
+        ParseOptions {
+            features: args.features,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+impl From<PrintArgs> for PrettyLimits {
+    fn from(args: PrintArgs) -> Self {
+        Self {
+            max_depth: args.max_depth,
+            max_elems: args.max_elems,
+            max_string: args.max_string,
+        }
+    }
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -57,195 +612,1491 @@ enum Format {
     Json,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CoverageFormat {
+    /// lcov tracefile, for coverage tools and editors
+    Lcov,
+    /// Standalone HTML page of the source with lines colored by coverage
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum GraphKind {
+    /// Struct/enum composition
+    Adt,
+    /// Calls between top-level functions
+    Calls,
+    /// Effect rows and how effects flow along calls
+    Effects,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum Emit {
+    /// AST plus resolved types and effect rows for every fn and let
+    TypedJson,
+    /// Syntax-highlighted source as a standalone HTML page
+    Html,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Run {
+            file,
+            trace,
+            trace_full,
+            repeat,
+            trace_out,
+            script,
+            deny,
+            allow_only,
+            caps,
+            audit_log,
+            no_audit_log,
+            audit_log_max_bytes,
+            audit_log_keep,
+            profile,
+            profile_folded,
+            stats,
+            check_profile,
+            features,
+            print,
+            seal,
+            args,
+        } => {
+            let seal_flag = match (&seal.trace_sign_key, &seal.trace_encrypt_key) {
+                (Some(_), _) => Some("--trace-sign-key"),
+                (None, Some(_)) => Some("--trace-encrypt-key"),
+                (None, None) => None,
+            };
+            if let Some(flag) = seal_flag.filter(|_| trace.is_none() && trace_full.is_none()) {
+                eprintln!("Error: {} needs --trace or --trace-full", flag);
+                std::process::exit(2);
+            }
+            let mut policy = EffectPolicy::deny(&deny);
+            if let Some(allowed) = allow_only {
+                policy = policy.union(EffectPolicy::allow_only(&allowed));
+            }
+            let rotation = Rotation {
+                max_bytes: audit_log_max_bytes,
+                keep: audit_log_keep,
+            };
+            let caps = load_caps(caps.as_deref());
+            let trace_seal = load_seal_keys(&seal);
+            // Each run gets its own options, and appends to the audit log
+            let run_opts = || RunOptions {
+                args: args.clone(),
+                policy,
+                caps: caps.clone(),
+                audit: open_audit_log(&file, audit_log.clone(), no_audit_log, rotation),
+                trace_seal: trace_seal.clone(),
+                ..RunOptions::default()
+            };
+            if let (Some(runs), Some(dir)) = (repeat, &trace_out) {
+                return cmd_run_repeated(
+                    &file,
+                    runs,
+                    dir,
+                    run_opts,
+                    script,
+                    check_profile,
+                    &features.into(),
+                );
+            }
+            let opts = RunOptions {
+                profiler: profile.then(|| Arc::new(Mutex::new(Profiler::new()))),
+                stats: stats.then(|| Arc::new(StatsRecorder::new())),
+                ..run_opts()
+            };
+            cmd_run(
+                &file,
+                trace,
+                trace_full,
+                script,
+                opts,
+                profile_folded,
+                check_profile,
+                &features.into(),
+                print.into(),
+            )
+        }
+
+        Commands::Check {
+            file,
+            no_cache,
+            emit_interface,
+            verbose_solver,
+            profile: ProfileArgs { profile },
+            features,
+            lints,
+        } => cmd_check(
+            &file,
+            no_cache,
+            emit_interface.as_deref(),
+            verbose_solver,
+            profile,
+            &features.into(),
+            lints.into(),
+        ),
+
+        Commands::ApiCheck { old, new } => cmd_api_check(&old, &new),
+
+        Commands::Test {
+            file,
+            filter,
+            coverage,
+            coverage_out,
+            coverage_format,
+            replay_dir,
+            open,
+            profile: ProfileArgs { profile },
+            features,
+        } => {
+            let opts = TestOptions {
+                filter,
+                coverage: coverage.then(|| Arc::new(Coverage::new())),
+                ..TestOptions::default()
+            };
+            let out = coverage_out.map(|path| (path, coverage_format));
+            cmd_test(
+                &file,
+                &opts,
+                out,
+                replay_dir.as_deref(),
+                &load_open_keys(&open),
+                profile,
+                &features.into(),
+            )
+        }
+
+        Commands::Mutate {
+            file,
+            step_limit,
+            profile: ProfileArgs { profile },
+            features,
+        } => {
+            let opts = MutateOptions {
+                profile,
+                step_limit,
+            };
+            cmd_mutate(&file, &opts, &features.into())
+        }
+
+        Commands::Bench {
+            file,
+            filter,
+            warmup,
+            iters,
+            profile: ProfileArgs { profile },
+            features,
+        } => {
+            let opts = BenchOptions {
+                warmup,
+                iters,
+                filter,
+            };
+            cmd_bench(&file, &opts, profile, &features.into())
+        }
+
+        Commands::Reduce {
+            file,
+            predicate,
+            output,
+            profile: ProfileArgs { profile },
+            features,
+        } => {
+            let opts = ReduceOptions {
+                predicate: predicate.into(),
+                profile,
+                parse: features.into(),
+            };
+            cmd_reduce(&file, &opts, output.as_deref())
+        }
+
+        Commands::Diff {
+            old,
+            new,
+            profile: ProfileArgs { profile },
+            features,
+        } => cmd_diff(&old, &new, profile, &features.into()),
+
+        Commands::Debug {
+            file,
+            breakpoints,
+            profile: ProfileArgs { profile },
+            features,
+            lints,
+            print,
+            args,
+        } => cmd_debug(
+            &file,
+            &breakpoints,
+            profile,
+            &features.into(),
+            lints.into(),
+            args,
+            print.into(),
+        ),
+
+        Commands::Watch {
+            file,
+            run,
+            no_clear,
+            interval_ms,
+            profile: ProfileArgs { profile },
+            features,
+            lints,
+            print,
+        } => cmd_watch(
+            &file,
+            run,
+            no_clear,
+            interval_ms,
+            profile,
+            &features.into(),
+            &lints.into(),
+            print.into(),
+        ),
+
+        Commands::Replay {
+            trace_path,
+            file,
+            force,
+            entry,
+            open,
+        } => cmd_replay(
+            &trace_path,
+            file.as_deref(),
+            force,
+            entry,
+            &load_open_keys(&open),
+        ),
+
+        Commands::Keygen { path, encryption } => cmd_keygen(&path, encryption),
+
+        Commands::Parse {
+            file,
+            format,
+            emit,
+            features,
+        } => cmd_parse(&file, format, emit, &features.into()),
+
+        Commands::Graph {
+            file,
+            kind,
+            features,
+        } => cmd_graph(&file, kind, &features.into()),
+
+        Commands::Ast { file, depth } => cmd_ast(&file, depth),
+
+        Commands::Fix {
+            file,
+            annotate: true,
+            dry_run,
+            ..
+        } => cmd_annotate(&file, dry_run),
+
+        Commands::Fix {
+            file, placeholders, ..
+        } => cmd_fix(&file, placeholders),
+    }
+}
+
+fn load_and_typecheck(path: &str, script: bool) -> Result<Module, Box<dyn std::error::Error>> {
+    let opts = ParseOptions::default();
+    let (module, _, _) = load_and_check(path, script, Profile::Default, &opts, LintConfig::new())?;
+    Ok(module)
+}
+
+/// Parse (as a script if `script`, with `opts`' features) and type-check
+/// under `profile` with `lints`, keeping
+/// the checker for its ADT registry and resolved types, and the sources for
+/// rendering runtime errors.
+fn load_and_check(
+    path: &str,
+    script: bool,
+    profile: Profile,
+    opts: &ParseOptions,
+    lints: LintConfig,
+) -> Result<(Module, TypeChecker, SourceDb), Box<dyn std::error::Error>> {
+    let (db, file) = load_sources(path)?;
+    let module = parse_or_exit(&db, file, script, opts);
+
+    let mut type_checker = TypeChecker::new_with_profile(profile).with_lint_config(lints);
+    if let Err(e) = type_checker.check_module(&module) {
+        report_type_error(&db, &e);
+        std::process::exit(1);
+    }
+    for warning in type_checker.take_warnings() {
+        report_warning(&db, &warning);
+    }
+
+    Ok((module, type_checker, db))
+}
+
+/// Read `path` into a fresh source database.
+fn load_sources(path: &str) -> Result<(SourceDb, FileId), Box<dyn std::error::Error>> {
+    let src = read_source(path)?;
+    let mut db = SourceDb::new();
+    let file = db.add(path, &src);
+    Ok((db, file))
+}
+
+/// Parse `file` of `db` (as a script if `script`) with `opts`, exiting
+/// with its location on a syntax error.
+fn parse_or_exit(db: &SourceDb, file: FileId, script: bool, opts: &ParseOptions) -> Module {
+    let parsed = match script {
+        true => parse_script_source_with_options(db, file, opts),
+        false => parse_source_with_options(db, file, opts),
+    };
+    parsed.unwrap_or_else(|e| {
+        eprintln!("{}: Parse error: {}", db.location(e.span()), e);
+        std::process::exit(1);
+    })
+}
+
+/// Print a runtime error, at its source location when it has one, then
+/// each place it refers to.
+fn report_runtime_error(db: &SourceDb, e: &RuntimeError) {
+    match e.span {
+        Some(span) => eprintln!("{}: Runtime error: {}", db.location(span), e),
+        None => eprintln!("Runtime error: {}", e),
+    }
+    report_notes(db, e.notes());
+    // The calls the error unwound through, innermost first
+    for function in &e.stack {
+        eprintln!("  in {}", function);
+    }
+}
+
+/// Print a type error at its location, then each place it refers to.
+fn report_type_error(db: &SourceDb, e: &TypeError) {
+    eprintln!("{}: Type error: {}", db.location(e.span()), e);
+    report_notes(db, e.notes());
+}
+
+/// Print a warning at its location, then each place it refers to.
+fn report_warning(db: &SourceDb, warning: &Warning) {
+    eprintln!("{}: Warning: {}", db.location(warning.span()), warning);
+    report_notes(db, warning.notes());
+}
+
+fn report_notes(db: &SourceDb, notes: Vec<(String, Span)>) {
+    for (note, span) in notes {
+        eprintln!("{}: note: {}", db.location(span), note);
+    }
+}
+
+/// The exit code for a run that failed with `e`
+fn runtime_exit_code(e: &RuntimeError) -> i32 {
+    match e.kind {
+        RuntimeErrorKind::IntegerOverflow { .. } => EXIT_INTEGER_OVERFLOW,
+        _ => EXIT_RUNTIME_ERROR,
+    }
+}
+
+// One parameter per `run` flag group
+#[allow(clippy::too_many_arguments)]
+fn cmd_run(
+    file: &str,
+    trace: Option<String>,
+    trace_full: Option<String>,
+    script: bool,
+    opts: RunOptions,
+    profile_folded: Option<String>,
+    check_profile: Profile,
+    parse_opts: &ParseOptions,
+    limits: PrettyLimits,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // `run --deny` refuses effects, so `run` checks lints at their defaults
+    let (module, checker, db) =
+        load_and_check(file, script, check_profile, parse_opts, LintConfig::new())?;
+
+    check_main_policy(&checker, &opts);
+    let opts = RunOptions {
+        trace_meta: trace_meta(&db, check_profile),
+        ..opts
+    };
+    let profiler = opts.profiler.clone();
+    let stats = opts.stats.clone();
+    let result = run_program(&module, trace, trace_full, opts);
+
+    // The profile covers the run up to any runtime error
+    if let Some(profiler) = profiler {
+        let profiler = profiler.lock().unwrap();
+        eprint!("{}", profiler.report());
+        if let Some(path) = profile_folded {
+            std::fs::write(&path, profiler.folded())?;
+            eprintln!("Folded stacks written to {}", path);
+        }
+    }
+    if let Some(stats) = stats {
+        eprint!("{}", stats.snapshot().report());
+    }
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            report_runtime_error(&db, &e);
+            std::process::exit(runtime_exit_code(&e));
+        }
+    };
+
+    // A script's main is implicit, so its value is printed rather than
+    // becoming the exit code
+    match result {
+        Some(strata_cli::eval::Value::Int(code)) if !script && main_returns_int(&checker) => {
+            match u8::try_from(code) {
+                Ok(code) => std::process::exit(code.into()),
+                Err(_) => {
+                    eprintln!(
+                        "Runtime error: main() returned {}, outside the exit code range 0-255",
+                        code
+                    );
+                    std::process::exit(EXIT_RUNTIME_ERROR);
+                }
+            }
+        }
+        Some(result) => print_result(&result, script, &limits),
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Run `file` `runs` times, tracing each run to `dir/run-N.jsonl` with
+/// options from `run_opts`, then report the host calls that went
+/// differently between runs. Exits 70 if any did, or if a run failed.
+fn cmd_run_repeated(
+    file: &str,
+    runs: u32,
+    dir: &str,
+    run_opts: impl Fn() -> RunOptions,
+    script: bool,
+    check_profile: Profile,
+    parse_opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, checker, db) =
+        load_and_check(file, script, check_profile, parse_opts, LintConfig::new())?;
+    let first = run_opts();
+    check_main_policy(&checker, &first);
+    let mut first = Some(first);
+    std::fs::create_dir_all(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create trace directory '{}': {}", dir, e))?;
+    let meta = trace_meta(&db, check_profile);
+
+    let mut traces = Vec::new();
+    let mut failed = false;
+    for run in 1..=runs {
+        let path = std::path::Path::new(dir).join(format!("run-{}.jsonl", run));
+        let writer = std::fs::File::create(&path)?;
+        let opts = RunOptions {
+            trace: Some(Box::new(writer)),
+            full_values: true,
+            trace_meta: meta.clone(),
+            ..first.take().unwrap_or_else(&run_opts)
+        };
+        match strata_cli::eval::run_module_with(&module, opts) {
+            Ok(value) => println!(
+                "run {} ... {}",
+                run,
+                pretty(&value, &PrettyLimits::default())
+            ),
+            Err(e) => {
+                println!("run {} ... FAILED", run);
+                report_runtime_error(&db, &e);
+                failed = true;
+            }
+        }
+        let trace = std::fs::File::open(&path)?;
+        traces.push(flaky::read_entries(std::io::BufReader::new(trace))?);
+    }
+    eprintln!("Traces written to {}", dir);
+
+    let divergences = flaky::compare(&traces);
+    match divergences.len() {
+        0 => println!(
+            "\nall {} runs made the same host calls, with the same results",
+            runs
+        ),
+        n => {
+            println!(
+                "\n{} host call{} went differently between runs:",
+                n,
+                if n == 1 { "" } else { "s" }
+            );
+            for divergence in &divergences {
+                println!("  {}", divergence);
+                if let Some(hint) = divergence.hint() {
+                    println!("    hint: {}", hint);
+                }
+            }
+        }
+    }
+    if failed || !divergences.is_empty() {
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    }
+    Ok(())
+}
+
+/// Refuse (exiting 1) a program whose main needs an effect `opts` denies
+/// or doesn't grant; the host registry refuses such calls again at dispatch
+fn check_main_policy(checker: &TypeChecker, opts: &RunOptions) {
+    if let Some(main_ty) = main_ty(checker) {
+        if let Err(e) = opts.policy.union(opts.caps.policy()).check_main(main_ty) {
+            eprintln!("Policy error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// What a trace of a run of the program in `db` records it was recorded
+/// from, so replay can tell when the program has changed since
+fn trace_meta(db: &SourceDb, check_profile: Profile) -> TraceMeta {
+    TraceMeta {
+        source_hash: db.source(FileId::default()).map(source_hash),
+        profile: Some(check_profile.to_string()),
+        flags: std::env::args().skip(1).take_while(|a| a != "--").collect(),
+    }
+}
+
+/// Run the tests of `file`, printing a line per test and a summary. With
+/// coverage on, the summary of what the tests reached goes to stderr, and
+/// the full report to `coverage_out` if given.
+fn cmd_test(
+    file: &str,
+    opts: &TestOptions,
+    coverage_out: Option<(String, CoverageFormat)>,
+    replay_dir: Option<&str>,
+    keys: &OpenKeys,
+    profile: Profile,
+    parse_opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, _, db) = load_and_check(file, false, profile, parse_opts, LintConfig::new())?;
+    let traces = match replay_dir {
+        Some(dir) => trace_files(dir, opts.filter.as_deref())?,
+        None => Vec::new(),
+    };
+    let count = test_runner::tests(&module, opts.filter.as_deref()).len();
+    match traces.len() {
+        0 => println!(
+            "running {} test{}",
+            count,
+            if count == 1 { "" } else { "s" }
+        ),
+        n => println!(
+            "running {} test{} and {} trace{}",
+            count,
+            if count == 1 { "" } else { "s" },
+            n,
+            if n == 1 { "" } else { "s" }
+        ),
+    }
 
-    match cli.command {
-        Commands::Run {
-            file,
-            trace,
-            trace_full,
-        } => cmd_run(&file, trace, trace_full),
+    let (mut passed, mut failed) = (0, 0);
+    let result = test_runner::run_tests(&module, opts, |decl, outcome| {
+        match &outcome {
+            TestOutcome::Passed => println!("test {} ... ok", decl.name.text),
+            TestOutcome::ReturnedFalse => {
+                println!("test {} ... FAILED (returned false)", decl.name.text)
+            }
+            TestOutcome::Failed(e) => {
+                println!("test {} ... FAILED", decl.name.text);
+                report_runtime_error(&db, e);
+            }
+        }
+        match outcome.passed() {
+            true => passed += 1,
+            false => failed += 1,
+        }
+    });
+    if let Err(e) = result {
+        report_runtime_error(&db, &e);
+        std::process::exit(runtime_exit_code(&e));
+    }
+    for (name, path) in &traces {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read trace file '{}': {}", path.display(), e)
+        })?;
+        let content = match open_trace(&path.display().to_string(), &content, keys) {
+            Ok(content) => content,
+            Err(e) => {
+                println!("trace {} ... FAILED", name);
+                eprintln!("Error: {}", e);
+                failed += 1;
+                continue;
+            }
+        };
+        let outcome = test_runner::replay(&module, &content);
+        match &outcome {
+            ReplayOutcome::Passed => println!("trace {} ... ok", name),
+            ReplayOutcome::ResultChanged { recorded, replayed } => {
+                let recorded = match recorded {
+                    Some(value) => format!("returned {}", value.to_value()),
+                    None => "failed".to_string(),
+                };
+                println!(
+                    "trace {} ... FAILED (returned {}; the recorded run {})",
+                    name,
+                    replayed.to_value(),
+                    recorded
+                );
+            }
+            ReplayOutcome::Failed(e) => {
+                println!("trace {} ... FAILED", name);
+                report_runtime_error(&db, e);
+            }
+        }
+        match outcome.passed() {
+            true => passed += 1,
+            false => failed += 1,
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        passed,
+        failed
+    );
 
-        Commands::Replay { trace_path, file } => cmd_replay(&trace_path, file.as_deref()),
+    if let Some(coverage) = &opts.coverage {
+        let report = coverage.report(&module, &db);
+        eprintln!("coverage: {}", report.summary());
+        if let Some((path, format)) = coverage_out {
+            let text = match format {
+                CoverageFormat::Lcov => report.lcov(&db),
+                CoverageFormat::Html => report.html(&db),
+            };
+            std::fs::write(&path, text)?;
+            eprintln!("Coverage report written to {}", path);
+        }
+    }
+    if failed > 0 {
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    }
+    Ok(())
+}
 
-        Commands::Parse { file, format } => cmd_parse(&file, format),
+/// The traces in `dir` whose names `filter` selects, as (name, path) in
+/// name order. A trace is a `*.jsonl` file, named without the extension.
+fn trace_files(
+    dir: &str,
+    filter: Option<&str>,
+) -> Result<Vec<(String, std::path::PathBuf)>, Box<dyn std::error::Error>> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow::anyhow!("Failed to read trace directory '{}': {}", dir, e))?;
+    let mut traces = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        if filter.is_none_or(|f| name.contains(f)) {
+            traces.push((name, path));
+        }
     }
+    traces.sort();
+    Ok(traces)
 }
 
-fn load_and_typecheck(path: &str) -> Result<strata_ast::ast::Module, Box<dyn std::error::Error>> {
-    let src = std::fs::read_to_string(path)?;
+/// Run the tests of `file` against each of its mutants, printing a line per
+/// mutant and a summary. The tests must pass unmutated first.
+fn cmd_mutate(
+    file: &str,
+    opts: &MutateOptions,
+    parse_opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, _, db) = load_and_check(file, false, opts.profile, parse_opts, LintConfig::new())?;
+    if test_runner::tests(&module, None).is_empty() {
+        println!("no tests to run against mutants");
+        return Ok(());
+    }
 
-    if src.len() > MAX_SOURCE_SIZE {
+    let mut failing = Vec::new();
+    let result = test_runner::run_tests(&module, &mutate::test_options(opts), |decl, outcome| {
+        if !outcome.passed() {
+            failing.push(decl.name.text.clone());
+        }
+    });
+    if let Err(e) = result {
+        report_runtime_error(&db, &e);
+        std::process::exit(runtime_exit_code(&e));
+    }
+    if !failing.is_empty() {
         eprintln!(
-            "Error: source file exceeds {}MB limit ({} bytes)",
-            MAX_SOURCE_SIZE / 1_000_000,
-            src.len()
+            "Error: tests fail without any mutation: {}",
+            failing.join(", ")
         );
-        std::process::exit(1);
+        std::process::exit(EXIT_RUNTIME_ERROR);
     }
 
-    let module = parse_str(path, &src)?;
+    let (mut killed, mut survived, mut unchecked) = (0, 0, 0);
+    mutate::run_mutants(&module, opts, |mutant, outcome| {
+        let verdict = match outcome {
+            MutantOutcome::Killed { test } => {
+                killed += 1;
+                match test {
+                    Some(test) => format!("killed by {}", test),
+                    None => "killed".to_string(),
+                }
+            }
+            MutantOutcome::Survived => {
+                survived += 1;
+                "SURVIVED".to_string()
+            }
+            MutantOutcome::Unchecked { .. } => {
+                unchecked += 1;
+                "skipped (does not type-check)".to_string()
+            }
+        };
+        println!("{}: {} ... {}", db.location(mutant.span), mutant, verdict);
+    });
+    println!(
+        "\nmutants: {} killed, {} survived, {} skipped",
+        killed, survived, unchecked
+    );
+    Ok(())
+}
 
-    let mut type_checker = TypeChecker::new();
-    if let Err(e) = type_checker.check_module(&module) {
-        eprintln!("Type error: {}", e);
-        std::process::exit(1);
+/// Run the benchmarks of `file`, printing a line per benchmark. One that
+/// fails is reported, and the rest still run.
+fn cmd_bench(
+    file: &str,
+    opts: &BenchOptions,
+    profile: Profile,
+    parse_opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, _, db) = load_and_check(file, false, profile, parse_opts, LintConfig::new())?;
+    if bench::benches(&module, opts.filter.as_deref()).is_empty() {
+        println!("no benchmarks to run");
+        return Ok(());
     }
 
-    Ok(module)
+    let mut failed = 0;
+    let result = bench::run_benches(&module, opts, |decl, result| match result {
+        Ok(result) => println!("{}", result.report()),
+        Err(e) => {
+            failed += 1;
+            println!("bench {} ... FAILED", decl.name.text);
+            report_runtime_error(&db, &e);
+        }
+    });
+    if let Err(e) = result {
+        report_runtime_error(&db, &e);
+        std::process::exit(runtime_exit_code(&e));
+    }
+    if failed > 0 {
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    }
+    Ok(())
 }
 
-fn cmd_run(
+/// Reduce `file` as `opts` says, writing the result to `output` or stdout.
+/// The program must parse and satisfy the predicate as it is.
+fn cmd_reduce(
     file: &str,
-    trace: Option<String>,
-    trace_full: Option<String>,
+    opts: &ReduceOptions,
+    output: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let module = load_and_typecheck(file)?;
+    let src = read_source(file)?;
+    let Some(reduction) = reduce::reduce(&src, opts) else {
+        eprintln!(
+            "Error: {} doesn't satisfy the predicate (or doesn't parse), so there is nothing to reduce",
+            file
+        );
+        std::process::exit(1);
+    };
+    match output {
+        Some(path) => std::fs::write(path, &reduction.source)?,
+        None => print!("{}", reduction.source),
+    }
+    eprintln!(
+        "reduced {} bytes to {} ({} candidates tried)",
+        src.len(),
+        reduction.source.len(),
+        reduction.tests
+    );
+    Ok(())
+}
 
-    let has_main_params = module
-        .items
-        .iter()
-        .any(|item| matches!(item, Item::Fn(d) if d.name.text == "main" && !d.params.is_empty()));
+/// Check `old` and `new` and print their item-level differences, one per
+/// line.
+fn cmd_diff(
+    old: &str,
+    new: &str,
+    profile: Profile,
+    parse_opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (old_module, old_checker, _) =
+        load_and_check(old, false, profile, parse_opts, LintConfig::new())?;
+    let (new_module, new_checker, _) =
+        load_and_check(new, false, profile, parse_opts, LintConfig::new())?;
+    let changes = diff::diff(&old_module, &old_checker, &new_module, &new_checker);
+    if changes.is_empty() {
+        println!("no item-level changes");
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    Ok(())
+}
 
+/// Evaluate a checked module: call `main` if there is one, returning its
+/// value, or else evaluate and print its let bindings.
+fn run_program(
+    module: &Module,
+    trace: Option<String>,
+    trace_full: Option<String>,
+    opts: RunOptions,
+) -> strata_cli::eval::Result<Option<strata_cli::eval::Value>> {
     let has_main = module
         .items
         .iter()
         .any(|item| matches!(item, Item::Fn(d) if d.name.text == "main"));
 
-    if let Some(trace_path) = trace_full {
-        // Replay-capable trace: all values recorded
-        let writer: Box<dyn std::io::Write + Send> = Box::new(std::fs::File::create(&trace_path)?);
-        let result = strata_cli::eval::run_module_traced_full(&module, writer)?;
-        print_result(&result, has_main);
-        eprintln!("Trace written to {}", trace_path);
-    } else if let Some(trace_path) = trace {
-        // Audit trace: large values hashed
-        let writer: Box<dyn std::io::Write + Send> = Box::new(std::fs::File::create(&trace_path)?);
-        let result = strata_cli::eval::run_module_traced(&module, writer)?;
-        print_result(&result, has_main);
-        eprintln!("Trace written to {}", trace_path);
-    } else if has_main_params {
-        // No trace — run with capability injection
-        let result = strata_cli::eval::run_module(&module)?;
-        print_result(&result, true);
-    } else if has_main {
-        // No trace — run module with simple main()
-        let result = strata_cli::eval::run_module(&module)?;
-        print_result(&result, true);
-    } else {
+    // `--trace-full` records all values (replay-capable); `--trace` hashes
+    // large ones (audit only)
+    let (trace_path, full_values) = match (trace_full, trace) {
+        (Some(path), _) => (Some(path), true),
+        (None, path) => (path, false),
+    };
+
+    if trace_path.is_none() && !has_main {
         // No main() — eval module (print let bindings)
-        strata_cli::eval::eval_module(&module)?;
+        strata_cli::eval::eval_module(module)?;
+        return Ok(None);
     }
 
-    Ok(())
+    // Run main() with capability injection
+    let writer = match &trace_path {
+        Some(path) => {
+            Some(Box::new(std::fs::File::create(path)?) as Box<dyn std::io::Write + Send>)
+        }
+        None => None,
+    };
+    let opts = RunOptions {
+        trace: writer,
+        full_values,
+        ..opts
+    };
+    let result = strata_cli::eval::run_module_with(module, opts)?;
+    if let Some(path) = trace_path {
+        eprintln!("Trace written to {}", path);
+    }
+    Ok(Some(result))
+}
+
+/// The `--caps` config, if given; an unreadable or invalid file rejects the run
+fn load_caps(path: Option<&str>) -> CapsConfig {
+    let Some(path) = path else {
+        return CapsConfig::default();
+    };
+    CapsConfig::load(std::path::Path::new(path)).unwrap_or_else(|e| {
+        eprintln!("Caps error in {}: {}", path, e);
+        std::process::exit(1);
+    })
+}
+
+/// The keys `args` names to seal a trace with, exiting 1 if one can't be
+/// read
+fn load_seal_keys(args: &SealArgs) -> SealKeys {
+    SealKeys {
+        signing: args
+            .trace_sign_key
+            .as_deref()
+            .map(|path| SigningKey::from_bytes(&read_key_or_exit(path))),
+        encryption: args.trace_encrypt_key.as_deref().map(read_key_or_exit),
+    }
+}
+
+/// The keys `args` names to open traces with, exiting 1 if one can't be
+/// read
+fn load_open_keys(args: &OpenArgs) -> OpenKeys {
+    OpenKeys {
+        verifying: args.trace_verify_key.as_deref().map(|path| {
+            seal::read_verifying_key(std::path::Path::new(path)).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            })
+        }),
+        decryption: args.trace_decrypt_key.as_deref().map(read_key_or_exit),
+        unverified: args.trace_unverified,
+    }
+}
+
+fn read_key_or_exit(path: &str) -> [u8; seal::KEY_LEN] {
+    seal::read_key(std::path::Path::new(path)).unwrap_or_else(|e| {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    })
+}
+
+/// The run's audit log, on unless `disabled`. An explicit `--audit-log` that
+/// can't be opened rejects the run; the default location only warns.
+fn open_audit_log(
+    program: &str,
+    path: Option<String>,
+    disabled: bool,
+    rotation: Rotation,
+) -> Option<AuditLog> {
+    if disabled {
+        return None;
+    }
+    let explicit = path.is_some();
+    let path = path
+        .map(std::path::PathBuf::from)
+        .or_else(audit::default_path)?;
+    match AuditLog::open(&path, rotation, program) {
+        Ok(log) => Some(log),
+        Err(e) if explicit => {
+            eprintln!("Audit log error in {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Warning: not writing audit log {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// The checked type of `main`, if the module has one
+fn main_ty(checker: &TypeChecker) -> Option<&Ty> {
+    checker
+        .typed_items()
+        .iter()
+        .find(|item| item.kind == TypedItemKind::Fn && item.name == "main")
+        .map(|item| &item.ty)
+}
+
+/// Whether the checked `main` returns `Int`, which becomes the exit code
+fn main_returns_int(checker: &TypeChecker) -> bool {
+    matches!(main_ty(checker), Some(Ty::Arrow(_, ret, _)) if **ret == Ty::int())
+}
+
+/// Parse an effect name for `--deny` / `--allow-only`
+fn parse_effect(name: &str) -> Result<Effect, String> {
+    Effect::from_name(name).ok_or_else(|| format!("unknown effect '{}'", name))
 }
 
-fn print_result(result: &strata_cli::eval::Value, _has_main: bool) {
+/// Parse a lint name for `--allow` / `--warn` / `--deny`
+fn parse_lint(name: &str) -> Result<Lint, String> {
+    Lint::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Lint::ALL.iter().map(|l| l.name()).collect();
+        format!("unknown lint '{}' (expected {})", name, names.join(", "))
+    })
+}
+
+fn parse_profile(name: &str) -> Result<Profile, String> {
+    Profile::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Profile::ALL.iter().map(|p| p.name()).collect();
+        format!("unknown profile '{}' (expected {})", name, names.join(", "))
+    })
+}
+
+fn print_result(result: &strata_cli::eval::Value, script: bool, limits: &PrettyLimits) {
     match result {
+        // A script prints its final value, if it has one, and nothing else
+        strata_cli::eval::Value::Unit if script => {}
+        other if script => println!("{}", pretty(other, limits)),
         strata_cli::eval::Value::Unit => {
             println!("Program completed successfully.");
         }
         other => {
-            println!("main() = {}", other);
+            println!("main() = {}", pretty(other, limits));
         }
     }
 }
 
-fn cmd_replay(trace_path: &str, file: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-    let trace_content = std::fs::read_to_string(trace_path)
-        .map_err(|e| anyhow::anyhow!("Failed to read trace file '{}': {}", trace_path, e))?;
+/// Parse and type-check under `profile` with `lints`. Code that already
+/// checked cleanly, by semantic hash, profile, and lint levels, is found in
+/// `.strata-cache/` beside the file and not re-checked.
+fn cmd_check(
+    file: &str,
+    no_cache: bool,
+    emit_interface: Option<&str>,
+    verbose_solver: bool,
+    profile: Profile,
+    opts: &ParseOptions,
+    lints: LintConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (db, file_id) = load_sources(file)?;
+    let module = parse_or_exit(&db, file_id, false, opts);
+    // A clean result under one profile says nothing about a stricter one,
+    // nor with a lint allowed about the lint warned or denied
+    let mut hash = match profile {
+        Profile::Default => semantic_hash(&module),
+        other => format!("{}-{}", semantic_hash(&module), other),
+    };
+    for lint in Lint::ALL {
+        if lints.level(*lint) != lint.default_level() {
+            hash = format!("{}-{}={:?}", hash, lint.name(), lints.level(*lint));
+        }
+    }
 
-    match file {
-        Some(source_path) => {
-            // Replay against source
-            let module = load_and_typecheck(source_path)?;
-            strata_cli::eval::run_module_replay(&module, &trace_content)?;
+    let root = std::path::Path::new(file)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(std::path::Path::new("."));
+    // A cached result has no checker to take an interface or solver
+    // statistics from
+    let cache = match no_cache || emit_interface.is_some() || verbose_solver {
+        true => None,
+        false => CheckCache::open(root)
+            .map_err(|e| eprintln!("Warning: not using the check cache: {}", e))
+            .ok(),
+    };
+    if cache.as_ref().is_some_and(|c| c.is_clean(&hash)) {
+        println!("{}: ok (cached)", file);
+        return Ok(());
+    }
 
-            let effect_count = trace_content.lines().filter(|l| !l.is_empty()).count();
-            println!("Replay successful: {} effects replayed.", effect_count);
+    let mut type_checker = TypeChecker::new_with_profile(profile).with_lint_config(lints);
+    let checked = type_checker.check_module(&module);
+    if verbose_solver {
+        for (name, stats) in type_checker.solve_stats() {
+            eprintln!(
+                "solver: {}: {} constraints, {} steps",
+                name, stats.constraints, stats.steps
+            );
         }
-        None => {
-            // Print trace summary
-            print_trace_summary(&trace_content)?;
+    }
+    if let Err(e) = checked {
+        report_type_error(&db, &e);
+        std::process::exit(1);
+    }
+    let warnings = type_checker.take_warnings();
+    for warning in &warnings {
+        report_warning(&db, warning);
+    }
+    // Warnings point into this version of the text, so only clean results
+    // are cached
+    if let (Some(cache), true) = (&cache, warnings.is_empty()) {
+        if let Err(e) = cache.mark_clean(&hash) {
+            eprintln!("Warning: not updating the check cache: {}", e);
         }
     }
+    if let Some(path) = emit_interface {
+        std::fs::write(path, type_checker.interface(&module).to_bytes())?;
+    }
+    println!("{}: ok", file);
+    Ok(())
+}
 
+/// Print each change from the interface file `old` to `new`, exiting with 1
+/// if any of them is breaking.
+fn cmd_api_check(old: &str, new: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let read = |path: &str| -> Result<Interface, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        Interface::from_bytes(&bytes).map_err(|e| format!("{}: {}", path, e).into())
+    };
+    let changes = compat::compare(&read(old)?, &read(new)?);
+    for change in &changes {
+        println!("{}", change);
+    }
+    let breaking = changes.iter().filter(|c| c.breaking).count();
+    println!(
+        "\n{} breaking, {} compatible",
+        breaking,
+        changes.len() - breaking
+    );
+    if breaking > 0 {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-fn print_trace_summary(trace_content: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let records: Vec<serde_json::Value> = trace_content
-        .lines()
-        .filter(|l| !l.is_empty())
-        .map(serde_json::from_str)
-        .collect::<Result<_, _>>()?;
+fn cmd_debug(
+    file: &str,
+    breakpoints: &[String],
+    profile: Profile,
+    opts: &ParseOptions,
+    lints: LintConfig,
+    args: Vec<String>,
+    limits: PrettyLimits,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, checker, db) = load_and_check(file, false, profile, opts, lints)?;
+    if main_ty(&checker).is_none() {
+        eprintln!("Error: {} has no main function to debug", file);
+        std::process::exit(1);
+    }
 
-    if records.is_empty() {
-        println!("Trace: empty (no effects recorded)");
-        return Ok(());
+    let src = db.source(FileId::default()).unwrap_or_default();
+    let input = Box::new(std::io::BufReader::new(std::io::stdin()));
+    let mut debugger =
+        Debugger::new(file, src, input, Box::new(std::io::stdout())).with_print_limits(limits);
+    for spec in breakpoints {
+        if let Err(e) = debugger.add_breakpoint(spec) {
+            eprintln!("Error: --break {}: {}", spec, e);
+            std::process::exit(2);
+        }
+    }
+    if !breakpoints.is_empty() {
+        debugger.run_to_breakpoint();
     }
 
-    // Extract effect entries (skip header/footer)
-    let mut effects = Vec::new();
-    let mut header_info = None;
-    let mut footer_info = None;
+    let opts = RunOptions {
+        args,
+        audit: open_audit_log(file, None, false, Rotation::default()),
+        debugger: Some(Arc::new(Mutex::new(debugger))),
+        ..RunOptions::default()
+    };
+    match strata_cli::eval::run_module_with(&module, opts) {
+        Ok(value) => println!("Program finished: {}", pretty(&value, &limits)),
+        Err(e) if e.kind == RuntimeErrorKind::Quit => {}
+        Err(e) => {
+            report_runtime_error(&db, &e);
+            std::process::exit(runtime_exit_code(&e));
+        }
+    }
+    Ok(())
+}
 
-    for record in &records {
-        match record.get("record").and_then(|r| r.as_str()) {
-            Some("header") => {
-                let version = record["schema_version"].as_str().unwrap_or("?");
-                let full = record["full_values"].as_bool().unwrap_or(false);
-                header_info = Some((version.to_string(), full));
+// One parameter per `watch` flag group
+#[allow(clippy::too_many_arguments)]
+fn cmd_watch(
+    file: &str,
+    run: bool,
+    no_clear: bool,
+    interval_ms: u64,
+    profile: Profile,
+    opts: &ParseOptions,
+    lints: &LintConfig,
+    limits: PrettyLimits,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let interval = std::time::Duration::from_millis(interval_ms);
+    let mut watcher = Watcher::new(std::path::Path::new(file));
+    let mut offer_run = run;
+    let mut read_failed = false;
+    loop {
+        let src = match watcher.poll() {
+            Ok(Some(src)) => src,
+            Ok(None) => {
+                std::thread::sleep(interval);
+                continue;
             }
-            Some("footer") => {
-                let status = record["program_status"].as_str().unwrap_or("?");
-                let trace_status = record["trace_status"].as_str().unwrap_or("?");
-                footer_info = Some((status.to_string(), trace_status.to_string()));
+            // Editors that save by replacing the file leave it briefly
+            // missing, so keep watching
+            Err(e) => {
+                if !read_failed {
+                    eprintln!("Error: cannot read {}: {}", file, e);
+                }
+                read_failed = true;
+                std::thread::sleep(interval);
+                continue;
             }
-            Some("effect") | None => {
-                // "effect" record or legacy format (no "record" field)
-                effects.push(record);
+        };
+        read_failed = false;
+
+        if !no_clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        let checked = watch::check(file, &src, profile, opts, lints);
+        for diagnostic in &checked.diagnostics {
+            println!("{}", diagnostic);
+        }
+        match &checked.module {
+            Some(_) if checked.diagnostics.is_empty() => println!("{}: no problems", file),
+            Some(_) => println!("{}: no errors", file),
+            None => println!("{}: check failed", file),
+        }
+
+        if let (true, Some(module)) = (offer_run, &checked.module) {
+            print!("Run main? [y/N] ");
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut answer = String::new();
+            if std::io::stdin().read_line(&mut answer)? == 0 {
+                // No one is answering; just keep checking
+                offer_run = false;
+            } else if answer.trim().eq_ignore_ascii_case("y") {
+                let opts = RunOptions {
+                    audit: open_audit_log(file, None, false, Rotation::default()),
+                    ..RunOptions::default()
+                };
+                match run_program(module, None, None, opts) {
+                    Ok(Some(result)) => print_result(&result, false, &limits),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("{}", watch::render_runtime_error(file, &src, &e)),
+                }
             }
-            _ => {}
         }
+        println!("Watching {} for changes (Ctrl-C to stop)", file);
     }
+}
 
-    if let Some((version, full)) = &header_info {
-        println!(
-            "Trace schema: v{}, mode: {}",
-            version,
-            if *full {
-                "full (replay-capable)"
-            } else {
-                "audit (hashed)"
+fn cmd_replay(
+    trace_path: &str,
+    file: Option<&str>,
+    force: bool,
+    entry: Option<usize>,
+    keys: &OpenKeys,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // A sealed trace is opened whole, since its signature covers all of it;
+    // otherwise it's read as replay goes, refusing a signature unless
+    // `--trace-unverified` waives it
+    let sealed = keys.verifying.is_some() || keys.decryption.is_some();
+    let records = || -> Result<_, Box<dyn std::error::Error>> {
+        Ok(TraceReader::new(trace_reader(trace_path)?).unverified(keys.unverified))
+    };
+    match (file, entry) {
+        (Some(source_path), _) => {
+            // Replay against source
+            let module = load_and_typecheck(source_path, false)?;
+            let replayer = match sealed {
+                true => TraceReplayer::from_jsonl(&read_trace(trace_path, keys)?),
+                false => TraceReplayer::from_records(records()?),
+            };
+            let replayer = replayer.unwrap_or_else(|e| {
+                let sealed = matches!(e, ReplayError::Sealed(_));
+                exit_replay_failed(trace_path, &e, sealed)
+            });
+            check_recorded_source(trace_path, replayer.header(), source_path, force)?;
+            let replayer = Arc::new(Mutex::new(replayer));
+            if let Err(e) = strata_cli::eval::run_module_replay_from(&module, replayer.clone()) {
+                let sealed = matches!(e.kind, RuntimeErrorKind::SealedTrace { .. });
+                exit_replay_failed(trace_path, &e, sealed);
             }
-        );
+
+            let effect_count = replayer.lock().unwrap().replayed();
+            println!("Replay successful: {} effects replayed.", effect_count);
+        }
+        (None, Some(n)) if sealed || keys.unverified || trace_path == "-" => {
+            let content = read_trace(trace_path, keys)?;
+            print_trace_entry(std::io::Cursor::new(content), n)?;
+        }
+        (None, Some(n)) => {
+            let file = std::fs::File::open(trace_path).map_err(|e| {
+                anyhow::anyhow!("Failed to read trace file '{}': {}", trace_path, e)
+            })?;
+            print_trace_entry(std::io::BufReader::new(file), n)?;
+        }
+        (None, None) if sealed => {
+            let content = read_trace(trace_path, keys)?;
+            print_trace_summary(TraceReader::new(content.as_bytes()))?;
+        }
+        (None, None) => print_trace_summary(records()?).unwrap_or_else(|e| {
+            eprintln!("Error: {}: {}", trace_path, e);
+            std::process::exit(1);
+        }),
     }
 
-    println!("Trace summary: {} effects", effects.len());
-    for entry in &effects {
-        let seq = entry["seq"].as_u64().unwrap_or(0);
-        let effect = entry["effect"].as_str().unwrap_or("?");
-        let op = entry["operation"].as_str().unwrap_or("?");
-        let access = entry["capability"]["access"].as_str().unwrap_or("?");
-        let status = entry["output"]["status"].as_str().unwrap_or("?");
-        let duration = entry["duration_ms"].as_u64().unwrap_or(0);
-        println!(
-            "  [{}] {}::{}    ({}) - {}, {}ms",
-            seq, effect, op, access, status, duration
+    Ok(())
+}
+
+/// The trace at `path` (`-` for stdin), to be read as replay goes
+fn trace_reader(
+    path: &str,
+) -> Result<Box<dyn std::io::BufRead + Send>, Box<dyn std::error::Error>> {
+    if path == "-" {
+        return Ok(Box::new(std::io::BufReader::new(std::io::stdin())));
+    }
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read trace file '{}': {}", path, e))?;
+    Ok(Box::new(std::io::BufReader::new(file)))
+}
+
+/// The records of the trace at `path` (`-` for stdin), read whole and
+/// opened with `keys`; one that doesn't open exits 1
+fn read_trace(path: &str, keys: &OpenKeys) -> Result<String, Box<dyn std::error::Error>> {
+    let mut content = String::new();
+    trace_reader(path)?
+        .read_to_string(&mut content)
+        .map_err(|e| anyhow::anyhow!("Failed to read trace file '{}': {}", path, e))?;
+    Ok(seal::open(&content, keys)
+        .map(|opened| opened.jsonl)
+        .unwrap_or_else(|e| exit_replay_failed(path, &e, true)))
+}
+
+/// Exit after replaying the trace at `path` failed with `e`. A trace that
+/// doesn't open (`sealed`) is rejected input, exiting 1 whether that's
+/// found reading it whole or as replay goes; any other failure exits 70.
+fn exit_replay_failed(path: &str, e: &dyn std::fmt::Display, sealed: bool) -> ! {
+    if sealed {
+        eprintln!("Error: {}: {}", path, e);
+        std::process::exit(1);
+    }
+    eprintln!("Replay failed: {}", e);
+    std::process::exit(EXIT_RUNTIME_ERROR);
+}
+
+/// Print effect entry `n` of the trace `reader` reads, found through an
+/// index rather than by parsing the entries before it
+fn print_trace_entry<R: std::io::BufRead + std::io::Seek>(
+    mut reader: R,
+    n: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = TraceIndex::build(&mut reader)?;
+    match index.entry(&mut reader, n)? {
+        Some(entry) => println!("{}", serde_json::to_string_pretty(&entry)?),
+        None => {
+            eprintln!(
+                "Error: the trace has {} effect entries, numbered from 0; there's no entry {}",
+                index.len(),
+                n
+            );
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+/// The records of the trace `content` read from `path`, its signature
+/// checked and its lines decrypted with `keys`
+fn open_trace(path: &str, content: &str, keys: &OpenKeys) -> Result<String, String> {
+    seal::open(content, keys)
+        .map(|opened| opened.jsonl)
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Write a fresh key to `path`: an encryption key, or a signing key with
+/// its public key alongside in `path.pub`. Existing files are left alone.
+fn cmd_keygen(path: &str, encryption: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let key = seal::generate_key();
+    write_key(path, &key)?;
+    if encryption {
+        println!("Wrote encryption key to {}", path);
+        return Ok(());
+    }
+    let public = format!("{}.pub", path);
+    write_key(&public, &seal::public_key(&key))?;
+    println!(
+        "Wrote signing key to {} and its public key to {}",
+        path, public
+    );
+    Ok(())
+}
+
+/// Write `key` as hex to the new file `path`, readable only by its owner
+fn write_key(path: &str, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("Failed to create key file '{}': {}", path, e))?;
+    writeln!(file, "{}", seal::hex(key))?;
+    Ok(())
+}
+
+/// Refuse (exiting 1) to replay a trace recorded from a program other than
+/// the one at `source_path`, unless `force`, which only warns. A trace from
+/// another version of `strata` is replayed with a note.
+fn check_recorded_source(
+    trace_path: &str,
+    header: Option<&TraceHeader>,
+    source_path: &str,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(header) = header else {
+        return Ok(());
+    };
+    let version = env!("CARGO_PKG_VERSION");
+    if let Some(recorded) = header.compiler_version.as_deref().filter(|v| *v != version) {
+        eprintln!(
+            "Note: {} was recorded by strata {}; this is strata {}",
+            trace_path, recorded, version
         );
     }
+    let Some(recorded) = header.source_hash.as_deref() else {
+        return Ok(());
+    };
+    let current = source_hash(&read_source(source_path)?);
+    if recorded == current {
+        return Ok(());
+    }
+    let problem = format!(
+        "{} was recorded from a different program than {} (source {}, now {})",
+        trace_path, source_path, recorded, current
+    );
+    if force {
+        eprintln!("Warning: {}; replaying anyway", problem);
+        return Ok(());
+    }
+    eprintln!("Error: {}", problem);
+    eprintln!(
+        "  The program has changed since the trace was recorded, so replaying it says \
+         little about this one. Pass --force to replay anyway, or check the changed \
+         program with `strata test --replay-dir`."
+    );
+    std::process::exit(1);
+}
+
+/// Print a summary of the trace `records` reads, a record at a time, so a
+/// trace of any length is summarized in bounded memory
+fn print_trace_summary(
+    records: impl Iterator<Item = Result<TraceRecord, ReplayError>>,
+) -> Result<(), ReplayError> {
+    let mut empty = true;
+    let mut effects = 0;
+    let mut footer = None;
+    for record in records {
+        empty = false;
+        match record? {
+            TraceRecord::Header(header) => print_trace_header(&header),
+            TraceRecord::Effect(entry) => {
+                effects += 1;
+                println!(
+                    "  [{}] {}::{}    ({}) - {}, {}ms",
+                    entry.seq,
+                    entry.effect,
+                    entry.operation,
+                    entry.capability.access,
+                    entry.output.status,
+                    entry.duration_ms
+                );
+            }
+            TraceRecord::Footer(f) => footer = Some(f),
+        }
+    }
 
-    if let Some((prog_status, trace_status)) = &footer_info {
-        println!("Program: {}, Trace: {}", prog_status, trace_status);
+    if empty {
+        println!("Trace: empty (no effects recorded)");
+        return Ok(());
+    }
+    // Entries are printed as they're read, so the count follows them
+    println!("Trace summary: {} effects", effects);
+    if let Some(footer) = footer {
+        println!(
+            "Program: {}, Trace: {}",
+            footer.program_status, footer.trace_status
+        );
     }
 
     Ok(())
 }
 
-fn cmd_parse(file: &str, format: Format) -> Result<(), Box<dyn std::error::Error>> {
-    let src = std::fs::read_to_string(file)?;
+fn print_trace_header(header: &TraceHeader) {
+    println!(
+        "Trace schema: v{}, mode: {}",
+        header.schema_version,
+        if header.full_values {
+            "full (replay-capable)"
+        } else {
+            "audit (hashed)"
+        }
+    );
+    // Traces from before these were recorded have none of them
+    if let Some(hash) = &header.source_hash {
+        println!(
+            "Recorded from: {} by strata {} (profile {})",
+            hash,
+            header.compiler_version.as_deref().unwrap_or("?"),
+            header.profile.as_deref().unwrap_or("?")
+        );
+    }
+    if !header.flags.is_empty() {
+        println!("Command: strata {}", header.flags.join(" "));
+    }
+}
+
+fn read_source(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let src = std::fs::read_to_string(path)?;
 
     if src.len() > MAX_SOURCE_SIZE {
         eprintln!(
@@ -256,17 +2107,169 @@ fn cmd_parse(file: &str, format: Format) -> Result<(), Box<dyn std::error::Error
         std::process::exit(1);
     }
 
-    let module = parse_str(file, &src)?;
+    Ok(src)
+}
+
+fn cmd_parse(
+    file: &str,
+    format: Format,
+    emit: Option<Emit>,
+    opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (db, file_id) = load_sources(file)?;
+    let src = db.source(file_id).unwrap_or_default();
+
+    // Highlighting is token-based and works on source that does not parse
+    if let Some(Emit::Html) = emit {
+        print!("{}", strata_cli::emit::html(file, src));
+        return Ok(());
+    }
+
+    let module = match (parse_source_with_options(&db, file_id, opts), &format) {
+        (Ok(module), _) => module,
+        // Tooling gets the structured diagnostic instead of a message
+        (Err(e), Format::Json) => {
+            println!("{}", serde_json::to_string_pretty(&e.to_diagnostic())?);
+            std::process::exit(1);
+        }
+        (Err(e), Format::Pretty) => {
+            eprintln!("{}: Parse error: {}", db.location(e.span()), e);
+            std::process::exit(1);
+        }
+    };
 
     let mut type_checker = TypeChecker::new();
     if let Err(e) = type_checker.check_module(&module) {
-        eprintln!("Type error: {}", e);
+        match format {
+            Format::Json => {
+                let diag = strata_types::fixes::diagnostic(&e, &module, src);
+                println!("{}", serde_json::to_string_pretty(&diag)?);
+            }
+            Format::Pretty => report_type_error(&db, &e),
+        }
         std::process::exit(1);
     }
 
+    if let Some(Emit::TypedJson) = emit {
+        let typed = strata_cli::emit::typed_json(&module, type_checker.typed_items())?;
+        println!("{}", serde_json::to_string_pretty(&typed)?);
+        return Ok(());
+    }
+
     match format {
-        Format::Pretty => println!("{:#?}", module),
+        Format::Pretty => print!("{}", strata_cli::ast_view::render(&module, src, None)),
         Format::Json => println!("{}", serde_json::to_string_pretty(&module)?),
     }
     Ok(())
 }
+
+fn cmd_graph(
+    file: &str,
+    kind: GraphKind,
+    opts: &ParseOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (module, checker, _) =
+        load_and_check(file, false, Profile::Default, opts, LintConfig::new())?;
+    let dot = match kind {
+        GraphKind::Adt => strata_cli::graph::adt_dot(&module, checker.adt_registry()),
+        GraphKind::Calls => strata_cli::graph::calls_dot(&module),
+        GraphKind::Effects => strata_cli::graph::effects_dot(&module, checker.typed_items()),
+    };
+    print!("{}", dot);
+    Ok(())
+}
+
+/// Syntax-only view: no type checking, so ill-typed files can be inspected.
+fn cmd_ast(file: &str, depth: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let (db, file_id) = load_sources(file)?;
+    let module = parse_or_exit(&db, file_id, false, &ParseOptions::default());
+    let src = db.source(file_id).unwrap_or_default();
+    print!("{}", strata_cli::ast_view::render(&module, src, depth));
+    Ok(())
+}
+
+/// Upper bound on fix rounds; each round fixes the first error only.
+const MAX_FIX_ROUNDS: usize = 16;
+
+/// Check, apply the machine-applicable fixes for the first error (and,
+/// with `placeholders`, those needing code filled in), and repeat until the
+/// file checks or no such fix remains.
+fn cmd_fix(file: &str, placeholders: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut src = read_source(file)?;
+    let mut applied = 0;
+    let mut remaining = None;
+    for _ in 0..MAX_FIX_ROUNDS {
+        let module = parse_str(file, &src)?;
+        let Err(e) = TypeChecker::new().check_module(&module) else {
+            remaining = None;
+            break;
+        };
+        let diag = strata_types::fixes::diagnostic(&e, &module, &src);
+        let safe: Vec<_> = diag
+            .fixes
+            .iter()
+            .filter(|f| placeholders || f.applicability == Applicability::MachineApplicable)
+            .cloned()
+            .collect();
+        remaining = Some(diag);
+        let (fixed, n) = Fix::apply_all(&src, &safe);
+        if n == 0 {
+            break;
+        }
+        src = fixed;
+        applied += n;
+    }
+
+    if applied > 0 {
+        std::fs::write(file, &src)?;
+    }
+    eprintln!(
+        "Applied {} fix{} to {}",
+        applied,
+        if applied == 1 { "" } else { "es" },
+        file
+    );
+    if let Some(diag) = remaining {
+        let mut db = SourceDb::new();
+        db.add(file, &src);
+        eprintln!("{}: Type error: {}", db.location(diag.span), diag.message);
+        for fix in &diag.fixes {
+            match fix.applicability {
+                Applicability::HasPlaceholders => eprintln!(
+                    "  suggestion: {} (`--placeholders` applies it)",
+                    fix.message
+                ),
+                Applicability::MachineApplicable => eprintln!("  suggestion: {}", fix.message),
+            }
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Insert the annotations the checker inferred for each top-level `fn`, or
+/// with `dry_run` list them as `file:line:col: message` lines.
+fn cmd_annotate(file: &str, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let opts = ParseOptions::default();
+    let (module, checker, db) =
+        load_and_check(file, false, Profile::Default, &opts, LintConfig::new())?;
+    let src = db.source(module.span.file).unwrap_or_default();
+    let fixes = strata_types::fixes::annotations(&module, checker.typed_items(), src);
+    if dry_run {
+        for fix in &fixes {
+            println!("{}: {}", db.location(fix.span), fix.message);
+        }
+        return Ok(());
+    }
+    let (annotated, n) = Fix::apply_all(src, &fixes);
+    if n > 0 {
+        std::fs::write(file, annotated)?;
+    }
+    eprintln!(
+        "Added {} annotation{} to {}",
+        n,
+        if n == 1 { "" } else { "s" },
+        file
+    );
+    Ok(())
+}
@@ -0,0 +1,358 @@
+//! Mutation testing for `strata mutate`.
+//!
+//! A mutant is the program with one small change a test suite ought to
+//! notice: a comparison flipped to its opposite (`<` to `>=`), `+` and `-`
+//! or `*` and `/` swapped, `&&` and `||` swapped, or an `if` or `while`
+//! condition negated. Each mutant that still type-checks has its tests run;
+//! if every test still passes, the mutant survived and points at code the
+//! tests don't pin down. `#[test]` and `bench` functions aren't mutated.
+//!
+//! Mutants are found and applied by the same walk over the syntax tree, so
+//! a mutant is named by the span of the expression it changes.
+
+use strata_ast::ast::{BinOp, Block, Expr, Item, Lit, Module, Stmt, UnOp};
+use strata_ast::span::Span;
+use strata_types::{Profile, TypeChecker};
+
+use crate::ast_view::binop_str;
+use crate::test_runner::{self, TestOptions};
+
+/// One change to a program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mutant {
+    /// The binary expression, or the `if` or `while`, changed
+    pub span: Span,
+    pub kind: MutantKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutantKind {
+    /// Replace a binary operator
+    Operator { from: BinOp, to: BinOp },
+    /// Negate an `if` or `while` condition
+    NegateCondition,
+}
+
+impl std::fmt::Display for Mutant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            MutantKind::Operator { from, to } => {
+                write!(f, "replace `{}` with `{}`", binop_str(from), binop_str(to))
+            }
+            MutantKind::NegateCondition => write!(f, "negate condition"),
+        }
+    }
+}
+
+/// The operator a mutant swaps `op` for
+fn mutated(op: BinOp) -> BinOp {
+    match op {
+        BinOp::Lt => BinOp::Ge,
+        BinOp::Le => BinOp::Gt,
+        BinOp::Gt => BinOp::Le,
+        BinOp::Ge => BinOp::Lt,
+        BinOp::Eq => BinOp::Ne,
+        BinOp::Ne => BinOp::Eq,
+        BinOp::Add => BinOp::Sub,
+        BinOp::Sub => BinOp::Add,
+        BinOp::Mul => BinOp::Div,
+        BinOp::Div => BinOp::Mul,
+        BinOp::And => BinOp::Or,
+        BinOp::Or => BinOp::And,
+    }
+}
+
+/// Every mutant of `m`, in source order
+pub fn mutants(m: &Module) -> Vec<Mutant> {
+    let mut found = Vec::new();
+    let mut copy = m.clone();
+    walk_module(&mut copy, &mut |expr| match expr {
+        Expr::Binary { op, span, .. } => found.push(Mutant {
+            span: *span,
+            kind: MutantKind::Operator {
+                from: *op,
+                to: mutated(*op),
+            },
+        }),
+        Expr::If { span, .. } | Expr::While { span, .. } => found.push(Mutant {
+            span: *span,
+            kind: MutantKind::NegateCondition,
+        }),
+        _ => {}
+    });
+    found.sort_by_key(|mutant| (mutant.span.file, mutant.span.start, mutant.span.end));
+    found
+}
+
+/// `m` with `mutant` applied
+pub fn apply(m: &Module, mutant: &Mutant) -> Module {
+    let mut mutated = m.clone();
+    walk_module(&mut mutated, &mut |expr| {
+        if expr.span() != mutant.span {
+            return;
+        }
+        match (expr, mutant.kind) {
+            (Expr::Binary { op, .. }, MutantKind::Operator { from, to }) if *op == from => *op = to,
+            (Expr::If { cond, .. } | Expr::While { cond, .. }, MutantKind::NegateCondition) => {
+                let span = cond.span();
+                let original = std::mem::replace(cond.as_mut(), Expr::Lit(Lit::Nil, span));
+                **cond = Expr::Unary {
+                    op: UnOp::Not,
+                    expr: Box::new(original),
+                    span,
+                };
+            }
+            _ => {}
+        }
+    });
+    mutated
+}
+
+/// How `strata mutate` tests a module's mutants.
+#[derive(Debug, Clone, Copy)]
+pub struct MutateOptions {
+    /// Limits each mutant is type-checked under
+    pub profile: Profile,
+    /// Fail a test once it has evaluated this many expressions, so a
+    /// mutant that loops forever is killed rather than hanging the run
+    pub step_limit: u64,
+}
+
+impl Default for MutateOptions {
+    fn default() -> Self {
+        Self {
+            profile: Profile::Default,
+            step_limit: 1_000_000,
+        }
+    }
+}
+
+/// What became of one mutant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MutantOutcome {
+    /// A test failed, or the module failed to set up (`None`)
+    Killed { test: Option<String> },
+    /// Every test passed
+    Survived,
+    /// The mutant doesn't type-check, so no test can run it
+    Unchecked { error: String },
+}
+
+/// The test options each mutant's tests run under
+pub fn test_options(opts: &MutateOptions) -> TestOptions {
+    TestOptions {
+        step_limit: Some(opts.step_limit),
+        ..TestOptions::default()
+    }
+}
+
+/// Type-check each mutant of the checked module `m` and run its tests,
+/// passing the outcome to `report` as each finishes
+pub fn run_mutants(
+    m: &Module,
+    opts: &MutateOptions,
+    mut report: impl FnMut(&Mutant, MutantOutcome),
+) {
+    let tests = test_options(opts);
+    for mutant in mutants(m) {
+        let mutated = apply(m, &mutant);
+        if let Err(e) = TypeChecker::new_with_profile(opts.profile).check_module(&mutated) {
+            report(
+                &mutant,
+                MutantOutcome::Unchecked {
+                    error: e.to_string(),
+                },
+            );
+            continue;
+        }
+        let mut killed_by = None;
+        let result = test_runner::run_tests(&mutated, &tests, |decl, outcome| {
+            if !outcome.passed() && killed_by.is_none() {
+                killed_by = Some(decl.name.text.clone());
+            }
+        });
+        let outcome = match (result, killed_by) {
+            (Err(_), _) => MutantOutcome::Killed { test: None },
+            (Ok(()), Some(test)) => MutantOutcome::Killed { test: Some(test) },
+            (Ok(()), None) => MutantOutcome::Survived,
+        };
+        report(&mutant, outcome);
+    }
+}
+
+/// Call `f` on every expression in the bodies of `m`'s functions, outer
+/// expressions before the ones inside them. Tests and benchmarks are
+/// skipped.
+fn walk_module(m: &mut Module, f: &mut impl FnMut(&mut Expr)) {
+    for item in &mut m.items {
+        if let Item::Fn(decl) = item {
+            if !decl.bench && !decl.attrs.iter().any(|a| a.name.text == "test") {
+                walk_block(&mut decl.body, f);
+            }
+        }
+    }
+}
+
+fn walk_block(block: &mut Block, f: &mut impl FnMut(&mut Expr)) {
+    for stmt in &mut block.stmts {
+        match stmt {
+            Stmt::Let { value, .. } | Stmt::Assign { value, .. } => walk_expr(value, f),
+            Stmt::Expr { expr, .. } => walk_expr(expr, f),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    walk_expr(value, f);
+                }
+            }
+            Stmt::Declare { .. } => {}
+            Stmt::Fn(decl) => walk_block(&mut decl.body, f),
+        }
+    }
+    if let Some(tail) = &mut block.tail {
+        walk_expr(tail, f);
+    }
+}
+
+fn walk_expr(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+    f(expr);
+    match expr {
+        Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+        Expr::Unary { expr, .. }
+        | Expr::Paren { inner: expr, .. }
+        | Expr::Borrow(expr, _)
+        | Expr::Field { expr, .. } => walk_expr(expr, f),
+        Expr::Call { callee, args, .. } => {
+            walk_expr(callee, f);
+            args.iter_mut().for_each(|a| walk_expr(a, f));
+        }
+        Expr::Binary { lhs, rhs, .. } => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => walk_block(block, f),
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            walk_expr(cond, f);
+            walk_block(then_, f);
+            if let Some(else_) = else_ {
+                walk_expr(else_, f);
+            }
+        }
+        Expr::While { cond, body, .. } => {
+            walk_expr(cond, f);
+            walk_block(body, f);
+        }
+        Expr::For { iter, body, .. } => {
+            walk_expr(iter, f);
+            walk_block(body, f);
+        }
+        Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            walk_expr(scrutinee, f);
+            arms.iter_mut().for_each(|arm| walk_expr(&mut arm.body, f));
+        }
+        Expr::Tuple { elems, .. } => elems.iter_mut().for_each(|e| walk_expr(e, f)),
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => fields
+            .iter_mut()
+            .for_each(|field| walk_expr(&mut field.value, f)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strata_parse::parse_str;
+
+    const SRC: &str = "\
+fn clamp(n: Int, hi: Int) -> Int { if n > hi { hi } else { n } }
+fn twice(n: Int) -> Int { n * 2 }
+#[test] fn clamps() -> Bool { clamp(9, 5) == 5 }
+";
+
+    fn described(m: &Module) -> Vec<String> {
+        mutants(m).iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn mutants_skip_tests_and_come_in_source_order() {
+        let m = parse_str("<mem>", SRC).unwrap();
+        assert_eq!(
+            described(&m),
+            [
+                "negate condition",
+                "replace `>` with `<=`",
+                "replace `*` with `/`"
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_changes_only_the_named_expression() {
+        let m = parse_str("<mem>", SRC).unwrap();
+        let flip = mutants(&m)[1];
+        let mutated = apply(&m, &flip);
+        // The flipped operator no longer offers `>`, and the rest is as before
+        assert_eq!(
+            described(&mutated),
+            [
+                "negate condition",
+                "replace `<=` with `>`",
+                "replace `*` with `/`"
+            ]
+        );
+
+        let negated = apply(&m, &mutants(&m)[0]);
+        let Item::Fn(clamp) = &negated.items[0] else {
+            panic!("expected Fn");
+        };
+        let Some(Expr::If { cond, .. }) = clamp.body.tail.as_deref() else {
+            panic!("expected if");
+        };
+        assert!(matches!(**cond, Expr::Unary { op: UnOp::Not, .. }));
+    }
+
+    #[test]
+    fn tests_kill_mutants_they_notice() {
+        let m = parse_str("<mem>", SRC).unwrap();
+        let mut outcomes = Vec::new();
+        run_mutants(&m, &MutateOptions::default(), |mutant, outcome| {
+            outcomes.push((mutant.to_string(), outcome))
+        });
+        let killed = MutantOutcome::Killed {
+            test: Some("clamps".to_string()),
+        };
+        assert_eq!(
+            outcomes,
+            [
+                ("negate condition".to_string(), killed.clone()),
+                ("replace `>` with `<=`".to_string(), killed),
+                ("replace `*` with `/`".to_string(), MutantOutcome::Survived),
+            ]
+        );
+    }
+
+    #[test]
+    fn step_limit_kills_mutants_that_never_finish() {
+        let src = "\
+fn count(n: Int) -> Int { let mut i = 0; while i < n { i = i + 1; }; i }
+#[test] fn counts() -> Bool { count(3) == 3 }
+";
+        let m = parse_str("<mem>", src).unwrap();
+        let opts = MutateOptions {
+            step_limit: 10_000,
+            ..MutateOptions::default()
+        };
+        let mut outcomes = Vec::new();
+        run_mutants(&m, &opts, |mutant, outcome| {
+            outcomes.push((mutant.to_string(), outcome))
+        });
+        // `i - 1` counts down forever
+        assert!(outcomes.contains(&(
+            "replace `+` with `-`".to_string(),
+            MutantOutcome::Killed {
+                test: Some("counts".to_string())
+            }
+        )));
+    }
+}
@@ -0,0 +1,395 @@
+//! The engine behind `strata-kernel`: a session that runs notebook cells
+//! one after another against shared checker and evaluator state.
+//!
+//! A cell is a script. Its items join the session, and each top-level
+//! `let name = value;` becomes a global later cells can use. From the first
+//! `let mut`, destructuring `let`, or declaration on, the rest of the cell
+//! runs as one block, so its variables stay local to the cell. The cell's
+//! final expression is its value.
+//!
+//! Like a script's `main`, cells take no capabilities: they can define and
+//! inspect effectful functions, but only run pure code. A cell that fails
+//! to parse, check, or run leaves the session as it was.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use strata_ast::ast::{Block, Expr, Ident, Item, LetDecl, Module, Pat, Stmt};
+use strata_ast::span::{FileId, SourceDb, Span};
+use strata_parse::{parse_script_source, ParseError};
+use strata_types::adt::AdtKind;
+use strata_types::{derive, named_args, resolve, TypeChecker, TypedItemKind};
+
+use crate::error::RuntimeError;
+use crate::eval::{self, Env, Value};
+
+/// Words completion offers besides the session's names
+const KEYWORDS: &[&str] = &[
+    "bench",
+    "const",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "in",
+    "let",
+    "linear",
+    "match",
+    "mut",
+    "pub",
+    "pure",
+    "return",
+    "spawn",
+    "struct",
+    "transparent",
+    "true",
+    "use",
+    "while",
+];
+
+/// Why a cell failed. The message starts with the failure's location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellError {
+    Parse(String),
+    Type(String),
+    Runtime(String),
+}
+
+impl CellError {
+    /// The kind of failure, as a notebook names it
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellError::Parse(_) => "ParseError",
+            CellError::Type(_) => "TypeError",
+            CellError::Runtime(_) => "RuntimeError",
+        }
+    }
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellError::Parse(msg) | CellError::Type(msg) | CellError::Runtime(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+/// Whether a cell's source is ready to run, for frontends deciding between
+/// running it and continuing the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completeness {
+    Complete,
+    /// Ends before a complete cell does, e.g. inside a block
+    Incomplete,
+    Invalid,
+}
+
+/// A cell, split into the checked module and what the session keeps
+struct Cell {
+    /// Variant imports expanded, `use`s dropped
+    module: Module,
+    /// Every item but the synthesized `main`, as written
+    defs: Vec<Item>,
+    /// Global holding the cell's value, if it ends in an expression
+    value: Option<String>,
+}
+
+/// Cells run so far and the definitions they left behind.
+pub struct Session {
+    db: SourceDb,
+    checker: TypeChecker,
+    env: Env,
+    /// Items of every successful cell except its `let`s
+    defs: Vec<Item>,
+    /// Cells run, including failed ones
+    count: usize,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            db: SourceDb::new(),
+            checker: TypeChecker::new(),
+            env: Env::new(),
+            defs: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Cells run so far, including failed ones
+    pub fn execution_count(&self) -> usize {
+        self.count
+    }
+
+    /// Run `code` as the next cell, named `cell[n]` in locations. Returns
+    /// the cell's value, or `None` if it has none or it is `()`.
+    pub fn execute(&mut self, code: &str) -> Result<Option<Value>, CellError> {
+        self.count += 1;
+        let file = self.db.add(format!("cell[{}]", self.count), code);
+        let cell = self.lower(file)?;
+
+        let checker = self.checker.snapshot();
+        let env = self.env.snapshot();
+        match self.run(&cell) {
+            Ok(value) => {
+                self.defs.extend(cell.defs);
+                Ok(value)
+            }
+            Err(e) => {
+                self.checker.restore(&checker);
+                self.env.restore(&env);
+                Err(e)
+            }
+        }
+    }
+
+    /// `name`'s type, if the session defines it: `name : type` for a
+    /// function or global, the declaration's outline for a struct or enum.
+    pub fn inspect(&self, name: &str) -> Option<String> {
+        if let Some(item) = self
+            .checker
+            .typed_items()
+            .iter()
+            .rev()
+            .find(|item| item.name == name && item.kind != TypedItemKind::Const)
+        {
+            let keyword = match item.kind {
+                TypedItemKind::Fn => "fn ",
+                TypedItemKind::ExternFn => "extern fn ",
+                TypedItemKind::Let | TypedItemKind::Const => "",
+            };
+            return Some(format!("{}{} : {}", keyword, name, item.ty));
+        }
+        if let Some(item) = self
+            .checker
+            .typed_items()
+            .iter()
+            .rev()
+            .find(|item| item.name == name)
+        {
+            return Some(format!("const {} : {}", name, item.ty));
+        }
+        let adt = self.checker.adt_registry().get(name)?;
+        let params = match adt.type_params.is_empty() {
+            true => String::new(),
+            false => format!("<{}>", adt.type_params.join(", ")),
+        };
+        let linear = if adt.linear { "linear " } else { "" };
+        Some(match &adt.kind {
+            AdtKind::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.name, f.ty))
+                    .collect();
+                format!(
+                    "{}struct {}{} {{ {} }}",
+                    linear,
+                    name,
+                    params,
+                    fields.join(", ")
+                )
+            }
+            AdtKind::Enum(variants) => {
+                let variants: Vec<&str> = variants.iter().map(|v| v.name.as_str()).collect();
+                format!(
+                    "{}enum {}{} {{ {} }}",
+                    linear,
+                    name,
+                    params,
+                    variants.join(", ")
+                )
+            }
+        })
+    }
+
+    /// Keywords and session names starting with `prefix`, sorted
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        let names = self
+            .checker
+            .typed_items()
+            .iter()
+            .map(|item| item.name.as_str())
+            .chain(self.checker.adt_registry().names())
+            .chain(KEYWORDS.iter().copied());
+        let mut matches: Vec<String> = names
+            .filter(|name| name.starts_with(prefix) && !name.starts_with('$'))
+            .map(str::to_string)
+            .collect();
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+
+    /// Parse `file`, add derived functions, expand variant imports against
+    /// earlier cells, and split the synthesized `main` into globals
+    fn lower(&self, file: FileId) -> Result<Cell, CellError> {
+        let parsed = parse_script_source(&self.db, file)
+            .map_err(|e| CellError::Parse(format!("{}: {}", self.db.location(e.span()), e)))?;
+        let parsed = derive::expand_derives(&parsed).into_owned();
+
+        let defs: Vec<Item> = parsed
+            .items
+            .iter()
+            .filter(|item| !is_script_main(item))
+            .cloned()
+            .collect();
+        let mut all = Module {
+            items: self.defs.clone(),
+            span: parsed.span,
+        };
+        all.items.extend(parsed.items);
+        let expanded = resolve::expand_variant_imports(&all)
+            .map_err(|e| CellError::Type(format!("{}: {}", self.db.name(file), e)))?;
+        let expanded = match named_args::expand_named_args(&expanded)
+            .map_err(|e| CellError::Type(format!("{}: {}", self.db.name(file), e)))?
+        {
+            Cow::Owned(m) => Cow::Owned(m),
+            Cow::Borrowed(_) => expanded,
+        };
+        let own = expanded.items[self.defs.len()..]
+            .iter()
+            .filter(|item| !matches!(item, Item::Use(_)));
+
+        let mut items = Vec::new();
+        let mut value = None;
+        for item in own {
+            match item {
+                Item::Fn(decl) if is_script_main(item) => {
+                    value = self.split_body(&decl.body, &mut items);
+                }
+                item => items.push(item.clone()),
+            }
+        }
+        Ok(Cell {
+            module: Module {
+                items,
+                span: expanded.span,
+            },
+            defs,
+            value,
+        })
+    }
+
+    /// Turn a cell's top-level statements into `let` items on `items`,
+    /// returning the global that holds its final expression
+    fn split_body(&self, body: &Block, items: &mut Vec<Item>) -> Option<String> {
+        let mut hidden = 0;
+        let mut hide = |value: Expr, span: Span, items: &mut Vec<Item>| {
+            // `$` can't start an identifier, so no cell can name these
+            let name = format!("${}.{}", self.count, hidden);
+            hidden += 1;
+            items.push(Item::Let(LetDecl {
+                name: Ident {
+                    text: name.clone(),
+                    span,
+                },
+                ty: None,
+                value,
+                span,
+                attrs: Vec::new(),
+            }));
+            name
+        };
+
+        for (i, stmt) in body.stmts.iter().enumerate() {
+            match stmt {
+                Stmt::Let {
+                    mutable: false,
+                    pat: Pat::Ident(name),
+                    ty,
+                    value,
+                    span,
+                } => items.push(Item::Let(LetDecl {
+                    name: name.clone(),
+                    ty: ty.clone(),
+                    value: value.clone(),
+                    span: *span,
+                    attrs: Vec::new(),
+                })),
+                Stmt::Expr { expr, span } => {
+                    hide(expr.clone(), *span, items);
+                }
+                _ => {
+                    // The rest runs together, sharing its locals
+                    let span = Span {
+                        start: stmt.span().start,
+                        end: body.span.end,
+                        file: body.span.file,
+                    };
+                    let rest = Block {
+                        stmts: body.stmts[i..].to_vec(),
+                        tail: body.tail.clone(),
+                        span,
+                    };
+                    return Some(hide(Expr::Block(rest), span, items));
+                }
+            }
+        }
+        let tail = body.tail.as_deref()?;
+        Some(hide(tail.clone(), tail.span(), items))
+    }
+
+    /// Check `cell`, then evaluate it into the environment
+    fn run(&mut self, cell: &Cell) -> Result<Option<Value>, CellError> {
+        self.checker
+            .check_module(&cell.module)
+            .map_err(|e| CellError::Type(format!("{}: {}", self.db.location(e.span()), e)))?;
+        self.checker.take_warnings();
+
+        // Tables for every type and name the session defines
+        let mut all = Module {
+            items: self.defs.clone(),
+            span: cell.module.span,
+        };
+        all.items.extend(cell.module.items.iter().cloned());
+        let env = std::mem::take(&mut self.env)
+            .with_linear_types(&all)
+            .with_resolution(&all)
+            .with_discriminants(&all)
+            .with_consts(&all);
+        self.env = env.map_err(|e| self.runtime_error(&e))?;
+
+        eval::define_items(&mut self.env, &cell.module).map_err(|e| self.runtime_error(&e))?;
+        let value = cell.value.as_ref().and_then(|name| self.env.get(name));
+        Ok(value.filter(|v| !matches!(v, Value::Unit)).cloned())
+    }
+
+    fn runtime_error(&self, e: &RuntimeError) -> CellError {
+        CellError::Runtime(match e.span {
+            Some(span) => format!("{}: {}", self.db.location(span), e),
+            None => e.to_string(),
+        })
+    }
+}
+
+/// Whether `code` is a complete cell
+pub fn completeness(code: &str) -> Completeness {
+    let mut db = SourceDb::new();
+    let file = db.add("cell", code);
+    match parse_script_source(&db, file) {
+        Ok(_) => Completeness::Complete,
+        Err(ParseError::UnterminatedString { .. } | ParseError::UnterminatedComment { .. }) => {
+            Completeness::Incomplete
+        }
+        // Ran out of input mid-construct
+        Err(e) if e.span().start as usize >= code.trim_end().len() => Completeness::Incomplete,
+        Err(_) => Completeness::Invalid,
+    }
+}
+
+/// Whether `item` is the `main` a script's statements were wrapped in,
+/// whose name is empty in the source
+fn is_script_main(item: &Item) -> bool {
+    matches!(item, Item::Fn(decl) if decl.name.text == "main" && decl.name.span.start == decl.name.span.end)
+}
@@ -0,0 +1,221 @@
+//! Bounded rendering of runtime values for the terminal.
+//!
+//! [`pretty`] prints a [`Value`] exactly as its `Display` does, except
+//! that nesting deeper than [`PrettyLimits::max_depth`] collapses to `…`,
+//! tuples, structs, records, and variants show at most
+//! [`PrettyLimits::max_elems`] elements followed by `… N more`, and
+//! strings longer than [`PrettyLimits::max_string`] characters are cut
+//! off with `…`. Values can't refer to themselves (closures print their
+//! parameters, not their environment), so the depth limit also bounds
+//! the work.
+
+use std::fmt::Write;
+
+use crate::eval::{tuple_struct_fields, Value};
+
+/// How much of a value [`pretty`] prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrettyLimits {
+    /// Nesting levels shown; deeper values print as `…`
+    pub max_depth: usize,
+    /// Elements of a tuple, struct, record, or variant shown
+    pub max_elems: usize,
+    /// Characters of a string shown
+    pub max_string: usize,
+}
+
+impl PrettyLimits {
+    /// No limits: the same text as `Display`
+    pub const UNLIMITED: Self = Self {
+        max_depth: usize::MAX,
+        max_elems: usize::MAX,
+        max_string: usize::MAX,
+    };
+}
+
+impl Default for PrettyLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 16,
+            max_elems: 100,
+            max_string: 1000,
+        }
+    }
+}
+
+/// Render `value` within `limits`.
+pub fn pretty(value: &Value, limits: &PrettyLimits) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, limits, 0);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value, limits: &PrettyLimits, depth: usize) {
+    match value {
+        Value::Str(s) => match s.char_indices().nth(limits.max_string) {
+            Some((cut, _)) => {
+                let shown = strata_parse::escape_str(&s[..cut]);
+                let _ = write!(out, "\"{}…\"", shown);
+            }
+            None => {
+                let _ = write!(out, "{}", value);
+            }
+        },
+        Value::Tuple(elems) => {
+            out.push('(');
+            write_seq(out, elems.iter(), elems.len(), limits, depth);
+            out.push(')');
+        }
+        Value::Struct { name, fields } => match tuple_struct_fields(fields) {
+            Some(fields) => {
+                let _ = write!(out, "{}(", name);
+                write_seq(out, fields.iter().copied(), fields.len(), limits, depth);
+                out.push(')');
+            }
+            None => {
+                let _ = write!(out, "{} {{ ", name);
+                // Sorted, as Display does
+                let mut sorted: Vec<_> = fields.iter().collect();
+                sorted.sort_by_key(|(k, _)| *k);
+                write_fields(out, sorted, limits, depth);
+                out.push_str(" }");
+            }
+        },
+        Value::Record(fields) => {
+            out.push('{');
+            write_fields(out, fields.iter().collect(), limits, depth);
+            out.push('}');
+        }
+        Value::Variant {
+            enum_name,
+            variant_name,
+            fields,
+        } => {
+            let _ = write!(out, "{}::{}", enum_name, variant_name);
+            if !fields.is_empty() {
+                out.push('(');
+                write_seq(out, fields.iter(), fields.len(), limits, depth);
+                out.push(')');
+            }
+        }
+        // Leaves: nothing to limit
+        other => {
+            let _ = write!(out, "{}", other);
+        }
+    }
+}
+
+/// The comma-separated `name: value` fields of a struct or record, one
+/// level down.
+fn write_fields(
+    out: &mut String,
+    fields: Vec<(&String, &Value)>,
+    limits: &PrettyLimits,
+    depth: usize,
+) {
+    if depth >= limits.max_depth {
+        out.push('…');
+        return;
+    }
+    for (i, (field, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        if i == limits.max_elems {
+            let _ = write!(out, "… {} more", fields.len() - i);
+            return;
+        }
+        let _ = write!(out, "{}: ", field);
+        write_value(out, value, limits, depth + 1);
+    }
+}
+
+/// The comma-separated elements of a tuple or variant, one level down.
+fn write_seq<'a>(
+    out: &mut String,
+    elems: impl Iterator<Item = &'a Value>,
+    len: usize,
+    limits: &PrettyLimits,
+    depth: usize,
+) {
+    if depth >= limits.max_depth {
+        out.push('…');
+        return;
+    }
+    for (i, elem) in elems.enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        if i == limits.max_elems {
+            let _ = write!(out, "… {} more", len - i);
+            return;
+        }
+        write_value(out, elem, limits, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuple(elems: Vec<Value>) -> Value {
+        Value::Tuple(elems.into())
+    }
+
+    #[test]
+    fn within_limits_matches_display() {
+        let value = tuple(vec![
+            Value::Int(1),
+            Value::Str("a\n".to_string()),
+            Value::Variant {
+                enum_name: "Option".to_string(),
+                variant_name: "Some".to_string(),
+                fields: vec![tuple(vec![])],
+            },
+            Value::Struct {
+                name: "P".to_string(),
+                fields: [("y", 2), ("x", 1)]
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), Value::Int(v)))
+                    .collect(),
+            },
+            Value::Record(std::sync::Arc::new(
+                [("b", 2), ("a", 1)]
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), Value::Int(v)))
+                    .collect(),
+            )),
+            Value::Struct {
+                name: "UserId".to_string(),
+                fields: [("0".to_string(), Value::Int(7))].into_iter().collect(),
+            },
+        ]);
+        assert_eq!(pretty(&value, &PrettyLimits::default()), value.to_string());
+        assert_eq!(pretty(&value, &PrettyLimits::UNLIMITED), value.to_string());
+    }
+
+    #[test]
+    fn limits_elide_with_ellipsis() {
+        let limits = PrettyLimits {
+            max_depth: 2,
+            max_elems: 3,
+            max_string: 4,
+        };
+        let wide = tuple((0..10).map(Value::Int).collect());
+        assert_eq!(pretty(&wide, &limits), "(0, 1, 2, … 7 more)");
+
+        let deep = tuple(vec![tuple(vec![tuple(vec![Value::Int(1)])])]);
+        assert_eq!(pretty(&deep, &limits), "(((…)))");
+
+        let record = Value::Record(std::sync::Arc::new(
+            (0..5).map(|i| (format!("f{i}"), Value::Int(i))).collect(),
+        ));
+        assert_eq!(pretty(&record, &limits), "{f0: 0, f1: 1, f2: 2, … 2 more}");
+
+        let long = Value::Str("abcdefgh".to_string());
+        assert_eq!(pretty(&long, &limits), "\"abcd…\"");
+        // Cut on a character boundary
+        let wide_chars = Value::Str("ééééé".to_string());
+        assert_eq!(pretty(&wide_chars, &limits), "\"éééé…\"");
+    }
+}
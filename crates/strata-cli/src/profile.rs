@@ -0,0 +1,152 @@
+//! Time spent per function, for `strata run --profile`.
+//!
+//! The evaluator brackets every Strata and host function call with
+//! [`Profiler::enter`] / [`Profiler::exit`]. Each call's *total* time is
+//! wall time from entry to exit; its *self* time leaves out the calls it
+//! made. A recursive function's total counts only its outermost active
+//! call, so nested recursion isn't counted twice.
+//!
+//! Self time is also recorded per call stack, in the folded-stacks format
+//! flamegraph tools read: `main;fib;fib 1234` (microseconds).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Accumulated timings of one function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FnStats {
+    pub calls: u64,
+    pub total: Duration,
+    pub self_time: Duration,
+    /// A host (extern) function rather than Strata code
+    pub host: bool,
+}
+
+#[derive(Debug)]
+struct Frame {
+    name: String,
+    start: Instant,
+    /// Time spent in calls made from this frame
+    children: Duration,
+}
+
+/// Collects per-function timings during a run.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    stack: Vec<Frame>,
+    fns: HashMap<String, FnStats>,
+    folded: HashMap<String, Duration>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A call to `name` begins.
+    pub fn enter(&mut self, name: &str, host: bool) {
+        self.fns.entry(name.to_string()).or_default().host = host;
+        self.stack.push(Frame {
+            name: name.to_string(),
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    /// The innermost call ends.
+    pub fn exit(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        let elapsed = frame.start.elapsed();
+        let self_time = elapsed.saturating_sub(frame.children);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+
+        let mut path: Vec<&str> = self.stack.iter().map(|f| f.name.as_str()).collect();
+        path.push(&frame.name);
+        *self.folded.entry(path.join(";")).or_default() += self_time;
+
+        let recursive = self.stack.iter().any(|f| f.name == frame.name);
+        let stats = self.fns.entry(frame.name).or_default();
+        stats.calls += 1;
+        stats.self_time += self_time;
+        if !recursive {
+            stats.total += elapsed;
+        }
+    }
+
+    /// Per-function timings, by descending self time.
+    pub fn stats(&self) -> Vec<(&str, &FnStats)> {
+        let mut stats: Vec<(&str, &FnStats)> =
+            self.fns.iter().map(|(n, s)| (n.as_str(), s)).collect();
+        stats.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then(a.0.cmp(b.0)));
+        stats
+    }
+
+    /// A table of [`Profiler::stats`], in milliseconds.
+    pub fn report(&self) -> String {
+        let mut out = format!(
+            "{:>10} {:>10} {:>8}  function\n",
+            "self ms", "total ms", "calls"
+        );
+        for (name, stats) in self.stats() {
+            out.push_str(&format!(
+                "{:>10.3} {:>10.3} {:>8}  {}{}\n",
+                stats.self_time.as_secs_f64() * 1000.0,
+                stats.total.as_secs_f64() * 1000.0,
+                stats.calls,
+                name,
+                if stats.host { " [host]" } else { "" }
+            ));
+        }
+        out
+    }
+
+    /// Self time per call stack as folded-stacks lines, in microseconds.
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<(&String, &Duration)> = self.folded.iter().collect();
+        lines.sort();
+        lines
+            .into_iter()
+            .map(|(stack, time)| format!("{} {}\n", stack, time.as_micros()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_calls_and_folds_stacks() {
+        let mut p = Profiler::new();
+        p.enter("main", false);
+        for _ in 0..2 {
+            p.enter("fib", false);
+            p.enter("fib", false);
+            p.exit();
+            p.exit();
+        }
+        p.enter("now", true);
+        p.exit();
+        p.exit();
+
+        let stats: HashMap<&str, &FnStats> = p.stats().into_iter().collect();
+        assert_eq!(stats["main"].calls, 1);
+        assert_eq!(stats["fib"].calls, 4);
+        assert!(stats["now"].host);
+        assert!(stats["main"].total >= stats["main"].self_time);
+        // Recursion isn't counted twice
+        assert!(stats["fib"].total <= stats["main"].total);
+
+        let folded = p.folded();
+        let stacks: Vec<&str> = folded
+            .lines()
+            .map(|l| l.rsplit_once(' ').unwrap().0)
+            .collect();
+        assert_eq!(stacks, ["main", "main;fib", "main;fib;fib", "main;now"]);
+        assert!(p.report().contains("now [host]"));
+    }
+}
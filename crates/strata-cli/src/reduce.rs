@@ -0,0 +1,489 @@
+//! Test-case reduction for `strata reduce`.
+//!
+//! Given a program that fails in some way worth keeping — the checker
+//! panics, checking fails with a particular error code, or a shell command
+//! succeeds on it — the reducer looks for the smallest program it can find
+//! that still fails that way. Each pass proposes smaller candidates and
+//! keeps the first that still satisfies the predicate:
+//!
+//! - delete top-level items, by delta debugging: try removing halves, then
+//!   quarters, and so on down to single items;
+//! - delete statements and block tails the same way;
+//! - replace an expression with one of its own subexpressions or blocks,
+//!   as in `a + f(b)` to `f(b)` and then to `b`.
+//!
+//! The passes repeat until none of them shrinks the program. Candidates are
+//! cut from the source text at the spans of syntax tree nodes, so comments
+//! and layout in what survives are left as written, and every candidate
+//! kept must parse, since the next pass works from its syntax tree.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{Command, Stdio};
+
+use strata_ast::ast::{Block, Expr, Item, Module, Stmt};
+use strata_ast::span::Span;
+use strata_parse::{parse_str_with_options, ParseOptions};
+use strata_types::{Profile, TypeChecker};
+
+/// What a candidate must do to be kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// Type-checking it panics
+    Panics,
+    /// Type-checking it fails with this error code, like `TYPE-INFINITE`
+    ErrorCode(String),
+    /// This shell command exits successfully, run by `sh -c` with the
+    /// path of a file holding the candidate as `$1`
+    Command(String),
+}
+
+/// How `strata reduce` shrinks a program.
+#[derive(Debug, Clone)]
+pub struct ReduceOptions {
+    pub predicate: Predicate,
+    /// Limits candidates are type-checked under
+    pub profile: Profile,
+    /// Features candidates are parsed with
+    pub parse: ParseOptions,
+}
+
+/// A reduced program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reduction {
+    pub source: String,
+    /// How many candidates the predicate was tried on
+    pub tests: usize,
+}
+
+/// Shrink `src` to a smaller program that still satisfies `opts`'
+/// predicate, or `None` if `src` itself doesn't
+pub fn reduce(src: &str, opts: &ReduceOptions) -> Option<Reduction> {
+    let mut reducer = Reducer {
+        opts,
+        tests: 0,
+        scratch: None,
+    };
+    let mut current = reducer.parse(src).filter(|m| reducer.holds(src, m))?;
+    let mut source = src.to_string();
+    loop {
+        let before = source.len();
+        for pass in [Pass::Items, Pass::Stmts, Pass::Exprs] {
+            if let Some(smaller) = reducer.run(pass, &source, &current) {
+                current = reducer.parse(&smaller).expect("kept candidates parse");
+                source = smaller;
+            }
+        }
+        if source.len() == before {
+            break;
+        }
+    }
+    // Lines the deletions left blank
+    let tidy = tidied(&source);
+    if tidy != source {
+        if let Some(m) = reducer.parse(&tidy) {
+            if reducer.holds(&tidy, &m) {
+                source = tidy;
+            }
+        }
+    }
+    if let Some(path) = &reducer.scratch {
+        let _ = std::fs::remove_file(path);
+    }
+    Some(Reduction {
+        source,
+        tests: reducer.tests,
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Pass {
+    Items,
+    Stmts,
+    Exprs,
+}
+
+struct Reducer<'o> {
+    opts: &'o ReduceOptions,
+    tests: usize,
+    /// Where `Predicate::Command` candidates are written, once one has been
+    scratch: Option<std::path::PathBuf>,
+}
+
+impl Reducer<'_> {
+    fn parse(&self, src: &str) -> Option<Module> {
+        parse_str_with_options("<reduce>", src, &self.opts.parse).ok()
+    }
+
+    /// Whether the candidate `src`, parsed as `m`, satisfies the predicate
+    fn holds(&mut self, src: &str, m: &Module) -> bool {
+        self.tests += 1;
+        let profile = self.opts.profile;
+        match &self.opts.predicate {
+            Predicate::Panics => {
+                // Candidates are expected to panic; don't print each one
+                let hook = panic::take_hook();
+                panic::set_hook(Box::new(|_| {}));
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    TypeChecker::new_with_profile(profile).check_module(m)
+                }));
+                panic::set_hook(hook);
+                result.is_err()
+            }
+            Predicate::ErrorCode(code) => TypeChecker::new_with_profile(profile)
+                .check_module(m)
+                .is_err_and(|e| e.code() == code),
+            Predicate::Command(command) => {
+                let path = self.scratch.get_or_insert_with(|| {
+                    std::env::temp_dir()
+                        .join(format!("strata-reduce-{}.strata", std::process::id()))
+                });
+                if std::fs::write(&*path, src).is_err() {
+                    return false;
+                }
+                Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .arg("sh")
+                    .arg(&*path)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok_and(|status| status.success())
+            }
+        }
+    }
+
+    /// Whether the candidate `src` parses and satisfies the predicate
+    fn keeps(&mut self, src: &str) -> bool {
+        match self.parse(src) {
+            Some(m) => self.holds(src, &m),
+            None => false,
+        }
+    }
+
+    /// Run one pass over `src`, parsed as `m`, returning the smaller
+    /// program it found, if any
+    fn run(&mut self, pass: Pass, src: &str, m: &Module) -> Option<String> {
+        match pass {
+            Pass::Items => self.delete_min(src, m.items.iter().map(item_extent).collect()),
+            Pass::Stmts => {
+                let mut units = Vec::new();
+                walk_module(m, &mut |node| {
+                    if let Node::Block(block) = node {
+                        units.extend(block.stmts.iter().map(Stmt::span));
+                        units.extend(block.tail.iter().map(|tail| tail.span()));
+                    }
+                });
+                self.delete_min(src, units)
+            }
+            Pass::Exprs => self.hoist(src, m),
+        }
+    }
+
+    /// Delta debugging over deleting `units` from `src`: try deleting the
+    /// units in chunks, halving the chunk size whenever no chunk can go
+    fn delete_min(&mut self, src: &str, mut units: Vec<Span>) -> Option<String> {
+        let mut deleted: Vec<Span> = Vec::new();
+        let mut chunks = 2;
+        while !units.is_empty() {
+            let size = units.len().div_ceil(chunks);
+            let mut shrunk = false;
+            for start in (0..units.len()).step_by(size) {
+                let end = (start + size).min(units.len());
+                let mut trial = deleted.clone();
+                trial.extend_from_slice(&units[start..end]);
+                if self.keeps(&deleting(src, &trial)) {
+                    deleted = trial;
+                    units.drain(start..end);
+                    chunks = (chunks - 1).max(2);
+                    shrunk = true;
+                    break;
+                }
+            }
+            if !shrunk {
+                if size == 1 {
+                    break;
+                }
+                chunks = (chunks * 2).min(units.len());
+            }
+        }
+        (!deleted.is_empty()).then(|| deleting(src, &deleted))
+    }
+
+    /// Replace the first expression that can be replaced by one of its
+    /// subexpressions, outermost first, until none can
+    fn hoist(&mut self, src: &str, m: &Module) -> Option<String> {
+        let mut hoisted: Option<(String, Module)> = None;
+        loop {
+            let (src, m) = hoisted.as_ref().map_or((src, m), |(s, m)| (s.as_str(), m));
+            let mut replacements = Vec::new();
+            walk_module(m, &mut |node| {
+                if let Node::Expr(expr) = node {
+                    for child in children(expr) {
+                        replacements.push((expr.span(), child));
+                    }
+                }
+            });
+            let mut found = None;
+            for (outer, inner) in replacements {
+                let candidate = format!(
+                    "{}{}{}",
+                    &src[..outer.start as usize],
+                    &src[inner.start as usize..inner.end as usize],
+                    &src[outer.end as usize..]
+                );
+                if let Some(smaller) = self.parse(&candidate) {
+                    if self.holds(&candidate, &smaller) {
+                        found = Some((candidate, smaller));
+                        break;
+                    }
+                }
+            }
+            match found {
+                Some(found) => hoisted = Some(found),
+                None => return hoisted.map(|(src, _)| src),
+            }
+        }
+    }
+}
+
+/// `src` without the text of `spans`; a span inside another is deleted
+/// with it
+fn deleting(src: &str, spans: &[Span]) -> String {
+    let mut spans = spans.to_vec();
+    spans.sort_by_key(|span| (span.start, std::cmp::Reverse(span.end)));
+    let mut out = String::with_capacity(src.len());
+    let mut at = 0;
+    for span in spans {
+        if (span.start as usize) < at {
+            continue;
+        }
+        out.push_str(&src[at..span.start as usize]);
+        at = span.end as usize;
+    }
+    out.push_str(&src[at..]);
+    out
+}
+
+/// `src` without trailing whitespace, blank lines at either end, or blank
+/// lines after a blank line
+fn tidied(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut blank = true;
+    for line in src.lines().map(str::trim_end) {
+        if line.is_empty() && blank {
+            continue;
+        }
+        blank = line.is_empty();
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// An item's span, widened to take in its attributes
+fn item_extent(item: &Item) -> Span {
+    let span = match item {
+        Item::Let(d) => d.span,
+        Item::Const(d) => d.span,
+        Item::Fn(d) => d.span,
+        Item::Struct(d) => d.span,
+        Item::Enum(d) => d.span,
+        Item::ExternFn(d) => d.span,
+        Item::Use(d) => d.span,
+    };
+    let start = item.attrs().iter().map(|a| a.span.start).min();
+    Span {
+        start: start.map_or(span.start, |start| start.min(span.start)),
+        ..span
+    }
+}
+
+/// The spans `expr` could be replaced with: its direct subexpressions and
+/// blocks, and a block's tail
+fn children(expr: &Expr) -> Vec<Span> {
+    match expr {
+        Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => Vec::new(),
+        Expr::Unary { expr, .. }
+        | Expr::Paren { inner: expr, .. }
+        | Expr::Borrow(expr, _)
+        | Expr::Field { expr, .. } => vec![expr.span()],
+        Expr::Call { callee, args, .. } => std::iter::once(callee.as_ref())
+            .chain(args)
+            .map(Expr::span)
+            .collect(),
+        Expr::Binary { lhs, rhs, .. } => vec![lhs.span(), rhs.span()],
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => {
+            block.tail.iter().map(|tail| tail.span()).collect()
+        }
+        Expr::If {
+            cond, then_, else_, ..
+        } => [cond.span(), then_.span]
+            .into_iter()
+            .chain(else_.iter().map(|e| e.span()))
+            .collect(),
+        Expr::While { cond, body, .. } => vec![cond.span(), body.span],
+        Expr::For { iter, body, .. } => vec![iter.span(), body.span],
+        Expr::Match {
+            scrutinee, arms, ..
+        } => std::iter::once(scrutinee.span())
+            .chain(arms.iter().map(|arm| arm.body.span()))
+            .collect(),
+        Expr::Tuple { elems, .. } => elems.iter().map(Expr::span).collect(),
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            fields.iter().map(|field| field.value.span()).collect()
+        }
+    }
+}
+
+/// A node the reducer can shrink
+enum Node<'a> {
+    Block(&'a Block),
+    Expr(&'a Expr),
+}
+
+/// Call `f` on every block and expression of `m`, outer nodes before the
+/// ones inside them
+fn walk_module<'a>(m: &'a Module, f: &mut impl FnMut(Node<'a>)) {
+    for item in &m.items {
+        match item {
+            Item::Fn(decl) => walk_block(&decl.body, f),
+            Item::Let(decl) => walk_expr(&decl.value, f),
+            Item::Const(decl) => walk_expr(&decl.value, f),
+            Item::Struct(_) | Item::Enum(_) | Item::ExternFn(_) | Item::Use(_) => {}
+        }
+    }
+}
+
+fn walk_block<'a>(block: &'a Block, f: &mut impl FnMut(Node<'a>)) {
+    f(Node::Block(block));
+    for stmt in &block.stmts {
+        match stmt {
+            Stmt::Let { value, .. } | Stmt::Assign { value, .. } => walk_expr(value, f),
+            Stmt::Expr { expr, .. } => walk_expr(expr, f),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    walk_expr(value, f);
+                }
+            }
+            Stmt::Declare { .. } => {}
+            Stmt::Fn(decl) => walk_block(&decl.body, f),
+        }
+    }
+    if let Some(tail) = &block.tail {
+        walk_expr(tail, f);
+    }
+}
+
+fn walk_expr<'a>(expr: &'a Expr, f: &mut impl FnMut(Node<'a>)) {
+    f(Node::Expr(expr));
+    match expr {
+        Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+        Expr::Unary { expr, .. }
+        | Expr::Paren { inner: expr, .. }
+        | Expr::Borrow(expr, _)
+        | Expr::Field { expr, .. } => walk_expr(expr, f),
+        Expr::Call { callee, args, .. } => {
+            walk_expr(callee, f);
+            args.iter().for_each(|a| walk_expr(a, f));
+        }
+        Expr::Binary { lhs, rhs, .. } => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => walk_block(block, f),
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            walk_expr(cond, f);
+            walk_block(then_, f);
+            if let Some(else_) = else_ {
+                walk_expr(else_, f);
+            }
+        }
+        Expr::While { cond, body, .. } => {
+            walk_expr(cond, f);
+            walk_block(body, f);
+        }
+        Expr::For { iter, body, .. } => {
+            walk_expr(iter, f);
+            walk_block(body, f);
+        }
+        Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            walk_expr(scrutinee, f);
+            arms.iter().for_each(|arm| walk_expr(&arm.body, f));
+        }
+        Expr::Tuple { elems, .. } => elems.iter().for_each(|e| walk_expr(e, f)),
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            fields.iter().for_each(|field| walk_expr(&field.value, f))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(predicate: Predicate) -> ReduceOptions {
+        ReduceOptions {
+            predicate,
+            profile: Profile::Default,
+            parse: ParseOptions::default(),
+        }
+    }
+
+    #[test]
+    fn deleting_skips_spans_inside_deleted_ones() {
+        let span = |start, end| Span {
+            start,
+            end,
+            ..Span::default()
+        };
+        assert_eq!(
+            deleting("abcdefgh", &[span(5, 6), span(1, 4), span(2, 3)]),
+            "aegh"
+        );
+    }
+
+    #[test]
+    fn reduces_to_what_keeps_the_error() {
+        let src = "\
+struct Point { x: Int, y: Int }
+fn helper(n: Int) -> Int { n * 2 }
+
+fn main() -> Int {
+    let a = helper(1);
+    let p = Point { x: a, y: 2 };
+    let flag: Bool = p.x + 1;
+    a
+}
+";
+        let reduced = reduce(src, &options(Predicate::ErrorCode("TYPE-MISMATCH".into()))).unwrap();
+        assert!(reduced.source.len() < src.len() / 2, "{}", reduced.source);
+        assert!(!reduced.source.contains("helper"), "{}", reduced.source);
+        let m = parse_str_with_options("<mem>", &reduced.source, &ParseOptions::default()).unwrap();
+        let e = TypeChecker::new().check_module(&m).unwrap_err();
+        assert_eq!(e.code(), "TYPE-MISMATCH");
+    }
+
+    #[test]
+    fn reduce_needs_the_predicate_to_hold_at_the_start() {
+        let src = "fn main() -> Int { 1 }\n";
+        assert_eq!(
+            reduce(src, &options(Predicate::ErrorCode("TYPE-MISMATCH".into()))),
+            None
+        );
+    }
+
+    #[test]
+    fn hoisting_keeps_the_subexpression_the_predicate_needs() {
+        let src = "fn main() { let x = 1 + (2 * true); }\n";
+        let reduced = reduce(src, &options(Predicate::ErrorCode("TYPE-MISMATCH".into()))).unwrap();
+        assert_eq!(reduced.source, "fn main() { let x = 2 * true; }\n");
+    }
+}
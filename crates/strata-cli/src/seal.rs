@@ -0,0 +1,578 @@
+//! Signing and encryption of effect traces, so a trace recorded in
+//! production can be handed to a developer without being read or altered
+//! on the way.
+//!
+//! A sealed trace is still JSONL, written a line at a time as the program
+//! runs. With an encryption key, each record is replaced by a `sealed` line
+//! holding it encrypted with ChaCha20-Poly1305 under a fresh nonce, and
+//! bound to its line number so lines can't be reordered or dropped from
+//! the middle. A `sealed_end` line follows them, holding the number of
+//! records encrypted, so lines can't be dropped from the end either. With a
+//! signing key, the trace ends with a `signature` line: an Ed25519
+//! signature over the SHA-256 of every line before it, as written (so a
+//! trace is encrypted, then signed).
+//!
+//! ```text
+//! {"record":"sealed","nonce":"5c1e…","data":"9a0f…"}
+//! {"record":"sealed_end","nonce":"07b2…","data":"c41d…"}
+//! {"record":"signature","algorithm":"ed25519","public_key":"3d4017c3…","signature":"e5564300…"}
+//! ```
+//!
+//! Opening is strict: an encrypted trace must be sealed throughout, and a
+//! signed one is only read with a key to verify it, or with the check
+//! explicitly waived.
+//!
+//! Keys are 32 bytes written as hex: an Ed25519 secret key (with its public
+//! key alongside), or a ChaCha20-Poly1305 key. `strata keygen` makes them.
+
+use std::path::Path;
+
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+/// Length in bytes of every key a trace is sealed or opened with.
+pub const KEY_LEN: usize = 32;
+
+/// Why a key couldn't be read or a trace couldn't be opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SealError {
+    /// A key file that can't be read or isn't 32 bytes of hex
+    Key(String),
+    /// The trace is encrypted and no decryption key was given
+    Encrypted,
+    /// A plain line in an encrypted trace (or one opened with a decryption
+    /// key)
+    Unsealed { line: usize },
+    /// An encrypted trace whose `sealed_end` line is missing or doesn't
+    /// count the records before it
+    Truncated,
+    /// A key to verify with was given, but the trace isn't signed
+    NotSigned,
+    /// The trace is signed, and neither a key to verify it with nor
+    /// leave to skip the check was given
+    Unverified,
+    /// The trace is signed by another key than the one to verify with
+    WrongKey { signed_by: String },
+    /// The signature doesn't match the trace: it was altered or truncated
+    BadSignature,
+    /// A sealed line that doesn't decrypt under the key, or was moved
+    Decrypt { line: usize },
+    /// A `sealed` or `signature` line that isn't well formed
+    Malformed { line: usize, reason: String },
+}
+
+impl std::fmt::Display for SealError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SealError::Key(msg) => write!(f, "{}", msg),
+            SealError::Encrypted => {
+                write!(f, "trace is encrypted; pass --trace-decrypt-key to read it")
+            }
+            SealError::Unsealed { line } => write!(
+                f,
+                "line {} isn't sealed, but the trace is encrypted: lines were added to it",
+                line
+            ),
+            SealError::Truncated => write!(
+                f,
+                "encrypted trace doesn't end with its record count: it was truncated"
+            ),
+            SealError::NotSigned => write!(f, "trace isn't signed"),
+            SealError::Unverified => write!(
+                f,
+                "trace is signed; pass --trace-verify-key to check the signature, \
+                 or --trace-unverified to read it unchecked"
+            ),
+            SealError::WrongKey { signed_by } => write!(
+                f,
+                "trace is signed by another key (public key {})",
+                signed_by
+            ),
+            SealError::BadSignature => write!(
+                f,
+                "trace signature doesn't match: it was altered or truncated after signing"
+            ),
+            SealError::Decrypt { line } => write!(
+                f,
+                "line {} doesn't decrypt: wrong key, or the line was altered or moved",
+                line
+            ),
+            SealError::Malformed { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for SealError {}
+
+/// The lines [`Sealer`] writes besides the trace's own records.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "record")]
+enum SealRecord {
+    /// One record, encrypted
+    #[serde(rename = "sealed")]
+    Sealed { nonce: String, data: String },
+    /// After the last record: how many were sealed, encrypted
+    #[serde(rename = "sealed_end")]
+    SealedEnd { nonce: String, data: String },
+    /// The last line of a signed trace
+    #[serde(rename = "signature")]
+    Signature {
+        algorithm: String,
+        public_key: String,
+        signature: String,
+    },
+}
+
+/// A line [`Sealer`] wrote in place of, or after, the trace's records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SealLine {
+    /// An encrypted record, or the record count after them
+    Sealed,
+    /// The signature ending a signed trace
+    Signature,
+}
+
+/// What `line` is, if [`Sealer`] wrote it.
+pub fn line_kind(line: &str) -> Option<SealLine> {
+    match serde_json::from_str::<SealRecord>(line).ok()? {
+        SealRecord::Sealed { .. } | SealRecord::SealedEnd { .. } => Some(SealLine::Sealed),
+        SealRecord::Signature { .. } => Some(SealLine::Signature),
+    }
+}
+
+/// Keys a trace is sealed with as it's written. With neither, it's written
+/// as is.
+#[derive(Clone, Default)]
+pub struct SealKeys {
+    pub signing: Option<SigningKey>,
+    pub encryption: Option<[u8; KEY_LEN]>,
+}
+
+impl std::fmt::Debug for SealKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the keys themselves
+        f.debug_struct("SealKeys")
+            .field("signing", &self.signing.is_some())
+            .field("encryption", &self.encryption.is_some())
+            .finish()
+    }
+}
+
+/// Keys a sealed trace is opened with.
+#[derive(Clone, Default)]
+pub struct OpenKeys {
+    /// Require a signature by this key
+    pub verifying: Option<VerifyingKey>,
+    pub decryption: Option<[u8; KEY_LEN]>,
+    /// Read a signed trace without a key to verify it with
+    pub unverified: bool,
+}
+
+impl std::fmt::Debug for OpenKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenKeys")
+            .field("verifying", &self.verifying.map(|k| hex(k.as_bytes())))
+            .field("decryption", &self.decryption.is_some())
+            .field("unverified", &self.unverified)
+            .finish()
+    }
+}
+
+/// Read a key written as hex (surrounding whitespace ignored).
+pub fn read_key(path: &Path) -> Result<[u8; KEY_LEN], SealError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| SealError::Key(format!("can't read key {}: {}", path.display(), e)))?;
+    unhex(text.trim())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            SealError::Key(format!(
+                "key {} isn't {} bytes of hex",
+                path.display(),
+                KEY_LEN
+            ))
+        })
+}
+
+/// Read an Ed25519 public key to verify signatures with.
+pub fn read_verifying_key(path: &Path) -> Result<VerifyingKey, SealError> {
+    VerifyingKey::from_bytes(&read_key(path)?).map_err(|_| {
+        SealError::Key(format!(
+            "key {} isn't an Ed25519 public key",
+            path.display()
+        ))
+    })
+}
+
+/// A fresh random key, for signing or encryption.
+pub fn generate_key() -> [u8; KEY_LEN] {
+    let mut key = [0; KEY_LEN];
+    OsRng.fill_bytes(&mut key);
+    key
+}
+
+/// The Ed25519 public key for the secret key `secret`.
+pub fn public_key(secret: &[u8; KEY_LEN]) -> [u8; KEY_LEN] {
+    SigningKey::from_bytes(secret).verifying_key().to_bytes()
+}
+
+/// Seals a trace's lines as they're written.
+pub struct Sealer {
+    keys: SealKeys,
+    /// Lines written so far
+    lines: u64,
+    /// Of the lines written so far, for the signature
+    digest: Sha256,
+}
+
+impl std::fmt::Debug for Sealer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sealer")
+            .field("keys", &self.keys)
+            .field("lines", &self.lines)
+            .finish()
+    }
+}
+
+impl Default for Sealer {
+    fn default() -> Self {
+        Self::new(SealKeys::default())
+    }
+}
+
+impl Sealer {
+    pub fn new(keys: SealKeys) -> Self {
+        Self {
+            keys,
+            lines: 0,
+            digest: Sha256::new(),
+        }
+    }
+
+    /// The line (without its newline) to write for the JSON `record`.
+    pub fn seal(&mut self, record: &str) -> String {
+        let line = match &self.keys.encryption {
+            Some(key) => {
+                let (nonce, data) = encrypt(key, record.as_bytes(), &self.lines.to_le_bytes());
+                let sealed = SealRecord::Sealed { nonce, data };
+                serde_json::to_string(&sealed).expect("serialize sealed record")
+            }
+            None => record.to_string(),
+        };
+        self.digest.update(line.as_bytes());
+        self.digest.update(b"\n");
+        self.lines += 1;
+        line
+    }
+
+    /// The lines that close the trace, in order: the `sealed_end` line if
+    /// it's encrypted, then the `signature` line if it's signed.
+    pub fn finish(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(key) = &self.keys.encryption {
+            let (nonce, data) = encrypt(key, &self.lines.to_le_bytes(), &end_aad(self.lines));
+            let end = SealRecord::SealedEnd { nonce, data };
+            let line = serde_json::to_string(&end).expect("serialize sealed end");
+            self.digest.update(line.as_bytes());
+            self.digest.update(b"\n");
+            lines.push(line);
+        }
+        if let Some(key) = &self.keys.signing {
+            let digest = std::mem::take(&mut self.digest).finalize();
+            let record = SealRecord::Signature {
+                algorithm: "ed25519".to_string(),
+                public_key: hex(key.verifying_key().as_bytes()),
+                signature: hex(&key.sign(&digest).to_bytes()),
+            };
+            lines.push(serde_json::to_string(&record).expect("serialize signature"));
+        }
+        lines
+    }
+}
+
+/// `msg` encrypted under `key` and a fresh nonce, bound to `aad`: the
+/// nonce and the ciphertext, as hex
+fn encrypt(key: &[u8; KEY_LEN], msg: &[u8], aad: &[u8]) -> (String, String) {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    // Encrypting into memory fails only past 2^38 bytes
+    let data = cipher
+        .encrypt(&nonce, Payload { msg, aad })
+        .expect("encrypt record");
+    (hex(&nonce), hex(&data))
+}
+
+/// What the `sealed_end` line after `records` sealed records is bound to:
+/// longer than a record's line number, so neither decrypts as the other
+fn end_aad(records: u64) -> Vec<u8> {
+    [b"end:".as_slice(), &records.to_le_bytes()].concat()
+}
+
+/// A trace, opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opened {
+    /// The trace's records, decrypted, without the signature line
+    pub jsonl: String,
+    /// Whether the trace was signed (and, if a key to verify with was
+    /// given, verified)
+    pub signed: bool,
+}
+
+/// Open the trace `content`: check its signature against `keys.verifying`
+/// (required if given; without it, a signature is refused unless
+/// `keys.unverified`), then decrypt its sealed lines. An encrypted trace,
+/// or any trace opened with `keys.decryption`, must be sealed line for line
+/// and end with its record count. A plain trace opens as it is.
+pub fn open(content: &str, keys: &OpenKeys) -> Result<Opened, SealError> {
+    let mut lines: Vec<&str> = content.lines().filter(|l| !l.is_empty()).collect();
+    let signature = match lines.last().map(|l| serde_json::from_str::<SealRecord>(l)) {
+        Some(Ok(SealRecord::Signature {
+            public_key,
+            signature,
+            ..
+        })) => {
+            lines.pop();
+            Some((public_key, signature))
+        }
+        _ => None,
+    };
+    match (&signature, &keys.verifying) {
+        (Some((public_key, signature)), Some(key)) => {
+            if *public_key != hex(key.as_bytes()) {
+                return Err(SealError::WrongKey {
+                    signed_by: public_key.clone(),
+                });
+            }
+            let malformed = |reason: &str| SealError::Malformed {
+                line: lines.len() + 1,
+                reason: reason.to_string(),
+            };
+            let signature: [u8; 64] = unhex(signature)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| malformed("signature isn't 64 bytes of hex"))?;
+            let mut digest = Sha256::new();
+            for line in &lines {
+                digest.update(line.as_bytes());
+                digest.update(b"\n");
+            }
+            key.verify(&digest.finalize(), &Signature::from_bytes(&signature))
+                .map_err(|_| SealError::BadSignature)?;
+        }
+        (None, Some(_)) => return Err(SealError::NotSigned),
+        (Some(_), None) if !keys.unverified => return Err(SealError::Unverified),
+        (_, None) => {}
+    }
+
+    if let Some(i) = lines
+        .iter()
+        .position(|l| line_kind(l) == Some(SealLine::Signature))
+    {
+        return Err(SealError::Malformed {
+            line: i + 1,
+            reason: "a signature can only be the last line".to_string(),
+        });
+    }
+    let encrypted = lines.iter().any(|l| line_kind(l) == Some(SealLine::Sealed));
+    let jsonl = match (encrypted, &keys.decryption) {
+        (false, None) => lines.iter().map(|line| format!("{}\n", line)).collect(),
+        (_, Some(key)) => decrypt_lines(key, &lines)?,
+        (true, None) => return Err(SealError::Encrypted),
+    };
+    Ok(Opened {
+        jsonl,
+        signed: signature.is_some(),
+    })
+}
+
+/// The records the encrypted trace `lines` hold: every line but the last
+/// sealed, and the last counting them
+fn decrypt_lines(key: &[u8; KEY_LEN], lines: &[&str]) -> Result<String, SealError> {
+    let Some((end, records)) = lines.split_last() else {
+        return Err(SealError::Truncated);
+    };
+    let mut jsonl = String::new();
+    for (i, line) in records.iter().enumerate() {
+        let Ok(SealRecord::Sealed { nonce, data }) = serde_json::from_str(line) else {
+            return Err(SealError::Unsealed { line: i + 1 });
+        };
+        let record = decrypt(key, i + 1, &nonce, &data, &(i as u64).to_le_bytes())?;
+        let record = String::from_utf8(record).map_err(|_| SealError::Decrypt { line: i + 1 })?;
+        jsonl.push_str(&record);
+        jsonl.push('\n');
+    }
+    let count = records.len() as u64;
+    match serde_json::from_str(end) {
+        Ok(SealRecord::SealedEnd { nonce, data }) => {
+            match decrypt(key, lines.len(), &nonce, &data, &end_aad(count)) {
+                Ok(plain) if plain == count.to_le_bytes() => Ok(jsonl),
+                _ => Err(SealError::Truncated),
+            }
+        }
+        // The count and the records after it were cut off
+        Ok(SealRecord::Sealed { .. }) => Err(SealError::Truncated),
+        _ => Err(SealError::Unsealed { line: lines.len() }),
+    }
+}
+
+/// The plaintext of sealed line `line` (from 1), bound to `aad`
+fn decrypt(
+    key: &[u8; KEY_LEN],
+    line: usize,
+    nonce: &str,
+    data: &str,
+    aad: &[u8],
+) -> Result<Vec<u8>, SealError> {
+    let nonce = unhex(nonce)
+        .filter(|n| n.len() == 12)
+        .ok_or_else(|| SealError::Malformed {
+            line,
+            reason: "nonce isn't 12 bytes of hex".to_string(),
+        })?;
+    let data = unhex(data).ok_or_else(|| SealError::Malformed {
+        line,
+        reason: "data isn't hex".to_string(),
+    })?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let payload = Payload { msg: &data, aad };
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), payload)
+        .map_err(|_| SealError::Decrypt { line })
+}
+
+/// `bytes` as lowercase hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The bytes the hex string `text` spells, if it is one
+pub(crate) fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: &str =
+        "{\"record\":\"header\"}\n{\"record\":\"effect\"}\n{\"record\":\"footer\"}\n";
+
+    fn sealed(keys: SealKeys) -> String {
+        let mut sealer = Sealer::new(keys);
+        let mut out = String::new();
+        for line in TRACE.lines() {
+            out.push_str(&sealer.seal(line));
+            out.push('\n');
+        }
+        for line in sealer.finish() {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out
+    }
+
+    fn keys() -> (SealKeys, OpenKeys) {
+        let (secret, key) = (generate_key(), generate_key());
+        let seal = SealKeys {
+            signing: Some(SigningKey::from_bytes(&secret)),
+            encryption: Some(key),
+        };
+        let open = OpenKeys {
+            verifying: Some(VerifyingKey::from_bytes(&public_key(&secret)).unwrap()),
+            decryption: Some(key),
+            unverified: false,
+        };
+        (seal, open)
+    }
+
+    #[test]
+    fn sealed_traces_open_with_the_keys() {
+        assert_eq!(sealed(SealKeys::default()), TRACE);
+        let (seal, open_keys) = keys();
+        let trace = sealed(seal);
+        assert!(!trace.contains("header"), "got: {trace}");
+        let opened = open(&trace, &open_keys).unwrap();
+        assert_eq!(opened.jsonl, TRACE);
+        assert!(opened.signed);
+
+        // A signature is only left unchecked when asked to
+        let decrypt_only = OpenKeys {
+            verifying: None,
+            ..open_keys.clone()
+        };
+        assert_eq!(open(&trace, &decrypt_only), Err(SealError::Unverified));
+        let unverified = OpenKeys {
+            unverified: true,
+            ..decrypt_only
+        };
+        assert_eq!(open(&trace, &unverified).unwrap().jsonl, TRACE);
+        let no_keys = OpenKeys {
+            unverified: true,
+            ..OpenKeys::default()
+        };
+        assert_eq!(open(&trace, &no_keys), Err(SealError::Encrypted));
+        assert_eq!(open(TRACE, &open_keys), Err(SealError::NotSigned));
+    }
+
+    #[test]
+    fn tampering_is_caught() {
+        let (seal, open_keys) = keys();
+        let trace = sealed(seal.clone());
+        let lines: Vec<&str> = trace.lines().collect();
+
+        // Dropping a line breaks the signature
+        let dropped = [lines[0], lines[2], lines[3], lines[4]].join("\n");
+        assert_eq!(open(&dropped, &open_keys), Err(SealError::BadSignature));
+        // and, unsigned, the line numbers the records are bound to
+        let unsigned = [lines[0], lines[2], lines[3]].join("\n");
+        let decrypt_only = OpenKeys {
+            verifying: None,
+            ..open_keys.clone()
+        };
+        assert_eq!(
+            open(&unsigned, &decrypt_only),
+            Err(SealError::Decrypt { line: 2 })
+        );
+        // or the record count, if they're dropped from the end
+        for cut in [
+            &lines[..2],
+            &lines[..3],
+            &[lines[0], lines[1], lines[3]][..],
+        ] {
+            assert_eq!(
+                open(&cut.join("\n"), &decrypt_only),
+                Err(SealError::Truncated),
+                "{cut:?}"
+            );
+        }
+
+        // Plain lines in an encrypted trace are refused, wherever they are
+        let spliced = [lines[0], "{\"record\":\"effect\"}", lines[1]].join("\n");
+        assert_eq!(
+            open(&spliced, &decrypt_only),
+            Err(SealError::Unsealed { line: 2 })
+        );
+        assert_eq!(
+            open(TRACE, &decrypt_only),
+            Err(SealError::Unsealed { line: 1 })
+        );
+
+        // Another key's signature is refused, naming the key
+        let (other, _) = keys();
+        let other_trace = sealed(SealKeys {
+            encryption: seal.encryption,
+            ..other
+        });
+        assert!(matches!(
+            open(&other_trace, &open_keys),
+            Err(SealError::WrongKey { .. })
+        ));
+    }
+}
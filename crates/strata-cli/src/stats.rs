@@ -0,0 +1,129 @@
+//! Evaluator counters for `--stats`.
+//!
+//! A run given a [`StatsRecorder`] (via `RunOptions::stats`) counts what the
+//! evaluator does; [`StatsRecorder::snapshot`] reads the counts back as
+//! [`RunStats`]. Each count is one relaxed atomic add, so spawned tasks and
+//! `par` branches count into the same recorder, and a run without one pays
+//! a single branch per event.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// What a run did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunStats {
+    /// Expressions evaluated
+    pub exprs: u64,
+    /// Strata function calls, `main` included
+    pub calls: u64,
+    /// Host function calls, replayed ones included
+    pub host_calls: u64,
+    /// Most scopes one environment held at once
+    pub peak_env_depth: u64,
+    /// Values built that own heap data: strings from literals and `show`,
+    /// tuples, structs, enum values with fields, and nested-function closures
+    pub values: u64,
+    /// Wall-clock time from the start of the run to the end of `main`
+    pub elapsed: Duration,
+}
+
+impl RunStats {
+    /// The counts, one per line, with the elapsed time in milliseconds.
+    pub fn report(&self) -> String {
+        let rows: [(String, &str); 6] = [
+            (self.exprs.to_string(), "expressions evaluated"),
+            (self.calls.to_string(), "function calls"),
+            (self.host_calls.to_string(), "host calls"),
+            (self.peak_env_depth.to_string(), "peak environment depth"),
+            (self.values.to_string(), "values allocated"),
+            (
+                format!("{:.3}", self.elapsed.as_secs_f64() * 1000.0),
+                "ms elapsed",
+            ),
+        ];
+        rows.iter()
+            .map(|(n, what)| format!("{:>12}  {}\n", n, what))
+            .collect()
+    }
+}
+
+/// Counts a run's events as it goes.
+#[derive(Debug, Default)]
+pub struct StatsRecorder {
+    exprs: AtomicU64,
+    calls: AtomicU64,
+    host_calls: AtomicU64,
+    peak_env_depth: AtomicU64,
+    values: AtomicU64,
+    elapsed_ns: AtomicU64,
+}
+
+impl StatsRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counts so far.
+    pub fn snapshot(&self) -> RunStats {
+        RunStats {
+            exprs: self.exprs.load(Ordering::Relaxed),
+            calls: self.calls.load(Ordering::Relaxed),
+            host_calls: self.host_calls.load(Ordering::Relaxed),
+            peak_env_depth: self.peak_env_depth.load(Ordering::Relaxed),
+            values: self.values.load(Ordering::Relaxed),
+            elapsed: Duration::from_nanos(self.elapsed_ns.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub(crate) fn expr(&self) {
+        self.exprs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn call(&self) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn host_call(&self) {
+        self.host_calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// An environment now holds `depth` scopes.
+    pub(crate) fn env_depth(&self, depth: usize) {
+        self.peak_env_depth
+            .fetch_max(depth as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn value(&self) {
+        self.values.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The run took `elapsed`.
+    pub(crate) fn finish(&self, elapsed: Duration) {
+        let ns = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed_ns.store(ns, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_reads_back_counts_and_peak() {
+        let stats = StatsRecorder::new();
+        stats.expr();
+        stats.expr();
+        stats.call();
+        stats.env_depth(4);
+        stats.env_depth(2);
+        stats.finish(Duration::from_millis(3));
+
+        let snap = stats.snapshot();
+        assert_eq!(snap.exprs, 2);
+        assert_eq!(snap.calls, 1);
+        assert_eq!(snap.host_calls, 0);
+        assert_eq!(snap.peak_env_depth, 4);
+        assert_eq!(snap.elapsed, Duration::from_millis(3));
+        assert!(snap.report().contains("       3.000  ms elapsed"));
+    }
+}
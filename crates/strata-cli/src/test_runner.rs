@@ -0,0 +1,150 @@
+//! Test runner for `strata test`.
+//!
+//! Each `#[test]` function is called once, in a fresh environment holding
+//! the module's items, so one test's globals can't leak into the next. A
+//! test fails if it returns `false` or stops with a runtime error, and
+//! passes otherwise. Tests take no parameters, so they are handed no
+//! capabilities; host calls are refused at dispatch as well.
+//!
+//! A trace recorded with `strata run --trace-full` is a test as well:
+//! [`replay`] runs `main` against it, and the program passes if it makes
+//! the same host calls with the same inputs and ends the same way.
+
+use std::sync::Arc;
+
+use strata_ast::ast::{Expr, FnDecl, Item, Module};
+
+use crate::coverage::Coverage;
+use crate::eval::{self, Result, RuntimeError, Value};
+use crate::host::{EffectPolicy, TraceReplayer, TraceValue};
+
+/// How `strata test` runs a module's tests.
+#[derive(Debug, Clone, Default)]
+pub struct TestOptions {
+    /// Run only tests whose name contains this
+    pub filter: Option<String>,
+    /// Where to count the statements and branches the tests reach, if
+    /// anywhere
+    pub coverage: Option<Arc<Coverage>>,
+    /// Fail a test once it has evaluated this many expressions
+    pub step_limit: Option<u64>,
+}
+
+/// How one test ended.
+#[derive(Debug)]
+pub enum TestOutcome {
+    Passed,
+    /// Returned `false`
+    ReturnedFalse,
+    /// Stopped with a runtime error
+    Failed(RuntimeError),
+}
+
+impl TestOutcome {
+    pub fn passed(&self) -> bool {
+        matches!(self, TestOutcome::Passed)
+    }
+}
+
+/// The module's `#[test]` functions that `filter` selects, in source order
+pub fn tests<'m>(m: &'m Module, filter: Option<&str>) -> Vec<&'m FnDecl> {
+    m.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(decl) if decl.attrs.iter().any(|a| a.name.text == "test") => Some(decl),
+            _ => None,
+        })
+        .filter(|decl| filter.is_none_or(|f| decl.name.text.contains(f)))
+        .collect()
+}
+
+/// Run the tests of the checked module `m` as `opts` says, passing each
+/// one's outcome to `report` as it finishes. A failing test doesn't stop
+/// the others; an error setting the module up does.
+pub fn run_tests(
+    m: &Module,
+    opts: &TestOptions,
+    mut report: impl FnMut(&FnDecl, TestOutcome),
+) -> Result<()> {
+    for decl in tests(m, opts.filter.as_deref()) {
+        let mut env = eval::module_env(m, EffectPolicy::allow_only(&[]), |mut env| {
+            if let Some(coverage) = &opts.coverage {
+                env = env.with_coverage(coverage.clone());
+            }
+            if let Some(limit) = opts.step_limit {
+                env = env.with_step_limit(limit);
+            }
+            env
+        })?;
+        // The test is called by name, as `name()`
+        let call = Expr::Call {
+            callee: Box::new(Expr::Var(decl.name.clone())),
+            args: Vec::new(),
+            names: Vec::new(),
+            span: decl.name.span,
+        };
+        let outcome = match eval::eval_expr(&mut env, &call) {
+            Ok(cf) => match cf.into_value() {
+                Value::Bool(false) => TestOutcome::ReturnedFalse,
+                _ => TestOutcome::Passed,
+            },
+            Err(e) => TestOutcome::Failed(e),
+        };
+        report(decl, outcome);
+    }
+    Ok(())
+}
+
+/// How one replayed trace ended.
+#[derive(Debug)]
+pub enum ReplayOutcome {
+    Passed,
+    /// `main` returned `replayed`, where the recorded run returned
+    /// `recorded` (or failed, for `None`)
+    ResultChanged {
+        recorded: Option<TraceValue>,
+        replayed: TraceValue,
+    },
+    /// The host calls diverged from the trace, or the program failed where
+    /// the recorded run didn't
+    Failed(RuntimeError),
+}
+
+impl ReplayOutcome {
+    pub fn passed(&self) -> bool {
+        matches!(self, ReplayOutcome::Passed)
+    }
+}
+
+/// Replay the trace `trace_jsonl` against the checked module `m`. A trace
+/// of a failed run passes if the replay fails too, and one without a
+/// recorded result (from before results were recorded) if the host calls
+/// match.
+pub fn replay(m: &Module, trace_jsonl: &str) -> ReplayOutcome {
+    let recorded = TraceReplayer::from_jsonl(trace_jsonl)
+        .ok()
+        .and_then(|replayer| {
+            replayer
+                .footer()
+                .map(|f| (f.program_status == "error", f.result.clone()))
+        });
+    match (eval::run_module_replay(m, trace_jsonl), recorded) {
+        (Ok(value), Some((true, _))) => ReplayOutcome::ResultChanged {
+            recorded: None,
+            replayed: TraceValue::from_value(&value),
+        },
+        (Ok(value), Some((false, Some(recorded)))) => {
+            let replayed = TraceValue::from_value(&value);
+            match replayed == recorded {
+                true => ReplayOutcome::Passed,
+                false => ReplayOutcome::ResultChanged {
+                    recorded: Some(recorded),
+                    replayed,
+                },
+            }
+        }
+        (Ok(_), _) => ReplayOutcome::Passed,
+        (Err(_), Some((true, _))) => ReplayOutcome::Passed,
+        (Err(e), _) => ReplayOutcome::Failed(e),
+    }
+}
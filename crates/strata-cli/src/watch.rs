@@ -0,0 +1,206 @@
+//! Edit-check loop for `strata watch`.
+//!
+//! [`Watcher`] polls a source file and returns its text only when the
+//! text changed, so saving without editing doesn't trigger a recheck.
+//! [`check`] parses and type-checks that text and renders each problem
+//! as `file:line:col: error[CODE]: message`; [`render_runtime_error`]
+//! renders a failed run the same way.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use strata_ast::ast::Module;
+use strata_ast::span::SourceDb;
+use strata_parse::{parse_source_with_options, ParseOptions};
+use strata_types::{LintConfig, Profile, TypeChecker};
+
+use crate::eval::RuntimeError;
+
+/// Polls one file for changes.
+#[derive(Debug)]
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    src: Option<String>,
+}
+
+impl Watcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: None,
+            src: None,
+        }
+    }
+
+    /// The file's text if it changed since the last poll; the first poll
+    /// always returns it. The text is only read when the modification
+    /// time moved.
+    pub fn poll(&mut self) -> std::io::Result<Option<String>> {
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        if modified.is_some() && modified == self.modified {
+            return Ok(None);
+        }
+        self.modified = modified;
+        let src = std::fs::read_to_string(&self.path)?;
+        if self.src.as_deref() == Some(src.as_str()) {
+            return Ok(None);
+        }
+        self.src = Some(src.clone());
+        Ok(Some(src))
+    }
+}
+
+/// Outcome of checking one version of the source.
+#[derive(Debug)]
+pub struct Checked {
+    /// The module, if it parsed and type-checked
+    pub module: Option<Module>,
+    /// Rendered errors, then warnings
+    pub diagnostics: Vec<String>,
+}
+
+impl Checked {
+    pub fn is_ok(&self) -> bool {
+        self.module.is_some()
+    }
+}
+
+/// Parse `src`, the contents of `file`, with `opts` and type-check it
+/// under `profile`, with lints at the levels `lints` sets.
+pub fn check(
+    file: &str,
+    src: &str,
+    profile: Profile,
+    opts: &ParseOptions,
+    lints: &LintConfig,
+) -> Checked {
+    let mut db = SourceDb::new();
+    let id = db.add(file, src);
+    let at = |span| db.location(span);
+
+    let module = match parse_source_with_options(&db, id, opts) {
+        Ok(module) => module,
+        Err(e) => {
+            return Checked {
+                module: None,
+                diagnostics: vec![format!("{}: error[{}]: {}", at(e.span()), e.code(), e)],
+            }
+        }
+    };
+
+    let mut checker = TypeChecker::new_with_profile(profile).with_lint_config(lints.clone());
+    let result = checker.check_module(&module);
+    let mut diagnostics = Vec::new();
+    if let Err(e) = &result {
+        diagnostics.push(format!("{}: error[{}]: {}", at(e.span()), e.code(), e));
+    }
+    for warning in checker.take_warnings() {
+        diagnostics.push(format!("{}: warning: {}", at(warning.span()), warning));
+    }
+    Checked {
+        module: result.is_ok().then_some(module),
+        diagnostics,
+    }
+}
+
+/// Render `error`, from running `src` (the contents of `file`), at the
+/// expression that failed, followed by the calls it unwound through.
+pub fn render_runtime_error(file: &str, src: &str, error: &RuntimeError) -> String {
+    let mut db = SourceDb::new();
+    db.add(file, src);
+    let diag = error.to_diagnostic();
+    let mut out = match error.span {
+        Some(span) => format!(
+            "{}: error[{}]: {}",
+            db.location(span),
+            diag.code,
+            diag.message
+        ),
+        None => format!("{}: error[{}]: {}", file, diag.code, diag.message),
+    };
+    for function in &error.stack {
+        out.push_str("\n  in ");
+        out.push_str(function);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostics_carry_line_and_column() {
+        let checked = check(
+            "w.strata",
+            "fn main() -> Int {\n    true\n}\n",
+            Profile::Default,
+            &ParseOptions::default(),
+            &LintConfig::new(),
+        );
+        assert!(!checked.is_ok());
+        assert_eq!(checked.diagnostics.len(), 1);
+        assert!(
+            checked.diagnostics[0].starts_with("w.strata:1:1: error[TYPE-MISMATCH]: "),
+            "{:?}",
+            checked.diagnostics
+        );
+
+        let checked = check(
+            "w.strata",
+            "fn main() -> Int {\n    let x = ;\n}\n",
+            Profile::Default,
+            &ParseOptions::default(),
+            &LintConfig::new(),
+        );
+        assert!(checked.diagnostics[0].starts_with("w.strata:2:13: error[PARSE-"));
+
+        assert!(check(
+            "w.strata",
+            "fn main() -> Int { 0 }\n",
+            Profile::Default,
+            &ParseOptions::default(),
+            &LintConfig::new(),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn runtime_errors_render_at_the_failing_subexpression() {
+        let src = "fn half(n: Int) -> Int {\n    1 + n / 0\n}\nfn main() -> Int { half(4) }\n";
+        let module = check(
+            "w.strata",
+            src,
+            Profile::Default,
+            &ParseOptions::default(),
+            &LintConfig::new(),
+        )
+        .module
+        .expect("checks");
+        let err = crate::eval::run_module(&module).unwrap_err();
+        assert_eq!(
+            render_runtime_error("w.strata", src, &err),
+            "w.strata:2:9: error[RUN-ERROR]: division by zero\n  in half\n  in main"
+        );
+    }
+
+    #[test]
+    fn poll_returns_only_changed_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("w.strata");
+        std::fs::write(&path, "a").unwrap();
+        let mut watcher = Watcher::new(&path);
+        assert_eq!(watcher.poll().unwrap().as_deref(), Some("a"));
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        // Rewritten with the same text: nothing to recheck
+        watcher.modified = None;
+        std::fs::write(&path, "a").unwrap();
+        assert_eq!(watcher.poll().unwrap(), None);
+
+        watcher.modified = None;
+        std::fs::write(&path, "b").unwrap();
+        assert_eq!(watcher.poll().unwrap().as_deref(), Some("b"));
+    }
+}
@@ -5,7 +5,13 @@
 use std::process::Command;
 
 fn strata_bin() -> Command {
-    Command::new(env!("CARGO_BIN_EXE_strata-cli"))
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_strata-cli"));
+    // Keep the default audit log out of the user's state directory
+    cmd.env(
+        "STRATA_AUDIT_LOG",
+        std::env::temp_dir().join("strata-cli-tests-audit.log"),
+    );
+    cmd
 }
 
 #[test]
@@ -15,8 +21,8 @@ fn cli_run_no_trace() {
     std::fs::write(
         &file,
         r#"
-        fn main() -> Int {
-            1 + 2
+        fn main() -> String {
+            "done"
         }
         "#,
     )
@@ -34,12 +40,585 @@ fn cli_run_no_trace() {
         String::from_utf8_lossy(&output.stderr)
     );
     assert!(
-        stdout.contains("main() = 3"),
+        stdout.contains(r#"main() = "done""#),
         "stdout should contain result: {}",
         stdout
     );
 }
 
+#[test]
+fn cli_run_features_select_cfg_items() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("cfg.strata");
+    std::fs::write(
+        &file,
+        r#"
+        #[cfg(feature = "fast")]
+        fn mode() -> String { "fast" }
+        #[cfg(not(feature = "fast"))]
+        fn mode() -> String { "slow" }
+        fn main() -> String { mode() }
+        "#,
+    )
+    .expect("write source");
+
+    let run = |extra: &[&str]| {
+        let output = strata_bin()
+            .arg("run")
+            .args(extra)
+            .arg(file.to_str().unwrap())
+            .output()
+            .expect("run binary");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert!(run(&[]).contains(r#"main() = "slow""#));
+    assert!(run(&["--features", "fast"]).contains(r#"main() = "fast""#));
+    assert!(run(&["--features", "other,fast"]).contains(r#"main() = "fast""#));
+
+    let output = strata_bin()
+        .args(["check", "--features", "fast", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert!(output.status.success());
+}
+
+#[test]
+fn cli_check_lint_level_flags() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("lints.strata");
+    std::fs::write(
+        &file,
+        "fn shadows() -> Int { let x = 1; let x = 2; x }\n\
+         fn pure_work(fs: FsCap, n: Int) -> Int { n }\n",
+    )
+    .expect("write source");
+
+    let check = |extra: &[&str]| {
+        let output = strata_bin()
+            .args(["check", "--no-cache"])
+            .args(extra)
+            .arg(file.to_str().unwrap())
+            .output()
+            .expect("run binary");
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    };
+    let (code, stderr) = check(&[]);
+    assert_eq!(code, Some(0));
+    assert!(stderr.contains("[unused_capability]"), "got: {stderr}");
+    assert!(!stderr.contains("[shadowed_binding]"), "got: {stderr}");
+
+    let (code, stderr) = check(&["--allow", "unused_capability", "--warn", "shadowed_binding"]);
+    assert_eq!(code, Some(0));
+    assert!(!stderr.contains("[unused_capability]"), "got: {stderr}");
+    assert!(stderr.contains("[shadowed_binding]"), "got: {stderr}");
+
+    let (code, stderr) = check(&["--deny", "shadowed_binding,unused_capability"]);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("(denied)"), "got: {stderr}");
+
+    let (code, stderr) = check(&["--deny", "unused_variable"]);
+    assert_eq!(code, Some(2));
+    assert!(
+        stderr.contains("unknown lint 'unused_variable'"),
+        "got: {stderr}"
+    );
+}
+
+/// Run `src` with `strata run`, returning the exit code and stderr
+#[test]
+fn cli_test_runs_tests_and_writes_coverage() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("tests.strata");
+    std::fs::write(
+        &file,
+        "fn double(n: Int) -> Int { n + n }\n\
+         #[test] fn doubles() -> Bool { double(2) == 4 }\n\
+         #[test] fn wrong() -> Bool { double(2) == 5 }\n\
+         #[test] fn divides() -> Int { let zero = 0; 1 / zero }\n",
+    )
+    .expect("write source");
+    let lcov = dir.path().join("cov.lcov");
+
+    let test = |extra: &[&str]| {
+        let output = strata_bin()
+            .args(["test", file.to_str().unwrap()])
+            .args(extra)
+            .output()
+            .expect("run binary");
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    };
+
+    let (code, stdout, stderr) = test(&[]);
+    assert_eq!(code, Some(70));
+    assert!(stdout.starts_with("running 3 tests\n"), "got: {stdout}");
+    assert!(stdout.contains("test doubles ... ok"), "got: {stdout}");
+    assert!(
+        stdout.contains("test wrong ... FAILED (returned false)"),
+        "got: {stdout}"
+    );
+    assert!(stdout.contains("test divides ... FAILED"), "got: {stdout}");
+    assert!(stderr.contains("division by zero"), "got: {stderr}");
+    assert!(
+        stdout.contains("test result: FAILED. 1 passed; 2 failed"),
+        "got: {stdout}"
+    );
+
+    let (code, stdout, stderr) = test(&[
+        "doubles",
+        "--coverage",
+        "--coverage-out",
+        lcov.to_str().unwrap(),
+    ]);
+    assert_eq!(code, Some(0), "got: {stdout}");
+    assert!(stdout.contains("test result: ok. 1 passed; 0 failed"));
+    assert!(
+        stderr.contains("coverage: lines: 1/1 (100.0%), branches: 0/0"),
+        "got: {stderr}"
+    );
+    let lcov = std::fs::read_to_string(&lcov).expect("report written");
+    assert!(lcov.contains("\nDA:1,1\n"), "got: {lcov}");
+}
+
+#[test]
+fn cli_test_replays_recorded_traces() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let traces = dir.path().join("traces");
+    std::fs::create_dir(&traces).expect("create trace dir");
+    let input = dir.path().join("data.txt");
+    std::fs::write(&input, "replay data").expect("write input");
+    let file = dir.path().join("prog.strata");
+    let write = |body: &str| {
+        let src = format!(
+            "extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};\n\
+             fn main(fs: FsCap) -> String & {{Fs}} {{ {} }}\n",
+            body.replace("PATH", &format!("{:?}", input.to_str().unwrap()))
+        );
+        std::fs::write(&file, src).expect("write source");
+    };
+    let test = || {
+        let output = strata_bin()
+            .args(["test", file.to_str().unwrap(), "--replay-dir"])
+            .arg(&traces)
+            .output()
+            .expect("run binary");
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        )
+    };
+
+    write("read_file(&fs, PATH)");
+    let run = strata_bin()
+        .args(["run", file.to_str().unwrap(), "--trace-full"])
+        .arg(traces.join("read.jsonl"))
+        .output()
+        .expect("run binary");
+    assert!(run.status.success());
+    // Files that aren't traces are skipped
+    std::fs::write(traces.join("notes.txt"), "not a trace").expect("write notes");
+
+    // A refactor that keeps the behavior passes
+    write("let data = read_file(&fs, PATH); data");
+    let (code, stdout) = test();
+    assert_eq!(code, Some(0), "got: {stdout}");
+    assert!(
+        stdout.starts_with("running 0 tests and 1 trace\n"),
+        "got: {stdout}"
+    );
+    assert!(stdout.contains("trace read ... ok"), "got: {stdout}");
+
+    write("read_file(&fs, PATH); \"other\"");
+    let (code, stdout) = test();
+    assert_eq!(code, Some(70));
+    assert!(
+        stdout.contains(
+            "trace read ... FAILED (returned \"other\"; the recorded run returned \"replay data\")"
+        ),
+        "got: {stdout}"
+    );
+
+    write("read_file(&fs, \"/elsewhere.txt\")");
+    let (code, stdout) = test();
+    assert_eq!(code, Some(70));
+    assert!(stdout.contains("trace read ... FAILED\n"), "got: {stdout}");
+    assert!(stdout.contains("test result: FAILED. 0 passed; 1 failed"));
+}
+
+#[test]
+fn cli_api_check_fails_on_breaking_changes() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let emit = |name: &str, src: &str| {
+        let file = dir.path().join(format!("{name}.strata"));
+        let iface = dir.path().join(format!("{name}.stif"));
+        std::fs::write(&file, src).expect("write source");
+        let output = strata_bin()
+            .args(["check", file.to_str().unwrap(), "--emit-interface"])
+            .arg(&iface)
+            .output()
+            .expect("run binary");
+        assert_eq!(output.status.code(), Some(0));
+        iface
+    };
+    let v1 = emit(
+        "v1",
+        "pub enum Shape { Circle(Float) }\npub fn zero() -> Int & {} { 0 }\n",
+    );
+    let v2 = emit(
+        "v2",
+        "pub enum Shape { Circle(Float) }\npub fn zero() -> Int & {} { 0 }\npub fn one() -> Int & {} { 1 }\n",
+    );
+    let v3 = emit(
+        "v3",
+        "pub enum Shape { Circle(Float), Square(Float) }\npub fn zero() -> Int & {} { 0 }\n",
+    );
+
+    let output = strata_bin()
+        .arg("api-check")
+        .args([&v1, &v2])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "got: {stdout}");
+    assert!(
+        stdout.contains("compatible: fn one: added"),
+        "got: {stdout}"
+    );
+
+    let output = strata_bin()
+        .arg("api-check")
+        .args([&v1, &v3])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "got: {stdout}");
+    assert!(
+        stdout.contains("breaking: enum Shape: variant Square added"),
+        "got: {stdout}"
+    );
+    assert!(stdout.contains("1 breaking, 0 compatible"), "got: {stdout}");
+}
+
+#[test]
+fn cli_diff_lists_item_changes() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let old = dir.path().join("old.strata");
+    let new = dir.path().join("new.strata");
+    std::fs::write(
+        &old,
+        "pub fn area(w: Int, h: Int) -> Int { w * h }\nstruct Point { x: Int }\n",
+    )
+    .expect("write source");
+    std::fs::write(
+        &new,
+        "struct Point { x: Int, y: Int }\npub fn area(w: Int, h: Int) -> Int { h * w }\n",
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["diff", old.to_str().unwrap(), new.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "got: {stdout}");
+    assert_eq!(stdout, "+ struct Point: field y: Int\n");
+
+    let output = strata_bin()
+        .args(["diff", old.to_str().unwrap(), old.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "no item-level changes\n"
+    );
+}
+
+#[test]
+fn cli_reduce_keeps_what_the_predicate_needs() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("big.strata");
+    std::fs::write(
+        &file,
+        "fn a() -> Int { 1 }\n\
+         fn b() -> String { let s = \"needle\"; s }\n\
+         fn c() -> Int { a() + 2 }\n",
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args([
+            "reduce",
+            file.to_str().unwrap(),
+            "--exec",
+            "grep -q needle \"$1\"",
+        ])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "got: {stdout}");
+    assert!(stdout.contains("\"needle\""), "got: {stdout}");
+    assert!(
+        !stdout.contains("fn a") && !stdout.contains("fn c"),
+        "got: {stdout}"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("reduced "));
+
+    // There is nothing to reduce if the program doesn't fail to begin with
+    let output = strata_bin()
+        .args([
+            "reduce",
+            file.to_str().unwrap(),
+            "--error-code",
+            "TYPE-INFINITE",
+        ])
+        .output()
+        .expect("run binary");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("doesn't satisfy the predicate"));
+}
+
+#[test]
+fn cli_mutate_reports_surviving_mutants() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("mutants.strata");
+    std::fs::write(
+        &file,
+        "fn clamp(n: Int, hi: Int) -> Int { if n > hi { hi } else { n } }\n\
+         fn twice(n: Int) -> Int { n * 2 }\n\
+         #[test] fn clamps() -> Bool { clamp(9, 5) == 5 }\n",
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["mutate", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "got: {stdout}");
+    assert!(
+        stdout.contains("mutants.strata:1:39: replace `>` with `<=` ... killed by clamps"),
+        "got: {stdout}"
+    );
+    assert!(
+        stdout.contains("mutants.strata:2:27: replace `*` with `/` ... SURVIVED"),
+        "got: {stdout}"
+    );
+    assert!(
+        stdout.contains("mutants: 2 killed, 1 survived, 0 skipped"),
+        "got: {stdout}"
+    );
+
+    // Mutants are only judged against tests that pass to begin with
+    std::fs::write(
+        &file,
+        "fn one() -> Int { 1 + 0 }\n#[test] fn wrong() -> Bool { one() == 2 }\n",
+    )
+    .expect("write source");
+    let output = strata_bin()
+        .args(["mutate", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert_eq!(output.status.code(), Some(70));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("tests fail without any mutation: wrong")
+    );
+}
+
+#[test]
+fn cli_bench_reports_time_and_steps_per_iter() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("benches.strata");
+    std::fs::write(
+        &file,
+        "fn fib(n: Int) -> Int { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }\n\
+         bench fn fib_5() -> Int { fib(5) }\n\
+         bench fn fib_10() -> Int { fib(10) }\n\
+         bench fn divide() -> Int { let zero = 0; 1 / zero }\n",
+    )
+    .expect("write source");
+
+    let bench = |extra: &[&str]| {
+        let output = strata_bin()
+            .args([
+                "bench",
+                file.to_str().unwrap(),
+                "--warmup",
+                "1",
+                "--iters",
+                "3",
+            ])
+            .args(extra)
+            .output()
+            .expect("run binary");
+        (
+            output.status.code(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+        )
+    };
+    let steps = |stdout: &str, name: &str| -> u64 {
+        let line = stdout
+            .lines()
+            .find(|l| l.starts_with(&format!("bench {name} ...")))
+            .unwrap_or_else(|| panic!("no line for {name} in: {stdout}"));
+        assert!(line.contains(" ns/iter ("), "got: {line}");
+        let steps = line.rsplit('(').next().unwrap();
+        steps
+            .trim_end_matches(" steps/iter)")
+            .parse()
+            .expect("steps")
+    };
+
+    let (code, stdout) = bench(&["fib"]);
+    assert_eq!(code, Some(0), "got: {stdout}");
+    // Steps are the same every run, and grow with the work
+    let (small, large) = (steps(&stdout, "fib_5"), steps(&stdout, "fib_10"));
+    assert!(0 < small && small < large, "got: {stdout}");
+    let (_, again) = bench(&["fib_10"]);
+    assert_eq!(steps(&again, "fib_10"), large);
+    assert!(!again.contains("fib_5"), "got: {again}");
+
+    // A failing benchmark is reported, and the others still run
+    let (code, stdout) = bench(&[]);
+    assert_eq!(code, Some(70));
+    assert!(stdout.contains("bench divide ... FAILED"), "got: {stdout}");
+    assert!(stdout.contains("bench fib_10 ..."), "got: {stdout}");
+}
+
+fn run_exit_code(src: &str) -> (Option<i32>, String) {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("exit.strata");
+    std::fs::write(&file, src).expect("write source");
+    let output = strata_bin()
+        .args(["run", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn cli_run_int_main_sets_exit_code() {
+    assert_eq!(run_exit_code("fn main() -> Int { 1 + 2 }").0, Some(3));
+    assert_eq!(run_exit_code("fn main() -> Int { 0 }").0, Some(0));
+}
+
+#[test]
+fn cli_run_named_arguments_go_to_their_parameters() {
+    let src = "fn draw(x: Int, y: Int) -> Int { x * 10 + y }
+fn main() -> Int {
+    fn sub(a: Int, b: Int) -> Int { a - b }
+    draw(y: 2, x: 1) + sub(b: 1, a: 100)
+}";
+    assert_eq!(run_exit_code(src).0, Some(111));
+}
+
+#[test]
+fn cli_run_struct_literals_get_field_defaults() {
+    let src = "const BASE: Int = 2;
+struct Config { retries: Int = BASE + 1, host: String }
+fn retries(c: Config) -> Int { match c { Config { retries: n, host: _ } => n } }
+fn main() -> Int {
+    retries(Config { host: \"a\" }) * 10 + retries(Config { host: \"b\", retries: 4 })
+}";
+    assert_eq!(run_exit_code(src).0, Some(34));
+}
+
+#[test]
+fn cli_run_derived_show_and_structural_eq() {
+    let src = "#[derive(Show)]
+struct Point { x: Int, y: Int }
+enum Shape { Dot, Circle(Int), Line(Point, Point) }
+fn main() -> Int {
+    let a = Point { x: 1, y: 2 };
+    let same = a == Point { x: 1, y: 2 } && a != Point { x: 2, y: 1 };
+    let shapes = Shape::Line(a, a) == Shape::Line(a, a) && Shape::Dot != Shape::Circle(1);
+    let shown = point_to_string(a) == show(a);
+    if same && shapes && shown { 42 } else { 1 }
+}";
+    assert_eq!(run_exit_code(src).0, Some(42));
+}
+
+#[test]
+fn cli_run_partial_application() {
+    let src = "fn scale(k: Int, x: Int, c: Int) -> Int { k * x + c }
+fn apply(f, x: Int) -> Int { f(x) }
+fn main() -> Int {
+    let lin = scale(_, 2, _);
+    let twice = lin(10, _);
+    let named = scale(c: 1, k: _, x: 3);
+    apply(scale(2, _, 1), 5) + lin(3, 4) + twice(0) + named(1)
+}";
+    assert_eq!(run_exit_code(src).0, Some(45));
+}
+
+#[test]
+fn cli_run_exit_code_out_of_range_is_runtime_error() {
+    let (code, stderr) = run_exit_code("fn main() -> Int { 256 }");
+    assert_eq!(code, Some(70));
+    assert!(stderr.contains("0-255"), "stderr: {}", stderr);
+    assert_eq!(run_exit_code("fn main() -> Int { -1 }").0, Some(70));
+}
+
+#[test]
+fn cli_run_errors_have_distinct_exit_codes() {
+    let (code, stderr) = run_exit_code("fn main() -> Int { todo() }");
+    assert_eq!(code, Some(70));
+    assert!(stderr.contains("Runtime error"), "stderr: {}", stderr);
+    assert_eq!(run_exit_code("fn main() -> Int { true }").0, Some(1));
+    assert_eq!(run_exit_code("fn main(n: Int) -> Int { n }").0, Some(1));
+    let (code, stderr) = run_exit_code("fn main() -> Int { 9223372036854775807 + 1 }");
+    assert_eq!(code, Some(71));
+    assert!(stderr.contains("RUN-INT-OVERFLOW"), "stderr: {}", stderr);
+}
+
+#[test]
+fn cli_run_errors_are_prefixed_with_file_line_col() {
+    let located = |stderr: &str, at: &str| {
+        stderr
+            .lines()
+            .any(|l| l.contains("exit.strata:") && l.contains(at))
+    };
+    let (_, stderr) = run_exit_code("fn main() -> Int {\n    let x = ;\n}");
+    assert!(
+        located(&stderr, ":2:13: Parse error:"),
+        "stderr: {}",
+        stderr
+    );
+    let (_, stderr) = run_exit_code("fn f() -> Int { 0 }\nfn main() -> Int { true }");
+    assert!(located(&stderr, ":2:1: Type error:"), "stderr: {}", stderr);
+    let (_, stderr) = run_exit_code("fn main() -> Int {\n    todo()\n}");
+    assert!(
+        located(&stderr, ":2:5: Runtime error:"),
+        "stderr: {}",
+        stderr
+    );
+    let (_, stderr) =
+        run_exit_code("fn half(n: Int) -> Int { n / 0 }\nfn main() -> Int { half(4) }");
+    assert!(
+        located(&stderr, ":1:26: Runtime error: division by zero"),
+        "stderr: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("\n  in half\n  in main\n"),
+        "stderr: {}",
+        stderr
+    );
+}
+
 #[test]
 fn cli_run_with_trace() {
     let dir = tempfile::tempdir().expect("create tempdir");
@@ -155,18 +734,72 @@ fn cli_replay_success() {
         "stdout should report success: {}",
         stdout
     );
+
+    // The header records what the trace was recorded from
+    let summary = strata_bin()
+        .args(["replay", trace.to_str().unwrap()])
+        .output()
+        .expect("replay binary");
+    let stdout = String::from_utf8_lossy(&summary.stdout);
+    let recorded_from = format!(
+        "Recorded from: {} by strata {} (profile default)",
+        strata_cli::host::source_hash(&src),
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(stdout.contains(&recorded_from), "got: {stdout}");
+    assert!(
+        stdout.contains(&format!(
+            "Command: strata run {} --trace-full {}",
+            source.display(),
+            trace.display()
+        )),
+        "got: {stdout}"
+    );
+
+    // A trace piped in replays as it's read
+    let mut child = strata_bin()
+        .args(["replay", "-", source.to_str().unwrap()])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("spawn binary");
+    let content = std::fs::read(&trace).expect("read trace");
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), &content).expect("write stdin");
+    let piped = child.wait_with_output().expect("wait for binary");
+    let stdout = String::from_utf8_lossy(&piped.stdout);
+    assert!(
+        stdout.contains("Replay successful: 1 effects replayed."),
+        "got: {stdout}, stderr: {}",
+        String::from_utf8_lossy(&piped.stderr)
+    );
+
+    // One entry can be read on its own
+    let entry = strata_bin()
+        .args(["replay", trace.to_str().unwrap(), "--entry", "0"])
+        .output()
+        .expect("replay binary");
+    let entry: serde_json::Value = serde_json::from_slice(&entry.stdout).expect("entry is JSON");
+    assert_eq!(entry["operation"], "read_file");
+    let missing = strata_bin()
+        .args(["replay", trace.to_str().unwrap(), "--entry", "1"])
+        .output()
+        .expect("replay binary");
+    assert_eq!(missing.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&missing.stderr);
+    assert!(stderr.contains("has 1 effect entries"), "got: {stderr}");
 }
 
 #[test]
-fn cli_replay_mismatch() {
+fn cli_replay_sealed_trace() {
     let dir = tempfile::tempdir().expect("create tempdir");
-    let source_v1 = dir.path().join("v1.strata");
-    let source_v2 = dir.path().join("v2.strata");
-    let input = dir.path().join("file.txt");
+    let source = dir.path().join("sealed.strata");
+    let input = dir.path().join("secret.txt");
     let trace = dir.path().join("trace.jsonl");
-    std::fs::write(&input, "original").expect("write input");
-
-    let src_v1 = format!(
+    let sign_key = dir.path().join("sign.key");
+    let crypt_key = dir.path().join("crypt.key");
+    std::fs::write(&input, "hunter2").expect("write input");
+    let src = format!(
         r#"
         extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
 
@@ -176,36 +809,330 @@ fn cli_replay_mismatch() {
         "#,
         input.to_str().unwrap()
     );
-    std::fs::write(&source_v1, &src_v1).expect("write v1 source");
+    std::fs::write(&source, &src).expect("write source");
+
+    for args in [
+        vec!["keygen", sign_key.to_str().unwrap()],
+        vec!["keygen", crypt_key.to_str().unwrap(), "--encryption"],
+    ] {
+        let out = strata_bin().args(&args).output().expect("keygen binary");
+        assert!(out.status.success(), "{args:?} should succeed");
+    }
+    let public_key = format!("{}.pub", sign_key.display());
+    assert!(std::path::Path::new(&public_key).exists());
 
-    // Record trace from v1
     let run_output = strata_bin()
         .args([
             "run",
-            source_v1.to_str().unwrap(),
+            source.to_str().unwrap(),
             "--trace-full",
             trace.to_str().unwrap(),
+            "--trace-sign-key",
+            sign_key.to_str().unwrap(),
+            "--trace-encrypt-key",
+            crypt_key.to_str().unwrap(),
         ])
         .output()
         .expect("run binary");
-    assert!(run_output.status.success());
+    assert!(
+        run_output.status.success(),
+        "run should succeed: {}",
+        String::from_utf8_lossy(&run_output.stderr)
+    );
+    let sealed = std::fs::read_to_string(&trace).expect("read trace");
+    assert!(
+        !sealed.contains("hunter2"),
+        "trace leaks the file: {sealed}"
+    );
+    assert!(sealed.lines().last().unwrap().contains("\"signature\""));
 
-    // v2 reads a different path
-    let src_v2 = r#"
-        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+    let replay = |trace: &std::path::Path| {
+        strata_bin()
+            .args([
+                "replay",
+                trace.to_str().unwrap(),
+                source.to_str().unwrap(),
+                "--trace-verify-key",
+                &public_key,
+                "--trace-decrypt-key",
+                crypt_key.to_str().unwrap(),
+            ])
+            .output()
+            .expect("replay binary")
+    };
+    let replayed = replay(&trace);
+    assert!(
+        replayed.status.success(),
+        "replay should succeed, stderr: {}",
+        String::from_utf8_lossy(&replayed.stderr)
+    );
 
-        fn main(fs: FsCap) -> String & {Fs} {
+    // Without the decryption key the trace can't be read, which is rejected
+    // input (exit 1) whether or not there's a source to replay against
+    for args in [&[source.to_str().unwrap()][..], &[]] {
+        let locked = strata_bin()
+            .args(["replay", trace.to_str().unwrap()])
+            .args(args)
+            .output()
+            .expect("replay binary");
+        assert_eq!(locked.status.code(), Some(1), "{args:?}");
+        let stderr = String::from_utf8_lossy(&locked.stderr);
+        assert!(stderr.contains("--trace-decrypt-key"), "got: {stderr}");
+    }
+
+    // and without a key to verify with, the signature must be waived
+    let unchecked = strata_bin()
+        .args([
+            "replay",
+            trace.to_str().unwrap(),
+            "--trace-decrypt-key",
+            crypt_key.to_str().unwrap(),
+        ])
+        .output()
+        .expect("replay binary");
+    assert_eq!(unchecked.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&unchecked.stderr);
+    assert!(stderr.contains("--trace-unverified"), "got: {stderr}");
+
+    // A trace with a line dropped is refused
+    let tampered = dir.path().join("tampered.jsonl");
+    let lines: Vec<&str> = sealed.lines().collect();
+    let kept: Vec<&str> = [&lines[..1], &lines[2..]].concat();
+    std::fs::write(&tampered, kept.join("\n") + "\n").expect("write trace");
+    let refused = replay(&tampered);
+    assert_eq!(refused.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(stderr.contains("signature doesn't match"), "got: {stderr}");
+
+    // A trace that's only signed is read as replay goes, and its signature
+    // is still refused unless waived, exiting 1 as when it's read whole
+    let signed = dir.path().join("signed.jsonl");
+    let run_output = strata_bin()
+        .args([
+            "run",
+            source.to_str().unwrap(),
+            "--trace-full",
+            signed.to_str().unwrap(),
+            "--trace-sign-key",
+            sign_key.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run binary");
+    assert!(run_output.status.success());
+    let streamed = |args: &[&str]| {
+        strata_bin()
+            .args(["replay", signed.to_str().unwrap()])
+            .args(args)
+            .output()
+            .expect("replay binary")
+    };
+    for args in [&[source.to_str().unwrap()][..], &[]] {
+        let refused = streamed(args);
+        assert_eq!(refused.status.code(), Some(1), "{args:?}");
+        let stderr = String::from_utf8_lossy(&refused.stderr);
+        assert!(stderr.contains("--trace-unverified"), "got: {stderr}");
+    }
+    let waived = streamed(&[source.to_str().unwrap(), "--trace-unverified"]);
+    assert!(
+        waived.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&waived.stderr)
+    );
+    let summary = streamed(&["--trace-unverified"]);
+    let stdout = String::from_utf8_lossy(&summary.stdout);
+    assert!(stdout.contains("Trace summary: 1 effects"), "got: {stdout}");
+}
+
+#[test]
+fn cli_variadic_log_is_traced_as_a_list() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("log.strata");
+    let trace = dir.path().join("trace.jsonl");
+    std::fs::write(
+        &source,
+        r#"
+        extern fn log(console: &ConsoleCap, fmt: String, args: ...Show) -> () & {Console};
+
+        fn main(console: ConsoleCap) -> () & {Console} {
+            log(&console, "{} of {}: {}", 2, 3, "done")
+        }
+        "#,
+    )
+    .expect("write source");
+
+    let run_output = strata_bin()
+        .args([
+            "run",
+            source.to_str().unwrap(),
+            "--trace-full",
+            trace.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run binary");
+    let stderr = String::from_utf8_lossy(&run_output.stderr);
+    assert!(run_output.status.success(), "run should succeed: {stderr}");
+    assert!(stderr.contains("2 of 3: done"), "stderr: {stderr}");
+    let recorded = std::fs::read_to_string(&trace).expect("read trace");
+    assert!(
+        recorded.contains(
+            r#""args":{"t":"List","v":[{"t":"Int","v":2},{"t":"Int","v":3},{"t":"Str","v":"done"}]}"#
+        ),
+        "trace: {recorded}"
+    );
+
+    let replay_output = strata_bin()
+        .args(["replay", trace.to_str().unwrap(), source.to_str().unwrap()])
+        .output()
+        .expect("replay binary");
+    assert!(
+        replay_output.status.success(),
+        "replay should succeed: {}",
+        String::from_utf8_lossy(&replay_output.stderr)
+    );
+}
+
+#[test]
+fn cli_run_repeat_reports_divergent_host_calls() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let input = dir.path().join("data.txt");
+    std::fs::write(&input, "same every time").expect("write input");
+    let steady = dir.path().join("steady.strata");
+    std::fs::write(
+        &steady,
+        format!(
+            r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+
+        fn main(fs: FsCap) -> String & {{Fs}} {{
+            read_file(&fs, "{}")
+        }}
+        "#,
+            input.to_str().unwrap()
+        ),
+    )
+    .expect("write source");
+    let flaky = dir.path().join("flaky.strata");
+    std::fs::write(
+        &flaky,
+        r#"
+        extern fn random_int(r: &RandCap) -> Int & {Rand};
+
+        fn main(r: RandCap) -> Int & {Rand} {
+            random_int(&r) * 1000 + random_int(&r)
+        }
+        "#,
+    )
+    .expect("write source");
+
+    let repeat = |source: &std::path::Path, out: &str| {
+        strata_bin()
+            .args([
+                "run",
+                source.to_str().unwrap(),
+                "--repeat",
+                "3",
+                "--trace-out",
+                dir.path().join(out).to_str().unwrap(),
+                "--no-audit-log",
+            ])
+            .output()
+            .expect("run binary")
+    };
+
+    let output = repeat(&steady, "steady");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stdout: {stdout}, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("all 3 runs made the same host calls"),
+        "got: {stdout}"
+    );
+    for run in 1..=3 {
+        assert!(dir.path().join(format!("steady/run-{run}.jsonl")).exists());
+    }
+
+    // Draws from an unseeded RandCap; the first matching in all three runs
+    // is a one-in-a-million chance
+    let output = repeat(&flaky, "flaky");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(70), "got: {stdout}");
+    assert!(
+        stdout.contains("seq 0: Rand::random_int returned")
+            && stdout.contains("hint: draw from a seed"),
+        "got: {stdout}"
+    );
+}
+
+#[test]
+fn cli_replay_mismatch() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source_v1 = dir.path().join("v1.strata");
+    let source_v2 = dir.path().join("v2.strata");
+    let input = dir.path().join("file.txt");
+    let trace = dir.path().join("trace.jsonl");
+    std::fs::write(&input, "original").expect("write input");
+
+    let src_v1 = format!(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+
+        fn main(fs: FsCap) -> String & {{Fs}} {{
+            read_file(&fs, "{}")
+        }}
+        "#,
+        input.to_str().unwrap()
+    );
+    std::fs::write(&source_v1, &src_v1).expect("write v1 source");
+
+    // Record trace from v1
+    let run_output = strata_bin()
+        .args([
+            "run",
+            source_v1.to_str().unwrap(),
+            "--trace-full",
+            trace.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run binary");
+    assert!(run_output.status.success());
+
+    // v2 reads a different path
+    let src_v2 = r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+
+        fn main(fs: FsCap) -> String & {Fs} {
             read_file(&fs, "/different/path.txt")
         }
     "#;
     std::fs::write(&source_v2, src_v2).expect("write v2 source");
 
-    // Replay v2 against v1's trace — should fail
+    // The trace records v1's source hash, so replay refuses v2 outright
+    let refused = strata_bin()
+        .args([
+            "replay",
+            trace.to_str().unwrap(),
+            source_v2.to_str().unwrap(),
+        ])
+        .output()
+        .expect("replay binary");
+    assert_eq!(refused.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&refused.stderr);
+    assert!(
+        stderr.contains("was recorded from a different program") && stderr.contains("--force"),
+        "stderr should name the changed program: {}",
+        stderr
+    );
+
+    // Replay v2 against v1's trace anyway — should fail
     let replay_output = strata_bin()
         .args([
             "replay",
             trace.to_str().unwrap(),
             source_v2.to_str().unwrap(),
+            "--force",
         ])
         .output()
         .expect("replay binary");
@@ -222,3 +1149,1084 @@ fn cli_replay_mismatch() {
         stderr
     );
 }
+
+#[test]
+fn cli_parse_json_reports_structured_parse_error() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("bad.strata");
+    std::fs::write(&file, "let a = 1").expect("write source");
+
+    let output = strata_bin()
+        .args(["parse", "--format", "json", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(!output.status.success(), "parse should fail");
+    let diag: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a JSON diagnostic");
+    assert_eq!(diag["code"], "PARSE-UNEXPECTED-TOKEN");
+    assert_eq!(diag["severity"], "error");
+    assert_eq!(diag["span"]["start"], 9);
+}
+
+#[test]
+fn cli_ast_prints_span_annotated_tree() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("tree.strata");
+    std::fs::write(&file, "fn add(a: Int, b: Int) -> Int {\n    a + b\n}\n").expect("write source");
+
+    let output = strata_bin()
+        .args(["ast", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(
+        output.status.success(),
+        "strata ast should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Module @ 1:1-4:1",
+            "  Fn add -> Int @ 1:1-3:2",
+            "    Param a: Int @ 1:8-1:14",
+            "    Param b: Int @ 1:16-1:22",
+            "    Block @ 1:31-3:2",
+            "      Binary + @ 2:5-2:10",
+            "        Var a @ 2:5-2:6",
+            "        Var b @ 2:9-2:10",
+        ]
+    );
+}
+
+#[test]
+fn cli_ast_depth_elides_deeper_nodes() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("tree.strata");
+    std::fs::write(&file, "let x = 1 + 2;\nfn main() -> Int { x }\n").expect("write source");
+
+    let output = strata_bin()
+        .args(["ast", "--depth", "1", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(output.status.success(), "strata ast --depth should succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "Module @ 1:1-3:1",
+            "  Let x @ 1:1-1:15 (1 child elided)",
+            "  Fn main -> Int @ 2:1-2:23 (1 child elided)",
+        ]
+    );
+}
+
+#[test]
+fn cli_parse_emit_typed_json_attaches_types_and_effects() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("typed.strata");
+    std::fs::write(
+        &file,
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+
+        fn main(fs: FsCap) -> String & {Fs} {
+            let s = read_file(&fs, "a");
+            s
+        }
+        "#,
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["parse", "--emit", "typed-json", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(
+        output.status.success(),
+        "strata parse --emit typed-json should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let out: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be JSON");
+    assert!(out["module"]["items"].is_array(), "AST is included");
+
+    let main = &out["items"][1];
+    assert_eq!(main["name"], "main");
+    assert_eq!(main["kind"], "fn");
+    assert_eq!(main["type"], "FsCap -> String & {Fs}");
+    assert_eq!(main["params"], serde_json::json!(["FsCap"]));
+    assert_eq!(main["ret"], "String");
+    assert_eq!(main["effects"]["concrete"], serde_json::json!(["Fs"]));
+    assert_eq!(main["effects"]["open"], false);
+    assert_eq!(main["locals"][0]["name"], "s");
+    assert_eq!(main["locals"][0]["type"], "String");
+}
+
+fn graph_output(src: &str, kind: &str) -> String {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("graph.strata");
+    std::fs::write(&file, src).expect("write source");
+
+    let output = strata_bin()
+        .args(["graph", "--kind", kind, file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(
+        output.status.success(),
+        "strata graph --kind {} should succeed, stderr: {}",
+        kind,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+const GRAPH_SRC: &str = r#"
+    extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+    struct Point { x: Int, y: Int }
+    enum Shape { Dot(Point), Empty }
+    linear struct Ctx { fs: FsCap, origin: Point }
+    fn helper(x: Int) -> Int { x + 1 }
+    fn load(fs: &FsCap) -> String & {Fs} { read_file(fs, "a") }
+    fn main(fs: FsCap) -> String & {Fs} {
+        let n = helper(helper(1));
+        load(&fs)
+    }
+"#;
+
+#[test]
+fn cli_graph_adt_draws_composition_edges() {
+    let dot = graph_output(GRAPH_SRC, "adt");
+    assert!(dot.starts_with("digraph adt {"), "got: {dot}");
+    assert!(
+        dot.contains(r#""Shape" -> "Point" [label="Dot"];"#),
+        "got: {dot}"
+    );
+    assert!(
+        dot.contains(r#""Ctx" [label="linear struct Ctx", shape=box];"#),
+        "got: {dot}"
+    );
+    assert!(
+        dot.contains(r#""Ctx" -> "FsCap" [label="fs"];"#),
+        "got: {dot}"
+    );
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+#[test]
+fn cli_graph_calls_lists_each_edge_once() {
+    let dot = graph_output(GRAPH_SRC, "calls");
+    assert!(dot.contains(r#""load" -> "read_file";"#), "got: {dot}");
+    assert!(dot.contains(r#""main" -> "load";"#), "got: {dot}");
+    assert_eq!(
+        dot.matches(r#""main" -> "helper";"#).count(),
+        1,
+        "got: {dot}"
+    );
+}
+
+#[test]
+fn cli_graph_effects_labels_rows_and_propagation() {
+    let dot = graph_output(GRAPH_SRC, "effects");
+    assert!(dot.contains(r#""read_file" -> "Fs";"#), "got: {dot}");
+    assert!(
+        dot.contains(r#""main" [label="main & {Fs}", shape=box];"#),
+        "got: {dot}"
+    );
+    assert!(
+        dot.contains(r#""main" -> "load" [label="Fs"];"#),
+        "got: {dot}"
+    );
+    // Pure callees do not propagate effects
+    assert!(!dot.contains(r#""main" -> "helper""#), "got: {dot}");
+}
+
+#[test]
+fn cli_parse_emit_html_highlights_source() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("hl.strata");
+    std::fs::write(&file, "fn f(fs: FsCap) -> Int & {Fs} { 1 < 2 }").expect("write source");
+
+    let output = strata_bin()
+        .args(["parse", "--emit", "html", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(
+        output.status.success(),
+        "strata parse --emit html should succeed"
+    );
+    let html = String::from_utf8_lossy(&output.stdout);
+    assert!(html.starts_with("<!DOCTYPE html>"), "got: {html}");
+    assert!(
+        html.contains(r#"<span class="tok-keyword">fn</span>"#),
+        "got: {html}"
+    );
+    assert!(
+        html.contains(r#"<span class="tok-capability">FsCap</span>"#),
+        "got: {html}"
+    );
+    assert!(
+        html.contains(r#"<span class="tok-effect">Fs</span>"#),
+        "got: {html}"
+    );
+    assert!(html.contains(") -&gt; "), "source text is escaped: {html}");
+    assert!(
+        html.contains("</span> &lt; <span"),
+        "source text is escaped: {html}"
+    );
+}
+
+#[test]
+fn cli_fix_applies_safe_fixes_in_place() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("fix.strata");
+    std::fs::write(
+        &file,
+        r#"extern fn now(t: &TimeCap) -> Int & {Time};
+fn f(fs: Fs, t: TimeCap) -> Int & {Fs} { now(&t) }
+"#,
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["fix", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "strata fix failed: {stderr}");
+    assert!(stderr.contains("Applied 2 fixes"), "got: {stderr}");
+    let fixed = std::fs::read_to_string(&file).expect("read source");
+    assert!(
+        fixed.contains("fn f(fs: FsCap, t: TimeCap) -> Int & {Fs, Time} {"),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn cli_fix_leaves_placeholder_fixes_as_suggestions() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("fix.strata");
+    let src = "enum E { A, B }\nfn f(e: E) -> Int { match e { E::A => 1 } }\n";
+    std::fs::write(&file, src).expect("write source");
+
+    let output = strata_bin()
+        .args(["fix", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(!output.status.success(), "unfixed error should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Applied 0 fixes"), "got: {stderr}");
+    assert!(
+        stderr.contains("suggestion: add an arm for `E::B` (`--placeholders` applies it)"),
+        "got: {stderr}"
+    );
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), src);
+}
+
+#[test]
+fn cli_fix_placeholders_adds_the_missing_arms() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("fix.strata");
+    let src = "enum S { Circle(Int), Square(Int), Empty }
+fn area(s: S) -> Int {
+    match s {
+        S::Circle(r) => r * r * 3,
+    }
+}
+";
+    std::fs::write(&file, src).expect("write source");
+
+    let output = strata_bin()
+        .args(["fix", "--placeholders", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "strata fix failed: {stderr}");
+    assert!(stderr.contains("Applied 1 fix"), "got: {stderr}");
+    let fixed = std::fs::read_to_string(&file).expect("read source");
+    assert!(
+        fixed.contains(
+            "        S::Circle(r) => r * r * 3,\n        S::Square(_) => todo(),\n        \
+             S::Empty => todo(),\n    }"
+        ),
+        "got: {fixed}"
+    );
+
+    // The fixed file checks
+    let output = strata_bin()
+        .args(["check", "--no-cache", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert!(
+        output.status.success(),
+        "fixed file should check: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn cli_fix_annotate_writes_inferred_signatures() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("annotate.strata");
+    let src =
+        "extern fn now(t: &TimeCap) -> Int & {Time};\nfn stamp(t: &TimeCap, n) { now(t) + n }\n";
+    std::fs::write(&file, src).expect("write source");
+
+    let output = strata_bin()
+        .args(["fix", "--annotate", "--dry-run", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(":2:24: annotate `n` as `Int`"),
+        "got: {stdout}"
+    );
+    assert!(
+        stdout.contains("annotate the effects of `stamp` as `{Time}`"),
+        "got: {stdout}"
+    );
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), src);
+
+    let output = strata_bin()
+        .args(["fix", "--annotate", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "strata fix failed: {stderr}");
+    assert!(stderr.contains("Added 3 annotations"), "got: {stderr}");
+    let fixed = std::fs::read_to_string(&file).expect("read source");
+    assert!(
+        fixed.contains("fn stamp(t: &TimeCap, n: Int) -> Int & {Time} {"),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn cli_parse_json_type_error_includes_fixes() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("fix.strata");
+    std::fs::write(&file, "fn f(fs: Fs) -> Int { 1 }").expect("write source");
+
+    let output = strata_bin()
+        .args(["parse", "--format", "json", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+
+    assert!(!output.status.success());
+    let diag: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("diagnostic is JSON");
+    assert_eq!(diag["code"], "TYPE-UNKNOWN-TYPE");
+    assert_eq!(diag["fixes"][0]["replacement"], "FsCap");
+    assert_eq!(diag["fixes"][0]["applicability"], "machine_applicable");
+}
+
+#[test]
+fn cli_run_script_prints_final_value() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("script.strata");
+    std::fs::write(
+        &file,
+        r#"
+        fn sq(x: Int) -> Int { x * x }
+        let mut total = 0;
+        let mut i = 1;
+        while i <= 3 { total = total + sq(i); i = i + 1; };
+        total
+        "#,
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["run", "--script", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert!(
+        output.status.success(),
+        "strata run --script should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "14\n");
+
+    // Without `--script`, a top-level expression is a parse error
+    let output = strata_bin()
+        .args(["run", file.to_str().unwrap()])
+        .output()
+        .expect("run binary");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn cli_run_passes_args_after_double_dash() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("args.strata");
+    std::fs::write(
+        &file,
+        r#"
+        fn main() -> String {
+            match arg(0) { Option::Some(a) => a, Option::None => "none" }
+        }
+        "#,
+    )
+    .expect("write source");
+
+    let output = strata_bin()
+        .args(["run", file.to_str().unwrap(), "--", "--hello", "world"])
+        .output()
+        .expect("run binary");
+    assert!(
+        output.status.success(),
+        "strata run should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("--hello"),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let (code, _) = run_exit_code("fn main() -> Int { arg_count() }");
+    assert_eq!(code, Some(0));
+}
+
+#[test]
+fn cli_run_reads_piped_stdin_and_replays_it() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("stdin.strata");
+    let trace = dir.path().join("trace.jsonl");
+    std::fs::write(
+        &source,
+        r#"
+        extern fn read_stdin(c: &ConsoleCap) -> String & {Console};
+
+        fn main(c: ConsoleCap) -> String & {Console} {
+            read_stdin(&c)
+        }
+        "#,
+    )
+    .expect("write source");
+
+    let mut child = strata_bin()
+        .args([
+            "run",
+            source.to_str().unwrap(),
+            "--trace-full",
+            trace.to_str().unwrap(),
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"piped data")
+        .expect("write stdin");
+    let output = child.wait_with_output().expect("wait for binary");
+    assert!(
+        output.status.success(),
+        "strata run should succeed, stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("piped data"));
+
+    // The input is in the trace, so replay needs no stdin
+    let trace_content = std::fs::read_to_string(&trace).expect("read trace file");
+    let entry: serde_json::Value =
+        serde_json::from_str(trace_content.lines().nth(1).unwrap()).expect("parse effect");
+    assert_eq!(entry["operation"], "read_stdin");
+    assert_eq!(entry["effect"], "Console");
+    assert_eq!(entry["output"]["value"]["v"], "piped data");
+
+    let replay = strata_bin()
+        .args(["replay", trace.to_str().unwrap(), source.to_str().unwrap()])
+        .stdin(Stdio::null())
+        .output()
+        .expect("replay binary");
+    assert!(
+        replay.status.success(),
+        "replay should succeed, stderr: {}",
+        String::from_utf8_lossy(&replay.stderr)
+    );
+}
+
+/// Run `src` with `strata run` and extra flags, returning the exit code and stderr
+fn run_with_flags(src: &str, flags: &[&str]) -> (Option<i32>, String) {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("policy.strata");
+    std::fs::write(&file, src).expect("write source");
+    let output = strata_bin()
+        .arg("run")
+        .arg(&file)
+        .args(flags)
+        .output()
+        .expect("run binary");
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn cli_run_policy_rejects_main_needing_denied_effect() {
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+
+        fn main(t: TimeCap) -> String & {Time} {
+            now(&t)
+        }
+    "#;
+
+    let (code, stderr) = run_with_flags(src, &["--deny", "Net,Time"]);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("Policy error"), "stderr: {}", stderr);
+    assert!(stderr.contains("TimeCap"), "stderr: {}", stderr);
+
+    let (code, stderr) = run_with_flags(src, &["--allow-only", "Fs"]);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+
+    let (code, stderr) = run_with_flags(src, &["--allow-only", "Fs,Time", "--deny", "Net"]);
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+
+    // Unknown effect names are usage errors
+    assert_eq!(run_with_flags(src, &["--deny", "Disk"]).0, Some(2));
+}
+
+#[test]
+fn cli_run_caps_file_grants_attenuated_capabilities() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let root = dir.path().join("root");
+    std::fs::create_dir(&root).expect("create root");
+    std::fs::write(root.join("data.txt"), "inside").expect("write data");
+    let caps = dir.path().join("caps.toml");
+    std::fs::write(
+        &caps,
+        format!(
+            "[fs]\nroot = \"{}\"\n\n[time]\nfixed = \"42.000\"\n",
+            root.to_str().unwrap()
+        ),
+    )
+    .expect("write caps");
+    let caps_flag = ["--caps", caps.to_str().unwrap()];
+
+    let read = |path: &str| {
+        format!(
+            r#"
+            extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+            extern fn now(t: &TimeCap) -> String & {{Time}};
+
+            fn main(fs: FsCap, t: TimeCap) -> (String, String) & {{Fs, Time}} {{
+                (read_file(&fs, "{}"), now(&t))
+            }}
+            "#,
+            path
+        )
+    };
+
+    let source = dir.path().join("caps.strata");
+    std::fs::write(&source, read("data.txt")).expect("write source");
+    let output = strata_bin()
+        .arg("run")
+        .arg(&source)
+        .args(caps_flag)
+        .output()
+        .expect("run binary");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains(r#"("inside", "42.000")"#),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // Paths may not escape the fs root
+    let (code, stderr) = run_with_flags(&read("../caps.toml"), &caps_flag);
+    assert_eq!(code, Some(70));
+    assert!(stderr.contains("outside the fs root"), "stderr: {}", stderr);
+
+    // A capability without a table is not granted
+    let src = r#"
+        extern fn random_int(r: &RandCap) -> Int & {Rand};
+        fn main(r: RandCap) -> Int & {Rand} { random_int(&r) }
+    "#;
+    let (code, stderr) = run_with_flags(src, &caps_flag);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("RandCap"), "stderr: {}", stderr);
+}
+
+#[test]
+fn cli_run_writes_audit_log_of_capability_calls() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let log = dir.path().join("audit.log");
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        extern fn pure_len(s: String) -> Int & {};
+
+        fn main(t: TimeCap) -> String & {Time} {
+            now(&t)
+        }
+    "#;
+
+    let (code, stderr) = run_with_flags(src, &["--audit-log", log.to_str().unwrap()]);
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    let content = std::fs::read_to_string(&log).expect("read audit log");
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3, "audit log: {}", content);
+    assert!(lines[0].contains("event=start program="), "{}", lines[0]);
+    assert!(
+        lines[1].contains("event=call fn=now cap=TimeCap access=borrow effect=Time status=ok"),
+        "{}",
+        lines[1]
+    );
+    assert!(
+        lines[2].contains("event=end status=success"),
+        "{}",
+        lines[2]
+    );
+
+    // A second run appends under its own run id
+    run_with_flags(src, &["--audit-log", log.to_str().unwrap()]);
+    let content = std::fs::read_to_string(&log).expect("read audit log");
+    assert_eq!(content.lines().count(), 6);
+
+    // The log is on by default, at $STRATA_AUDIT_LOG; opting out writes nothing
+    let source = dir.path().join("audited.strata");
+    std::fs::write(&source, src).expect("write source");
+    let default_log = dir.path().join("default.log");
+    for (flags, written) in [(&[][..], true), (&["--no-audit-log"][..], false)] {
+        let output = strata_bin()
+            .arg("run")
+            .arg(&source)
+            .args(flags)
+            .env("STRATA_AUDIT_LOG", &default_log)
+            .output()
+            .expect("run binary");
+        assert!(output.status.success());
+        assert_eq!(default_log.exists(), written);
+        let _ = std::fs::remove_file(&default_log);
+    }
+}
+
+#[test]
+fn cli_run_profile_reports_functions_and_folded_stacks() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let folded = dir.path().join("profile.folded");
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+
+        fn fib(n: Int) -> Int {
+            if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+        }
+
+        fn main(t: TimeCap) -> Int & {Time} {
+            let _ = now(&t);
+            fib(10) - 55
+        }
+    "#;
+
+    let (code, stderr) = run_with_flags(
+        src,
+        &["--profile", "--profile-folded", folded.to_str().unwrap()],
+    );
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert!(stderr.contains("self ms"), "stderr: {}", stderr);
+    // fib(10) makes 177 calls
+    let fib_row = stderr
+        .lines()
+        .find(|l| l.ends_with(" fib"))
+        .unwrap_or_else(|| panic!("no fib row: {}", stderr));
+    assert!(fib_row.contains(" 177 "), "{}", fib_row);
+    assert!(stderr.contains("now [host]"), "stderr: {}", stderr);
+
+    let content = std::fs::read_to_string(&folded).expect("read folded stacks");
+    assert!(content.lines().any(|l| l.starts_with("main;fib;fib ")));
+    assert!(content.lines().any(|l| l.starts_with("main;now ")));
+}
+
+#[test]
+fn cli_run_stats_counts_evaluator_work() {
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+
+        fn fib(n: Int) -> Int {
+            if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+        }
+
+        fn main(t: TimeCap) -> Int & {Time} {
+            let _ = now(&t);
+            let _ = now(&t);
+            fib(10) - 55
+        }
+    "#;
+
+    let (code, stderr) = run_with_flags(src, &["--stats"]);
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    let count = |what: &str| {
+        let row = stderr
+            .lines()
+            .find(|l| l.ends_with(what))
+            .unwrap_or_else(|| panic!("no '{}' row: {}", what, stderr));
+        row.split_whitespace().next().unwrap().to_string()
+    };
+    // main and fib(10)'s 177 calls
+    assert_eq!(count("function calls"), "178");
+    assert_eq!(count("host calls"), "2");
+    assert!(count("ms elapsed").contains('.'), "stderr: {}", stderr);
+}
+
+#[test]
+fn cli_run_print_limits_elide_large_results() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file = dir.path().join("big.strata");
+    let src = r#"
+        fn main() -> (Int, Int, Int, Int, (String, ((Int)))) {
+            (1, 2, 3, 4, ("abcdefgh", ((5))))
+        }
+    "#;
+    std::fs::write(&file, src).expect("write source");
+
+    let output = strata_bin()
+        .arg("run")
+        .arg(&file)
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(r#"main() = (1, 2, 3, 4, ("abcdefgh", 5))"#),
+        "stdout: {}",
+        stdout
+    );
+
+    let output = strata_bin()
+        .arg("run")
+        .arg(&file)
+        .args(["--max-elems", "2", "--max-string", "3"])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("main() = (1, 2, … 3 more)"),
+        "stdout: {}",
+        stdout
+    );
+
+    let output = strata_bin()
+        .arg("run")
+        .arg(&file)
+        .args(["--max-depth", "1", "--max-string", "3"])
+        .output()
+        .expect("run binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(r#"main() = (1, 2, 3, 4, (…))"#),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn cli_debug_stops_at_breakpoints_and_prints_locals() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("debug.strata");
+    std::fs::write(
+        &source,
+        "fn fib(n: Int) -> Int {\n    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }\n}\n\nfn main() -> Int {\n    let x = fib(2);\n    x\n}\n",
+    )
+    .expect("write source");
+
+    let mut child = strata_bin()
+        .args(["debug", source.to_str().unwrap(), "--break", "fib"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn binary");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"locals\ndelete 1\nbreak 7\ncontinue\nprint x\ncontinue\n")
+        .expect("write stdin");
+    let output = child.wait_with_output().expect("wait for binary");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.starts_with("Breakpoint 1, fib at "),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("n = 2\n"), "stdout: {}", stdout);
+    assert!(
+        stdout.contains("Breakpoint 2, main at "),
+        "stdout: {}",
+        stdout
+    );
+    assert!(stdout.contains("x = 1\n"), "stdout: {}", stdout);
+    assert!(
+        stdout.ends_with("Program finished: 1\n"),
+        "stdout: {}",
+        stdout
+    );
+
+    // A bad breakpoint is a usage error
+    let output = strata_bin()
+        .args(["debug", source.to_str().unwrap(), "--break", "99"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("run binary");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_check_caches_clean_results_by_semantic_hash() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("checked.strata");
+    let check = |flags: &[&str]| {
+        let output = strata_bin()
+            .arg("check")
+            .arg(&source)
+            .args(flags)
+            .output()
+            .expect("run binary");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).contains("(cached)")
+    };
+
+    std::fs::write(&source, "fn main() -> Int { 0 }\n").expect("write source");
+    assert!(!check(&[]));
+    assert!(check(&[]));
+    assert!(dir.path().join(".strata-cache/version").exists());
+
+    // Layout and comments don't change the semantic hash; code does
+    std::fs::write(&source, "// entry\nfn main() -> Int {\n    0\n}\n").expect("write source");
+    assert!(check(&[]));
+    assert!(!check(&["--no-cache"]));
+    std::fs::write(&source, "fn main() -> Int { 1 }\n").expect("write source");
+    assert!(!check(&[]));
+
+    // Errors are never cached
+    std::fs::write(&source, "fn main() -> Int { true }\n").expect("write source");
+    for _ in 0..2 {
+        let output = strata_bin()
+            .arg("check")
+            .arg(&source)
+            .output()
+            .expect("run binary");
+        assert_eq!(output.status.code(), Some(1));
+    }
+}
+
+#[test]
+fn cli_check_profile_sets_the_checker_limits() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("wide.strata");
+    let elems: Vec<String> = (0..17).map(|i| i.to_string()).collect();
+    let program = format!("fn main() -> Int {{ let t = ({}); 0 }}\n", elems.join(", "));
+    std::fs::write(&source, program).expect("write source");
+    let check = |profile: &str| {
+        strata_bin()
+            .arg("check")
+            .arg(&source)
+            .args(["--profile", profile])
+            .output()
+            .expect("run binary")
+    };
+
+    // Cached as clean under the default profile, yet still checked (and
+    // rejected) under the strict one
+    assert!(check("default").status.success());
+    let output = check("strict");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("wide.strata:1:28: Type error:"),
+        "{}",
+        stderr
+    );
+    assert!(check("permissive").status.success());
+
+    let output = check("lenient");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("unknown profile 'lenient' (expected strict, default, permissive)"),
+        "{}",
+        stderr
+    );
+
+    // `run` spells it --check-profile, --profile being the timing profiler
+    let output = strata_bin()
+        .arg("run")
+        .arg(&source)
+        .args(["--check-profile", "strict", "--no-audit-log"])
+        .output()
+        .expect("run binary");
+    assert_eq!(output.status.code(), Some(1));
+
+    // and each command's help says which meaning its --profile has
+    let help = |command: &str| {
+        let output = strata_bin()
+            .args([command, "--help"])
+            .output()
+            .expect("run binary");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let check_help = help("check");
+    assert!(
+        check_help.contains("`run` spells this `--check-profile`"),
+        "{}",
+        check_help
+    );
+    let run_help = help("run");
+    assert!(
+        run_help.contains("are `--check-profile` here"),
+        "{}",
+        run_help
+    );
+}
+
+#[test]
+fn cli_check_verbose_solver_prints_each_item_s_constraints() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let source = dir.path().join("solve.strata");
+    std::fs::write(
+        &source,
+        "fn double(x: Int) -> Int { x * 2 }\nfn main() -> Int { double(true) }\n",
+    )
+    .expect("write source");
+    let output = strata_bin()
+        .args(["check", "--verbose-solver"])
+        .arg(&source)
+        .output()
+        .expect("run binary");
+    // Printed for the item that fails too
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert!(
+        lines[0].starts_with("solver: double: ") && lines[0].ends_with(" steps"),
+        "{stderr}"
+    );
+    assert!(lines[1].starts_with("solver: main: "), "{stderr}");
+    assert!(lines[2].contains("Type error:"), "{stderr}");
+}
+
+#[test]
+fn kernel_install_writes_kernelspec() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_strata-kernel"))
+        .args(["install", "--dir"])
+        .arg(dir.path())
+        .output()
+        .expect("run binary");
+    assert!(output.status.success());
+
+    let spec = std::fs::read_to_string(dir.path().join("strata/kernel.json")).unwrap();
+    let spec: serde_json::Value = serde_json::from_str(&spec).unwrap();
+    assert_eq!(spec["language"], "strata");
+    assert_eq!(spec["argv"][1], "run");
+    assert_eq!(spec["argv"][2], "{connection_file}");
+}
+
+#[test]
+fn kernel_answers_requests_over_zeromq() {
+    use strata_cli::jupyter::Message;
+
+    let ports: Vec<u16> = (0..5)
+        .map(|_| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        })
+        .collect();
+    let dir = tempfile::tempdir().unwrap();
+    let connection = dir.path().join("kernel.json");
+    let info = serde_json::json!({
+        "ip": "127.0.0.1",
+        "transport": "tcp",
+        "shell_port": ports[0],
+        "iopub_port": ports[1],
+        "stdin_port": ports[2],
+        "control_port": ports[3],
+        "hb_port": ports[4],
+        "key": "secret",
+        "signature_scheme": "hmac-sha256",
+    });
+    std::fs::write(&connection, info.to_string()).unwrap();
+    let mut kernel = Command::new(env!("CARGO_BIN_EXE_strata-kernel"))
+        .arg("run")
+        .arg(&connection)
+        .spawn()
+        .expect("start kernel");
+
+    // Sockets connect whenever the kernel binds; a reply that never comes
+    // fails the test rather than hanging it
+    let context = zmq::Context::new();
+    let connect = |kind: zmq::SocketType, port: u16| {
+        let socket = context.socket(kind).unwrap();
+        socket.set_rcvtimeo(10_000).unwrap();
+        socket
+            .connect(&format!("tcp://127.0.0.1:{}", port))
+            .unwrap();
+        socket
+    };
+
+    // Heartbeats come back unchanged
+    let hb = connect(zmq::REQ, ports[4]);
+    hb.send("ping", 0).unwrap();
+    assert_eq!(hb.recv_bytes(0).unwrap(), b"ping");
+
+    let shell = connect(zmq::DEALER, ports[0]);
+    let request = |msg_type: &str, content: serde_json::Value| Message {
+        identities: Vec::new(),
+        header: serde_json::json!({"msg_id": msg_type, "msg_type": msg_type, "session": "t"}),
+        parent_header: serde_json::json!({}),
+        metadata: serde_json::json!({}),
+        content,
+    };
+    let roundtrip = |msg: Message| {
+        shell.send_multipart(msg.to_frames(b"secret"), 0).unwrap();
+        Message::from_frames(&shell.recv_multipart(0).unwrap(), b"secret").unwrap()
+    };
+    let reply = roundtrip(request(
+        "execute_request",
+        serde_json::json!({"code": "6 * 7"}),
+    ));
+    assert_eq!(reply.msg_type(), "execute_reply");
+    assert_eq!(reply.content["status"], "ok");
+    assert_eq!(reply.parent_header["msg_id"], "execute_request");
+
+    let reply = roundtrip(request(
+        "shutdown_request",
+        serde_json::json!({"restart": false}),
+    ));
+    assert_eq!(reply.msg_type(), "shutdown_reply");
+    assert!(kernel.wait().unwrap().success());
+}
@@ -5,7 +5,8 @@
 //! injection at the main() entry point and structured trace emission.
 
 use strata_cli::eval::{
-    run_module, run_module_replay, run_module_traced, run_module_traced_full, Value,
+    run_module, run_module_replay, run_module_traced, run_module_traced_full, run_module_with,
+    RunOptions, RuntimeErrorKind, Value,
 };
 
 use std::sync::{Arc, Mutex};
@@ -208,6 +209,43 @@ fn host_write_then_read() {
     }
 }
 
+#[test]
+fn borrowed_cap_param_in_user_fn_not_consumed() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file_path = dir.path().join("borrowed.txt");
+    let path_str = file_path.to_str().unwrap();
+
+    // `save` and `load` borrow fs; the borrow is passed on and used twice,
+    // and main's fs is still usable after each call.
+    let src = format!(
+        r#"
+        extern fn write_file(fs: &FsCap, path: String, content: String) -> () & {{Fs}};
+        extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+
+        fn save(fs: &FsCap, content: String) -> () & {{Fs}} {{
+            write_file(fs, "{path}", "scratch");
+            write_file(fs, "{path}", content)
+        }}
+
+        fn load(fs: &FsCap) -> String & {{Fs}} {{
+            read_file(fs, "{path}")
+        }}
+
+        fn main(fs: FsCap) -> String & {{Fs}} {{
+            save(&fs, "borrowed data");
+            load(&fs)
+        }}
+        "#,
+        path = path_str
+    );
+
+    let result = run_ok(&src);
+    match result {
+        Value::Str(s) => assert_eq!(s, "borrowed data"),
+        other => panic!("expected Str, got: {}", other),
+    }
+}
+
 // =========================================================================
 // Phase 3: Trace emission tests
 // =========================================================================
@@ -355,6 +393,56 @@ fn trace_records_write_file() {
     assert_eq!(entry["output"]["value"]["t"], "Unit");
 }
 
+#[test]
+fn trace_records_defaulted_args() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file_path = dir.path().join("trace_default.txt");
+    let path_str = file_path.to_str().unwrap();
+
+    let src = format!(
+        r#"
+        const EMPTY: String = "(empty)";
+        extern fn write_file(fs: &FsCap, path: String, content: String = EMPTY) -> () & {{Fs}};
+
+        fn main(fs: FsCap) -> () & {{Fs}} {{
+            write_file(&fs, "{}")
+        }}
+        "#,
+        path_str
+    );
+
+    let (_result, entries) = run_traced(&src);
+    assert_eq!(entries.len(), 1);
+    // The host got the default, and the trace records it like any argument
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "(empty)");
+    assert_eq!(entries[0]["inputs"]["path"]["v"], path_str);
+    assert_eq!(entries[0]["inputs"]["content"]["v"], "(empty)");
+}
+
+#[test]
+fn trace_records_named_args_in_param_order() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file_path = dir.path().join("trace_named.txt");
+    let path_str = file_path.to_str().unwrap();
+
+    let src = format!(
+        r#"
+        extern fn write_file(fs: &FsCap, path: String, content: String) -> () & {{Fs}};
+
+        fn main(fs: FsCap) -> () & {{Fs}} {{
+            write_file(&fs, content: "named", path: "{}")
+        }}
+        "#,
+        path_str
+    );
+
+    let (_result, entries) = run_traced(&src);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "named");
+    assert_eq!(entries[0]["inputs"]["path"]["v"], path_str);
+    assert_eq!(entries[0]["inputs"]["content"]["v"], "named");
+}
+
 #[test]
 fn trace_hashes_large_output() {
     let dir = tempfile::tempdir().expect("create tempdir");
@@ -967,6 +1055,338 @@ fn replay_rejects_unknown_version() {
     );
 }
 
+/// Program arguments are recorded in the trace header, and replay runs the
+/// program with the recorded arguments.
+#[test]
+fn trace_header_records_args_for_replay() {
+    let src = r#"
+        fn main() -> String {
+            match arg(1) { Option::Some(a) => a, Option::None => "none" }
+        }
+    "#;
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    let mut tc = strata_types::TypeChecker::new();
+    tc.check_module(&module).expect("type check failed");
+
+    let args = vec!["first".to_string(), "second".to_string()];
+    let buf = SharedBuf::new();
+    let opts = RunOptions {
+        args: args.clone(),
+        trace: Some(Box::new(buf.clone())),
+        full_values: true,
+        ..RunOptions::default()
+    };
+    let live = run_module_with(&module, opts).expect("live run failed");
+    assert!(
+        matches!(&live, Value::Str(s) if s == "second"),
+        "got {}",
+        live
+    );
+
+    let trace = buf.contents();
+    let header: serde_json::Value =
+        serde_json::from_str(trace.lines().next().expect("header")).expect("header JSON");
+    assert_eq!(header["args"], serde_json::json!(["first", "second"]));
+
+    let replayed = run_module_replay(&module, &trace).expect("replay failed");
+    assert!(
+        matches!(&replayed, Value::Str(s) if s == "second"),
+        "got {}",
+        replayed
+    );
+
+    // Without arguments, `arg(1)` is `None`
+    let bare = run_module(&module).expect("run failed");
+    assert!(
+        matches!(&bare, Value::Str(s) if s == "none"),
+        "got {}",
+        bare
+    );
+}
+
+/// The trace header records the program's source hash, the compiler
+/// version, and the run's profile and flags; traces from before they were
+/// recorded load without them.
+#[test]
+fn trace_header_records_what_it_was_recorded_from() {
+    use strata_cli::host::{source_hash, TraceMeta, TraceReplayer};
+
+    let src = "fn main() -> Int { 7 }";
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    let buf = SharedBuf::new();
+    let opts = RunOptions {
+        trace: Some(Box::new(buf.clone())),
+        full_values: true,
+        trace_meta: TraceMeta {
+            source_hash: Some(source_hash(src)),
+            profile: Some("strict".to_string()),
+            flags: vec!["run".to_string(), "main.strata".to_string()],
+        },
+        ..RunOptions::default()
+    };
+    run_module_with(&module, opts).expect("run failed");
+
+    let replayer = TraceReplayer::from_jsonl(&buf.contents()).expect("trace loads");
+    let header = replayer.header().expect("header");
+    assert_eq!(header.source_hash, Some(source_hash(src)));
+    assert!(header
+        .source_hash
+        .as_deref()
+        .unwrap()
+        .starts_with("sha256:"));
+    assert_eq!(
+        header.compiler_version.as_deref(),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(header.profile.as_deref(), Some("strict"));
+    assert_eq!(header.flags, ["run", "main.strata"]);
+
+    let legacy = r#"{"record":"header","schema_version":"0.1","timestamp":"2026-01-01T00:00:00.000Z","full_values":true}"#;
+    let replayer = TraceReplayer::from_jsonl(legacy).expect("legacy trace loads");
+    let header = replayer.header().expect("header");
+    assert_eq!(header.source_hash, None);
+    assert!(header.flags.is_empty());
+}
+
+/// `#[redact]` keeps an extern fn's values out of the trace. A hashed
+/// input still replays, matched by its hash; a redacted output can't.
+#[test]
+fn redacted_values_stay_out_of_the_trace() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let secret = dir.path().join("api-key.txt");
+    std::fs::write(&secret, "hunter2").expect("write secret");
+    let program = |attr: &str, path: &str| {
+        let src = format!(
+            r#"
+            {attr}
+            extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+
+            fn main(fs: FsCap) -> Int & {{Fs}} {{
+                read_file(&fs, "{path}");
+                1
+            }}
+            "#
+        );
+        let module = strata_parse::parse_str("<test>", &src).expect("parse failed");
+        let mut tc = strata_types::TypeChecker::new();
+        tc.check_module(&module).expect("type check failed");
+        module
+    };
+    let record = |module: &strata_ast::ast::Module| {
+        let buf = SharedBuf::new();
+        run_module_traced_full(module, Box::new(buf.clone())).expect("run failed");
+        buf.contents()
+    };
+    let path = secret.to_str().unwrap();
+
+    let module = program("#[redact(path)]", path);
+    let trace = record(&module);
+    assert!(!trace.contains("api-key"), "path leaked: {trace}");
+    assert!(trace.contains(r#""path":{"t":"Redacted","v":"sha256:"#));
+    assert!(trace.contains("hunter2"), "only the path is redacted");
+    run_module_replay(&module, &trace).expect("hashed input replays");
+
+    // Another path doesn't match the hash, and isn't shown either
+    let moved = program("#[redact(path)]", "/elsewhere/other-key.txt");
+    let err = run_module_replay(&moved, &trace).unwrap_err().to_string();
+    assert!(err.contains("input mismatch"), "got: {err}");
+    assert!(!err.contains("other-key"), "got: {err}");
+
+    let module = program("#[redact(output, mode = \"mask\")]", path);
+    let trace = record(&module);
+    assert!(!trace.contains("hunter2"), "output leaked: {trace}");
+    assert!(trace.contains(r#""value":{"t":"Redacted","v":null}"#));
+    let err = run_module_replay(&module, &trace).unwrap_err().to_string();
+    assert!(
+        err.contains("output for 'read_file' at seq 0 was redacted"),
+        "got: {err}"
+    );
+}
+
+/// The host registry refuses calls gated by a denied effect, even when
+/// nothing checked the program against the policy up front. The refused
+/// call is still traced.
+#[test]
+fn policy_denies_host_call_at_dispatch() {
+    use strata_cli::host::EffectPolicy;
+    use strata_types::Effect;
+
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+
+        fn main(t: TimeCap) -> String & {Time} {
+            now(&t)
+        }
+    "#;
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    let buf = SharedBuf::new();
+    let opts = RunOptions {
+        policy: EffectPolicy::allow_only(&[Effect::Fs]),
+        trace: Some(Box::new(buf.clone())),
+        ..RunOptions::default()
+    };
+    let err = run_module_with(&module, opts)
+        .expect_err("denied effect should fail")
+        .to_string();
+    assert!(err.contains("denied by effect policy"), "got: {}", err);
+
+    let effect = buf
+        .contents()
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).expect("invalid JSONL line"))
+        .find(|v| v["record"] == "effect")
+        .expect("denied call should be traced");
+    assert_eq!(effect["operation"], "now");
+    assert_eq!(effect["output"]["status"], "error");
+}
+
+/// Records observer events as strings; refuses host calls named `deny`.
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+    deny: Option<&'static str>,
+}
+
+impl strata_cli::eval::RuntimeObserver for Recorder {
+    fn on_call(&self, name: &str, args: &[Value]) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("call {}/{}", name, args.len()));
+    }
+
+    fn on_host_call(&self, name: &str, _args: &[Value]) -> Result<(), String> {
+        self.events.lock().unwrap().push(format!("host {}", name));
+        if self.deny == Some(name) {
+            return Err("not in business hours".to_string());
+        }
+        Ok(())
+    }
+
+    fn on_error(&self, error: &strata_cli::eval::RuntimeError) {
+        self.events.lock().unwrap().push(format!("error {}", error));
+    }
+}
+
+#[test]
+fn observer_sees_calls_and_can_refuse_host_calls() {
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+
+        fn stamp(t: &TimeCap, label: String) -> String & {Time} {
+            now(t)
+        }
+
+        fn main(t: TimeCap) -> String & {Time} {
+            stamp(&t, "start")
+        }
+    "#;
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+
+    let observer = Arc::new(Recorder::default());
+    let opts = RunOptions {
+        observer: Some(observer.clone()),
+        ..RunOptions::default()
+    };
+    run_module_with(&module, opts).expect("run failed");
+    assert_eq!(
+        *observer.events.lock().unwrap(),
+        ["call main/1", "call stamp/2", "host now"]
+    );
+
+    let observer = Arc::new(Recorder {
+        deny: Some("now"),
+        ..Recorder::default()
+    });
+    let opts = RunOptions {
+        observer: Some(observer.clone()),
+        ..RunOptions::default()
+    };
+    let err = run_module_with(&module, opts).expect_err("refused call should fail");
+    assert!(
+        err.to_string().contains("not in business hours"),
+        "got: {}",
+        err
+    );
+    assert!(
+        matches!(&err.kind, RuntimeErrorKind::Host { function, .. } if function == "now"),
+        "got: {:?}",
+        err.kind
+    );
+    assert_eq!(err.stack, ["stamp", "main"]);
+    let events = observer.events.lock().unwrap();
+    assert!(
+        events.last().unwrap().starts_with("error ") && events.last().unwrap().contains("now"),
+        "events: {:?}",
+        events
+    );
+}
+
+/// A replayer reading from a `BufRead` takes the trace in as replay goes,
+/// reaching the footer at the end, and an index reads any one entry
+/// without the ones before it.
+#[test]
+fn trace_replays_as_it_is_read() {
+    use std::io::Cursor;
+    use strata_cli::eval::run_module_replay_from;
+    use strata_cli::host::{TraceIndex, TraceReplayer};
+
+    let src = r#"
+        extern fn random_int(r: &RandCap) -> Int & {Rand};
+
+        fn main(r: RandCap) -> Int & {Rand} {
+            random_int(&r) * 1000000 + random_int(&r) * 1000 + random_int(&r)
+        }
+    "#;
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    let buf = SharedBuf::new();
+    let opts = RunOptions {
+        trace: Some(Box::new(buf.clone())),
+        full_values: true,
+        ..RunOptions::default()
+    };
+    let recorded = run_module_with(&module, opts).expect("run failed");
+    let trace = buf.contents();
+
+    let replayer = TraceReplayer::from_reader(Cursor::new(trace.clone())).expect("trace loads");
+    assert!(replayer.header().is_some());
+    assert!(!replayer.is_trace_complete(), "footer isn't read yet");
+    let replayer = Arc::new(Mutex::new(replayer));
+    let replayed = run_module_replay_from(&module, replayer.clone()).expect("replay failed");
+    assert_eq!(replayed.to_string(), recorded.to_string());
+    let replayer = replayer.lock().unwrap();
+    assert_eq!(replayer.replayed(), 3);
+    assert!(replayer.is_trace_complete());
+
+    let mut reader = Cursor::new(trace.clone());
+    let index = TraceIndex::build(&mut reader).expect("trace indexes");
+    assert_eq!(index.len(), 3);
+    assert!(index.footer().is_some());
+    let effects: Vec<serde_json::Value> = trace
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .filter(|r: &serde_json::Value| r["record"] == "effect")
+        .collect();
+    let last = index.entry(&mut reader, 2).expect("read").expect("entry 2");
+    assert_eq!(
+        serde_json::to_value(&last).unwrap()["output"],
+        effects[2]["output"]
+    );
+    assert!(index.entry(&mut reader, 3).expect("read").is_none());
+
+    // Entries the program never reaches are counted, not held
+    let mut lines: Vec<&str> = trace.lines().collect();
+    let extra = lines[1];
+    lines.insert(1, extra);
+    let longer = lines.join("\n");
+    let replayer = TraceReplayer::from_reader(Cursor::new(longer)).expect("trace loads");
+    let err = run_module_replay_from(&module, Arc::new(Mutex::new(replayer))).unwrap_err();
+    assert!(
+        err.to_string().contains("1 unreplayed entries"),
+        "got: {err}"
+    );
+}
+
 #[test]
 fn replay_detects_missing_footer() {
     use strata_cli::host::TraceReplayer;
@@ -1007,3 +1427,139 @@ fn trace_tagged_str_not_confused_with_int() {
     assert_eq!(int_back, TraceValue::Int(42));
     assert_ne!(str_back, int_back);
 }
+
+#[test]
+fn par_traces_branches_in_order_and_replays() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let slow = dir.path().join("slow.txt");
+    let fast = dir.path().join("fast.txt");
+    std::fs::write(&slow, "x".repeat(1 << 20)).expect("write");
+    std::fs::write(&fast, "fast").expect("write");
+
+    // The first branch reads twice, the second once; however the threads
+    // interleave, the trace lists the first branch's calls first
+    let src = format!(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+
+        fn both(fs: &FsCap) -> String & {{Fs}} {{
+            let _a = read_file(fs, "{slow}");
+            read_file(fs, "{fast}")
+        }}
+        fn one(fs: &FsCap) -> String & {{Fs}} {{ read_file(fs, "{fast}") }}
+
+        fn main(fs: FsCap) -> String & {{Fs}} {{
+            let (a, b) = par(both, &fs, one, &fs);
+            read_file(&fs, "{fast}")
+        }}
+        "#,
+        slow = slow.display(),
+        fast = fast.display(),
+    );
+
+    for _ in 0..5 {
+        let (_result, entries) = run_traced(&src);
+        let paths: Vec<_> = entries
+            .iter()
+            .map(|e| e["inputs"]["path"]["v"].as_str().unwrap())
+            .collect();
+        let seqs: Vec<_> = entries.iter().map(|e| e["seq"].as_u64().unwrap()).collect();
+        assert_eq!(seqs, [0, 1, 2, 3]);
+        assert!(paths[0].ends_with("slow.txt"), "{paths:?}");
+        assert!(
+            paths[1..].iter().all(|p| p.ends_with("fast.txt")),
+            "{paths:?}"
+        );
+    }
+
+    let (live, replay) = trace_and_replay(&src);
+    assert_eq!(live.to_string(), replay.to_string());
+}
+
+#[test]
+fn spawned_tasks_trace_their_ids_and_replay_in_any_order() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let data = dir.path().join("data.txt");
+    std::fs::write(&data, "data").expect("write");
+
+    let src = format!(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {{Fs}};
+        extern fn now(t: &TimeCap) -> String & {{Time}};
+
+        fn main(fs: FsCap, t: TimeCap) -> (String, String) & {{Fs, Time}} {{
+            let reads = spawn {{
+                let _first = read_file(&fs, "{data}");
+                read_file(&fs, "{data}")
+            }};
+            let stamp = spawn {{ now(&t) }};
+            (join(reads), join(stamp))
+        }}
+        "#,
+        data = data.display(),
+    );
+
+    let (_result, entries) = run_traced(&src);
+    let tasks: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            (
+                e["task"].as_str().unwrap(),
+                e["operation"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(
+        tasks.iter().filter(|t| **t == ("1", "read_file")).count(),
+        2,
+        "{tasks:?}"
+    );
+    assert!(tasks.contains(&("2", "now")), "{tasks:?}");
+
+    // Each task replays its own calls in order, wherever the scheduler put
+    // the other task's between them
+    let module = strata_parse::parse_str("<test>", &src).expect("parse failed");
+    let buf = SharedBuf::new();
+    let live = run_module_traced_full(&module, Box::new(buf.clone())).expect("live run failed");
+    let trace = buf.contents();
+    let mut lines: Vec<&str> = trace.lines().collect();
+    let last = lines.len() - 1;
+    lines[1..last].reverse();
+    let reordered = lines.join("\n");
+    for trace in [trace.as_str(), reordered.as_str()] {
+        let replay = run_module_replay(&module, trace).expect("replay failed");
+        assert_eq!(live.to_string(), replay.to_string());
+    }
+}
+
+#[test]
+fn transparent_structs_cross_the_host_boundary_unwrapped() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let file_path = dir.path().join("transparent.txt");
+    std::fs::write(&file_path, "wrapped").expect("write test file");
+    let path_str = file_path.to_str().unwrap();
+
+    let src = format!(
+        r#"
+        transparent struct Path(String);
+        transparent struct Contents(String);
+        extern fn read_file(fs: &FsCap, path: Path) -> Contents & {{Fs}};
+
+        fn main(fs: FsCap) -> Contents & {{Fs}} {{
+            read_file(&fs, Path("{}"))
+        }}
+        "#,
+        path_str
+    );
+
+    // The host reads and returns plain strings; the program sees newtypes
+    let (result, entries) = run_traced(&src);
+    assert_eq!(result.to_string(), "Contents(\"wrapped\")");
+    assert_eq!(entries[0]["inputs"]["path"]["t"], "Str");
+    assert_eq!(entries[0]["inputs"]["path"]["v"], path_str);
+    assert_eq!(entries[0]["output"]["value"]["v"], "wrapped");
+
+    let (live, replay) = trace_and_replay(&src);
+    assert_eq!(live.to_string(), replay.to_string());
+}
@@ -0,0 +1,104 @@
+//! Integration tests for the notebook session behind `strata-kernel`.
+
+use strata_cli::eval::Value;
+use strata_cli::notebook::{completeness, CellError, Completeness, Session};
+
+/// The cell's value, displayed
+fn run(session: &mut Session, code: &str) -> Option<String> {
+    let value = session.execute(code).expect("cell failed");
+    value.as_ref().map(Value::to_string)
+}
+
+#[test]
+fn cells_share_functions_and_globals() {
+    let mut s = Session::new();
+    assert_eq!(run(&mut s, "fn double(x: Int) -> Int { x * 2 }"), None);
+    assert_eq!(run(&mut s, "let base = double(20);"), None);
+    assert_eq!(run(&mut s, "base + 2"), Some("42".to_string()));
+    assert_eq!(s.execution_count(), 3);
+}
+
+#[test]
+fn mutable_locals_stay_in_their_cell() {
+    let mut s = Session::new();
+    let code = "let mut total = 0;\ntotal = total + 5;\nlet doubled = total * 2;\ndoubled";
+    assert_eq!(run(&mut s, code), Some("10".to_string()));
+    let err = s.execute("doubled").unwrap_err();
+    assert_eq!(err.name(), "TypeError");
+}
+
+#[test]
+fn failed_cell_leaves_session_unchanged() {
+    let mut s = Session::new();
+    run(&mut s, "let x = 1;");
+
+    let err = s.execute("let y = x + true;").unwrap_err();
+    assert!(matches!(err, CellError::Type(_)), "got {:?}", err);
+    assert!(err.to_string().starts_with("cell[2]:1:"), "got {}", err);
+
+    let err = s.execute("let z = 1 / 0;").unwrap_err();
+    assert!(matches!(err, CellError::Runtime(_)), "got {:?}", err);
+    assert!(s.execute("z").is_err());
+
+    assert!(matches!(s.execute("let = 1;"), Err(CellError::Parse(_))));
+    assert_eq!(run(&mut s, "x"), Some("1".to_string()));
+    assert_eq!(s.execution_count(), 6);
+}
+
+#[test]
+fn types_and_imports_carry_across_cells() {
+    let mut s = Session::new();
+    run(&mut s, "enum Level { Low, High }");
+    run(&mut s, "use Level::*;");
+    let v = run(&mut s, "let l = High;\nmatch l { Low => 0, High => 1 }");
+    assert_eq!(v, Some("1".to_string()));
+}
+
+#[test]
+fn derived_functions_carry_across_cells() {
+    let mut s = Session::new();
+    run(&mut s, "#[derive(Show)]\nstruct P { x: Int }");
+    let v = run(&mut s, "p_to_string(P { x: 2 }) == show(P { x: 2 })");
+    assert_eq!(v, Some("true".to_string()));
+}
+
+#[test]
+fn inspect_shows_types_and_effects() {
+    let mut s = Session::new();
+    run(
+        &mut s,
+        "extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};\n\
+         fn load(fs: &FsCap) -> String { read_file(fs, \"a.txt\") }\n\
+         struct Point { x: Int, y: Int }\n\
+         let origin = 0;",
+    );
+    assert_eq!(
+        s.inspect("load").as_deref(),
+        Some("fn load : &FsCap -> String & {Fs}")
+    );
+    assert_eq!(s.inspect("origin").as_deref(), Some("origin : Int"));
+    assert_eq!(
+        s.inspect("Point").as_deref(),
+        Some("struct Point { x: Int, y: Int }")
+    );
+    assert_eq!(s.inspect("missing"), None);
+}
+
+#[test]
+fn completions_cover_names_and_keywords() {
+    let mut s = Session::new();
+    run(
+        &mut s,
+        "fn lerp(a: Int, b: Int) -> Int { a + b }\nlet level = 3;\n1",
+    );
+    assert_eq!(s.completions("le"), vec!["lerp", "let", "level"]);
+    assert!(s.completions("").iter().all(|name| !name.starts_with('$')));
+}
+
+#[test]
+fn completeness_of_partial_cells() {
+    assert_eq!(completeness("let x = 1;"), Completeness::Complete);
+    assert_eq!(completeness("fn f() {\n  1"), Completeness::Incomplete);
+    assert_eq!(completeness("\"open"), Completeness::Incomplete);
+    assert_eq!(completeness("let = 1; 2"), Completeness::Invalid);
+}
@@ -0,0 +1,261 @@
+//! Soundness harness: a program the type checker accepts must not trip the
+//! evaluator's defense-in-depth checks.
+//!
+//! The evaluator still guards against failed irrefutable patterns, reused
+//! capabilities (`CAP-MOVE-RUNTIME`), and matches with no arm taken, though
+//! the checker should rule each out. Every program in the corpus (the
+//! `examples/` directory plus the programs below) that checks is run, and
+//! hitting one of those guards ([`eval::is_checker_escape`]) fails the test.
+//! Other runtime errors, such as an effect refused by policy, are fine.
+//!
+//! Programs may only use the Time and Rand effects, so the corpus can't
+//! touch the filesystem or network.
+
+use std::path::Path;
+use strata_cli::eval::{self, run_module_with, RunOptions};
+use strata_cli::host::EffectPolicy;
+use strata_types::Effect;
+
+/// Programs aimed at the checks the evaluator backs up: patterns,
+/// exhaustiveness, and capability moves
+const CORPUS: &[(&str, &str)] = &[
+    (
+        "enum match",
+        r#"
+        enum Shape { Dot, Line(Int), Box(Int, Int) }
+        fn area(s: Shape) -> Int {
+            match s { Shape::Dot => 0, Shape::Line(n) => n, Shape::Box(w, h) => w * h }
+        }
+        fn main() -> Int { area(Shape::Dot) + area(Shape::Line(2)) + area(Shape::Box(3, 4)) }
+        "#,
+    ),
+    (
+        "nested option match",
+        r#"
+        fn first(a: Option<Option<Int>>) -> Int {
+            match a {
+                Option::Some(Option::Some(n)) => n,
+                Option::Some(Option::None) => 1,
+                Option::None => 0,
+            }
+        }
+        fn main() -> Int {
+            first(Option::Some(Option::Some(5))) + first(Option::Some(Option::None))
+                + first(Option::None)
+        }
+        "#,
+    ),
+    (
+        "tuple and bool matrix",
+        r#"
+        fn xor(a: Bool, b: Bool) -> Bool {
+            match (a, b) { (true, false) => true, (false, true) => true, _ => false }
+        }
+        fn main() -> Bool { xor(true, false) && !xor(true, true) }
+        "#,
+    ),
+    (
+        "int ranges",
+        r#"
+        fn bucket(n: Int) -> Int {
+            match n { ..=-1 => 0, 0..5 => 1, 5..=9 => 2, _ => 3 }
+        }
+        fn main() -> Int { bucket(-3) + bucket(0) + bucket(9) + bucket(10) }
+        "#,
+    ),
+    (
+        "string prefix",
+        r#"
+        fn route(s: String) -> String {
+            match s { "get " + path => path, "" => "empty", _ => "other" }
+        }
+        fn main() -> String { route("get /a") }
+        "#,
+    ),
+    (
+        "const patterns",
+        r#"
+        const LIMIT: Int = 10;
+        fn at_limit(n: Int) -> Bool { match n { LIMIT => true, _ => false } }
+        fn main() -> Bool { at_limit(10) && !at_limit(3) }
+        "#,
+    ),
+    (
+        "destructuring lets",
+        r#"
+        struct Point { x: Int, y: Int }
+        fn main() -> Int {
+            let (a, (b, c)) = (1, (2, 3));
+            let Point { x, y } = Point { x: a + b, y: c };
+            x * y
+        }
+        "#,
+    ),
+    (
+        "shadowed bindings in arms",
+        r#"
+        fn main() -> Int {
+            let x = 1;
+            let y = match (x, 2) { (x, y) => x + y };
+            let x = y * 10;
+            x
+        }
+        "#,
+    ),
+    (
+        "capability passed down once",
+        r#"
+        extern fn now(t: TimeCap) -> String & {Time};
+        fn stamp(t: TimeCap) -> String & {Time} { now(t) }
+        fn main(t: TimeCap) -> Bool & {Time} { stamp(t) != "" }
+        "#,
+    ),
+    (
+        "capability moved on one branch only",
+        r#"
+        extern fn now(t: TimeCap) -> String & {Time};
+        fn pick(t: TimeCap, read: Bool) -> String & {Time} {
+            if read { now(t) } else { drop(t); "" }
+        }
+        fn main(t: TimeCap) -> String & {Time} { pick(t, false) }
+        "#,
+    ),
+    (
+        "capability inside a linear enum",
+        r#"
+        extern fn random_int(r: RandCap) -> Int & {Rand};
+        linear enum Slot { Full(RandCap), Empty }
+        fn roll(s: Slot) -> Int & {Rand} {
+            match s { Slot::Full(r) => random_int(r), Slot::Empty => 0 }
+        }
+        fn main(r: RandCap) -> Bool & {Rand} { roll(Slot::Full(r)) + roll(Slot::Empty) >= 0 }
+        "#,
+    ),
+    (
+        "capabilities split across par",
+        r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        extern fn random_int(r: RandCap) -> Int & {Rand};
+        fn stamp(t: &TimeCap) -> String & {Time} { now(t) }
+        fn roll(r: RandCap) -> Int & {Rand} { random_int(r) }
+        fn main(t: TimeCap, r: RandCap) -> Bool & {Time, Rand} {
+            let (a, n) = par(stamp, &t, roll, r);
+            a != "" && stamp(&t) != "" && n >= 0
+        }
+        "#,
+    ),
+    (
+        "capabilities moved into tasks",
+        r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        extern fn random_int(r: RandCap) -> Int & {Rand};
+        fn main(t: TimeCap, r: RandCap) -> Bool & {Time, Rand} {
+            let stamp = spawn { now(&t) };
+            let roll = spawn { let inner = spawn { random_int(r) }; join(inner) };
+            join(stamp) != "" && join(roll) >= 0
+        }
+        "#,
+    ),
+    (
+        "composite comparisons",
+        r#"
+        struct Point { x: Int, y: Int }
+        fn closest(a: (Int, String), b: (Int, String)) -> (Int, String) {
+            if a <= b { a } else { b }
+        }
+        fn main() -> Bool {
+            let p = Point { x: 1, y: 2 };
+            p == Point { x: 1, y: 2 } && Option::Some(p) != Option::None
+                && closest((2, "b"), (2, "a")) == (2, "a")
+        }
+        "#,
+    ),
+    (
+        "nested functions and loops",
+        r#"
+        fn main() -> Int {
+            fn even(n: Int) -> Bool { if n == 0 { true } else { odd(n - 1) } }
+            fn odd(n: Int) -> Bool { if n == 0 { false } else { even(n - 1) } }
+            let mut i = 0;
+            let mut count = 0;
+            while i < 10 {
+                count = count + match even(i) { true => 1, false => 0 };
+                i = i + 1;
+            };
+            count
+        }
+        "#,
+    ),
+];
+
+/// Check and run one program. `Ok(false)` if it doesn't check, so it was
+/// not run; `Err` names the guard it tripped.
+fn check_then_run(name: &str, src: &str) -> Result<bool, String> {
+    let Ok(module) = strata_parse::parse_str(name, src) else {
+        return Ok(false);
+    };
+    if strata_types::TypeChecker::new()
+        .check_module(&module)
+        .is_err()
+    {
+        return Ok(false);
+    }
+    let opts = RunOptions {
+        policy: EffectPolicy::allow_only(&[Effect::Time, Effect::Rand]),
+        ..RunOptions::default()
+    };
+    match run_module_with(&module, opts) {
+        Err(e) if eval::is_checker_escape(&e) => Err(format!("{name}: {e:#}")),
+        _ => Ok(true),
+    }
+}
+
+#[test]
+fn checked_programs_never_trip_runtime_defenses() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut paths: Vec<_> = std::fs::read_dir(&examples)
+        .expect("read examples")
+        .map(|entry| entry.expect("read examples").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "strata"))
+        .collect();
+    paths.sort();
+
+    let mut escapes = Vec::new();
+    // Some examples show off type errors; those are skipped
+    for path in &paths {
+        let src = std::fs::read_to_string(path).expect("read example");
+        if let Err(e) = check_then_run(&path.display().to_string(), &src) {
+            escapes.push(e);
+        }
+    }
+    for (name, src) in CORPUS {
+        match check_then_run(name, src) {
+            Ok(ran) => assert!(ran, "corpus program '{name}' should check"),
+            Err(e) => escapes.push(e),
+        }
+    }
+    assert!(
+        escapes.is_empty(),
+        "accepted programs failed at runtime:\n{}",
+        escapes.join("\n")
+    );
+}
+
+#[test]
+fn runtime_defenses_are_recognized() {
+    // A module the checker would reject (`let` with a refutable pattern),
+    // run anyway: the evaluator's guard fires and is classified as an escape
+    let src = "fn main() -> Int { let Option::Some(x) = Option::None; x }";
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    assert!(strata_types::TypeChecker::new()
+        .check_module(&module)
+        .is_err());
+    let err = eval::run_module(&module).unwrap_err();
+    assert!(eval::is_checker_escape(&err), "{err:#}");
+
+    // An ordinary runtime error is not
+    let src = "fn main() -> Int { unreachable() }";
+    let module = strata_parse::parse_str("<test>", src).expect("parse failed");
+    let err = eval::run_module(&module).unwrap_err();
+    assert!(!eval::is_checker_escape(&err), "{err:#}");
+}
@@ -0,0 +1,77 @@
+//! Golden tests for rendered diagnostics.
+//!
+//! Each `tests/ui/NAME.strata` is checked with `strata check`, and its
+//! stderr (errors and warnings, with line and column) must match
+//! `tests/ui/NAME.stderr` exactly. Message wording carries hints users act
+//! on, so a change to it shows up as a diff to that file.
+//!
+//! After an intended change, rewrite the expectations with
+//! `STRATA_BLESS=1 cargo test -p strata-cli --test ui` and review the diff.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn ui_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/ui")
+}
+
+/// The stderr of `strata check` on `name`, run from the ui directory so
+/// locations read `NAME.strata:line:col`.
+fn check_stderr(name: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_strata-cli"))
+        .args(["check", "--no-cache", name])
+        .current_dir(ui_dir())
+        .output()
+        .expect("run binary");
+    String::from_utf8(output.stderr).expect("stderr is UTF-8")
+}
+
+#[test]
+fn ui_diagnostics_match_golden_files() {
+    let bless = std::env::var_os("STRATA_BLESS").is_some();
+    let mut sources: Vec<_> = std::fs::read_dir(ui_dir())
+        .expect("read tests/ui")
+        .map(|entry| entry.expect("read tests/ui").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "strata"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty(), "no programs in tests/ui");
+
+    let mut failures = Vec::new();
+    for source in &sources {
+        let name = source.file_name().unwrap().to_str().unwrap();
+        let actual = check_stderr(name);
+        let golden = source.with_extension("stderr");
+        if bless {
+            std::fs::write(&golden, &actual).expect("write golden file");
+            continue;
+        }
+        match std::fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{name}: stderr differs from {}\n--- expected\n{expected}+++ actual\n{actual}",
+                golden.display()
+            )),
+            Err(_) => failures.push(format!("{name}: no {} yet", golden.display())),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\n\nIf the new output is intended, rerun with STRATA_BLESS=1 and review the diff",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn ui_golden_files_have_programs() {
+    for entry in std::fs::read_dir(ui_dir()).expect("read tests/ui") {
+        let path = entry.expect("read tests/ui").path();
+        if path.extension().is_some_and(|ext| ext == "stderr") {
+            assert!(
+                path.with_extension("strata").exists(),
+                "{} has no program; delete it",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Lexer throughput on large generated sources.
+//!
+//! Run with `cargo bench -p strata-parse --bench lexer`. The lexer is
+//! private, so each scenario goes through `parse_str_with_options` with the
+//! token limit lifted; the inputs are flat enough that lexing dominates.
+//! Uses a plain timing loop (no external harness) and reports the median
+//! per scenario with throughput.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use strata_parse::{parse_str_with_options, ParseOptions};
+
+const ITERATIONS: usize = 9;
+
+/// Repeat `item(i)` until the source is at least `bytes` long
+fn generate(bytes: usize, item: impl Fn(usize) -> String) -> String {
+    let mut src = String::with_capacity(bytes + 256);
+    let mut i = 0;
+    while src.len() < bytes {
+        src.push_str(&item(i));
+        i += 1;
+    }
+    src
+}
+
+/// Code-generator style: many small functions of arithmetic on locals
+fn idents(i: usize) -> String {
+    format!(
+        "fn step_{i}(state_value: Int, delta_amount: Int) -> Int {{\n    \
+         let scaled_{i} = state_value * 31 + delta_amount;\n    \
+         let offset_{i} = scaled_{i} - 0x7f_ff + 1_000;\n    \
+         if offset_{i} >= 0 {{ offset_{i} }} else {{ 0 - offset_{i} }}\n}}\n"
+    )
+}
+
+/// String tables: long literals, a few with escapes
+fn strings(i: usize) -> String {
+    format!(
+        "fn label_{i}() -> String {{\n    \
+         let a = \"generated label number {i} for the lookup table entry\";\n    \
+         let b = \"escaped\\tcolumn\\n{i}\";\n    \
+         let c = r#\"raw \"quoted\" {i}\"#;\n    \
+         a\n}}\n"
+    )
+}
+
+/// Heavily commented output
+fn comments(i: usize) -> String {
+    format!(
+        "// Generated from schema entry {i}; do not edit by hand.\n\
+         /* Source: tables/entry_{i}.json\n   \
+         Owner: codegen pipeline */\n\
+         fn c_{i}() -> Int {{ {i} }}\n"
+    )
+}
+
+fn bench(name: &str, src: &str) {
+    let opts = ParseOptions {
+        max_tokens: usize::MAX,
+        ..ParseOptions::default()
+    };
+    let mut times: Vec<Duration> = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            let module = parse_str_with_options("bench.strata", src, &opts).expect("parse");
+            black_box(module);
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    let median = times[ITERATIONS / 2];
+    let mb = src.len() as f64 / (1024.0 * 1024.0);
+    println!(
+        "{:<20} {:>7.1} MiB {:>12?} {:>9.1} MiB/s",
+        name,
+        mb,
+        median,
+        mb / median.as_secs_f64()
+    );
+}
+
+fn main() {
+    for mb in [1, 8] {
+        let bytes = mb << 20;
+        bench(&format!("idents_{mb}mb"), &generate(bytes, idents));
+        bench(&format!("strings_{mb}mb"), &generate(bytes, strings));
+        bench(&format!("comments_{mb}mb"), &generate(bytes, comments));
+    }
+}
@@ -0,0 +1,156 @@
+//! Structured parse errors.
+//!
+//! Every failure from the lexer or parser is a `ParseError` carrying the span
+//! it applies to, so tooling can report it without scraping message text.
+//! `to_diagnostic()` converts it to the shared [`Diagnostic`] format.
+
+use strata_ast::diag::Diagnostic;
+use strata_ast::span::Span;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// A token other than any of `expected` was found.
+    UnexpectedToken {
+        found: String,
+        expected: Vec<String>,
+        span: Span,
+    },
+    /// A string literal runs to end of file without a closing `"`.
+    UnterminatedString { span: Span },
+    /// A `/*` block comment runs to end of file; the span covers the
+    /// innermost unclosed `/*`.
+    UnterminatedComment { span: Span },
+    /// An unknown or malformed `\` escape; the span covers the escape only.
+    InvalidEscape { escape: String, span: Span },
+    /// A malformed numeric literal (bad digit for its radix, misplaced `_`).
+    InvalidNumber {
+        literal: String,
+        reason: String,
+        span: Span,
+    },
+    /// An integer literal outside the range of `Int` (i64).
+    IntegerOverflow { literal: String, span: Span },
+    /// A character that cannot start any token.
+    UnexpectedChar { ch: char, span: Span },
+    /// Input nests expressions, blocks, patterns, or types deeper than the
+    /// configured limit. The span is the token at which the limit was hit.
+    TooDeep { limit: u32, span: Span },
+    /// The source has more tokens than the configured limit.
+    TooManyTokens { limit: usize, span: Span },
+    /// The source is longer than the configured byte limit, or than a
+    /// `Span` can address. Reported before lexing; the span is empty at
+    /// the start of the file.
+    SourceTooLarge {
+        len: usize,
+        limit: usize,
+        span: Span,
+    },
+    /// The parser thread, with its `bytes` of stack, could not be started.
+    /// The span is empty at the start of the file.
+    NoStack {
+        bytes: usize,
+        reason: String,
+        span: Span,
+    },
+    /// Well-formed tokens in a construct the grammar rejects (e.g. an
+    /// assignment to a non-variable).
+    InvalidSyntax { message: String, span: Span },
+}
+
+impl ParseError {
+    /// Source span the error applies to.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnterminatedString { span }
+            | ParseError::UnterminatedComment { span }
+            | ParseError::InvalidEscape { span, .. }
+            | ParseError::InvalidNumber { span, .. }
+            | ParseError::IntegerOverflow { span, .. }
+            | ParseError::UnexpectedChar { span, .. }
+            | ParseError::TooDeep { span, .. }
+            | ParseError::TooManyTokens { span, .. }
+            | ParseError::SourceTooLarge { span, .. }
+            | ParseError::NoStack { span, .. }
+            | ParseError::InvalidSyntax { span, .. } => *span,
+        }
+    }
+
+    /// Stable machine-readable code for this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "PARSE-UNEXPECTED-TOKEN",
+            ParseError::UnterminatedString { .. } => "PARSE-UNTERMINATED-STRING",
+            ParseError::UnterminatedComment { .. } => "PARSE-UNTERMINATED-COMMENT",
+            ParseError::InvalidEscape { .. } => "PARSE-INVALID-ESCAPE",
+            ParseError::InvalidNumber { .. } => "PARSE-INVALID-NUMBER",
+            ParseError::IntegerOverflow { .. } => "PARSE-INT-OVERFLOW",
+            ParseError::UnexpectedChar { .. } => "PARSE-UNEXPECTED-CHAR",
+            ParseError::TooDeep { .. } => "PARSE-TOO-DEEP",
+            ParseError::TooManyTokens { .. } => "PARSE-TOKEN-LIMIT",
+            ParseError::SourceTooLarge { .. } => "PARSE-SOURCE-TOO-LARGE",
+            ParseError::NoStack { .. } => "PARSE-NO-STACK",
+            ParseError::InvalidSyntax { .. } => "PARSE-INVALID-SYNTAX",
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.code(), self.to_string(), self.span())
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                found, expected, ..
+            } => match expected.as_slice() {
+                [] => write!(f, "unexpected {}", found),
+                [one] => write!(f, "expected {}, found {}", one, found),
+                many => write!(f, "expected one of {}, found {}", many.join(", "), found),
+            },
+            ParseError::UnterminatedString { .. } => {
+                write!(f, "Lexer error: unterminated string literal")
+            }
+            ParseError::UnterminatedComment { .. } => {
+                write!(f, "Lexer error: unterminated block comment")
+            }
+            ParseError::InvalidEscape { escape, .. } => {
+                write!(f, "Lexer error: invalid escape sequence `{}`", escape)
+            }
+            ParseError::InvalidNumber {
+                literal, reason, ..
+            } => write!(
+                f,
+                "Lexer error: invalid numeric literal `{}`: {}",
+                literal, reason
+            ),
+            ParseError::IntegerOverflow { literal, .. } => {
+                write!(f, "Lexer error: integer literal out of range: {}", literal)
+            }
+            ParseError::UnexpectedChar { ch, .. } => {
+                write!(f, "Lexer error: unexpected character {:?}", ch)
+            }
+            ParseError::TooDeep { limit, .. } => {
+                write!(f, "maximum nesting depth exceeded (limit: {})", limit)
+            }
+            ParseError::TooManyTokens { limit, .. } => write!(
+                f,
+                "Lexer error: token count limit exceeded (max {} tokens)",
+                limit
+            ),
+            ParseError::SourceTooLarge { len, limit, .. } => {
+                write!(f, "source is {} bytes, over the {} byte limit", len, limit)
+            }
+            ParseError::NoStack { bytes, reason, .. } => write!(
+                f,
+                "could not start the parser with a {} KiB stack: {}",
+                bytes / 1024,
+                reason
+            ),
+            ParseError::InvalidSyntax { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -1,25 +1,83 @@
+use crate::error::ParseError;
 use crate::token::{Tok, TokKind};
-use strata_ast::span::Span;
+use std::borrow::Cow;
+use strata_ast::span::{FileId, Span};
 
-/// Maximum number of tokens allowed in a single source file.
-/// This prevents denial of service from pathological inputs.
-const MAX_TOKEN_COUNT: usize = 200_000;
+const WS: u8 = 1;
+const IDENT_START: u8 = 2;
+const DIGIT: u8 = 4;
+
+/// Byte classes for the scanning loops, so skipping whitespace or taking
+/// an identifier is one table lookup per byte. Only ASCII bytes have a
+/// class; anything else stops every scan.
+static CLASS: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut b = 0;
+    while b < 128 {
+        let c = b as u8;
+        if matches!(c, b' ' | b'\t' | b'\n' | b'\r' | 0x0B | 0x0C) {
+            table[b] = WS;
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            table[b] = IDENT_START;
+        } else if c.is_ascii_digit() {
+            table[b] = DIGIT;
+        }
+        b += 1;
+    }
+    table
+};
+
+/// Length of the run of bytes at the start of `bytes` whose class is in `mask`.
+fn run_len(bytes: &[u8], mask: u8) -> usize {
+    bytes
+        .iter()
+        .position(|&b| CLASS[b as usize] & mask == 0)
+        .unwrap_or(bytes.len())
+}
+
+/// Escape `s` so that `"` + result + `"` lexes back to exactly `s`.
+pub fn escape_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
 
 pub struct Lexer<'a> {
+    /// The source, which token text borrows from
+    text: &'a str,
     src: &'a [u8],
+    /// File every span is stamped with
+    file: FileId,
     pos: usize,
     /// Number of tokens emitted (for limit enforcement)
     token_count: usize,
+    /// Maximum number of tokens allowed in a single source file.
+    /// This prevents denial of service from pathological inputs.
+    max_tokens: usize,
     /// True once token limit is hit (latches to prevent repeated errors)
     hit_token_limit: bool,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(src: &'a str) -> Self {
+    pub fn new(src: &'a str, file: FileId, max_tokens: usize) -> Self {
         Self {
+            text: src,
             src: src.as_bytes(),
+            file,
             pos: 0,
             token_count: 0,
+            max_tokens,
             hit_token_limit: false,
         }
     }
@@ -41,278 +99,592 @@ impl<'a> Lexer<'a> {
         self.src.get(self.pos + 1).copied()
     }
 
+    /// Span from `start` to the current position.
     fn span(&self, start: usize) -> Span {
-        Span {
-            start: start as u32,
-            end: self.pos as u32,
-        }
+        self.span_of(start, self.pos)
     }
 
-    fn skip_ws_and_comments(&mut self) {
+    /// The parser rejects sources longer than `MAX_SOURCE_LEN` before
+    /// lexing, so offsets always fit.
+    fn span_of(&self, start: usize, end: usize) -> Span {
+        Span::clamped(self.file, start, end)
+    }
+
+    /// Skip whitespace, `//` line comments, and `/* */` block comments.
+    ///
+    /// Block comments nest, so `/* a /* b */ c */` is one comment. A block
+    /// comment still open at end of input is an error spanning the innermost
+    /// unclosed `/*`.
+    fn skip_ws_and_comments(&mut self) -> Result<(), ParseError> {
         loop {
-            while matches!(self.peek(), Some(b) if (b as char).is_whitespace()) {
-                self.bump();
-            }
+            self.pos += run_len(&self.src[self.pos..], WS);
             // line comment: //
             if self.peek() == Some(b'/') && self.peek2() == Some(b'/') {
-                self.bump();
-                self.bump();
-                while let Some(b) = self.peek() {
-                    if b == b'\n' {
-                        break;
+                let rest = &self.src[self.pos..];
+                self.pos += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+                continue;
+            }
+            // block comment: /* ... */, nesting
+            if self.peek() == Some(b'/') && self.peek2() == Some(b'*') {
+                self.skip_block_comment()?;
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
+    /// Skip a block comment starting at the current `/*`.
+    fn skip_block_comment(&mut self) -> Result<(), ParseError> {
+        // Start offsets of the currently open `/*`s
+        let mut open = Vec::new();
+        loop {
+            match (self.peek(), self.peek2()) {
+                (Some(b'/'), Some(b'*')) => {
+                    open.push(self.pos);
+                    self.pos += 2;
+                }
+                (Some(b'*'), Some(b'/')) => {
+                    self.pos += 2;
+                    open.pop();
+                    if open.is_empty() {
+                        return Ok(());
                     }
-                    self.bump();
                 }
-                continue;
+                (Some(_), _) => {
+                    // Jump to the next byte that could open or close a comment
+                    let rest = &self.src[self.pos + 1..];
+                    self.pos += 1 + rest
+                        .iter()
+                        .position(|&b| b == b'/' || b == b'*')
+                        .unwrap_or(rest.len());
+                }
+                (None, _) => {
+                    let start = open.last().copied().unwrap_or(self.pos);
+                    return Err(ParseError::UnterminatedComment {
+                        span: self.span_of(start, start + 2),
+                    });
+                }
             }
-            break;
         }
     }
 
-    pub fn next_tok(&mut self) -> Tok {
-        // If token limit was already hit, return EOF to prevent infinite error loop
-        if self.hit_token_limit {
-            return Tok {
-                kind: TokKind::Eof,
-                span: Span {
-                    start: self.pos as u32,
-                    end: self.pos as u32,
-                },
-            };
+    /// Lex a string literal whose opening `"` has been consumed.
+    ///
+    /// Escapes: `\n`, `\t`, `\r`, `\0`, `\"`, `\\`, and `\u{XXXX}` (1-6 hex
+    /// digits naming a Unicode scalar value). A `\` at the end of a line
+    /// continues the string: the newline and the next line's leading
+    /// whitespace are dropped. Any other escape is an error whose span covers
+    /// just the escape sequence.
+    ///
+    /// A string without escapes borrows its text from the source.
+    fn lex_string(&mut self, start: usize) -> Result<Cow<'a, str>, ParseError> {
+        let body_start = self.pos;
+        let rest = &self.src[body_start..];
+        let Some(i) = rest.iter().position(|&b| b == b'"' || b == b'\\') else {
+            self.pos = self.src.len();
+            return Err(ParseError::UnterminatedString {
+                span: self.span(start),
+            });
+        };
+        self.pos = body_start + i;
+        if rest[i] == b'"' {
+            self.pos += 1;
+            return Ok(Cow::Borrowed(&self.text[body_start..body_start + i]));
         }
 
-        // Security: Check token count limit (streaming)
-        self.token_count += 1;
-        if self.token_count > MAX_TOKEN_COUNT {
-            self.hit_token_limit = true;
-            return Tok {
-                kind: TokKind::Error(format!(
-                    "token count limit exceeded (max {} tokens)",
-                    MAX_TOKEN_COUNT
-                )),
-                span: Span {
-                    start: self.pos as u32,
-                    end: self.pos as u32,
-                },
+        let mut bytes = rest[..i].to_vec();
+        loop {
+            let Some(b) = self.bump() else {
+                return Err(ParseError::UnterminatedString {
+                    span: self.span(start),
+                });
             };
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    let esc_start = self.pos - 1;
+                    if let Some(ch) = self.lex_escape(esc_start)? {
+                        let mut buf = [0; 4];
+                        bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                    }
+                }
+                _ => bytes.push(b),
+            }
         }
+        // Source is valid UTF-8 and escapes only split at ASCII boundaries
+        Ok(Cow::Owned(String::from_utf8(bytes).unwrap_or_default()))
+    }
 
-        self.skip_ws_and_comments();
-        let start = self.pos;
-        let Some(b) = self.bump() else {
-            return Tok {
-                kind: TokKind::Eof,
-                span: Span {
-                    start: self.pos as u32,
-                    end: self.pos as u32,
-                },
-            };
+    /// Lex a `"""` multi-line string whose opening delimiter has been consumed.
+    ///
+    /// The opening `"""` must end its line and the closing `"""` must sit on
+    /// a line of its own. The closing line's indentation is stripped from
+    /// every content line (blank lines may be shorter); the newline before
+    /// the closing line is not part of the value. Escapes work as in `"..."`
+    /// strings, including `\` line continuation.
+    fn lex_multiline_string(&mut self, start: usize) -> Result<Cow<'a, str>, ParseError> {
+        let file = self.file;
+        let invalid = |message: &str, from: usize, to: usize| ParseError::InvalidSyntax {
+            message: message.to_string(),
+            span: Span::clamped(file, from, to),
         };
-        let c = b as char;
 
-        // 2-char operators first
-        if c == '&' && self.peek() == Some(b'&') {
+        // Rest of the opening line must be blank
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\r')) {
             self.bump();
-            return Tok {
-                kind: TokKind::AndAnd,
-                span: self.span(start),
-            };
         }
-        if c == '|' && self.peek() == Some(b'|') {
-            self.bump();
-            return Tok {
-                kind: TokKind::OrOr,
-                span: self.span(start),
-            };
+        if self.peek() != Some(b'\n') {
+            return Err(invalid(
+                "multi-line string must start on the line after `\"\"\"`",
+                start,
+                self.pos,
+            ));
         }
-        if c == '=' && self.peek() == Some(b'=') {
-            self.bump();
-            return Tok {
-                kind: TokKind::EqEq,
-                span: self.span(start),
-            };
+        self.bump();
+        let body_start = self.pos;
+
+        // Find the closing delimiter, skipping escaped characters
+        let mut close = body_start;
+        loop {
+            match self.src.get(close) {
+                None => {
+                    return Err(ParseError::UnterminatedString {
+                        span: self.span_of(start, self.src.len()),
+                    })
+                }
+                Some(b'\\') => close += 2,
+                Some(b'"') if self.src[close..].starts_with(b"\"\"\"") => break,
+                Some(_) => close += 1,
+            }
         }
-        // FatArrow: =>
-        if c == '=' && self.peek() == Some(b'>') {
-            self.bump();
-            return Tok {
-                kind: TokKind::FatArrow,
-                span: self.span(start),
-            };
+
+        // The closing line is only indentation; it sets the strip width
+        let last_nl = self.src[body_start..close]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map(|i| body_start + i);
+        let indent_start = last_nl.map_or(body_start, |i| i + 1);
+        let indent = &self.src[indent_start..close];
+        if !indent.iter().all(|&b| b == b' ' || b == b'\t') {
+            return Err(invalid(
+                "closing `\"\"\"` must be on its own line",
+                indent_start,
+                close + 3,
+            ));
         }
-        if c == '!' && self.peek() == Some(b'=') {
-            self.bump();
-            return Tok {
-                kind: TokKind::BangEq,
-                span: self.span(start),
-            };
+
+        let mut bytes = Vec::new();
+        let mut line_start = body_start;
+        let mut joining = false;
+        while let Some(content_end) = last_nl.filter(|&nl| line_start <= nl) {
+            let line_end = self.src[line_start..=content_end]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(content_end, |i| line_start + i);
+            let line = &self.src[line_start..line_end];
+            let blank = line.iter().all(|&b| b == b' ' || b == b'\t' || b == b'\r');
+
+            let mut from = line_start;
+            if joining {
+                // Continuation: drop all leading whitespace
+                while from < line_end && matches!(self.src[from], b' ' | b'\t') {
+                    from += 1;
+                }
+            } else if blank {
+                from = line_end;
+            } else if line.starts_with(indent) {
+                from += indent.len();
+            } else {
+                return Err(invalid(
+                    "line in multi-line string is indented less than the closing `\"\"\"`",
+                    line_start,
+                    line_end,
+                ));
+            }
+
+            let mut to = line_end;
+            if to > from && self.src[to - 1] == b'\r' {
+                to -= 1;
+            }
+            // A line ending in an unescaped `\` continues onto the next line
+            let trailing = self.src[from..to]
+                .iter()
+                .rev()
+                .take_while(|&&b| b == b'\\')
+                .count();
+            joining = trailing % 2 == 1;
+            if joining {
+                to -= 1;
+            }
+
+            self.pos = from;
+            self.decode_escapes(to, &mut bytes)?;
+            if !joining && line_end < content_end {
+                bytes.push(b'\n');
+            }
+            line_start = line_end + 1;
         }
-        if c == '<' && self.peek() == Some(b'=') {
-            self.bump();
-            return Tok {
-                kind: TokKind::Le,
-                span: self.span(start),
+
+        self.pos = close + 3;
+        Ok(Cow::Owned(String::from_utf8(bytes).unwrap_or_default()))
+    }
+
+    /// Decode the string body from `self.pos` up to `end`, processing escapes.
+    fn decode_escapes(&mut self, end: usize, out: &mut Vec<u8>) -> Result<(), ParseError> {
+        while self.pos < end {
+            let b = self.src[self.pos];
+            self.pos += 1;
+            if b == b'\\' {
+                if let Some(ch) = self.lex_escape(self.pos - 1)? {
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            } else {
+                out.push(b);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lex the escape sequence after a `\` at `esc_start`. Returns None for a
+    /// line continuation, which produces no character.
+    fn lex_escape(&mut self, esc_start: usize) -> Result<Option<char>, ParseError> {
+        let invalid = |lexer: &Self| ParseError::InvalidEscape {
+            escape: String::from_utf8_lossy(&lexer.src[esc_start..lexer.pos]).into_owned(),
+            span: lexer.span(esc_start),
+        };
+        let Some(esc) = self.bump() else {
+            return Err(ParseError::UnterminatedString {
+                span: self.span(esc_start),
+            });
+        };
+        let ch = match esc {
+            b'\n' | b'\r' => {
+                if esc == b'\r' && self.peek() == Some(b'\n') {
+                    self.bump();
+                }
+                while matches!(self.peek(), Some(b' ' | b'\t' | b'\r' | b'\n')) {
+                    self.bump();
+                }
+                return Ok(None);
+            }
+            b'n' => '\n',
+            b't' => '\t',
+            b'r' => '\r',
+            b'0' => '\0',
+            b'"' => '"',
+            b'\\' => '\\',
+            b'u' => {
+                if self.peek() != Some(b'{') {
+                    return Err(invalid(self));
+                }
+                self.bump();
+                let digits_start = self.pos;
+                while matches!(self.peek(), Some(b) if b.is_ascii_hexdigit()) {
+                    self.bump();
+                }
+                let digits = &self.src[digits_start..self.pos];
+                if self.peek() != Some(b'}') || digits.is_empty() || digits.len() > 6 {
+                    if self.peek() == Some(b'}') {
+                        self.bump();
+                    }
+                    return Err(invalid(self));
+                }
+                self.bump();
+                let code = std::str::from_utf8(digits)
+                    .ok()
+                    .and_then(|d| u32::from_str_radix(d, 16).ok());
+                match code.and_then(char::from_u32) {
+                    Some(ch) => ch,
+                    None => return Err(invalid(self)),
+                }
+            }
+            _ => {
+                // Include the whole (possibly multi-byte) escaped character
+                while self.pos < self.src.len() && (self.src[self.pos] & 0xC0) == 0x80 {
+                    self.pos += 1;
+                }
+                return Err(invalid(self));
+            }
+        };
+        Ok(Some(ch))
+    }
+
+    /// Lex a raw string after `r`, `hashes` `#`s, and the opening `"`. No
+    /// escapes are processed; it ends at `"` followed by the same number of
+    /// `#`s.
+    fn lex_raw_string(&mut self, start: usize, hashes: usize) -> Result<&'a str, ParseError> {
+        let body_start = self.pos;
+        loop {
+            let Some(b) = self.bump() else {
+                return Err(ParseError::UnterminatedString {
+                    span: self.span(start),
+                });
             };
+            let closes = b == b'"'
+                && self.src.len() >= self.pos + hashes
+                && self.src[self.pos..self.pos + hashes]
+                    .iter()
+                    .all(|&h| h == b'#');
+            if closes {
+                let body = &self.text[body_start..self.pos - 1];
+                self.pos += hashes;
+                return Ok(body);
+            }
         }
-        if c == '>' && self.peek() == Some(b'=') {
-            self.bump();
-            return Tok {
-                kind: TokKind::Ge,
+    }
+
+    /// Lex a numeric literal whose first digit `first` has been consumed.
+    ///
+    /// Accepts decimal ints and floats, `0x`/`0b` ints, and `_` separators
+    /// between digits (`1_000_000`). The value is normalized into the token;
+    /// the original spelling is recoverable from the span.
+    fn lex_number(&mut self, first: u8, start: usize) -> TokKind<'a> {
+        let radix = match (first, self.peek()) {
+            (b'0', Some(b'x' | b'X')) => 16,
+            (b'0', Some(b'b' | b'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.bump(); // radix prefix
+        }
+        let digits_start = if radix == 10 { start } else { self.pos };
+        let mut dot = false;
+
+        // Take every alphanumeric/underscore byte so `0b102` or `0xFG` are
+        // reported as one bad literal rather than a literal and an ident.
+        while let Some(b) = self.peek() {
+            if b == b'.' && radix == 10 && !dot && self.peek2() != Some(b'.') {
+                // `1..` is an int followed by a range operator, not a float
+                dot = true;
+            } else if CLASS[b as usize] & (IDENT_START | DIGIT) == 0 {
+                break;
+            } else if radix == 10 && !b.is_ascii_digit() && b != b'_' {
+                // Decimal literals end at a letter (e.g. `1` then ident `x`)
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let digits = &self.text[digits_start..self.pos];
+        let invalid = |reason: String| {
+            TokKind::Error(ParseError::InvalidNumber {
+                literal: self.text[start..self.pos].to_string(),
+                reason,
                 span: self.span(start),
+            })
+        };
+
+        if digits.ends_with('_') || digits.contains("_.") || digits.contains("._") {
+            return invalid("`_` must separate digits".to_string());
+        }
+        let clean = if digits.contains('_') {
+            Cow::Owned(digits.replace('_', ""))
+        } else {
+            Cow::Borrowed(digits)
+        };
+        if clean.is_empty() {
+            return invalid("no digits after radix prefix".to_string());
+        }
+        if let Some(bad) = clean.chars().find(|&ch| ch != '.' && !ch.is_digit(radix)) {
+            let base = if radix == 16 { "hexadecimal" } else { "binary" };
+            return invalid(format!("invalid digit {:?} in {} literal", bad, base));
+        }
+
+        if dot {
+            return match clean.parse::<f64>() {
+                Ok(f) => TokKind::Float(f),
+                Err(_) => invalid("invalid float literal".to_string()),
             };
         }
-        // Arrow: ->
-        if c == '-' && self.peek() == Some(b'>') {
-            self.bump();
-            return Tok {
-                kind: TokKind::Arrow,
+        match i64::from_str_radix(&clean, radix) {
+            Ok(i) => TokKind::Int(i),
+            Err(_) => TokKind::Error(ParseError::IntegerOverflow {
+                literal: self.text[start..self.pos].to_string(),
                 span: self.span(start),
-            };
+            }),
         }
-        // ColonColon: ::
-        if c == ':' && self.peek() == Some(b':') {
-            self.bump();
+    }
+
+    /// Lex an identifier or keyword whose first byte has been consumed.
+    fn lex_word(&mut self, start: usize) -> TokKind<'a> {
+        self.pos += run_len(&self.src[self.pos..], IDENT_START | DIGIT);
+        match &self.text[start..self.pos] {
+            "let" => TokKind::KwLet,
+            "fn" => TokKind::KwFn,
+            "true" => TokKind::KwTrue,
+            "false" => TokKind::KwFalse,
+            "nil" => TokKind::KwNil,
+            "if" => TokKind::KwIf,
+            "else" => TokKind::KwElse,
+            "while" => TokKind::KwWhile,
+            "for" => TokKind::KwFor,
+            "in" => TokKind::KwIn,
+            "spawn" => TokKind::KwSpawn,
+            "return" => TokKind::KwReturn,
+            "mut" => TokKind::KwMut,
+            "match" => TokKind::KwMatch,
+            "enum" => TokKind::KwEnum,
+            "struct" => TokKind::KwStruct,
+            "extern" => TokKind::KwExtern,
+            "linear" => TokKind::KwLinear,
+            "transparent" => TokKind::KwTransparent,
+            "bench" => TokKind::KwBench,
+            "pure" => TokKind::KwPure,
+            "const" => TokKind::KwConst,
+            "pub" => TokKind::KwPub,
+            "use" => TokKind::KwUse,
+            word => TokKind::Ident(word),
+        }
+    }
+
+    /// Consume the next byte if it is `b`.
+    fn eat(&mut self, b: u8) -> bool {
+        let hit = self.peek() == Some(b);
+        if hit {
+            self.pos += 1;
+        }
+        hit
+    }
+
+    pub fn next_tok(&mut self) -> Tok<'a> {
+        // If token limit was already hit, return EOF to prevent infinite error loop
+        if self.hit_token_limit {
             return Tok {
-                kind: TokKind::ColonColon,
-                span: self.span(start),
+                kind: TokKind::Eof,
+                span: self.span(self.pos),
             };
         }
 
-        // 1-char punctuation/operators
-        let single = match c {
-            '(' => Some(TokKind::LParen),
-            ')' => Some(TokKind::RParen),
-            '{' => Some(TokKind::LBrace),
-            '}' => Some(TokKind::RBrace),
-            ',' => Some(TokKind::Comma),
-            ':' => Some(TokKind::Colon),
-            ';' => Some(TokKind::Semicolon),
-            '+' => Some(TokKind::Plus),
-            '-' => Some(TokKind::Minus),
-            '*' => Some(TokKind::Star),
-            '/' => Some(TokKind::Slash),
-            '=' => Some(TokKind::Eq),
-            '<' => Some(TokKind::Lt),
-            '>' => Some(TokKind::Gt),
-            '!' => Some(TokKind::Bang),      // <-- single '!'
-            '&' => Some(TokKind::Ampersand), // single '&' for effect annotations
-            _ => None,
-        };
-        if let Some(k) = single {
+        // Security: Check token count limit (streaming)
+        self.token_count += 1;
+        if self.token_count > self.max_tokens {
+            self.hit_token_limit = true;
+            let span = self.span(self.pos);
             return Tok {
-                kind: k,
-                span: self.span(start),
+                kind: TokKind::Error(ParseError::TooManyTokens {
+                    limit: self.max_tokens,
+                    span,
+                }),
+                span,
             };
         }
 
-        // string
-        if c == '"' {
-            let mut s = String::new();
-            while let Some(b) = self.peek() {
-                self.bump();
-                let ch = b as char;
-                if ch == '"' {
-                    break;
-                }
-                if ch == '\\' {
-                    let Some(esc) = self.bump().map(|x| x as char) else {
-                        break;
-                    };
-                    let real = match esc {
-                        'n' => '\n',
-                        't' => '\t',
-                        '"' => '"',
-                        '\\' => '\\',
-                        _ => esc,
-                    };
-                    s.push(real);
-                } else {
-                    s.push(ch);
-                }
-            }
+        if let Err(err) = self.skip_ws_and_comments() {
             return Tok {
-                kind: TokKind::Str(s),
-                span: self.span(start),
+                span: err.span(),
+                kind: TokKind::Error(err),
             };
         }
+        let start = self.pos;
+        let Some(b) = self.bump() else {
+            return Tok {
+                kind: TokKind::Eof,
+                span: self.span(self.pos),
+            };
+        };
 
-        // number (int/float)
-        if c.is_ascii_digit() {
-            let mut s = String::from(c);
-            let mut dot = false;
-            while let Some(p) = self.peek() {
-                let ch = p as char;
-                if ch.is_ascii_digit() {
-                    s.push(ch);
-                    self.bump();
-                } else if ch == '.' && !dot {
-                    dot = true;
-                    s.push('.');
-                    self.bump();
+        let kind = match b {
+            // 2-char operators are tried before their 1-char prefixes
+            b'&' if self.eat(b'&') => TokKind::AndAnd,
+            b'|' if self.eat(b'|') => TokKind::OrOr,
+            b'|' if self.eat(b'>') => TokKind::PipeGt,
+            b'=' if self.eat(b'=') => TokKind::EqEq,
+            b'=' if self.eat(b'>') => TokKind::FatArrow,
+            b'!' if self.eat(b'=') => TokKind::BangEq,
+            b'<' if self.eat(b'=') => TokKind::Le,
+            b'>' if self.eat(b'=') => TokKind::Ge,
+            b'-' if self.eat(b'>') => TokKind::Arrow,
+            b':' if self.eat(b':') => TokKind::ColonColon,
+            // Range operators: ..= and .., and ... for variadic parameters
+            b'.' if self.eat(b'.') => {
+                if self.eat(b'=') {
+                    TokKind::DotDotEq
+                } else if self.eat(b'.') {
+                    TokKind::DotDotDot
                 } else {
-                    break;
+                    TokKind::DotDot
                 }
             }
-            if dot {
-                let kind = match s.parse::<f64>() {
-                    Ok(f) => TokKind::Float(f),
-                    Err(_) => TokKind::Error(format!("invalid float literal: {}", s)),
-                };
-                return Tok {
-                    kind,
-                    span: self.span(start),
-                };
-            } else {
-                let kind = match s.parse::<i64>() {
-                    Ok(i) => TokKind::Int(i),
-                    Err(_) => TokKind::Error(format!("integer literal out of range: {}", s)),
-                };
-                return Tok {
-                    kind,
-                    span: self.span(start),
+
+            b'.' => TokKind::Dot,
+            b'(' => TokKind::LParen,
+            b')' => TokKind::RParen,
+            b'{' => TokKind::LBrace,
+            b'}' => TokKind::RBrace,
+            b',' => TokKind::Comma,
+            b':' => TokKind::Colon,
+            b';' => TokKind::Semicolon,
+            b'+' => TokKind::Plus,
+            b'-' => TokKind::Minus,
+            b'*' => TokKind::Star,
+            b'/' => TokKind::Slash,
+            b'=' => TokKind::Eq,
+            b'<' => TokKind::Lt,
+            b'>' => TokKind::Gt,
+            b'!' => TokKind::Bang,
+            b'&' => TokKind::Ampersand, // single '&' for effect annotations
+            b'#' => TokKind::Hash,
+            b'[' => TokKind::LBracket,
+            b']' => TokKind::RBracket,
+
+            // string: """ multi-line """ or "..."
+            b'"' => {
+                let result = if self.peek() == Some(b'"') && self.peek2() == Some(b'"') {
+                    self.pos += 2;
+                    self.lex_multiline_string(start)
+                } else {
+                    self.lex_string(start)
                 };
+                match result {
+                    Ok(s) => TokKind::Str(s),
+                    Err(err) => {
+                        return Tok {
+                            span: err.span(),
+                            kind: TokKind::Error(err),
+                        }
+                    }
+                }
             }
-        }
 
-        // ident / keywords
-        if c.is_ascii_alphabetic() || c == '_' {
-            let mut s = String::from(c);
-            while let Some(p) = self.peek() {
-                let ch = p as char;
-                if ch.is_ascii_alphanumeric() || ch == '_' {
-                    s.push(ch);
-                    self.bump();
-                } else {
-                    break;
+            // raw string: r"..." or r#"..."# (any number of #)
+            b'r' if self.src.get(self.pos + self.hashes_ahead()) == Some(&b'"') => {
+                let hashes = self.hashes_ahead();
+                self.pos += hashes + 1;
+                match self.lex_raw_string(start, hashes) {
+                    Ok(s) => TokKind::Str(Cow::Borrowed(s)),
+                    Err(err) => TokKind::Error(err),
                 }
             }
-            let kind = match s.as_str() {
-                "let" => TokKind::KwLet,
-                "fn" => TokKind::KwFn,
-                "true" => TokKind::KwTrue,
-                "false" => TokKind::KwFalse,
-                "nil" => TokKind::KwNil,
-                "if" => TokKind::KwIf,
-                "else" => TokKind::KwElse,
-                "while" => TokKind::KwWhile,
-                "return" => TokKind::KwReturn,
-                "mut" => TokKind::KwMut,
-                "match" => TokKind::KwMatch,
-                "enum" => TokKind::KwEnum,
-                "struct" => TokKind::KwStruct,
-                "extern" => TokKind::KwExtern,
-                _ => TokKind::Ident(s),
-            };
-            return Tok {
-                kind,
-                span: self.span(start),
-            };
-        }
 
-        // fallback
+            b'0'..=b'9' => self.lex_number(b, start),
+            _ if CLASS[b as usize] & IDENT_START != 0 => self.lex_word(start),
+
+            // Anything else cannot start a token. Consume the whole (possibly
+            // multi-byte) character so the span covers it.
+            _ => {
+                let ch = (self.text.get(start..))
+                    .and_then(|rest| rest.chars().next())
+                    .unwrap_or(b as char);
+                self.pos = start + ch.len_utf8();
+                let span = self.span(start);
+                return Tok {
+                    kind: TokKind::Error(ParseError::UnexpectedChar { ch, span }),
+                    span,
+                };
+            }
+        };
         Tok {
-            kind: TokKind::Eof,
+            kind,
             span: self.span(start),
         }
     }
+
+    /// Number of `#`s at the current position.
+    fn hashes_ahead(&self) -> usize {
+        self.src[self.pos..]
+            .iter()
+            .take_while(|&&b| b == b'#')
+            .count()
+    }
 }
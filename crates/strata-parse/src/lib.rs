@@ -2,11 +2,19 @@
 #![deny(unused_must_use)]
 #![warn(clippy::dbg_macro, clippy::todo, clippy::unimplemented)]
 
+mod error;
 mod lexer;
 mod parser;
+mod semantic;
 mod token;
 
-pub use parser::parse_str;
+pub use error::ParseError;
+pub use lexer::escape_str;
+pub use parser::{
+    parse_script, parse_script_source, parse_script_source_with_options, parse_source,
+    parse_source_with_options, parse_str, parse_str_with_options, ParseOptions, MAX_NESTING_LIMIT,
+};
+pub use semantic::{semantic_token_modifiers, semantic_tokens, TokenClass, TokenModifier};
 
 #[cfg(test)]
 mod infer_smoke {
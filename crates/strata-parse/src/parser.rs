@@ -1,51 +1,196 @@
+use crate::error::ParseError;
 use crate::lexer::Lexer;
 use crate::token::{Tok, TokKind};
-use anyhow::{bail, Result};
 use strata_ast::ast::{
-    BinOp, Block, EnumDef, Expr, ExternFnDecl, Field, FieldInit, FnDecl, Ident, Item, LetDecl, Lit,
-    MatchArm, Module, Param, Pat, PatField, Path, Stmt, StructDef, TypeExpr, UnOp, Variant,
-    VariantFields,
+    AttrArg, Attribute, BinOp, Block, ConstDecl, EnumDef, Expr, ExternFnDecl, Field, FieldInit,
+    FnDecl, Ident, Item, LetDecl, Lit, MatchArm, Module, Param, Pat, PatField, Path, Stmt,
+    StructDef, TypeExpr, UnOp, UseDecl, Variant, VariantFields,
 };
-use strata_ast::span::Span;
+use strata_ast::span::{FileId, SourceDb, Span, MAX_SOURCE_LEN};
 
-/// Maximum nesting depth for blocks, ifs, whiles, and nested expressions.
+type Result<T> = std::result::Result<T, ParseError>;
+
+/// Default maximum nesting depth for expressions, blocks, patterns, and types.
 /// This prevents stack overflow from deeply nested input.
 const MAX_NESTING_DEPTH: u32 = 512;
 
-pub fn parse_str(_file: &str, src: &str) -> Result<Module> {
-    let mut p = Parser::new(src);
-    p.parse_module()
+/// Default maximum number of tokens in a single source file.
+const MAX_TOKEN_COUNT: usize = 200_000;
+
+/// Stack reserved per level of nesting on the parser thread. Generous enough
+/// for unoptimized builds, where a level of block nesting costs ~10 KiB.
+const STACK_BYTES_PER_LEVEL: usize = 32 * 1024;
+
+/// Fixed stack reserved on the parser thread on top of the per-level budget.
+const BASE_STACK_BYTES: usize = 1024 * 1024;
+
+/// Most stack the parser thread reserves.
+const MAX_STACK_BYTES: usize = 64 * 1024 * 1024;
+
+/// Deepest nesting limit the parser thread's stack can hold; a larger
+/// `max_nesting_depth` is treated as this (2016).
+pub const MAX_NESTING_LIMIT: u32 =
+    ((MAX_STACK_BYTES - BASE_STACK_BYTES) / STACK_BYTES_PER_LEVEL) as u32;
+
+/// Resource limits applied while parsing untrusted source, and the features
+/// `#[cfg(feature = "...")]` attributes test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Maximum recursion depth through expressions, blocks, patterns, and
+    /// types before `ParseError::TooDeep` is reported. Values above
+    /// [`MAX_NESTING_LIMIT`] are treated as `MAX_NESTING_LIMIT`.
+    pub max_nesting_depth: u32,
+    /// Maximum number of tokens the lexer will produce.
+    pub max_tokens: usize,
+    /// Longest source, in bytes, that will be parsed. Values above
+    /// `MAX_SOURCE_LEN` (4 GiB - 1, the most a `Span` can address) are
+    /// treated as `MAX_SOURCE_LEN`.
+    pub max_source_bytes: usize,
+    /// Features enabled for conditional compilation; an item under
+    /// `#[cfg(feature = "x")]` is kept only if `x` is listed.
+    pub features: Vec<String>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: MAX_NESTING_DEPTH,
+            max_tokens: MAX_TOKEN_COUNT,
+            max_source_bytes: MAX_SOURCE_LEN,
+            features: Vec::new(),
+        }
+    }
+}
+
+pub fn parse_str(file: &str, src: &str) -> Result<Module> {
+    parse_str_with_options(file, src, &ParseOptions::default())
+}
+
+/// Parse with explicit resource limits.
+///
+/// Parsing runs on a dedicated thread whose stack is sized from
+/// `max_nesting_depth`, so the depth limit triggers before the stack runs
+/// out regardless of the caller's own stack size. If the thread cannot be
+/// started, parsing fails with `ParseError::NoStack`.
+pub fn parse_str_with_options(_file: &str, src: &str, opts: &ParseOptions) -> Result<Module> {
+    run_parser(src, FileId::default(), opts, |p| p.parse_module())
+}
+
+/// Parse `file` of `db`. Every span in the module, and in any error,
+/// carries `file`, so diagnostics render against the right source. An id
+/// `db` doesn't hold parses as an empty file.
+pub fn parse_source(db: &SourceDb, file: FileId) -> Result<Module> {
+    parse_source_with_options(db, file, &ParseOptions::default())
+}
+
+/// [`parse_source`] with explicit limits and features.
+pub fn parse_source_with_options(
+    db: &SourceDb,
+    file: FileId,
+    opts: &ParseOptions,
+) -> Result<Module> {
+    let src = db.source(file).unwrap_or_default();
+    run_parser(src, file, opts, |p| p.parse_module())
+}
+
+/// [`parse_source`] for a script; see [`parse_script`].
+pub fn parse_script_source(db: &SourceDb, file: FileId) -> Result<Module> {
+    parse_script_source_with_options(db, file, &ParseOptions::default())
+}
+
+/// [`parse_script_source`] with explicit limits and features.
+pub fn parse_script_source_with_options(
+    db: &SourceDb,
+    file: FileId,
+    opts: &ParseOptions,
+) -> Result<Module> {
+    let src = db.source(file).unwrap_or_default();
+    run_parser(src, file, opts, |p| p.parse_script())
+}
+
+/// Run `parse` on a dedicated thread sized for `opts`
+fn run_parser(
+    src: &str,
+    file: FileId,
+    opts: &ParseOptions,
+    parse: impl Fn(&mut Parser) -> Result<Module> + Sync,
+) -> Result<Module> {
+    let limit = opts.max_source_bytes.min(MAX_SOURCE_LEN);
+    if src.len() > limit {
+        return Err(ParseError::SourceTooLarge {
+            len: src.len(),
+            limit,
+            span: Span::clamped(file, 0, 0),
+        });
+    }
+    let depth = opts.max_nesting_depth.min(MAX_NESTING_LIMIT);
+    let stack_size = BASE_STACK_BYTES + depth as usize * STACK_BYTES_PER_LEVEL;
+    std::thread::scope(|scope| {
+        let spawned = std::thread::Builder::new()
+            .name("strata-parse".to_string())
+            .stack_size(stack_size)
+            .spawn_scoped(scope, || parse(&mut Parser::new(src, file, opts)));
+        // The caller's own stack may be too small for the depth limit, so
+        // parsing there instead could overflow
+        match spawned {
+            Ok(handle) => handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic)),
+            Err(e) => Err(ParseError::NoStack {
+                bytes: stack_size,
+                reason: e.to_string(),
+                span: Span::clamped(file, 0, 0),
+            }),
+        }
+    })
+}
+
+/// Parse a script: top-level statements and a final expression, run in
+/// order as the body of a synthesized `fn main()`, alongside ordinary items.
+/// `let` is a statement here, not a global, so functions cannot see it.
+pub fn parse_script(_file: &str, src: &str) -> Result<Module> {
+    let opts = ParseOptions::default();
+    run_parser(src, FileId::default(), &opts, |p| p.parse_script())
 }
 
 struct Parser<'a> {
     lex: Lexer<'a>,
-    cur: Tok,
-    nxt: Tok,
-    /// Current nesting depth for blocks/ifs/whiles/exprs
+    /// File every span is stamped with
+    file: FileId,
+    cur: Tok<'a>,
+    nxt: Tok<'a>,
+    /// Current nesting depth for exprs/blocks/patterns/types
     depth: u32,
+    /// Limit for `depth`
+    max_depth: u32,
+    /// Features `#[cfg(feature = "...")]` is true for
+    features: Vec<String>,
 }
 
 impl<'a> Parser<'a> {
-    fn new(src: &'a str) -> Self {
-        let mut lex = Lexer::new(src);
+    fn new(src: &'a str, file: FileId, opts: &ParseOptions) -> Self {
+        let mut lex = Lexer::new(src, file, opts.max_tokens);
         let cur = lex.next_tok();
         let nxt = lex.next_tok();
         Self {
             lex,
+            file,
             cur,
             nxt,
             depth: 0,
+            max_depth: opts.max_nesting_depth.min(MAX_NESTING_LIMIT),
+            features: opts.features.clone(),
         }
     }
 
     /// Increment depth and check limit
     fn enter_nesting(&mut self) -> Result<()> {
         self.depth += 1;
-        if self.depth > MAX_NESTING_DEPTH {
-            bail!(
-                "maximum nesting depth exceeded (limit: {})",
-                MAX_NESTING_DEPTH
-            );
+        if self.depth > self.max_depth {
+            return Err(ParseError::TooDeep {
+                limit: self.max_depth,
+                span: self.cur.span,
+            });
         }
         Ok(())
     }
@@ -61,33 +206,64 @@ impl<'a> Parser<'a> {
 
     /// Check if current token is a lexer error and surface it
     fn check_lex_error(&self) -> Result<()> {
-        if let TokKind::Error(msg) = &self.cur.kind {
-            bail!("Lexer error at {:?}: {}", self.cur.span, msg);
+        if let TokKind::Error(err) = &self.cur.kind {
+            return Err(err.clone());
         }
         Ok(())
     }
 
+    /// Error for the current token when one of `expected` was required.
+    /// A lexer error token is surfaced as-is.
+    fn unexpected(&self, expected: &[&str]) -> ParseError {
+        if let TokKind::Error(err) = &self.cur.kind {
+            return err.clone();
+        }
+        ParseError::UnexpectedToken {
+            found: self.cur.kind.describe(),
+            expected: expected.iter().map(|e| e.to_string()).collect(),
+            span: self.cur.span,
+        }
+    }
+
+    fn invalid(&self, message: &str, span: Span) -> ParseError {
+        ParseError::InvalidSyntax {
+            message: message.to_string(),
+            span,
+        }
+    }
+
     fn at(&self, k: &TokKind) -> bool {
         std::mem::discriminant(&self.cur.kind) == std::mem::discriminant(k)
     }
 
-    fn expect(&mut self, k: TokKind) -> Result<Tok> {
-        // Surface lexer errors immediately with proper span
-        if let TokKind::Error(msg) = &self.cur.kind {
-            bail!("Lexer error at {:?}: {}", self.cur.span, msg);
+    /// Parse `item (, item)* ,?` up to, but not consuming, `close`.
+    /// A trailing comma is always accepted.
+    fn parse_comma_separated<T>(
+        &mut self,
+        close: &TokKind,
+        mut item: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        while !self.at(close) {
+            items.push(item(self)?);
+            if !matches!(self.cur.kind, TokKind::Comma) {
+                break;
+            }
+            self.bump();
         }
+        Ok(items)
+    }
+
+    fn expect(&mut self, k: TokKind) -> Result<Tok<'a>> {
+        // Surface lexer errors immediately with proper span
+        self.check_lex_error()?;
 
         if self.at(&k) {
             let t = self.cur.clone();
             self.bump();
             Ok(t)
         } else {
-            bail!(
-                "expected {:?}, found {:?} at {:?}",
-                k,
-                self.cur.kind,
-                self.cur.span
-            )
+            Err(self.unexpected(&[&k.describe()]))
         }
     }
 
@@ -99,13 +275,96 @@ impl<'a> Parser<'a> {
         while !matches!(self.cur.kind, TokKind::Eof) {
             // Surface any lexer errors immediately
             self.check_lex_error()?;
-            items.push(self.parse_item()?);
+            items.extend(self.parse_attributed_item()?);
+        }
+        Ok(Module {
+            items,
+            span: Span {
+                start,
+                end: self.cur.span.end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// Parse a script: items, with everything between them gathered in
+    /// order into the body of a synthesized `fn main()`. A final expression
+    /// without `;` is the body's value.
+    fn parse_script(&mut self) -> Result<Module> {
+        let start = self.cur.span.start;
+        let mut items = Vec::new();
+        let mut stmts = Vec::new();
+        let mut tail = None;
+        let mut body_span: Option<Span> = None;
+        while !matches!(self.cur.kind, TokKind::Eof) {
+            self.check_lex_error()?;
+            match self.cur.kind {
+                TokKind::KwFn
+                | TokKind::KwConst
+                | TokKind::KwExtern
+                | TokKind::KwStruct
+                | TokKind::KwEnum
+                | TokKind::KwLinear
+                | TokKind::KwTransparent
+                | TokKind::KwBench
+                | TokKind::KwPure
+                | TokKind::KwPub
+                | TokKind::KwUse
+                | TokKind::Hash => items.extend(self.parse_attributed_item()?),
+                _ => {
+                    let stmt_start = self.cur.span.start;
+                    if let Some(expr) = self.parse_body_stmt(&mut stmts, &TokKind::Eof)? {
+                        tail = Some(Box::new(expr));
+                    }
+                    let end = match (&tail, stmts.last()) {
+                        (Some(expr), _) => node_end(expr),
+                        (None, Some(stmt)) => stmt_end(stmt),
+                        (None, None) => stmt_start,
+                    };
+                    body_span = Some(Span {
+                        start: body_span.map_or(stmt_start, |s| s.start),
+                        end,
+                        file: self.file,
+                    });
+                }
+            }
+        }
+        if let Some(span) = body_span {
+            if let Some(main) = items.iter().find_map(|item| match item {
+                Item::Fn(d) if d.name.text == "main" => Some(&d.name),
+                _ => None,
+            }) {
+                return Err(self.invalid(
+                    "a script's top-level statements are its `main`; it cannot also declare one",
+                    main.span,
+                ));
+            }
+            items.push(Item::Fn(FnDecl {
+                name: Ident {
+                    text: "main".to_string(),
+                    span: Span {
+                        start: span.start,
+                        end: span.start,
+                        file: self.file,
+                    },
+                },
+                params: Vec::new(),
+                ret_ty: None,
+                effects: None,
+                public: false,
+                bench: false,
+                pure: false,
+                body: Block { stmts, tail, span },
+                attrs: Vec::new(),
+                span,
+            }));
         }
         Ok(Module {
             items,
             span: Span {
                 start,
                 end: self.cur.span.end,
+                file: self.file,
             },
         })
     }
@@ -113,11 +372,350 @@ impl<'a> Parser<'a> {
     fn parse_item(&mut self) -> Result<Item> {
         match self.cur.kind {
             TokKind::KwLet => Ok(Item::Let(self.parse_let()?)),
+            TokKind::KwConst => Ok(Item::Const(self.parse_const()?)),
             TokKind::KwExtern => Ok(Item::ExternFn(self.parse_extern_fn()?)),
             TokKind::KwFn => Ok(Item::Fn(self.parse_fn_decl()?)),
             TokKind::KwStruct => Ok(Item::Struct(self.parse_struct_def()?)),
             TokKind::KwEnum => Ok(Item::Enum(self.parse_enum_def()?)),
-            _ => bail!("unexpected token at top level: {:?}", self.cur.kind),
+            TokKind::KwLinear => self.parse_linear_adt(),
+            TokKind::KwTransparent => Ok(Item::Struct(self.parse_transparent_struct()?)),
+            TokKind::KwBench => Ok(Item::Fn(self.parse_bench_fn()?)),
+            TokKind::KwPure => Ok(Item::Fn(self.parse_pure_fn()?)),
+            TokKind::KwPub => self.parse_pub_item(),
+            TokKind::KwUse => Ok(Item::Use(self.parse_use()?)),
+            _ => Err(self.unexpected(&[
+                "`fn`",
+                "`let`",
+                "`const`",
+                "`struct`",
+                "`enum`",
+                "`extern`",
+                "`linear`",
+                "`transparent`",
+                "`bench`",
+                "`pure`",
+                "`pub`",
+                "`use`",
+            ])),
+        }
+    }
+
+    /// Parse an item with the `#[...]` attributes before it, or `None` if a
+    /// `cfg` attribute is false for the enabled features
+    fn parse_attributed_item(&mut self) -> Result<Option<Item>> {
+        let mut attrs = Vec::new();
+        let mut enabled = true;
+        while matches!(self.cur.kind, TokKind::Hash) {
+            let attr = self.parse_attribute()?;
+            if attr.name.text == "cfg" {
+                enabled &= self.cfg_holds(&attr)?;
+            }
+            attrs.push(attr);
+        }
+        let mut item = self.parse_item()?;
+        if let Some(attr) = attrs.iter().find(|a| a.name.text == "transparent") {
+            match &mut item {
+                Item::Struct(def) if attr.args.is_empty() && def.tuple && def.fields.len() == 1 => {
+                    def.transparent = true
+                }
+                _ => {
+                    return Err(self.invalid(
+                        "`#[transparent]` applies to a struct wrapping exactly one field: \
+                         `#[transparent] struct Name(T);`",
+                        attr.span,
+                    ))
+                }
+            }
+        }
+        *item.attrs_mut() = attrs;
+        Ok(enabled.then_some(item))
+    }
+
+    /// Parse `#[name]` or `#[name(args)]`
+    fn parse_attribute(&mut self) -> Result<Attribute> {
+        let hash = self.expect(TokKind::Hash)?;
+        self.expect(TokKind::LBracket)?;
+        // `transparent` is a keyword, but also names an attribute
+        let name = if matches!(self.cur.kind, TokKind::KwTransparent) {
+            let name = Ident {
+                text: "transparent".to_string(),
+                span: self.cur.span,
+            };
+            self.bump();
+            name
+        } else {
+            self.parse_ident()?
+        };
+        let args = if matches!(self.cur.kind, TokKind::LParen) {
+            self.parse_attr_args()?.0
+        } else {
+            Vec::new()
+        };
+        let close = self.expect(TokKind::RBracket)?;
+        Ok(Attribute {
+            name,
+            args,
+            span: Span {
+                start: hash.span.start,
+                end: close.span.end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// Parse `(arg, ..)`, returning the arguments and the end of the `)`
+    fn parse_attr_args(&mut self) -> Result<(Vec<AttrArg>, u32)> {
+        self.expect(TokKind::LParen)?;
+        let args = self.parse_comma_separated(&TokKind::RParen, Self::parse_attr_arg)?;
+        let close = self.expect(TokKind::RParen)?;
+        Ok((args, close.span.end))
+    }
+
+    /// Parse `word`, `"string"`, `name = "value"`, or `name(args)`
+    fn parse_attr_arg(&mut self) -> Result<AttrArg> {
+        self.enter_nesting()?;
+        self.check_lex_error()?;
+        let arg = match &self.cur.kind {
+            TokKind::Str(value) => {
+                let arg = AttrArg::Str {
+                    value: value.to_string(),
+                    span: self.cur.span,
+                };
+                self.bump();
+                arg
+            }
+            TokKind::Ident(_) => {
+                let name = self.parse_ident()?;
+                match self.cur.kind {
+                    TokKind::Eq => {
+                        self.bump();
+                        self.check_lex_error()?;
+                        let TokKind::Str(value) = &self.cur.kind else {
+                            return Err(self.unexpected(&["string"]));
+                        };
+                        let arg = AttrArg::NameValue {
+                            span: Span {
+                                start: name.span.start,
+                                end: self.cur.span.end,
+                                file: self.file,
+                            },
+                            name,
+                            value: value.to_string(),
+                        };
+                        self.bump();
+                        arg
+                    }
+                    TokKind::LParen => {
+                        let (args, end) = self.parse_attr_args()?;
+                        AttrArg::List {
+                            span: Span {
+                                start: name.span.start,
+                                end,
+                                file: self.file,
+                            },
+                            name,
+                            args,
+                        }
+                    }
+                    _ => AttrArg::Word(name),
+                }
+            }
+            _ => return Err(self.unexpected(&["identifier", "string"])),
+        };
+        self.exit_nesting();
+        Ok(arg)
+    }
+
+    /// Whether a `#[cfg(predicate)]` attribute holds for the enabled features
+    fn cfg_holds(&self, attr: &Attribute) -> Result<bool> {
+        match attr.args.as_slice() {
+            [pred] => self.cfg_predicate_holds(pred),
+            _ => Err(self.invalid("`cfg` takes one predicate", attr.span)),
+        }
+    }
+
+    /// Evaluate `feature = "name"`, `not(p)`, `all(p, ..)`, or `any(p, ..)`
+    fn cfg_predicate_holds(&self, pred: &AttrArg) -> Result<bool> {
+        let unknown = |name: &str, span| {
+            self.invalid(
+                &format!(
+                    "unknown cfg predicate `{}`; expected `feature`, `not`, `all`, or `any`",
+                    name
+                ),
+                span,
+            )
+        };
+        match pred {
+            AttrArg::NameValue { name, value, .. } if name.text == "feature" => {
+                Ok(self.features.iter().any(|f| f == value))
+            }
+            AttrArg::List { name, args, .. } => match name.text.as_str() {
+                "not" => match args.as_slice() {
+                    [p] => Ok(!self.cfg_predicate_holds(p)?),
+                    _ => Err(self.invalid("`not` takes one predicate", name.span)),
+                },
+                "all" | "any" => {
+                    let holds = args
+                        .iter()
+                        .map(|p| self.cfg_predicate_holds(p))
+                        .collect::<Result<Vec<bool>>>()?;
+                    Ok(if name.text == "all" {
+                        holds.iter().all(|h| *h)
+                    } else {
+                        holds.iter().any(|h| *h)
+                    })
+                }
+                other => Err(unknown(other, name.span)),
+            },
+            AttrArg::NameValue { name, .. } | AttrArg::Word(name) => {
+                Err(unknown(&name.text, name.span))
+            }
+            AttrArg::Str { span, .. } => Err(self.invalid(
+                "expected a cfg predicate such as `feature = \"name\"`",
+                *span,
+            )),
+        }
+    }
+
+    /// Parse `use Enum::*;` or `use Enum::Variant;`
+    fn parse_use(&mut self) -> Result<UseDecl> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwUse)?;
+        let enum_name = self.parse_ident()?;
+        self.expect(TokKind::ColonColon)?;
+        let variant = match self.cur.kind {
+            TokKind::Star => {
+                self.bump();
+                None
+            }
+            TokKind::Ident(_) => Some(self.parse_ident()?),
+            _ => return Err(self.unexpected(&["`*`", "identifier"])),
+        };
+        let semi = self.expect(TokKind::Semicolon)?;
+        Ok(UseDecl {
+            enum_name,
+            variant,
+            attrs: Vec::new(),
+            span: Span {
+                start,
+                end: semi.span.end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// Parse `pub` followed by a function, struct, or enum definition
+    fn parse_pub_item(&mut self) -> Result<Item> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwPub)?;
+        let mut item = match self.cur.kind {
+            TokKind::KwFn => Item::Fn(self.parse_fn_decl()?),
+            TokKind::KwPure => Item::Fn(self.parse_pure_fn()?),
+            TokKind::KwStruct => Item::Struct(self.parse_struct_def()?),
+            TokKind::KwEnum => Item::Enum(self.parse_enum_def()?),
+            TokKind::KwLinear => self.parse_linear_adt()?,
+            TokKind::KwTransparent => Item::Struct(self.parse_transparent_struct()?),
+            _ => {
+                return Err(self.unexpected(&[
+                    "`fn`",
+                    "`pure`",
+                    "`struct`",
+                    "`enum`",
+                    "`linear`",
+                    "`transparent`",
+                ]))
+            }
+        };
+        match &mut item {
+            Item::Fn(d) => {
+                d.public = true;
+                d.span.start = start;
+            }
+            Item::Struct(d) => {
+                d.public = true;
+                d.span.start = start;
+            }
+            Item::Enum(d) => {
+                d.public = true;
+                d.span.start = start;
+            }
+            _ => unreachable!("only definitions follow `pub`"),
+        }
+        Ok(item)
+    }
+
+    /// Parse `transparent struct Name(T);`, which must wrap exactly one field
+    fn parse_transparent_struct(&mut self) -> Result<StructDef> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwTransparent)?;
+        if !matches!(self.cur.kind, TokKind::KwStruct) {
+            return Err(self.unexpected(&["`struct`"]));
+        }
+        let mut def = self.parse_struct_def()?;
+        def.span.start = start;
+        if !def.tuple || def.fields.len() != 1 {
+            return Err(self.invalid(
+                "a `transparent` struct wraps exactly one field: `transparent struct Name(T);`",
+                def.span,
+            ));
+        }
+        def.transparent = true;
+        Ok(def)
+    }
+
+    /// Parse `bench fn name() { ... }`, which takes no parameters
+    fn parse_bench_fn(&mut self) -> Result<FnDecl> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwBench)?;
+        if !matches!(self.cur.kind, TokKind::KwFn) {
+            return Err(self.unexpected(&["`fn`"]));
+        }
+        let mut decl = self.parse_fn_decl()?;
+        decl.span.start = start;
+        if let Some(param) = decl.params.first() {
+            return Err(self.invalid(
+                &format!(
+                    "benchmark '{}' cannot take parameters: `bench fn {}() {{ ... }}`",
+                    decl.name.text, decl.name.text
+                ),
+                param.span,
+            ));
+        }
+        decl.bench = true;
+        Ok(decl)
+    }
+
+    /// Parse `pure fn name(..) { ... }`
+    fn parse_pure_fn(&mut self) -> Result<FnDecl> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwPure)?;
+        if !matches!(self.cur.kind, TokKind::KwFn) {
+            return Err(self.unexpected(&["`fn`"]));
+        }
+        let mut decl = self.parse_fn_decl()?;
+        decl.span.start = start;
+        decl.pure = true;
+        Ok(decl)
+    }
+
+    /// Parse `linear struct ...` or `linear enum ...`
+    fn parse_linear_adt(&mut self) -> Result<Item> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwLinear)?;
+        match self.cur.kind {
+            TokKind::KwStruct => {
+                let mut def = self.parse_struct_def()?;
+                def.linear = true;
+                def.span.start = start;
+                Ok(Item::Struct(def))
+            }
+            TokKind::KwEnum => {
+                let mut def = self.parse_enum_def()?;
+                def.linear = true;
+                def.span.start = start;
+                Ok(Item::Enum(def))
+            }
+            _ => Err(self.unexpected(&["`struct`", "`enum`"])),
         }
     }
 
@@ -126,13 +724,13 @@ impl<'a> Parser<'a> {
             TokKind::Ident(s) => {
                 let span = self.cur.span;
                 let id = Ident {
-                    text: s.clone(),
+                    text: s.to_string(),
                     span,
                 };
                 self.bump();
                 Ok(id)
             }
-            _ => bail!("expected identifier, found {:?}", self.cur.kind),
+            _ => Err(self.unexpected(&["identifier"])),
         }
     }
 
@@ -153,9 +751,41 @@ impl<'a> Parser<'a> {
             name,
             ty,
             value,
+            attrs: Vec::new(),
+            span: Span {
+                start,
+                end: semi.span.end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// Parse `const NAME: Type = value;`
+    fn parse_const(&mut self) -> Result<ConstDecl> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwConst)?;
+        let name = self.parse_ident()?;
+        if !matches!(self.cur.kind, TokKind::Colon) {
+            let message = format!(
+                "a `const` needs a type annotation: `const {}: T = ...;`",
+                name.text
+            );
+            return Err(self.invalid(&message, self.cur.span));
+        }
+        self.bump();
+        let ty = self.parse_type()?;
+        self.expect(TokKind::Eq)?;
+        let value = self.parse_expr_bp(0)?;
+        let semi = self.expect(TokKind::Semicolon)?;
+        Ok(ConstDecl {
+            name,
+            ty,
+            value,
+            attrs: Vec::new(),
             span: Span {
                 start,
                 end: semi.span.end,
+                file: self.file,
             },
         })
     }
@@ -190,10 +820,15 @@ impl<'a> Parser<'a> {
             params,
             ret_ty,
             effects,
+            public: false,
+            bench: false,
+            pure: false,
             body,
+            attrs: Vec::new(),
             span: Span {
                 start,
                 end: body_end,
+                file: self.file,
             },
         })
     }
@@ -229,9 +864,11 @@ impl<'a> Parser<'a> {
             params,
             ret_ty,
             effects,
+            attrs: Vec::new(),
             span: Span {
                 start,
                 end: semi.span.end,
+                file: self.file,
             },
         })
     }
@@ -247,23 +884,14 @@ impl<'a> Parser<'a> {
         self.bump(); // consume &
         self.expect(TokKind::LBrace)?;
 
-        let mut effects = Vec::new();
-        if !matches!(self.cur.kind, TokKind::RBrace) {
-            effects.push(self.parse_ident()?);
-            while matches!(self.cur.kind, TokKind::Comma) {
-                self.bump(); // consume comma
-                if matches!(self.cur.kind, TokKind::RBrace) {
-                    break; // trailing comma
-                }
-                effects.push(self.parse_ident()?);
-            }
-        }
+        let effects = self.parse_comma_separated(&TokKind::RBrace, Self::parse_ident)?;
 
         self.expect(TokKind::RBrace)?;
         Ok(Some(effects))
     }
 
     /// Parse a struct definition: `struct Name<T, U> { field: Type, ... }`
+    /// or a tuple struct `struct Name<T>(Type, ...);`
     fn parse_struct_def(&mut self) -> Result<StructDef> {
         let start = self.cur.span.start;
         self.expect(TokKind::KwStruct)?;
@@ -272,18 +900,56 @@ impl<'a> Parser<'a> {
         // Parse optional type parameters: <T, U>
         let type_params = self.parse_type_params()?;
 
-        // Parse fields: { field: Type, ... }
-        self.expect(TokKind::LBrace)?;
-        let fields = self.parse_struct_fields()?;
-        let end_tok = self.expect(TokKind::RBrace)?;
+        // Tuple struct: (Type, ...); its fields are named by position
+        let tuple = matches!(self.cur.kind, TokKind::LParen);
+        let (fields, end_tok) = if tuple {
+            self.bump(); // consume '('
+            let tys = self.parse_comma_separated(&TokKind::RParen, Self::parse_type)?;
+            let rparen = self.expect(TokKind::RParen)?;
+            if tys.is_empty() {
+                return Err(self.invalid(
+                    "a tuple struct needs at least one field",
+                    Span {
+                        start,
+                        end: rparen.span.end,
+                        file: self.file,
+                    },
+                ));
+            }
+            let fields = tys
+                .into_iter()
+                .enumerate()
+                .map(|(i, ty)| Field {
+                    name: Ident {
+                        text: i.to_string(),
+                        span: ty.span(),
+                    },
+                    span: ty.span(),
+                    ty,
+                    default: None,
+                })
+                .collect();
+            (fields, self.expect(TokKind::Semicolon)?)
+        } else {
+            // Parse fields: { field: Type, ... }
+            self.expect(TokKind::LBrace)?;
+            let fields = self.parse_struct_fields()?;
+            (fields, self.expect(TokKind::RBrace)?)
+        };
 
         Ok(StructDef {
             name,
             type_params,
             fields,
+            linear: false,
+            tuple,
+            transparent: false,
+            public: false,
+            attrs: Vec::new(),
             span: Span {
                 start,
                 end: end_tok.span.end,
+                file: self.file,
             },
         })
     }
@@ -295,19 +961,13 @@ impl<'a> Parser<'a> {
         }
         self.bump(); // consume '<'
 
-        let mut params = Vec::new();
-        if !matches!(self.cur.kind, TokKind::Gt) {
-            params.push(self.parse_ident()?);
-            while matches!(self.cur.kind, TokKind::Comma) {
-                self.bump();
-                params.push(self.parse_ident()?);
-            }
-        }
+        let params = self.parse_comma_separated(&TokKind::Gt, Self::parse_ident)?;
         self.expect(TokKind::Gt)?;
         Ok(params)
     }
 
-    /// Parse struct fields: `field: Type, ...`
+    /// Parse struct fields: `field: Type, ...`, each with an optional
+    /// default value: `field: Type = expr`
     fn parse_struct_fields(&mut self) -> Result<Vec<Field>> {
         let mut fields = Vec::new();
 
@@ -316,14 +976,25 @@ impl<'a> Parser<'a> {
             let name = self.parse_ident()?;
             self.expect(TokKind::Colon)?;
             let ty = self.parse_type()?;
-            let ty_end = ty.span().end;
+            let mut end = ty.span().end;
+
+            let default = if matches!(self.cur.kind, TokKind::Eq) {
+                self.bump(); // consume =
+                let value = self.parse_expr_bp(0)?;
+                end = value.span().end;
+                Some(value)
+            } else {
+                None
+            };
 
             fields.push(Field {
                 name,
                 ty,
+                default,
                 span: Span {
                     start: field_start,
-                    end: ty_end,
+                    end,
+                    file: self.file,
                 },
             });
 
@@ -356,13 +1027,36 @@ impl<'a> Parser<'a> {
             name,
             type_params,
             variants,
+            linear: false,
+            public: false,
+            attrs: Vec::new(),
             span: Span {
                 start,
                 end: end_tok.span.end,
+                file: self.file,
             },
         })
     }
 
+    /// Parse an enum discriminant: an Int literal, optionally negated
+    fn parse_discriminant(&mut self) -> Result<(i64, Span)> {
+        let start = self.cur.span.start;
+        let negative = matches!(self.cur.kind, TokKind::Minus);
+        if negative {
+            self.bump();
+        }
+        let TokKind::Int(n) = self.cur.kind else {
+            return Err(self.unexpected(&["integer"]));
+        };
+        let span = Span {
+            start,
+            end: self.cur.span.end,
+            file: self.file,
+        };
+        self.bump();
+        Ok((if negative { -n } else { n }, span))
+    }
+
     /// Parse enum variants: `Variant1, Variant2(T), ...`
     fn parse_enum_variants(&mut self) -> Result<Vec<Variant>> {
         let mut variants = Vec::new();
@@ -374,26 +1068,30 @@ impl<'a> Parser<'a> {
             // Check for tuple fields: Variant(T, U)
             let (fields, var_end) = if matches!(self.cur.kind, TokKind::LParen) {
                 self.bump(); // consume '('
-                let mut tys = Vec::new();
-                if !matches!(self.cur.kind, TokKind::RParen) {
-                    tys.push(self.parse_type()?);
-                    while matches!(self.cur.kind, TokKind::Comma) {
-                        self.bump();
-                        tys.push(self.parse_type()?);
-                    }
-                }
+                let tys = self.parse_comma_separated(&TokKind::RParen, Self::parse_type)?;
                 let rparen = self.expect(TokKind::RParen)?;
                 (VariantFields::Tuple(tys), rparen.span.end)
             } else {
                 (VariantFields::Unit, name.span.end)
             };
 
+            // Optional discriminant: Variant = 1
+            let (discriminant, var_end) = if matches!(self.cur.kind, TokKind::Eq) {
+                self.bump(); // consume '='
+                let (value, span) = self.parse_discriminant()?;
+                (Some((value, span)), span.end)
+            } else {
+                (None, var_end)
+            };
+
             variants.push(Variant {
                 name,
                 fields,
+                discriminant,
                 span: Span {
                     start: var_start,
                     end: var_end,
+                    file: self.file,
                 },
             });
 
@@ -409,6 +1107,13 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_type(&mut self) -> Result<TypeExpr> {
+        self.enter_nesting()?;
+        let result = self.parse_type_inner();
+        self.exit_nesting();
+        result
+    }
+
+    fn parse_type_inner(&mut self) -> Result<TypeExpr> {
         let start = self.cur.span.start;
 
         // Check for reference type: &T (but not effect annotation: & {Fs})
@@ -417,7 +1122,14 @@ impl<'a> Parser<'a> {
             self.bump(); // consume &
             let inner = self.parse_type()?;
             let end = inner.span().end;
-            return Ok(TypeExpr::Ref(Box::new(inner), Span { start, end }));
+            return Ok(TypeExpr::Ref(
+                Box::new(inner),
+                Span {
+                    start,
+                    end,
+                    file: self.file,
+                },
+            ));
         }
 
         // Check if it's a function type: fn(T1, T2) -> R
@@ -425,15 +1137,7 @@ impl<'a> Parser<'a> {
             self.bump(); // consume 'fn'
             self.expect(TokKind::LParen)?;
 
-            let mut params = Vec::new();
-            if !matches!(self.cur.kind, TokKind::RParen) {
-                params.push(self.parse_type()?);
-                while matches!(self.cur.kind, TokKind::Comma) {
-                    self.bump();
-                    params.push(self.parse_type()?);
-                }
-            }
-
+            let params = self.parse_comma_separated(&TokKind::RParen, Self::parse_type)?;
             self.expect(TokKind::RParen)?;
             self.expect(TokKind::Arrow)?;
             let ret = Box::new(self.parse_type()?);
@@ -451,7 +1155,11 @@ impl<'a> Parser<'a> {
                 params,
                 ret,
                 effects,
-                span: Span { start, end },
+                span: Span {
+                    start,
+                    end,
+                    file: self.file,
+                },
             });
         }
 
@@ -467,6 +1175,7 @@ impl<'a> Parser<'a> {
                     Span {
                         start,
                         end: end_tok.span.end,
+                        file: self.file,
                     },
                 ));
             }
@@ -498,10 +1207,33 @@ impl<'a> Parser<'a> {
                 Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             ));
         }
 
+        // Record type: {x: Int, y: Int}
+        if matches!(self.cur.kind, TokKind::LBrace) {
+            self.bump(); // consume '{'
+            let fields = self.parse_struct_fields()?;
+            let end_tok = self.expect(TokKind::RBrace)?;
+            let span = Span {
+                start,
+                end: end_tok.span.end,
+                file: self.file,
+            };
+            if fields.is_empty() {
+                return Err(self.invalid("a record type needs at least one field", span));
+            }
+            if let Some(field) = fields.iter().find(|f| f.default.is_some()) {
+                return Err(self.invalid(
+                    "a record type field can't have a default value; only struct fields can",
+                    field.span,
+                ));
+            }
+            return Ok(TypeExpr::Record(fields, span));
+        }
+
         // Otherwise, it's a path type (possibly with generic args): Int, Option<T>, Foo::Bar<A, B>
         // Grammar: Ident ('::' Ident)* ('<' Type (',' Type)* '>')?
         let mut segs = vec![self.parse_ident()?];
@@ -515,14 +1247,7 @@ impl<'a> Parser<'a> {
         // Check for generic type arguments: <T, U>
         if matches!(self.cur.kind, TokKind::Lt) {
             self.bump(); // consume '<'
-            let mut args = Vec::new();
-            if !matches!(self.cur.kind, TokKind::Gt) {
-                args.push(self.parse_type()?);
-                while matches!(self.cur.kind, TokKind::Comma) {
-                    self.bump();
-                    args.push(self.parse_type()?);
-                }
-            }
+            let args = self.parse_comma_separated(&TokKind::Gt, Self::parse_type)?;
             let end_tok = self.expect(TokKind::Gt)?;
 
             return Ok(TypeExpr::App {
@@ -531,6 +1256,7 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             });
         }
@@ -543,37 +1269,27 @@ impl<'a> Parser<'a> {
             Span {
                 start,
                 end: last_seg_end,
+                file: self.file,
             },
         ))
     }
 
     fn parse_params(&mut self) -> Result<Vec<Param>> {
-        let mut params = Vec::new();
-
-        // Empty param list: ()
-        if matches!(self.cur.kind, TokKind::RParen) {
-            return Ok(params);
-        }
-
-        // Parse first parameter
-        params.push(self.parse_param()?);
-
-        // Parse remaining parameters: , param
-        while matches!(self.cur.kind, TokKind::Comma) {
-            self.bump(); // consume comma
-            params.push(self.parse_param()?);
-        }
-
-        Ok(params)
+        self.parse_comma_separated(&TokKind::RParen, Self::parse_param)
     }
 
     fn parse_param(&mut self) -> Result<Param> {
         let start = self.cur.span.start;
         let name = self.parse_ident()?;
 
-        // Optional type annotation: : Type
+        // Optional type annotation: : Type, or : ...Type for a variadic
+        let mut variadic = false;
         let (ty, end) = if matches!(self.cur.kind, TokKind::Colon) {
             self.bump(); // consume :
+            if matches!(self.cur.kind, TokKind::DotDotDot) {
+                self.bump(); // consume ...
+                variadic = true;
+            }
             let type_expr = self.parse_type()?;
             let type_end = type_expr.span().end;
             (Some(type_expr), type_end)
@@ -581,10 +1297,26 @@ impl<'a> Parser<'a> {
             (None, name.span.end)
         };
 
+        // Optional default value: = expr
+        let (default, end) = if matches!(self.cur.kind, TokKind::Eq) {
+            self.bump(); // consume =
+            let value = self.parse_expr_bp(0)?;
+            let value_end = value.span().end;
+            (Some(value), value_end)
+        } else {
+            (None, end)
+        };
+
         Ok(Param {
             name,
             ty,
-            span: Span { start, end },
+            default,
+            variadic,
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
         })
     }
 
@@ -617,6 +1349,7 @@ impl<'a> Parser<'a> {
             span: Span {
                 start,
                 end: end_tok.span.end,
+                file: self.file,
             },
         })
     }
@@ -637,6 +1370,7 @@ impl<'a> Parser<'a> {
             span: Span {
                 start: pat_start,
                 end: body_end,
+                file: self.file,
             },
         })
     }
@@ -649,6 +1383,80 @@ impl<'a> Parser<'a> {
         result
     }
 
+    /// Parse an integer literal pattern or a range pattern.
+    fn parse_int_or_range_pattern(&mut self) -> Result<Pat> {
+        let start = self.cur.span.start;
+        let lo = match self.cur.kind {
+            TokKind::DotDot | TokKind::DotDotEq => None,
+            _ => Some(self.parse_int_pattern_bound()?),
+        };
+
+        let inclusive = match self.cur.kind {
+            TokKind::DotDotEq => true,
+            TokKind::DotDot => false,
+            _ => {
+                // Plain integer literal (lo is always present here)
+                let (v, span) = lo.expect("range operator or lower bound");
+                return Ok(Pat::Literal(Lit::Int(v), span));
+            }
+        };
+        let op_span = self.cur.span;
+        self.bump();
+
+        let hi = match self.cur.kind {
+            TokKind::Int(_) | TokKind::Minus => Some(self.parse_int_pattern_bound()?),
+            _ => None,
+        };
+
+        if hi.is_none() {
+            if inclusive {
+                return Err(
+                    self.invalid("inclusive range pattern requires an upper bound", op_span)
+                );
+            }
+            if lo.is_none() {
+                return Err(self.invalid("range pattern requires at least one bound", op_span));
+            }
+        }
+
+        let end = hi.map(|(_, s)| s.end).unwrap_or(op_span.end);
+        Ok(Pat::Range {
+            lo: lo.map(|(v, _)| v),
+            hi: hi.map(|(v, _)| v),
+            inclusive,
+            span: Span {
+                start,
+                end,
+                file: self.file,
+            },
+        })
+    }
+
+    /// Parse an optionally negated integer literal in a pattern.
+    fn parse_int_pattern_bound(&mut self) -> Result<(i64, Span)> {
+        let start = self.cur.span.start;
+        let negative = matches!(self.cur.kind, TokKind::Minus);
+        if negative {
+            self.bump();
+        }
+        match self.cur.kind {
+            TokKind::Int(v) => {
+                let end = self.cur.span.end;
+                self.bump();
+                let v = if negative { -v } else { v };
+                Ok((
+                    v,
+                    Span {
+                        start,
+                        end,
+                        file: self.file,
+                    },
+                ))
+            }
+            _ => Err(self.unexpected(&["integer"])),
+        }
+    }
+
     fn parse_pattern_inner(&mut self) -> Result<Pat> {
         let start = self.cur.span.start;
 
@@ -664,6 +1472,7 @@ impl<'a> Parser<'a> {
                     Span {
                         start,
                         end: end_tok.span.end,
+                        file: self.file,
                     },
                 ));
             }
@@ -694,18 +1503,52 @@ impl<'a> Parser<'a> {
                 Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             ));
         }
 
-        // Literal patterns: numbers, strings, booleans
-        match &self.cur.kind {
-            TokKind::Int(v) => {
-                let v = *v;
-                let span = self.cur.span;
+        // Record pattern: { x, y: 0 } or { x, .. }
+        if matches!(self.cur.kind, TokKind::LBrace) {
+            self.bump(); // consume '{'
+
+            let mut fields = Vec::new();
+            let mut rest = false;
+            while !matches!(self.cur.kind, TokKind::RBrace) {
+                if matches!(self.cur.kind, TokKind::DotDot) {
+                    self.bump();
+                    rest = true;
+                    break;
+                }
+                fields.push(self.parse_pat_field()?);
+                if !matches!(self.cur.kind, TokKind::Comma) {
+                    break;
+                }
                 self.bump();
-                return Ok(Pat::Literal(Lit::Int(v), span));
             }
+            let rbrace = self.expect(TokKind::RBrace)?;
+
+            return Ok(Pat::Record {
+                fields,
+                rest,
+                span: Span {
+                    start,
+                    end: rbrace.span.end,
+                    file: self.file,
+                },
+            });
+        }
+
+        // Integer literal and range patterns: 3, -1, 0..10, 1..=9, 100.., ..=-1
+        if matches!(
+            self.cur.kind,
+            TokKind::Int(_) | TokKind::Minus | TokKind::DotDot | TokKind::DotDotEq
+        ) {
+            return self.parse_int_or_range_pattern();
+        }
+
+        // Literal patterns: numbers, strings, booleans
+        match &self.cur.kind {
             TokKind::Float(v) => {
                 let v = *v;
                 let span = self.cur.span;
@@ -713,9 +1556,24 @@ impl<'a> Parser<'a> {
                 return Ok(Pat::Literal(Lit::Float(v), span));
             }
             TokKind::Str(s) => {
-                let s = s.clone();
+                let s = s.to_string();
                 let span = self.cur.span;
                 self.bump();
+                // Prefix pattern: "get " + rest
+                if matches!(self.cur.kind, TokKind::Plus) {
+                    self.bump();
+                    let rest = self.parse_pattern()?;
+                    let end = rest.span().end;
+                    return Ok(Pat::StrPrefix {
+                        prefix: s,
+                        rest: Box::new(rest),
+                        span: Span {
+                            start,
+                            end,
+                            file: self.file,
+                        },
+                    });
+                }
                 return Ok(Pat::Literal(Lit::Str(s), span));
             }
             TokKind::KwTrue => {
@@ -739,7 +1597,7 @@ impl<'a> Parser<'a> {
         // Identifier patterns: _, x, Foo, Foo::Bar, etc.
         if let TokKind::Ident(s) = &self.cur.kind {
             // Wildcard pattern: _
-            if s == "_" {
+            if *s == "_" {
                 let span = self.cur.span;
                 self.bump();
                 return Ok(Pat::Wildcard(span));
@@ -758,6 +1616,7 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: path_end,
+                    file: self.file,
                 },
             };
 
@@ -765,17 +1624,7 @@ impl<'a> Parser<'a> {
             if matches!(self.cur.kind, TokKind::LParen) {
                 self.bump(); // consume '('
 
-                let mut fields = Vec::new();
-                if !matches!(self.cur.kind, TokKind::RParen) {
-                    fields.push(self.parse_pattern()?);
-                    while matches!(self.cur.kind, TokKind::Comma) {
-                        self.bump();
-                        if matches!(self.cur.kind, TokKind::RParen) {
-                            break; // trailing comma
-                        }
-                        fields.push(self.parse_pattern()?);
-                    }
-                }
+                let fields = self.parse_comma_separated(&TokKind::RParen, Self::parse_pattern)?;
                 let rparen = self.expect(TokKind::RParen)?;
 
                 return Ok(Pat::Variant {
@@ -784,6 +1633,7 @@ impl<'a> Parser<'a> {
                     span: Span {
                         start,
                         end: rparen.span.end,
+                        file: self.file,
                     },
                 });
             }
@@ -792,17 +1642,7 @@ impl<'a> Parser<'a> {
             if matches!(self.cur.kind, TokKind::LBrace) {
                 self.bump(); // consume '{'
 
-                let mut fields = Vec::new();
-                while !matches!(self.cur.kind, TokKind::RBrace) {
-                    let field = self.parse_pat_field()?;
-                    fields.push(field);
-
-                    if matches!(self.cur.kind, TokKind::Comma) {
-                        self.bump();
-                    } else {
-                        break;
-                    }
-                }
+                let fields = self.parse_comma_separated(&TokKind::RBrace, Self::parse_pat_field)?;
                 let rbrace = self.expect(TokKind::RBrace)?;
 
                 return Ok(Pat::Struct {
@@ -811,6 +1651,7 @@ impl<'a> Parser<'a> {
                     span: Span {
                         start,
                         end: rbrace.span.end,
+                        file: self.file,
                     },
                 });
             }
@@ -827,11 +1668,12 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: path_end,
+                    file: self.file,
                 },
             });
         }
 
-        bail!("unexpected token in pattern: {:?}", self.cur.kind)
+        Err(self.unexpected(&["pattern"]))
     }
 
     /// Parse a struct pattern field: `x` or `x: pat`
@@ -857,6 +1699,7 @@ impl<'a> Parser<'a> {
             span: Span {
                 start: field_start,
                 end: field_end,
+                file: self.file,
             },
         })
     }
@@ -865,7 +1708,23 @@ impl<'a> Parser<'a> {
     fn parse_struct_expr(&mut self, path: Path) -> Result<Expr> {
         let start = path.span.start;
         self.expect(TokKind::LBrace)?;
+        let fields = self.parse_field_inits()?;
+        let end_tok = self.expect(TokKind::RBrace)?;
+
+        Ok(Expr::StructExpr {
+            path,
+            fields,
+            span: Span {
+                start,
+                end: end_tok.span.end,
+                file: self.file,
+            },
+        })
+    }
 
+    /// Parse the fields of a struct or record expression, up to the closing
+    /// `}`: `x: 1, y` (shorthand for `y: y`)
+    fn parse_field_inits(&mut self) -> Result<Vec<FieldInit>> {
         let mut fields = Vec::new();
         while !matches!(self.cur.kind, TokKind::RBrace) {
             let field_start = self.cur.span.start;
@@ -889,6 +1748,7 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start: field_start,
                     end: field_end,
+                    file: self.file,
                 },
             });
 
@@ -899,17 +1759,7 @@ impl<'a> Parser<'a> {
                 break;
             }
         }
-
-        let end_tok = self.expect(TokKind::RBrace)?;
-
-        Ok(Expr::StructExpr {
-            path,
-            fields,
-            span: Span {
-                start,
-                end: end_tok.span.end,
-            },
-        })
+        Ok(fields)
     }
 
     // ======= blocks and statements =======
@@ -925,72 +1775,50 @@ impl<'a> Parser<'a> {
     fn parse_block_inner(&mut self) -> Result<Block> {
         let start = self.cur.span.start;
         self.expect(TokKind::LBrace)?;
+        self.parse_block_rest(start)
+    }
+
+    /// Parse a `{` in expression position: a record when it opens with
+    /// `name:`, otherwise a block
+    fn parse_block_or_record(&mut self) -> Result<Expr> {
+        self.enter_nesting()?;
+        let result = self.parse_block_or_record_inner();
+        self.exit_nesting();
+        result
+    }
+
+    fn parse_block_or_record_inner(&mut self) -> Result<Expr> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::LBrace)?;
+        if !matches!(
+            (&self.cur.kind, &self.nxt.kind),
+            (TokKind::Ident(_), TokKind::Colon)
+        ) {
+            return Ok(Expr::Block(self.parse_block_rest(start)?));
+        }
+
+        let fields = self.parse_field_inits()?;
+        let end_tok = self.expect(TokKind::RBrace)?;
+        Ok(Expr::Record {
+            fields,
+            span: Span {
+                start,
+                end: end_tok.span.end,
+                file: self.file,
+            },
+        })
+    }
 
+    /// Parse the rest of a block whose `{` (at `start`) was consumed
+    fn parse_block_rest(&mut self, start: u32) -> Result<Block> {
         let mut stmts = Vec::new();
         let mut tail = None;
 
-        loop {
-            // End of block?
-            if matches!(self.cur.kind, TokKind::RBrace) {
+        while !matches!(self.cur.kind, TokKind::RBrace) {
+            if let Some(expr) = self.parse_body_stmt(&mut stmts, &TokKind::RBrace)? {
+                tail = Some(Box::new(expr));
                 break;
             }
-
-            // Try to parse a statement or tail expression
-            match self.cur.kind {
-                TokKind::KwLet => {
-                    stmts.push(self.parse_let_stmt()?);
-                }
-                TokKind::KwReturn => {
-                    stmts.push(self.parse_return_stmt()?);
-                }
-                _ => {
-                    // Parse expression, then determine if it's a statement or tail
-                    let expr = self.parse_expr_bp(0)?;
-                    let expr_span = Span {
-                        start: node_start(&expr),
-                        end: node_end(&expr),
-                    };
-
-                    if matches!(self.cur.kind, TokKind::Eq) {
-                        // Assignment: expr = value;
-                        // expr must be a variable
-                        let target = match expr {
-                            Expr::Var(id) => id,
-                            _ => bail!("assignment target must be a variable"),
-                        };
-                        self.bump(); // consume '='
-                        let value = self.parse_expr_bp(0)?;
-                        let semi = self.expect(TokKind::Semicolon)?;
-                        let span = Span {
-                            start: expr_span.start,
-                            end: semi.span.end,
-                        };
-                        stmts.push(Stmt::Assign {
-                            target,
-                            value,
-                            span,
-                        });
-                    } else if matches!(self.cur.kind, TokKind::Semicolon) {
-                        // Expression statement
-                        let semi_end = self.cur.span.end;
-                        self.bump(); // consume ';'
-                        let span = Span {
-                            start: expr_span.start,
-                            end: semi_end,
-                        };
-                        stmts.push(Stmt::Expr { expr, span });
-                    } else if matches!(self.cur.kind, TokKind::RBrace) {
-                        // Tail expression (no semicolon before closing brace)
-                        tail = Some(Box::new(expr));
-                        break;
-                    } else {
-                        bail!(
-                            "expected ';', '=', or '}}' after expression, found {:?}",
-                            self.cur.kind
-                        );
-                    }
-                }
-            }
         }
 
         let end_tok = self.expect(TokKind::RBrace)?;
@@ -1000,10 +1828,79 @@ impl<'a> Parser<'a> {
             span: Span {
                 start,
                 end: end_tok.span.end,
+                file: self.file,
             },
         })
     }
 
+    /// Parse one statement of a body that ends at `close`, pushing it onto
+    /// `stmts`. An expression directly followed by `close` is the body's
+    /// tail instead and is returned.
+    fn parse_body_stmt(&mut self, stmts: &mut Vec<Stmt>, close: &TokKind) -> Result<Option<Expr>> {
+        match self.cur.kind {
+            TokKind::KwLet => {
+                stmts.push(self.parse_let_stmt()?);
+            }
+            TokKind::KwReturn => {
+                stmts.push(self.parse_return_stmt()?);
+            }
+            TokKind::KwFn => {
+                stmts.push(Stmt::Fn(Box::new(self.parse_fn_decl()?)));
+            }
+            _ => {
+                // Parse expression, then determine if it's a statement or tail
+                let expr = self.parse_expr_bp(0)?;
+                let expr_span = Span {
+                    start: node_start(&expr),
+                    end: node_end(&expr),
+                    file: self.file,
+                };
+
+                if matches!(self.cur.kind, TokKind::Eq) {
+                    // Assignment: expr = value;
+                    // expr must be a variable
+                    let target = match expr {
+                        Expr::Var(id) => id,
+                        _ => {
+                            return Err(
+                                self.invalid("assignment target must be a variable", expr_span)
+                            )
+                        }
+                    };
+                    self.bump(); // consume '='
+                    let value = self.parse_expr_bp(0)?;
+                    let semi = self.expect(TokKind::Semicolon)?;
+                    let span = Span {
+                        start: expr_span.start,
+                        end: semi.span.end,
+                        file: self.file,
+                    };
+                    stmts.push(Stmt::Assign {
+                        target,
+                        value,
+                        span,
+                    });
+                } else if matches!(self.cur.kind, TokKind::Semicolon) {
+                    // Expression statement
+                    let semi_end = self.cur.span.end;
+                    self.bump(); // consume ';'
+                    let span = Span {
+                        start: expr_span.start,
+                        end: semi_end,
+                        file: self.file,
+                    };
+                    stmts.push(Stmt::Expr { expr, span });
+                } else if self.at(close) {
+                    // Tail expression (no semicolon before the end)
+                    return Ok(Some(expr));
+                } else {
+                    return Err(self.unexpected(&["`;`", "`=`", &close.describe()]));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     /// Parse a let statement: `let [mut] pattern [: Type] = expr;`
     /// Supports destructuring patterns like `let (a, b) = expr;`
     fn parse_let_stmt(&mut self) -> Result<Stmt> {
@@ -1025,7 +1922,10 @@ impl<'a> Parser<'a> {
         let ty = if matches!(self.cur.kind, TokKind::Colon) {
             // Type annotations only allowed for simple identifier patterns
             if !matches!(pat, Pat::Ident(_)) {
-                bail!("type annotations not supported for destructuring patterns");
+                return Err(self.invalid(
+                    "type annotations not supported for destructuring patterns",
+                    pat.span(),
+                ));
             }
             self.bump();
             Some(self.parse_type()?)
@@ -1035,7 +1935,34 @@ impl<'a> Parser<'a> {
 
         // `mut` only valid for simple identifier patterns
         if mutable && !matches!(pat, Pat::Ident(_)) {
-            bail!("`mut` not supported for destructuring patterns");
+            return Err(self.invalid("`mut` not supported for destructuring patterns", pat.span()));
+        }
+
+        // Declaration without a value: `let mut x: T;`
+        if matches!(self.cur.kind, TokKind::Semicolon) {
+            let decl_span = Span {
+                start,
+                end: self.cur.span.end,
+                file: self.file,
+            };
+            let (Pat::Ident(name), Some(ty)) = (pat, ty) else {
+                return Err(self.invalid(
+                    "a `let` without a value needs a type annotation: `let mut x: T;`",
+                    decl_span,
+                ));
+            };
+            if !mutable {
+                return Err(self.invalid(
+                    "a `let` without a value must be `let mut`, since it is assigned later",
+                    decl_span,
+                ));
+            }
+            self.bump();
+            return Ok(Stmt::Declare {
+                name,
+                ty,
+                span: decl_span,
+            });
         }
 
         self.expect(TokKind::Eq)?;
@@ -1050,6 +1977,7 @@ impl<'a> Parser<'a> {
             span: Span {
                 start,
                 end: semi.span.end,
+                file: self.file,
             },
         })
     }
@@ -1073,6 +2001,7 @@ impl<'a> Parser<'a> {
             span: Span {
                 start,
                 end: semi.span.end,
+                file: self.file,
             },
         })
     }
@@ -1108,7 +2037,11 @@ impl<'a> Parser<'a> {
             (None, then_end)
         };
 
-        let span = Span { start, end };
+        let span = Span {
+            start,
+            end,
+            file: self.file,
+        };
 
         Ok(Expr::If {
             cond,
@@ -1130,11 +2063,50 @@ impl<'a> Parser<'a> {
         let span = Span {
             start,
             end: body_end,
+            file: self.file,
         };
 
         Ok(Expr::While { cond, body, span })
     }
 
+    /// Parse a for loop: `for pat in iter { body }`
+    fn parse_for(&mut self) -> Result<Expr> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwFor)?;
+
+        let pat = self.parse_pattern()?;
+        self.expect(TokKind::KwIn)?;
+        let iter = Box::new(self.parse_expr_bp(0)?);
+        let body = self.parse_block()?;
+        let span = Span {
+            start,
+            end: body.span.end,
+            file: self.file,
+        };
+
+        Ok(Expr::For {
+            pat,
+            iter,
+            body,
+            span,
+        })
+    }
+
+    /// Parse a task: `spawn { body }`
+    fn parse_spawn(&mut self) -> Result<Expr> {
+        let start = self.cur.span.start;
+        self.expect(TokKind::KwSpawn)?;
+
+        let body = self.parse_block()?;
+        let span = Span {
+            start,
+            end: body.span.end,
+            file: self.file,
+        };
+
+        Ok(Expr::Spawn { body, span })
+    }
+
     // ======= expressions (Pratt parser) =======
     //
     // Precedence (low -> high):
@@ -1146,12 +2118,31 @@ impl<'a> Parser<'a> {
     //   20: * /
     // prefix (unary) binds tighter than all infix; we give it rbp = 100
 
+    /// Every recursive expression form (parens, unary operators, calls,
+    /// if/while/match) passes through here, so this is where depth is checked.
     fn parse_expr_bp(&mut self, min_bp: u8) -> Result<Expr> {
+        self.enter_nesting()?;
+        let result = self.parse_expr_bp_inner(min_bp);
+        self.exit_nesting();
+        result
+    }
+
+    fn parse_expr_bp_inner(&mut self, min_bp: u8) -> Result<Expr> {
         // prefix: literals, vars, (), unary ! and -
         let mut lhs = self.parse_prefix()?;
 
         loop {
             let (op, lbp, rbp) = match self.cur.kind {
+                // pipe (loosest): `x |> f` is a call, `f(x)`
+                TokKind::PipeGt => {
+                    if min_bp > 0 {
+                        break;
+                    }
+                    self.bump();
+                    let rhs = self.parse_expr_bp(1)?;
+                    lhs = pipe_call(lhs, rhs, self.file);
+                    continue;
+                }
                 // logical
                 TokKind::OrOr => (BinOp::Or, 1, 2),
                 TokKind::AndAnd => (BinOp::And, 3, 4),
@@ -1171,14 +2162,32 @@ impl<'a> Parser<'a> {
                 // call application (tightest)
                 TokKind::LParen => {
                     let start = node_start(&lhs);
-                    let (args, rparen_end) = self.parse_call_args()?;
+                    let (args, names, rparen_end) = self.parse_call_args()?;
                     let span = Span {
                         start,
                         end: rparen_end,
+                        file: self.file,
                     };
                     lhs = Expr::Call {
                         callee: Box::new(lhs),
                         args,
+                        names,
+                        span,
+                    };
+                    continue;
+                }
+                // record field access, as tight as a call
+                TokKind::Dot => {
+                    self.bump();
+                    let field = self.parse_ident()?;
+                    let span = Span {
+                        start: node_start(&lhs),
+                        end: field.span.end,
+                        file: self.file,
+                    };
+                    lhs = Expr::Field {
+                        expr: Box::new(lhs),
+                        field,
                         span,
                     };
                     continue;
@@ -1194,6 +2203,7 @@ impl<'a> Parser<'a> {
             let span = Span {
                 start: node_start(&lhs),
                 end: node_end(&rhs),
+                file: self.file,
             };
             lhs = Expr::Binary {
                 lhs: Box::new(lhs),
@@ -1214,14 +2224,12 @@ impl<'a> Parser<'a> {
         match tok_kind {
             // unary prefix
             TokKind::Bang => {
-                self.enter_nesting()?;
                 self.bump();
-                let result = self.parse_expr_bp(100);
-                self.exit_nesting();
-                let inner = result?;
+                let inner = self.parse_expr_bp(100)?;
                 let span = Span {
                     start: tok_span.start,
                     end: node_end(&inner),
+                    file: self.file,
                 };
                 Ok(Expr::Unary {
                     op: UnOp::Not,
@@ -1230,14 +2238,12 @@ impl<'a> Parser<'a> {
                 })
             }
             TokKind::Minus => {
-                self.enter_nesting()?;
                 self.bump();
-                let result = self.parse_expr_bp(100);
-                self.exit_nesting();
-                let inner = result?;
+                let inner = self.parse_expr_bp(100)?;
                 let span = Span {
                     start: tok_span.start,
                     end: node_end(&inner),
+                    file: self.file,
                 };
                 Ok(Expr::Unary {
                     op: UnOp::Neg,
@@ -1248,14 +2254,12 @@ impl<'a> Parser<'a> {
 
             // Borrow expression: &expr
             TokKind::Ampersand => {
-                self.enter_nesting()?;
                 self.bump();
-                let result = self.parse_expr_bp(100);
-                self.exit_nesting();
-                let inner = result?;
+                let inner = self.parse_expr_bp(100)?;
                 let span = Span {
                     start: tok_span.start,
                     end: node_end(&inner),
+                    file: self.file,
                 };
                 Ok(Expr::Borrow(Box::new(inner), span))
             }
@@ -1271,7 +2275,15 @@ impl<'a> Parser<'a> {
             }
             TokKind::Str(s) => {
                 self.bump();
-                Ok(Expr::Lit(Lit::Str(s), tok_span))
+                // Adjacent literals concatenate at parse time: "a" "b" == "ab"
+                let mut s = s.into_owned();
+                let mut span = tok_span;
+                while let TokKind::Str(next) = &self.cur.kind {
+                    s.push_str(next);
+                    span.end = self.cur.span.end;
+                    self.bump();
+                }
+                Ok(Expr::Lit(Lit::Str(s), span))
             }
             TokKind::KwTrue => {
                 self.bump();
@@ -1304,6 +2316,7 @@ impl<'a> Parser<'a> {
                         span: Span {
                             start,
                             end: path_end,
+                            file: self.file,
                         },
                     };
 
@@ -1322,20 +2335,19 @@ impl<'a> Parser<'a> {
                 // If the identifier starts with uppercase (type name convention) and
                 // is followed by `{`, parse as struct expression.
                 // This avoids ambiguity with blocks like `if x { }` since those use
-                // lowercase variable names.
+                // lowercase variable names, and `if MAX { }` since consts are
+                // SCREAMING_CASE.
                 if matches!(self.cur.kind, TokKind::LBrace) {
-                    let is_type_name = first_id
-                        .text
-                        .chars()
-                        .next()
-                        .map(|c| c.is_ascii_uppercase())
-                        .unwrap_or(false);
+                    let text = &first_id.text;
+                    let is_type_name = text.starts_with(|c: char| c.is_ascii_uppercase())
+                        && (text.len() == 1 || text.chars().any(|c| c.is_ascii_lowercase()));
                     if is_type_name {
                         let path = Path {
                             segments: vec![first_id],
                             span: Span {
                                 start,
                                 end: start + 1, // Will be updated by parse_struct_expr
+                                file: self.file,
                             },
                         };
                         return self.parse_struct_expr(path);
@@ -1346,52 +2358,31 @@ impl<'a> Parser<'a> {
                 Ok(Expr::Var(first_id))
             }
 
-            TokKind::LParen => {
-                self.enter_nesting()?;
-                let result = self.parse_paren_or_tuple(tok_span.start);
-                self.exit_nesting();
-                result
-            }
+            TokKind::LParen => self.parse_paren_or_tuple(tok_span.start),
 
-            // Block expression
-            TokKind::LBrace => {
-                let block = self.parse_block()?;
-                Ok(Expr::Block(block))
-            }
+            // Block or record expression
+            TokKind::LBrace => self.parse_block_or_record(),
 
             // If expression
-            TokKind::KwIf => {
-                self.enter_nesting()?;
-                let e = self.parse_if();
-                self.exit_nesting();
-                e
-            }
+            TokKind::KwIf => self.parse_if(),
 
             // While loop
-            TokKind::KwWhile => {
-                self.enter_nesting()?;
-                let e = self.parse_while();
-                self.exit_nesting();
-                e
-            }
+            TokKind::KwWhile => self.parse_while(),
 
-            // Match expression
-            TokKind::KwMatch => {
-                self.enter_nesting()?;
-                let e = self.parse_match();
-                self.exit_nesting();
-                e
-            }
+            // For loop
+            TokKind::KwFor => self.parse_for(),
 
-            // Lexer error - surface it with proper context
-            TokKind::Error(msg) => bail!("Lexer error at {:?}: {}", self.cur.span, msg),
+            // Task
+            TokKind::KwSpawn => self.parse_spawn(),
+
+            // Match expression
+            TokKind::KwMatch => self.parse_match(),
 
-            _ => bail!("unexpected token in expression: {:?}", tok_kind),
+            _ => Err(self.unexpected(&["expression"])),
         }
     }
 
     /// Parse parenthesized expression or tuple. Called after '(' is consumed.
-    /// Nesting depth is managed by the caller.
     fn parse_paren_or_tuple(&mut self, start: u32) -> Result<Expr> {
         self.bump(); // '('
 
@@ -1403,6 +2394,7 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             });
         }
@@ -1427,6 +2419,7 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             })
         } else {
@@ -1437,27 +2430,86 @@ impl<'a> Parser<'a> {
                 span: Span {
                     start,
                     end: end_tok.span.end,
+                    file: self.file,
                 },
             })
         }
     }
 
     /// Parse call arguments and return (args, closing_paren_span_end)
-    fn parse_call_args(&mut self) -> Result<(Vec<Expr>, u32)> {
+    /// Parse call arguments, each positional or named (`x: 10`), with the
+    /// names: empty if no argument is named.
+    fn parse_call_args(&mut self) -> Result<(Vec<Expr>, Vec<Option<Ident>>, u32)> {
         self.expect(TokKind::LParen)?; // we are at '('
-        let mut args = Vec::new();
-        if !matches!(self.cur.kind, TokKind::RParen) {
-            loop {
-                args.push(self.parse_expr_bp(0)?);
-                if matches!(self.cur.kind, TokKind::Comma) {
-                    self.bump();
-                    continue;
+        let mut names = Vec::new();
+        let args = self.parse_comma_separated(&TokKind::RParen, |p| {
+            let name = if matches!(p.cur.kind, TokKind::Ident(_))
+                && matches!(p.nxt.kind, TokKind::Colon)
+            {
+                let name = p.parse_ident()?;
+                p.bump(); // consume :
+                Some(name)
+            } else {
+                None
+            };
+            names.push(name);
+            // A lone `_` is a hole, for a partial application
+            if matches!(&p.cur.kind, TokKind::Ident(s) if *s == "_")
+                && matches!(p.nxt.kind, TokKind::Comma | TokKind::RParen)
+            {
+                let span = p.cur.span;
+                p.bump();
+                return Ok(Expr::Hole(span));
+            }
+            p.parse_expr_bp(0)
+        })?;
+        let rparen = self.expect(TokKind::RParen)?;
+        if names.iter().all(Option::is_none) {
+            names.clear();
+        }
+        Ok((args, names, rparen.span.end))
+    }
+}
+
+/// `arg |> rhs` as the call it stands for. A call on the right takes `arg`
+/// in its first `_`, or as its first argument when it has none:
+/// `x |> g(2)` is `g(x, 2)`, `x |> g(2, _)` is `g(2, x)`. Anything else
+/// is called with `arg`: `x |> f` is `f(x)`.
+fn pipe_call(arg: Expr, rhs: Expr, file: FileId) -> Expr {
+    let span = Span {
+        start: node_start(&arg),
+        end: node_end(&rhs),
+        file,
+    };
+    match rhs {
+        Expr::Call {
+            callee,
+            mut args,
+            mut names,
+            ..
+        } => {
+            match args.iter().position(|a| matches!(a, Expr::Hole(_))) {
+                Some(hole) => args[hole] = arg,
+                None => {
+                    args.insert(0, arg);
+                    if !names.is_empty() {
+                        names.insert(0, None);
+                    }
                 }
-                break;
+            }
+            Expr::Call {
+                callee,
+                args,
+                names,
+                span,
             }
         }
-        let rparen = self.expect(TokKind::RParen)?;
-        Ok((args, rparen.span.end))
+        callee => Expr::Call {
+            callee: Box::new(callee),
+            args: vec![arg],
+            names: vec![],
+            span,
+        },
     }
 }
 
@@ -1474,11 +2526,15 @@ fn node_start(e: &Expr) -> u32 {
         Expr::Block(block) => block.span.start,
         Expr::If { span, .. } => span.start,
         Expr::While { span, .. } => span.start,
+        Expr::For { span, .. } => span.start,
+        Expr::Spawn { span, .. } => span.start,
         Expr::Match { span, .. } => span.start,
         Expr::Tuple { span, .. } => span.start,
         Expr::StructExpr { span, .. } => span.start,
+        Expr::Record { span, .. } => span.start,
+        Expr::Field { span, .. } => span.start,
         Expr::PathExpr(path) => path.span.start,
-        Expr::Borrow(_, span) => span.start,
+        Expr::Borrow(_, span) | Expr::Hole(span) => span.start,
     }
 }
 
@@ -1493,10 +2549,25 @@ fn node_end(e: &Expr) -> u32 {
         Expr::Block(block) => block.span.end,
         Expr::If { span, .. } => span.end,
         Expr::While { span, .. } => span.end,
+        Expr::For { span, .. } => span.end,
+        Expr::Spawn { span, .. } => span.end,
         Expr::Match { span, .. } => span.end,
         Expr::Tuple { span, .. } => span.end,
         Expr::StructExpr { span, .. } => span.end,
+        Expr::Record { span, .. } => span.end,
+        Expr::Field { span, .. } => span.end,
         Expr::PathExpr(path) => path.span.end,
-        Expr::Borrow(_, span) => span.end,
+        Expr::Borrow(_, span) | Expr::Hole(span) => span.end,
+    }
+}
+
+fn stmt_end(s: &Stmt) -> u32 {
+    match s {
+        Stmt::Let { span, .. }
+        | Stmt::Declare { span, .. }
+        | Stmt::Assign { span, .. }
+        | Stmt::Expr { span, .. }
+        | Stmt::Return { span, .. } => span.end,
+        Stmt::Fn(decl) => decl.span.end,
     }
 }
@@ -0,0 +1,434 @@
+//! Token classification for syntax highlighting.
+//!
+//! `semantic_tokens` works from the token stream plus light name resolution
+//! (which names are declared as functions, types, and type parameters), so
+//! it never fails: source that does not parse is still classified up to the
+//! first lexer error.
+
+use crate::lexer::Lexer;
+use crate::parser::ParseOptions;
+use crate::token::{Tok, TokKind};
+use std::collections::HashSet;
+use strata_ast::span::{FileId, Span, MAX_SOURCE_LEN};
+use strata_types::adt::is_capability_type;
+
+/// Built-in type names that need no declaration.
+const BUILTIN_TYPES: &[&str] = &["Int", "Float", "Bool", "String", "Unit", "Option"];
+
+/// Built-in functions that need no declaration.
+const BUILTIN_FNS: &[&str] = &[
+    "drop",
+    "discriminant",
+    "show",
+    "par",
+    "join",
+    "iter",
+    "next",
+    "map",
+    "filter",
+    "take",
+    "todo",
+    "unreachable",
+    "arg_count",
+    "arg",
+];
+
+/// What a highlighted span of source is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    Keyword,
+    /// Built-in, declared, or type-parameter type name
+    Type,
+    /// Capability type name: `FsCap`, `NetCap`, ...
+    Capability,
+    /// Effect name inside an `& { ... }` annotation
+    Effect,
+    /// Function name at its declaration or a call site
+    Function,
+    /// Enum variant after `::`
+    Variant,
+    /// Any other identifier
+    Variable,
+    Number,
+    String,
+    Comment,
+}
+
+impl TokenClass {
+    /// All classes, in legend order.
+    pub const ALL: &'static [TokenClass] = &[
+        TokenClass::Keyword,
+        TokenClass::Type,
+        TokenClass::Capability,
+        TokenClass::Effect,
+        TokenClass::Function,
+        TokenClass::Variant,
+        TokenClass::Variable,
+        TokenClass::Number,
+        TokenClass::String,
+        TokenClass::Comment,
+    ];
+
+    /// Name of the class in the LSP semantic token legend. Standard LSP
+    /// token types are used where one fits.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenClass::Keyword => "keyword",
+            TokenClass::Type => "type",
+            TokenClass::Capability => "capability",
+            TokenClass::Effect => "effect",
+            TokenClass::Function => "function",
+            TokenClass::Variant => "enumMember",
+            TokenClass::Variable => "variable",
+            TokenClass::Number => "number",
+            TokenClass::String => "string",
+            TokenClass::Comment => "comment",
+        }
+    }
+}
+
+/// Something more about a highlighted span, beyond its class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenModifier {
+    /// A use of an item declared `#[deprecated]`
+    Deprecated,
+}
+
+impl TokenModifier {
+    /// All modifiers, in legend order.
+    pub const ALL: &'static [TokenModifier] = &[TokenModifier::Deprecated];
+
+    /// Name of the modifier in the LSP semantic token legend. `deprecated`
+    /// is the standard modifier editors draw struck through.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenModifier::Deprecated => "deprecated",
+        }
+    }
+}
+
+/// Classify `src` into highlighted spans, sorted by position. Punctuation
+/// and operators are not included.
+pub fn semantic_tokens(src: &str) -> Vec<(Span, TokenClass)> {
+    // Offsets past this can't be expressed as spans
+    if src.len() > MAX_SOURCE_LEN {
+        return Vec::new();
+    }
+    let (toks, lexed_to) = lex_all(src);
+    let names = Declared::collect(&toks);
+
+    let mut out = Vec::new();
+    let mut in_effects = false;
+    let mut prev_end = 0;
+    for (i, tok) in toks.iter().enumerate() {
+        comments_between(src, prev_end, tok.span.start as usize, &mut out);
+        prev_end = tok.span.end as usize;
+
+        let prev = i.checked_sub(1).map(|j| &toks[j].kind);
+        let next = toks.get(i + 1).map(|t| &t.kind);
+        if matches!(tok.kind, TokKind::LBrace) && matches!(prev, Some(TokKind::Ampersand)) {
+            in_effects = true;
+        } else if matches!(tok.kind, TokKind::RBrace) {
+            in_effects = false;
+        }
+
+        let class = match &tok.kind {
+            TokKind::Ident(name) => Some(names.classify(name, prev, next, in_effects)),
+            TokKind::Int(_) | TokKind::Float(_) => Some(TokenClass::Number),
+            TokKind::Str(_) => Some(TokenClass::String),
+            kind if is_keyword(kind) => Some(TokenClass::Keyword),
+            _ => None,
+        };
+        if let Some(class) = class {
+            out.push((tok.span, class));
+        }
+    }
+    comments_between(src, prev_end, lexed_to, &mut out);
+    out
+}
+
+/// Spans of `src` that carry a [`TokenModifier`], sorted by position: each
+/// use of a function, extern, const, or type declared after a
+/// `#[deprecated]` attribute. Like [`semantic_tokens`] this works from the
+/// token stream, so a local that shares a deprecated item's name is marked
+/// too.
+pub fn semantic_token_modifiers(src: &str) -> Vec<(Span, TokenModifier)> {
+    if src.len() > MAX_SOURCE_LEN {
+        return Vec::new();
+    }
+    let (toks, _) = lex_all(src);
+    let mut deprecated = HashSet::new();
+    // Indices of the deprecated declarations' names, which aren't uses
+    let mut declarations = HashSet::new();
+    let mut i = 0;
+    while i < toks.len() {
+        let Some(end) = attribute_end(&toks, i) else {
+            i += 1;
+            continue;
+        };
+        let is_deprecated = matches!(&toks[i + 2].kind, TokKind::Ident(n) if *n == "deprecated");
+        i = end;
+        if !is_deprecated {
+            continue;
+        }
+        // Skip any further attributes and modifiers up to the declared name
+        let mut j = end;
+        loop {
+            match toks.get(j).map(|t| &t.kind) {
+                Some(TokKind::Hash) => match attribute_end(&toks, j) {
+                    Some(next) => j = next,
+                    None => break,
+                },
+                Some(
+                    TokKind::KwPub
+                    | TokKind::KwLinear
+                    | TokKind::KwTransparent
+                    | TokKind::KwBench
+                    | TokKind::KwPure
+                    | TokKind::KwExtern
+                    | TokKind::KwFn
+                    | TokKind::KwStruct
+                    | TokKind::KwEnum
+                    | TokKind::KwConst,
+                ) => j += 1,
+                Some(TokKind::Ident(name))
+                    if matches!(
+                        toks[j - 1].kind,
+                        TokKind::KwFn | TokKind::KwStruct | TokKind::KwEnum | TokKind::KwConst
+                    ) =>
+                {
+                    deprecated.insert(*name);
+                    declarations.insert(j);
+                    break;
+                }
+                _ => break,
+            }
+        }
+    }
+    toks.iter()
+        .enumerate()
+        .filter(|(i, tok)| {
+            matches!(&tok.kind, TokKind::Ident(name) if deprecated.contains(name))
+                && !declarations.contains(i)
+                && !matches!(i.checked_sub(1).map(|j| &toks[j].kind), Some(TokKind::Dot))
+        })
+        .map(|(_, tok)| (tok.span, TokenModifier::Deprecated))
+        .collect()
+}
+
+/// Index just past the `]` of an attribute `#[name ...]` starting at
+/// `start`, or `None` if there is no attribute there.
+fn attribute_end(toks: &[Tok<'_>], start: usize) -> Option<usize> {
+    match (
+        toks.get(start).map(|t| &t.kind),
+        toks.get(start + 1).map(|t| &t.kind),
+        toks.get(start + 2).map(|t| &t.kind),
+    ) {
+        (Some(TokKind::Hash), Some(TokKind::LBracket), Some(TokKind::Ident(_))) => {}
+        _ => return None,
+    }
+    let mut depth = 0;
+    for (i, tok) in toks.iter().enumerate().skip(start + 1) {
+        match tok.kind {
+            TokKind::LBracket => depth += 1,
+            TokKind::RBracket => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Tokens up to (not including) end of file or the first lexer error, and
+/// the offset lexing stopped at.
+fn lex_all(src: &str) -> (Vec<Tok<'_>>, usize) {
+    let mut lex = Lexer::new(src, FileId::default(), ParseOptions::default().max_tokens);
+    let mut toks = Vec::new();
+    loop {
+        let tok = lex.next_tok();
+        match tok.kind {
+            TokKind::Eof => return (toks, src.len()),
+            TokKind::Error(_) => return (toks, tok.span.start as usize),
+            _ => toks.push(tok),
+        }
+    }
+}
+
+fn is_keyword(kind: &TokKind) -> bool {
+    matches!(
+        kind,
+        TokKind::KwLet
+            | TokKind::KwFn
+            | TokKind::KwTrue
+            | TokKind::KwFalse
+            | TokKind::KwNil
+            | TokKind::KwIf
+            | TokKind::KwElse
+            | TokKind::KwWhile
+            | TokKind::KwFor
+            | TokKind::KwIn
+            | TokKind::KwSpawn
+            | TokKind::KwReturn
+            | TokKind::KwMut
+            | TokKind::KwMatch
+            | TokKind::KwEnum
+            | TokKind::KwStruct
+            | TokKind::KwExtern
+            | TokKind::KwLinear
+            | TokKind::KwTransparent
+            | TokKind::KwBench
+            | TokKind::KwPure
+            | TokKind::KwConst
+            | TokKind::KwPub
+            | TokKind::KwUse
+    )
+}
+
+/// Names declared anywhere in the file.
+struct Declared<'a> {
+    fns: HashSet<&'a str>,
+    types: HashSet<&'a str>,
+    variants: HashSet<&'a str>,
+}
+
+impl<'a> Declared<'a> {
+    /// Functions follow `fn`; types follow `struct`/`enum`; type parameters
+    /// are the names in `<...>` right after a declared name; variants start
+    /// each entry of an `enum` body.
+    fn collect(toks: &[Tok<'a>]) -> Self {
+        let mut fns: HashSet<&str> = BUILTIN_FNS.iter().copied().collect();
+        let mut types: HashSet<&str> = BUILTIN_TYPES.iter().copied().collect();
+        let mut variants = HashSet::new();
+        for (i, pair) in toks.windows(2).enumerate() {
+            let TokKind::Ident(name) = &pair[1].kind else {
+                continue;
+            };
+            match pair[0].kind {
+                TokKind::KwFn => fns.insert(name),
+                TokKind::KwStruct | TokKind::KwEnum => types.insert(name),
+                _ => continue,
+            };
+            if matches!(toks.get(i + 2).map(|t| &t.kind), Some(TokKind::Lt)) {
+                for tok in toks[i + 3..].iter() {
+                    match &tok.kind {
+                        TokKind::Ident(param) => {
+                            types.insert(param);
+                        }
+                        TokKind::Comma => {}
+                        _ => break,
+                    }
+                }
+            }
+            if matches!(pair[0].kind, TokKind::KwEnum) {
+                collect_variants(&toks[i + 2..], &mut variants);
+            }
+        }
+        Self {
+            fns,
+            types,
+            variants,
+        }
+    }
+
+    fn classify(
+        &self,
+        name: &str,
+        prev: Option<&TokKind>,
+        next: Option<&TokKind>,
+        in_effects: bool,
+    ) -> TokenClass {
+        if in_effects {
+            TokenClass::Effect
+        } else if is_capability_type(name) {
+            TokenClass::Capability
+        } else if matches!(prev, Some(TokKind::KwFn)) {
+            TokenClass::Function
+        } else if matches!(prev, Some(TokKind::ColonColon)) || self.variants.contains(name) {
+            TokenClass::Variant
+        } else if matches!(next, Some(TokKind::ColonColon)) || self.types.contains(name) {
+            TokenClass::Type
+        } else if matches!(next, Some(TokKind::LParen)) && self.fns.contains(name) {
+            TokenClass::Function
+        } else {
+            TokenClass::Variable
+        }
+    }
+}
+
+/// Variant names of the enum body starting at the first `{` in `toks`.
+fn collect_variants<'a>(toks: &[Tok<'a>], variants: &mut HashSet<&'a str>) {
+    let Some(open) = toks.iter().position(|t| matches!(t.kind, TokKind::LBrace)) else {
+        return;
+    };
+    let mut depth = 0;
+    let mut entry_start = true;
+    for tok in &toks[open + 1..] {
+        match &tok.kind {
+            TokKind::LParen => depth += 1,
+            TokKind::RParen => depth -= 1,
+            TokKind::RBrace if depth == 0 => return,
+            TokKind::Comma if depth == 0 => {
+                entry_start = true;
+                continue;
+            }
+            TokKind::Ident(name) if depth == 0 && entry_start => {
+                variants.insert(name);
+            }
+            _ => {}
+        }
+        entry_start = false;
+    }
+}
+
+/// Push a `Comment` span for each comment in `src[start..end]`, a gap
+/// between tokens that holds only whitespace and comments.
+fn comments_between(src: &str, start: usize, end: usize, out: &mut Vec<(Span, TokenClass)>) {
+    let bytes = &src.as_bytes()[..end.min(src.len())];
+    let mut pos = start;
+    while pos + 1 < bytes.len() {
+        let comment_end = match (bytes[pos], bytes[pos + 1]) {
+            (b'/', b'/') => bytes[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(bytes.len(), |n| pos + n),
+            (b'/', b'*') => block_comment_end(bytes, pos),
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        out.push((
+            Span::clamped(FileId::default(), pos, comment_end),
+            TokenClass::Comment,
+        ));
+        pos = comment_end;
+    }
+}
+
+/// End offset of the (nesting) block comment starting at `start`, or the
+/// end of `bytes` if it is unterminated.
+fn block_comment_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut pos = start;
+    while pos + 1 < bytes.len() {
+        match (bytes[pos], bytes[pos + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                pos += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                pos += 2;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
@@ -1,11 +1,16 @@
+use crate::error::ParseError;
+use std::borrow::Cow;
 use strata_ast::span::Span;
 
+/// A token's kind. Identifier and string text borrows from the source;
+/// only strings whose value differs from their spelling (escapes,
+/// multi-line indentation) own their text.
 #[derive(Debug, Clone, PartialEq)]
-pub enum TokKind {
+pub enum TokKind<'a> {
     // trivia / eof / error
     Eof,
     /// Error token (e.g., token limit exceeded)
-    Error(String),
+    Error(ParseError),
     // punctuation
     LParen,
     RParen,
@@ -15,8 +20,12 @@ pub enum TokKind {
     Colon,
     ColonColon, // :: for namespaced paths (ADT support)
     Semicolon,
-    Arrow,    // -> for function return types
-    FatArrow, // => for pattern matching (ADT support)
+    Arrow,     // -> for function return types
+    FatArrow,  // => for pattern matching (ADT support)
+    Dot,       // . for record field access
+    DotDot,    // .. for exclusive/open range patterns
+    DotDotEq,  // ..= for inclusive range patterns
+    DotDotDot, // ... for variadic extern fn parameters
     // assignment
     Eq,
     // arithmetic
@@ -35,12 +44,18 @@ pub enum TokKind {
     // logical
     AndAnd,
     OrOr,
+    // pipe: x |> f
+    PipeGt,
     // unary
     Bang, // <-- needed for '!'
     // effect annotation
     Ampersand, // single '&' for effect annotations
+    // item attributes: #[cfg(...)]
+    Hash,
+    LBracket,
+    RBracket,
     // idents / keywords
-    Ident(String),
+    Ident(&'a str),
     KwLet,
     KwFn,
     KwTrue,
@@ -49,20 +64,102 @@ pub enum TokKind {
     KwIf,
     KwElse,
     KwWhile,
+    KwFor,
+    KwIn,
+    KwSpawn,
     KwReturn,
     KwMut,
-    KwMatch,  // match keyword (ADT support)
-    KwEnum,   // enum keyword (ADT support)
-    KwStruct, // struct keyword (ADT support)
-    KwExtern, // extern keyword (extern fn declarations)
+    KwMatch,       // match keyword (ADT support)
+    KwEnum,        // enum keyword (ADT support)
+    KwStruct,      // struct keyword (ADT support)
+    KwExtern,      // extern keyword (extern fn declarations)
+    KwLinear,      // linear keyword (linear struct/enum)
+    KwTransparent, // transparent keyword (transparent struct)
+    KwBench,       // bench keyword (bench fn declarations)
+    KwPure,        // pure keyword (pure fn declarations)
+    KwConst,       // const keyword (constant declarations)
+    KwPub,         // pub keyword (item visibility)
+    KwUse,         // use keyword (variant imports)
     // literals
     Int(i64),
     Float(f64),
-    Str(String),
+    Str(Cow<'a, str>),
 }
 
 #[derive(Debug, Clone)]
-pub struct Tok {
-    pub kind: TokKind,
+pub struct Tok<'a> {
+    pub kind: TokKind<'a>,
     pub span: Span,
 }
+
+impl TokKind<'_> {
+    /// Human-readable name used in "expected X, found Y" messages.
+    pub fn describe(&self) -> String {
+        let text = match self {
+            TokKind::Eof => return "end of file".to_string(),
+            TokKind::Error(_) => return "invalid token".to_string(),
+            TokKind::Ident(s) => return format!("identifier `{}`", s),
+            TokKind::Int(i) => return format!("integer `{}`", i),
+            TokKind::Float(x) => return format!("float `{}`", x),
+            TokKind::Str(s) => return format!("string {:?}", s),
+            TokKind::LParen => "(",
+            TokKind::RParen => ")",
+            TokKind::LBrace => "{",
+            TokKind::RBrace => "}",
+            TokKind::Comma => ",",
+            TokKind::Colon => ":",
+            TokKind::ColonColon => "::",
+            TokKind::Semicolon => ";",
+            TokKind::Arrow => "->",
+            TokKind::FatArrow => "=>",
+            TokKind::Dot => ".",
+            TokKind::DotDot => "..",
+            TokKind::DotDotEq => "..=",
+            TokKind::DotDotDot => "...",
+            TokKind::Eq => "=",
+            TokKind::Plus => "+",
+            TokKind::Minus => "-",
+            TokKind::Star => "*",
+            TokKind::Slash => "/",
+            TokKind::EqEq => "==",
+            TokKind::BangEq => "!=",
+            TokKind::Lt => "<",
+            TokKind::Le => "<=",
+            TokKind::Gt => ">",
+            TokKind::Ge => ">=",
+            TokKind::AndAnd => "&&",
+            TokKind::OrOr => "||",
+            TokKind::PipeGt => "|>",
+            TokKind::Bang => "!",
+            TokKind::Ampersand => "&",
+            TokKind::Hash => "#",
+            TokKind::LBracket => "[",
+            TokKind::RBracket => "]",
+            TokKind::KwLet => "let",
+            TokKind::KwFn => "fn",
+            TokKind::KwTrue => "true",
+            TokKind::KwFalse => "false",
+            TokKind::KwNil => "nil",
+            TokKind::KwIf => "if",
+            TokKind::KwElse => "else",
+            TokKind::KwWhile => "while",
+            TokKind::KwFor => "for",
+            TokKind::KwIn => "in",
+            TokKind::KwSpawn => "spawn",
+            TokKind::KwReturn => "return",
+            TokKind::KwMut => "mut",
+            TokKind::KwMatch => "match",
+            TokKind::KwEnum => "enum",
+            TokKind::KwStruct => "struct",
+            TokKind::KwExtern => "extern",
+            TokKind::KwLinear => "linear",
+            TokKind::KwTransparent => "transparent",
+            TokKind::KwBench => "bench",
+            TokKind::KwPure => "pure",
+            TokKind::KwConst => "const",
+            TokKind::KwPub => "pub",
+            TokKind::KwUse => "use",
+        };
+        format!("`{}`", text)
+    }
+}
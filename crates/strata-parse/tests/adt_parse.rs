@@ -65,6 +65,18 @@ fn parse_struct_trailing_comma() {
     assert_eq!(s.fields.len(), 2);
 }
 
+#[test]
+fn parse_struct_field_defaults() {
+    let s = parse_struct("struct Config { retries: Int = 3, host: String, }");
+    assert!(matches!(s.fields[0].default, Some(Expr::Lit(..))));
+    assert!(s.fields[1].default.is_none());
+    // The field's span covers its default
+    assert_eq!(s.fields[0].span.end - s.fields[0].span.start, 16);
+
+    // Record types have no defaults
+    assert!(parse_str("<mem>", "fn f(r: { x: Int = 1 }) -> Int { 1 }").is_err());
+}
+
 #[test]
 fn parse_struct_generic_single() {
     let s = parse_struct("struct Box<T> { value: T }");
@@ -127,6 +139,83 @@ fn parse_enum_trailing_comma() {
     assert_eq!(e.variants.len(), 2);
 }
 
+// ============ Linear ADT Parsing Tests ============
+
+#[test]
+fn parse_linear_struct() {
+    let s = parse_struct("linear struct Handle { fs: FsCap }");
+    assert!(s.linear);
+    assert_eq!(s.name.text, "Handle");
+    assert_eq!(s.span.start, 0);
+    assert!(!parse_struct("struct P { x: Int }").linear);
+}
+
+#[test]
+fn parse_linear_enum() {
+    let e = parse_enum("linear enum Slot<T> { Full(T), Empty }");
+    assert!(e.linear);
+    assert_eq!(e.variants.len(), 2);
+    assert!(!parse_enum("enum E { A }").linear);
+}
+
+#[test]
+fn parse_linear_requires_adt() {
+    let err = parse_str("<mem>", "linear fn f() {}").unwrap_err();
+    assert!(
+        err.to_string().contains("expected one of `struct`, `enum`"),
+        "got: {err}"
+    );
+}
+
+// ============ Tuple Struct Parsing Tests ============
+
+#[test]
+fn parse_tuple_struct() {
+    let s = parse_struct("struct Pair<T>(T, Int);");
+    assert!(s.tuple);
+    assert!(!s.transparent);
+    assert_eq!(s.type_params.len(), 1);
+    let names: Vec<&str> = s.fields.iter().map(|f| f.name.text.as_str()).collect();
+    assert_eq!(names, ["0", "1"]);
+    assert!(matches!(&s.fields[1].ty, TypeExpr::Path(segs, _) if segs[0].text == "Int"));
+    assert!(!parse_struct("struct P { x: Int }").tuple);
+}
+
+#[test]
+fn parse_tuple_struct_needs_fields_and_semicolon() {
+    let err = parse_str("<mem>", "struct Unit();").unwrap_err();
+    assert!(err.to_string().contains("at least one field"), "got: {err}");
+    assert!(parse_str("<mem>", "struct UserId(Int) fn main() {}").is_err());
+}
+
+#[test]
+fn parse_transparent_struct() {
+    let s = parse_struct("transparent struct Path(String);");
+    assert!(s.transparent && s.tuple);
+    assert_eq!(s.span.start, 0);
+
+    for src in [
+        "transparent struct P(Int, Int);",
+        "transparent struct P { x: Int }",
+    ] {
+        let err = parse_str("<mem>", src).unwrap_err();
+        assert!(err.to_string().contains("exactly one field"), "got: {err}");
+    }
+    let err = parse_str("<mem>", "transparent enum E { A }").unwrap_err();
+    assert!(err.to_string().contains("expected `struct`"), "got: {err}");
+}
+
+#[test]
+fn parse_tuple_struct_pattern() {
+    let Expr::Match { arms, .. } = parse_expr("match id { UserId(n) => n }") else {
+        panic!("expected Match");
+    };
+    assert!(matches!(
+        &arms[0].pat,
+        Pat::Variant { path, fields, .. } if path.segments.len() == 1 && fields.len() == 1
+    ));
+}
+
 // ============ Type Parsing Tests ============
 
 #[test]
@@ -303,6 +392,80 @@ fn parse_match_trailing_comma() {
     assert_eq!(arms.len(), 2);
 }
 
+// ============ Range Pattern Tests ============
+
+/// Bounds and inclusivity of each arm's range pattern
+fn range_arms(src: &str) -> Vec<(Option<i64>, Option<i64>, bool)> {
+    let Expr::Match { arms, .. } = parse_expr(src) else {
+        panic!("expected Match");
+    };
+    arms.iter()
+        .filter_map(|arm| match &arm.pat {
+            Pat::Range {
+                lo, hi, inclusive, ..
+            } => Some((*lo, *hi, *inclusive)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parse_range_patterns() {
+    let ranges = range_arms("match x { ..=-1 => a, 0..10 => b, 10..=99 => c, 100.. => d }");
+    assert_eq!(
+        ranges,
+        vec![
+            (None, Some(-1), true),
+            (Some(0), Some(10), false),
+            (Some(10), Some(99), true),
+            (Some(100), None, false),
+        ]
+    );
+}
+
+#[test]
+fn parse_negative_literal_pattern() {
+    let e = parse_expr("match x { -5 => a, _ => b }");
+    let Expr::Match { arms, .. } = e else {
+        panic!("expected Match");
+    };
+    assert!(matches!(
+        &arms[0].pat,
+        Pat::Literal(strata_ast::ast::Lit::Int(-5), _)
+    ));
+}
+
+#[test]
+fn parse_half_open_range_is_not_float() {
+    // `1..` must lex as Int followed by DotDot, not as a float literal
+    let ranges = range_arms("match x { 1.. => a, _ => b }");
+    assert_eq!(ranges, vec![(Some(1), None, false)]);
+}
+
+#[test]
+fn parse_range_inclusive_without_upper_bound_fails() {
+    let err = parse_str("<mem>", "fn f(x: Int) -> Int { match x { 1..= => 0 } }")
+        .expect_err("expected parse error");
+    assert!(err.to_string().contains("upper bound"), "got: {err}");
+}
+
+// ============ String Prefix Pattern Tests ============
+
+#[test]
+fn parse_string_prefix_pattern() {
+    let e = parse_expr(r#"match cmd { "get " + path => path, _ => "" }"#);
+    let Expr::Match { arms, .. } = e else {
+        panic!("expected Match");
+    };
+    let Pat::StrPrefix { prefix, rest, span } = &arms[0].pat else {
+        panic!("expected StrPrefix, got {:?}", arms[0].pat);
+    };
+    assert_eq!(prefix, "get ");
+    assert!(matches!(rest.as_ref(), Pat::Ident(id) if id.text == "path"));
+    // `let x = match cmd { ` is 20 bytes; the pattern ends after `path`
+    assert_eq!((span.start, span.end), (20, 33));
+}
+
 // ============ Tuple Expression Tests ============
 
 #[test]
@@ -0,0 +1,101 @@
+// Tests for `#[name(args)]` item attributes
+
+use strata_ast::ast::{AttrArg, Item};
+use strata_parse::{parse_str, ParseError};
+
+#[test]
+fn attributes_are_kept_in_source_order() {
+    let m = parse_str(
+        "<mem>",
+        "#[test] #[allow(shadowed_binding, unused_capability)] fn f() {}",
+    )
+    .expect("parse ok");
+    let attrs = m.items[0].attrs();
+    let names: Vec<&str> = attrs.iter().map(|a| a.name.text.as_str()).collect();
+    assert_eq!(names, ["test", "allow"]);
+    assert!(attrs[0].args.is_empty());
+    let lints: Vec<&str> = attrs[1]
+        .args
+        .iter()
+        .map(|arg| match arg {
+            AttrArg::Word(ident) => ident.text.as_str(),
+            other => panic!("expected a word, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(lints, ["shadowed_binding", "unused_capability"]);
+}
+
+#[test]
+fn argument_forms() {
+    let m = parse_str(
+        "<mem>",
+        r#"#[deprecated("use area")] #[cfg(not(feature = "net"))] #[cfg(all())] fn f() {}"#,
+    )
+    .expect("parse ok");
+    // The `cfg` attributes held, so the item and its attributes are kept
+    let attrs = m.items[0].attrs();
+    assert!(
+        matches!(attrs[0].args.as_slice(), [AttrArg::Str { value, .. }] if value == "use area")
+    );
+    let [AttrArg::List { name, args, .. }] = attrs[1].args.as_slice() else {
+        panic!("expected not(..), got {:?}", attrs[1].args);
+    };
+    assert_eq!(name.text, "not");
+    assert!(matches!(
+        args.as_slice(),
+        [AttrArg::NameValue { name, value, .. }] if name.text == "feature" && value == "net"
+    ));
+}
+
+#[test]
+fn unknown_attributes_parse() {
+    // Whether a name means anything is for the checker to decide
+    let m = parse_str("<mem>", "#[inline(always)] struct P { x: Int }").expect("parse ok");
+    let Item::Struct(def) = &m.items[0] else {
+        panic!("expected Struct");
+    };
+    assert_eq!(def.attrs[0].name.text, "inline");
+}
+
+#[test]
+fn attribute_spans_cover_hash_through_bracket() {
+    let src = r#"#[deprecated("x")] fn f() {}"#;
+    let m = parse_str("<mem>", src).expect("parse ok");
+    let attr = &m.items[0].attrs()[0];
+    assert_eq!(
+        &src[attr.span.start as usize..attr.span.end as usize],
+        r#"#[deprecated("x")]"#
+    );
+    assert_eq!(
+        &src[attr.args[0].span().start as usize..attr.args[0].span().end as usize],
+        r#""x""#
+    );
+}
+
+#[test]
+fn transparent_attribute_marks_a_newtype() {
+    let m = parse_str("<mem>", "#[transparent] struct Meters(Float);").expect("parse ok");
+    let Item::Struct(def) = &m.items[0] else {
+        panic!("expected Struct");
+    };
+    assert!(def.transparent);
+    let err = parse_str("<mem>", "#[transparent] struct P { x: Int }").expect_err("not a newtype");
+    assert!(
+        matches!(&err, ParseError::InvalidSyntax { message, .. } if message.contains("exactly one field")),
+        "got {}",
+        err
+    );
+}
+
+#[test]
+fn malformed_attribute_syntax() {
+    for src in [
+        "#[] fn f() {}",
+        "#[allow(] fn f() {}",
+        "#[deprecated(1)] fn f() {}",
+        "#[cfg(feature = net)] fn f() {}",
+        "#[test fn f() {}",
+    ] {
+        assert!(parse_str("<mem>", src).is_err(), "{}", src);
+    }
+}
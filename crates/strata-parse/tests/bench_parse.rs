@@ -0,0 +1,52 @@
+// Tests for `bench fn` benchmark declarations
+
+use strata_ast::ast::Item;
+use strata_parse::{parse_script, parse_str};
+
+#[test]
+fn bench_fn_is_a_function_marked_bench() {
+    let src = "fn f() -> Int { 1 } bench fn f_once() -> Int { f() }";
+    let m = parse_str("<mem>", src).expect("parse ok");
+    let benches: Vec<(&str, bool)> = m
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::Fn(d) => (d.name.text.as_str(), d.bench),
+            other => panic!("unexpected item {:?}", other),
+        })
+        .collect();
+    assert_eq!(benches, [("f", false), ("f_once", true)]);
+    // The item starts at `bench`
+    let Item::Fn(bench) = &m.items[1] else {
+        panic!("expected Fn");
+    };
+    assert_eq!(bench.span.start, src.find("bench").unwrap() as u32);
+}
+
+#[test]
+fn bench_fn_in_a_script() {
+    let m = parse_script("<mem>", "bench fn b() {} 1 + 2").expect("parse ok");
+    assert!(m
+        .items
+        .iter()
+        .any(|item| matches!(item, Item::Fn(d) if d.bench && d.name.text == "b")));
+}
+
+#[test]
+fn bench_fn_takes_no_parameters() {
+    let err = parse_str("<mem>", "bench fn b(n: Int) -> Int { n }")
+        .expect_err("parameters rejected")
+        .to_string();
+    assert!(
+        err.contains("benchmark 'b' cannot take parameters"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn bench_is_followed_by_fn() {
+    let err = parse_str("<mem>", "bench struct S { x: Int }")
+        .expect_err("only fn follows bench")
+        .to_string();
+    assert!(err.contains("`fn`"), "got: {err}");
+}
@@ -132,6 +132,40 @@ fn let_mut_with_type_annotation() {
     assert!(ty.is_some());
 }
 
+#[test]
+fn let_mut_without_value_is_declaration() {
+    let e = parse_expr_only("{ let mut x: Int; x = 1; x }");
+    let Expr::Block(block) = e else {
+        panic!("expected Block");
+    };
+    let Stmt::Declare { name, ty, span } = &block.stmts[0] else {
+        panic!("expected Declare statement");
+    };
+    assert_eq!(name.text, "x");
+    assert!(matches!(ty, strata_ast::ast::TypeExpr::Path(..)));
+    // Covers `let mut x: Int;` including the semicolon
+    assert_eq!(span.end - span.start, "let mut x: Int;".len() as u32);
+    assert!(matches!(block.stmts[1], Stmt::Assign { .. }));
+}
+
+#[test]
+fn let_without_value_requires_mut_and_type() {
+    let err = parse_str("<mem>", "fn f() -> Int { let x: Int; 1 }").unwrap_err();
+    assert!(err.to_string().contains("must be `let mut`"), "got: {err}");
+
+    let err = parse_str("<mem>", "fn f() -> Int { let mut x; 1 }").unwrap_err();
+    assert!(
+        err.to_string().contains("needs a type annotation"),
+        "got: {err}"
+    );
+
+    let err = parse_str("<mem>", "fn f() -> Int { let (a, b); 1 }").unwrap_err();
+    assert!(
+        err.to_string().contains("needs a type annotation"),
+        "got: {err}"
+    );
+}
+
 // ============ Assignment tests ============
 
 #[test]
@@ -309,6 +343,45 @@ fn while_loop_with_statements() {
     assert!(body.tail.is_none());
 }
 
+// ============ For tests ============
+
+#[test]
+fn for_loop_binds_a_pattern() {
+    let e = parse_expr_only("for (a, b) in pairs { a + b; }");
+    let Expr::For {
+        pat, iter, body, ..
+    } = e
+    else {
+        panic!("expected For");
+    };
+    assert!(matches!(pat, Pat::Tuple(ref pats, _) if pats.len() == 2));
+    assert!(matches!(*iter, Expr::Var(ref id) if id.text == "pairs"));
+    assert_eq!(body.stmts.len(), 1);
+}
+
+#[test]
+fn for_loop_requires_in() {
+    assert!(parse_str("<mem>", "let x = for n items { 1 };").is_err());
+}
+
+// ============ Spawn tests ============
+
+#[test]
+fn spawn_block() {
+    let e = parse_expr_only("spawn { let y = 1; y + 1 }");
+    let Expr::Spawn { body, span } = e else {
+        panic!("expected Spawn");
+    };
+    assert_eq!(body.stmts.len(), 1);
+    assert!(matches!(body.tail.as_deref(), Some(Expr::Binary { .. })));
+    assert_eq!((span.start, span.end), (8, 34));
+}
+
+#[test]
+fn spawn_requires_a_block() {
+    assert!(parse_str("<mem>", "let x = spawn 1;").is_err());
+}
+
 // ============ Function body tests ============
 
 #[test]
@@ -365,3 +438,15 @@ fn while_inside_if() {
     let tail = then_.tail.unwrap();
     assert!(matches!(*tail, Expr::While { .. }));
 }
+
+#[test]
+fn nested_fn_statement() {
+    let body = parse_fn_body("fn f() -> Int { fn helper(x: Int) -> Int { x + 1 } helper(1) }");
+    assert_eq!(body.stmts.len(), 1);
+    let Stmt::Fn(decl) = &body.stmts[0] else {
+        panic!("expected nested Fn");
+    };
+    assert_eq!(decl.name.text, "helper");
+    assert_eq!(decl.params.len(), 1);
+    assert!(matches!(body.tail.as_deref(), Some(Expr::Call { .. })));
+}
@@ -0,0 +1,121 @@
+// Tests for `#[cfg(...)]` conditional compilation of items
+
+use strata_ast::ast::Item;
+use strata_parse::{parse_str, parse_str_with_options, ParseError, ParseOptions};
+
+/// Helper: names of the items of `src` kept with `features` enabled
+fn kept(src: &str, features: &[&str]) -> Vec<String> {
+    let opts = ParseOptions {
+        features: features.iter().map(|f| f.to_string()).collect(),
+        ..ParseOptions::default()
+    };
+    let m = parse_str_with_options("<mem>", src, &opts).expect("parse ok");
+    m.items
+        .iter()
+        .map(|item| match item {
+            Item::Fn(d) => d.name.text.clone(),
+            Item::Let(d) => d.name.text.clone(),
+            Item::Const(d) => d.name.text.clone(),
+            Item::ExternFn(d) => d.name.text.clone(),
+            Item::Struct(d) => d.name.text.clone(),
+            Item::Enum(d) => d.name.text.clone(),
+            Item::Use(d) => d.enum_name.text.clone(),
+        })
+        .collect()
+}
+
+#[test]
+fn feature_selects_between_items() {
+    let src = r#"
+        #[cfg(feature = "net")]
+        extern fn fetch(net: &NetCap, url: String) -> String & {Net};
+        #[cfg(feature = "net")]
+        fn source() -> String { "remote" }
+        #[cfg(not(feature = "net"))]
+        fn source() -> String { "local" }
+        fn main() -> String { source() }
+    "#;
+    assert_eq!(kept(src, &[]), ["source", "main"]);
+    assert_eq!(kept(src, &["net"]), ["fetch", "source", "main"]);
+}
+
+#[test]
+fn all_any_and_stacked_attributes() {
+    let src = r#"
+        #[cfg(all(feature = "a", feature = "b"))] fn both() {}
+        #[cfg(any(feature = "a", feature = "b"))] fn either() {}
+        #[cfg(feature = "a")] #[cfg(not(feature = "b"))] fn only_a() {}
+        #[cfg(all())] fn always() {}
+        #[cfg(any())] fn never() {}
+    "#;
+    assert_eq!(kept(src, &[]), ["always"]);
+    assert_eq!(kept(src, &["a"]), ["either", "only_a", "always"]);
+    assert_eq!(kept(src, &["a", "b"]), ["both", "either", "always"]);
+}
+
+#[test]
+fn cfg_applies_to_every_item_kind() {
+    let src = r#"
+        #[cfg(feature = "x")] const LIMIT: Int = 1;
+        #[cfg(feature = "x")] let ready = true;
+        #[cfg(feature = "x")] struct P { a: Int }
+        #[cfg(feature = "x")] pub enum E { A }
+        #[cfg(feature = "x")] use E::*;
+        fn main() {}
+    "#;
+    assert_eq!(kept(src, &[]), ["main"]);
+    assert_eq!(kept(src, &["x"]).len(), 6);
+}
+
+#[test]
+fn malformed_attributes_are_errors() {
+    for (src, message) in [
+        (
+            "#[cfg(os = \"linux\")] fn f() {}",
+            "unknown cfg predicate `os`",
+        ),
+        (
+            "#[cfg(not(feature = \"a\", feature = \"b\"))] fn f() {}",
+            "`not` takes one predicate",
+        ),
+    ] {
+        let err = parse_str("<mem>", src).expect_err(src);
+        assert!(
+            matches!(&err, ParseError::InvalidSyntax { message: m, .. } if m.contains(message)),
+            "{}: got {}",
+            src,
+            err
+        );
+    }
+    assert!(matches!(
+        parse_str("<mem>", "#[cfg(feature = net)] fn f() {}"),
+        Err(ParseError::UnexpectedToken { .. })
+    ));
+    // An attribute needs an item after it
+    assert!(parse_str("<mem>", "#[cfg(feature = \"a\")]").is_err());
+}
+
+#[test]
+fn scripts_accept_cfg_items() {
+    let opts = ParseOptions {
+        features: vec!["fast".to_string()],
+        ..ParseOptions::default()
+    };
+    let mut db = strata_ast::span::SourceDb::new();
+    let file = db.add(
+        "<mem>",
+        "#[cfg(feature = \"fast\")] fn step() -> Int { 2 }\n\
+         #[cfg(not(feature = \"fast\"))] fn step() -> Int { 1 }\n\
+         step()",
+    );
+    let m = strata_parse::parse_script_source_with_options(&db, file, &opts).expect("parse ok");
+    let fns: Vec<&str> = m
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(d) => Some(d.name.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fns, ["step", "main"]);
+}
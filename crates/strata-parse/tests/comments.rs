@@ -1,5 +1,6 @@
 use strata_ast::ast::{Expr, Item, Lit};
-use strata_parse::parse_str;
+use strata_ast::span::Span;
+use strata_parse::{parse_str, ParseError};
 
 #[test]
 fn line_comments_and_ws_are_ignored() {
@@ -21,3 +22,73 @@ let b = (  // split
     assert!(matches!(take(0), Expr::Lit(Lit::Int(1), _)));
     assert!(matches!(take(1), Expr::Binary { .. }));
 }
+
+#[test]
+fn block_comments_are_ignored() {
+    let src = r#"
+/* leading
+   multi-line comment */
+let a = /* inline */ 1;
+let b = 2 /* before semicolon */;
+let s = "/* not a comment */";
+"#;
+    let m = parse_str("<mem>", src).unwrap();
+    assert_eq!(m.items.len(), 3);
+    let Item::Let(s) = &m.items[2] else {
+        panic!("expected Let declaration");
+    };
+    assert!(matches!(&s.value, Expr::Lit(Lit::Str(v), _) if v == "/* not a comment */"));
+}
+
+#[test]
+fn block_comments_nest() {
+    let src = "/* outer /* inner */ still outer // not a line comment */ let a = 1;";
+    let m = parse_str("<mem>", src).unwrap();
+    assert_eq!(m.items.len(), 1);
+
+    // Without nesting, the first `*/` would end the comment here
+    let src = "/* a /* b */ c */ let a = 1 /* x /* y /* z */ */ */;";
+    assert_eq!(parse_str("<mem>", src).unwrap().items.len(), 1);
+}
+
+#[test]
+fn unterminated_block_comment_points_at_opening() {
+    let err = parse_str("<mem>", "let a = 1; /* never closed").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnterminatedComment {
+            span: Span {
+                start: 11,
+                end: 13,
+                ..Span::default()
+            }
+        }
+    );
+    assert_eq!(err.to_diagnostic().code, "PARSE-UNTERMINATED-COMMENT");
+}
+
+#[test]
+fn unterminated_nested_comment_points_at_innermost_open() {
+    // The inner comment closes; the outer one at 0 is left open
+    let err = parse_str("<mem>", "/* a /* b */ c").unwrap_err();
+    assert_eq!(
+        err.span(),
+        Span {
+            start: 0,
+            end: 2,
+            ..Span::default()
+        }
+    );
+
+    // Both open; the innermost unclosed `/*` is reported
+    let err = parse_str("<mem>", "/* a /* b").unwrap_err();
+    assert!(matches!(err, ParseError::UnterminatedComment { .. }));
+    assert_eq!(
+        err.span(),
+        Span {
+            start: 5,
+            end: 7,
+            ..Span::default()
+        }
+    );
+}
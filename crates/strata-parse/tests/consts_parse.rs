@@ -0,0 +1,48 @@
+// Tests for `const` item parsing
+
+use strata_ast::ast::{Expr, Item, TypeExpr};
+use strata_parse::{parse_str, ParseError};
+
+#[test]
+fn parse_const_item() {
+    let m = parse_str("<mem>", "const MAX: Int = 10 * 10;").expect("parse ok");
+    let Item::Const(c) = &m.items[0] else {
+        panic!("expected Const");
+    };
+    assert_eq!(c.name.text, "MAX");
+    assert!(matches!(&c.ty, TypeExpr::Path(segs, _) if segs[0].text == "Int"));
+    assert!(matches!(c.value, Expr::Binary { .. }));
+    assert_eq!((c.span.start, c.span.end), (0, 25));
+}
+
+#[test]
+fn const_without_type_is_error() {
+    let err = parse_str("<mem>", "const MAX = 10;").unwrap_err();
+    let ParseError::InvalidSyntax { message, span } = &err else {
+        panic!("expected InvalidSyntax, got: {err}");
+    };
+    assert_eq!(
+        message,
+        "a `const` needs a type annotation: `const MAX: T = ...;`"
+    );
+    assert_eq!(span.start, 10);
+}
+
+#[test]
+fn screaming_case_before_brace_is_not_a_struct() {
+    // `if DEBUG { ... }` tests a const; `Point { ... }` builds a struct
+    let m = parse_str("<mem>", "let a = if DEBUG { 1 } else { 2 };").expect("parse ok");
+    let Item::Let(ld) = &m.items[0] else {
+        panic!("expected Let");
+    };
+    let Expr::If { cond, .. } = &ld.value else {
+        panic!("expected If, got {:?}", ld.value);
+    };
+    assert!(matches!(cond.as_ref(), Expr::Var(id) if id.text == "DEBUG"));
+
+    let m = parse_str("<mem>", "let p = Point { x: 1 };").expect("parse ok");
+    let Item::Let(ld) = &m.items[0] else {
+        panic!("expected Let");
+    };
+    assert!(matches!(ld.value, Expr::StructExpr { .. }));
+}
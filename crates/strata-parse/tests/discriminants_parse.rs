@@ -0,0 +1,27 @@
+// Tests for explicit enum discriminants
+
+use strata_ast::ast::Item;
+use strata_parse::parse_str;
+
+#[test]
+fn parse_explicit_discriminants() {
+    let m = parse_str("<mem>", "enum Level { Low = -1, Mid, High = 10 }").expect("parse ok");
+    let Item::Enum(def) = &m.items[0] else {
+        panic!("expected Enum");
+    };
+    let values: Vec<Option<i64>> = def
+        .variants
+        .iter()
+        .map(|v| v.discriminant.map(|(d, _)| d))
+        .collect();
+    assert_eq!(values, vec![Some(-1), None, Some(10)]);
+    // The variant span covers its discriminant
+    let low = &def.variants[0];
+    assert_eq!((low.span.start, low.span.end), (13, 21));
+}
+
+#[test]
+fn discriminant_must_be_an_int_literal() {
+    assert!(parse_str("<mem>", "enum E { A = x }").is_err());
+    assert!(parse_str("<mem>", "enum E { A = 1.5 }").is_err());
+}
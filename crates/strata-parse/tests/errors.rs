@@ -1,13 +1,118 @@
-use strata_parse::parse_str;
+use strata_ast::diag::Severity;
+use strata_ast::span::Span;
+use strata_parse::{parse_str, ParseError};
 
 #[test]
 fn missing_semicolon_is_error() {
     let err = parse_str("<mem>", "let a = 1").unwrap_err().to_string();
-    assert!(err.contains("expected Semicolon"));
+    assert!(
+        err.contains("expected `;`, found end of file"),
+        "got: {err}"
+    );
 }
 
 #[test]
 fn unexpected_token_top_level() {
-    let err = parse_str("<mem>", "42;").unwrap_err().to_string();
-    assert!(err.contains("unexpected token at top level"));
+    let err = parse_str("<mem>", "42;").unwrap_err();
+    let ParseError::UnexpectedToken {
+        found, expected, ..
+    } = &err
+    else {
+        panic!("expected UnexpectedToken, got: {err}");
+    };
+    assert_eq!(found, "integer `42`");
+    assert!(expected.contains(&"`fn`".to_string()), "got: {expected:?}");
+}
+
+#[test]
+fn unexpected_token_carries_span_and_expected_set() {
+    let err = parse_str("<mem>", "fn f() { 1 2 }").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnexpectedToken {
+            found: "integer `2`".to_string(),
+            expected: vec!["`;`".to_string(), "`=`".to_string(), "`}`".to_string()],
+            span: Span {
+                start: 11,
+                end: 12,
+                ..Span::default()
+            },
+        }
+    );
+    assert!(
+        err.to_string().contains("expected one of `;`, `=`, `}`"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn unterminated_string_is_error() {
+    let err = parse_str("<mem>", "let s = \"abc;").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnterminatedString {
+            span: Span {
+                start: 8,
+                end: 13,
+                ..Span::default()
+            }
+        }
+    );
+}
+
+#[test]
+fn integer_overflow_is_error() {
+    let err = parse_str("<mem>", "let n = 99999999999999999999;").unwrap_err();
+    let ParseError::IntegerOverflow { literal, span } = &err else {
+        panic!("expected IntegerOverflow, got: {err}");
+    };
+    assert_eq!(literal, "99999999999999999999");
+    assert_eq!(span.start, 8);
+}
+
+#[test]
+fn unexpected_character_is_error() {
+    // Previously an unknown character silently ended the file
+    let err = parse_str("<mem>", "let a = 1; @ let b = 2;").unwrap_err();
+    assert_eq!(
+        err,
+        ParseError::UnexpectedChar {
+            ch: '@',
+            span: Span {
+                start: 11,
+                end: 12,
+                ..Span::default()
+            }
+        }
+    );
+
+    let err = parse_str("<mem>", "let a = 1; é").unwrap_err();
+    assert_eq!(
+        err.span(),
+        Span {
+            start: 11,
+            end: 13,
+            ..Span::default()
+        }
+    );
+}
+
+#[test]
+fn invalid_syntax_has_span() {
+    let err = parse_str("<mem>", "fn f() { 1 = 2; }").unwrap_err();
+    let ParseError::InvalidSyntax { message, span } = &err else {
+        panic!("expected InvalidSyntax, got: {err}");
+    };
+    assert_eq!(message, "assignment target must be a variable");
+    assert_eq!(span.start, 9);
+}
+
+#[test]
+fn parse_error_converts_to_diagnostic() {
+    let err = parse_str("<mem>", "let a = 1").unwrap_err();
+    let diag = err.to_diagnostic();
+    assert_eq!(diag.code, "PARSE-UNEXPECTED-TOKEN");
+    assert_eq!(diag.severity, Severity::Error);
+    assert_eq!(diag.span, err.span());
+    assert_eq!(diag.message, err.to_string());
 }
@@ -1,13 +1,37 @@
 //! Tests for parser security limits.
 //!
-//! Note: The actual nesting limit (512) cannot be tested directly because
-//! the test stack would overflow before the check triggers. We test that:
-//! 1. Depth tracking is active (moderate depths work)
-//! 2. The limit constant exists and is reasonable
-//!
-//! The production limit of 512 protects against malicious input in real programs.
+//! Parsing runs on its own thread with a stack sized from the nesting limit,
+//! so the default limit (512) is exercised directly even from small test
+//! thread stacks.
+
+use strata_parse::{
+    parse_str, parse_str_with_options, ParseError, ParseOptions, MAX_NESTING_LIMIT,
+};
+
+/// Parse and return the `TooDeep` error, panicking on any other outcome
+fn expect_too_deep(src: &str, opts: &ParseOptions) -> (u32, u32) {
+    let err = parse_str_with_options("<test>", src, opts).expect_err("expected TooDeep");
+    match err {
+        ParseError::TooDeep { limit, span } => (limit, span.start),
+        _ => panic!("expected ParseError::TooDeep, got: {err}"),
+    }
+}
+
+/// Tiny deterministic PRNG (xorshift64) for the fuzz tests
+struct Rng(u64);
 
-use strata_parse::parse_str;
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[(self.next() % items.len() as u64) as usize]
+    }
+}
 
 /// Test that moderate nesting depths work fine
 /// This verifies the depth tracking doesn't break normal code
@@ -148,3 +172,173 @@ fn test_deep_else_if_chain_errors() {
         err_msg
     );
 }
+
+// ============================================================================
+// NESTING DEPTH LIMIT
+// ============================================================================
+
+#[test]
+fn test_100k_open_parens_reports_too_deep() {
+    let src = format!("let x = {}1;", "(".repeat(100_000));
+    let (limit, _) = expect_too_deep(&src, &ParseOptions::default());
+    assert_eq!(limit, 512);
+}
+
+#[test]
+fn test_too_deep_message_and_span() {
+    let src = format!("let x = {}1{};", "(".repeat(600), ")".repeat(600));
+    let err = parse_str("<test>", &src).unwrap_err();
+    assert!(
+        err.to_string().contains("maximum nesting depth exceeded"),
+        "got: {err}"
+    );
+    // The span points into the run of parens, not at the start of the file
+    let (_, start) = expect_too_deep(&src, &ParseOptions::default());
+    assert!(start > 8 && start < 8 + 600, "span start {start}");
+}
+
+#[test]
+fn test_deep_nesting_of_each_form_errors() {
+    let d = 100_000;
+    let cases = [
+        format!("let x = {}1;", "-".repeat(d)),
+        format!("let x = {}1;", "!".repeat(d)),
+        format!("let x = {}1;", "{".repeat(d)),
+        format!("let x = {}1;", "f(".repeat(d)),
+        format!("let x = {}1;", "if true { ".repeat(d)),
+        format!("fn f(x: {}Int) {{}}", "List<".repeat(d)),
+        format!("fn f(x: {}Int) {{}}", "& ".repeat(d)),
+        format!("fn f() {{ match x {{ {}1 => 1 }} }}", "(".repeat(d)),
+    ];
+    for src in &cases {
+        expect_too_deep(src, &ParseOptions::default());
+    }
+}
+
+#[test]
+fn test_default_limit_is_reachable() {
+    // 500 nested parens stay under the default limit and parse successfully
+    let src = format!("let x = {}1{};", "(".repeat(500), ")".repeat(500));
+    assert!(parse_str("<test>", &src).is_ok());
+}
+
+#[test]
+fn test_configurable_nesting_limit() {
+    let opts = ParseOptions {
+        max_nesting_depth: 8,
+        ..ParseOptions::default()
+    };
+    let shallow = format!("let x = {}1{};", "(".repeat(4), ")".repeat(4));
+    assert!(parse_str_with_options("<test>", &shallow, &opts).is_ok());
+
+    let deep = format!("let x = {}1{};", "(".repeat(20), ")".repeat(20));
+    let (limit, _) = expect_too_deep(&deep, &opts);
+    assert_eq!(limit, 8);
+    // Same input is fine under the default limit
+    assert!(parse_str("<test>", &deep).is_ok());
+}
+
+#[test]
+fn test_nesting_limit_is_clamped_to_the_stack() {
+    // A limit the parser thread's stack can't hold is lowered, not trusted
+    let opts = ParseOptions {
+        max_nesting_depth: u32::MAX,
+        ..ParseOptions::default()
+    };
+    let deep = format!("let x = {}1;", "(".repeat(100_000));
+    let (limit, _) = expect_too_deep(&deep, &opts);
+    assert_eq!(limit, MAX_NESTING_LIMIT);
+
+    let reachable = MAX_NESTING_LIMIT as usize - 10;
+    let src = format!(
+        "let x = {}1{};",
+        "(".repeat(reachable),
+        ")".repeat(reachable)
+    );
+    assert!(parse_str_with_options("<test>", &src, &opts).is_ok());
+}
+
+#[test]
+fn test_configurable_token_limit() {
+    let opts = ParseOptions {
+        max_tokens: 10,
+        ..ParseOptions::default()
+    };
+    let err = parse_str_with_options("<test>", "let x = 1 + 2 + 3 + 4 + 5 + 6;", &opts)
+        .expect_err("token limit should trip");
+    assert!(err.to_string().contains("max 10 tokens"), "got: {err}");
+}
+
+// ============================================================================
+// FUZZ: random bracket soups never crash the parser
+// ============================================================================
+
+#[test]
+fn fuzz_random_nesting_never_overflows() {
+    let openers = ["(", "{ ", "-", "!", "f(", "if x { ", "match y { _ => ", "&"];
+    let fillers = ["1", "x", ")", "}", ",", "+", " ", ";", "=>"];
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    for _ in 0..200 {
+        let depth = (rng.next() % 4000) as usize;
+        let mut src = String::from("let x = ");
+        for _ in 0..depth {
+            src.push_str(rng.pick(&openers));
+            if rng.next().is_multiple_of(8) {
+                src.push_str(rng.pick(&fillers));
+            }
+        }
+        src.push_str("1;");
+        // Must return (Ok or Err) rather than overflow the stack or panic
+        let _ = parse_str("<fuzz>", &src);
+    }
+}
+
+#[test]
+fn fuzz_random_types_and_patterns_never_overflow() {
+    let type_openers = ["List<", "&", "(", "fn(", "Option<"];
+    let pat_openers = ["(", "Opt::Some(", "P { a: ", "_, "];
+    let mut rng = Rng(0x0dd_ba11_cafe_f00d);
+    for _ in 0..100 {
+        let depth = (rng.next() % 3000) as usize;
+        let ty: String = (0..depth).map(|_| rng.pick(&type_openers)).collect();
+        let _ = parse_str("<fuzz>", &format!("fn f(x: {ty}Int) {{}}"));
+        let pat: String = (0..depth).map(|_| rng.pick(&pat_openers)).collect();
+        let _ = parse_str("<fuzz>", &format!("fn f() {{ match x {{ {pat}1 => 1 }} }}"));
+    }
+}
+
+#[test]
+fn test_configurable_source_size_limit() {
+    let opts = ParseOptions {
+        max_source_bytes: 16,
+        ..ParseOptions::default()
+    };
+    assert!(parse_str_with_options("<test>", "let x = 1;", &opts).is_ok());
+    let err = parse_str_with_options("<test>", "let x = 1; let y = 2;", &opts)
+        .expect_err("size limit should trip");
+    assert!(
+        matches!(err, ParseError::SourceTooLarge { len: 21, limit: 16, span } if span.is_empty()),
+        "got: {err:?}"
+    );
+    assert_eq!(err.code(), "PARSE-SOURCE-TOO-LARGE");
+}
+
+#[test]
+fn test_span_offsets_never_wrap() {
+    use strata_ast::span::{FileId, Span, MAX_SOURCE_LEN};
+
+    let file = FileId::default();
+    let past = MAX_SOURCE_LEN + 1;
+    assert_eq!(
+        Span::checked(file, 3, 7).map(|s| (s.start, s.end)),
+        Some((3, 7))
+    );
+    assert_eq!(Span::checked(file, 0, past), None);
+    assert_eq!(Span::checked(file, 7, 3), None);
+
+    // Clamped offsets saturate instead of wrapping to small numbers
+    let span = Span::clamped(file, past, past + 10);
+    assert_eq!((span.start, span.end), (u32::MAX, u32::MAX));
+    let span = Span::clamped(file, 9, 2);
+    assert_eq!((span.start, span.end), (9, 9));
+}
@@ -1,5 +1,5 @@
 use strata_ast::ast::{Expr, Item, Lit};
-use strata_parse::parse_str;
+use strata_parse::{parse_str, ParseError};
 
 #[test]
 fn ints_floats_bools_nil_string_escapes() {
@@ -21,3 +21,231 @@ fn ints_floats_bools_nil_string_escapes() {
     assert!(matches!(take(3), Expr::Lit(Lit::Nil, _)));
     assert!(matches!(take(4), Expr::Lit(Lit::Str(s), _) if s == "hi\n\"there\""));
 }
+
+/// Parse `let x = <src>;` and return the literal value expression
+fn lit(src: &str) -> Expr {
+    let m = parse_str("<mem>", &format!("let x = {src};")).unwrap();
+    let Item::Let(ld) = &m.items[0] else {
+        panic!("expected Let declaration");
+    };
+    ld.value.clone()
+}
+
+fn lit_err(src: &str) -> ParseError {
+    parse_str("<mem>", &format!("let x = {src};")).unwrap_err()
+}
+
+#[test]
+fn numeric_separators() {
+    assert!(matches!(
+        lit("1_000_000"),
+        Expr::Lit(Lit::Int(1_000_000), _)
+    ));
+    assert!(matches!(lit("1_0"), Expr::Lit(Lit::Int(10), _)));
+    assert!(
+        matches!(lit("1_000.000_5"), Expr::Lit(Lit::Float(f), _) if (f - 1000.0005).abs() < 1e-9)
+    );
+}
+
+#[test]
+fn hex_and_binary_literals() {
+    assert!(matches!(lit("0xFF"), Expr::Lit(Lit::Int(255), _)));
+    assert!(matches!(
+        lit("0xdead_beef"),
+        Expr::Lit(Lit::Int(0xdead_beef), _)
+    ));
+    assert!(matches!(lit("0b1010"), Expr::Lit(Lit::Int(10), _)));
+    assert!(matches!(lit("0b_1111_0000"), Expr::Lit(Lit::Int(240), _)));
+    assert!(matches!(
+        lit("0x7FFF_FFFF_FFFF_FFFF"),
+        Expr::Lit(Lit::Int(i64::MAX), _)
+    ));
+    // Span covers the literal as written
+    let Expr::Lit(_, span) = lit("0x1_F") else {
+        panic!("expected literal");
+    };
+    assert_eq!((span.start, span.end), (8, 13));
+}
+
+#[test]
+fn radix_literal_overflow() {
+    for src in ["0x8000_0000_0000_0000", "9_223_372_036_854_775_808"] {
+        let err = lit_err(src);
+        assert!(
+            matches!(err, ParseError::IntegerOverflow { ref literal, .. } if literal == src),
+            "{src}: got {err}"
+        );
+    }
+    let wide = format!("0b1{}", "0".repeat(64));
+    assert!(matches!(lit_err(&wide), ParseError::IntegerOverflow { .. }));
+}
+
+#[test]
+fn malformed_numeric_literals() {
+    for (src, reason) in [
+        ("0x", "no digits"),
+        ("0b", "no digits"),
+        ("0b102", "invalid digit '2' in binary"),
+        ("0xFG", "invalid digit 'G' in hexadecimal"),
+        ("1_", "`_` must separate digits"),
+        ("1_.5", "`_` must separate digits"),
+    ] {
+        let err = lit_err(src);
+        assert!(
+            matches!(&err, ParseError::InvalidNumber { literal, reason: r, .. }
+                if literal == src && r.contains(reason)),
+            "{src}: got {err}"
+        );
+    }
+}
+
+#[test]
+fn int_before_range_operator_still_lexes() {
+    let m = parse_str(
+        "<mem>",
+        "fn f(n: Int) -> Int { match n { 0x0..0b10 => 1, _ => 0 } }",
+    );
+    assert!(m.is_ok(), "{:?}", m.err());
+}
+
+fn str_lit(src: &str) -> String {
+    match lit(src) {
+        Expr::Lit(Lit::Str(s), _) => s,
+        other => panic!("expected string literal, got {other:?}"),
+    }
+}
+
+#[test]
+fn string_escapes() {
+    assert_eq!(str_lit(r#""a\tb\r\n\0""#), "a\tb\r\n\0");
+    assert_eq!(str_lit(r#""q\"\\""#), "q\"\\");
+    assert_eq!(str_lit(r#""\u{41}\u{e9}\u{1F600}""#), "Aé😀");
+    // Non-ASCII source text passes through unchanged
+    assert_eq!(str_lit(r#""héllo → ok""#), "héllo → ok");
+}
+
+#[test]
+fn invalid_escape_span_covers_escape() {
+    for (src, escape, start) in [
+        (r#""ab\qcd""#, r"\q", 11),
+        (r#""\u{110000}""#, r"\u{110000}", 9),
+        (r#""x\u{}""#, r"\u{}", 10),
+        (r#""\u41""#, r"\u", 9),
+        (r#""\u{1234567}""#, r"\u{1234567}", 9),
+        (r#""\é""#, r"\é", 9),
+    ] {
+        let err = lit_err(src);
+        let ParseError::InvalidEscape { escape: got, span } = &err else {
+            panic!("{src}: expected InvalidEscape, got {err}");
+        };
+        assert_eq!(got, escape, "{src}");
+        assert_eq!(span.start, start, "{src}");
+        assert_eq!((span.end - span.start) as usize, escape.len(), "{src}");
+    }
+}
+
+#[test]
+fn raw_strings() {
+    assert_eq!(str_lit(r#"r"C:\path\n""#), r"C:\path\n");
+    assert_eq!(str_lit(r###"r#"say "hi""#"###), r#"say "hi""#);
+    assert_eq!(str_lit(r####"r##"a "# b"##"####), r##"a "# b"##);
+    assert_eq!(str_lit(r#"r"""#), "");
+    // `r` alone is still an identifier
+    assert!(matches!(lit("r"), Expr::Var(id) if id.text == "r"));
+}
+
+#[test]
+fn unterminated_raw_string() {
+    let err = lit_err(r##"r#"abc"##);
+    assert!(
+        matches!(err, ParseError::UnterminatedString { span } if span.start == 8),
+        "got {err}"
+    );
+}
+
+#[test]
+fn escape_str_round_trips() {
+    for s in [
+        "plain",
+        "tab\there",
+        "q\"b\\s",
+        "nl\r\n",
+        "nul\0",
+        "bell\u{7}",
+        "é😀",
+    ] {
+        let src = format!("\"{}\"", strata_parse::escape_str(s));
+        assert_eq!(str_lit(&src), s, "{src}");
+    }
+}
+
+#[test]
+fn multiline_string_strips_closing_indent() {
+    let src = "\"\"\"\n        server {\n            port = 80\n\n        }\n        \"\"\"";
+    assert_eq!(str_lit(src), "server {\n    port = 80\n\n}");
+}
+
+#[test]
+fn multiline_string_edge_cases() {
+    // No content lines
+    assert_eq!(str_lit("\"\"\"\n\"\"\""), "");
+    // Closing delimiter at column 0 keeps all indentation
+    assert_eq!(str_lit("\"\"\"\n  a\n\"\"\""), "  a");
+    // Escapes and embedded quotes
+    assert_eq!(
+        str_lit("\"\"\"\n  say \"hi\"\\t\\\"\"\"\n  \"\"\""),
+        "say \"hi\"\t\"\"\""
+    );
+    // CRLF line endings
+    assert_eq!(str_lit("\"\"\"\r\n  a\r\n  b\r\n  \"\"\""), "a\nb");
+    // Trailing `\` joins lines and drops the next line's indentation
+    assert_eq!(
+        str_lit("\"\"\"\n  one \\\n     two\n  three\n  \"\"\""),
+        "one two\nthree"
+    );
+}
+
+#[test]
+fn multiline_string_errors() {
+    let err = lit_err("\"\"\"text\n  \"\"\"");
+    assert!(
+        err.to_string().contains("must start on the line after"),
+        "got {err}"
+    );
+
+    let err = lit_err("\"\"\"\n  a\n  b\"\"\"");
+    assert!(
+        err.to_string().contains("must be on its own line"),
+        "got {err}"
+    );
+
+    // Under-indented line: span covers that line
+    let err = lit_err("\"\"\"\n    a\n  b\n    \"\"\"");
+    let ParseError::InvalidSyntax { message, span } = &err else {
+        panic!("got {err}");
+    };
+    assert!(message.contains("indented less"), "{message}");
+    assert_eq!((span.start, span.end), (18, 21));
+
+    let err = lit_err("\"\"\"\n  a\n");
+    assert!(
+        matches!(err, ParseError::UnterminatedString { .. }),
+        "got {err}"
+    );
+}
+
+#[test]
+fn backslash_newline_continues_plain_string() {
+    assert_eq!(str_lit("\"alpha \\\n      beta\""), "alpha beta");
+    assert_eq!(str_lit("\"a\\\r\n  b\""), "ab");
+}
+
+#[test]
+fn adjacent_string_literals_concatenate() {
+    let e = lit("\"ab\" \"cd\"\n    r\"\\e\"");
+    let Expr::Lit(Lit::Str(s), span) = e else {
+        panic!("expected string literal");
+    };
+    assert_eq!(s, "abcd\\e");
+    assert_eq!((span.start, span.end), (8, 27));
+}
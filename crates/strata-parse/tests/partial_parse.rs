@@ -0,0 +1,35 @@
+// Tests for `_` holes in call arguments (partial application)
+
+use strata_ast::ast::{Expr, Item};
+use strata_parse::parse_str;
+
+fn tail(src: &str) -> Expr {
+    let m = parse_str("<mem>", src).expect("parse ok");
+    match &m.items[0] {
+        Item::Fn(d) => *d.body.tail.clone().expect("tail"),
+        other => panic!("unexpected item {:?}", other),
+    }
+}
+
+#[test]
+fn lone_underscore_argument_is_a_hole() {
+    let Expr::Call { args, names, .. } = tail("fn f() { g(1, _, x: _) }") else {
+        panic!("expected a call");
+    };
+    assert!(matches!(args[0], Expr::Lit(..)));
+    assert!(matches!(args[1], Expr::Hole(span) if (span.start, span.end) == (14, 15)));
+    assert!(matches!(args[2], Expr::Hole(_)));
+    assert_eq!(names[2].as_ref().map(|n| n.text.as_str()), Some("x"));
+    assert!(tail("fn f() { g(1, _) }").is_partial_call());
+    assert!(!tail("fn f() { g(1, 2) }").is_partial_call());
+}
+
+#[test]
+fn underscore_in_a_larger_argument_is_a_name() {
+    // Only a whole argument is a hole; `_ + 1` reads a variable named `_`
+    let Expr::Call { args, .. } = tail("fn f() { g(_ + 1, (_)) }") else {
+        panic!("expected a call");
+    };
+    assert!(matches!(&args[0], Expr::Binary { lhs, .. } if matches!(**lhs, Expr::Var(_))));
+    assert!(matches!(&args[1], Expr::Paren { inner, .. } if matches!(**inner, Expr::Var(_))));
+}
@@ -0,0 +1,71 @@
+// Tests for the pipe operator: `x |> f |> g(2)` parses as `g(f(x), 2)`
+
+use strata_ast::ast::{BinOp, Expr, Item};
+use strata_parse::parse_str;
+
+fn val(m: &str) -> Expr {
+    let module = parse_str("<mem>", &format!("let v = {m};")).unwrap();
+    let Item::Let(ld) = &module.items[0] else {
+        panic!("expected Let declaration");
+    };
+    ld.value.clone()
+}
+
+/// `e` as the call form it parses to: `g(f(x), 2)`
+fn show(e: &Expr) -> String {
+    match e {
+        Expr::Var(id) => id.text.clone(),
+        Expr::Lit(..) => "1".to_string(),
+        Expr::Hole(_) => "_".to_string(),
+        Expr::Paren { inner, .. } => format!("({})", show(inner)),
+        Expr::Binary { lhs, rhs, .. } => format!("{} op {}", show(lhs), show(rhs)),
+        Expr::Call { callee, args, .. } => {
+            let args: Vec<String> = args.iter().map(show).collect();
+            format!("{}({})", show(callee), args.join(", "))
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn pipes_are_calls_with_the_left_side_first() {
+    for (src, call) in [
+        ("x |> f", "f(x)"),
+        ("x |> f |> g(1)", "g(f(x), 1)"),
+        ("x |> g(1, _)", "g(1, x)"),
+        ("x |> g(_, _)", "g(x, _)"),
+        ("x |> (g(1))", "(g(1))(x)"),
+        ("h(x |> f)", "h(f(x))"),
+    ] {
+        assert_eq!(show(&val(src)), call, "{src}");
+    }
+}
+
+#[test]
+fn pipe_binds_loosest() {
+    // `a || b |> f` is `f(a || b)`, and so is `a + 1 |> f` with `+`
+    let e = val("a || b |> f");
+    assert!(
+        matches!(&e, Expr::Call { args, .. } if matches!(args[0], Expr::Binary { op: BinOp::Or, .. })),
+        "{e:?}"
+    );
+    assert_eq!(show(&val("a + 1 |> f |> g")), "g(f(a op 1))");
+
+    // The call spans the whole pipeline
+    let Expr::Call { span, .. } = val("x |> f |> g(1)") else {
+        panic!("expected a call");
+    };
+    assert_eq!((span.start, span.end), (8, 22));
+}
+
+#[test]
+fn named_arguments_keep_their_names() {
+    let Expr::Call { names, .. } = val("x |> draw(y: 1)") else {
+        panic!("expected a call");
+    };
+    let names: Vec<_> = names
+        .iter()
+        .map(|n| n.as_ref().map(|n| n.text.as_str()))
+        .collect();
+    assert_eq!(names, [None, Some("y")]);
+}
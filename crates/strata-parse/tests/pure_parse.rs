@@ -0,0 +1,45 @@
+// Tests for `pure fn` declarations
+
+use strata_ast::ast::Item;
+use strata_parse::parse_str;
+
+#[test]
+fn pure_fn_is_a_function_marked_pure() {
+    let src = "fn f() -> Int { 1 } pure fn g() -> Int { f() } pub pure fn h() -> Int { 2 }";
+    let m = parse_str("<mem>", src).expect("parse ok");
+    let pure: Vec<(&str, bool, bool)> = m
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::Fn(d) => (d.name.text.as_str(), d.pure, d.public),
+            other => panic!("unexpected item {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        pure,
+        [("f", false, false), ("g", true, false), ("h", true, true)]
+    );
+    // The item starts at `pure`, or `pub` before it
+    let starts: Vec<u32> = m.items[1..]
+        .iter()
+        .map(|item| match item {
+            Item::Fn(d) => d.span.start,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(
+        starts,
+        [
+            src.find("pure").unwrap() as u32,
+            src.find("pub").unwrap() as u32
+        ]
+    );
+}
+
+#[test]
+fn pure_is_followed_by_fn() {
+    let err = parse_str("<mem>", "pure struct S { x: Int }")
+        .expect_err("only fn follows pure")
+        .to_string();
+    assert!(err.contains("`fn`"), "got: {err}");
+}
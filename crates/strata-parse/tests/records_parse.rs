@@ -0,0 +1,112 @@
+// Tests for anonymous record parsing: expressions, field access, types,
+// and patterns
+
+use strata_ast::ast::{Expr, Item, Pat, Stmt, TypeExpr};
+use strata_parse::parse_str;
+
+/// Helper: parse an expression from a let statement
+fn parse_expr(src: &str) -> Expr {
+    let m = parse_str("<mem>", &format!("let x = {};", src)).expect("parse ok");
+    let Item::Let(ld) = &m.items[0] else {
+        panic!("expected Let");
+    };
+    ld.value.clone()
+}
+
+/// Helper: the pattern of the first arm of `match v { <arm> }`
+fn parse_arm_pattern(arm: &str) -> Pat {
+    match parse_expr(&format!("match v {{ {} }}", arm)) {
+        Expr::Match { arms, .. } => arms[0].pat.clone(),
+        other => panic!("expected Match, got {:?}", other),
+    }
+}
+
+#[test]
+fn parse_record_expression() {
+    let Expr::Record { fields, .. } = parse_expr("{ x: 1, y: 2, }") else {
+        panic!("expected Record");
+    };
+    let names: Vec<&str> = fields.iter().map(|f| f.name.text.as_str()).collect();
+    assert_eq!(names, ["x", "y"]);
+}
+
+#[test]
+fn braces_without_a_field_are_still_a_block() {
+    assert!(matches!(parse_expr("{ x }"), Expr::Block(_)));
+    assert!(matches!(parse_expr("{ 1 }"), Expr::Block(_)));
+    assert!(matches!(parse_expr("{}"), Expr::Block(_)));
+}
+
+#[test]
+fn parse_field_access_chains_left_to_right() {
+    // `r.a.b(1)` is `((r.a).b)(1)`
+    let Expr::Call { callee, args, .. } = parse_expr("r.a.b(1)") else {
+        panic!("expected Call");
+    };
+    assert_eq!(args.len(), 1);
+    let Expr::Field { expr, field, .. } = callee.as_ref() else {
+        panic!("expected Field");
+    };
+    assert_eq!(field.text, "b");
+    assert!(matches!(expr.as_ref(), Expr::Field { field, .. } if field.text == "a"));
+}
+
+#[test]
+fn field_access_binds_tighter_than_operators() {
+    let Expr::Binary { lhs, .. } = parse_expr("-p.x + { x: 1 }.x") else {
+        panic!("expected Binary");
+    };
+    let Expr::Unary { expr, .. } = lhs.as_ref() else {
+        panic!("expected Unary");
+    };
+    assert!(matches!(expr.as_ref(), Expr::Field { .. }));
+}
+
+#[test]
+fn parse_record_type() {
+    let m = parse_str("<mem>", "fn f(p: {x: Int, y: {z: Bool}}) -> Int { 0 }").expect("parse ok");
+    let Item::Fn(decl) = &m.items[0] else {
+        panic!("expected Fn");
+    };
+    let Some(TypeExpr::Record(fields, _)) = &decl.params[0].ty else {
+        panic!("expected a record type");
+    };
+    assert_eq!(fields[0].name.text, "x");
+    assert!(matches!(fields[1].ty, TypeExpr::Record(ref inner, _) if inner.len() == 1));
+}
+
+#[test]
+fn empty_record_type_is_rejected() {
+    let err = parse_str("<mem>", "fn f(p: {}) -> Int { 0 }").expect_err("empty record type");
+    assert!(format!("{err:?}").contains("at least one field"), "{err:?}");
+}
+
+#[test]
+fn parse_record_patterns() {
+    let Pat::Record { fields, rest, .. } = parse_arm_pattern("{ x, y: 0, .. } => 1") else {
+        panic!("expected record pattern");
+    };
+    assert!(rest);
+    assert!(matches!(&fields[0].pat, Pat::Ident(id) if id.text == "x"));
+    assert!(matches!(&fields[1].pat, Pat::Literal(..)));
+
+    let Pat::Record { rest, .. } = parse_arm_pattern("{ x } => 1") else {
+        panic!("expected record pattern");
+    };
+    assert!(!rest);
+}
+
+#[test]
+fn parse_record_destructuring_let() {
+    let m = parse_str("<mem>", "fn f() -> Int { let { x, .. } = r; x }").expect("parse ok");
+    let Item::Fn(decl) = &m.items[0] else {
+        panic!("expected Fn");
+    };
+    assert!(matches!(
+        &decl.body.stmts[0],
+        Stmt::Let {
+            pat: Pat::Record { rest: true, .. },
+            ..
+        }
+    ));
+}
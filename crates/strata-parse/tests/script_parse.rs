@@ -0,0 +1,52 @@
+// Tests for script mode: top-level statements form a synthesized `main`
+
+use strata_ast::ast::{Expr, Item, Stmt};
+use strata_parse::{parse_script, parse_str, ParseError};
+
+#[test]
+fn bare_expression_becomes_main() {
+    let m = parse_script("<mem>", "1 + 2").expect("parse ok");
+    let [Item::Fn(main)] = m.items.as_slice() else {
+        panic!("expected a single synthesized main");
+    };
+    assert_eq!(main.name.text, "main");
+    assert!(main.params.is_empty());
+    assert!(main.body.stmts.is_empty());
+    assert!(matches!(
+        main.body.tail.as_deref(),
+        Some(Expr::Binary { .. })
+    ));
+    assert_eq!((main.body.span.start, main.body.span.end), (0, 5));
+}
+
+#[test]
+fn statements_between_items_keep_their_order() {
+    let src = "let x = 1;\nfn f(n: Int) -> Int { n }\nx = f(x);\nx";
+    let m = parse_script("<mem>", src).expect("parse ok");
+    assert!(matches!(&m.items[0], Item::Fn(f) if f.name.text == "f"));
+    let Item::Fn(main) = &m.items[1] else {
+        panic!("expected synthesized main");
+    };
+    assert!(matches!(main.body.stmts[0], Stmt::Let { .. }));
+    assert!(matches!(main.body.stmts[1], Stmt::Assign { .. }));
+    assert!(matches!(main.body.tail.as_deref(), Some(Expr::Var(_))));
+    assert_eq!(main.body.span.start, 0);
+    assert_eq!(main.body.span.end as usize, src.len());
+}
+
+#[test]
+fn script_of_items_only_has_no_main() {
+    let m = parse_script("<mem>", "fn f() -> Int { 1 }").expect("parse ok");
+    assert_eq!(m.items.len(), 1);
+}
+
+#[test]
+fn script_cannot_also_declare_main() {
+    let err = parse_script("<mem>", "fn main() -> Int { 1 }\n2").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidSyntax { span, .. } if span.start == 3));
+}
+
+#[test]
+fn top_level_expression_needs_script_mode() {
+    assert!(parse_str("<mem>", "1 + 2").is_err());
+}
@@ -0,0 +1,122 @@
+//! Token classification for highlighting (`semantic_tokens`).
+
+use strata_parse::{semantic_token_modifiers, semantic_tokens, TokenClass, TokenModifier};
+
+/// `(text, class)` for every classified span
+fn classified(src: &str) -> Vec<(&str, TokenClass)> {
+    semantic_tokens(src)
+        .into_iter()
+        .map(|(span, class)| (&src[span.start as usize..span.end as usize], class))
+        .collect()
+}
+
+fn class_of(src: &str, text: &str) -> TokenClass {
+    classified(src)
+        .into_iter()
+        .find(|(t, _)| *t == text)
+        .unwrap_or_else(|| panic!("{text} not classified in {src}"))
+        .1
+}
+
+#[test]
+fn classifies_keywords_types_caps_and_effects() {
+    let src = "extern fn read(fs: &FsCap, p: String) -> String & {Fs};";
+    assert_eq!(
+        classified(src),
+        [
+            ("extern", TokenClass::Keyword),
+            ("fn", TokenClass::Keyword),
+            ("read", TokenClass::Function),
+            ("fs", TokenClass::Variable),
+            ("FsCap", TokenClass::Capability),
+            ("p", TokenClass::Variable),
+            ("String", TokenClass::Type),
+            ("String", TokenClass::Type),
+            ("Fs", TokenClass::Effect),
+        ]
+    );
+}
+
+#[test]
+fn call_sites_of_declared_functions_are_functions() {
+    let src = "fn helper(x: Int) -> Int { x } fn main(f: Int) -> Int { let y = helper(1); f }";
+    let classes = classified(src);
+    assert!(classes.contains(&("helper", TokenClass::Function)));
+    assert_eq!(
+        classes.iter().filter(|(t, _)| *t == "helper").count(),
+        2,
+        "declaration and call"
+    );
+    assert_eq!(class_of(src, "y"), TokenClass::Variable);
+    assert_eq!(class_of(src, "1"), TokenClass::Number);
+}
+
+#[test]
+fn declared_types_params_and_variants() {
+    let src = r#"
+        enum Opt<T> { Some(T), None }
+        struct Box2 { v: Opt<Int> }
+        fn f(o: Opt<Int>) -> Int { match o { Opt::Some(x) => x, Opt::None => 0 } }
+    "#;
+    let classes = classified(src);
+    for name in ["Opt", "T", "Box2", "Int"] {
+        assert!(
+            classes.contains(&(name, TokenClass::Type)),
+            "{name} should be a type: {classes:?}"
+        );
+    }
+    assert!(classes
+        .iter()
+        .filter(|(t, _)| *t == "Some" || *t == "None")
+        .all(|(_, c)| *c == TokenClass::Variant));
+}
+
+#[test]
+fn comments_and_strings_are_classified() {
+    let src = "// lead\nlet s = \"a // b\"; /* tail /* nested */ */";
+    assert_eq!(
+        classified(src),
+        [
+            ("// lead", TokenClass::Comment),
+            ("let", TokenClass::Keyword),
+            ("s", TokenClass::Variable),
+            ("\"a // b\"", TokenClass::String),
+            ("/* tail /* nested */ */", TokenClass::Comment),
+        ]
+    );
+}
+
+#[test]
+fn unparseable_source_is_classified_up_to_lexer_error() {
+    // Not valid syntax, but still highlighted; stops at the bad character
+    let src = "let let fn 1 $ // not reached";
+    assert_eq!(
+        classified(src),
+        [
+            ("let", TokenClass::Keyword),
+            ("let", TokenClass::Keyword),
+            ("fn", TokenClass::Keyword),
+            ("1", TokenClass::Number),
+        ]
+    );
+}
+
+#[test]
+fn uses_of_deprecated_items_are_marked() {
+    let src = r#"#[deprecated("use area")] pub fn size() -> Int { 1 }
+#[deprecated] struct Old { x: Int }
+fn main(o: Old) -> Int { size() + o.size }"#;
+    let marked: Vec<(&str, TokenModifier)> = semantic_token_modifiers(src)
+        .into_iter()
+        .map(|(span, m)| (&src[span.start as usize..span.end as usize], m))
+        .collect();
+    // Not the declarations, and not a field that shares the name
+    assert_eq!(
+        marked,
+        [
+            ("Old", TokenModifier::Deprecated),
+            ("size", TokenModifier::Deprecated),
+        ]
+    );
+    assert_eq!(TokenModifier::Deprecated.name(), "deprecated");
+}
@@ -165,3 +165,28 @@ fn test_parse_triple_qualified_type_path() {
         panic!("Expected Let item");
     }
 }
+
+/// Spans from a `SourceDb` file carry its id, in the module and in errors,
+/// so locations render against the right file
+#[test]
+fn test_parse_source_stamps_file_id() {
+    use strata_ast::span::{FileId, SourceDb};
+    use strata_parse::parse_source;
+
+    let mut db = SourceDb::new();
+    let main = db.add("main.strata", "fn main() -> Int { 0 }");
+    let lib = db.add("lib.strata", "fn helper() -> Int {\n    1 +\n}");
+    assert_eq!(main, FileId::default());
+    assert_ne!(lib, main);
+
+    let module = parse_source(&db, main).expect("parse failed");
+    let strata_ast::ast::Item::Fn(decl) = &module.items[0] else {
+        panic!("Expected Fn item");
+    };
+    assert_eq!(decl.span.file, main);
+    assert_eq!(format!("{:?}", decl.span), "Span { start: 0, end: 22 }");
+
+    let err = parse_source(&db, lib).expect_err("should fail");
+    assert_eq!(err.span().file, lib);
+    assert_eq!(db.location(err.span()), "lib.strata:3:1");
+}
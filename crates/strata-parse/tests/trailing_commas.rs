@@ -0,0 +1,146 @@
+//! Trailing commas are accepted in every comma-separated list.
+
+use strata_ast::ast::{Expr, Item, Pat, Stmt, TypeExpr, VariantFields};
+use strata_parse::parse_str;
+
+fn parse_ok(src: &str) -> strata_ast::ast::Module {
+    parse_str("<mem>", src).unwrap_or_else(|e| panic!("{src}: {e}"))
+}
+
+fn fn_body_tail(src: &str) -> Expr {
+    let m = parse_ok(src);
+    let Item::Fn(f) = &m.items[0] else {
+        panic!("expected fn");
+    };
+    *f.body.tail.clone().expect("tail expression")
+}
+
+#[test]
+fn trailing_comma_in_params_and_call_args() {
+    let m = parse_ok("fn add(a: Int, b: Int,) -> Int { a + b } fn main() -> Int { add(1, 2,) }");
+    let Item::Fn(add) = &m.items[0] else {
+        panic!("expected fn");
+    };
+    assert_eq!(add.params.len(), 2);
+    let Item::Fn(main) = &m.items[1] else {
+        panic!("expected fn");
+    };
+    let Some(tail) = &main.body.tail else {
+        panic!("expected tail");
+    };
+    assert!(matches!(tail.as_ref(), Expr::Call { args, .. } if args.len() == 2));
+}
+
+#[test]
+fn trailing_comma_in_extern_params() {
+    let m = parse_ok("extern fn read(fs: FsCap, path: String,) -> String & {Fs,};");
+    let Item::ExternFn(e) = &m.items[0] else {
+        panic!("expected extern fn");
+    };
+    assert_eq!(e.params.len(), 2);
+}
+
+#[test]
+fn trailing_comma_in_type_params_and_args() {
+    let m = parse_ok(
+        "enum Either<L, R,> { Left(L,), Right(R) } fn f(x: Either<Int, Bool,>) -> Int { 1 }",
+    );
+    let Item::Enum(e) = &m.items[0] else {
+        panic!("expected enum");
+    };
+    assert_eq!(e.type_params.len(), 2);
+    assert!(matches!(&e.variants[0].fields, VariantFields::Tuple(tys) if tys.len() == 1));
+    let Item::Fn(f) = &m.items[1] else {
+        panic!("expected fn");
+    };
+    assert!(matches!(
+        f.params[0].ty.as_ref(),
+        Some(TypeExpr::App { args, .. }) if args.len() == 2
+    ));
+}
+
+#[test]
+fn trailing_comma_in_fn_types_and_tuple_types() {
+    let m = parse_ok("fn apply(f: fn(Int, Bool,) -> Int, t: (Int, Bool,)) -> Int { 1 }");
+    let Item::Fn(f) = &m.items[0] else {
+        panic!("expected fn");
+    };
+    assert!(matches!(
+        f.params[0].ty.as_ref(),
+        Some(TypeExpr::Arrow { params, .. }) if params.len() == 2
+    ));
+    assert!(matches!(
+        f.params[1].ty.as_ref(),
+        Some(TypeExpr::Tuple(elems, _)) if elems.len() == 2
+    ));
+}
+
+#[test]
+fn trailing_comma_in_tuples() {
+    assert!(matches!(
+        fn_body_tail("fn f() -> (Int, Int) { (1, 2,) }"),
+        Expr::Tuple { elems, .. } if elems.len() == 2
+    ));
+}
+
+#[test]
+fn trailing_comma_in_struct_defs_literals_and_patterns() {
+    let m = parse_ok(
+        r#"
+        struct P { x: Int, y: Int, }
+        fn f(p: P) -> Int {
+            let q = P { x: 1, y: 2, };
+            match p { P { x, y: _, } => x }
+        }
+    "#,
+    );
+    let Item::Struct(s) = &m.items[0] else {
+        panic!("expected struct");
+    };
+    assert_eq!(s.fields.len(), 2);
+    let Item::Fn(f) = &m.items[1] else {
+        panic!("expected fn");
+    };
+    let Stmt::Let { value, .. } = &f.body.stmts[0] else {
+        panic!("expected let");
+    };
+    assert!(matches!(value, Expr::StructExpr { fields, .. } if fields.len() == 2));
+    let Some(tail) = &f.body.tail else {
+        panic!("expected tail");
+    };
+    let Expr::Match { arms, .. } = tail.as_ref() else {
+        panic!("expected match");
+    };
+    assert!(matches!(&arms[0].pat, Pat::Struct { fields, .. } if fields.len() == 2));
+}
+
+#[test]
+fn trailing_comma_in_variant_and_tuple_patterns() {
+    let tail = fn_body_tail(
+        r#"
+        fn f(x: Opt<(Int, Int)>) -> Int {
+            match x { Opt::Some((a, b,),) => a, Opt::None => 0, }
+        }
+    "#,
+    );
+    let Expr::Match { arms, .. } = tail else {
+        panic!("expected match");
+    };
+    let Pat::Variant { fields, .. } = &arms[0].pat else {
+        panic!("expected variant pattern");
+    };
+    assert_eq!(fields.len(), 1);
+    assert!(matches!(&fields[0], Pat::Tuple(elems, _) if elems.len() == 2));
+}
+
+#[test]
+fn lone_or_doubled_commas_still_rejected() {
+    for src in [
+        "fn f(,) -> Int { 1 }",
+        "fn f(a: Int,,) -> Int { 1 }",
+        "let x = g(1,,);",
+        "enum E<,> { A }",
+    ] {
+        assert!(parse_str("<mem>", src).is_err(), "{src} should not parse");
+    }
+}
@@ -0,0 +1,59 @@
+// Tests for `use Enum::*;` and `use Enum::Variant;` items
+
+use strata_ast::ast::Item;
+use strata_parse::{parse_script, parse_str};
+
+/// Helper: `(enum, variant)` for each `use` item of `src`
+fn imports(src: &str) -> Vec<(String, Option<String>)> {
+    let m = parse_str("<mem>", src).expect("parse ok");
+    m.items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Use(u) => Some((
+                u.enum_name.text.clone(),
+                u.variant.as_ref().map(|v| v.text.clone()),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn glob_and_single_variant_imports() {
+    assert_eq!(
+        imports("use Option::*; enum Shape { Dot } use Shape::Dot;"),
+        [
+            ("Option".to_string(), None),
+            ("Shape".to_string(), Some("Dot".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn use_span_covers_the_semicolon() {
+    let m = parse_str("<mem>", "use Option::Some;").expect("parse ok");
+    let Item::Use(u) = &m.items[0] else {
+        panic!("expected Use");
+    };
+    assert_eq!((u.span.start, u.span.end), (0, 17));
+}
+
+#[test]
+fn malformed_imports_are_rejected() {
+    for src in [
+        "use Option;",
+        "use Option::*",
+        "use Option::Some::x;",
+        "use *;",
+        "use Option::(Some);",
+    ] {
+        assert!(parse_str("<mem>", src).is_err(), "{src}");
+    }
+}
+
+#[test]
+fn scripts_accept_use_items() {
+    let m = parse_script("<mem>", "use Option::*;\nSome(1)").expect("parse ok");
+    assert!(matches!(&m.items[0], Item::Use(u) if u.variant.is_none()));
+    assert!(matches!(&m.items[1], Item::Fn(d) if d.name.text == "main"));
+}
@@ -0,0 +1,79 @@
+// Tests for `pub` visibility on functions, structs, and enums
+
+use strata_ast::ast::Item;
+use strata_parse::{parse_script, parse_str};
+
+/// Helper: whether each item of `src` is declared `pub`
+fn visibility(src: &str) -> Vec<bool> {
+    let m = parse_str("<mem>", src).expect("parse ok");
+    m.items
+        .iter()
+        .map(|item| match item {
+            Item::Fn(d) => d.public,
+            Item::Struct(d) => d.public,
+            Item::Enum(d) => d.public,
+            other => panic!("unexpected item {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn items_are_private_unless_pub() {
+    assert_eq!(
+        visibility("fn a() -> Int { 0 } pub fn b() -> Int { 0 }"),
+        [false, true]
+    );
+    assert_eq!(
+        visibility("struct A { x: Int } pub struct B(Int); pub enum C { D } enum E { F }"),
+        [false, true, true, false]
+    );
+}
+
+#[test]
+fn pub_combines_with_other_modifiers() {
+    let m = parse_str(
+        "<mem>",
+        "pub linear struct Token(FsCap); pub transparent struct Path(String);",
+    )
+    .expect("parse ok");
+    let Item::Struct(token) = &m.items[0] else {
+        panic!("expected Struct");
+    };
+    assert!(token.public && token.linear);
+    let Item::Struct(path) = &m.items[1] else {
+        panic!("expected Struct");
+    };
+    assert!(path.public && path.transparent);
+}
+
+#[test]
+fn pub_item_span_starts_at_pub() {
+    let m = parse_str("<mem>", "pub fn f() -> Int { 0 }").expect("parse ok");
+    let Item::Fn(decl) = &m.items[0] else {
+        panic!("expected Fn");
+    };
+    assert_eq!(decl.span.start, 0);
+}
+
+#[test]
+fn pub_needs_a_definition() {
+    for src in [
+        "pub let x = 1;",
+        "pub const N: Int = 1;",
+        "pub extern fn f() -> Int;",
+    ] {
+        let err = parse_str("<mem>", src).expect_err("pub on a non-definition");
+        assert!(format!("{err:?}").contains("`fn`"), "{src}: {err:?}");
+    }
+}
+
+#[test]
+fn scripts_accept_pub_items() {
+    let m = parse_script(
+        "<mem>",
+        "pub fn double(n: Int) -> Int { n * 2 }\ndouble(21)",
+    )
+    .expect("parse ok");
+    assert!(matches!(&m.items[0], Item::Fn(d) if d.public && d.name.text == "double"));
+    assert!(matches!(&m.items[1], Item::Fn(d) if !d.public && d.name.text == "main"));
+}
@@ -0,0 +1,156 @@
+//! Exhaustiveness checker benchmarks.
+//!
+//! Run with `cargo bench -p strata-types --bench exhaustive`. Uses a plain
+//! timing loop (no external harness) and reports the median per scenario.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use strata_ast::span::Span;
+use strata_types::adt::{AdtDef, AdtRegistry, VariantDef};
+use strata_types::exhaustive::{
+    ExhaustivenessChecker, LiteralPat, PatternMatrix, PatternRow, SimplifiedPat,
+};
+use strata_types::infer::ty::{Ty, TypeVarId};
+
+const ITERATIONS: usize = 20;
+
+fn span() -> Span {
+    Span::default()
+}
+
+/// Registry with `Wide` (`n` unit variants) and generic `Opt<T>`
+fn registry(n: usize) -> AdtRegistry {
+    let mut reg = AdtRegistry::with_builtins();
+    let variants = (0..n).map(|i| VariantDef::unit(format!("V{i}"))).collect();
+    reg.register(AdtDef::new_enum("Wide", vec![], variants))
+        .expect("register Wide");
+    reg.register(AdtDef::new_enum(
+        "Opt",
+        vec!["T".to_string()],
+        vec![
+            VariantDef::tuple("Some", vec![Ty::Var(TypeVarId(0))]),
+            VariantDef::unit("None"),
+        ],
+    ))
+    .expect("register Opt");
+    reg
+}
+
+fn ctor(name: &str, args: Vec<SimplifiedPat>) -> SimplifiedPat {
+    SimplifiedPat::Constructor {
+        name: name.to_string(),
+        args,
+    }
+}
+
+/// One arm per variant of a `variants`-wide enum
+fn wide_enum(variants: usize) -> PatternMatrix {
+    let ty = Ty::adt("Wide", vec![]);
+    let rows = (0..variants)
+        .map(|i| PatternRow::new(vec![ctor(&format!("Wide::V{i}"), vec![])], i))
+        .collect();
+    PatternMatrix::with_rows(rows, vec![ty])
+}
+
+/// `Some^d(None)` for every `d < depth`, plus `Some^depth(_)`
+fn deep_nesting(depth: usize) -> PatternMatrix {
+    let mut ty = Ty::int();
+    for _ in 0..depth {
+        ty = Ty::adt("Opt", vec![ty]);
+    }
+    let nest =
+        |d: usize, inner: SimplifiedPat| (0..d).fold(inner, |p, _| ctor("Opt::Some", vec![p]));
+    let mut rows: Vec<PatternRow> = (0..depth)
+        .map(|d| PatternRow::new(vec![nest(d, ctor("Opt::None", vec![]))], d))
+        .collect();
+    rows.push(PatternRow::new(
+        vec![nest(depth, SimplifiedPat::Wildcard)],
+        depth,
+    ));
+    PatternMatrix::with_rows(rows, vec![ty])
+}
+
+/// Every combination of `cols` Bool columns, as a tuple
+fn bool_tuple(cols: usize) -> PatternMatrix {
+    let ty = Ty::Tuple(vec![Ty::bool_(); cols]);
+    let rows = (0..1usize << cols)
+        .map(|bits| {
+            let args = (0..cols)
+                .map(|i| SimplifiedPat::Literal(LiteralPat::Bool(bits & (1 << i) != 0)))
+                .collect();
+            PatternRow::new(vec![ctor(&format!("Tuple{cols}"), args)], bits)
+        })
+        .collect();
+    PatternMatrix::with_rows(rows, vec![ty])
+}
+
+/// `cols` Bool columns: one arm per column matching `true` there and `_`
+/// elsewhere, plus the all-`false` arm
+fn bool_one_hot(cols: usize) -> PatternMatrix {
+    let ty = Ty::Tuple(vec![Ty::bool_(); cols]);
+    let tuple = format!("Tuple{cols}");
+    let mut rows: Vec<PatternRow> = (0..cols)
+        .map(|hot| {
+            let args = (0..cols)
+                .map(|i| match i == hot {
+                    true => SimplifiedPat::Literal(LiteralPat::Bool(true)),
+                    false => SimplifiedPat::Wildcard,
+                })
+                .collect();
+            PatternRow::new(vec![ctor(&tuple, args)], hot)
+        })
+        .collect();
+    let all_false = vec![SimplifiedPat::Literal(LiteralPat::Bool(false)); cols];
+    rows.push(PatternRow::new(vec![ctor(&tuple, all_false)], cols));
+    PatternMatrix::with_rows(rows, vec![ty])
+}
+
+/// `cols` Bool columns: one arm per adjacent pair matching `(true, false)`
+/// there and `_` elsewhere. Not exhaustive; without memoization the witness
+/// search revisits the same residual matrices exponentially often.
+fn bool_adjacent_pairs(cols: usize) -> PatternMatrix {
+    let ty = Ty::Tuple(vec![Ty::bool_(); cols]);
+    let tuple = format!("Tuple{cols}");
+    let rows = (0..cols - 1)
+        .map(|i| {
+            let args = (0..cols)
+                .map(|j| match j {
+                    _ if j == i => SimplifiedPat::Literal(LiteralPat::Bool(true)),
+                    _ if j == i + 1 => SimplifiedPat::Literal(LiteralPat::Bool(false)),
+                    _ => SimplifiedPat::Wildcard,
+                })
+                .collect();
+            PatternRow::new(vec![ctor(&tuple, args)], i)
+        })
+        .collect();
+    PatternMatrix::with_rows(rows, vec![ty])
+}
+
+fn bench(name: &str, registry: &AdtRegistry, matrix: &PatternMatrix) {
+    let mut times: Vec<Duration> = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            let mut checker = ExhaustivenessChecker::new(registry, span());
+            let witness = checker.check_exhaustive(matrix).expect("limit exceeded");
+            let redundant = checker.check_redundant(matrix).expect("limit exceeded");
+            black_box((witness, redundant));
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    println!("{:<28} {:>12?}", name, times[ITERATIONS / 2]);
+}
+
+fn main() {
+    let reg = registry(500);
+    bench("wide_enum_100", &reg, &wide_enum(100));
+    bench("wide_enum_500", &reg, &wide_enum(500));
+    bench("deep_nesting_32", &reg, &deep_nesting(32));
+    bench("deep_nesting_96", &reg, &deep_nesting(96));
+    bench("bool_tuple_6", &reg, &bool_tuple(6));
+    bench("bool_tuple_8", &reg, &bool_tuple(8));
+    bench("bool_one_hot_8", &reg, &bool_one_hot(8));
+    bench("bool_one_hot_16", &reg, &bool_one_hot(16));
+    bench("bool_adjacent_pairs_8", &reg, &bool_adjacent_pairs(8));
+    bench("bool_adjacent_pairs_16", &reg, &bool_adjacent_pairs(16));
+}
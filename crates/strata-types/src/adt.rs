@@ -3,11 +3,15 @@
 //! This module provides the infrastructure for struct and enum type definitions,
 //! including a registry for looking up ADT metadata during type checking.
 
-use crate::infer::ty::{Ty, TypeVarId};
+use crate::consts::ConstValue;
+use crate::effects::CapKind;
+use crate::infer::ty::{Kind, Ty, TypeVarId};
 use std::collections::HashMap;
+use strata_ast::ast::{EnumDef, VariantFields as AstVariantFields};
+use strata_ast::span::Span;
 
 /// Definition of an algebraic data type (struct or enum)
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AdtDef {
     /// Name of the ADT (e.g., "Option", "Point")
     pub name: String,
@@ -15,10 +19,14 @@ pub struct AdtDef {
     pub type_params: Vec<String>,
     /// Kind of ADT (struct or enum)
     pub kind: AdtKind,
+    /// Declared `linear`: may store capabilities, and every value is affine
+    pub linear: bool,
+    /// A tuple struct, whose fields are named `0`, `1`, ...
+    pub tuple: bool,
 }
 
 /// Kind of ADT: struct with fields or enum with variants
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum AdtKind {
     /// Struct with named fields
     Struct(Vec<FieldDef>),
@@ -27,25 +35,30 @@ pub enum AdtKind {
 }
 
 /// Field definition in a struct
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct FieldDef {
     /// Field name
     pub name: String,
     /// Field type (may reference type parameters)
     pub ty: Ty,
+    /// Value a struct literal leaving the field off gives it
+    pub default: Option<ConstValue>,
 }
 
 /// Variant definition in an enum
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VariantDef {
     /// Variant name (e.g., "Some", "None")
     pub name: String,
     /// Variant fields
     pub fields: VariantFields,
+    /// Integer tag: explicit (`Red = 1`) or one more than the previous
+    /// variant's, starting from 0
+    pub discriminant: i64,
 }
 
 /// Fields of an enum variant
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum VariantFields {
     /// Unit variant (no data): `None`
     Unit,
@@ -64,6 +77,8 @@ impl AdtDef {
             name: name.into(),
             type_params,
             kind: AdtKind::Struct(fields),
+            linear: false,
+            tuple: false,
         }
     }
 
@@ -77,6 +92,34 @@ impl AdtDef {
             name: name.into(),
             type_params,
             kind: AdtKind::Enum(variants),
+            linear: false,
+            tuple: false,
+        }
+    }
+
+    /// Mark this ADT as `linear`
+    pub fn with_linear(mut self, linear: bool) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    /// Mark this struct as a tuple struct
+    pub fn with_tuple(mut self, tuple: bool) -> Self {
+        self.tuple = tuple;
+        self
+    }
+
+    /// Types of all fields, across every variant for enums
+    pub fn field_types(&self) -> Vec<&Ty> {
+        match &self.kind {
+            AdtKind::Struct(fields) => fields.iter().map(|f| &f.ty).collect(),
+            AdtKind::Enum(variants) => variants
+                .iter()
+                .flat_map(|v| match &v.fields {
+                    VariantFields::Unit => &[][..],
+                    VariantFields::Tuple(tys) => &tys[..],
+                })
+                .collect(),
         }
     }
 
@@ -90,6 +133,11 @@ impl AdtDef {
         matches!(self.kind, AdtKind::Struct(_))
     }
 
+    /// Check if this is a tuple struct: `struct UserId(Int);`
+    pub fn is_tuple_struct(&self) -> bool {
+        self.tuple && self.is_struct()
+    }
+
     /// Check if this is an enum
     pub fn is_enum(&self) -> bool {
         matches!(self.kind, AdtKind::Enum(_))
@@ -115,6 +163,12 @@ impl AdtDef {
     pub fn find_variant(&self, name: &str) -> Option<&VariantDef> {
         self.variants()?.iter().find(|v| v.name == name)
     }
+
+    /// Whether this is an enum whose variants all carry no data
+    pub fn is_unit_only(&self) -> bool {
+        self.variants()
+            .is_some_and(|vs| vs.iter().all(|v| matches!(v.fields, VariantFields::Unit)))
+    }
 }
 
 impl VariantDef {
@@ -123,6 +177,7 @@ impl VariantDef {
         Self {
             name: name.into(),
             fields: VariantFields::Unit,
+            discriminant: 0,
         }
     }
 
@@ -131,9 +186,16 @@ impl VariantDef {
         Self {
             name: name.into(),
             fields: VariantFields::Tuple(fields),
+            discriminant: 0,
         }
     }
 
+    /// Set the variant's discriminant
+    pub fn with_discriminant(mut self, discriminant: i64) -> Self {
+        self.discriminant = discriminant;
+        self
+    }
+
     /// Get the arity of this variant (number of fields)
     pub fn arity(&self) -> usize {
         match &self.fields {
@@ -143,6 +205,63 @@ impl VariantDef {
     }
 }
 
+/// Invalid discriminants in an enum definition
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiscriminantError {
+    /// Explicit discriminant in an enum with data-carrying variants
+    OnDataEnum { variant: String, span: Span },
+    /// Implicit discriminant past `i64::MAX`
+    Overflow { variant: String, span: Span },
+    /// Two variants with the same discriminant
+    Duplicate {
+        value: i64,
+        variant: String,
+        previous: String,
+        span: Span,
+    },
+}
+
+/// Discriminants of `def`'s variants, in declaration order. A variant
+/// without an explicit `= n` gets one more than the previous variant, and
+/// the first gets 0. Explicit values are only allowed on unit-only enums.
+pub fn enum_discriminants(def: &EnumDef) -> Result<Vec<i64>, DiscriminantError> {
+    let unit_only = def
+        .variants
+        .iter()
+        .all(|v| matches!(v.fields, AstVariantFields::Unit));
+    let mut values: Vec<i64> = Vec::with_capacity(def.variants.len());
+    for variant in &def.variants {
+        let value = match variant.discriminant {
+            Some((_, span)) if !unit_only => {
+                return Err(DiscriminantError::OnDataEnum {
+                    variant: variant.name.text.clone(),
+                    span,
+                })
+            }
+            Some((value, _)) => value,
+            None => match values.last() {
+                Some(prev) => prev
+                    .checked_add(1)
+                    .ok_or_else(|| DiscriminantError::Overflow {
+                        variant: variant.name.text.clone(),
+                        span: variant.span,
+                    })?,
+                None => 0,
+            },
+        };
+        if let Some(i) = values.iter().position(|v| *v == value) {
+            return Err(DiscriminantError::Duplicate {
+                value,
+                variant: variant.name.text.clone(),
+                previous: def.variants[i].name.text.clone(),
+                span: variant.discriminant.map_or(variant.span, |(_, s)| s),
+            });
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
 /// Registry of all ADT definitions
 #[derive(Clone, Debug, Default)]
 pub struct AdtRegistry {
@@ -165,8 +284,18 @@ impl AdtRegistry {
         reg
     }
 
-    /// Register built-in tuple types
+    /// Register built-in tuple types, `Task<T>`, and `Iter<T>`
     fn register_builtins(&mut self) {
+        // The handle `spawn` gives: linear, so it is joined at most once, and
+        // without variants, so only `spawn` makes one
+        let mut task = AdtDef::new_enum("Task", vec!["T".to_string()], vec![]);
+        task.linear = true;
+        let _ = self.register(task);
+
+        // The lazy sequence `iter` and its adapters give, likewise without
+        // variants; single-use exactly when its items are
+        let _ = self.register(AdtDef::new_enum("Iter", vec!["T".to_string()], vec![]));
+
         // Register Tuple2 through Tuple8
         for n in 2..=8 {
             let name = format!("Tuple{}", n);
@@ -177,6 +306,7 @@ impl AdtRegistry {
                 .map(|i| FieldDef {
                     name: format!("_{}", i),
                     ty: Ty::Var(TypeVarId(i as u32)),
+                    default: None,
                 })
                 .collect();
 
@@ -202,6 +332,20 @@ impl AdtRegistry {
         self.adts.get(name)
     }
 
+    /// Record the default values of struct `name`'s fields, one per field
+    /// in declaration order; does nothing if `name` isn't a struct
+    pub fn set_field_defaults(&mut self, name: &str, defaults: Vec<Option<ConstValue>>) {
+        if let Some(AdtDef {
+            kind: AdtKind::Struct(fields),
+            ..
+        }) = self.adts.get_mut(name)
+        {
+            for (field, default) in fields.iter_mut().zip(defaults) {
+                field.default = default;
+            }
+        }
+    }
+
     /// Check if an ADT with the given name exists
     pub fn contains(&self, name: &str) -> bool {
         self.adts.contains_key(name)
@@ -221,6 +365,78 @@ impl AdtRegistry {
     pub fn is_empty(&self) -> bool {
         self.adts.is_empty()
     }
+
+    /// Check if `name` is a registered `linear` ADT
+    pub fn is_linear(&self, name: &str) -> bool {
+        self.adts.get(name).is_some_and(|def| def.linear)
+    }
+
+    /// Kind of a type, taking `linear` ADTs into account.
+    ///
+    /// Like `Ty::kind()`, but a value of a `linear` ADT is affine even when
+    /// none of its type arguments are.
+    pub fn kind(&self, ty: &Ty) -> Kind {
+        let affine = match ty {
+            Ty::Adt { name, args } => {
+                self.is_linear(name) || args.iter().any(|a| self.kind(a) == Kind::Affine)
+            }
+            Ty::Tuple(tys) => tys.iter().any(|t| self.kind(t) == Kind::Affine),
+            Ty::List(inner) => self.kind(inner) == Kind::Affine,
+            _ => ty.kind() == Kind::Affine,
+        };
+        if affine {
+            Kind::Affine
+        } else {
+            Kind::Unrestricted
+        }
+    }
+
+    /// Name of the first single-use component of a type: a capability type,
+    /// or a `linear` ADT. Returns None for unrestricted types.
+    pub fn find_affine_name(&self, ty: &Ty) -> Option<String> {
+        match ty {
+            Ty::Adt { name, args } if !is_capability_type(name) => {
+                if self.is_linear(name) {
+                    Some(name.clone())
+                } else {
+                    args.iter().find_map(|a| self.find_affine_name(a))
+                }
+            }
+            Ty::Tuple(tys) => tys.iter().find_map(|t| self.find_affine_name(t)),
+            Ty::List(inner) => self.find_affine_name(inner),
+            _ => find_capability_name(ty),
+        }
+    }
+
+    /// Capabilities a parameter of type `ty` grants: the capability itself,
+    /// a borrowed capability, or any capability held (transitively) by a
+    /// `linear` ADT value.
+    pub fn held_caps(&self, ty: &Ty) -> Vec<CapKind> {
+        let mut caps = Vec::new();
+        self.collect_held_caps(ty, &mut Vec::new(), &mut caps);
+        caps
+    }
+
+    fn collect_held_caps(&self, ty: &Ty, visiting: &mut Vec<String>, caps: &mut Vec<CapKind>) {
+        match ty {
+            Ty::Cap(kind) => caps.push(*kind),
+            Ty::Ref(inner) => self.collect_held_caps(inner, visiting, caps),
+            Ty::Adt { name, args } if self.is_linear(name) && !visiting.contains(name) => {
+                let Some(def) = self.adts.get(name) else {
+                    return;
+                };
+                visiting.push(name.clone());
+                for field_ty in def.field_types() {
+                    self.collect_held_caps(field_ty, visiting, caps);
+                }
+                for arg in args {
+                    self.collect_held_caps(arg, visiting, caps);
+                }
+                visiting.pop();
+            }
+            _ => {}
+        }
+    }
 }
 
 /// Check if a type name is a capability type.
@@ -240,6 +456,7 @@ pub fn contains_capability(ty: &Ty) -> bool {
             params.iter().any(contains_capability) || contains_capability(ret)
         }
         Ty::Tuple(tys) => tys.iter().any(contains_capability),
+        Ty::Record(fields, _) => fields.values().any(contains_capability),
         Ty::List(ty) => contains_capability(ty),
         Ty::Ref(inner) => contains_capability(inner),
     }
@@ -262,6 +479,7 @@ pub fn find_capability_name(ty: &Ty) -> Option<String> {
             .find_map(find_capability_name)
             .or_else(|| find_capability_name(ret)),
         Ty::Tuple(tys) => tys.iter().find_map(find_capability_name),
+        Ty::Record(fields, _) => fields.values().find_map(find_capability_name),
         Ty::List(ty) => find_capability_name(ty),
         Ty::Ref(inner) => find_capability_name(inner),
         Ty::Const(_) | Ty::Var(_) | Ty::Never => None,
@@ -281,10 +499,12 @@ mod tests {
                 FieldDef {
                     name: "x".into(),
                     ty: Ty::int(),
+                    default: None,
                 },
                 FieldDef {
                     name: "y".into(),
                     ty: Ty::int(),
+                    default: None,
                 },
             ],
         );
@@ -326,6 +546,37 @@ mod tests {
         assert_eq!(tuple2.arity(), 2);
     }
 
+    #[test]
+    fn test_registry_kind_linear_adt() {
+        let mut reg = AdtRegistry::new();
+        let handle = AdtDef::new_struct(
+            "Handle",
+            vec![],
+            vec![FieldDef {
+                name: "fs".to_string(),
+                ty: Ty::Cap(crate::effects::CapKind::Fs),
+                default: None,
+            }],
+        )
+        .with_linear(true);
+        reg.register(handle).unwrap();
+        reg.register(AdtDef::new_struct("Point", vec![], vec![]))
+            .unwrap();
+
+        // Ty::kind() alone cannot see linearity; the registry can
+        assert_eq!(Ty::adt0("Handle").kind(), Kind::Unrestricted);
+        assert_eq!(reg.kind(&Ty::adt0("Handle")), Kind::Affine);
+        assert_eq!(
+            reg.kind(&Ty::tuple(vec![Ty::int(), Ty::adt0("Handle")])),
+            Kind::Affine
+        );
+        assert_eq!(reg.kind(&Ty::adt0("Point")), Kind::Unrestricted);
+        assert_eq!(
+            reg.find_affine_name(&Ty::adt("Option", vec![Ty::adt0("Handle")])),
+            Some("Handle".to_string())
+        );
+    }
+
     #[test]
     fn test_registry_register() {
         let mut reg = AdtRegistry::new();
@@ -381,6 +632,7 @@ mod tests {
         assert!(is_capability_type("TimeCap"));
         assert!(is_capability_type("RandCap"));
         assert!(is_capability_type("AiCap"));
+        assert!(is_capability_type("ConsoleCap"));
         assert!(!is_capability_type("Int"));
         assert!(!is_capability_type("Option"));
     }
@@ -0,0 +1,406 @@
+//! Registry of item attributes.
+//!
+//! The parser accepts any `#[name(args)]` before an item and records it in
+//! the AST; this module decides what the names mean. Known attributes are
+//! checked for the item kinds they apply to and the arguments they take.
+//! An unknown name is not an error: it is reported through the
+//! `unknown_attribute` lint, so code written for a newer toolchain still
+//! checks with an older one.
+//!
+//! `cfg` and `transparent` are acted on by the parser, which has already
+//! rejected malformed uses of them by the time the checker runs.
+//! `allow`, `warn`, and `deny` set lint levels inside their item; see
+//! [`ItemLintLevels`]. `redact` hides an extern function's values from
+//! traces; see [`redaction`]. `derive` adds functions for a struct or enum;
+//! see [`derive`](crate::derive).
+
+use crate::derive::Derive;
+use crate::warnings::{Lint, LintLevel, Warning};
+use strata_ast::ast::{AttrArg, Attribute, ExternFnDecl, Item, Module};
+use strata_ast::span::Span;
+
+/// An attribute the toolchain understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownAttr {
+    /// `#[deprecated]` or `#[deprecated("use new_fn")]`
+    Deprecated,
+    /// `#[allow(lint, ..)]`
+    Allow,
+    /// `#[warn(lint, ..)]`
+    Warn,
+    /// `#[deny(lint, ..)]`
+    Deny,
+    /// `#[test]` on a function with no parameters
+    Test,
+    /// `#[cfg(predicate)]`, evaluated by the parser
+    Cfg,
+    /// `#[transparent]` on a one-field tuple struct, the same as
+    /// `transparent struct`
+    Transparent,
+    /// `#[redact]` or `#[redact(param, .., output, mode = "mask")]` on an
+    /// extern function
+    Redact,
+    /// `#[derive(Show)]` on a struct or enum
+    Derive,
+}
+
+impl KnownAttr {
+    /// All known attributes.
+    pub const ALL: &'static [KnownAttr] = &[
+        KnownAttr::Deprecated,
+        KnownAttr::Allow,
+        KnownAttr::Warn,
+        KnownAttr::Deny,
+        KnownAttr::Test,
+        KnownAttr::Cfg,
+        KnownAttr::Transparent,
+        KnownAttr::Redact,
+        KnownAttr::Derive,
+    ];
+
+    /// The name written between `#[` and `]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            KnownAttr::Deprecated => "deprecated",
+            KnownAttr::Allow => "allow",
+            KnownAttr::Warn => "warn",
+            KnownAttr::Deny => "deny",
+            KnownAttr::Test => "test",
+            KnownAttr::Cfg => "cfg",
+            KnownAttr::Transparent => "transparent",
+            KnownAttr::Redact => "redact",
+            KnownAttr::Derive => "derive",
+        }
+    }
+
+    /// Look up an attribute by name.
+    pub fn from_name(name: &str) -> Option<KnownAttr> {
+        KnownAttr::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    /// The lint level a lint-control attribute sets.
+    pub fn lint_level(self) -> Option<LintLevel> {
+        match self {
+            KnownAttr::Allow => Some(LintLevel::Allow),
+            KnownAttr::Warn => Some(LintLevel::Warn),
+            KnownAttr::Deny => Some(LintLevel::Deny),
+            _ => None,
+        }
+    }
+
+    /// Whether the attribute may be written on `item`.
+    pub fn applies_to(self, item: &Item) -> bool {
+        match self {
+            KnownAttr::Deprecated => matches!(
+                item,
+                Item::Fn(_) | Item::ExternFn(_) | Item::Struct(_) | Item::Enum(_) | Item::Const(_)
+            ),
+            KnownAttr::Test => matches!(item, Item::Fn(_)),
+            KnownAttr::Transparent => matches!(item, Item::Struct(_)),
+            KnownAttr::Derive => matches!(item, Item::Struct(_) | Item::Enum(_)),
+            KnownAttr::Redact => matches!(item, Item::ExternFn(_)),
+            KnownAttr::Allow | KnownAttr::Warn | KnownAttr::Deny | KnownAttr::Cfg => true,
+        }
+    }
+}
+
+/// A misuse of a known attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttrError {
+    pub msg: String,
+    pub span: Span,
+}
+
+/// Check the attributes of every item in `module`, returning a warning
+/// for each unknown attribute.
+pub fn check_module(module: &Module) -> Result<Vec<Warning>, AttrError> {
+    let mut warnings = Vec::new();
+    for item in &module.items {
+        for attr in item.attrs() {
+            match KnownAttr::from_name(&attr.name.text) {
+                Some(known) => check_attr(known, attr, item)?,
+                None => warnings.push(Warning::UnknownAttribute {
+                    name: attr.name.text.clone(),
+                    span: attr.span,
+                }),
+            }
+        }
+    }
+    Ok(warnings)
+}
+
+fn check_attr(known: KnownAttr, attr: &Attribute, item: &Item) -> Result<(), AttrError> {
+    let invalid = |msg: String, span: Span| Err(AttrError { msg, span });
+    if !known.applies_to(item) {
+        return invalid(
+            format!(
+                "`#[{}]` cannot be used on {}",
+                known.name(),
+                item_kind(item)
+            ),
+            attr.span,
+        );
+    }
+    match known {
+        KnownAttr::Deprecated => match attr.args.as_slice() {
+            [] | [AttrArg::Str { .. }] => Ok(()),
+            _ => invalid(
+                "`#[deprecated]` takes at most one note: `#[deprecated(\"use new_fn\")]`"
+                    .to_string(),
+                attr.span,
+            ),
+        },
+        KnownAttr::Allow | KnownAttr::Warn | KnownAttr::Deny => {
+            if attr.args.is_empty() {
+                return invalid(
+                    format!(
+                        "`#[{0}]` names the lints it sets: `#[{0}(shadowed_binding)]`",
+                        known.name()
+                    ),
+                    attr.span,
+                );
+            }
+            for arg in &attr.args {
+                match arg {
+                    AttrArg::Word(lint) if Lint::from_name(&lint.text).is_some() => {}
+                    AttrArg::Word(lint) => {
+                        let known: Vec<&str> = Lint::ALL.iter().map(|l| l.name()).collect();
+                        return invalid(
+                            format!(
+                                "unknown lint `{}`; expected one of {}",
+                                lint.text,
+                                known.join(", ")
+                            ),
+                            lint.span,
+                        );
+                    }
+                    other => return invalid("expected a lint name".to_string(), other.span()),
+                }
+            }
+            Ok(())
+        }
+        KnownAttr::Test => {
+            if !attr.args.is_empty() {
+                return invalid("`#[test]` takes no arguments".to_string(), attr.span);
+            }
+            match item {
+                Item::Fn(decl) if !decl.params.is_empty() => invalid(
+                    format!("test function '{}' cannot take parameters", decl.name.text),
+                    decl.name.span,
+                ),
+                _ => Ok(()),
+            }
+        }
+        KnownAttr::Redact => {
+            let Item::ExternFn(decl) = item else {
+                return Ok(());
+            };
+            for arg in &attr.args {
+                match arg {
+                    AttrArg::Word(word) if word.text == "output" => {
+                        if decl.params.iter().any(|p| p.name.text == "output") {
+                            return invalid(
+                                format!(
+                                    "`output` names what '{}' returns, but it also has a \
+                                     parameter named `output`; rename the parameter",
+                                    decl.name.text
+                                ),
+                                word.span,
+                            );
+                        }
+                    }
+                    AttrArg::Word(word) if decl.params.iter().any(|p| p.name.text == word.text) => {
+                    }
+                    AttrArg::Word(word) => {
+                        return invalid(
+                            format!(
+                                "'{}' has no parameter `{}`; `#[redact]` names parameters, \
+                                 or `output` for the returned value",
+                                decl.name.text, word.text
+                            ),
+                            word.span,
+                        )
+                    }
+                    AttrArg::NameValue { name, value, .. }
+                        if name.text == "mode" && (value == "hash" || value == "mask") => {}
+                    other => {
+                        return invalid(
+                            "expected a parameter name, `output`, or `mode = \"hash\"` or \
+                             `mode = \"mask\"`"
+                                .to_string(),
+                            other.span(),
+                        )
+                    }
+                }
+            }
+            Ok(())
+        }
+        KnownAttr::Derive => {
+            if attr.args.is_empty() {
+                return invalid(
+                    "`#[derive]` names what it derives: `#[derive(Show)]`".to_string(),
+                    attr.span,
+                );
+            }
+            let mut seen = Vec::new();
+            for arg in &attr.args {
+                let AttrArg::Word(word) = arg else {
+                    return invalid("expected a name to derive".to_string(), arg.span());
+                };
+                if word.text == "Eq" {
+                    return invalid(
+                        "`Eq` isn't derived: `==` already compares structs and enums \
+                         field by field"
+                            .to_string(),
+                        word.span,
+                    );
+                }
+                let Some(derive) = Derive::from_name(&word.text) else {
+                    let known: Vec<&str> = Derive::ALL.iter().map(|d| d.name()).collect();
+                    return invalid(
+                        format!(
+                            "cannot derive `{}`; expected one of {}",
+                            word.text,
+                            known.join(", ")
+                        ),
+                        word.span,
+                    );
+                };
+                if seen.contains(&derive) {
+                    return invalid(format!("`{}` is derived twice", word.text), word.span);
+                }
+                seen.push(derive);
+            }
+            Ok(())
+        }
+        KnownAttr::Cfg | KnownAttr::Transparent => Ok(()),
+    }
+}
+
+/// What `#[redact]` on an extern function hides from traces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redaction {
+    /// The parameters whose arguments are hidden, by name
+    pub params: Vec<String>,
+    /// Whether the returned value is hidden
+    pub output: bool,
+    /// Replace the values with a mask rather than their hash, so replay
+    /// can't check them
+    pub mask: bool,
+}
+
+/// What the `#[redact]` attributes of `decl` hide, if it has any. If
+/// they name no parameter and not `output`, every argument and the returned
+/// value are hidden. Call after [`check_module`] accepts the attributes.
+pub fn redaction(decl: &ExternFnDecl) -> Option<Redaction> {
+    let attrs: Vec<&Attribute> = decl
+        .attrs
+        .iter()
+        .filter(|a| a.name.text == "redact")
+        .collect();
+    if attrs.is_empty() {
+        return None;
+    }
+    let mut redaction = Redaction::default();
+    for arg in attrs.iter().flat_map(|a| &a.args) {
+        match arg {
+            AttrArg::Word(word) if word.text == "output" => redaction.output = true,
+            AttrArg::Word(word) => redaction.params.push(word.text.clone()),
+            AttrArg::NameValue { name, value, .. } if name.text == "mode" => {
+                redaction.mask = value == "mask"
+            }
+            _ => {}
+        }
+    }
+    if redaction.params.is_empty() && !redaction.output {
+        redaction.params = decl.params.iter().map(|p| p.name.text.clone()).collect();
+        redaction.output = true;
+    }
+    Some(redaction)
+}
+
+/// Lint levels set by `#[allow]`, `#[warn]`, and `#[deny]` on items. Each
+/// applies to findings inside its item, attributes included, over the
+/// checker's [`LintConfig`](crate::LintConfig); when one item sets a lint
+/// more than once, the last attribute wins.
+#[derive(Debug, Clone, Default)]
+pub struct ItemLintLevels {
+    /// `(item with its attributes, lint, level)`, in source order
+    scopes: Vec<(Span, Lint, LintLevel)>,
+}
+
+impl ItemLintLevels {
+    /// The levels `module`'s items set. Call after [`check_module`] accepts
+    /// the attributes; unknown lint names are skipped.
+    pub fn from_module(module: &Module) -> Self {
+        let mut scopes = Vec::new();
+        for item in &module.items {
+            let span = item_span(item);
+            for attr in item.attrs() {
+                let Some(level) =
+                    KnownAttr::from_name(&attr.name.text).and_then(|a| a.lint_level())
+                else {
+                    continue;
+                };
+                for arg in &attr.args {
+                    if let AttrArg::Word(name) = arg {
+                        if let Some(lint) = Lint::from_name(&name.text) {
+                            scopes.push((span, lint, level));
+                        }
+                    }
+                }
+            }
+        }
+        Self { scopes }
+    }
+
+    /// The level an item sets for `lint` at `span`, if `span` is inside
+    /// an item that sets one.
+    pub fn level_at(&self, lint: Lint, span: Span) -> Option<LintLevel> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|(scope, l, _)| {
+                *l == lint
+                    && scope.file == span.file
+                    && scope.start <= span.start
+                    && span.end <= scope.end
+            })
+            .map(|(_, _, level)| *level)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+}
+
+/// `item`'s span, widened to cover its attributes
+fn item_span(item: &Item) -> Span {
+    let span = match item {
+        Item::Let(d) => d.span,
+        Item::Const(d) => d.span,
+        Item::Fn(d) => d.span,
+        Item::ExternFn(d) => d.span,
+        Item::Struct(d) => d.span,
+        Item::Enum(d) => d.span,
+        Item::Use(d) => d.span,
+    };
+    let start = item.attrs().iter().map(|a| a.span.start).min();
+    Span {
+        start: start.map_or(span.start, |s| s.min(span.start)),
+        ..span
+    }
+}
+
+/// How diagnostics name the kind of `item`.
+fn item_kind(item: &Item) -> &'static str {
+    match item {
+        Item::Let(_) => "a `let` item",
+        Item::Const(_) => "a `const`",
+        Item::Fn(_) => "a function",
+        Item::ExternFn(_) => "an extern function",
+        Item::Struct(_) => "a struct",
+        Item::Enum(_) => "an enum",
+        Item::Use(_) => "a `use` declaration",
+    }
+}
@@ -2,14 +2,30 @@
 // Type checker for Strata - validates programs and infers types
 
 use super::adt::{
-    contains_capability, find_capability_name, AdtDef, AdtRegistry, FieldDef, VariantDef,
-    VariantFields,
+    contains_capability, enum_discriminants, find_capability_name, AdtDef, AdtKind, AdtRegistry,
+    DiscriminantError, FieldDef, VariantDef, VariantFields,
 };
+use super::attrs::ItemLintLevels;
+use super::consts::{self, ConstTable};
+use super::derive;
 use super::effects::{CapKind, Effect, EffectRow};
-use super::infer::ty::{free_effect_vars_env, Scheme, Ty, TypeVarId};
-use super::infer::{InferCtx, Solver};
-use std::collections::HashMap;
-use strata_ast::ast::{EnumDef, Ident, Item, LetDecl, Module, StructDef, TypeExpr};
+use super::infer::ty::{free_effect_vars, free_effect_vars_env, Scheme, Ty, TyConst, TypeVarId};
+use super::infer::{InferCtx, SolveStats, Solver, VariadicBound};
+use super::interface::{self, Interface};
+use super::named_args;
+use super::profile::Profile;
+use super::provenance::{self, EffectCall};
+use super::resolve::{self, Resolution};
+use super::typed::{TypedItem, TypedItemKind, TypedLocal};
+use super::warnings::{Lint, LintConfig, LintLevel, Warning};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use strata_ast::ast::{
+    Block, ConstDecl, EnumDef, Expr, FnDecl, Ident, Item, LetDecl, Module, Stmt, StructDef,
+    TypeExpr,
+};
+use strata_ast::diag::Diagnostic;
 use strata_ast::span::Span;
 
 /// Type errors that can occur during type checking
@@ -17,14 +33,35 @@ use strata_ast::span::Span;
 pub enum TypeError {
     /// Type mismatch - expected one type but found another
     Mismatch { expected: Ty, found: Ty, span: Span },
+    /// Type mismatch in an argument passed by name (the types boxed to
+    /// keep the error small)
+    ArgMismatch {
+        callee: String,
+        param: String,
+        expected: Box<Ty>,
+        found: Box<Ty>,
+        span: Span,
+    },
     /// Reference to an unknown variable
     UnknownVariable { name: String, span: Span },
+    /// Reference to an imported module's function that isn't `pub`
+    PrivateItem { name: String, span: Span },
     /// Assignment to an immutable variable
     ImmutableAssignment { name: String, span: Span },
+    /// A `spawn` block reading or assigning a mutable variable from outside
+    /// it, which the task would share with the code that spawned it
+    SpawnCapturesMutable { name: String, span: Span },
     /// Feature not yet implemented
     NotImplemented { msg: String, span: Span },
     /// Inference depth limit exceeded (pathological input)
     DepthLimitExceeded { span: Span },
+    /// An item's constraints went over the profile's budget for them
+    /// (pathological input): `what` is "constraints" or "solver steps"
+    SolveLimitExceeded {
+        what: &'static str,
+        limit: usize,
+        span: Span,
+    },
     /// Occurs check failure (infinite type)
     OccursCheck { var: TypeVarId, ty: Ty, span: Span },
     /// Arity mismatch (different number of arguments)
@@ -33,10 +70,18 @@ pub enum TypeError {
         found: usize,
         span: Span,
     },
+    /// A tuple with more elements than the checking profile allows
+    TupleTooWide {
+        max: usize,
+        found: usize,
+        span: Span,
+    },
     /// Internal invariant violation (indicates a bug in the type checker)
     InvariantViolation { msg: String, span: Span },
     /// Duplicate type definition
     DuplicateType { name: String, span: Span },
+    /// Top-level function or const defined twice, or rebound by a `let`
+    DuplicateDefinition { name: String, span: Span },
     /// Unknown type referenced
     UnknownType { name: String, span: Span },
     /// Unknown variant referenced
@@ -45,7 +90,15 @@ pub enum TypeError {
         variant: String,
         span: Span,
     },
-    /// Capability stored in ADT (forbidden until linear types)
+    /// `use` of a struct's variants
+    UseNotEnum { name: String, span: Span },
+    /// Unqualified variant that more than one imported enum defines
+    AmbiguousVariant {
+        name: String,
+        enums: Vec<String>,
+        span: Span,
+    },
+    /// Capability (or linear ADT) stored in a non-linear ADT
     CapabilityInAdt {
         field: String,
         cap_type: String,
@@ -63,8 +116,14 @@ pub enum TypeError {
         field: String,
         span: Span,
     },
-    /// Duplicate field in struct expression
+    /// Duplicate field in a struct expression, record, or record type
     DuplicateField { field: String, span: Span },
+    /// A record read or matched for a field it doesn't have
+    NoSuchField {
+        field: String,
+        record: Ty,
+        span: Span,
+    },
     /// Wrong number of type arguments
     WrongTypeArgCount {
         type_name: String,
@@ -73,13 +132,20 @@ pub enum TypeError {
         span: Span,
     },
     /// Non-exhaustive match - pattern matching doesn't cover all cases
-    NonExhaustiveMatch { witness: String, span: Span },
+    NonExhaustiveMatch {
+        witness: String,
+        /// Every uncovered pattern, as source for new arms
+        missing: Vec<String>,
+        span: Span,
+    },
     /// Unreachable pattern - arm will never match
     UnreachablePattern { arm_index: usize, span: Span },
     /// Exhaustiveness check exceeded limits (DoS protection)
     ExhaustivenessLimitExceeded { msg: String, span: Span },
     /// Refutable pattern in let binding
     RefutablePattern { pat_desc: String, span: Span },
+    /// Range pattern that matches no values
+    EmptyRangePattern { span: Span },
     /// Effect row mismatch
     EffectMismatch {
         expected: crate::effects::EffectRow,
@@ -99,12 +165,25 @@ pub enum TypeError {
     UnknownEffect { name: String, span: Span },
     /// Extern function missing required effect annotation
     MissingExternEffects { fn_name: String, span: Span },
-    /// Undeclared effect: function uses effects not in its annotation
+    /// `pure fn` that performs effects; `chain` names the functions they
+    /// come through, down to an extern, starting with the call at `span`.
+    /// With no concrete effects, the row's tail is that of a function
+    /// parameter
+    ImpureFunction {
+        fn_name: String,
+        effects: crate::effects::EffectRow,
+        chain: Vec<String>,
+        span: Span,
+    },
+    /// Undeclared effect: function uses effects not in its annotation.
+    /// `effect` is the first missing one, and `chain` the calls it comes
+    /// through, each with its span, down to the extern performing it
     UndeclaredEffect {
         effect: String,
         fn_name: String,
         declared: crate::effects::EffectRow,
         actual: crate::effects::EffectRow,
+        chain: Vec<(String, Span)>,
         span: Span,
     },
     /// Function performs effect but lacks the required capability parameter
@@ -129,77 +208,402 @@ pub enum TypeError {
         used_at: Span,
         previous_use: Span,
     },
+    /// Task handle joined, or passed on, a second time
+    TaskAlreadyJoined {
+        name: String,
+        used_at: Span,
+        previous_use: Span,
+    },
+    /// Capability consumed in some branch of an earlier `if`/`match`, then used
+    /// on a path where it may no longer be available
+    CapabilityMaybeUsed {
+        name: String,
+        used_at: Span,
+        moved_in_branch: Span,
+    },
     /// Capability used inside a loop (would be used multiple times)
     CapabilityUsedInLoop { name: String, used_at: Span },
+    /// Capability bound by a `match &x` arm used by value
+    CapabilityBorrowed {
+        name: String,
+        used_at: Span,
+        borrowed_at: Span,
+    },
+    /// Capability of an enclosing function used inside a nested `fn`
+    CapabilityCaptured {
+        name: String,
+        fn_name: String,
+        used_at: Span,
+    },
+    /// `let mut x: T;` read before every path to the read assigns it
+    PossiblyUninitialized {
+        name: String,
+        declared_at: Span,
+        used_at: Span,
+        /// Branch or loop through which `x` arrives unassigned, when other
+        /// paths do assign it
+        unassigned_on: Option<Span>,
+    },
+    /// Enum discriminant that is not allowed (on a data-carrying enum, or
+    /// past `i64::MAX`)
+    InvalidDiscriminant { msg: String, span: Span },
+    /// Two variants of an enum with the same discriminant
+    DuplicateDiscriminant {
+        enum_name: String,
+        value: i64,
+        variant: String,
+        previous: String,
+        span: Span,
+    },
+    /// `discriminant(x)` where `x` is not an enum
+    NotAnEnum { ty: Ty, span: Span },
+    /// `show(x)`, or an argument for a `...Show` parameter, where `x`
+    /// holds a capability; `callee` is the function called
+    ShowCapability { callee: String, ty: Ty, span: Span },
+    /// A partial application of a builtin or a constructor, which are only
+    /// applied where they are called
+    NotPartiallyApplicable { callee: String, span: Span },
+    /// An argument bound by a partial application that holds a capability,
+    /// which every call of the result could use again; `callee` is the
+    /// function applied, if it is a name
+    PartialCapability {
+        callee: Option<String>,
+        ty: Ty,
+        span: Span,
+    },
+    /// `iter(first, step)` over single-use items
+    IterSingleUse { ty: Ty, span: Span },
+    /// `==`/`!=` on a type without equality (closures, capabilities), or
+    /// an ordering (`<`, ...) on a type without one
+    NotComparable { ty: Ty, ordered: bool, span: Span },
+    /// A parameter of `main` that the runtime cannot supply (only
+    /// capabilities are passed in)
+    InvalidMainParam { name: String, ty: Ty, span: Span },
+    /// `const` declared with a type other than Int, Float, Bool, or String
+    InvalidConstType { name: String, ty: Ty, span: Span },
+    /// `const` value that can only be computed at runtime
+    NotConstant { name: String, span: Span },
+    /// `const` values that depend on each other
+    ConstCycle { cycle: Vec<String>, span: Span },
+    /// Overflow or division by zero while evaluating a `const`
+    ConstEval { message: String, span: Span },
     /// Reference type (&T) escaped its allowed position (extern fn params only)
     RefEscape { ty: Ty, context: String, span: Span },
     /// Reference type (&T) found in ADT field definition
     RefInAdtField { field: String, ty: Ty, span: Span },
+    /// Known attribute on an item it doesn't apply to, or with the wrong
+    /// arguments
+    InvalidAttribute { msg: String, span: Span },
+    /// Parameter or struct field default value where none is allowed, of
+    /// the wrong type, or not a compile-time constant
+    InvalidDefault { msg: String, span: Span },
+    /// Variadic parameter anywhere but last in an extern fn, or with a
+    /// bound that holds a capability
+    InvalidVariadic { msg: String, span: Span },
+    /// Named arguments that don't fit the parameters of the function called
+    NamedArgument { msg: String, span: Span },
+    /// A lint configured at `Deny` level fired
+    DeniedLint { warning: Warning },
+}
+
+impl TypeError {
+    /// Primary source span the error applies to.
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::Mismatch { span, .. }
+            | TypeError::ArgMismatch { span, .. }
+            | TypeError::UnknownVariable { span, .. }
+            | TypeError::PrivateItem { span, .. }
+            | TypeError::ImmutableAssignment { span, .. }
+            | TypeError::SpawnCapturesMutable { span, .. }
+            | TypeError::NotImplemented { span, .. }
+            | TypeError::DepthLimitExceeded { span, .. }
+            | TypeError::SolveLimitExceeded { span, .. }
+            | TypeError::OccursCheck { span, .. }
+            | TypeError::ArityMismatch { span, .. }
+            | TypeError::TupleTooWide { span, .. }
+            | TypeError::InvariantViolation { span, .. }
+            | TypeError::DuplicateType { span, .. }
+            | TypeError::DuplicateDefinition { span, .. }
+            | TypeError::UnknownType { span, .. }
+            | TypeError::UnknownVariant { span, .. }
+            | TypeError::UseNotEnum { span, .. }
+            | TypeError::AmbiguousVariant { span, .. }
+            | TypeError::CapabilityInAdt { span, .. }
+            | TypeError::MissingField { span, .. }
+            | TypeError::UnknownField { span, .. }
+            | TypeError::DuplicateField { span, .. }
+            | TypeError::NoSuchField { span, .. }
+            | TypeError::WrongTypeArgCount { span, .. }
+            | TypeError::NonExhaustiveMatch { span, .. }
+            | TypeError::UnreachablePattern { span, .. }
+            | TypeError::ExhaustivenessLimitExceeded { span, .. }
+            | TypeError::RefutablePattern { span, .. }
+            | TypeError::EmptyRangePattern { span, .. }
+            | TypeError::EffectMismatch { span, .. }
+            | TypeError::EffectCycle { span, .. }
+            | TypeError::EffectChainTooDeep { span, .. }
+            | TypeError::UnknownEffect { span, .. }
+            | TypeError::MissingExternEffects { span, .. }
+            | TypeError::ImpureFunction { span, .. }
+            | TypeError::UndeclaredEffect { span, .. }
+            | TypeError::MissingCapability { span, .. }
+            | TypeError::ExternMissingCapability { span, .. }
+            | TypeError::ReservedCapabilityName { span, .. }
+            | TypeError::InvalidDiscriminant { span, .. }
+            | TypeError::DuplicateDiscriminant { span, .. }
+            | TypeError::NotAnEnum { span, .. }
+            | TypeError::NotComparable { span, .. }
+            | TypeError::ShowCapability { span, .. }
+            | TypeError::NotPartiallyApplicable { span, .. }
+            | TypeError::PartialCapability { span, .. }
+            | TypeError::IterSingleUse { span, .. }
+            | TypeError::InvalidMainParam { span, .. }
+            | TypeError::InvalidConstType { span, .. }
+            | TypeError::NotConstant { span, .. }
+            | TypeError::ConstCycle { span, .. }
+            | TypeError::ConstEval { span, .. }
+            | TypeError::RefEscape { span, .. }
+            | TypeError::RefInAdtField { span, .. }
+            | TypeError::InvalidAttribute { span, .. }
+            | TypeError::InvalidDefault { span, .. }
+            | TypeError::InvalidVariadic { span, .. }
+            | TypeError::NamedArgument { span, .. } => *span,
+            TypeError::CapabilityAlreadyUsed { used_at, .. }
+            | TypeError::TaskAlreadyJoined { used_at, .. }
+            | TypeError::CapabilityMaybeUsed { used_at, .. }
+            | TypeError::CapabilityUsedInLoop { used_at, .. }
+            | TypeError::CapabilityCaptured { used_at, .. }
+            | TypeError::CapabilityBorrowed { used_at, .. }
+            | TypeError::PossiblyUninitialized { used_at, .. } => *used_at,
+            TypeError::DeniedLint { warning } => warning.span(),
+            // Module-wide limit with no single location
+            TypeError::EffectVarLimitExceeded { .. } => Span::default(),
+        }
+    }
+
+    /// Other places the message refers to, each with what happened there,
+    /// for tools to show next to the primary span.
+    pub fn notes(&self) -> Vec<(String, Span)> {
+        match self {
+            TypeError::UndeclaredEffect { fn_name, chain, .. } => {
+                let callers = std::iter::once(fn_name).chain(chain.iter().map(|(name, _)| name));
+                callers
+                    .zip(chain)
+                    .map(|(caller, (callee, at))| {
+                        (format!("'{}' calls '{}' here", caller, callee), *at)
+                    })
+                    .collect()
+            }
+            TypeError::CapabilityAlreadyUsed { previous_use, .. } => {
+                vec![("permission was transferred here".to_string(), *previous_use)]
+            }
+            TypeError::TaskAlreadyJoined {
+                name, previous_use, ..
+            } => vec![(
+                format!("'{}' was joined or passed on here", name),
+                *previous_use,
+            )],
+            TypeError::CapabilityMaybeUsed {
+                moved_in_branch, ..
+            } => vec![(
+                "permission was transferred in this branch".to_string(),
+                *moved_in_branch,
+            )],
+            TypeError::CapabilityBorrowed {
+                name, borrowed_at, ..
+            } => vec![(format!("the match borrows '{}' here", name), *borrowed_at)],
+            TypeError::PossiblyUninitialized {
+                name,
+                declared_at,
+                unassigned_on,
+                ..
+            } => {
+                let mut notes = vec![(format!("'{}' is declared here", name), *declared_at)];
+                if let Some(path) = unassigned_on {
+                    notes.push((format!("'{}' is not assigned on this path", name), *path));
+                }
+                notes
+            }
+            TypeError::DeniedLint { warning } => warning.notes(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Stable machine-readable code for this kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TypeError::Mismatch { .. } | TypeError::ArgMismatch { .. } => "TYPE-MISMATCH",
+            TypeError::UnknownVariable { .. } => "TYPE-UNKNOWN-VARIABLE",
+            TypeError::PrivateItem { .. } => "TYPE-PRIVATE-ITEM",
+            TypeError::ImmutableAssignment { .. } => "TYPE-IMMUTABLE-ASSIGN",
+            TypeError::SpawnCapturesMutable { .. } => "TYPE-SPAWN-MUTABLE-CAPTURE",
+            TypeError::NotImplemented { .. } => "TYPE-NOT-IMPLEMENTED",
+            TypeError::DepthLimitExceeded { .. } => "TYPE-DEPTH-LIMIT",
+            TypeError::SolveLimitExceeded { .. } => "TYPE-SOLVE-LIMIT",
+            TypeError::OccursCheck { .. } => "TYPE-INFINITE",
+            TypeError::ArityMismatch { .. } => "TYPE-ARITY",
+            TypeError::TupleTooWide { .. } => "TYPE-TUPLE-ARITY",
+            TypeError::InvariantViolation { .. } => "TYPE-INTERNAL",
+            TypeError::DuplicateType { .. } => "TYPE-DUPLICATE-TYPE",
+            TypeError::DuplicateDefinition { .. } => "TYPE-DUPLICATE-DEFINITION",
+            TypeError::UnknownType { .. } => "TYPE-UNKNOWN-TYPE",
+            TypeError::UnknownVariant { .. } => "TYPE-UNKNOWN-VARIANT",
+            TypeError::UseNotEnum { .. } => "TYPE-USE-NOT-ENUM",
+            TypeError::AmbiguousVariant { .. } => "TYPE-AMBIGUOUS-VARIANT",
+            TypeError::CapabilityInAdt { .. } => "TYPE-CAP-IN-ADT",
+            TypeError::MissingField { .. } => "TYPE-MISSING-FIELD",
+            TypeError::UnknownField { .. } => "TYPE-UNKNOWN-FIELD",
+            TypeError::DuplicateField { .. } => "TYPE-DUPLICATE-FIELD",
+            TypeError::NoSuchField { .. } => "TYPE-NO-SUCH-FIELD",
+            TypeError::WrongTypeArgCount { .. } => "TYPE-TYPE-ARG-COUNT",
+            TypeError::NonExhaustiveMatch { .. } => "TYPE-NON-EXHAUSTIVE",
+            TypeError::UnreachablePattern { .. } => "TYPE-UNREACHABLE-PATTERN",
+            TypeError::ExhaustivenessLimitExceeded { .. } => "TYPE-EXHAUSTIVENESS-LIMIT",
+            TypeError::RefutablePattern { .. } => "TYPE-REFUTABLE-PATTERN",
+            TypeError::EmptyRangePattern { .. } => "TYPE-EMPTY-RANGE",
+            TypeError::EffectMismatch { .. } => "EFFECT-MISMATCH",
+            TypeError::EffectVarLimitExceeded { .. } => "EFFECT-VAR-LIMIT",
+            TypeError::EffectCycle { .. } => "EFFECT-CYCLE",
+            TypeError::EffectChainTooDeep { .. } => "EFFECT-CHAIN-TOO-DEEP",
+            TypeError::UnknownEffect { .. } => "EFFECT-UNKNOWN",
+            TypeError::MissingExternEffects { .. } => "EFFECT-EXTERN-UNANNOTATED",
+            TypeError::ImpureFunction { .. } => "EFFECT-IMPURE",
+            TypeError::UndeclaredEffect { .. } => "EFFECT-UNDECLARED",
+            TypeError::MissingCapability { .. } => "CAP-MISSING",
+            TypeError::ExternMissingCapability { .. } => "CAP-EXTERN-MISSING",
+            TypeError::ReservedCapabilityName { .. } => "CAP-RESERVED-NAME",
+            TypeError::CapabilityAlreadyUsed { .. } => "CAP-ALREADY-USED",
+            TypeError::TaskAlreadyJoined { .. } => "TASK-ALREADY-JOINED",
+            TypeError::CapabilityMaybeUsed { .. } => "CAP-MAYBE-USED",
+            TypeError::CapabilityUsedInLoop { .. } => "CAP-USED-IN-LOOP",
+            TypeError::CapabilityCaptured { .. } => "CAP-CAPTURED",
+            TypeError::CapabilityBorrowed { .. } => "CAP-BORROWED",
+            TypeError::PossiblyUninitialized { .. } => "TYPE-UNINITIALIZED",
+            TypeError::InvalidDiscriminant { .. } => "TYPE-INVALID-DISCRIMINANT",
+            TypeError::DuplicateDiscriminant { .. } => "TYPE-DUPLICATE-DISCRIMINANT",
+            TypeError::NotAnEnum { .. } => "TYPE-NOT-ENUM",
+            TypeError::NotComparable { .. } => "TYPE-NOT-COMPARABLE",
+            TypeError::ShowCapability { .. } => "TYPE-SHOW-CAPABILITY",
+            TypeError::NotPartiallyApplicable { .. } => "TYPE-PARTIAL",
+            TypeError::PartialCapability { .. } => "CAP-CAPTURED",
+            TypeError::IterSingleUse { .. } => "TYPE-ITER-SINGLE-USE",
+            TypeError::InvalidMainParam { .. } => "TYPE-MAIN-PARAM",
+            TypeError::InvalidConstType { .. } => "TYPE-CONST-TYPE",
+            TypeError::NotConstant { .. } => "TYPE-NOT-CONSTANT",
+            TypeError::ConstCycle { .. } => "TYPE-CONST-CYCLE",
+            TypeError::ConstEval { .. } => "TYPE-CONST-EVAL",
+            TypeError::RefEscape { .. } => "REF-ESCAPE",
+            TypeError::RefInAdtField { .. } => "REF-IN-ADT-FIELD",
+            TypeError::InvalidAttribute { .. } => "TYPE-INVALID-ATTRIBUTE",
+            TypeError::InvalidDefault { .. } => "TYPE-INVALID-DEFAULT",
+            TypeError::InvalidVariadic { .. } => "TYPE-INVALID-VARIADIC",
+            TypeError::NamedArgument { .. } => "TYPE-NAMED-ARG",
+            TypeError::DeniedLint { .. } => "LINT-DENIED",
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.code(), self.to_string(), self.span())
+    }
 }
 
 impl std::fmt::Display for TypeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             TypeError::Mismatch {
+                expected, found, ..
+            } => {
+                write!(f, "Type mismatch: expected {}, found {}", expected, found)
+            }
+            TypeError::ArgMismatch {
+                callee,
+                param,
                 expected,
                 found,
-                span,
+                ..
             } => {
                 write!(
                     f,
-                    "Type mismatch at {:?}: expected {}, found {}",
-                    span, expected, found
+                    "Type mismatch: parameter '{}' of '{}' expected {}, found {}",
+                    param, callee, expected, found
                 )
             }
-            TypeError::UnknownVariable { name, span } => {
-                write!(f, "Unknown variable '{}' at {:?}", name, span)
+            TypeError::UnknownVariable { name, .. } => {
+                write!(f, "Unknown variable '{}'", name)
+            }
+            TypeError::PrivateItem { name, .. } => {
+                write!(
+                    f,
+                    "Function '{}' is private to its module; declare it `pub fn {}` to use it here",
+                    name, name
+                )
             }
-            TypeError::ImmutableAssignment { name, span } => {
+            TypeError::SpawnCapturesMutable { name, .. } => {
                 write!(
                     f,
-                    "Cannot assign to immutable variable '{}' at {:?}",
-                    name, span
+                    "Task cannot use mutable variable '{}' from outside its `spawn` block; \
+                     copy it into an immutable binding before spawning",
+                    name
                 )
             }
-            TypeError::NotImplemented { msg, span } => {
-                write!(f, "{} at {:?}", msg, span)
+            TypeError::ImmutableAssignment { name, .. } => {
+                write!(f, "Cannot assign to immutable variable '{}'", name)
+            }
+            TypeError::NotImplemented { msg, .. } => {
+                write!(f, "{}", msg)
+            }
+            TypeError::DepthLimitExceeded { .. } => {
+                write!(
+                    f,
+                    "Type inference depth limit exceeded (pathological input)"
+                )
             }
-            TypeError::DepthLimitExceeded { span } => {
+            TypeError::SolveLimitExceeded { what, limit, .. } => {
                 write!(
                     f,
-                    "Type inference depth limit exceeded at {:?} (pathological input)",
-                    span
+                    "Solve limit exceeded: this item needs more than {} {}, \
+                     the most the checking profile allows (pathological input)",
+                    limit, what
                 )
             }
-            TypeError::OccursCheck { var, ty, span } => {
-                write!(f, "Infinite type at {:?}: {} occurs in {}", span, var, ty)
+            TypeError::OccursCheck { var, ty, .. } => {
+                write!(f, "Infinite type: {} occurs in {}", var, ty)
             }
             TypeError::ArityMismatch {
-                expected,
-                found,
-                span,
+                expected, found, ..
             } => {
                 write!(
                     f,
-                    "Arity mismatch at {:?}: expected {} arguments, found {}",
-                    span, expected, found
+                    "Arity mismatch: expected {} arguments, found {}",
+                    expected, found
+                )
+            }
+            TypeError::TupleTooWide { max, found, .. } => {
+                write!(
+                    f,
+                    "Tuple too wide: {} elements, but the checking profile allows {}",
+                    found, max
                 )
             }
-            TypeError::InvariantViolation { msg, span } => {
+            TypeError::InvariantViolation { msg, .. } => {
                 write!(
                     f,
-                    "Internal error at {:?}: {} (this is a bug in the type checker)",
-                    span, msg
+                    "Internal error: {} (this is a bug in the type checker)",
+                    msg
                 )
             }
-            TypeError::DuplicateType { name, span } => {
-                write!(f, "Duplicate type definition '{}' at {:?}", name, span)
+            TypeError::DuplicateType { name, .. } => {
+                write!(f, "Duplicate type definition '{}'", name)
             }
-            TypeError::UnknownType { name, span } => {
-                write!(f, "Unknown type '{}' at {:?}", name, span)?;
+            TypeError::DuplicateDefinition { name, .. } => {
+                write!(f, "Duplicate definition of '{}'", name)
+            }
+            TypeError::UnknownType { name, .. } => {
+                write!(f, "Unknown type '{}'", name)?;
                 // Ergonomic hint: user may have written an effect name where a cap type goes
                 match name.as_str() {
-                    "Fs" | "Net" | "Time" | "Rand" | "Ai" => {
+                    "Fs" | "Net" | "Time" | "Rand" | "Ai" | "Console" => {
                         write!(
                             f,
                             ". Did you mean {}Cap? Effects like {} go in & {{...}}, capability types are parameter types",
@@ -211,109 +615,90 @@ impl std::fmt::Display for TypeError {
                 Ok(())
             }
             TypeError::UnknownVariant {
-                type_name,
-                variant,
-                span,
+                type_name, variant, ..
             } => {
+                write!(f, "Unknown variant '{}::{}'", type_name, variant)
+            }
+            TypeError::UseNotEnum { name, .. } => {
                 write!(
                     f,
-                    "Unknown variant '{}::{}' at {:?}",
-                    type_name, variant, span
+                    "Cannot import from '{}': only enum variants can be imported with `use`",
+                    name
                 )
             }
-            TypeError::CapabilityInAdt {
-                field,
-                cap_type,
-                span,
-            } => {
+            TypeError::AmbiguousVariant { name, enums, .. } => {
+                let qualified: Vec<String> =
+                    enums.iter().map(|e| format!("`{}::{}`", e, name)).collect();
                 write!(
                     f,
-                    "Capability '{}' cannot be stored in ADT field '{}' at {:?}. \
-                     Storing capabilities requires linear types (planned for Issue 011). \
-                     Pass capabilities as function parameters instead.",
-                    cap_type, field, span
+                    "Variant '{}' is ambiguous: it could be {}; write the qualified path",
+                    name,
+                    qualified.join(" or ")
                 )
             }
-            TypeError::MissingField {
-                struct_name,
-                field,
-                span,
+            TypeError::CapabilityInAdt {
+                field, cap_type, ..
             } => {
                 write!(
                     f,
-                    "Missing field '{}' in struct '{}' at {:?}",
-                    field, struct_name, span
+                    "Capability '{}' cannot be stored in ADT field '{}'. \
+                     Declare the type as `linear struct` or `linear enum` to store \
+                     capabilities, or pass capabilities as function parameters instead.",
+                    cap_type, field
                 )
             }
+            TypeError::MissingField {
+                struct_name, field, ..
+            } => {
+                write!(f, "Missing field '{}' in struct '{}'", field, struct_name)
+            }
             TypeError::UnknownField {
-                struct_name,
-                field,
-                span,
+                struct_name, field, ..
             } => {
-                write!(
-                    f,
-                    "Unknown field '{}' in struct '{}' at {:?}",
-                    field, struct_name, span
-                )
+                write!(f, "Unknown field '{}' in struct '{}'", field, struct_name)
             }
-            TypeError::DuplicateField { field, span } => {
-                write!(
-                    f,
-                    "Duplicate field '{}' in struct expression at {:?}",
-                    field, span
-                )
+            TypeError::DuplicateField { field, .. } => {
+                write!(f, "Duplicate field '{}' in struct or record", field)
+            }
+            TypeError::NoSuchField { field, record, .. } => {
+                write!(f, "No field '{}' in record type {}", field, record)
             }
             TypeError::WrongTypeArgCount {
                 type_name,
                 expected,
                 found,
-                span,
+                ..
             } => {
                 write!(
                     f,
-                    "Type '{}' expects {} type argument(s), but {} provided at {:?}",
-                    type_name, expected, found, span
+                    "Type '{}' expects {} type argument(s), but {} provided",
+                    type_name, expected, found
                 )
             }
-            TypeError::NonExhaustiveMatch { witness, span } => {
-                write!(
-                    f,
-                    "Non-exhaustive match at {:?}: pattern '{}' not covered",
-                    span, witness
-                )
+            TypeError::NonExhaustiveMatch { witness, .. } => {
+                write!(f, "Non-exhaustive match: pattern '{}' not covered", witness)
             }
-            TypeError::UnreachablePattern { arm_index, span } => {
-                write!(
-                    f,
-                    "Unreachable pattern at {:?}: arm {} will never match",
-                    span, arm_index
-                )
+            TypeError::UnreachablePattern { arm_index, .. } => {
+                write!(f, "Unreachable pattern: arm {} will never match", arm_index)
             }
-            TypeError::ExhaustivenessLimitExceeded { msg, span } => {
-                write!(
-                    f,
-                    "Exhaustiveness check limit exceeded at {:?}: {}",
-                    span, msg
-                )
+            TypeError::ExhaustivenessLimitExceeded { msg, .. } => {
+                write!(f, "Exhaustiveness check limit exceeded: {}", msg)
             }
-            TypeError::RefutablePattern { pat_desc, span } => {
+            TypeError::RefutablePattern { pat_desc, .. } => {
                 write!(
                     f,
-                    "Refutable pattern in let binding at {:?}: {} may not match all values. \
+                    "Refutable pattern in let binding: {} may not match all values. \
                      Use `match` instead.",
-                    span, pat_desc
+                    pat_desc
                 )
             }
+            TypeError::EmptyRangePattern { .. } => {
+                write!(f, "Range pattern matches no values")
+            }
             TypeError::EffectMismatch {
-                expected,
-                found,
-                span,
+                expected, found, ..
             } => {
-                write!(
-                    f,
-                    "Effect mismatch at {:?}: expected {}, found {}",
-                    span, expected, found
-                )
+                write!(f, "Effect mismatch: expected {}, found {}", expected, found)
             }
             TypeError::EffectVarLimitExceeded { limit } => {
                 write!(
@@ -322,31 +707,31 @@ impl std::fmt::Display for TypeError {
                     limit
                 )
             }
-            TypeError::EffectCycle { var, span } => {
+            TypeError::EffectCycle { var, .. } => {
                 write!(
                     f,
-                    "Cyclic effect variable {} at {:?}: effect variable refers to itself",
-                    var, span
+                    "Cyclic effect variable {}: effect variable refers to itself",
+                    var
                 )
             }
-            TypeError::EffectChainTooDeep { depth, span } => {
+            TypeError::EffectChainTooDeep { depth, .. } => {
                 write!(
                     f,
-                    "Effect substitution chain too deep ({} steps) at {:?}; possible cycle",
-                    depth, span
+                    "Effect substitution chain too deep ({} steps); possible cycle",
+                    depth
                 )
             }
-            TypeError::UnknownEffect { name, span } => {
+            TypeError::UnknownEffect { name, .. } => {
                 write!(
                     f,
-                    "Unknown effect '{}' at {:?}; known effects are Fs, Net, Time, Rand, Ai",
-                    name, span
+                    "Unknown effect '{}'; known effects are Fs, Net, Time, Rand, Ai, Console",
+                    name
                 )?;
                 // Ergonomic hint: user may have written FsCap in an effect annotation
                 if name.ends_with("Cap") {
                     if let Some(effect_name) = name.strip_suffix("Cap") {
                         let normalized = match effect_name {
-                            "Fs" | "Net" | "Time" | "Rand" | "Ai" => Some(effect_name),
+                            "Fs" | "Net" | "Time" | "Rand" | "Ai" | "Console" => Some(effect_name),
                             _ => None,
                         };
                         if let Some(eff) = normalized {
@@ -360,41 +745,84 @@ impl std::fmt::Display for TypeError {
                 }
                 Ok(())
             }
-            TypeError::MissingExternEffects { fn_name, span } => {
+            TypeError::MissingExternEffects { fn_name, .. } => {
                 write!(
                     f,
-                    "Extern function '{}' at {:?} must declare its effects. \
+                    "Extern function '{}' must declare its effects. \
                      Use `& {{}}` for pure or `& {{Fs, Net, ...}}` for effectful.",
-                    fn_name, span
+                    fn_name
                 )
             }
+            TypeError::ImpureFunction {
+                fn_name,
+                effects,
+                chain,
+                ..
+            } => {
+                if effects.concrete == 0 {
+                    return write!(
+                        f,
+                        "Pure function '{}' performs whatever effects its function parameters do; \
+                         give them function types, which have no effects",
+                        fn_name
+                    );
+                }
+                write!(
+                    f,
+                    "Pure function '{}' performs {}",
+                    fn_name,
+                    crate::effects::EffectRow::closed(effects.concrete)
+                )?;
+                for (i, callee) in chain.iter().enumerate() {
+                    match i {
+                        0 => write!(f, ": it calls '{}'", callee)?,
+                        _ => write!(f, ", which calls '{}'", callee)?,
+                    }
+                }
+                Ok(())
+            }
             TypeError::UndeclaredEffect {
                 effect,
                 fn_name,
                 declared,
                 actual,
-                span,
+                chain,
+                ..
             } => {
+                let missing: Vec<String> = crate::effects::ALL_EFFECTS
+                    .iter()
+                    .filter(|e| actual.contains(**e) && !declared.contains(**e))
+                    .map(|e| format!("{:?}", e))
+                    .collect();
                 write!(
                     f,
-                    "Function '{}' uses {} but only declares {}; add {} to the effect annotation at {:?}",
-                    fn_name, actual, declared, effect, span
-                )
+                    "Function '{}' uses {} but only declares {}; add {} to the effect annotation",
+                    fn_name,
+                    actual,
+                    declared,
+                    missing.join(", ")
+                )?;
+                if !chain.is_empty() {
+                    write!(f, ". {} comes from {}", effect, fn_name)?;
+                    for (callee, _) in chain {
+                        write!(f, " -> {}", callee)?;
+                    }
+                }
+                Ok(())
             }
             TypeError::MissingCapability {
                 effect,
                 cap_type,
                 fn_name,
-                span,
+                ..
             } => {
                 write!(
                     f,
-                    "Function '{}' requires capability {} because its effect row includes {{{}}} at {:?}. \
+                    "Function '{}' requires capability {} because its effect row includes {{{}}}. \
                      Add a `{}: {}` parameter to this function.",
                     fn_name,
                     cap_type,
                     effect,
-                    span,
                     cap_type.to_lowercase().replace("cap", ""),
                     cap_type
                 )
@@ -403,67 +831,221 @@ impl std::fmt::Display for TypeError {
                 effect,
                 cap_type,
                 fn_name,
-                span,
+                ..
             } => {
                 write!(
                     f,
-                    "Extern function '{}' declares {{{}}} effect but lacks the required '{}' capability parameter at {:?}. \
+                    "Extern function '{}' declares {{{}}} effect but lacks the required '{}' capability parameter. \
                      Add a `{}: {}` parameter. Alternatively, remove {{{}}} from the effect annotation if this extern is actually pure.",
                     fn_name,
                     effect,
                     cap_type,
-                    span,
                     cap_type.to_lowercase().replace("cap", ""),
                     cap_type,
                     effect
                 )
             }
-            TypeError::ReservedCapabilityName { name, span } => {
+            TypeError::ReservedCapabilityName { name, .. } => {
                 write!(
                     f,
-                    "Type name '{}' is reserved for the built-in {} capability type at {:?}",
+                    "Type name '{}' is reserved for the built-in {} capability type",
                     name,
-                    name.to_lowercase().replace("cap", " ").trim(),
-                    span
+                    name.to_lowercase().replace("cap", " ").trim()
                 )
             }
-            TypeError::CapabilityAlreadyUsed {
-                name,
-                used_at,
-                previous_use,
-            } => {
+            TypeError::CapabilityAlreadyUsed { name, .. } => {
                 write!(
                     f,
                     "capability '{}' has already been used; \
-                     permission was transferred at {:?}; \
-                     '{}' is no longer available at {:?}",
-                    name, previous_use, name, used_at
+                     permission was transferred earlier; \
+                     '{}' is no longer available",
+                    name, name
+                )
+            }
+            TypeError::TaskAlreadyJoined { name, .. } => {
+                write!(
+                    f,
+                    "task '{}' has already been joined or passed on; \
+                     a task can be joined only once",
+                    name
+                )
+            }
+            TypeError::CapabilityMaybeUsed { name, .. } => {
+                write!(
+                    f,
+                    "capability '{}' may have already been used; \
+                     permission was transferred in a conditional branch; \
+                     '{}' is not available on every path here",
+                    name, name
+                )
+            }
+            TypeError::CapabilityBorrowed { name, .. } => {
+                write!(
+                    f,
+                    "cannot transfer capability '{}'; \
+                     the match only borrows what it binds: use `&{}`",
+                    name, name
                 )
             }
-            TypeError::CapabilityUsedInLoop { name, used_at } => {
+            TypeError::CapabilityUsedInLoop { name, .. } => {
                 write!(
                     f,
-                    "cannot use single-use capability '{}' inside loop at {:?}; \
+                    "cannot use single-use capability '{}' inside loop; \
                      '{}' would be used on every iteration",
-                    name, used_at, name
+                    name, name
+                )
+            }
+            TypeError::CapabilityCaptured { name, fn_name, .. } => {
+                write!(
+                    f,
+                    "cannot use single-use capability '{}' inside nested function '{}'; \
+                     pass it to '{}' as a parameter instead",
+                    name, fn_name, fn_name
+                )
+            }
+            TypeError::PossiblyUninitialized {
+                name,
+                unassigned_on,
+                ..
+            } => {
+                write!(
+                    f,
+                    "variable '{}' is possibly uninitialized; \
+                     it is declared without a value",
+                    name
+                )?;
+                match unassigned_on {
+                    Some(_) => write!(f, " and not assigned on every path"),
+                    None => write!(f, " and not assigned before this use"),
+                }
+            }
+            TypeError::InvalidDiscriminant { msg, .. } => {
+                write!(f, "Invalid discriminant: {}", msg)
+            }
+            TypeError::DuplicateDiscriminant {
+                enum_name,
+                value,
+                variant,
+                previous,
+                ..
+            } => {
+                write!(
+                    f,
+                    "Duplicate discriminant {}: '{}::{}' and '{}::{}' have the same value",
+                    value, enum_name, previous, enum_name, variant
+                )
+            }
+            TypeError::NotAnEnum { ty, .. } => {
+                write!(f, "discriminant() expects an enum value, found {}", ty)
+            }
+            TypeError::ShowCapability { callee, ty, .. } => {
+                write!(
+                    f,
+                    "{}() cannot format {}: it holds a capability",
+                    callee, ty
+                )
+            }
+            TypeError::NotPartiallyApplicable { callee, .. } => {
+                write!(
+                    f,
+                    "'{}' can't be applied partially: it is built in, and only called directly",
+                    callee
+                )
+            }
+            TypeError::PartialCapability { callee, ty, .. } => {
+                let callee = match callee {
+                    Some(name) => format!(" of '{}'", name),
+                    None => String::new(),
+                };
+                write!(
+                    f,
+                    "partial application{} cannot bind {}: it holds a capability, \
+                     which every call could use again; leave it as `_` and pass it at each call",
+                    callee, ty
                 )
             }
-            TypeError::RefEscape { ty, context, span } => {
+            TypeError::IterSingleUse { ty, .. } => {
                 write!(
                     f,
-                    "reference type '{}' cannot escape to {} at {:?}; \
-                     &T is only allowed in extern function parameters",
-                    ty, context, span
+                    "iter() cannot step through {}: each item goes both to the loop and to the step function, so it must not be single-use",
+                    ty)
+            }
+            TypeError::NotComparable {
+                ty, ordered: false, ..
+            } => {
+                write!(
+                    f,
+                    "Cannot compare values of type {} with `==`: closures and capabilities have no equality",
+                    ty)
+            }
+            TypeError::NotComparable {
+                ty, ordered: true, ..
+            } => {
+                write!(
+                    f,
+                    "Cannot order values of type {}: `<`, `<=`, `>`, and `>=` need Int, Float, String, or tuples of those",
+                    ty)
+            }
+            TypeError::InvalidMainParam { name, ty, .. } => {
+                write!(
+                    f,
+                    "Parameter '{}' of main has type {}; main can only take \
+                     capabilities (such as FsCap or &NetCap), which the runtime provides",
+                    name, ty
+                )
+            }
+            TypeError::InvalidConstType { name, ty, .. } => {
+                write!(
+                    f,
+                    "Const '{}' has type {}; consts must be Int, Float, Bool, or String",
+                    name, ty
+                )
+            }
+            TypeError::NotConstant { name, .. } => {
+                write!(
+                    f,
+                    "Value of const '{}' is not a compile-time constant; \
+                     consts may use literals, other consts, and operators",
+                    name
+                )
+            }
+            TypeError::ConstCycle { cycle, .. } => {
+                write!(
+                    f,
+                    "Const '{}' depends on itself: {} -> {}",
+                    cycle[0],
+                    cycle.join(" -> "),
+                    cycle[0]
+                )
+            }
+            TypeError::ConstEval { message, .. } => {
+                write!(f, "Const evaluation failed: {}", message)
+            }
+            TypeError::RefEscape { ty, context, .. } => {
+                write!(
+                    f,
+                    "reference type '{}' cannot escape to {}; \
+                     &T is only allowed in function parameters",
+                    ty, context
                 )
             }
-            TypeError::RefInAdtField { field, ty, span } => {
+            TypeError::RefInAdtField { field, ty, .. } => {
                 write!(
                     f,
-                    "reference type '{}' cannot be stored in ADT field '{}' at {:?}; \
-                     &T is only allowed in extern function parameters",
-                    ty, field, span
+                    "reference type '{}' cannot be stored in ADT field '{}'; \
+                     &T is only allowed in function parameters",
+                    ty, field
                 )
             }
+            TypeError::InvalidAttribute { msg, .. }
+            | TypeError::InvalidDefault { msg, .. }
+            | TypeError::InvalidVariadic { msg, .. }
+            | TypeError::NamedArgument { msg, .. } => {
+                write!(f, "{}", msg)
+            }
+            TypeError::DeniedLint { warning } => {
+                write!(f, "{} (denied)", warning)
+            }
         }
     }
 }
@@ -471,15 +1053,44 @@ impl std::fmt::Display for TypeError {
 impl std::error::Error for TypeError {}
 
 /// Type checker with environment for let bindings
+///
+/// Cloning is cheap: the environment and ADT registry are shared until one
+/// of the clones changes them. See [`TypeChecker::snapshot`].
+#[derive(Clone)]
 pub struct TypeChecker {
     /// Maps variable names to their type schemes
-    env: HashMap<String, Scheme>,
+    env: Arc<HashMap<String, Scheme>>,
     /// Inference context for constraint generation
     infer_ctx: InferCtx,
     /// Registry of ADT (struct/enum) definitions
-    adt_registry: AdtRegistry,
+    adt_registry: Arc<AdtRegistry>,
+    /// Lint levels for optional checks
+    lint_config: LintConfig,
+    /// Lint levels the checked module's items set for themselves
+    item_lints: Arc<ItemLintLevels>,
+    /// Warnings collected from lints at `Warn` level
+    warnings: Vec<Warning>,
+    /// Resolved types of checked top-level items, in source order
+    typed_items: Vec<TypedItem>,
+    /// What solving each checked item's constraints took, in checking order
+    solve_stats: Vec<(String, SolveStats)>,
+    /// The entry of `solve_stats` for the item being checked
+    solve_item: Option<usize>,
+    /// Values of the module's consts, evaluated before function bodies
+    consts: Arc<ConstTable>,
+    /// Functions of imported interfaces that aren't `pub`
+    private_imports: HashSet<String>,
+    /// What the checked module's names refer to
+    resolution: Arc<Resolution>,
+    /// The calls each checked function makes by name, for effect errors to
+    /// say where an effect comes from
+    effect_calls: Arc<HashMap<String, Vec<EffectCall>>>,
 }
 
+/// A [`TypeChecker`]'s state, saved by [`TypeChecker::snapshot`].
+#[derive(Clone)]
+pub struct CheckerSnapshot(TypeChecker);
+
 impl Default for TypeChecker {
     fn default() -> Self {
         Self::new()
@@ -489,11 +1100,189 @@ impl Default for TypeChecker {
 impl TypeChecker {
     /// Create a new type checker with an empty environment
     pub fn new() -> Self {
-        Self {
-            env: HashMap::new(),
-            infer_ctx: InferCtx::new(),
-            adt_registry: AdtRegistry::with_builtins(),
+        Self::new_with_profile(Profile::Default)
+    }
+
+    /// Create a type checker enforcing the limits of `profile`
+    pub fn new_with_profile(profile: Profile) -> Self {
+        let mut checker = Self {
+            env: Arc::default(),
+            infer_ctx: InferCtx::new().with_limits(profile.limits()),
+            adt_registry: Arc::new(AdtRegistry::with_builtins()),
+            lint_config: LintConfig::new(),
+            item_lints: Arc::default(),
+            warnings: Vec::new(),
+            typed_items: Vec::new(),
+            solve_stats: Vec::new(),
+            solve_item: None,
+            consts: Arc::default(),
+            private_imports: HashSet::new(),
+            resolution: Arc::default(),
+            effect_calls: Arc::default(),
+        };
+        checker.register_intrinsics();
+        checker
+    }
+
+    /// Register built-in functions. User definitions of the same name
+    /// replace them in pass 1.
+    ///
+    /// - `drop: ∀a. (a) -> ()` consumes its argument, so a capability can be
+    ///   discarded explicitly before the end of its scope.
+    /// - `discriminant: ∀a. (a) -> Int` reads an enum value's tag; its
+    ///   argument is checked to be an enum once types are solved.
+    /// - `show: ∀a. (a) -> String` formats any value; its argument is
+    ///   checked to hold no capability once types are solved.
+    /// - `par: ∀x a y b. ((x) -> a, x, (y) -> b, y) -> (a, b)` calls
+    ///   `f(x)` and `g(y)`, possibly at once. Each function keeps its own
+    ///   effect row, and the call has the effects of both; the arguments
+    ///   are how the branches get their capabilities.
+    /// - `join: ∀a. (Task<a>) -> a` waits for a task started by `spawn` and
+    ///   gives its result. `Task` is linear, so a task is joined at most once.
+    /// - `iter: ∀a. (a, (a) -> Option<a>) -> Iter<a>` is the sequence of
+    ///   `first`, `step(first)`, ... up to the first `None`; `next: ∀a.
+    ///   (Iter<a>) -> Option<(a, Iter<a>)>` splits off its first item, and a
+    ///   `for` loop runs over the items. `map`, `filter` and `take` adapt a
+    ///   sequence without running it. An `Iter` runs its functions lazily,
+    ///   wherever it is iterated, so they must be pure.
+    /// - `todo: () -> !` and `unreachable: () -> !` never return; a call to
+    ///   either fits any expected type and fails at runtime if reached.
+    fn register_intrinsics(&mut self) {
+        let a = self.infer_ctx.fresh_var_id();
+        Arc::make_mut(&mut self.env).insert(
+            "drop".to_string(),
+            Scheme {
+                type_vars: vec![a],
+                effect_vars: vec![],
+                ty: Ty::arrow(vec![Ty::Var(a)], Ty::unit()),
+            },
+        );
+        let a = self.infer_ctx.fresh_var_id();
+        let discriminant = Scheme {
+            type_vars: vec![a],
+            effect_vars: vec![],
+            ty: Ty::arrow(vec![Ty::Var(a)], Ty::int()),
+        };
+        self.infer_ctx.set_discriminant_scheme(discriminant.clone());
+        Arc::make_mut(&mut self.env).insert("discriminant".to_string(), discriminant);
+        let a = self.infer_ctx.fresh_var_id();
+        let show = Scheme {
+            type_vars: vec![a],
+            effect_vars: vec![],
+            ty: Ty::arrow(vec![Ty::Var(a)], Ty::string()),
+        };
+        self.infer_ctx.set_show_scheme(show.clone());
+        Arc::make_mut(&mut self.env).insert("show".to_string(), show);
+        let [a, b, x, y] = [(); 4].map(|_| self.infer_ctx.fresh_var_id());
+        let effect_vars: Vec<_> = (0..3)
+            .map(|_| self.infer_ctx.fresh_effect_var_id())
+            .collect::<Result<_, _>>()
+            .expect("a fresh checker has effect variables to spare");
+        let branch = |arg, ret, var| {
+            Ty::arrow_eff(vec![Ty::Var(arg)], Ty::Var(ret), EffectRow::open(0, var))
+        };
+        let par = Scheme {
+            type_vars: vec![a, b, x, y],
+            effect_vars: effect_vars.clone(),
+            ty: Ty::arrow_eff(
+                vec![
+                    branch(x, a, effect_vars[0]),
+                    Ty::Var(x),
+                    branch(y, b, effect_vars[1]),
+                    Ty::Var(y),
+                ],
+                Ty::Tuple(vec![Ty::Var(a), Ty::Var(b)]),
+                EffectRow::open(0, effect_vars[2]),
+            ),
+        };
+        self.infer_ctx.set_par_scheme(par.clone());
+        Arc::make_mut(&mut self.env).insert("par".to_string(), par);
+        let a = self.infer_ctx.fresh_var_id();
+        Arc::make_mut(&mut self.env).insert(
+            "join".to_string(),
+            Scheme {
+                type_vars: vec![a],
+                effect_vars: vec![],
+                ty: Ty::arrow(vec![Ty::adt("Task", vec![Ty::Var(a)])], Ty::Var(a)),
+            },
+        );
+        let iter_of = |a| Ty::adt("Iter", vec![Ty::Var(a)]);
+        let a = self.infer_ctx.fresh_var_id();
+        let step = Ty::arrow(vec![Ty::Var(a)], Ty::adt("Option", vec![Ty::Var(a)]));
+        let iter = Scheme {
+            type_vars: vec![a],
+            effect_vars: vec![],
+            ty: Ty::arrow(vec![Ty::Var(a), step], iter_of(a)),
+        };
+        self.infer_ctx.set_iter_scheme(iter.clone());
+        Arc::make_mut(&mut self.env).insert("iter".to_string(), iter);
+        let a = self.infer_ctx.fresh_var_id();
+        let split = Ty::Tuple(vec![Ty::Var(a), iter_of(a)]);
+        Arc::make_mut(&mut self.env).insert(
+            "next".to_string(),
+            Scheme {
+                type_vars: vec![a],
+                effect_vars: vec![],
+                ty: Ty::arrow(vec![iter_of(a)], Ty::adt("Option", vec![split])),
+            },
+        );
+        let [a, b] = [(); 2].map(|_| self.infer_ctx.fresh_var_id());
+        Arc::make_mut(&mut self.env).insert(
+            "map".to_string(),
+            Scheme {
+                type_vars: vec![a, b],
+                effect_vars: vec![],
+                ty: Ty::arrow(
+                    vec![iter_of(a), Ty::arrow(vec![Ty::Var(a)], Ty::Var(b))],
+                    iter_of(b),
+                ),
+            },
+        );
+        let a = self.infer_ctx.fresh_var_id();
+        Arc::make_mut(&mut self.env).insert(
+            "filter".to_string(),
+            Scheme {
+                type_vars: vec![a],
+                effect_vars: vec![],
+                ty: Ty::arrow(
+                    vec![iter_of(a), Ty::arrow(vec![Ty::Var(a)], Ty::bool_())],
+                    iter_of(a),
+                ),
+            },
+        );
+        let a = self.infer_ctx.fresh_var_id();
+        Arc::make_mut(&mut self.env).insert(
+            "take".to_string(),
+            Scheme {
+                type_vars: vec![a],
+                effect_vars: vec![],
+                ty: Ty::arrow(vec![iter_of(a), Ty::int()], iter_of(a)),
+            },
+        );
+        for name in ["todo", "unreachable"] {
+            Arc::make_mut(&mut self.env).insert(
+                name.to_string(),
+                Scheme {
+                    type_vars: vec![],
+                    effect_vars: vec![],
+                    ty: Ty::arrow(vec![], Ty::Never),
+                },
+            );
         }
+        Arc::make_mut(&mut self.env).insert(
+            "arg_count".to_string(),
+            Scheme::mono(Ty::arrow(vec![], Ty::int())),
+        );
+    }
+
+    /// Global functions whose calls never return (`todo`, `unreachable`,
+    /// unless the program defines its own).
+    fn diverging_fns(&self) -> Vec<String> {
+        self.env
+            .iter()
+            .filter(|(_, scheme)| matches!(&scheme.ty, Ty::Arrow(_, ret, _) if **ret == Ty::Never))
+            .map(|(name, _)| name.clone())
+            .collect()
     }
 
     /// Get a reference to the ADT registry
@@ -501,16 +1290,152 @@ impl TypeChecker {
         &self.adt_registry
     }
 
-    /// Infer the type of an expression
-    ///
-    /// This is the main entry point for expression type checking.
-    /// It creates a CheckContext, infers the expression type, solves constraints,
+    /// Set the level for a single lint
+    pub fn set_lint_level(&mut self, lint: Lint, level: LintLevel) {
+        self.lint_config.set(lint, level);
+    }
+
+    /// Replace the lint configuration
+    pub fn with_lint_config(mut self, config: LintConfig) -> Self {
+        self.lint_config = config;
+        self
+    }
+
+    /// Warnings collected so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Take the collected warnings, leaving the list empty
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Resolved types of the `fn`, `extern fn`, `let`, and `const` items checked so
+    /// far, in source order
+    pub fn typed_items(&self) -> &[TypedItem] {
+        &self.typed_items
+    }
+
+    /// The constraints and solver steps each `fn`, `let`, and `const` item
+    /// checked so far took, in checking order. An item that failed to check
+    /// is the last, with what it took up to the error.
+    pub fn solve_stats(&self) -> &[(String, SolveStats)] {
+        &self.solve_stats
+    }
+
+    /// Values of the consts in the last checked module
+    pub fn consts(&self) -> &ConstTable {
+        &self.consts
+    }
+
+    /// What the names of the last checked module refer to
+    pub fn resolution(&self) -> &Resolution {
+        &self.resolution
+    }
+
+    /// Save the checker's state, for a notebook-style frontend to return to
+    /// with [`restore`](Self::restore) after checking cells speculatively.
+    /// Cheap: the snapshot shares the environment and ADT registry with the
+    /// checker until the checker next changes them.
+    pub fn snapshot(&self) -> CheckerSnapshot {
+        CheckerSnapshot(self.clone())
+    }
+
+    /// Return to the state saved in `snapshot`, forgetting every definition,
+    /// typed item, and warning since. A snapshot can be restored any number
+    /// of times, so it can be branched from repeatedly.
+    pub fn restore(&mut self, snapshot: &CheckerSnapshot) {
+        *self = snapshot.0.clone();
+    }
+
+    /// The generalized schemes of `module`'s functions, for checking other
+    /// modules against it. Call after `check_module(module)` succeeds.
+    pub fn interface(&self, module: &Module) -> Interface {
+        Interface::from_env(module, &self.env, &self.adt_registry)
+    }
+
+    /// Bring an upstream module's functions into scope from its interface,
+    /// with fresh variables, so their bodies aren't re-checked. A function
+    /// the checked module defines itself replaces an imported one.
+    pub fn import_interface(&mut self, interface: &Interface) -> Result<(), TypeError> {
+        for (name, scheme) in &interface.fns {
+            let type_vars: Vec<TypeVarId> = scheme
+                .type_vars
+                .iter()
+                .map(|_| self.infer_ctx.fresh_var_id())
+                .collect();
+            let effect_vars = scheme
+                .effect_vars
+                .iter()
+                .map(|_| self.infer_ctx.fresh_effect_var_id())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(infer_error_to_type_error)?;
+            let types = scheme
+                .type_vars
+                .iter()
+                .copied()
+                .zip(type_vars.iter().copied());
+            let effects = scheme
+                .effect_vars
+                .iter()
+                .copied()
+                .zip(effect_vars.iter().copied());
+            let ty = interface::rename(&scheme.ty, &types.collect(), &effects.collect())
+                .ok_or_else(|| TypeError::InvariantViolation {
+                    msg: format!("interface scheme of '{}' has an unbound variable", name),
+                    span: Span::default(),
+                })?;
+            Arc::make_mut(&mut self.env).insert(
+                name.clone(),
+                Scheme {
+                    type_vars,
+                    effect_vars,
+                    ty,
+                },
+            );
+        }
+        self.private_imports
+            .extend(interface.private.iter().cloned());
+        Ok(())
+    }
+
+    /// The level of `lint` at `span`: the level the enclosing item sets,
+    /// else the configured one.
+    fn lint_level(&self, lint: Lint, span: Span) -> LintLevel {
+        self.item_lints
+            .level_at(lint, span)
+            .unwrap_or_else(|| self.lint_config.level(lint))
+    }
+
+    /// Route lint findings through their level at the finding.
+    /// `Deny` reports the first finding as an error; `Warn` records them all.
+    fn emit_warnings(&mut self, found: Vec<Warning>) -> Result<(), TypeError> {
+        for warning in found {
+            match self.lint_level(warning.lint(), warning.span()) {
+                LintLevel::Allow => {}
+                LintLevel::Warn => self.warnings.push(warning),
+                LintLevel::Deny => return Err(TypeError::DeniedLint { warning }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Infer the type of an expression
+    ///
+    /// This is the main entry point for expression type checking.
+    /// It creates a CheckContext, infers the expression type, solves constraints,
     /// and returns the final type.
     pub fn infer_expr(&mut self, expr: &strata_ast::ast::Expr) -> Result<Ty, TypeError> {
         use super::infer::constraint::CheckContext;
 
         // Create a CheckContext from the current environment with ADT registry
-        let ctx = CheckContext::from_env_with_registry(self.env.clone(), self.adt_registry.clone());
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone())
+                .with_consts(self.consts.clone())
+                .with_resolution(self.resolution.clone());
+
+        let nested = self.predeclare_nested_fns(nested_fns_in_expr(expr))?;
 
         // Infer the expression type
         let ty = self
@@ -520,10 +1445,15 @@ impl TypeChecker {
 
         // Solve constraints
         let constraints = self.infer_ctx.take_constraints();
-        let mut solver = Solver::new();
-        let subst = solver
-            .solve(constraints)
-            .map_err(solve_error_to_type_error)?;
+        self.infer_ctx.take_let_bindings();
+        let subst = self.solve(constraints)?;
+        self.check_enum_args(&subst)?;
+        self.check_show_args(&subst)?;
+        self.check_iter_args(&subst)?;
+        self.check_partial_args(&subst)?;
+        self.check_comparisons(&subst)?;
+        let local_fns = self.resolve_nested_fns(&nested, &subst)?;
+        self.check_nested_fns(&nested, &local_fns, &subst)?;
 
         // Apply substitution to get final type
         let final_ty = subst
@@ -532,6 +1462,74 @@ impl TypeChecker {
         Ok(final_ty)
     }
 
+    /// Check that `expr` has type `expected`, in the environment of the
+    /// module checked so far (its functions, types, and consts are in
+    /// scope).
+    ///
+    /// For validating snippets such as a configured callback that must be
+    /// `fn(Int) -> Bool`. The check leaves the checker as it found it: no
+    /// constraints, type variables, or warnings from `expr` remain, so it
+    /// can be repeated or followed by more checks. Returns every error
+    /// found: the type error, if any, and any use of an unassigned
+    /// variable.
+    pub fn check_expr_with_expected(
+        &mut self,
+        expr: &strata_ast::ast::Expr,
+        expected: &Ty,
+    ) -> Result<(), Vec<TypeError>> {
+        let saved_ctx = self.infer_ctx.clone();
+        let saved_warnings = self.warnings.len();
+        let result = self.check_expr_against(expr, expected);
+        self.infer_ctx = saved_ctx;
+        self.warnings.truncate(saved_warnings);
+
+        let mut errors: Vec<TypeError> = result.err().into_iter().collect();
+        if let Err(e) = crate::init_check::check_expr(expr, &self.diverging_fns()) {
+            errors.push(init_error_to_type_error(e));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn check_expr_against(
+        &mut self,
+        expr: &strata_ast::ast::Expr,
+        expected: &Ty,
+    ) -> Result<(), TypeError> {
+        use super::infer::constraint::CheckContext;
+        use super::infer::ty::Constraint;
+
+        // Anything left over from an earlier failed check isn't ours
+        self.infer_ctx.take_constraints();
+        self.infer_ctx.take_let_bindings();
+
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone())
+                .with_consts(self.consts.clone())
+                .with_resolution(self.resolution.clone());
+        let nested = self.predeclare_nested_fns(nested_fns_in_expr(expr))?;
+        let ty = self
+            .infer_ctx
+            .infer_expr_ctx(&ctx, expr)
+            .map_err(infer_error_to_type_error)?;
+        self.infer_ctx
+            .add_constraint(Constraint::Equal(ty, expected.clone(), expr.span()));
+
+        let constraints = self.infer_ctx.take_constraints();
+        self.infer_ctx.take_let_bindings();
+        let subst = self.solve(constraints)?;
+        self.check_enum_args(&subst)?;
+        self.check_show_args(&subst)?;
+        self.check_iter_args(&subst)?;
+        self.check_partial_args(&subst)?;
+        self.check_comparisons(&subst)?;
+        let local_fns = self.resolve_nested_fns(&nested, &subst)?;
+        self.check_nested_fns(&nested, &local_fns, &subst)
+    }
+
     /// Type check an entire module using two-pass approach
     ///
     /// Pass 1a: Register all ADT definitions (struct/enum)
@@ -540,6 +1538,42 @@ impl TypeChecker {
     /// Pass 2: Check let bindings and function bodies
     ///         After checking each function, generalize and update env
     pub fn check_module(&mut self, module: &Module) -> Result<(), TypeError> {
+        let module =
+            resolve::expand_variant_imports(module).map_err(resolve_error_to_type_error)?;
+        let module = match derive::expand_derives(&module) {
+            Cow::Owned(expanded) => Cow::Owned(expanded),
+            Cow::Borrowed(_) => module,
+        };
+        let module =
+            match named_args::expand_named_args(&module).map_err(|e| TypeError::NamedArgument {
+                msg: e.to_string(),
+                span: e.span(),
+            })? {
+                Cow::Owned(expanded) => Cow::Owned(expanded),
+                Cow::Borrowed(_) => module,
+            };
+        self.check_items(&module).map_err(|err| match err {
+            // A name only an import's private functions define
+            TypeError::UnknownVariable { name, span } if self.private_imports.contains(&name) => {
+                TypeError::PrivateItem { name, span }
+            }
+            err => err,
+        })
+    }
+
+    fn check_items(&mut self, module: &Module) -> Result<(), TypeError> {
+        self.resolution = Arc::new(resolve::resolve(module));
+
+        let found =
+            crate::attrs::check_module(module).map_err(|e| TypeError::InvalidAttribute {
+                msg: e.msg,
+                span: e.span,
+            })?;
+        self.item_lints = Arc::new(ItemLintLevels::from_module(module));
+        self.emit_warnings(found)?;
+        let found = crate::deprecation::check_module(module, &self.resolution);
+        self.emit_warnings(found)?;
+
         // Pass 1a: Register all ADT definitions
         for item in &module.items {
             match item {
@@ -549,14 +1583,46 @@ impl TypeChecker {
             }
         }
 
-        // Pass 1b: Add enum constructors to environment
+        // Pass 1a': Only linear ADTs may hold linear ADTs (needs every ADT
+        // registered, since a field may name a type defined later)
+        for item in &module.items {
+            let (name, linear, span) = match item {
+                Item::Struct(def) => (&def.name.text, def.linear, def.span),
+                Item::Enum(def) => (&def.name.text, def.linear, def.span),
+                _ => continue,
+            };
+            if !linear {
+                self.check_no_linear_fields(name, span)?;
+            }
+        }
+
+        // The prelude `Option`, unless the module defines its own
+        self.register_prelude_option()?;
+        // `arg : (Int) -> Option<String>`, the program's i-th argument
+        if self.has_prelude_shaped_option() {
+            Arc::make_mut(&mut self.env).insert(
+                "arg".to_string(),
+                Scheme::mono(Ty::arrow(
+                    vec![Ty::int()],
+                    Ty::adt("Option", vec![Ty::string()]),
+                )),
+            );
+        }
+
+        // Pass 1b: Add enum and tuple struct constructors to environment
         // (Must happen after all ADTs are registered so types can reference each other)
         for item in &module.items {
-            if let Item::Enum(def) = item {
-                self.register_enum_constructors(def)?;
+            match item {
+                Item::Enum(def) => self.register_enum_constructors(def)?,
+                Item::Struct(def) if def.tuple => self.register_struct_constructor(def)?,
+                _ => {}
             }
         }
 
+        // Functions are predeclared by name below, so a second definition
+        // would silently replace the first's signature
+        check_duplicate_definitions(module)?;
+
         // Pass 1c: Predeclare all functions with MONOMORPHIC signatures
         // This ensures that recursive calls see the same type variables,
         // preventing unsound polymorphic self-reference.
@@ -571,10 +1637,11 @@ impl TypeChecker {
                     let fn_scheme = Scheme::mono(fn_ty);
 
                     // Add to environment
-                    self.env.insert(decl.name.text.clone(), fn_scheme);
+                    Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), fn_scheme);
                 }
                 Item::ExternFn(decl) => {
                     // Register extern fn with its type signature (no body to check)
+                    let variadic = self.variadic_bound(decl)?;
                     let fn_ty = self.extract_extern_fn_signature(decl)?;
 
                     // Validate: extern fn must have capability parameters matching
@@ -602,30 +1669,111 @@ impl TypeChecker {
                     }
 
                     let fn_scheme = Scheme::mono(fn_ty);
-                    self.env.insert(decl.name.text.clone(), fn_scheme);
+                    if let Some(bound) = variadic {
+                        self.infer_ctx.set_extern_variadic(
+                            &decl.name.text,
+                            fn_scheme.clone(),
+                            bound,
+                        );
+                    }
+                    Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), fn_scheme);
+                }
+                _ => {}
+            }
+        }
+
+        // Pass 1d: Check and evaluate consts, before any body that may use
+        // one as a pattern. Annotations put every const in scope for every
+        // other's value; evaluation orders them and rejects cycles.
+        for item in &module.items {
+            if let Item::Const(decl) = item {
+                let ty = self.ty_from_type_expr(&decl.ty)?;
+                if !consts::is_const_type(&ty) {
+                    return Err(TypeError::InvalidConstType {
+                        name: decl.name.text.clone(),
+                        ty,
+                        span: decl.ty.span(),
+                    });
                 }
+                Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), Scheme::mono(ty));
+            }
+        }
+        for item in &module.items {
+            if let Item::Const(decl) = item {
+                self.tally_solving(&decl.name.text, |c| c.check_const(decl))?;
+            }
+        }
+        self.consts = Arc::new(consts::evaluate(module).map_err(const_error_to_type_error)?);
+        for item in &module.items {
+            match item {
+                Item::ExternFn(decl) => self.check_extern_defaults(module, decl)?,
+                Item::Struct(def) => self.check_field_defaults(module, def)?,
                 _ => {}
             }
         }
 
         // Pass 2: Check all items (let bindings and function bodies)
         for item in &module.items {
-            self.check_item(item)?;
+            match item {
+                Item::Fn(FnDecl { name, .. }) | Item::Let(LetDecl { name, .. }) => {
+                    self.tally_solving(&name.text, |c| c.check_item(item))?
+                }
+                _ => self.check_item(item)?,
+            }
+        }
+
+        // Pass 3: `pure fn`s perform no effects (needs every function's
+        // final effect row, since a call may be to one defined later)
+        for item in &module.items {
+            match item {
+                Item::Fn(decl) if decl.pure => self.check_pure_fn(decl)?,
+                _ => {}
+            }
         }
 
         Ok(())
     }
 
+    /// Run `check` on the item called `name`, tallying the constraints it
+    /// solves and the steps solving them takes
+    fn tally_solving(
+        &mut self,
+        name: &str,
+        check: impl FnOnce(&mut Self) -> Result<(), TypeError>,
+    ) -> Result<(), TypeError> {
+        self.solve_stats
+            .push((name.to_string(), SolveStats::default()));
+        self.solve_item = Some(self.solve_stats.len() - 1);
+        let result = check(self);
+        self.solve_item = None;
+        result
+    }
+
     /// Type check a single top-level item
     fn check_item(&mut self, item: &Item) -> Result<(), TypeError> {
         match item {
             Item::Let(decl) => self.check_let(decl),
             Item::Fn(decl) => self.check_fn(decl),
+            // Consts are checked and evaluated in pass 1d
+            Item::Const(_) => Ok(()),
             // ADT registration happens in pass 1 (register_struct/register_enum)
             Item::Struct(_) => Ok(()),
             Item::Enum(_) => Ok(()),
+            // Variant imports are expanded away before checking
+            Item::Use(_) => Ok(()),
             // Extern fn has no body; type was registered in pass 1c
-            Item::ExternFn(_) => Ok(()),
+            Item::ExternFn(decl) => {
+                if let Some(scheme) = self.env.get(&decl.name.text) {
+                    self.typed_items.push(TypedItem {
+                        name: decl.name.text.clone(),
+                        kind: TypedItemKind::ExternFn,
+                        span: decl.span,
+                        ty: scheme.ty.clone(),
+                        locals: Vec::new(),
+                    });
+                }
+                Ok(())
+            }
         }
     }
 
@@ -633,7 +1781,12 @@ impl TypeChecker {
     fn check_let(&mut self, decl: &LetDecl) -> Result<(), TypeError> {
         // Create a CheckContext with ADT registry so struct/enum expressions work
         use super::infer::constraint::CheckContext;
-        let ctx = CheckContext::from_env_with_registry(self.env.clone(), self.adt_registry.clone());
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone())
+                .with_consts(self.consts.clone())
+                .with_resolution(self.resolution.clone());
+
+        let nested = self.predeclare_nested_fns(nested_fns_in_expr(&decl.value))?;
 
         // Infer the type of the value expression
         let inferred_ty = self
@@ -654,15 +1807,19 @@ impl TypeChecker {
 
         // Solve constraints
         let constraints = self.infer_ctx.take_constraints();
-        let mut solver = Solver::new();
-        let subst = solver
-            .solve(constraints)
-            .map_err(solve_error_to_type_error)?;
+        let subst = self.solve(constraints)?;
+        self.check_enum_args(&subst)?;
+        self.check_show_args(&subst)?;
+        self.check_iter_args(&subst)?;
+        self.check_partial_args(&subst)?;
+        self.check_comparisons(&subst)?;
 
         // Apply substitution to get final type
         let final_ty = subst
             .apply(&inferred_ty)
             .map_err(|e| subst_error_to_type_error(e, decl.span))?;
+        let local_fns = self.resolve_nested_fns(&nested, &subst)?;
+        self.check_nested_fns(&nested, &local_fns, &subst)?;
 
         // ---- Settle point: reject &T escaping to let bindings ----
         // After solving, the type may have resolved to contain Ty::Ref through
@@ -676,6 +1833,18 @@ impl TypeChecker {
             });
         }
 
+        crate::init_check::check_expr(&decl.value, &self.diverging_fns())
+            .map_err(init_error_to_type_error)?;
+
+        let locals = self.resolve_let_bindings(&subst)?;
+        self.typed_items.push(TypedItem {
+            name: decl.name.text.clone(),
+            kind: TypedItemKind::Let,
+            span: decl.span,
+            ty: final_ty.clone(),
+            locals,
+        });
+
         // Generalize: free vars in type that aren't already in environment become ∀-bound
         use super::infer::ty::free_vars_env;
         let env_vars = free_vars_env(&self.env);
@@ -685,8 +1854,168 @@ impl TypeChecker {
             .generalize(final_ty, &env_vars, &env_eff_vars);
 
         // Add to environment
-        self.env.insert(decl.name.text.clone(), scheme);
+        Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), scheme);
+
+        Ok(())
+    }
+
+    /// Type check a const's value against its annotation (Pass 1d)
+    fn check_const(&mut self, decl: &ConstDecl) -> Result<(), TypeError> {
+        use super::infer::constraint::CheckContext;
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone());
+        let nested = self.predeclare_nested_fns(nested_fns_in_expr(&decl.value))?;
+        let inferred_ty = self
+            .infer_ctx
+            .infer_expr_ctx(&ctx, &decl.value)
+            .map_err(infer_error_to_type_error)?;
+        let expected = self.ty_from_type_expr(&decl.ty)?;
+        self.infer_ctx
+            .add_constraint(super::infer::ty::Constraint::Equal(
+                expected.clone(),
+                inferred_ty,
+                decl.span,
+            ));
+        let constraints = self.infer_ctx.take_constraints();
+        self.infer_ctx.take_let_bindings();
+        let subst = self.solve(constraints)?;
+        self.check_enum_args(&subst)?;
+        self.check_show_args(&subst)?;
+        self.check_iter_args(&subst)?;
+        self.check_partial_args(&subst)?;
+        self.check_comparisons(&subst)?;
+        let local_fns = self.resolve_nested_fns(&nested, &subst)?;
+        self.check_nested_fns(&nested, &local_fns, &subst)?;
+
+        self.typed_items.push(TypedItem {
+            name: decl.name.text.clone(),
+            kind: TypedItemKind::Const,
+            span: decl.span,
+            ty: expected,
+            locals: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Check an extern fn's parameter default values (Pass 1d). Once one
+    /// parameter has a default, every later one must; each default is a
+    /// compile-time constant of its parameter's type, which must be one a
+    /// const may have. Calls may then leave the defaulted parameters off.
+    fn check_extern_defaults(
+        &mut self,
+        module: &Module,
+        decl: &strata_ast::ast::ExternFnDecl,
+    ) -> Result<(), TypeError> {
+        use super::infer::constraint::CheckContext;
+        let Some(required) = decl.params.iter().position(|p| p.default.is_some()) else {
+            return Ok(());
+        };
+        let Some(scheme) = self.env.get(&decl.name.text).cloned() else {
+            return Ok(());
+        };
+        let Ty::Arrow(param_tys, _, _) = &scheme.ty else {
+            return Ok(());
+        };
+        let defaulted = &decl.params[required..];
+        if let Some(param) = defaulted.iter().find(|p| p.default.is_none()) {
+            return Err(TypeError::InvalidDefault {
+                msg: format!(
+                    "Parameter '{}' of extern fn '{}' needs a default value, \
+                     since '{}' before it has one",
+                    param.name.text, decl.name.text, decl.params[required].name.text
+                ),
+                span: param.span,
+            });
+        }
+        consts::evaluate_defaults(module, &decl.params)
+            .map_err(|err| default_error("parameter", err))?;
+
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone());
+        for (param, ty) in defaulted.iter().zip(&param_tys[required..]) {
+            let Some(value) = &param.default else {
+                continue;
+            };
+            if !consts::is_const_type(ty) {
+                return Err(TypeError::InvalidDefault {
+                    msg: format!(
+                        "Parameter '{}' of extern fn '{}' has a default value, but only \
+                         Int, Float, Bool, and String parameters may",
+                        param.name.text, decl.name.text
+                    ),
+                    span: value.span(),
+                });
+            }
+            self.check_default(&ctx, value, ty)?;
+        }
+
+        self.infer_ctx
+            .set_extern_defaults(&decl.name.text, scheme, required);
+        Ok(())
+    }
+
+    /// Check the default values of struct `def`'s fields (Pass 1d) and
+    /// record them in the ADT registry, for struct literals that leave the
+    /// fields off
+    fn check_field_defaults(&mut self, module: &Module, def: &StructDef) -> Result<(), TypeError> {
+        use super::infer::constraint::CheckContext;
+        if def.fields.iter().all(|f| f.default.is_none()) {
+            return Ok(());
+        }
+        let Some(field_defs) = self
+            .adt_registry
+            .get(&def.name.text)
+            .and_then(|adt| adt.fields())
+            .map(<[FieldDef]>::to_vec)
+        else {
+            return Ok(());
+        };
+        let defaults = consts::evaluate_field_defaults(module, &def.fields)
+            .map_err(|err| default_error("field", err))?;
+
+        let ctx =
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone());
+        for (field, field_def) in def.fields.iter().zip(&field_defs) {
+            let Some(value) = &field.default else {
+                continue;
+            };
+            if !consts::is_const_type(&field_def.ty) {
+                return Err(TypeError::InvalidDefault {
+                    msg: format!(
+                        "Field '{}' of struct '{}' has a default value, but only \
+                         Int, Float, Bool, and String fields may",
+                        field.name.text, def.name.text
+                    ),
+                    span: value.span(),
+                });
+            }
+            self.check_default(&ctx, value, &field_def.ty)?;
+        }
 
+        Arc::make_mut(&mut self.adt_registry).set_field_defaults(&def.name.text, defaults);
+        Ok(())
+    }
+
+    /// Check a default value against the type `ty` it gives a value of
+    fn check_default(
+        &mut self,
+        ctx: &super::infer::constraint::CheckContext,
+        value: &Expr,
+        ty: &Ty,
+    ) -> Result<(), TypeError> {
+        let inferred_ty = self
+            .infer_ctx
+            .infer_expr_ctx(ctx, value)
+            .map_err(infer_error_to_type_error)?;
+        self.infer_ctx
+            .add_constraint(super::infer::ty::Constraint::Equal(
+                ty.clone(),
+                inferred_ty,
+                value.span(),
+            ));
+        let constraints = self.infer_ctx.take_constraints();
+        self.infer_ctx.take_let_bindings();
+        self.solve(constraints)?;
         Ok(())
     }
 
@@ -738,7 +2067,9 @@ impl TypeChecker {
 
         // Create a CheckContext for the function body with ADT registry
         let mut fn_ctx =
-            CheckContext::from_env_with_registry(self.env.clone(), self.adt_registry.clone());
+            CheckContext::from_env_with_registry((*self.env).clone(), (*self.adt_registry).clone())
+                .with_consts(self.consts.clone())
+                .with_resolution(self.resolution.clone());
         fn_ctx.expected_return = Some(ret_ty.clone());
         fn_ctx.body_effects = Some(body_eff);
 
@@ -751,6 +2082,11 @@ impl TypeChecker {
             );
         }
 
+        let nested = self.predeclare_nested_fns(nested_fns_in_block(&decl.body))?;
+        // Calls inferred outside any function (in a `let`'s value) have no
+        // function to trace an effect to
+        self.infer_ctx.take_calls();
+
         // Infer body type using full block inference
         let body_ty = self
             .infer_ctx
@@ -761,9 +2097,9 @@ impl TypeChecker {
         // A diverging body (Never) satisfies any return type.
         if body_ty != Ty::Never {
             self.infer_ctx
-                .add_constraint(super::infer::ty::Constraint::Equal(
-                    body_ty,
+                .add_constraint(super::infer::ty::Constraint::Subsume(
                     ret_ty.clone(),
+                    body_ty,
                     decl.span,
                 ));
         }
@@ -779,59 +2115,33 @@ impl TypeChecker {
 
         // Solve constraints
         let constraints = self.infer_ctx.take_constraints();
-        let mut solver = Solver::new();
-        let subst = solver
-            .solve(constraints)
-            .map_err(solve_error_to_type_error)?;
+        let calls = self.infer_ctx.take_calls();
+        let subst = self
+            .solve_traced(constraints)
+            .map_err(|(err, origin)| self.undeclared_effect(decl, err, origin, &calls))?;
+        self.record_effect_calls(decl, calls, &subst)?;
+        self.check_enum_args(&subst)?;
+        self.check_show_args(&subst)?;
+        self.check_iter_args(&subst)?;
+        self.check_partial_args(&subst)?;
+        self.check_comparisons(&subst)?;
 
         // Apply substitution to get the final function type
         let final_fn_ty = subst
             .apply(&predeclared_ty)
             .map_err(|e| subst_error_to_type_error(e, decl.span))?;
 
-        // ---- Settle point: reject &T in resolved return type ----
-        // After solving, check that the return type doesn't contain Ty::Ref.
-        // This catches cases where &T propagates through inference into a return.
-        if let Ty::Arrow(_, ref resolved_ret, _) = final_fn_ty {
-            if !resolved_ret.is_first_class() {
-                return Err(TypeError::RefEscape {
-                    ty: resolved_ret.as_ref().clone(),
-                    context: format!("return type of '{}'", decl.name.text),
-                    span: decl.ret_ty.as_ref().map(|t| t.span()).unwrap_or(decl.span),
-                });
-            }
+        self.check_solved_signature(decl, &final_fn_ty, &subst)?;
+        if decl.name.text == "main" {
+            check_main_params(decl, &final_fn_ty)?;
         }
 
-        // ---- Capability validation ----
-        // After solving, check that the function has capability parameters for each
-        // concrete effect in its resolved effect row. Every function with concrete
-        // effects must have matching capabilities — no exceptions.
-        if let Ty::Arrow(ref resolved_params, _, ref resolved_eff) = final_fn_ty {
-            let param_caps: Vec<CapKind> = resolved_params
-                .iter()
-                .filter_map(|ty| match ty {
-                    Ty::Cap(kind) => Some(*kind),
-                    Ty::Ref(inner) => match inner.as_ref() {
-                        Ty::Cap(kind) => Some(*kind),
-                        _ => None,
-                    },
-                    _ => None,
-                })
-                .collect();
+        let local_fns = self.resolve_nested_fns(&nested, &subst)?;
 
-            // Resolve the effect row through the substitution
-            let resolved_eff = subst
-                .apply_effect_row(resolved_eff)
-                .map_err(|e| subst_error_to_type_error(e, decl.span))?;
-
-            validate_caps_against_effects(
-                &decl.name.text,
-                &param_caps,
-                &resolved_eff,
-                decl.name.span,
-                false,
-            )?;
-        }
+        // ---- Definite assignment of `let mut x: T;` ----
+        let params: Vec<String> = decl.params.iter().map(|p| p.name.text.clone()).collect();
+        crate::init_check::check_function_body(&params, &decl.body, &self.diverging_fns())
+            .map_err(init_error_to_type_error)?;
 
         // ---- Move checker (affine/single-use enforcement) ----
         // After type checking succeeds, validate that affine bindings (capabilities)
@@ -849,19 +2159,61 @@ impl TypeChecker {
                 })
                 .collect::<Result<Vec<_>, TypeError>>()?;
 
-            crate::move_check::check_function_body(
+            let unused = crate::move_check::check_function_body(
                 &param_info,
                 &decl.body,
                 &self.env,
                 &self.adt_registry,
+                &local_fns,
             )
             .map_err(move_error_to_type_error)?;
+
+            // ---- Unused-capability and unjoined-task lints ----
+            let found = unused
+                .into_iter()
+                .map(|(name, span, task)| match task {
+                    true => Warning::UnjoinedTask { name, span },
+                    false => Warning::UnusedCapability { name, span },
+                })
+                .collect();
+            self.emit_warnings(found)?;
+        }
+
+        // Nested functions' signatures are checked after the move checker,
+        // so a captured capability is reported as such rather than as a
+        // missing parameter
+        self.check_nested_fns(&nested, &local_fns, &subst)?;
+
+        // ---- Shadowed-binding lint ----
+        if self.lint_level(Lint::ShadowedBinding, decl.span) != LintLevel::Allow {
+            let params: Vec<(String, Span)> = decl
+                .params
+                .iter()
+                .map(|p| (p.name.text.clone(), p.name.span))
+                .collect();
+            let found = crate::shadow_check::check_function_body(&params, &decl.body);
+            self.emit_warnings(found)?;
+        }
+
+        // Record the solved signature with its resolved effect row
+        if let Ty::Arrow(ref params, ref ret, ref eff) = final_fn_ty {
+            let resolved_eff = subst
+                .apply_effect_row(eff)
+                .map_err(|e| subst_error_to_type_error(e, decl.span))?;
+            let locals = self.resolve_let_bindings(&subst)?;
+            self.typed_items.push(TypedItem {
+                name: decl.name.text.clone(),
+                kind: TypedItemKind::Fn,
+                span: decl.span,
+                ty: Ty::Arrow(params.clone(), ret.clone(), resolved_eff),
+                locals,
+            });
         }
 
         // NOW generalize: compute env vars excluding this function's own type vars
         // (since this function is still monomorphic in env, its vars are included in env_vars,
         // but we want to generalize those vars if they're not constrained by the environment)
-        let mut env_for_generalize = self.env.clone();
+        let mut env_for_generalize = (*self.env).clone();
         env_for_generalize.remove(&decl.name.text);
         let env_vars = free_vars_env(&env_for_generalize);
         let env_eff_vars = free_effect_vars_env(&env_for_generalize);
@@ -870,15 +2222,290 @@ impl TypeChecker {
             .generalize(final_fn_ty, &env_vars, &env_eff_vars);
 
         // Update environment with the generalized scheme
-        self.env.insert(decl.name.text.clone(), gen_scheme);
+        Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), gen_scheme);
 
         Ok(())
     }
 
+    /// `err` from solving `decl`'s constraints, as an [`UndeclaredEffect`]
+    /// naming the calls the first missing effect comes through if it is
+    /// the body's effects not fitting the annotation. `origin` is the
+    /// call the effect came in through, and `calls` every call `decl` makes.
+    ///
+    /// [`UndeclaredEffect`]: TypeError::UndeclaredEffect
+    fn undeclared_effect(
+        &self,
+        decl: &FnDecl,
+        err: TypeError,
+        origin: Option<Span>,
+        calls: &[(String, Span, EffectRow)],
+    ) -> TypeError {
+        let (declared, actual) = match err {
+            TypeError::EffectMismatch {
+                expected,
+                found,
+                span,
+            } if span == decl.span && decl.effects.is_some() => (expected, found),
+            err => return err,
+        };
+        let missing = actual.concrete & !declared.concrete;
+        let Some(effect) = super::effects::ALL_EFFECTS
+            .iter()
+            .copied()
+            .find(|e| missing & e.bit() != 0)
+        else {
+            return TypeError::EffectMismatch {
+                expected: declared,
+                found: actual,
+                span: decl.span,
+            };
+        };
+        let row_of = |name: &str| match self.env.get(name).map(|s| &s.ty) {
+            Some(Ty::Arrow(_, _, row)) => Some(*row),
+            _ => None,
+        };
+        let mut chain = Vec::new();
+        if let Some((callee, span, _)) = calls.iter().find(|(_, span, _)| Some(*span) == origin) {
+            chain.push((callee.clone(), *span));
+            let rest = provenance::chain(&self.effect_calls, callee, effect.bit(), row_of);
+            chain.extend(rest.into_iter().map(|link| (link.name, link.span)));
+        }
+        TypeError::UndeclaredEffect {
+            effect: format!("{:?}", effect),
+            fn_name: decl.name.text.clone(),
+            declared,
+            actual,
+            chain,
+            span: decl.span,
+        }
+    }
+
+    /// Keep the calls `decl` makes, with the effects `subst` solves them
+    /// to, for later effect errors to trace through
+    fn record_effect_calls(
+        &mut self,
+        decl: &FnDecl,
+        calls: Vec<(String, Span, EffectRow)>,
+        subst: &super::infer::Subst,
+    ) -> Result<(), TypeError> {
+        let calls = calls
+            .into_iter()
+            .map(|(callee, span, row)| {
+                let row = subst
+                    .apply_effect_row(&row)
+                    .map_err(|e| subst_error_to_type_error(e, span))?;
+                Ok(EffectCall {
+                    callee,
+                    span,
+                    effects: row.concrete,
+                })
+            })
+            .collect::<Result<Vec<_>, TypeError>>()?;
+        Arc::make_mut(&mut self.effect_calls).insert(decl.name.text.clone(), calls);
+        Ok(())
+    }
+
+    /// Check that the `pure fn` `decl` performs no effects, then close its
+    /// effect row, so callers and the typed items see it as `& {}`
+    fn check_pure_fn(&mut self, decl: &FnDecl) -> Result<(), TypeError> {
+        let Some(scheme) = self.env.get(&decl.name.text) else {
+            return Ok(());
+        };
+        let Ty::Arrow(params, ret, eff) = &scheme.ty else {
+            return Ok(());
+        };
+        let row_of = |name: &str| match self.env.get(name).map(|s| &s.ty) {
+            Some(Ty::Arrow(_, _, row)) => Some(*row),
+            _ => None,
+        };
+        // A call to a function checked after this one may not have its
+        // effects in this row, so the chain is looked for either way
+        let chain = provenance::chain(&self.effect_calls, &decl.name.text, !0, row_of);
+        let concrete = chain
+            .first()
+            .map_or(eff.concrete, |link| eff.concrete | link.effects);
+        if concrete != 0 {
+            return Err(TypeError::ImpureFunction {
+                fn_name: decl.name.text.clone(),
+                effects: EffectRow::closed(concrete),
+                span: chain.first().map_or(decl.name.span, |link| link.span),
+                chain: chain.into_iter().map(|link| link.name).collect(),
+            });
+        }
+        if let Some(tail) = eff.tail {
+            let in_signature = params
+                .iter()
+                .chain([ret.as_ref()])
+                .any(|ty| free_effect_vars(ty).contains(&tail));
+            if in_signature {
+                return Err(TypeError::ImpureFunction {
+                    fn_name: decl.name.text.clone(),
+                    effects: *eff,
+                    chain: Vec::new(),
+                    span: decl.name.span,
+                });
+            }
+        }
+
+        let pure_ty = Ty::Arrow(params.clone(), ret.clone(), EffectRow::pure());
+        let mut scheme = scheme.clone();
+        scheme.effect_vars.retain(|v| Some(*v) != eff.tail);
+        scheme.ty = pure_ty.clone();
+        Arc::make_mut(&mut self.env).insert(decl.name.text.clone(), scheme);
+        for item in &mut self.typed_items {
+            if item.kind == TypedItemKind::Fn && item.name == decl.name.text {
+                item.ty = pure_ty.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks on a function's signature once its body's constraints are
+    /// solved: no `&T` in the return type, and a capability parameter for
+    /// every concrete effect.
+    fn check_solved_signature(
+        &self,
+        decl: &strata_ast::ast::FnDecl,
+        final_fn_ty: &Ty,
+        subst: &super::infer::Subst,
+    ) -> Result<(), TypeError> {
+        // ---- Settle point: reject &T in resolved return type ----
+        // After solving, check that the return type doesn't contain Ty::Ref.
+        // This catches cases where &T propagates through inference into a return.
+        if let Ty::Arrow(_, resolved_ret, _) = final_fn_ty {
+            if !resolved_ret.is_first_class() {
+                return Err(TypeError::RefEscape {
+                    ty: resolved_ret.as_ref().clone(),
+                    context: format!("return type of '{}'", decl.name.text),
+                    span: decl.ret_ty.as_ref().map(|t| t.span()).unwrap_or(decl.span),
+                });
+            }
+        }
+
+        // ---- Capability validation ----
+        // After solving, check that the function has capability parameters for each
+        // concrete effect in its resolved effect row. Every function with concrete
+        // effects must have matching capabilities — no exceptions.
+        if let Ty::Arrow(resolved_params, _, resolved_eff) = final_fn_ty {
+            let param_caps: Vec<CapKind> = resolved_params
+                .iter()
+                .flat_map(|ty| self.adt_registry.held_caps(ty))
+                .collect();
+
+            // Resolve the effect row through the substitution
+            let resolved_eff = subst
+                .apply_effect_row(resolved_eff)
+                .map_err(|e| subst_error_to_type_error(e, decl.span))?;
+
+            validate_caps_against_effects(
+                &decl.name.text,
+                &param_caps,
+                &resolved_eff,
+                decl.name.span,
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Predeclare the nested functions in a body about to be inferred,
+    /// with fresh type variables for unannotated parts. Like Pass 1c for
+    /// top-level fns, the signatures are monomorphic, so every call within
+    /// a recursive group sees the same type variables. Returns each with
+    /// its signature, to be checked once the body is solved.
+    fn predeclare_nested_fns<'a>(
+        &mut self,
+        decls: Vec<&'a strata_ast::ast::FnDecl>,
+    ) -> Result<Vec<(&'a strata_ast::ast::FnDecl, Ty)>, TypeError> {
+        decls
+            .into_iter()
+            .map(|decl| {
+                let ty = self.extract_fn_signature(decl)?;
+                self.infer_ctx.set_local_fn_sig(decl.span, ty.clone());
+                Ok((decl, ty))
+            })
+            .collect()
+    }
+
+    /// Apply the solved substitution to nested functions' signatures,
+    /// keyed by declaration span for the move checker
+    fn resolve_nested_fns(
+        &self,
+        nested: &[(&strata_ast::ast::FnDecl, Ty)],
+        subst: &super::infer::Subst,
+    ) -> Result<HashMap<Span, Ty>, TypeError> {
+        nested
+            .iter()
+            .map(|(decl, ty)| {
+                let resolved = subst
+                    .apply(ty)
+                    .map_err(|e| subst_error_to_type_error(e, decl.span))?;
+                Ok((decl.span, resolved))
+            })
+            .collect()
+    }
+
+    /// Check the solved signatures of nested functions
+    fn check_nested_fns(
+        &self,
+        nested: &[(&strata_ast::ast::FnDecl, Ty)],
+        resolved: &HashMap<Span, Ty>,
+        subst: &super::infer::Subst,
+    ) -> Result<(), TypeError> {
+        for (decl, _) in nested {
+            self.check_solved_signature(decl, &resolved[&decl.span], subst)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve the block-level `let` bindings recorded since the last call
+    fn resolve_let_bindings(
+        &mut self,
+        subst: &super::infer::Subst,
+    ) -> Result<Vec<TypedLocal>, TypeError> {
+        self.infer_ctx
+            .take_let_bindings()
+            .into_iter()
+            .map(|b| {
+                let ty = subst
+                    .apply(&b.ty)
+                    .map_err(|e| subst_error_to_type_error(e, b.span))?;
+                Ok(TypedLocal {
+                    name: b.name,
+                    span: b.span,
+                    ty,
+                })
+            })
+            .collect()
+    }
+
     /// Extract a function's type signature without checking its body
     ///
     /// This is used in pass 1 to predeclare functions.
     fn extract_fn_signature(&mut self, decl: &strata_ast::ast::FnDecl) -> Result<Ty, TypeError> {
+        if let Some(param) = decl.params.iter().find(|p| p.variadic) {
+            return Err(TypeError::InvalidVariadic {
+                msg: format!(
+                    "Parameter '{}' of fn '{}' is variadic; only extern fn parameters may be",
+                    param.name.text, decl.name.text
+                ),
+                span: param.span,
+            });
+        }
+        if let Some((param, value)) = decl
+            .params
+            .iter()
+            .find_map(|p| p.default.as_ref().map(|value| (p, value)))
+        {
+            return Err(TypeError::InvalidDefault {
+                msg: format!(
+                    "Parameter '{}' of fn '{}' has a default value; only extern fn \
+                     parameters may",
+                    param.name.text, decl.name.text
+                ),
+                span: value.span(),
+            });
+        }
         // Extract parameter types
         let mut param_tys = Vec::new();
         for param in &decl.params {
@@ -892,11 +2519,16 @@ impl TypeChecker {
             param_tys.push(param_ty);
         }
 
-        // Reject &T in regular fn params
+        // &T is allowed as a whole parameter type (read-only borrow), but not
+        // nested inside another type where it could be stored
         for (param, param_ty) in decl.params.iter().zip(param_tys.iter()) {
-            if matches!(param_ty, Ty::Ref(_)) {
+            let inner = match param_ty {
+                Ty::Ref(inner) => inner.as_ref(),
+                other => other,
+            };
+            if !inner.is_first_class() {
                 return Err(TypeError::NotImplemented {
-                    msg: "Reference types (&T) are only allowed in extern function parameters"
+                    msg: "Reference types (&T) are only allowed as a whole parameter type"
                         .to_string(),
                     span: param.span,
                 });
@@ -937,7 +2569,8 @@ impl TypeChecker {
         decl: &strata_ast::ast::ExternFnDecl,
     ) -> Result<Ty, TypeError> {
         let mut param_tys = Vec::new();
-        for param in &decl.params {
+        // A variadic parameter's arguments are checked at each call
+        for param in decl.params.iter().filter(|p| !p.variadic) {
             let param_ty = if let Some(ref ty_expr) = param.ty {
                 self.ty_from_type_expr(ty_expr)?
             } else {
@@ -985,19 +2618,66 @@ impl TypeChecker {
         Ok(Ty::arrow_eff(param_tys, ret_ty, eff))
     }
 
+    /// What the arguments for an extern fn's variadic parameter must be, if
+    /// it has one. It comes last, and the fn's parameters have no defaults,
+    /// so which parameter an argument is for is never in doubt. The bound
+    /// is `Show` or a type holding no capability.
+    fn variadic_bound(
+        &mut self,
+        decl: &strata_ast::ast::ExternFnDecl,
+    ) -> Result<Option<VariadicBound>, TypeError> {
+        let Some(pos) = decl.params.iter().position(|p| p.variadic) else {
+            return Ok(None);
+        };
+        let param = &decl.params[pos];
+        let invalid = |msg: String, span| Err(TypeError::InvalidVariadic { msg, span });
+        if pos + 1 != decl.params.len() {
+            return invalid(
+                format!(
+                    "Variadic parameter '{}' of extern fn '{}' must be its last",
+                    param.name.text, decl.name.text
+                ),
+                param.span,
+            );
+        }
+        if let Some(defaulted) = decl.params.iter().find(|p| p.default.is_some()) {
+            return invalid(
+                format!(
+                    "Extern fn '{}' has both a variadic parameter and a default for '{}'",
+                    decl.name.text, defaulted.name.text
+                ),
+                defaulted.span,
+            );
+        }
+        let Some(ty_expr) = &param.ty else {
+            return Ok(None);
+        };
+        if matches!(ty_expr, TypeExpr::Path(path, _) if path.len() == 1 && path[0].text == "Show") {
+            return Ok(Some(VariadicBound::Show));
+        }
+        let ty = self.ty_from_type_expr(ty_expr)?;
+        if contains_ref(&ty)
+            || contains_capability(&ty)
+            || self.adt_registry.find_affine_name(&ty).is_some()
+        {
+            return invalid(
+                format!(
+                    "Variadic parameter '{}' of extern fn '{}' takes {}, which holds a \
+                     capability or is single-use",
+                    param.name.text, decl.name.text, ty
+                ),
+                ty_expr.span(),
+            );
+        }
+        Ok(Some(VariadicBound::Ty(ty)))
+    }
+
     /// Resolve an effect name to an Effect enum variant.
     fn resolve_effect_name(name: &str, span: Span) -> Result<Effect, TypeError> {
-        match name {
-            "Fs" => Ok(Effect::Fs),
-            "Net" => Ok(Effect::Net),
-            "Time" => Ok(Effect::Time),
-            "Rand" => Ok(Effect::Rand),
-            "Ai" => Ok(Effect::Ai),
-            _ => Err(TypeError::UnknownEffect {
-                name: name.to_string(),
-                span,
-            }),
-        }
+        Effect::from_name(name).ok_or_else(|| TypeError::UnknownEffect {
+            name: name.to_string(),
+            span,
+        })
     }
 
     /// Convert a list of AST effect identifiers to a closed EffectRow.
@@ -1017,7 +2697,7 @@ impl TypeChecker {
     /// Validates:
     /// - No duplicate type definitions
     /// - All field types are valid
-    /// - No capabilities stored in fields (until linear types)
+    /// - No capabilities stored in fields unless declared `linear`
     fn register_struct(&mut self, def: &StructDef) -> Result<(), TypeError> {
         // Check for reserved capability type names
         if CapKind::from_name(&def.name.text).is_some() {
@@ -1059,7 +2739,7 @@ impl TypeChecker {
             }
 
             // Check for capability types in fields
-            if contains_capability(&ty) {
+            if !def.linear && contains_capability(&ty) {
                 // Find which capability type for better error message
                 let cap_name = find_capability_name(&ty).unwrap_or("capability".to_string());
                 return Err(TypeError::CapabilityInAdt {
@@ -1072,13 +2752,16 @@ impl TypeChecker {
             fields.push(FieldDef {
                 name: field.name.text.clone(),
                 ty,
+                default: None,
             });
         }
 
         // Create and register the ADT definition
         let type_params = def.type_params.iter().map(|p| p.text.clone()).collect();
-        let adt_def = AdtDef::new_struct(&def.name.text, type_params, fields);
-        self.adt_registry
+        let adt_def = AdtDef::new_struct(&def.name.text, type_params, fields)
+            .with_linear(def.linear)
+            .with_tuple(def.tuple);
+        Arc::make_mut(&mut self.adt_registry)
             .register(adt_def)
             .map_err(|msg| TypeError::DuplicateType {
                 name: msg,
@@ -1091,7 +2774,7 @@ impl TypeChecker {
     /// Validates:
     /// - No duplicate type definitions
     /// - All variant types are valid
-    /// - No capabilities stored in variant payloads (until linear types)
+    /// - No capabilities stored in variant payloads unless declared `linear`
     fn register_enum(&mut self, def: &EnumDef) -> Result<(), TypeError> {
         use strata_ast::ast::VariantFields as AstVariantFields;
 
@@ -1140,7 +2823,7 @@ impl TypeChecker {
                         }
 
                         // Check for capability types in variant payload
-                        if contains_capability(&ty) {
+                        if !def.linear && contains_capability(&ty) {
                             let cap_name =
                                 find_capability_name(&ty).unwrap_or("capability".to_string());
                             return Err(TypeError::CapabilityInAdt {
@@ -1157,10 +2840,46 @@ impl TypeChecker {
             variants.push(variant_def);
         }
 
+        // Number the variants; explicit discriminants need a unit-only enum
+        let discriminants = enum_discriminants(def).map_err(|e| match e {
+            DiscriminantError::OnDataEnum { variant, span } => TypeError::InvalidDiscriminant {
+                msg: format!(
+                    "'{}::{}' has an explicit discriminant, but '{}' has variants with fields",
+                    def.name.text, variant, def.name.text
+                ),
+                span,
+            },
+            DiscriminantError::Overflow { variant, span } => TypeError::InvalidDiscriminant {
+                msg: format!(
+                    "the implicit discriminant of '{}::{}' overflows Int",
+                    def.name.text, variant
+                ),
+                span,
+            },
+            DiscriminantError::Duplicate {
+                value,
+                variant,
+                previous,
+                span,
+            } => TypeError::DuplicateDiscriminant {
+                enum_name: def.name.text.clone(),
+                value,
+                variant,
+                previous,
+                span,
+            },
+        })?;
+        let variants = variants
+            .into_iter()
+            .zip(discriminants)
+            .map(|(v, d)| v.with_discriminant(d))
+            .collect();
+
         // Create and register the ADT definition
         let type_params = def.type_params.iter().map(|p| p.text.clone()).collect();
-        let adt_def = AdtDef::new_enum(&def.name.text, type_params, variants);
-        self.adt_registry
+        let adt_def =
+            AdtDef::new_enum(&def.name.text, type_params, variants).with_linear(def.linear);
+        Arc::make_mut(&mut self.adt_registry)
             .register(adt_def)
             .map_err(|msg| TypeError::DuplicateType {
                 name: msg,
@@ -1168,6 +2887,30 @@ impl TypeChecker {
             })
     }
 
+    /// Reject fields of a non-linear ADT whose type is a `linear` ADT.
+    fn check_no_linear_fields(&self, name: &str, span: Span) -> Result<(), TypeError> {
+        let Some(def) = self.adt_registry.get(name) else {
+            return Ok(());
+        };
+        let field_names: Vec<String> = match &def.kind {
+            AdtKind::Struct(fields) => fields.iter().map(|f| f.name.clone()).collect(),
+            AdtKind::Enum(variants) => variants
+                .iter()
+                .flat_map(|v| (0..v.arity()).map(move |i| format!("{}::{}.{}", name, v.name, i)))
+                .collect(),
+        };
+        for (field, ty) in field_names.into_iter().zip(def.field_types()) {
+            if let Some(linear_name) = self.adt_registry.find_affine_name(ty) {
+                return Err(TypeError::CapabilityInAdt {
+                    field,
+                    cap_type: linear_name,
+                    span,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Register enum variant constructors as polymorphic functions in the environment.
     ///
     /// For `enum Option<T> { Some(T), None }`:
@@ -1241,11 +2984,355 @@ impl TypeChecker {
 
             // Register with qualified name: EnumName::VariantName
             let qualified_name = format!("{}::{}", def.name.text, variant.name);
-            self.env.insert(qualified_name, scheme);
+            Arc::make_mut(&mut self.env).insert(qualified_name, scheme);
+        }
+
+        // Unit-only enums can be built from their discriminant:
+        // `E::from_discriminant : ∀T. (Int) -> Option<E<T>>`
+        if adt_def.is_unit_only()
+            && self.has_prelude_shaped_option()
+            && !variants.iter().any(|v| v.name == "from_discriminant")
+        {
+            Arc::make_mut(&mut self.env).insert(
+                format!("{}::from_discriminant", def.name.text),
+                Scheme {
+                    type_vars,
+                    effect_vars: vec![],
+                    ty: Ty::arrow(vec![Ty::int()], Ty::adt("Option", vec![result_ty])),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Register a tuple struct's constructor as a polymorphic function.
+    ///
+    /// For `struct Pair<T>(T, T);`: `Pair : ∀T. (T, T) -> Pair<T>`
+    fn register_struct_constructor(&mut self, def: &StructDef) -> Result<(), TypeError> {
+        let adt_def = self
+            .adt_registry
+            .get(&def.name.text)
+            .cloned()
+            .ok_or_else(|| TypeError::InvariantViolation {
+                msg: format!("struct '{}' not registered", def.name.text),
+                span: def.span,
+            })?;
+        let fields = adt_def
+            .fields()
+            .ok_or_else(|| TypeError::InvariantViolation {
+                msg: format!("'{}' is not a struct", def.name.text),
+                span: def.span,
+            })?;
+
+        // Fresh scheme variables, as for enum constructors
+        let type_vars: Vec<TypeVarId> = (0..adt_def.arity())
+            .map(|_| self.infer_ctx.fresh_var_id())
+            .collect();
+        let var_remap: HashMap<TypeVarId, Ty> = (0..adt_def.arity())
+            .map(|i| (TypeVarId(i as u32), Ty::Var(type_vars[i])))
+            .collect();
+        let params = fields
+            .iter()
+            .map(|f| remap_type_vars(&f.ty, &var_remap))
+            .collect();
+        let result_ty = Ty::adt(
+            &def.name.text,
+            type_vars.iter().map(|v| Ty::Var(*v)).collect(),
+        );
+
+        Arc::make_mut(&mut self.env).insert(
+            def.name.text.clone(),
+            Scheme {
+                type_vars,
+                effect_vars: vec![],
+                ty: Ty::arrow(params, result_ty),
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether `Option` is `enum Option<T> { Some(T), None }`, either the
+    /// prelude's or a user definition of the same shape.
+    fn has_prelude_shaped_option(&self) -> bool {
+        let Some(def) = self.adt_registry.get("Option") else {
+            return false;
+        };
+        let Some(variants) = def.variants() else {
+            return false;
+        };
+        def.arity() == 1 && variants.len() == 2 && variants.iter().any(|v| {
+            v.name == "Some"
+                && matches!(&v.fields, VariantFields::Tuple(tys) if tys == &[Ty::Var(TypeVarId(0))])
+        })
+            && variants
+                .iter()
+                .any(|v| v.name == "None" && matches!(v.fields, VariantFields::Unit))
+    }
+
+    /// Register the prelude `enum Option<T> { Some(T), None }` unless the
+    /// module defines its own `Option`.
+    fn register_prelude_option(&mut self) -> Result<(), TypeError> {
+        if self.adt_registry.contains("Option") {
+            return Ok(());
+        }
+        let def = prelude_option_def();
+        self.register_enum(&def)?;
+        self.register_enum_constructors(&def)
+    }
+
+    /// Solve `constraints`, numbering the row variables the solver makes
+    /// after every variable inference has handed out
+    fn solve(
+        &mut self,
+        constraints: Vec<super::infer::ty::Constraint>,
+    ) -> Result<super::infer::Subst, TypeError> {
+        self.solve_traced(constraints).map_err(|(err, _)| err)
+    }
+
+    /// [`Self::solve`], keeping with an error the span of the constraint
+    /// a missing effect came in through, if any
+    fn solve_traced(
+        &mut self,
+        constraints: Vec<super::infer::ty::Constraint>,
+    ) -> Result<super::infer::Subst, (TypeError, Option<Span>)> {
+        let limits = self.infer_ctx.limits();
+        let count = constraints.len();
+        if let Some(i) = self.solve_item {
+            self.solve_stats[i].1.constraints += count;
+        }
+        if count > limits.constraints {
+            let err = TypeError::SolveLimitExceeded {
+                what: "constraints",
+                limit: limits.constraints,
+                span: constraints[0].span(),
+            };
+            return Err((err, None));
+        }
+        let mut solver =
+            Solver::with_next_var(self.infer_ctx.next_var()).with_step_limit(limits.solve_steps);
+        let result = solver.solve(constraints);
+        self.infer_ctx.set_next_var(solver.next_var());
+        if let Some(i) = self.solve_item {
+            self.solve_stats[i].1.steps += solver.steps();
+        }
+        let err = match result {
+            Ok(subst) => return Ok(subst),
+            Err(err) => err,
+        };
+        let origin = err.origin;
+        let err = match solve_error_to_type_error(err) {
+            TypeError::Mismatch {
+                expected,
+                found,
+                span,
+            } => match self.infer_ctx.named_arg(span) {
+                Some((callee, param)) => TypeError::ArgMismatch {
+                    callee: callee.clone(),
+                    param: param.clone(),
+                    expected: Box::new(expected),
+                    found: Box::new(found),
+                    span,
+                },
+                None => TypeError::Mismatch {
+                    expected,
+                    found,
+                    span,
+                },
+            },
+            err => err,
+        };
+        Err((err, origin))
+    }
+
+    /// Check that every `discriminant(x)` argument recorded during
+    /// inference resolved to an enum.
+    fn check_enum_args(&mut self, subst: &super::infer::Subst) -> Result<(), TypeError> {
+        for (ty, span) in self.infer_ctx.take_enum_args() {
+            let ty = subst
+                .apply(&ty)
+                .map_err(|e| subst_error_to_type_error(e, span))?;
+            let is_enum = match &ty {
+                Ty::Adt { name, .. } => self
+                    .adt_registry
+                    .get(name)
+                    .is_some_and(|def| def.variants().is_some()),
+                _ => false,
+            };
+            if !is_enum {
+                return Err(TypeError::NotAnEnum { ty, span });
+            }
         }
+        Ok(())
+    }
+
+    /// Check that no `show(x)` argument recorded during inference resolved
+    /// to a type holding a capability, directly or through a `linear` ADT.
+    fn check_show_args(&mut self, subst: &super::infer::Subst) -> Result<(), TypeError> {
+        for (ty, callee, span) in self.infer_ctx.take_show_args() {
+            let ty = subst
+                .apply(&ty)
+                .map_err(|e| subst_error_to_type_error(e, span))?;
+            if contains_capability(&ty) || self.adt_registry.find_affine_name(&ty).is_some() {
+                return Err(TypeError::ShowCapability { callee, ty, span });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that no argument bound by a partial application recorded
+    /// during inference holds a capability, directly or through a `linear`
+    /// ADT.
+    fn check_partial_args(&mut self, subst: &super::infer::Subst) -> Result<(), TypeError> {
+        for (ty, callee, span) in self.infer_ctx.take_partial_args() {
+            let ty = subst
+                .apply(&ty)
+                .map_err(|e| subst_error_to_type_error(e, span))?;
+            if contains_capability(&ty) || self.adt_registry.find_affine_name(&ty).is_some() {
+                return Err(TypeError::PartialCapability { callee, ty, span });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that no `iter(first, step)` call recorded during inference
+    /// steps through single-use items.
+    fn check_iter_args(&mut self, subst: &super::infer::Subst) -> Result<(), TypeError> {
+        for (ty, span) in self.infer_ctx.take_iter_args() {
+            let ty = subst
+                .apply(&ty)
+                .map_err(|e| subst_error_to_type_error(e, span))?;
+            if contains_capability(&ty) || self.adt_registry.find_affine_name(&ty).is_some() {
+                return Err(TypeError::IterSingleUse { ty, span });
+            }
+        }
+        Ok(())
+    }
 
+    /// Check that the operands of every comparison recorded during
+    /// inference resolved to a type with equality, or with an ordering for
+    /// `<` and friends. A still-unsolved type variable is let through.
+    fn check_comparisons(&mut self, subst: &super::infer::Subst) -> Result<(), TypeError> {
+        for (ty, ordered, span) in self.infer_ctx.take_comparisons() {
+            let ty = subst
+                .apply(&ty)
+                .map_err(|e| subst_error_to_type_error(e, span))?;
+            let ok = match ordered {
+                true => is_orderable(&ty),
+                false => self.is_equatable(&ty, &mut Vec::new()),
+            };
+            if !ok {
+                return Err(TypeError::NotComparable { ty, ordered, span });
+            }
+        }
         Ok(())
     }
+
+    /// Whether `==` is defined on `ty`: structurally, on anything built
+    /// from primitives, tuples, records, and non-linear ADTs. `visiting` holds the
+    /// ADTs being checked, so a recursive type is assumed equatable.
+    fn is_equatable(&self, ty: &Ty, visiting: &mut Vec<String>) -> bool {
+        match ty {
+            Ty::Var(_) | Ty::Const(_) | Ty::Never => true,
+            Ty::Arrow(..) | Ty::Cap(_) => false,
+            Ty::Tuple(tys) => tys.iter().all(|t| self.is_equatable(t, visiting)),
+            Ty::Record(fields, _) => fields.values().all(|t| self.is_equatable(t, visiting)),
+            Ty::List(inner) | Ty::Ref(inner) => self.is_equatable(inner, visiting),
+            Ty::Adt { name, args } => {
+                if visiting.contains(name) {
+                    return true;
+                }
+                let Some(def) = self.adt_registry.get(name) else {
+                    return false;
+                };
+                if def.linear || !args.iter().all(|a| self.is_equatable(a, visiting)) {
+                    return false;
+                }
+                // Field types mention type parameters as variables, which
+                // the arguments above stand in for
+                visiting.push(name.clone());
+                let fields_ok = def
+                    .field_types()
+                    .into_iter()
+                    .all(|t| self.is_equatable(t, visiting));
+                visiting.pop();
+                fields_ok
+            }
+        }
+    }
+}
+
+/// Whether `<` and friends are defined on `ty`: numbers, strings, and
+/// tuples of those, compared element by element.
+fn is_orderable(ty: &Ty) -> bool {
+    match ty {
+        Ty::Var(_) | Ty::Never => true,
+        Ty::Const(c) => matches!(c, TyConst::Int | TyConst::Float | TyConst::String),
+        Ty::Tuple(tys) => tys.iter().all(is_orderable),
+        _ => false,
+    }
+}
+
+/// Reject a top-level function, extern function, or const whose name is
+/// defined again at top level. Two `let`s may still share a name; the
+/// later one shadows the earlier.
+fn check_duplicate_definitions(module: &Module) -> Result<(), TypeError> {
+    // name -> whether every definition so far was a `let`
+    let mut seen: HashMap<&str, bool> = HashMap::new();
+    for item in &module.items {
+        let (name, is_let) = match item {
+            Item::Fn(decl) => (&decl.name, false),
+            Item::ExternFn(decl) => (&decl.name, false),
+            Item::Const(decl) => (&decl.name, false),
+            Item::Let(decl) => (&decl.name, true),
+            // A tuple struct's constructor is a function of the same name
+            Item::Struct(def) if def.tuple => (&def.name, false),
+            _ => continue,
+        };
+        match seen.insert(&name.text, is_let) {
+            Some(true) if is_let => {}
+            Some(_) => {
+                return Err(TypeError::DuplicateDefinition {
+                    name: name.text.clone(),
+                    span: name.span,
+                })
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// The AST of the prelude `enum Option<T> { Some(T), None }`.
+fn prelude_option_def() -> EnumDef {
+    use strata_ast::ast::{Variant, VariantFields as AstVariantFields};
+    let span = Span::default();
+    let ident = |text: &str| Ident {
+        text: text.to_string(),
+        span,
+    };
+    EnumDef {
+        name: ident("Option"),
+        type_params: vec![ident("T")],
+        variants: vec![
+            Variant {
+                name: ident("Some"),
+                fields: AstVariantFields::Tuple(vec![TypeExpr::Path(vec![ident("T")], span)]),
+                discriminant: None,
+                span,
+            },
+            Variant {
+                name: ident("None"),
+                fields: AstVariantFields::Unit,
+                discriminant: None,
+                span,
+            },
+        ],
+        linear: false,
+        public: true,
+        attrs: Vec::new(),
+        span,
+    }
 }
 
 /// Remap type variables in a type according to a substitution map
@@ -1259,6 +3346,13 @@ fn remap_type_vars(ty: &Ty, remap: &std::collections::HashMap<TypeVarId, Ty>) ->
             *eff,
         ),
         Ty::Tuple(tys) => Ty::Tuple(tys.iter().map(|t| remap_type_vars(t, remap)).collect()),
+        Ty::Record(fields, tail) => Ty::record_with_tail(
+            fields
+                .iter()
+                .map(|(name, t)| (name.clone(), remap_type_vars(t, remap)))
+                .collect(),
+            tail.map(|v| remap_type_vars(&Ty::Var(v), remap)),
+        ),
         Ty::List(t) => Ty::List(Box::new(remap_type_vars(t, remap))),
         Ty::Adt { name, args } => Ty::Adt {
             name: name.clone(),
@@ -1338,14 +3432,24 @@ impl TypeChecker {
                     })
                 }
             }
-            TypeExpr::Arrow { params, ret, .. } => {
+            TypeExpr::Arrow {
+                params,
+                ret,
+                effects,
+                ..
+            } => {
                 let param_tys: Result<Vec<Ty>, TypeError> = params
                     .iter()
                     .map(|p| self.ty_from_type_expr_with_params(p, type_params))
                     .collect();
                 let param_tys = param_tys?;
                 let ret_ty = self.ty_from_type_expr_with_params(ret, type_params)?;
-                Ok(Ty::arrow(param_tys, ret_ty))
+                // An unannotated function type is pure
+                let eff = match effects {
+                    Some(effects) => self.resolve_effect_annotation(effects)?,
+                    None => EffectRow::pure(),
+                };
+                Ok(Ty::arrow_eff(param_tys, ret_ty, eff))
             }
             TypeExpr::App { base, args, span } => {
                 // Generic type application: Option<Int>, Result<T, E>
@@ -1404,6 +3508,19 @@ impl TypeChecker {
                 let inner_ty = self.ty_from_type_expr_with_params(inner, type_params)?;
                 Ok(Ty::Ref(Box::new(inner_ty)))
             }
+            TypeExpr::Record(fields, _span) => {
+                let mut field_tys = BTreeMap::new();
+                for field in fields {
+                    let ty = self.ty_from_type_expr_with_params(&field.ty, type_params)?;
+                    if field_tys.insert(field.name.text.clone(), ty).is_some() {
+                        return Err(TypeError::DuplicateField {
+                            field: field.name.text.clone(),
+                            span: field.name.span,
+                        });
+                    }
+                }
+                Ok(Ty::Record(field_tys, None))
+            }
         }
     }
 
@@ -1428,12 +3545,19 @@ fn infer_error_to_type_error(err: super::infer::constraint::InferError) -> TypeE
         InferError::ImmutableAssignment { name, span } => {
             TypeError::ImmutableAssignment { name, span }
         }
+        InferError::SpawnCapturesMutable { name, span } => {
+            TypeError::SpawnCapturesMutable { name, span }
+        }
+        InferError::NotPartiallyApplicable { callee, span } => {
+            TypeError::NotPartiallyApplicable { callee, span }
+        }
         InferError::NotImplemented { msg, span } => TypeError::NotImplemented { msg, span },
         InferError::DepthLimitExceeded { span } => TypeError::DepthLimitExceeded { span },
         InferError::DuplicateBinding { name, span } => {
             TypeError::DuplicateField { field: name, span }
         }
         InferError::UnknownType { name, span } => TypeError::UnknownType { name, span },
+        InferError::UnknownEffect { name, span } => TypeError::UnknownEffect { name, span },
         InferError::UnknownVariant {
             type_name,
             variant,
@@ -1471,14 +3595,18 @@ fn infer_error_to_type_error(err: super::infer::constraint::InferError) -> TypeE
             span,
         },
         InferError::DuplicateField { field, span } => TypeError::DuplicateField { field, span },
-        InferError::TupleArityLimit { max, found, span } => TypeError::ArityMismatch {
-            expected: max,
-            found,
+        InferError::TupleArityLimit { max, found, span } => {
+            TypeError::TupleTooWide { max, found, span }
+        }
+        InferError::NonExhaustiveMatch {
+            witness,
+            missing,
+            span,
+        } => TypeError::NonExhaustiveMatch {
+            witness,
+            missing,
             span,
         },
-        InferError::NonExhaustiveMatch { witness, span } => {
-            TypeError::NonExhaustiveMatch { witness, span }
-        }
         InferError::UnreachablePattern { arm_index, span } => {
             TypeError::UnreachablePattern { arm_index, span }
         }
@@ -1488,14 +3616,15 @@ fn infer_error_to_type_error(err: super::infer::constraint::InferError) -> TypeE
         InferError::RefutablePattern { pat_desc, span } => {
             TypeError::RefutablePattern { pat_desc, span }
         }
+        InferError::EmptyRangePattern { span } => TypeError::EmptyRangePattern { span },
         InferError::EffectVarLimitExceeded { limit } => TypeError::EffectVarLimitExceeded { limit },
         InferError::EffectCycle { var } => TypeError::EffectCycle {
             var,
-            span: Span { start: 0, end: 0 },
+            span: Span::default(),
         },
         InferError::EffectChainTooDeep { depth } => TypeError::EffectChainTooDeep {
             depth,
-            span: Span { start: 0, end: 0 },
+            span: Span::default(),
         },
         InferError::InstantiationArityMismatch {
             expected_types,
@@ -1504,7 +3633,7 @@ fn infer_error_to_type_error(err: super::infer::constraint::InferError) -> TypeE
         } => TypeError::ArityMismatch {
             expected: expected_types,
             found: got_types,
-            span: Span { start: 0, end: 0 },
+            span: Span::default(),
         },
         InferError::RefEscape { ty, context, span } => TypeError::RefEscape { ty, context, span },
     }
@@ -1545,6 +3674,75 @@ fn unifier_error_to_type_error(err: super::infer::unifier::TypeError, span: Span
         super::infer::unifier::TypeError::EffectChainTooDeep { depth } => {
             TypeError::EffectChainTooDeep { depth, span }
         }
+        super::infer::unifier::TypeError::NoSuchField { field, record } => TypeError::NoSuchField {
+            field,
+            record,
+            span,
+        },
+        super::infer::unifier::TypeError::StepLimitExceeded { limit } => {
+            TypeError::SolveLimitExceeded {
+                what: "solver steps",
+                limit,
+                span,
+            }
+        }
+    }
+}
+
+/// Convert an InitError to a TypeError
+fn init_error_to_type_error(err: crate::init_check::InitError) -> TypeError {
+    TypeError::PossiblyUninitialized {
+        name: err.name,
+        declared_at: err.declared_at,
+        used_at: err.used_at,
+        unassigned_on: err.unassigned_on,
+    }
+}
+
+/// Convert a ResolveError to a TypeError
+/// A default value's evaluation error; `owner` is what the default is for
+/// ("parameter" or "field")
+fn default_error(owner: &str, err: consts::ConstError) -> TypeError {
+    match err {
+        consts::ConstError::NotConstant { name, span } => TypeError::InvalidDefault {
+            msg: format!(
+                "Default value of {} '{}' is not a compile-time constant; \
+                 defaults may use literals, consts, and operators",
+                owner, name
+            ),
+            span,
+        },
+        err => const_error_to_type_error(err),
+    }
+}
+
+fn resolve_error_to_type_error(err: resolve::ResolveError) -> TypeError {
+    use resolve::ResolveError;
+    match err {
+        ResolveError::UnknownType { name, span } => TypeError::UnknownType { name, span },
+        ResolveError::NotAnEnum { name, span } => TypeError::UseNotEnum { name, span },
+        ResolveError::UnknownVariant {
+            enum_name,
+            variant,
+            span,
+        } => TypeError::UnknownVariant {
+            type_name: enum_name,
+            variant,
+            span,
+        },
+        ResolveError::AmbiguousVariant { name, enums, span } => {
+            TypeError::AmbiguousVariant { name, enums, span }
+        }
+    }
+}
+
+/// Convert a ConstError to a TypeError
+fn const_error_to_type_error(err: crate::consts::ConstError) -> TypeError {
+    use crate::consts::ConstError;
+    match err {
+        ConstError::NotConstant { name, span } => TypeError::NotConstant { name, span },
+        ConstError::Cycle { cycle, span } => TypeError::ConstCycle { cycle, span },
+        ConstError::Arithmetic { message, span } => TypeError::ConstEval { message, span },
     }
 }
 
@@ -1561,10 +3759,69 @@ fn move_error_to_type_error(err: crate::move_check::MoveError) -> TypeError {
             used_at,
             previous_use,
         },
+        MoveError::TaskAlreadyJoined {
+            name,
+            used_at,
+            previous_use,
+        } => TypeError::TaskAlreadyJoined {
+            name,
+            used_at,
+            previous_use,
+        },
+        MoveError::MaybeUsed {
+            name,
+            used_at,
+            moved_in_branch,
+        } => TypeError::CapabilityMaybeUsed {
+            name,
+            used_at,
+            moved_in_branch,
+        },
         MoveError::UsedInLoop { name, used_at } => {
             TypeError::CapabilityUsedInLoop { name, used_at }
         }
+        MoveError::Captured {
+            name,
+            fn_name,
+            used_at,
+        } => TypeError::CapabilityCaptured {
+            name,
+            fn_name,
+            used_at,
+        },
+        MoveError::MovedFromBorrow {
+            name,
+            used_at,
+            borrowed_at,
+        } => TypeError::CapabilityBorrowed {
+            name,
+            used_at,
+            borrowed_at,
+        },
+    }
+}
+
+/// Check that every parameter of `main` is a capability, owned or borrowed,
+/// since those are the only arguments the runtime can supply.
+fn check_main_params(decl: &strata_ast::ast::FnDecl, main_ty: &Ty) -> Result<(), TypeError> {
+    let Ty::Arrow(params, _, _) = main_ty else {
+        return Ok(());
+    };
+    for (param, ty) in decl.params.iter().zip(params) {
+        let is_cap = match ty {
+            Ty::Cap(_) => true,
+            Ty::Ref(inner) => matches!(inner.as_ref(), Ty::Cap(_)),
+            _ => false,
+        };
+        if !is_cap {
+            return Err(TypeError::InvalidMainParam {
+                name: param.name.text.clone(),
+                ty: ty.clone(),
+                span: param.span,
+            });
+        }
     }
+    Ok(())
 }
 
 /// Validate that a function's capability parameters cover all concrete effects.
@@ -1604,6 +3861,102 @@ fn validate_caps_against_effects(
     Ok(())
 }
 
+/// Every nested `fn` declared in `block`, including inside other nested
+/// functions, in source order
+pub(crate) fn nested_fns_in_block(block: &Block) -> Vec<&FnDecl> {
+    let mut out = Vec::new();
+    collect_nested_fns_block(block, &mut out);
+    out
+}
+
+/// Every nested `fn` declared in `expr`
+fn nested_fns_in_expr(expr: &Expr) -> Vec<&FnDecl> {
+    let mut out = Vec::new();
+    collect_nested_fns_expr(expr, &mut out);
+    out
+}
+
+fn collect_nested_fns_block<'a>(block: &'a Block, out: &mut Vec<&'a FnDecl>) {
+    for stmt in &block.stmts {
+        match stmt {
+            Stmt::Let { value, .. } | Stmt::Assign { value, .. } => {
+                collect_nested_fns_expr(value, out)
+            }
+            Stmt::Expr { expr, .. } => collect_nested_fns_expr(expr, out),
+            Stmt::Return { value, .. } => {
+                if let Some(v) = value {
+                    collect_nested_fns_expr(v, out);
+                }
+            }
+            Stmt::Declare { .. } => {}
+            Stmt::Fn(decl) => {
+                out.push(decl);
+                collect_nested_fns_block(&decl.body, out);
+            }
+        }
+    }
+    if let Some(tail) = &block.tail {
+        collect_nested_fns_expr(tail, out);
+    }
+}
+
+fn collect_nested_fns_expr<'a>(expr: &'a Expr, out: &mut Vec<&'a FnDecl>) {
+    match expr {
+        Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+        Expr::Paren { inner, .. } | Expr::Unary { expr: inner, .. } | Expr::Borrow(inner, _) => {
+            collect_nested_fns_expr(inner, out)
+        }
+        Expr::Binary { lhs, rhs, .. } => {
+            collect_nested_fns_expr(lhs, out);
+            collect_nested_fns_expr(rhs, out);
+        }
+        Expr::Call { callee, args, .. } => {
+            collect_nested_fns_expr(callee, out);
+            for arg in args {
+                collect_nested_fns_expr(arg, out);
+            }
+        }
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => {
+            collect_nested_fns_block(block, out)
+        }
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            collect_nested_fns_expr(cond, out);
+            collect_nested_fns_block(then_, out);
+            if let Some(e) = else_ {
+                collect_nested_fns_expr(e, out);
+            }
+        }
+        Expr::While { cond, body, .. }
+        | Expr::For {
+            iter: cond, body, ..
+        } => {
+            collect_nested_fns_expr(cond, out);
+            collect_nested_fns_block(body, out);
+        }
+        Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            collect_nested_fns_expr(scrutinee, out);
+            for arm in arms {
+                collect_nested_fns_expr(&arm.body, out);
+            }
+        }
+        Expr::Tuple { elems, .. } => {
+            for e in elems {
+                collect_nested_fns_expr(e, out);
+            }
+        }
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            for f in fields {
+                collect_nested_fns_expr(&f.value, out);
+            }
+        }
+        Expr::Field { expr, .. } => collect_nested_fns_expr(expr, out),
+    }
+}
+
 /// Check if a type contains any Ty::Ref (reference) anywhere in its structure.
 fn contains_ref(ty: &Ty) -> bool {
     match ty {
@@ -1611,6 +3964,7 @@ fn contains_ref(ty: &Ty) -> bool {
         Ty::Const(_) | Ty::Var(_) | Ty::Never | Ty::Cap(_) => false,
         Ty::Arrow(params, ret, _) => params.iter().any(contains_ref) || contains_ref(ret),
         Ty::Tuple(tys) => tys.iter().any(contains_ref),
+        Ty::Record(fields, _) => fields.values().any(contains_ref),
         Ty::List(inner) => contains_ref(inner),
         Ty::Adt { args, .. } => args.iter().any(contains_ref),
     }
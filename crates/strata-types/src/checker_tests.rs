@@ -10,7 +10,7 @@ use strata_ast::span::Span;
 
 /// Helper to create a dummy span
 fn sp() -> Span {
-    Span { start: 0, end: 0 }
+    Span::default()
 }
 
 /// Helper to create an identifier
@@ -203,6 +203,7 @@ fn test_let_without_annotation() {
             ty: None,
             value: Expr::Lit(Lit::Int(42), sp()),
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -218,6 +219,7 @@ fn test_let_with_matching_annotation() {
             ty: Some(ty_int()),
             value: Expr::Lit(Lit::Int(42), sp()),
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -234,12 +236,14 @@ fn test_variable_reference() {
                 ty: None,
                 value: Expr::Lit(Lit::Int(42), sp()),
                 span: sp(),
+                attrs: Vec::new(),
             }),
             Item::Let(LetDecl {
                 name: ident("y"),
                 ty: None,
                 value: Expr::Var(ident("x")),
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -257,6 +261,7 @@ fn test_multiple_lets() {
                 ty: Some(ty_int()),
                 value: Expr::Lit(Lit::Int(1), sp()),
                 span: sp(),
+                attrs: Vec::new(),
             }),
             Item::Let(LetDecl {
                 name: ident("y"),
@@ -268,6 +273,7 @@ fn test_multiple_lets() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
             Item::Let(LetDecl {
                 name: ident("z"),
@@ -279,6 +285,7 @@ fn test_multiple_lets() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -341,6 +348,7 @@ fn test_annotation_mismatch() {
             ty: Some(ty_bool()),
             value: Expr::Lit(Lit::Int(123), sp()),
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -422,6 +430,7 @@ fn test_function_call_unknown_function() {
     let expr = Expr::Call {
         callee: Box::new(Expr::Var(ident("foo"))),
         args: vec![],
+        names: Vec::new(),
         span: sp(),
     };
     let result = tc.infer_expr(&expr);
@@ -828,7 +837,12 @@ fn make_struct(name: &str, type_params: &[&str], fields: Vec<Field>) -> StructDe
         name: ident(name),
         type_params: type_params.iter().map(|s| ident(s)).collect(),
         fields,
+        linear: false,
+        tuple: false,
+        transparent: false,
+        public: false,
         span: sp(),
+        attrs: Vec::new(),
     }
 }
 
@@ -837,6 +851,7 @@ fn make_field(name: &str, ty: TypeExpr) -> Field {
     Field {
         name: ident(name),
         ty,
+        default: None,
         span: sp(),
     }
 }
@@ -847,7 +862,10 @@ fn make_enum(name: &str, type_params: &[&str], variants: Vec<Variant>) -> EnumDe
         name: ident(name),
         type_params: type_params.iter().map(|s| ident(s)).collect(),
         variants,
+        linear: false,
+        public: false,
         span: sp(),
+        attrs: Vec::new(),
     }
 }
 
@@ -856,6 +874,7 @@ fn make_unit_variant(name: &str) -> Variant {
     Variant {
         name: ident(name),
         fields: VariantFields::Unit,
+        discriminant: None,
         span: sp(),
     }
 }
@@ -865,6 +884,7 @@ fn make_tuple_variant(name: &str, fields: Vec<TypeExpr>) -> Variant {
     Variant {
         name: ident(name),
         fields: VariantFields::Tuple(fields),
+        discriminant: None,
         span: sp(),
     }
 }
@@ -1317,6 +1337,7 @@ fn expr_variant_call(type_name: &str, variant_name: &str, args: Vec<Expr>) -> Ex
             span: sp(),
         })),
         args,
+        names: Vec::new(),
         span: sp(),
     }
 }
@@ -1363,16 +1384,22 @@ fn test_option_without_pattern() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(Expr::Lit(Lit::Int(0), sp()))), // Just return 0
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1401,10 +1428,15 @@ fn test_variant_pattern_direct() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1421,6 +1453,7 @@ fn test_variant_pattern_direct() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1438,10 +1471,15 @@ fn test_match_with_literal_pattern() {
             params: vec![Param {
                 name: ident("x"),
                 ty: Some(ty_int()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_int()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -1457,6 +1495,7 @@ fn test_match_with_literal_pattern() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -1488,10 +1527,15 @@ fn test_match_option_some_none() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1510,6 +1554,7 @@ fn test_match_option_some_none() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1536,10 +1581,15 @@ fn test_match_arm_type_mismatch() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: None,
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1558,6 +1608,7 @@ fn test_match_arm_type_mismatch() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1625,6 +1676,9 @@ fn test_match_nested_variant_pattern() {
                 params: vec![],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1659,6 +1713,7 @@ fn test_match_nested_variant_pattern() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1708,6 +1763,9 @@ fn test_pattern_variant_arity_mismatch() {
                 params: vec![],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1726,6 +1784,7 @@ fn test_pattern_variant_arity_mismatch() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1754,10 +1813,15 @@ fn test_pattern_unknown_variant() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -1770,6 +1834,7 @@ fn test_pattern_unknown_variant() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1885,25 +1950,18 @@ fn test_tuple_destructuring_basic() {
 
 #[test]
 fn test_tuple_arity_limit() {
-    // Tuples with more than 8 elements should fail
+    // Tuples with more than 64 elements should fail under the default profile
+    let elems = |n: i64| (0..n).map(|i| Expr::Lit(Lit::Int(i), sp())).collect();
     let mut tc = TypeChecker::new();
-    let expr = expr_tuple(vec![
-        Expr::Lit(Lit::Int(1), sp()),
-        Expr::Lit(Lit::Int(2), sp()),
-        Expr::Lit(Lit::Int(3), sp()),
-        Expr::Lit(Lit::Int(4), sp()),
-        Expr::Lit(Lit::Int(5), sp()),
-        Expr::Lit(Lit::Int(6), sp()),
-        Expr::Lit(Lit::Int(7), sp()),
-        Expr::Lit(Lit::Int(8), sp()),
-        Expr::Lit(Lit::Int(9), sp()), // 9th element - too many!
-    ]);
-    let result = tc.infer_expr(&expr);
-    assert!(result.is_err());
-    // The error should be about arity limit
+    assert!(tc.infer_expr(&expr_tuple(elems(64))).is_ok());
+    let result = tc.infer_expr(&expr_tuple(elems(65)));
     assert!(matches!(
         result.unwrap_err(),
-        TypeError::ArityMismatch { .. }
+        TypeError::TupleTooWide {
+            max: 64,
+            found: 65,
+            ..
+        }
     ));
 }
 
@@ -1928,6 +1986,9 @@ fn test_struct_construction() {
                 params: vec![],
                 ret_ty: Some(ty_adt("Point")),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_struct(
@@ -1940,6 +2001,7 @@ fn test_struct_construction() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -1964,6 +2026,9 @@ fn test_struct_missing_field_error() {
                 params: vec![],
                 ret_ty: Some(ty_adt("Point")),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_struct(
@@ -1973,6 +2038,7 @@ fn test_struct_missing_field_error() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2002,6 +2068,9 @@ fn test_struct_unknown_field_error() {
                 params: vec![],
                 ret_ty: Some(ty_adt("Point")),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_struct(
@@ -2015,6 +2084,7 @@ fn test_struct_unknown_field_error() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2044,6 +2114,9 @@ fn test_struct_field_type_mismatch() {
                 params: vec![],
                 ret_ty: Some(ty_adt("Point")),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_struct(
@@ -2056,6 +2129,7 @@ fn test_struct_field_type_mismatch() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2116,10 +2190,15 @@ fn test_struct_pattern_destructuring() {
                 params: vec![Param {
                     name: ident("p"),
                     ty: Some(ty_adt("Point")),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2137,6 +2216,7 @@ fn test_struct_pattern_destructuring() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2168,10 +2248,15 @@ fn test_exhaustive_option_both_variants() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2190,6 +2275,7 @@ fn test_exhaustive_option_both_variants() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2217,10 +2303,15 @@ fn test_non_exhaustive_option_some_only_error() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2233,6 +2324,7 @@ fn test_non_exhaustive_option_some_only_error() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2265,10 +2357,15 @@ fn test_non_exhaustive_option_none_only_error() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2281,6 +2378,7 @@ fn test_non_exhaustive_option_none_only_error() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2303,10 +2401,15 @@ fn test_exhaustive_bool_both_values() {
             params: vec![Param {
                 name: ident("b"),
                 ty: Some(ty_bool()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_int()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2325,6 +2428,7 @@ fn test_exhaustive_bool_both_values() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2341,10 +2445,15 @@ fn test_non_exhaustive_bool_true_only_error() {
             params: vec![Param {
                 name: ident("b"),
                 ty: Some(ty_bool()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_int()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2357,6 +2466,7 @@ fn test_non_exhaustive_bool_true_only_error() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2387,10 +2497,15 @@ fn test_exhaustive_with_wildcard() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2406,6 +2521,7 @@ fn test_exhaustive_with_wildcard() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2432,10 +2548,15 @@ fn test_redundant_arm_after_wildcard_error() {
                 params: vec![Param {
                     name: ident("opt"),
                     ty: Some(ty_generic("Option", vec![ty_int()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2451,6 +2572,7 @@ fn test_redundant_arm_after_wildcard_error() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2473,10 +2595,15 @@ fn test_redundant_bool_after_both_covered() {
             params: vec![Param {
                 name: ident("b"),
                 ty: Some(ty_bool()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_int()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2499,6 +2626,7 @@ fn test_redundant_bool_after_both_covered() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2520,10 +2648,15 @@ fn test_int_literal_needs_wildcard() {
             params: vec![Param {
                 name: ident("n"),
                 ty: Some(ty_int()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_string()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2542,6 +2675,7 @@ fn test_int_literal_needs_wildcard() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2563,10 +2697,15 @@ fn test_int_literal_with_wildcard_exhaustive() {
             params: vec![Param {
                 name: ident("n"),
                 ty: Some(ty_int()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_string()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2585,6 +2724,7 @@ fn test_int_literal_with_wildcard_exhaustive() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2611,10 +2751,15 @@ fn test_result_enum_exhaustive() {
                 params: vec![Param {
                     name: ident("r"),
                     ty: Some(ty_generic("Result", vec![ty_int(), ty_string()])),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2633,6 +2778,7 @@ fn test_result_enum_exhaustive() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2657,10 +2803,15 @@ fn test_struct_single_constructor_exhaustive() {
                 params: vec![Param {
                     name: ident("p"),
                     ty: Some(ty_adt("Point")),
+                    default: None,
+                    variadic: false,
                     span: sp(),
                 }],
                 ret_ty: Some(ty_int()),
                 effects: None,
+                public: false,
+                bench: false,
+                pure: false,
                 body: Block {
                     stmts: vec![],
                     tail: Some(Box::new(expr_match(
@@ -2678,6 +2829,7 @@ fn test_struct_single_constructor_exhaustive() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2696,10 +2848,15 @@ fn test_multiple_redundant_arms() {
             params: vec![Param {
                 name: ident("b"),
                 ty: Some(ty_bool()),
+                default: None,
+                variadic: false,
                 span: sp(),
             }],
             ret_ty: Some(ty_int()),
             effects: None,
+            public: false,
+            bench: false,
+            pure: false,
             body: Block {
                 stmts: vec![],
                 tail: Some(Box::new(expr_match(
@@ -2723,6 +2880,7 @@ fn test_multiple_redundant_arms() {
                 span: sp(),
             },
             span: sp(),
+            attrs: Vec::new(),
         })],
         span: sp(),
     };
@@ -2855,15 +3013,20 @@ fn test_destructuring_let_refutable_pattern_error() {
             Variant {
                 name: ident("Some"),
                 fields: VF::Tuple(vec![TypeExpr::Path(vec![ident("T")], sp())]),
+                discriminant: None,
                 span: sp(),
             },
             Variant {
                 name: ident("None"),
                 fields: VF::Unit,
+                discriminant: None,
                 span: sp(),
             },
         ],
+        linear: false,
+        public: false,
         span: sp(),
+        attrs: Vec::new(),
     };
     tc.check_module(&Module {
         items: vec![Item::Enum(option_enum)],
@@ -2890,6 +3053,7 @@ fn test_destructuring_let_refutable_pattern_error() {
                     span: sp(),
                 })),
                 args: vec![Expr::Lit(Lit::Int(42), sp())],
+                names: Vec::new(),
                 span: sp(),
             },
             span: sp(),
@@ -2947,6 +3111,7 @@ fn test_capability_in_let_binding_direct() {
                 ty: None,
                 value: expr_struct("NetCap", vec![]),
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -2977,6 +3142,7 @@ fn test_capability_in_let_binding_tuple() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
@@ -3005,6 +3171,7 @@ fn test_non_capability_in_let_binding_ok() {
                     span: sp(),
                 },
                 span: sp(),
+                attrs: Vec::new(),
             }),
         ],
         span: sp(),
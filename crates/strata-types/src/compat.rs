@@ -0,0 +1,238 @@
+//! API compatibility between two versions of a module's interface.
+//!
+//! [`compare`] lists what changed from an old [`Interface`] to a new one and
+//! whether each change can break a downstream module that checked against
+//! the old one. Adding things is compatible; removing or retyping them is
+//! breaking, as is anything that makes an existing use ill-typed:
+//!
+//! - a `pub fn` that gains an effect, since callers whose effect rows are
+//!   annotated no longer cover it (dropping an effect is compatible);
+//! - a struct that gains a field, since struct literals must set every
+//!   field;
+//! - an enum that gains a variant, since a `match` without a wildcard arm
+//!   is no longer exhaustive;
+//! - an ADT that becomes `linear`, since its values can then be used only
+//!   once.
+
+use crate::adt::{AdtDef, AdtKind};
+use crate::effects::ALL_EFFECTS;
+use crate::infer::ty::{renumbered, Scheme, Ty};
+use crate::interface::Interface;
+
+/// One difference between two interfaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiChange {
+    /// `fn load`, `struct Point`, or `enum Shape`
+    pub item: String,
+    pub message: String,
+    /// Whether code that checked against the old interface may not check
+    /// against the new one
+    pub breaking: bool,
+}
+
+impl std::fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let class = if self.breaking {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        write!(f, "{}: {}: {}", class, self.item, self.message)
+    }
+}
+
+/// The changes from `old` to `new`: functions in the order `old` lists
+/// them and then new ones, then ADTs the same way
+pub fn compare(old: &Interface, new: &Interface) -> Vec<ApiChange> {
+    let mut changes = Vec::new();
+    let mut change = |item: String, message: String, breaking: bool| {
+        changes.push(ApiChange {
+            item,
+            message,
+            breaking,
+        })
+    };
+
+    for (name, old_scheme) in &old.fns {
+        let item = format!("fn {}", name);
+        match new.fns.iter().find(|(n, _)| n == name) {
+            Some((_, new_scheme)) => {
+                if let Some((message, breaking)) = scheme_change(old_scheme, new_scheme) {
+                    change(item, message, breaking);
+                }
+            }
+            None if new.private.contains(name) => change(item, "no longer `pub`".to_string(), true),
+            None => change(item, "removed".to_string(), true),
+        }
+    }
+    for (name, scheme) in &new.fns {
+        if !old.fns.iter().any(|(n, _)| n == name) {
+            let message = format!("added: {}", renumbered(&scheme.ty));
+            change(format!("fn {}", name), message, false);
+        }
+    }
+
+    for old_def in &old.adts {
+        let item = format!("{} {}", adt_kind(old_def), old_def.name);
+        match new.adts.iter().find(|d| d.name == old_def.name) {
+            Some(new_def) => {
+                for (message, breaking) in adt_changes(old_def, new_def) {
+                    change(item.clone(), message, breaking);
+                }
+            }
+            None => change(item, "removed, or no longer `pub`".to_string(), true),
+        }
+    }
+    for def in &new.adts {
+        if !old.adts.iter().any(|d| d.name == def.name) {
+            change(
+                format!("{} {}", adt_kind(def), def.name),
+                "added".to_string(),
+                false,
+            );
+        }
+    }
+    changes
+}
+
+/// How a function's scheme changed, and whether that breaks callers
+fn scheme_change(old: &Scheme, new: &Scheme) -> Option<(String, bool)> {
+    let (old_ty, new_ty) = (renumbered(&old.ty), renumbered(&new.ty));
+    if old_ty == new_ty {
+        return None;
+    }
+    // The same type apart from the function's own effects
+    if let (Ty::Arrow(old_params, old_ret, old_row), Ty::Arrow(new_params, new_ret, new_row)) =
+        (&old_ty, &new_ty)
+    {
+        if old_params == new_params && old_ret == new_ret && old_row.tail == new_row.tail {
+            let added = effect_names(new_row.concrete & !old_row.concrete);
+            let dropped = effect_names(old_row.concrete & !new_row.concrete);
+            return Some(match (added.is_empty(), dropped.is_empty()) {
+                (false, true) => (format!("adds effects {{{}}}", added), true),
+                (true, false) => (format!("no longer has effects {{{}}}", dropped), false),
+                _ => (
+                    format!("adds effects {{{}}} and drops {{{}}}", added, dropped),
+                    true,
+                ),
+            });
+        }
+    }
+    Some((format!("type changed from {} to {}", old_ty, new_ty), true))
+}
+
+/// The effects in the bitmask `mask`, comma-separated
+fn effect_names(mask: u64) -> String {
+    let names: Vec<String> = ALL_EFFECTS
+        .iter()
+        .filter(|e| mask & e.bit() != 0)
+        .map(|e| format!("{:?}", e))
+        .collect();
+    names.join(", ")
+}
+
+fn adt_kind(def: &AdtDef) -> &'static str {
+    match def.kind {
+        AdtKind::Struct(_) => "struct",
+        AdtKind::Enum(_) => "enum",
+    }
+}
+
+/// How an ADT changed, and whether each change breaks its users
+fn adt_changes(old: &AdtDef, new: &AdtDef) -> Vec<(String, bool)> {
+    let mut changes = Vec::new();
+    if adt_kind(old) != adt_kind(new) {
+        let message = format!("changed from {} to {}", adt_kind(old), adt_kind(new));
+        return vec![(message, true)];
+    }
+    if old.type_params.len() != new.type_params.len() {
+        let message = format!(
+            "type parameters changed from <{}> to <{}>",
+            old.type_params.join(", "),
+            new.type_params.join(", ")
+        );
+        return vec![(message, true)];
+    }
+    match (old.linear, new.linear) {
+        (false, true) => changes.push(("now `linear`".to_string(), true)),
+        (true, false) => changes.push(("no longer `linear`".to_string(), false)),
+        _ => {}
+    }
+    if old.tuple != new.tuple {
+        let message = match new.tuple {
+            true => "now a tuple struct",
+            false => "no longer a tuple struct",
+        };
+        changes.push((message.to_string(), true));
+    }
+    match (&old.kind, &new.kind) {
+        (AdtKind::Struct(old_fields), AdtKind::Struct(new_fields)) => {
+            for field in old_fields {
+                match new_fields.iter().find(|f| f.name == field.name) {
+                    Some(f) if f.ty != field.ty => changes.push((
+                        format!(
+                            "field {} changed type from {} to {}",
+                            field.name, field.ty, f.ty
+                        ),
+                        true,
+                    )),
+                    // Literals that left the field off no longer build
+                    Some(f) if field.default.is_some() && f.default.is_none() => changes.push((
+                        format!("field {} no longer has a default", field.name),
+                        true,
+                    )),
+                    Some(_) => {}
+                    None => changes.push((format!("field {} removed", field.name), true)),
+                }
+            }
+            for field in new_fields {
+                if !old_fields.iter().any(|f| f.name == field.name) {
+                    // Existing literals leave a defaulted field off
+                    match field.default {
+                        Some(_) => changes
+                            .push((format!("field {} added, with a default", field.name), false)),
+                        None => changes.push((format!("field {} added", field.name), true)),
+                    }
+                }
+            }
+        }
+        (AdtKind::Enum(old_variants), AdtKind::Enum(new_variants)) => {
+            for variant in old_variants {
+                match new_variants.iter().find(|v| v.name == variant.name) {
+                    Some(v) if v.fields != variant.fields => {
+                        changes.push((format!("variant {} changed its fields", variant.name), true))
+                    }
+                    // Only a unit-only enum's numbers can be seen, by casts
+                    Some(v)
+                        if v.discriminant != variant.discriminant
+                            && old.is_unit_only()
+                            && new.is_unit_only() =>
+                    {
+                        changes.push((
+                            format!(
+                                "variant {} renumbered from {} to {}",
+                                variant.name, variant.discriminant, v.discriminant
+                            ),
+                            true,
+                        ))
+                    }
+                    Some(_) => {}
+                    None => changes.push((format!("variant {} removed", variant.name), true)),
+                }
+            }
+            for variant in new_variants {
+                if !old_variants.iter().any(|v| v.name == variant.name) {
+                    changes.push((
+                        format!(
+                            "variant {} added; matches without a wildcard arm are no longer exhaustive",
+                            variant.name
+                        ),
+                        true,
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+    changes
+}
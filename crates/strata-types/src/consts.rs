@@ -0,0 +1,269 @@
+//! Compile-time evaluation of `const` items.
+//!
+//! A const's value may use literals, other consts, `-`/`!`, arithmetic,
+//! comparisons, and `&&`/`||`. Values are computed in dependency order
+//! before any function body is checked, so a match arm can use a const
+//! name as a literal pattern. The checker has already type-checked each
+//! value against its annotation, so operand kinds are known to agree.
+//!
+//! An `extern fn` parameter's or struct field's default value is a
+//! constant expression too, evaluated the same way.
+
+use crate::infer::ty::Ty;
+use std::collections::HashMap;
+use strata_ast::ast::{BinOp, ConstDecl, Expr, Field, Item, Lit, Module, Param, UnOp};
+use strata_ast::span::Span;
+
+/// The value of a `const`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl ConstValue {
+    /// The value as a literal, for use as a pattern
+    pub fn to_lit(&self) -> Lit {
+        match self {
+            ConstValue::Int(n) => Lit::Int(*n),
+            ConstValue::Float(x) => Lit::Float(*x),
+            ConstValue::Bool(b) => Lit::Bool(*b),
+            ConstValue::Str(s) => Lit::Str(s.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConstValue::Int(n) => write!(f, "{}", n),
+            ConstValue::Float(x) => write!(f, "{}", x),
+            ConstValue::Bool(b) => write!(f, "{}", b),
+            ConstValue::Str(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// Evaluated consts by name.
+pub type ConstTable = HashMap<String, ConstValue>;
+
+/// Whether a const may have type `ty`
+pub fn is_const_type(ty: &Ty) -> bool {
+    [Ty::int(), Ty::float(), Ty::bool_(), Ty::string()].contains(ty)
+}
+
+/// Failure to evaluate a const.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstError {
+    /// Part of the value can only be computed at runtime (a call, a local
+    /// variable, a block, ...)
+    NotConstant { name: String, span: Span },
+    /// Consts whose values depend on each other, starting from the one
+    /// whose value is reported
+    Cycle { cycle: Vec<String>, span: Span },
+    /// Integer overflow or division by zero
+    Arithmetic { message: String, span: Span },
+}
+
+fn const_decls(module: &Module) -> HashMap<&str, &ConstDecl> {
+    module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Const(decl) => Some((decl.name.text.as_str(), decl)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Evaluate every `const` in `module`.
+pub fn evaluate(module: &Module) -> Result<ConstTable, ConstError> {
+    let decls = const_decls(module);
+    let mut evaluator = Evaluator {
+        decls: &decls,
+        values: ConstTable::new(),
+        in_progress: Vec::new(),
+    };
+    for item in &module.items {
+        if let Item::Const(decl) = item {
+            evaluator.eval_const(decl)?;
+        }
+    }
+    Ok(evaluator.values)
+}
+
+/// Evaluate the default values of `params`, parameters of an `extern fn`
+/// in `module`, one per parameter (`None` for a parameter without one). A
+/// default that isn't constant is reported with the parameter's name.
+pub fn evaluate_defaults(
+    module: &Module,
+    params: &[Param],
+) -> Result<Vec<Option<ConstValue>>, ConstError> {
+    let defaults = params
+        .iter()
+        .map(|param| (param.name.text.as_str(), param.default.as_ref()));
+    evaluate_each(module, defaults)
+}
+
+/// Evaluate the default values of `fields`, fields of a struct in
+/// `module`, like [`evaluate_defaults`].
+pub fn evaluate_field_defaults(
+    module: &Module,
+    fields: &[Field],
+) -> Result<Vec<Option<ConstValue>>, ConstError> {
+    let defaults = fields
+        .iter()
+        .map(|field| (field.name.text.as_str(), field.default.as_ref()));
+    evaluate_each(module, defaults)
+}
+
+fn evaluate_each<'a>(
+    module: &Module,
+    defaults: impl Iterator<Item = (&'a str, Option<&'a Expr>)>,
+) -> Result<Vec<Option<ConstValue>>, ConstError> {
+    let decls = const_decls(module);
+    let mut evaluator = Evaluator {
+        decls: &decls,
+        values: ConstTable::new(),
+        in_progress: Vec::new(),
+    };
+    defaults
+        .map(|(name, value)| {
+            value
+                .map(|value| evaluator.eval_expr(name, value))
+                .transpose()
+        })
+        .collect()
+}
+
+struct Evaluator<'a> {
+    decls: &'a HashMap<&'a str, &'a ConstDecl>,
+    values: ConstTable,
+    /// Consts whose values are being computed, outermost first
+    in_progress: Vec<&'a str>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn eval_const(&mut self, decl: &'a ConstDecl) -> Result<ConstValue, ConstError> {
+        let name = decl.name.text.as_str();
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(pos) = self.in_progress.iter().position(|n| *n == name) {
+            let first = self.decls[self.in_progress[pos]];
+            return Err(ConstError::Cycle {
+                cycle: self.in_progress[pos..]
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect(),
+                span: first.name.span,
+            });
+        }
+        self.in_progress.push(name);
+        let value = self.eval_expr(name, &decl.value);
+        self.in_progress.pop();
+        let value = value?;
+        self.values.insert(name.to_string(), value.clone());
+        Ok(value)
+    }
+
+    /// Evaluate `expr`, part of the value of the const `owner`
+    fn eval_expr(&mut self, owner: &str, expr: &Expr) -> Result<ConstValue, ConstError> {
+        let not_constant = || ConstError::NotConstant {
+            name: owner.to_string(),
+            span: expr.span(),
+        };
+        match expr {
+            Expr::Lit(Lit::Int(n), _) => Ok(ConstValue::Int(*n)),
+            Expr::Lit(Lit::Float(x), _) => Ok(ConstValue::Float(*x)),
+            Expr::Lit(Lit::Bool(b), _) => Ok(ConstValue::Bool(*b)),
+            Expr::Lit(Lit::Str(s), _) => Ok(ConstValue::Str(s.clone())),
+            Expr::Var(id) => match self.decls.get(id.text.as_str()) {
+                Some(decl) => self.eval_const(decl),
+                None => Err(not_constant()),
+            },
+            Expr::Paren { inner, .. } => self.eval_expr(owner, inner),
+            Expr::Unary { op, expr, span } => match (op, self.eval_expr(owner, expr)?) {
+                (UnOp::Neg, ConstValue::Int(n)) => n
+                    .checked_neg()
+                    .map(ConstValue::Int)
+                    .ok_or_else(|| overflow(*span)),
+                (UnOp::Neg, ConstValue::Float(x)) => Ok(ConstValue::Float(-x)),
+                (UnOp::Not, ConstValue::Bool(b)) => Ok(ConstValue::Bool(!b)),
+                _ => Err(not_constant()),
+            },
+            Expr::Binary { op, lhs, rhs, span } => {
+                let l = self.eval_expr(owner, lhs)?;
+                // `&&` and `||` short-circuit, as at runtime
+                match (op, &l) {
+                    (BinOp::And, ConstValue::Bool(false)) | (BinOp::Or, ConstValue::Bool(true)) => {
+                        return Ok(l)
+                    }
+                    _ => {}
+                }
+                let r = self.eval_expr(owner, rhs)?;
+                binary(*op, l, r, *span).ok_or_else(not_constant)?
+            }
+            _ => Err(not_constant()),
+        }
+    }
+}
+
+fn overflow(span: Span) -> ConstError {
+    ConstError::Arithmetic {
+        message: "integer overflow".to_string(),
+        span,
+    }
+}
+
+/// Apply `op`; `None` if it doesn't apply to these operands.
+fn binary(
+    op: BinOp,
+    l: ConstValue,
+    r: ConstValue,
+    span: Span,
+) -> Option<Result<ConstValue, ConstError>> {
+    use ConstValue::*;
+    let value = match (op, l, r) {
+        (BinOp::And | BinOp::Or, Bool(_), Bool(b)) => Bool(b),
+        (BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div, Int(a), Int(b)) => {
+            return Some(int_arith(op, a, b, span))
+        }
+        (BinOp::Eq, l, r) => Bool(l == r),
+        (BinOp::Ne, l, r) => Bool(l != r),
+        (BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge, l, r) => {
+            let ord = match (l, r) {
+                (Int(a), Int(b)) => a.partial_cmp(&b),
+                (Float(a), Float(b)) => a.partial_cmp(&b),
+                _ => return None,
+            };
+            // NaN compares false either way
+            Bool(ord.is_some_and(|o| match op {
+                BinOp::Lt => o.is_lt(),
+                BinOp::Le => o.is_le(),
+                BinOp::Gt => o.is_gt(),
+                _ => o.is_ge(),
+            }))
+        }
+        _ => return None,
+    };
+    Some(Ok(value))
+}
+
+fn int_arith(op: BinOp, a: i64, b: i64, span: Span) -> Result<ConstValue, ConstError> {
+    if matches!(op, BinOp::Div) && b == 0 {
+        return Err(ConstError::Arithmetic {
+            message: "division by zero".to_string(),
+            span,
+        });
+    }
+    let result = match op {
+        BinOp::Add => a.checked_add(b),
+        BinOp::Sub => a.checked_sub(b),
+        BinOp::Mul => a.checked_mul(b),
+        _ => a.checked_div(b),
+    };
+    result.map(ConstValue::Int).ok_or_else(|| overflow(span))
+}
@@ -0,0 +1,85 @@
+//! Deprecated-item lint.
+//!
+//! Reports each use of a function, extern, const, or type marked
+//! `#[deprecated]` or `#[deprecated("note")]`, carrying the note so the
+//! warning can say what to use instead. Uses are read from the module's
+//! [`Resolution`], so a local that happens to share a deprecated name is
+//! not reported. Uses inside a deprecated item are not reported either:
+//! deprecated code may keep calling other deprecated code.
+
+use crate::resolve::{DefId, Resolution};
+use crate::warnings::Warning;
+use strata_ast::ast::{AttrArg, Attribute, Item, Module};
+use strata_ast::span::Span;
+
+/// A deprecated definition and its note.
+struct Deprecated {
+    def: DefId,
+    note: Option<String>,
+    /// The whole item, whose own uses aren't reported
+    span: Span,
+}
+
+/// Warnings for every use of a deprecated item in `module`, in source order.
+pub(crate) fn check_module(module: &Module, resolution: &Resolution) -> Vec<Warning> {
+    let deprecated: Vec<Deprecated> = module.items.iter().filter_map(deprecation).collect();
+    if deprecated.is_empty() {
+        return Vec::new();
+    }
+    let mut warnings: Vec<Warning> = resolution
+        .iter()
+        .filter(|(span, _, _)| !deprecated.iter().any(|d| contains(d.span, *span)))
+        .filter_map(|(span, name, def)| {
+            let found = deprecated.iter().find(|d| refers_to(def, &d.def))?;
+            Some(Warning::Deprecated {
+                name: name.to_string(),
+                note: found.note.clone(),
+                span,
+            })
+        })
+        .collect();
+    warnings.sort_by_key(|w| {
+        let span = w.span();
+        (span.file, span.start, span.end)
+    });
+    warnings
+}
+
+/// The definition `item` marks deprecated, if it has `#[deprecated]`
+fn deprecation(item: &Item) -> Option<Deprecated> {
+    let attr = item.attrs().iter().find(|a| a.name.text == "deprecated")?;
+    let (def, span) = match item {
+        Item::Fn(decl) => (DefId::Fn(decl.name.span), decl.span),
+        Item::ExternFn(decl) => (DefId::Extern(decl.name.span), decl.span),
+        Item::Const(decl) => (DefId::Const(decl.name.span), decl.span),
+        Item::Struct(def) => (DefId::Type(def.name.text.clone()), def.span),
+        Item::Enum(def) => (DefId::Type(def.name.text.clone()), def.span),
+        Item::Let(_) | Item::Use(_) => return None,
+    };
+    Some(Deprecated {
+        def,
+        note: note(attr),
+        span,
+    })
+}
+
+/// The note of `#[deprecated("note")]`
+fn note(attr: &Attribute) -> Option<String> {
+    match attr.args.as_slice() {
+        [AttrArg::Str { value, .. }] => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Whether a use resolved to `def` uses the deprecated `target`. A
+/// deprecated type's constructors and variants are deprecated with it.
+fn refers_to(def: &DefId, target: &DefId) -> bool {
+    match (def, target) {
+        (DefId::Ctor { adt, .. }, DefId::Type(name)) => adt == name,
+        _ => def == target,
+    }
+}
+
+fn contains(outer: Span, inner: Span) -> bool {
+    outer.file == inner.file && outer.start <= inner.start && inner.end <= outer.end
+}
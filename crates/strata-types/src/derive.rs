@@ -0,0 +1,380 @@
+//! `#[derive(Show)]` on structs and enums.
+//!
+//! There are no traits yet, so a derive lowers to plain functions added to
+//! the module before checking and evaluation, named after the type in
+//! snake case: `Show` adds `fn point_to_string(value: Point) -> String`,
+//! formatting the value the way the `show` builtin does.
+//!
+//! There is no `Eq` to derive: `==` compares structs and enums field by
+//! field already, on any type whose fields have equality.
+//!
+//! The functions are `pub` when the type is, and every node in them starts
+//! at the derive's argument, so a type error in one points at the `Show`
+//! that asked for it. Names get spans of their own past its end, since name
+//! resolution tells uses apart by span.
+//! Generic types get unannotated parameters; the patterns the bodies
+//! match still pin them to the type.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use strata_ast::ast::{
+    AttrArg, Attribute, Block, EnumDef, Expr, FieldInit, FnDecl, Ident, Item, MatchArm, Module,
+    Param, Pat, PatField, Path, StructDef, TypeExpr, VariantFields,
+};
+use strata_ast::span::Span;
+
+/// What `#[derive(..)]` can derive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Derive {
+    /// `Show`: a `_to_string` function
+    Show,
+}
+
+impl Derive {
+    /// All derivable names.
+    pub const ALL: &'static [Derive] = &[Derive::Show];
+
+    /// The name written inside `#[derive(..)]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Derive::Show => "Show",
+        }
+    }
+
+    /// Look up a derivable name.
+    pub fn from_name(name: &str) -> Option<Derive> {
+        Derive::ALL.iter().copied().find(|d| d.name() == name)
+    }
+
+    /// The name of the function deriving this for the type `type_name`:
+    /// `http_request_to_string`
+    pub fn fn_name(self, type_name: &str) -> String {
+        let suffix = match self {
+            Derive::Show => "to_string",
+        };
+        format!("{}_{}", snake_case(type_name), suffix)
+    }
+}
+
+/// What the `#[derive(..)]` attributes in `attrs` ask for, each with the
+/// span of its argument. Names that aren't derivable are skipped; the
+/// attribute check reports them.
+pub fn derives(attrs: &[Attribute]) -> Vec<(Derive, Span)> {
+    attrs
+        .iter()
+        .filter(|a| a.name.text == "derive")
+        .flat_map(|a| &a.args)
+        .filter_map(|arg| match arg {
+            AttrArg::Word(word) => Some((Derive::from_name(&word.text)?, word.span)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `module` with the functions its `#[derive(..)]` attributes ask for
+/// added after their types. Borrowed when it has none, or they are all
+/// there already: expanding twice changes nothing.
+pub fn expand_derives(module: &Module) -> Cow<'_, Module> {
+    // A derived function has its derive's span, which no function written
+    // out can have
+    let derived: Vec<Span> = module
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(decl) => Some(decl.span),
+            _ => None,
+        })
+        .collect();
+    let missing = |item: &Item| {
+        let adt = matches!(item, Item::Struct(_) | Item::Enum(_));
+        derives(if adt { item.attrs() } else { &[] })
+            .into_iter()
+            .filter(|(_, span)| !derived.contains(span))
+            .collect::<Vec<_>>()
+    };
+    if module.items.iter().all(|item| missing(item).is_empty()) {
+        return Cow::Borrowed(module);
+    }
+
+    let mut items = Vec::with_capacity(module.items.len());
+    for item in &module.items {
+        items.push(item.clone());
+        let (adt, public) = match item {
+            Item::Struct(def) => (Adt::Struct(def), def.public),
+            Item::Enum(def) => (Adt::Enum(def), def.public),
+            _ => continue,
+        };
+        for (derive, span) in missing(item) {
+            let gen = Gen::new(span);
+            let decl = match derive {
+                Derive::Show => gen.to_string_fn(&adt),
+            };
+            items.push(Item::Fn(FnDecl { public, ..decl }));
+        }
+    }
+    Cow::Owned(Module {
+        items,
+        span: module.span,
+    })
+}
+
+/// `HttpRequest` as `http_request`
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+enum Adt<'a> {
+    Struct(&'a StructDef),
+    Enum(&'a EnumDef),
+}
+
+impl Adt<'_> {
+    fn name(&self) -> &Ident {
+        match self {
+            Adt::Struct(def) => &def.name,
+            Adt::Enum(def) => &def.name,
+        }
+    }
+
+    fn generic(&self) -> bool {
+        match self {
+            Adt::Struct(def) => !def.type_params.is_empty(),
+            Adt::Enum(def) => !def.type_params.is_empty(),
+        }
+    }
+
+    /// Each constructor: its path and how many fields it has, with their
+    /// names for a struct with named fields
+    fn ctors(&self) -> Vec<Ctor> {
+        match self {
+            Adt::Struct(def) => vec![Ctor {
+                path: vec![def.name.text.clone()],
+                fields: match def.tuple {
+                    true => Fields::Tuple(def.fields.len()),
+                    false => {
+                        Fields::Named(def.fields.iter().map(|f| f.name.text.clone()).collect())
+                    }
+                },
+            }],
+            Adt::Enum(def) => def
+                .variants
+                .iter()
+                .map(|v| Ctor {
+                    path: vec![def.name.text.clone(), v.name.text.clone()],
+                    fields: match &v.fields {
+                        VariantFields::Unit => Fields::Unit,
+                        VariantFields::Tuple(tys) => Fields::Tuple(tys.len()),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+struct Ctor {
+    path: Vec<String>,
+    fields: Fields,
+}
+
+enum Fields {
+    Unit,
+    Tuple(usize),
+    Named(Vec<String>),
+}
+
+/// Builds the nodes of a derived function: names and paths each with a
+/// span of their own, everything else with the derive's
+struct Gen {
+    span: Span,
+    /// How many name spans have been handed out
+    names: Cell<u32>,
+}
+
+impl Gen {
+    fn new(span: Span) -> Gen {
+        Gen {
+            span,
+            names: Cell::new(0),
+        }
+    }
+
+    /// A span starting at the derive's, distinct from every other one
+    fn name_span(&self) -> Span {
+        let n = self.names.get() + 1;
+        self.names.set(n);
+        Span {
+            end: self.span.end.saturating_add(n),
+            ..self.span
+        }
+    }
+
+    fn ident(&self, text: &str) -> Ident {
+        Ident {
+            text: text.to_string(),
+            span: self.name_span(),
+        }
+    }
+
+    fn var(&self, text: &str) -> Expr {
+        Expr::Var(self.ident(text))
+    }
+
+    fn path(&self, segments: &[String]) -> Path {
+        Path {
+            segments: segments.iter().map(|s| self.ident(s)).collect(),
+            span: self.name_span(),
+        }
+    }
+
+    fn block(&self, tail: Expr) -> Block {
+        Block {
+            stmts: Vec::new(),
+            tail: Some(Box::new(tail)),
+            span: self.span,
+        }
+    }
+
+    fn call(&self, callee: &str, args: Vec<Expr>) -> Expr {
+        Expr::Call {
+            callee: Box::new(self.var(callee)),
+            args,
+            names: Vec::new(),
+            span: self.span,
+        }
+    }
+
+    /// `name: Type` for a parameter of type `adt`, unannotated when `adt`
+    /// is generic
+    fn param(&self, name: &str, adt: &Adt) -> Param {
+        Param {
+            name: self.ident(name),
+            ty: (!adt.generic())
+                .then(|| TypeExpr::Path(vec![self.ident(&adt.name().text)], self.name_span())),
+            default: None,
+            variadic: false,
+            span: self.span,
+        }
+    }
+
+    fn fn_decl(&self, name: String, params: Vec<Param>, ret: &str, body: Expr) -> FnDecl {
+        FnDecl {
+            name: self.ident(&name),
+            params,
+            ret_ty: Some(TypeExpr::Path(vec![self.ident(ret)], self.name_span())),
+            effects: Some(Vec::new()),
+            public: false,
+            bench: false,
+            pure: false,
+            body: self.block(body),
+            attrs: Vec::new(),
+            span: self.span,
+        }
+    }
+
+    /// A pattern for `ctor` binding its fields to `{prefix}0`, `{prefix}1`, ...
+    fn ctor_pat(&self, ctor: &Ctor, prefix: &str) -> Pat {
+        let path = self.path(&ctor.path);
+        let binding = |i: usize| Pat::Ident(self.ident(&format!("{prefix}{i}")));
+        match &ctor.fields {
+            Fields::Unit => Pat::Variant {
+                path,
+                fields: Vec::new(),
+                span: self.span,
+            },
+            Fields::Tuple(n) => Pat::Variant {
+                path,
+                fields: (0..*n).map(binding).collect(),
+                span: self.span,
+            },
+            Fields::Named(names) => Pat::Struct {
+                path,
+                fields: names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| PatField {
+                        name: self.ident(name),
+                        pat: binding(i),
+                        span: self.span,
+                    })
+                    .collect(),
+                span: self.span,
+            },
+        }
+    }
+
+    /// `ctor` built from `{prefix}0`, `{prefix}1`, ...
+    fn ctor_expr(&self, ctor: &Ctor, prefix: &str) -> Expr {
+        let path = self.path(&ctor.path);
+        let binding = |i: usize| self.var(&format!("{prefix}{i}"));
+        match &ctor.fields {
+            Fields::Unit => Expr::PathExpr(path),
+            Fields::Tuple(n) => Expr::Call {
+                callee: Box::new(match path.segments.len() {
+                    1 => Expr::Var(path.segments[0].clone()),
+                    _ => Expr::PathExpr(path),
+                }),
+                args: (0..*n).map(binding).collect(),
+                names: Vec::new(),
+                span: self.span,
+            },
+            Fields::Named(names) => Expr::StructExpr {
+                path,
+                fields: names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| FieldInit {
+                        name: self.ident(name),
+                        value: binding(i),
+                        span: self.span,
+                    })
+                    .collect(),
+                span: self.span,
+            },
+        }
+    }
+
+    fn arm(&self, pat: Pat, body: Expr) -> MatchArm {
+        MatchArm {
+            pat,
+            body,
+            span: self.span,
+        }
+    }
+
+    /// `fn t_to_string(value: T) -> String`: `show` of the value, taken
+    /// apart and rebuilt so the match pins a generic type
+    fn to_string_fn(&self, adt: &Adt) -> FnDecl {
+        let ctors = adt.ctors();
+        let body = match ctors.is_empty() {
+            true => self.call("show", vec![self.var("value")]),
+            false => Expr::Match {
+                scrutinee: Box::new(self.var("value")),
+                arms: ctors
+                    .iter()
+                    .map(|ctor| {
+                        let shown = self.call("show", vec![self.ctor_expr(ctor, "f")]);
+                        self.arm(self.ctor_pat(ctor, "f"), shown)
+                    })
+                    .collect(),
+                span: self.span,
+            },
+        };
+        self.fn_decl(
+            Derive::Show.fn_name(&adt.name().text),
+            vec![self.param("value", adt)],
+            "String",
+            body,
+        )
+    }
+}
@@ -33,6 +33,7 @@ pub enum Effect {
     Time = 2,
     Rand = 3,
     Ai = 4,
+    Console = 5,
     // Add more as needed; keep < 64 without changing representation.
 }
 
@@ -41,6 +42,14 @@ impl Effect {
     pub fn bit(self) -> u64 {
         1u64 << (self as u8)
     }
+
+    /// Parse an effect name (e.g., "Fs") to an Effect.
+    pub fn from_name(name: &str) -> Option<Effect> {
+        ALL_EFFECTS
+            .iter()
+            .copied()
+            .find(|e| format!("{:?}", e) == name)
+    }
 }
 
 /// All known effects, in discriminant order.
@@ -50,6 +59,7 @@ pub const ALL_EFFECTS: &[Effect] = &[
     Effect::Time,
     Effect::Rand,
     Effect::Ai,
+    Effect::Console,
 ];
 
 /// Capability kind — each capability gates exactly one effect.
@@ -65,6 +75,7 @@ pub enum CapKind {
     Time,
     Rand,
     Ai,
+    Console,
 }
 
 impl CapKind {
@@ -76,6 +87,7 @@ impl CapKind {
             CapKind::Time => Effect::Time,
             CapKind::Rand => Effect::Rand,
             CapKind::Ai => Effect::Ai,
+            CapKind::Console => Effect::Console,
         }
     }
 
@@ -87,6 +99,7 @@ impl CapKind {
             Effect::Time => CapKind::Time,
             Effect::Rand => CapKind::Rand,
             Effect::Ai => CapKind::Ai,
+            Effect::Console => CapKind::Console,
         }
     }
 
@@ -98,6 +111,7 @@ impl CapKind {
             "TimeCap" => Some(CapKind::Time),
             "RandCap" => Some(CapKind::Rand),
             "AiCap" => Some(CapKind::Ai),
+            "ConsoleCap" => Some(CapKind::Console),
             _ => None,
         }
     }
@@ -110,6 +124,7 @@ impl CapKind {
             CapKind::Time => "TimeCap",
             CapKind::Rand => "RandCap",
             CapKind::Ai => "AiCap",
+            CapKind::Console => "ConsoleCap",
         }
     }
 }
@@ -13,15 +13,17 @@
 //! 3. Tracking which constructors are covered
 
 use crate::adt::AdtRegistry;
+use crate::consts::ConstTable;
 use crate::infer::ty::Ty;
-use std::collections::HashSet;
+use crate::profile::Limits;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use strata_ast::span::Span;
 
-/// Maximum size of pattern matrix (rows × columns) to prevent DoS
-const MAX_PATTERN_MATRIX_SIZE: usize = 10_000;
-
-/// Maximum recursion depth for exhaustiveness checking
-const MAX_EXHAUSTIVENESS_DEPTH: usize = 100;
+/// Maximum number of missing patterns collected for one match; a match
+/// missing more than this gets a list cut at this length.
+const MAX_MISSING_PATTERNS: usize = 64;
 
 /// Errors that can occur during exhaustiveness checking
 #[derive(Debug, Clone)]
@@ -93,6 +95,16 @@ impl std::fmt::Display for WitnessPat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             WitnessPat::Wildcard => write!(f, "_"),
+            WitnessPat::Constructor { name, args } if is_record_ctor(name) => {
+                write!(f, "{{")?;
+                for (i, (field, arg)) in record_fields(name).zip(args).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, arg)?;
+                }
+                write!(f, "}}")
+            }
             WitnessPat::Constructor { name, args } => {
                 write!(f, "{}", name)?;
                 if !args.is_empty() {
@@ -114,19 +126,22 @@ impl std::fmt::Display for WitnessPat {
 
 /// Simplified pattern for exhaustiveness checking.
 /// This is a normalized representation that's easier to work with than AST patterns.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SimplifiedPat {
     /// Wildcard pattern (matches anything)
     Wildcard,
-    /// Constructor pattern (enum variant, struct, or tuple)
+    /// Constructor pattern (enum variant, struct, tuple, or record)
     Constructor {
-        /// Fully qualified constructor name (e.g., "Option::Some", "Point", "Tuple2")
+        /// Fully qualified constructor name (e.g., "Option::Some", "Point",
+        /// "Tuple2"), or a record's sorted field names (e.g., "{x, y}")
         name: String,
         /// Constructor arguments (sub-patterns)
         args: Vec<SimplifiedPat>,
     },
     /// Literal pattern (Int, Bool, String)
     Literal(LiteralPat),
+    /// Integer range pattern with inclusive bounds
+    Range { lo: i64, hi: i64 },
 }
 
 /// Literal patterns
@@ -135,6 +150,8 @@ pub enum LiteralPat {
     Int(i64),
     Bool(bool),
     String(String),
+    /// Every string starting with the prefix: `"get " + _`
+    StrPrefix(String),
 }
 
 impl std::fmt::Display for LiteralPat {
@@ -143,6 +160,7 @@ impl std::fmt::Display for LiteralPat {
             LiteralPat::Int(n) => write!(f, "{}", n),
             LiteralPat::Bool(b) => write!(f, "{}", b),
             LiteralPat::String(s) => write!(f, "\"{}\"", s),
+            LiteralPat::StrPrefix(s) => write!(f, "\"{}\" + _", s),
         }
     }
 }
@@ -261,7 +279,24 @@ impl Constructor {
     }
 }
 
-/// Context for exhaustiveness checking
+/// A matrix row during the search. Rows borrow sub-patterns from the
+/// original arms, so specialization never deep-clones nested patterns.
+type Row<'p> = Vec<&'p SimplifiedPat>;
+
+/// Shared wildcard for expanding constructor arguments.
+static WILDCARD: SimplifiedPat = SimplifiedPat::Wildcard;
+
+/// Context for exhaustiveness checking.
+///
+/// Both exhaustiveness and redundancy reduce to a single usefulness query
+/// `U(P, q)`: is there a value matched by row `q` but by no row of `P`?
+/// A match is exhaustive iff the all-wildcard row is not useful, and an arm
+/// is redundant iff it is not useful against the arms above it.
+///
+/// Rows borrow their sub-patterns, constructor sets are memoized per type,
+/// complete signatures are specialized by bucketing rows in a single pass
+/// (O(rows + constructors) per column rather than O(rows × constructors)),
+/// and a row of only wildcards short-circuits the search.
 pub struct ExhaustivenessChecker<'a> {
     /// ADT registry for looking up type information
     registry: &'a AdtRegistry,
@@ -269,6 +304,14 @@ pub struct ExhaustivenessChecker<'a> {
     depth: usize,
     /// Span for error reporting
     span: Span,
+    /// Constructor sets per column type
+    ctor_cache: HashMap<Ty, Rc<Vec<Constructor>>>,
+    /// Largest pattern matrix (rows × columns) searched, to prevent DoS
+    max_matrix_size: usize,
+    /// Maximum recursion depth. Each level consumes one pattern node, so
+    /// this bounds the total size of a single row (all nested
+    /// sub-patterns), not just its nesting depth.
+    max_depth: usize,
 }
 
 impl<'a> ExhaustivenessChecker<'a> {
@@ -277,94 +320,32 @@ impl<'a> ExhaustivenessChecker<'a> {
             registry,
             depth: 0,
             span,
+            ctor_cache: HashMap::new(),
+            max_matrix_size: Limits::default().pattern_matrix,
+            max_depth: Limits::default().exhaustiveness_depth,
         }
     }
 
+    /// Search within the exhaustiveness budget of `limits`
+    pub fn with_limits(mut self, limits: &Limits) -> Self {
+        self.max_matrix_size = limits.pattern_matrix;
+        self.max_depth = limits.exhaustiveness_depth;
+        self
+    }
+
     /// Check if a pattern matrix is exhaustive.
     /// Returns None if exhaustive, Some(witness) if not.
     pub fn check_exhaustive(
         &mut self,
         matrix: &PatternMatrix,
     ) -> Result<Option<Witness>, ExhaustivenessError> {
-        // DoS protection: check matrix size
-        if matrix.size() > MAX_PATTERN_MATRIX_SIZE {
-            return Err(ExhaustivenessError::MatrixTooLarge {
-                size: matrix.size(),
-                span: self.span,
-            });
-        }
-
-        // DoS protection: check recursion depth
-        if self.depth > MAX_EXHAUSTIVENESS_DEPTH {
-            return Err(ExhaustivenessError::DepthExceeded { span: self.span });
-        }
-
-        self.depth += 1;
-        let result = self.check_exhaustive_inner(matrix);
-        self.depth -= 1;
-        result
-    }
-
-    fn check_exhaustive_inner(
-        &mut self,
-        matrix: &PatternMatrix,
-    ) -> Result<Option<Witness>, ExhaustivenessError> {
-        // Base case 1: No columns - exhaustive iff there are rows
-        if matrix.num_columns() == 0 {
-            if matrix.is_empty() {
-                // No rows, no columns - not exhaustive
-                // Return empty witness (to be extended by callers)
-                return Ok(Some(Witness::from_patterns(vec![])));
-            } else {
-                // Has rows, no columns - exhaustive
-                return Ok(None);
-            }
-        }
-
-        // Base case 2: Empty matrix with columns - not exhaustive
-        if matrix.is_empty() {
-            // Return a witness with wildcards for each column
-            let witness = Witness::from_patterns(
-                matrix
-                    .column_types
-                    .iter()
-                    .map(|_| WitnessPat::Wildcard)
-                    .collect(),
-            );
-            return Ok(Some(witness));
-        }
-
-        // Get constructors for the first column type
-        let first_type = matrix.first_column_type().unwrap();
-        let all_constructors = self.constructors_for_type(first_type);
-
-        // Get constructors that appear in the first column
-        let used_constructors = self.used_constructors(matrix);
-
-        // Check if we have a complete signature (all constructors covered)
-        let is_complete = self.is_complete_signature(&all_constructors, &used_constructors);
-
-        if is_complete {
-            // Complete signature: specialize on each constructor
-            for ctor in &all_constructors {
-                let specialized = self.specialize_matrix(matrix, ctor)?;
-                if let Some(witness) = self.check_exhaustive(&specialized)? {
-                    // Found a gap - reconstruct witness with this constructor
-                    return Ok(Some(self.reconstruct_witness(ctor, witness)));
-                }
-            }
-            // All constructors exhaustive
-            Ok(None)
-        } else {
-            // Incomplete signature: check default matrix
-            let default = self.default_matrix(matrix)?;
-            if let Some(witness) = self.check_exhaustive(&default)? {
-                // Found a gap - find a missing constructor
-                let missing = self.find_missing_constructor(&all_constructors, &used_constructors);
-                return Ok(Some(self.add_missing_constructor(missing, witness)));
-            }
-            Ok(None)
-        }
+        let rows: Vec<Row> = matrix
+            .rows
+            .iter()
+            .map(|r| r.patterns.iter().collect())
+            .collect();
+        let query: Row = vec![&WILDCARD; matrix.num_columns()];
+        self.useful(&rows, &matrix.column_types, &query)
     }
 
     /// Check which arms are redundant (unreachable).
@@ -373,110 +354,278 @@ impl<'a> ExhaustivenessChecker<'a> {
         &mut self,
         matrix: &PatternMatrix,
     ) -> Result<Vec<usize>, ExhaustivenessError> {
+        let rows: Vec<Row> = matrix
+            .rows
+            .iter()
+            .map(|r| r.patterns.iter().collect())
+            .collect();
         let mut redundant = vec![];
 
-        // Check each row to see if it's useful against preceding rows
-        for i in 0..matrix.rows.len() {
-            // Build a matrix of all preceding rows
-            let preceding: Vec<PatternRow> = matrix.rows[..i].to_vec();
-            let preceding_matrix = PatternMatrix::with_rows(preceding, matrix.column_types.clone());
-
-            // Get the current row as a single-row matrix
-            let current_row = matrix.rows[i].clone();
-
-            // Check if this row is useful (would match something not already covered)
-            if !self.is_useful(&preceding_matrix, &current_row)? {
-                redundant.push(matrix.rows[i].arm_index);
+        // A row is redundant if it matches nothing the preceding rows miss
+        for (i, row) in matrix.rows.iter().enumerate() {
+            if self
+                .useful(&rows[..i], &matrix.column_types, &rows[i])?
+                .is_none()
+            {
+                redundant.push(row.arm_index);
             }
         }
 
         Ok(redundant)
     }
 
-    /// Check if a row is useful against a matrix (would match something new).
-    fn is_useful(
+    /// Usefulness of `row` against `rows`. Returns a witness value matched by
+    /// `row` but by none of `rows`, or None if `row` adds nothing.
+    fn useful<'p>(
         &mut self,
-        matrix: &PatternMatrix,
-        row: &PatternRow,
-    ) -> Result<bool, ExhaustivenessError> {
-        // DoS protection
-        if matrix.size() > MAX_PATTERN_MATRIX_SIZE {
+        rows: &[Row<'p>],
+        tys: &[Ty],
+        row: &[&'p SimplifiedPat],
+    ) -> Result<Option<Witness>, ExhaustivenessError> {
+        // DoS protection: check matrix size
+        let size = rows.len() * tys.len().max(1);
+        if size > self.max_matrix_size {
             return Err(ExhaustivenessError::MatrixTooLarge {
-                size: matrix.size(),
+                size,
                 span: self.span,
             });
         }
 
-        if self.depth > MAX_EXHAUSTIVENESS_DEPTH {
+        // DoS protection: check recursion depth
+        if self.depth > self.max_depth {
             return Err(ExhaustivenessError::DepthExceeded { span: self.span });
         }
 
         self.depth += 1;
-        let result = self.is_useful_inner(matrix, row);
+        let result = self.useful_inner(rows, tys, row);
         self.depth -= 1;
         result
     }
 
-    fn is_useful_inner(
+    fn useful_inner<'p>(
         &mut self,
-        matrix: &PatternMatrix,
-        row: &PatternRow,
-    ) -> Result<bool, ExhaustivenessError> {
-        // Base case: No columns - useful iff matrix is empty
-        if matrix.num_columns() == 0 || row.patterns.is_empty() {
-            return Ok(matrix.is_empty());
+        rows: &[Row<'p>],
+        tys: &[Ty],
+        row: &[&'p SimplifiedPat],
+    ) -> Result<Option<Witness>, ExhaustivenessError> {
+        // Base case: no columns - useful iff there are no rows
+        if tys.is_empty() || row.is_empty() {
+            return Ok(rows.is_empty().then(|| Witness::from_patterns(vec![])));
+        }
+
+        // A row of only wildcards covers everything
+        if rows
+            .iter()
+            .any(|r| r.iter().all(|p| matches!(p, SimplifiedPat::Wildcard)))
+        {
+            return Ok(None);
         }
 
-        let first_pat = row.first().unwrap();
-        let first_type = matrix.first_column_type().unwrap();
+        // No rows: the row itself is an uncovered case
+        if rows.is_empty() {
+            return Ok(Some(Witness::from_patterns(
+                row.iter().map(|p| witness_of(p)).collect(),
+            )));
+        }
+
+        let first_type = &tys[0];
+
+        // Int columns: split the domain into intervals instead of constructors
+        if is_int_column(first_type, rows, row) {
+            return self.useful_int(rows, tys, row);
+        }
 
-        match first_pat {
+        // String columns: literals and prefixes cover sets of strings
+        if is_str_column(first_type, rows, row) {
+            return self.useful_str(rows, tys, row);
+        }
+
+        match row[0] {
             SimplifiedPat::Wildcard => {
-                // Wildcard: need to check if useful for any constructor
                 let all_constructors = self.constructors_for_type(first_type);
-                let used_constructors = self.used_constructors(matrix);
-
-                if self.is_complete_signature(&all_constructors, &used_constructors) {
-                    // Complete signature: check each constructor
-                    for ctor in &all_constructors {
-                        let specialized_matrix = self.specialize_matrix(matrix, ctor)?;
-                        let specialized_row = self.specialize_row(row, ctor);
-                        if self.is_useful(&specialized_matrix, &specialized_row)? {
-                            return Ok(true);
+                let used_constructors = used_constructors(rows);
+
+                if is_complete_signature(&all_constructors, &used_constructors) {
+                    // Complete signature: specialize on each constructor
+                    let buckets = specialize_all(rows, &all_constructors);
+                    for (ctor, specialized) in all_constructors.iter().zip(buckets) {
+                        let specialized_tys = specialize_types(tys, ctor);
+                        let specialized_row = specialize_row(row, ctor);
+                        if let Some(witness) =
+                            self.useful(&specialized, &specialized_tys, &specialized_row)?
+                        {
+                            // Found a gap - reconstruct witness with this constructor
+                            return Ok(Some(reconstruct_witness(ctor, witness)));
                         }
                     }
-                    Ok(false)
+                    Ok(None)
                 } else {
                     // Incomplete signature: check default matrix
-                    let default = self.default_matrix(matrix)?;
-                    let default_row = self.default_row(row);
-                    self.is_useful(&default, &default_row)
+                    let default = default_rows(rows);
+                    match self.useful(&default, &tys[1..], &row[1..])? {
+                        Some(witness) => {
+                            let missing =
+                                find_missing_constructor(&all_constructors, &used_constructors);
+                            Ok(Some(add_missing_constructor(missing, witness)))
+                        }
+                        None => Ok(None),
+                    }
                 }
             }
 
             SimplifiedPat::Constructor { name, args } => {
-                // Constructor: specialize matrix and row on this constructor
                 // Look up the constructor from the type to get proper arg_types
                 let ctor = self
                     .lookup_constructor(first_type, name, args.len())
                     .unwrap_or_else(|| Constructor::new(name.clone(), args.len()));
-                let specialized_matrix = self.specialize_matrix(matrix, &ctor)?;
-                let specialized_row = self.specialize_row(row, &ctor);
-                self.is_useful(&specialized_matrix, &specialized_row)
+                self.useful_specialized(rows, tys, row, &ctor)
             }
 
+            // Int-valued heads are always routed above; kept for totality
+            SimplifiedPat::Range { .. } => self.useful_int(rows, tys, row),
+
             SimplifiedPat::Literal(lit) => {
                 // Literal: treat as a nullary constructor
-                let ctor = Constructor::new(format!("{}", lit), 0);
-                let specialized_matrix = self.specialize_matrix(matrix, &ctor)?;
-                let specialized_row = self.specialize_row(row, &ctor);
-                self.is_useful(&specialized_matrix, &specialized_row)
+                let ctor = Constructor::new(lit.to_string(), 0);
+                self.useful_specialized(rows, tys, row, &ctor)
             }
         }
     }
 
-    /// Get all constructors for a type
-    fn constructors_for_type(&self, ty: &Ty) -> Vec<Constructor> {
+    /// Usefulness for an Int column using interval analysis.
+    ///
+    /// The head of the candidate row covers an interval `q` (all of i64 for a
+    /// wildcard). The boundaries of every head interval in the matrix split
+    /// `q` into segments such that each matrix head either contains a segment
+    /// or is disjoint from it. The row is useful iff it is useful on some
+    /// segment against the rows whose head contains that segment. Segments no
+    /// head touches share one residual matrix (the wildcard rows), which is
+    /// checked once.
+    fn useful_int<'p>(
+        &mut self,
+        rows: &[Row<'p>],
+        tys: &[Ty],
+        row: &[&'p SimplifiedPat],
+    ) -> Result<Option<Witness>, ExhaustivenessError> {
+        let (q_lo, q_hi) = int_range(row[0]).unwrap_or((i64::MIN, i64::MAX));
+
+        // Head intervals overlapping q, sorted by lower bound
+        let mut heads: Vec<(i64, i64, &Row<'p>)> = rows
+            .iter()
+            .filter_map(|r| int_range(r[0]).map(|(lo, hi)| (lo, hi, r)))
+            .filter(|&(lo, hi, _)| lo <= q_hi && hi >= q_lo)
+            .collect();
+        heads.sort_by_key(|&(lo, _, _)| lo);
+        let wildcard_rows: Vec<Row<'p>> = default_rows(rows);
+
+        // Segment start points within q
+        let mut cuts: Vec<i64> = vec![q_lo];
+        for &(lo, hi, _) in &heads {
+            if lo > q_lo {
+                cuts.push(lo);
+            }
+            if hi < q_hi {
+                cuts.push(hi + 1);
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        let mut uncovered: Option<Option<Witness>> = None;
+        let mut next_head = 0;
+        let mut active: Vec<(i64, i64, &Row<'p>)> = vec![];
+
+        for (i, &seg_lo) in cuts.iter().enumerate() {
+            let seg_hi = cuts.get(i + 1).map(|c| c - 1).unwrap_or(q_hi);
+
+            // Heads containing this segment: started at or before it, not yet ended
+            while next_head < heads.len() && heads[next_head].0 <= seg_lo {
+                active.push(heads[next_head]);
+                next_head += 1;
+            }
+            active.retain(|&(_, hi, _)| hi >= seg_lo);
+
+            let witness = if active.is_empty() {
+                if uncovered.is_none() {
+                    uncovered = Some(self.useful(&wildcard_rows, &tys[1..], &row[1..])?);
+                }
+                uncovered.clone().flatten()
+            } else {
+                let mut specialized: Vec<Row<'p>> =
+                    active.iter().map(|&(_, _, r)| r[1..].to_vec()).collect();
+                specialized.extend(wildcard_rows.iter().cloned());
+                self.useful(&specialized, &tys[1..], &row[1..])?
+            };
+
+            if let Some(witness) = witness {
+                let mut patterns = vec![range_witness(seg_lo, seg_hi)];
+                patterns.extend(witness.patterns);
+                return Ok(Some(Witness::from_patterns(patterns)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Usefulness for a String column.
+    ///
+    /// A literal head covers one string, a prefix head every string that
+    /// starts with it, and a wildcard every string. Only finitely many heads
+    /// single out strings, so the candidate head always matches some string
+    /// that no narrower matrix head does. The rows matching that string are
+    /// exactly those whose head covers the candidate's whole set, and they
+    /// also match every other string of the set, so the row is useful iff it
+    /// is useful against them.
+    fn useful_str<'p>(
+        &mut self,
+        rows: &[Row<'p>],
+        tys: &[Ty],
+        row: &[&'p SimplifiedPat],
+    ) -> Result<Option<Witness>, ExhaustivenessError> {
+        let covering: Vec<Row<'p>> = rows
+            .iter()
+            .filter(|r| str_head_covers(r[0], row[0]))
+            .map(|r| r[1..].to_vec())
+            .collect();
+        Ok(self
+            .useful(&covering, &tys[1..], &row[1..])?
+            .map(|witness| {
+                let mut patterns = vec![witness_of(row[0])];
+                patterns.extend(witness.patterns);
+                Witness::from_patterns(patterns)
+            }))
+    }
+
+    /// Usefulness after specializing both the matrix and the row on `ctor`.
+    fn useful_specialized<'p>(
+        &mut self,
+        rows: &[Row<'p>],
+        tys: &[Ty],
+        row: &[&'p SimplifiedPat],
+        ctor: &Constructor,
+    ) -> Result<Option<Witness>, ExhaustivenessError> {
+        let specialized: Vec<Row> = rows
+            .iter()
+            .filter_map(|r| specialize_row_on_ctor(r, ctor))
+            .collect();
+        let specialized_tys = specialize_types(tys, ctor);
+        let specialized_row = specialize_row(row, ctor);
+        Ok(self
+            .useful(&specialized, &specialized_tys, &specialized_row)?
+            .map(|witness| reconstruct_witness(ctor, witness)))
+    }
+
+    /// Get all constructors for a type (cached per type)
+    fn constructors_for_type(&mut self, ty: &Ty) -> Rc<Vec<Constructor>> {
+        if let Some(ctors) = self.ctor_cache.get(ty) {
+            return Rc::clone(ctors);
+        }
+        let ctors = Rc::new(self.compute_constructors(ty));
+        self.ctor_cache.insert(ty.clone(), Rc::clone(&ctors));
+        ctors
+    }
+
+    fn compute_constructors(&self, ty: &Ty) -> Vec<Constructor> {
         match ty {
             Ty::Const(c) => {
                 use crate::infer::ty::TyConst;
@@ -505,7 +654,7 @@ impl<'a> ExhaustivenessChecker<'a> {
                                             crate::adt::VariantFields::Tuple(tys) => {
                                                 // Substitute type parameters
                                                 tys.iter()
-                                                    .map(|t| self.substitute_type_args(t, args))
+                                                    .map(|t| substitute_type_args(t, args))
                                                     .collect()
                                             }
                                         };
@@ -521,7 +670,7 @@ impl<'a> ExhaustivenessChecker<'a> {
                             .map(|fields| {
                                 fields
                                     .iter()
-                                    .map(|f| self.substitute_type_args(&f.ty, args))
+                                    .map(|f| substitute_type_args(&f.ty, args))
                                     .collect()
                             })
                             .unwrap_or_default();
@@ -539,6 +688,15 @@ impl<'a> ExhaustivenessChecker<'a> {
                 vec![Constructor::with_arg_types(name, tys.clone())]
             }
 
+            // Record: single constructor over every field of the type
+            Ty::Record(fields, _) => {
+                let name = record_ctor_name(fields.keys());
+                vec![Constructor::with_arg_types(
+                    name,
+                    fields.values().cloned().collect(),
+                )]
+            }
+
             // Type variables, Never, Arrow, List - no known constructors
             _ => vec![],
         }
@@ -546,295 +704,345 @@ impl<'a> ExhaustivenessChecker<'a> {
 
     /// Look up a constructor by name from a type's constructors.
     /// Returns the constructor with proper arg_types populated.
-    fn lookup_constructor(&self, ty: &Ty, name: &str, arity: usize) -> Option<Constructor> {
-        let all_ctors = self.constructors_for_type(ty);
-        all_ctors
-            .into_iter()
-            .find(|c| c.name == name && c.arity == arity)
-    }
-
-    /// Substitute type arguments in a type
-    #[allow(clippy::only_used_in_recursion)]
-    fn substitute_type_args(&self, ty: &Ty, args: &[Ty]) -> Ty {
-        match ty {
-            Ty::Var(v) => {
-                // Type variable: substitute if in range
-                let idx = v.0 as usize;
-                if idx < args.len() {
-                    args[idx].clone()
-                } else {
-                    ty.clone()
-                }
-            }
-            Ty::Arrow(params, ret, eff) => Ty::arrow_eff(
-                params
-                    .iter()
-                    .map(|t| self.substitute_type_args(t, args))
-                    .collect(),
-                self.substitute_type_args(ret, args),
-                *eff,
-            ),
-            Ty::Tuple(tys) => Ty::Tuple(
-                tys.iter()
-                    .map(|t| self.substitute_type_args(t, args))
-                    .collect(),
-            ),
-            Ty::List(t) => Ty::List(Box::new(self.substitute_type_args(t, args))),
-            Ty::Adt {
-                name,
-                args: inner_args,
-            } => Ty::Adt {
-                name: name.clone(),
-                args: inner_args
-                    .iter()
-                    .map(|t| self.substitute_type_args(t, args))
-                    .collect(),
-            },
-            _ => ty.clone(),
-        }
+    /// A record pattern may name fewer fields than the record has.
+    fn lookup_constructor(&mut self, ty: &Ty, name: &str, arity: usize) -> Option<Constructor> {
+        self.constructors_for_type(ty)
+            .iter()
+            .find(|c| {
+                ctor_key(&c.name) == ctor_key(name) && (c.arity == arity || is_record_ctor(name))
+            })
+            .cloned()
     }
+}
 
-    /// Get constructors used in the first column of the matrix
-    fn used_constructors(&self, matrix: &PatternMatrix) -> HashSet<String> {
-        let mut used = HashSet::new();
-        for row in &matrix.rows {
-            if let Some(pat) = row.first() {
-                match pat {
-                    SimplifiedPat::Constructor { name, .. } => {
-                        used.insert(name.clone());
-                    }
-                    SimplifiedPat::Literal(lit) => {
-                        used.insert(format!("{}", lit));
-                    }
-                    SimplifiedPat::Wildcard => {}
-                }
+/// Substitute type arguments in a type
+fn substitute_type_args(ty: &Ty, args: &[Ty]) -> Ty {
+    match ty {
+        Ty::Var(v) => {
+            // Type variable: substitute if in range
+            let idx = v.0 as usize;
+            if idx < args.len() {
+                args[idx].clone()
+            } else {
+                ty.clone()
             }
         }
-        used
+        Ty::Arrow(params, ret, eff) => Ty::arrow_eff(
+            params
+                .iter()
+                .map(|t| substitute_type_args(t, args))
+                .collect(),
+            substitute_type_args(ret, args),
+            *eff,
+        ),
+        Ty::Tuple(tys) => Ty::Tuple(tys.iter().map(|t| substitute_type_args(t, args)).collect()),
+        Ty::Record(fields, tail) => Ty::Record(
+            fields
+                .iter()
+                .map(|(name, t)| (name.clone(), substitute_type_args(t, args)))
+                .collect(),
+            *tail,
+        ),
+        Ty::List(t) => Ty::List(Box::new(substitute_type_args(t, args))),
+        Ty::Adt {
+            name,
+            args: inner_args,
+        } => Ty::Adt {
+            name: name.clone(),
+            args: inner_args
+                .iter()
+                .map(|t| substitute_type_args(t, args))
+                .collect(),
+        },
+        _ => ty.clone(),
     }
+}
 
-    /// Check if the used constructors form a complete signature
-    fn is_complete_signature(&self, all: &[Constructor], used: &HashSet<String>) -> bool {
-        // If all constructors is empty, signature is incomplete (infinite type)
-        if all.is_empty() {
-            return false;
-        }
-        // Complete if all constructors are used
-        all.iter().all(|c| used.contains(&c.name))
+/// Inclusive interval covered by an Int literal or range pattern
+fn int_range(pat: &SimplifiedPat) -> Option<(i64, i64)> {
+    match pat {
+        SimplifiedPat::Literal(LiteralPat::Int(n)) => Some((*n, *n)),
+        SimplifiedPat::Range { lo, hi } => Some((*lo, *hi)),
+        _ => None,
     }
+}
 
-    /// Find a constructor not in the used set
-    fn find_missing_constructor(
-        &self,
-        all: &[Constructor],
-        used: &HashSet<String>,
-    ) -> Option<Constructor> {
-        all.iter().find(|c| !used.contains(&c.name)).cloned()
-    }
+/// Whether the first column should be checked with interval analysis
+fn is_int_column(ty: &Ty, rows: &[Row], row: &[&SimplifiedPat]) -> bool {
+    *ty == Ty::int()
+        || int_range(row[0]).is_some()
+        || rows.iter().any(|r| int_range(r[0]).is_some())
+}
 
-    /// Specialize a matrix on a constructor.
-    /// Keeps only rows where the first pattern matches the constructor,
-    /// and expands the constructor's arguments.
-    fn specialize_matrix(
-        &self,
-        matrix: &PatternMatrix,
-        ctor: &Constructor,
-    ) -> Result<PatternMatrix, ExhaustivenessError> {
-        let mut new_rows = vec![];
+/// Whether a pattern is a String literal or prefix
+fn is_str_head(pat: &SimplifiedPat) -> bool {
+    matches!(
+        pat,
+        SimplifiedPat::Literal(LiteralPat::String(_) | LiteralPat::StrPrefix(_))
+    )
+}
 
-        for row in &matrix.rows {
-            if let Some(specialized) = self.specialize_row_on_ctor(row, ctor) {
-                new_rows.push(specialized);
-            }
-        }
+/// Whether the first column should be checked by string prefixes
+fn is_str_column(ty: &Ty, rows: &[Row], row: &[&SimplifiedPat]) -> bool {
+    *ty == Ty::string() || is_str_head(row[0]) || rows.iter().any(|r| is_str_head(r[0]))
+}
 
-        // New column types: constructor's arg types + remaining original types
-        let mut new_column_types = ctor.arg_types.clone();
-        if matrix.column_types.len() > 1 {
-            new_column_types.extend(matrix.column_types[1..].to_vec());
+/// Whether String pattern `head` matches every string `pat` does
+fn str_head_covers(head: &SimplifiedPat, pat: &SimplifiedPat) -> bool {
+    use LiteralPat::{StrPrefix, String as Str};
+    match (head, pat) {
+        (SimplifiedPat::Wildcard, _) => true,
+        (SimplifiedPat::Literal(Str(h)), SimplifiedPat::Literal(Str(s))) => h == s,
+        (SimplifiedPat::Literal(StrPrefix(h)), SimplifiedPat::Literal(Str(s) | StrPrefix(s))) => {
+            s.starts_with(h.as_str())
         }
-
-        Ok(PatternMatrix::with_rows(new_rows, new_column_types))
+        _ => false,
     }
+}
 
-    /// Specialize a single row on a constructor
-    fn specialize_row_on_ctor(&self, row: &PatternRow, ctor: &Constructor) -> Option<PatternRow> {
-        let first = row.first()?;
-
-        match first {
-            SimplifiedPat::Wildcard => {
-                // Wildcard matches any constructor: expand with wildcards
-                let mut new_patterns: Vec<SimplifiedPat> =
-                    (0..ctor.arity).map(|_| SimplifiedPat::Wildcard).collect();
-                new_patterns.extend(row.rest().to_vec());
-                Some(PatternRow::new(new_patterns, row.arm_index))
-            }
-
-            SimplifiedPat::Constructor { name, args } if name == &ctor.name => {
-                // Matching constructor: expand args
-                let mut new_patterns = args.clone();
-                new_patterns.extend(row.rest().to_vec());
-                Some(PatternRow::new(new_patterns, row.arm_index))
-            }
-
-            SimplifiedPat::Literal(lit) if format!("{}", lit) == ctor.name => {
-                // Matching literal: no args to expand
-                let new_patterns = row.rest().to_vec();
-                Some(PatternRow::new(new_patterns, row.arm_index))
-            }
-
-            _ => {
-                // Non-matching constructor
-                None
-            }
-        }
+/// Source form of an inclusive interval, e.g. `..=-1`, `3`, `5..=9`, `10..`
+fn format_range(lo: i64, hi: i64) -> String {
+    match (lo, hi) {
+        (i64::MIN, i64::MAX) => "_".to_string(),
+        _ if lo == hi => lo.to_string(),
+        (i64::MIN, _) => format!("..={}", hi),
+        (_, i64::MAX) => format!("{}..", lo),
+        _ => format!("{}..={}", lo, hi),
     }
+}
 
-    /// Specialize a row for usefulness checking
-    fn specialize_row(&self, row: &PatternRow, ctor: &Constructor) -> PatternRow {
-        let first = row.first();
-
-        match first {
-            Some(SimplifiedPat::Wildcard) => {
-                // Expand wildcard with wildcards for constructor args
-                let mut new_patterns: Vec<SimplifiedPat> =
-                    (0..ctor.arity).map(|_| SimplifiedPat::Wildcard).collect();
-                new_patterns.extend(row.rest().to_vec());
-                PatternRow::new(new_patterns, row.arm_index)
-            }
+/// Witness pattern for an uncovered interval
+fn range_witness(lo: i64, hi: i64) -> WitnessPat {
+    if (lo, hi) == (i64::MIN, i64::MAX) {
+        WitnessPat::Wildcard
+    } else {
+        WitnessPat::Literal(format_range(lo, hi))
+    }
+}
 
-            Some(SimplifiedPat::Constructor { args, .. }) => {
-                // Use the constructor's args
-                let mut new_patterns = args.clone();
-                new_patterns.extend(row.rest().to_vec());
-                PatternRow::new(new_patterns, row.arm_index)
-            }
+/// Constructor name at the head of a pattern (None for wildcards)
+fn head_name(pat: &SimplifiedPat) -> Option<Cow<'_, str>> {
+    match pat {
+        SimplifiedPat::Wildcard => None,
+        SimplifiedPat::Constructor { name, .. } => Some(Cow::Borrowed(ctor_key(name))),
+        SimplifiedPat::Literal(LiteralPat::Bool(true)) => Some(Cow::Borrowed("true")),
+        SimplifiedPat::Literal(LiteralPat::Bool(false)) => Some(Cow::Borrowed("false")),
+        SimplifiedPat::Literal(lit) => Some(Cow::Owned(lit.to_string())),
+        SimplifiedPat::Range { lo, hi } => Some(Cow::Owned(format_range(*lo, *hi))),
+    }
+}
 
-            Some(SimplifiedPat::Literal(_)) => {
-                // Literal has no args
-                PatternRow::new(row.rest().to_vec(), row.arm_index)
-            }
+/// Get constructors used in the first column of the matrix
+fn used_constructors(rows: &[Row]) -> HashSet<String> {
+    rows.iter()
+        .filter_map(|row| row.first().and_then(|p| head_name(p)))
+        .map(Cow::into_owned)
+        .collect()
+}
 
-            None => PatternRow::new(vec![], row.arm_index),
-        }
+/// Check if the used constructors form a complete signature
+fn is_complete_signature(all: &[Constructor], used: &HashSet<String>) -> bool {
+    // If all constructors is empty, signature is incomplete (infinite type)
+    if all.is_empty() {
+        return false;
     }
+    // Complete if all constructors are used
+    all.iter().all(|c| used.contains(ctor_key(&c.name)))
+}
 
-    /// Build the default matrix (rows that start with wildcard).
-    /// Removes the first column.
-    fn default_matrix(&self, matrix: &PatternMatrix) -> Result<PatternMatrix, ExhaustivenessError> {
-        let mut new_rows = vec![];
+/// Find a constructor not in the used set
+fn find_missing_constructor(all: &[Constructor], used: &HashSet<String>) -> Option<Constructor> {
+    all.iter()
+        .find(|c| !used.contains(ctor_key(&c.name)))
+        .cloned()
+}
 
-        for row in &matrix.rows {
-            if let Some(first) = row.first() {
-                if matches!(first, SimplifiedPat::Wildcard) {
-                    new_rows.push(PatternRow::new(row.rest().to_vec(), row.arm_index));
+/// Specialize the matrix on every constructor of a complete signature in a
+/// single pass. Returns one row bucket per constructor, in `all` order.
+fn specialize_all<'p>(rows: &[Row<'p>], all: &[Constructor]) -> Vec<Vec<Row<'p>>> {
+    let index: HashMap<&str, usize> = all
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (ctor_key(&c.name), i))
+        .collect();
+    let mut buckets: Vec<Vec<Row>> = vec![vec![]; all.len()];
+
+    for row in rows {
+        match row.first().and_then(|p| head_name(p)) {
+            // Wildcard matches every constructor
+            None => {
+                for (ctor, bucket) in all.iter().zip(buckets.iter_mut()) {
+                    bucket.push(specialize_row(row, ctor));
+                }
+            }
+            Some(name) => {
+                if let Some(&i) = index.get(name.as_ref()) {
+                    buckets[i].push(specialize_row(row, &all[i]));
                 }
             }
         }
+    }
 
-        // Remove first column type
-        let new_column_types = if matrix.column_types.len() > 1 {
-            matrix.column_types[1..].to_vec()
-        } else {
-            vec![]
-        };
+    buckets
+}
 
-        Ok(PatternMatrix::with_rows(new_rows, new_column_types))
+/// Specialize a single matrix row on a constructor; None if it doesn't match
+fn specialize_row_on_ctor<'p>(row: &[&'p SimplifiedPat], ctor: &Constructor) -> Option<Row<'p>> {
+    match head_name(row.first()?) {
+        Some(name) if name != ctor_key(&ctor.name) => None,
+        _ => Some(specialize_row(row, ctor)),
     }
+}
 
-    /// Build default row for usefulness checking
-    fn default_row(&self, row: &PatternRow) -> PatternRow {
-        PatternRow::new(row.rest().to_vec(), row.arm_index)
+/// Specialize a row whose head is known to match `ctor`
+fn specialize_row<'p>(row: &[&'p SimplifiedPat], ctor: &Constructor) -> Row<'p> {
+    let mut new_patterns: Row = match row.first() {
+        // Expand wildcard with wildcards for constructor args
+        Some(SimplifiedPat::Wildcard) => vec![&WILDCARD; ctor.arity],
+        // Line a record pattern's fields up with the record type's; fields
+        // the pattern leaves out match anything
+        Some(SimplifiedPat::Constructor { name, args }) if is_record_ctor(name) => {
+            let named: Vec<&str> = record_fields(name).collect();
+            record_fields(&ctor.name)
+                .map(|field| match named.iter().position(|n| *n == field) {
+                    Some(i) => &args[i],
+                    None => &WILDCARD,
+                })
+                .collect()
+        }
+        Some(SimplifiedPat::Constructor { args, .. }) => args.iter().collect(),
+        Some(SimplifiedPat::Literal(_)) | Some(SimplifiedPat::Range { .. }) | None => vec![],
+    };
+    if row.len() > 1 {
+        new_patterns.extend_from_slice(&row[1..]);
     }
+    new_patterns
+}
 
-    /// Reconstruct a witness by prepending a constructor
-    fn reconstruct_witness(&self, ctor: &Constructor, inner: Witness) -> Witness {
-        let (ctor_args, rest): (Vec<_>, Vec<_>) = inner
-            .patterns
-            .into_iter()
-            .enumerate()
-            .partition(|(i, _)| *i < ctor.arity);
-
-        let args: Vec<WitnessPat> = ctor_args.into_iter().map(|(_, p)| p).collect();
-        let rest: Vec<WitnessPat> = rest.into_iter().map(|(_, p)| p).collect();
-
-        let ctor_pat = WitnessPat::Constructor {
-            name: ctor.name.clone(),
-            args,
-        };
+/// Column types after specializing on a constructor
+fn specialize_types(tys: &[Ty], ctor: &Constructor) -> Vec<Ty> {
+    let mut new_tys = ctor.arg_types.clone();
+    // Constructors without known arg types still expand to `arity` columns
+    while new_tys.len() < ctor.arity {
+        new_tys.push(Ty::unit());
+    }
+    if tys.len() > 1 {
+        new_tys.extend_from_slice(&tys[1..]);
+    }
+    new_tys
+}
 
-        let mut patterns = vec![ctor_pat];
-        patterns.extend(rest);
+/// Build the default matrix (rows that start with wildcard), first column removed
+fn default_rows<'p>(rows: &[Row<'p>]) -> Vec<Row<'p>> {
+    rows.iter()
+        .filter(|row| matches!(row.first(), Some(SimplifiedPat::Wildcard)))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
 
-        Witness::from_patterns(patterns)
+/// Witness pattern for a row pattern that no matrix row covers
+fn witness_of(pat: &SimplifiedPat) -> WitnessPat {
+    match pat {
+        SimplifiedPat::Wildcard => WitnessPat::Wildcard,
+        SimplifiedPat::Constructor { name, args } => WitnessPat::Constructor {
+            name: name.clone(),
+            args: args.iter().map(witness_of).collect(),
+        },
+        SimplifiedPat::Literal(lit) => WitnessPat::Literal(lit.to_string()),
+        SimplifiedPat::Range { lo, hi } => range_witness(*lo, *hi),
     }
+}
 
-    /// Add a missing constructor to a witness
-    fn add_missing_constructor(&self, missing: Option<Constructor>, inner: Witness) -> Witness {
-        match missing {
-            Some(ctor) => {
-                let ctor_pat = WitnessPat::Constructor {
-                    name: ctor.name.clone(),
-                    args: (0..ctor.arity).map(|_| WitnessPat::Wildcard).collect(),
-                };
-
-                let mut patterns = vec![ctor_pat];
-                patterns.extend(inner.patterns);
-                Witness::from_patterns(patterns)
-            }
-            None => {
-                // No known missing constructor - use wildcard
-                let mut patterns = vec![WitnessPat::Wildcard];
-                patterns.extend(inner.patterns);
-                Witness::from_patterns(patterns)
-            }
-        }
-    }
+/// Reconstruct a witness by prepending a constructor
+fn reconstruct_witness(ctor: &Constructor, inner: Witness) -> Witness {
+    let mut patterns = inner.patterns;
+    let split = ctor.arity.min(patterns.len());
+    let rest = patterns.split_off(split);
+
+    let mut result = vec![WitnessPat::Constructor {
+        name: ctor.name.clone(),
+        args: patterns,
+    }];
+    result.extend(rest);
+    Witness::from_patterns(result)
+}
+
+/// Add a missing constructor to a witness
+fn add_missing_constructor(missing: Option<Constructor>, inner: Witness) -> Witness {
+    let head = match missing {
+        Some(ctor) => WitnessPat::Constructor {
+            name: ctor.name,
+            args: vec![WitnessPat::Wildcard; ctor.arity],
+        },
+        // No known missing constructor - use wildcard
+        None => WitnessPat::Wildcard,
+    };
+    let mut patterns = vec![head];
+    patterns.extend(inner.patterns);
+    Witness::from_patterns(patterns)
 }
 
 /// Convert an AST pattern to a SimplifiedPat for exhaustiveness checking.
-/// This requires the resolved scrutinee type to properly handle variant patterns.
-/// Note: registry is passed through for potential future use in complex pattern simplification.
-#[allow(clippy::only_used_in_recursion)]
-pub fn simplify_pattern(pat: &strata_ast::ast::Pat, registry: &AdtRegistry) -> SimplifiedPat {
+/// The registry is used to put struct field sub-patterns in definition order;
+/// identifiers naming one of `consts` are literals.
+pub fn simplify_pattern(
+    pat: &strata_ast::ast::Pat,
+    registry: &AdtRegistry,
+    consts: &ConstTable,
+) -> SimplifiedPat {
     use strata_ast::ast::Pat;
 
     match pat {
         Pat::Wildcard(_) => SimplifiedPat::Wildcard,
 
-        Pat::Ident(_) => {
+        Pat::Ident(ident) => match consts.get(&ident.text) {
+            Some(value) => simplify_literal(&value.to_lit()),
             // Variable bindings act as wildcards for exhaustiveness
-            SimplifiedPat::Wildcard
-        }
+            None => SimplifiedPat::Wildcard,
+        },
 
-        Pat::Literal(lit, _) => {
-            use strata_ast::ast::Lit;
-            let lit_pat = match lit {
-                Lit::Int(n) => LiteralPat::Int(*n),
-                Lit::Bool(b) => LiteralPat::Bool(*b),
-                Lit::Str(s) => LiteralPat::String(s.clone()),
-                Lit::Float(_) => {
-                    // Floats are tricky for pattern matching - treat as wildcard
-                    return SimplifiedPat::Wildcard;
-                }
-                Lit::Nil => {
-                    // Nil matches Unit - treat as a unit constructor
-                    return SimplifiedPat::Constructor {
-                        name: "()".to_string(),
-                        args: vec![],
-                    };
-                }
-            };
-            SimplifiedPat::Literal(lit_pat)
-        }
+        Pat::Literal(lit, _) => simplify_literal(lit),
+
+        // Fold the remainder into the prefix: `"a" + "b"` is `"ab"` and
+        // `"a" + ("b" + r)` is `"ab" + r`
+        Pat::StrPrefix { prefix, rest, .. } => match simplify_pattern(rest, registry, consts) {
+            SimplifiedPat::Literal(LiteralPat::String(s)) => {
+                SimplifiedPat::Literal(LiteralPat::String(format!("{}{}", prefix, s)))
+            }
+            SimplifiedPat::Literal(LiteralPat::StrPrefix(s)) => {
+                SimplifiedPat::Literal(LiteralPat::StrPrefix(format!("{}{}", prefix, s)))
+            }
+            _ if prefix.is_empty() => SimplifiedPat::Wildcard,
+            _ => SimplifiedPat::Literal(LiteralPat::StrPrefix(prefix.clone())),
+        },
+
+        // Empty ranges are rejected during inference and never reach here
+        Pat::Range { .. } => match pat.range_bounds() {
+            Some((lo, hi)) => SimplifiedPat::Range { lo, hi },
+            None => SimplifiedPat::Wildcard,
+        },
 
         Pat::Tuple(pats, _) => {
-            let args: Vec<SimplifiedPat> =
-                pats.iter().map(|p| simplify_pattern(p, registry)).collect();
+            let args: Vec<SimplifiedPat> = pats
+                .iter()
+                .map(|p| simplify_pattern(p, registry, consts))
+                .collect();
             let name = format!("Tuple{}", args.len());
             SimplifiedPat::Constructor { name, args }
         }
 
+        // Named fields in sorted order, as the record type has them
+        Pat::Record { fields, .. } => {
+            let mut fields: Vec<_> = fields.iter().collect();
+            fields.sort_by(|a, b| a.name.text.cmp(&b.name.text));
+            let name = record_ctor_name(fields.iter().map(|f| &f.name.text));
+            let args = fields
+                .iter()
+                .map(|f| simplify_pattern(&f.pat, registry, consts))
+                .collect();
+            SimplifiedPat::Constructor { name, args }
+        }
+
         Pat::Variant { path, fields, .. } => {
             // Build fully qualified name
             let name = path
@@ -846,7 +1054,7 @@ pub fn simplify_pattern(pat: &strata_ast::ast::Pat, registry: &AdtRegistry) -> S
 
             let args: Vec<SimplifiedPat> = fields
                 .iter()
-                .map(|p| simplify_pattern(p, registry))
+                .map(|p| simplify_pattern(p, registry, consts))
                 .collect();
 
             SimplifiedPat::Constructor { name, args }
@@ -861,29 +1069,64 @@ pub fn simplify_pattern(pat: &strata_ast::ast::Pat, registry: &AdtRegistry) -> S
                 .collect::<Vec<_>>()
                 .join("::");
 
-            // For struct patterns, we need to order fields consistently
-            // For simplicity, convert field patterns in order
-            let args: Vec<SimplifiedPat> = fields
-                .iter()
-                .map(|f| simplify_pattern(&f.pat, registry))
-                .collect();
+            // Order sub-patterns by the struct definition so they line up with
+            // the constructor's argument columns. Omitted fields (partial
+            // patterns are allowed) match anything.
+            let args: Vec<SimplifiedPat> = match registry.get(&name).and_then(|d| d.fields()) {
+                Some(defs) => defs
+                    .iter()
+                    .map(|def| {
+                        fields
+                            .iter()
+                            .find(|f| f.name.text == def.name)
+                            .map(|f| simplify_pattern(&f.pat, registry, consts))
+                            .unwrap_or(SimplifiedPat::Wildcard)
+                    })
+                    .collect(),
+                None => fields
+                    .iter()
+                    .map(|f| simplify_pattern(&f.pat, registry, consts))
+                    .collect(),
+            };
 
             SimplifiedPat::Constructor { name, args }
         }
     }
 }
 
+fn simplify_literal(lit: &strata_ast::ast::Lit) -> SimplifiedPat {
+    use strata_ast::ast::Lit;
+    let lit_pat = match lit {
+        Lit::Int(n) => LiteralPat::Int(*n),
+        Lit::Bool(b) => LiteralPat::Bool(*b),
+        Lit::Str(s) => LiteralPat::String(s.clone()),
+        Lit::Float(_) => {
+            // Floats are tricky for pattern matching - treat as wildcard
+            return SimplifiedPat::Wildcard;
+        }
+        Lit::Nil => {
+            // Nil matches Unit - treat as a unit constructor
+            return SimplifiedPat::Constructor {
+                name: "()".to_string(),
+                args: vec![],
+            };
+        }
+    };
+    SimplifiedPat::Literal(lit_pat)
+}
+
 /// Build a PatternMatrix from match arms and the scrutinee type.
 pub fn build_pattern_matrix(
     arms: &[strata_ast::ast::MatchArm],
     scrutinee_ty: &Ty,
     registry: &AdtRegistry,
+    consts: &ConstTable,
 ) -> PatternMatrix {
     let column_types = vec![scrutinee_ty.clone()];
     let mut matrix = PatternMatrix::new(column_types);
 
     for (i, arm) in arms.iter().enumerate() {
-        let pat = simplify_pattern(&arm.pat, registry);
+        let pat = simplify_pattern(&arm.pat, registry, consts);
         matrix.add_row(PatternRow::new(vec![pat], i));
     }
 
@@ -896,10 +1139,12 @@ pub fn check_match(
     arms: &[strata_ast::ast::MatchArm],
     scrutinee_ty: &Ty,
     registry: &AdtRegistry,
+    consts: &ConstTable,
+    limits: &Limits,
     span: Span,
 ) -> Result<(Option<Witness>, Vec<usize>), ExhaustivenessError> {
-    let matrix = build_pattern_matrix(arms, scrutinee_ty, registry);
-    let mut checker = ExhaustivenessChecker::new(registry, span);
+    let matrix = build_pattern_matrix(arms, scrutinee_ty, registry, consts);
+    let mut checker = ExhaustivenessChecker::new(registry, span).with_limits(limits);
 
     let witness = checker.check_exhaustive(&matrix)?;
     let redundant = checker.check_redundant(&matrix)?;
@@ -907,6 +1152,138 @@ pub fn check_match(
     Ok((witness, redundant))
 }
 
+/// Every pattern the arms of a non-exhaustive match miss, as source text
+/// that can be pasted in as new arms. Empty if the match is exhaustive.
+///
+/// Witnesses are collected one at a time, adding each as a covered row
+/// before asking for the next. Literal sub-patterns (uncovered Int ranges,
+/// strings) are widened to `_` so the list stays finite.
+pub fn missing_patterns(
+    arms: &[strata_ast::ast::MatchArm],
+    scrutinee_ty: &Ty,
+    registry: &AdtRegistry,
+    consts: &ConstTable,
+    limits: &Limits,
+    span: Span,
+) -> Result<Vec<String>, ExhaustivenessError> {
+    let mut matrix = build_pattern_matrix(arms, scrutinee_ty, registry, consts);
+    let mut checker = ExhaustivenessChecker::new(registry, span).with_limits(limits);
+    let mut missing = Vec::new();
+    while missing.len() < MAX_MISSING_PATTERNS {
+        let Some(witness) = checker.check_exhaustive(&matrix)? else {
+            break;
+        };
+        let pats: Vec<SimplifiedPat> = witness.patterns.iter().map(widen_witness).collect();
+        if pats.len() != matrix.num_columns() {
+            break;
+        }
+        missing.push(
+            pats.iter()
+                .map(|p| pattern_source(p, registry))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        let arm_index = matrix.num_rows();
+        matrix.add_row(PatternRow::new(pats, arm_index));
+    }
+    Ok(missing)
+}
+
+/// A witness as a pattern, with literals widened to wildcards
+fn widen_witness(pat: &WitnessPat) -> SimplifiedPat {
+    match pat {
+        WitnessPat::Constructor { name, .. } if name == "true" || name == "false" => {
+            SimplifiedPat::Literal(LiteralPat::Bool(name == "true"))
+        }
+        WitnessPat::Constructor { name, args } => SimplifiedPat::Constructor {
+            name: name.clone(),
+            args: args.iter().map(widen_witness).collect(),
+        },
+        WitnessPat::Wildcard | WitnessPat::Literal(_) => SimplifiedPat::Wildcard,
+    }
+}
+
+/// Surface syntax for a simplified pattern: tuples in parentheses, structs
+/// and records with named fields, and the unit constructor as `nil`.
+fn pattern_source(pat: &SimplifiedPat, registry: &AdtRegistry) -> String {
+    let join = |args: &[SimplifiedPat]| {
+        args.iter()
+            .map(|a| pattern_source(a, registry))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match pat {
+        SimplifiedPat::Wildcard => "_".to_string(),
+        SimplifiedPat::Literal(lit) => lit.to_string(),
+        SimplifiedPat::Range { lo, hi } => format_range(*lo, *hi),
+        SimplifiedPat::Constructor { name, .. } if name == "()" => "nil".to_string(),
+        SimplifiedPat::Constructor { name, args } if is_tuple_ctor(name) => {
+            format!("({})", join(args))
+        }
+        SimplifiedPat::Constructor { name, args } if is_record_ctor(name) => {
+            let fields: Vec<String> = record_fields(name)
+                .zip(args)
+                .map(|(f, a)| format!("{}: {}", f, pattern_source(a, registry)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        SimplifiedPat::Constructor { name, args }
+            if registry.get(name).is_some_and(|d| d.is_tuple_struct()) =>
+        {
+            format!("{}({})", name, join(args))
+        }
+        SimplifiedPat::Constructor { name, args } => {
+            match registry.get(name).and_then(|d| d.fields()) {
+                Some(fields) => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .zip(args)
+                        .map(|(f, a)| format!("{}: {}", f.name, pattern_source(a, registry)))
+                        .collect();
+                    format!("{} {{ {} }}", name, fields.join(", "))
+                }
+                None if args.is_empty() => name.clone(),
+                None => format!("{}({})", name, join(args)),
+            }
+        }
+    }
+}
+
+/// Whether `name` is a builtin tuple constructor (`Tuple2`, `Tuple3`, ...)
+fn is_tuple_ctor(name: &str) -> bool {
+    name.strip_prefix("Tuple")
+        .is_some_and(|n| n.parse::<u8>().is_ok())
+}
+
+/// Constructor name of a record with the given (sorted) fields: `{x, y}`
+fn record_ctor_name<'a>(fields: impl Iterator<Item = &'a String>) -> String {
+    let fields: Vec<&str> = fields.map(String::as_str).collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// Whether `name` is a record constructor (`{x, y}`)
+fn is_record_ctor(name: &str) -> bool {
+    name.starts_with('{')
+}
+
+/// Field names of a record constructor, in order
+fn record_fields(name: &str) -> impl Iterator<Item = &str> {
+    name.trim_start_matches('{')
+        .trim_end_matches('}')
+        .split(", ")
+        .filter(|f| !f.is_empty())
+}
+
+/// What a pattern head is matched on: a record type has one constructor,
+/// which a record pattern matches whichever of its fields it names
+fn ctor_key(name: &str) -> &str {
+    if is_record_ctor(name) {
+        "{..}"
+    } else {
+        name
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -916,7 +1293,7 @@ mod tests {
     }
 
     fn span() -> Span {
-        Span { start: 0, end: 0 }
+        Span::default()
     }
 
     #[test]
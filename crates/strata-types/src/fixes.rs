@@ -0,0 +1,427 @@
+//! Suggested fixes for type errors.
+//!
+//! Fixes are computed from the error, the checked module, and its source
+//! text (some edits land on punctuation the AST has no span for, such as
+//! the closing brace of an effect annotation). Each fix is an edit a tool
+//! can show or apply; see [`Applicability`] for which are safe unattended.
+//!
+//! [`annotations`] works on a module that checks: it spells out the
+//! parameter types, return types, and effect rows the checker inferred.
+
+use crate::checker::TypeError;
+use crate::effects::{EffectRow, ALL_EFFECTS};
+use crate::infer::ty::{free_effect_vars, Ty, TyConst};
+use crate::typed::{TypedItem, TypedItemKind};
+use strata_ast::ast::{Block, Expr, FnDecl, Item, Module, Param, Stmt};
+use strata_ast::diag::{Applicability, Diagnostic, Fix};
+use strata_ast::span::Span;
+
+/// Effect names, which users sometimes write where a capability type goes.
+const EFFECT_NAMES: &[&str] = &["Fs", "Net", "Time", "Rand", "Ai", "Console"];
+
+/// `err` as a diagnostic with its suggested fixes attached.
+pub fn diagnostic(err: &TypeError, module: &Module, src: &str) -> Diagnostic {
+    err.to_diagnostic()
+        .with_fixes(suggest_fixes(err, module, src))
+}
+
+/// Fixes for `err`. Returns an empty list when no edit is known to help.
+///
+/// - Effect name used as a type (`fs: Fs`): use the capability type.
+/// - Capability type in an effect annotation (`& {FsCap}`): use the effect.
+/// - Body performs effects missing from the annotation: add them.
+/// - Effect without a capability parameter: add one. Safe only when
+///   nothing in the module calls the function, since callers would need a
+///   new argument.
+/// - Non-exhaustive match: add an arm for every uncovered pattern, each
+///   with a `todo()` body to fill in.
+pub fn suggest_fixes(err: &TypeError, module: &Module, src: &str) -> Vec<Fix> {
+    let fix = match err {
+        TypeError::UnknownType { name, span } => effect_as_type(name, *span, src),
+        TypeError::UnknownEffect { name, span } => cap_as_effect(name, *span, src),
+        TypeError::EffectMismatch {
+            expected,
+            found,
+            span,
+        }
+        | TypeError::UndeclaredEffect {
+            declared: expected,
+            actual: found,
+            span,
+            ..
+        } => {
+            let missing: Vec<String> = ALL_EFFECTS
+                .iter()
+                .filter(|e| found.contains(**e) && !expected.contains(**e))
+                .map(|e| format!("{:?}", e))
+                .collect();
+            find_fn(module, |d| d.span == *span).and_then(|decl| add_effects(decl, &missing, src))
+        }
+        TypeError::MissingCapability {
+            effect,
+            cap_type,
+            fn_name,
+            ..
+        } => find_fn(module, |d| d.name.text == *fn_name).and_then(|decl| {
+            let safe = !is_called(module, fn_name);
+            add_cap_param(&decl.name, &decl.params, effect, cap_type, safe, src)
+        }),
+        TypeError::ExternMissingCapability {
+            effect,
+            cap_type,
+            fn_name,
+            ..
+        } => module.items.iter().find_map(|item| match item {
+            Item::ExternFn(decl) if decl.name.text == *fn_name => {
+                let safe = !is_called(module, fn_name);
+                // Externs borrow the capabilities they are handed
+                let cap_type = format!("&{}", cap_type);
+                add_cap_param(&decl.name, &decl.params, effect, &cap_type, safe, src)
+            }
+            _ => None,
+        }),
+        TypeError::NonExhaustiveMatch {
+            witness,
+            missing,
+            span,
+        } => {
+            let pats = if missing.is_empty() {
+                std::slice::from_ref(witness)
+            } else {
+                missing.as_slice()
+            };
+            add_match_arms(pats, *span, src)
+        }
+        _ => None,
+    };
+    fix.into_iter().collect()
+}
+
+fn text(src: &str, span: Span) -> Option<&str> {
+    src.get(span.start as usize..span.end as usize)
+}
+
+/// Empty span at `offset` in the same file as `near`.
+fn insert_at(offset: usize, near: Span) -> Span {
+    Span::clamped(near.file, offset, offset)
+}
+
+fn effect_as_type(name: &str, span: Span, src: &str) -> Option<Fix> {
+    if !EFFECT_NAMES.contains(&name) || text(src, span)? != name {
+        return None;
+    }
+    Some(Fix {
+        message: format!("use the capability type `{}Cap`", name),
+        span,
+        replacement: format!("{}Cap", name),
+        applicability: Applicability::MachineApplicable,
+    })
+}
+
+fn cap_as_effect(name: &str, span: Span, src: &str) -> Option<Fix> {
+    let effect = name.strip_suffix("Cap")?;
+    if !EFFECT_NAMES.contains(&effect) || text(src, span)? != name {
+        return None;
+    }
+    Some(Fix {
+        message: format!("use the effect `{}`", effect),
+        span,
+        replacement: effect.to_string(),
+        applicability: Applicability::MachineApplicable,
+    })
+}
+
+fn find_fn(module: &Module, pred: impl Fn(&FnDecl) -> bool) -> Option<&FnDecl> {
+    module.items.iter().find_map(|item| match item {
+        Item::Fn(decl) if pred(decl) => Some(decl),
+        _ => None,
+    })
+}
+
+/// Insert `missing` effects before the `}` closing `decl`'s annotation,
+/// which is the last `}` before the body.
+fn add_effects(decl: &FnDecl, missing: &[String], src: &str) -> Option<Fix> {
+    let declared = decl.effects.as_ref()?;
+    if missing.is_empty() {
+        return None;
+    }
+    let head = src.get(decl.span.start as usize..decl.body.span.start as usize)?;
+    let close = decl.span.start as usize + head.rfind('}')?;
+    let sep = if declared.is_empty() { "" } else { ", " };
+    let list = missing.join(", ");
+    Some(Fix {
+        message: format!(
+            "add `{}` to the effect annotation of `{}`",
+            list, decl.name.text
+        ),
+        span: insert_at(close, decl.span),
+        replacement: format!("{}{}", sep, list),
+        applicability: Applicability::MachineApplicable,
+    })
+}
+
+/// Append a `name: CapType` parameter, naming it after the effect and
+/// avoiding names already taken by other parameters.
+fn add_cap_param(
+    fn_name: &strata_ast::ast::Ident,
+    params: &[Param],
+    effect: &str,
+    cap_type: &str,
+    safe: bool,
+    src: &str,
+) -> Option<Fix> {
+    let base = effect.to_lowercase();
+    let taken = |n: &str| params.iter().any(|p| p.name.text == n);
+    let name = std::iter::once(base.clone())
+        .chain((2..).map(|i| format!("{}{}", base, i)))
+        .find(|n| !taken(n))?;
+
+    // Defaulted and variadic parameters stay last
+    let trailing = params.iter().find(|p| p.default.is_some() || p.variadic);
+    let (offset, replacement) = match (trailing, params.last()) {
+        (Some(first), _) => (
+            first.span.start as usize,
+            format!("{}: {}, ", name, cap_type),
+        ),
+        (None, Some(last)) => (last.span.end as usize, format!(", {}: {}", name, cap_type)),
+        (None, None) => {
+            let after_name = fn_name.span.end as usize;
+            let lparen = after_name + src.get(after_name..)?.find('(')?;
+            (lparen + 1, format!("{}: {}", name, cap_type))
+        }
+    };
+    Some(Fix {
+        message: format!(
+            "add a `{}: {}` parameter to `{}`",
+            name, cap_type, fn_name.text
+        ),
+        span: insert_at(offset, fn_name.span),
+        replacement,
+        applicability: if safe {
+            Applicability::MachineApplicable
+        } else {
+            Applicability::HasPlaceholders
+        },
+    })
+}
+
+/// Add a `pat => todo(),` arm for each of `pats` after the last arm of the
+/// match at `span`.
+fn add_match_arms(pats: &[String], span: Span, src: &str) -> Option<Fix> {
+    let matched = text(src, span)?;
+    let inner = matched.strip_suffix('}')?;
+    let trimmed = inner.trim_end();
+    let sep = if trimmed.ends_with(',') || trimmed.ends_with('{') {
+        ""
+    } else {
+        ","
+    };
+    let offset = span.start as usize + trimmed.len();
+    let replacement = if inner[trimmed.len()..].contains('\n') {
+        // Multi-line match: one arm per line, indented like the last arm
+        let line_start = trimmed.rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = trimmed[line_start..]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let arms: String = pats
+            .iter()
+            .map(|p| format!("\n{}{} => todo(),", indent, p))
+            .collect();
+        format!("{}{}", sep, arms)
+    } else {
+        let arms: Vec<String> = pats.iter().map(|p| format!("{} => todo()", p)).collect();
+        format!("{} {}", sep, arms.join(", "))
+    };
+    let message = match pats {
+        [pat] => format!("add an arm for `{}`", pat),
+        _ => {
+            let quoted: Vec<String> = pats.iter().map(|p| format!("`{}`", p)).collect();
+            format!("add arms for {}", quoted.join(", "))
+        }
+    };
+    Some(Fix {
+        message,
+        span: insert_at(offset, span),
+        replacement,
+        applicability: Applicability::HasPlaceholders,
+    })
+}
+
+/// Edits that write out what the checker inferred for each top-level `fn`
+/// of a checked `module`: missing parameter types, return types, and
+/// effect annotations, from the checker's `typed` items.
+///
+/// A type that can't be written in source (a type variable, an open
+/// record) is left inferred, as is a `()` return type. An inferred effect
+/// row is written as its effects, `& {}` if none, unless its tail is shared
+/// with a parameter's function type: closing it would stop the function
+/// from passing on the effects of the functions it is given.
+pub fn annotations(module: &Module, typed: &[TypedItem], src: &str) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    for item in &module.items {
+        let Item::Fn(decl) = item else { continue };
+        let ty = typed.iter().find_map(|t| match &t.ty {
+            Ty::Arrow(params, ret, eff) if t.kind == TypedItemKind::Fn && t.span == decl.span => {
+                Some((params, ret, eff))
+            }
+            _ => None,
+        });
+        let Some((param_tys, ret, eff)) = ty else {
+            continue;
+        };
+        let name = &decl.name.text;
+
+        for (param, ty) in decl.params.iter().zip(param_tys) {
+            if param.ty.is_some() {
+                continue;
+            }
+            if let Some(written) = source_type(ty) {
+                fixes.push(Fix {
+                    message: format!("annotate `{}` as `{}`", param.name.text, written),
+                    span: insert_at(param.name.span.end as usize, param.name.span),
+                    replacement: format!(": {}", written),
+                    applicability: Applicability::MachineApplicable,
+                });
+            }
+        }
+
+        // The return type goes after the parameter list's `)`, the last one
+        // before the body when there is no return type
+        let head = src.get(decl.span.start as usize..decl.body.span.start as usize);
+        let Some(rparen) = head.and_then(|h| h.rfind(')')) else {
+            continue;
+        };
+        let after_params = decl.span.start as usize + rparen + 1;
+        let after_ret = match &decl.ret_ty {
+            Some(ty) => ty.span().end as usize,
+            None => {
+                let written = source_type(ret).filter(|_| *ret.as_ref() != Ty::unit());
+                if let Some(written) = written {
+                    fixes.push(Fix {
+                        message: format!("annotate `{}` as returning `{}`", name, written),
+                        span: insert_at(after_params, decl.span),
+                        replacement: format!(" -> {}", written),
+                        applicability: Applicability::MachineApplicable,
+                    });
+                }
+                after_params
+            }
+        };
+
+        if decl.effects.is_none() {
+            let shared = eff.tail.is_some_and(|tail| {
+                param_tys
+                    .iter()
+                    .any(|p| free_effect_vars(p).contains(&tail))
+            });
+            if !shared {
+                let row = source_row(eff);
+                fixes.push(Fix {
+                    message: format!("annotate the effects of `{}` as `{}`", name, row),
+                    span: insert_at(after_ret, decl.span),
+                    replacement: format!(" & {}", row),
+                    applicability: Applicability::MachineApplicable,
+                });
+            }
+        }
+    }
+    fixes
+}
+
+/// `ty` in source syntax, if it can be written there.
+fn source_type(ty: &Ty) -> Option<String> {
+    let list = |tys: &[Ty]| -> Option<String> {
+        let written: Option<Vec<String>> = tys.iter().map(source_type).collect();
+        Some(written?.join(", "))
+    };
+    Some(match ty {
+        Ty::Const(TyConst::Unit) => "()".to_string(),
+        Ty::Const(_) | Ty::Cap(_) => ty.to_string(),
+        Ty::Arrow(params, ret, eff) => {
+            if !eff.is_closed() {
+                return None;
+            }
+            let effects = if eff.is_empty() {
+                String::new()
+            } else {
+                format!(" & {}", source_row(eff))
+            };
+            format!("fn({}) -> {}{}", list(params)?, source_type(ret)?, effects)
+        }
+        Ty::Tuple(elems) => format!("({})", list(elems)?),
+        Ty::Record(fields, None) => {
+            let written: Option<Vec<String>> = fields
+                .iter()
+                .map(|(name, ty)| Some(format!("{}: {}", name, source_type(ty)?)))
+                .collect();
+            format!("{{{}}}", written?.join(", "))
+        }
+        Ty::Adt { name, args } if args.is_empty() => name.clone(),
+        Ty::Adt { name, args } => format!("{}<{}>", name, list(args)?),
+        Ty::Ref(inner) => format!("&{}", source_type(inner)?),
+        Ty::Var(_) | Ty::Record(..) | Ty::List(_) | Ty::Never => return None,
+    })
+}
+
+/// The effects of `row` as an annotation, ignoring any tail.
+fn source_row(row: &EffectRow) -> String {
+    let effects: Vec<String> = row.iter().map(|e| format!("{:?}", e)).collect();
+    format!("{{{}}}", effects.join(", "))
+}
+
+/// Whether any function body in `module` calls `name` directly.
+fn is_called(module: &Module, name: &str) -> bool {
+    module.items.iter().any(|item| match item {
+        Item::Fn(decl) => block_calls(&decl.body, name),
+        Item::Let(decl) => expr_calls(&decl.value, name),
+        _ => false,
+    })
+}
+
+fn block_calls(block: &Block, name: &str) -> bool {
+    block.stmts.iter().any(|stmt| match stmt {
+        Stmt::Let { value, .. } | Stmt::Assign { value, .. } => expr_calls(value, name),
+        Stmt::Expr { expr, .. } => expr_calls(expr, name),
+        Stmt::Declare { .. } => false,
+        Stmt::Fn(decl) => block_calls(&decl.body, name),
+        Stmt::Return { value, .. } => value.as_ref().is_some_and(|v| expr_calls(v, name)),
+    }) || block.tail.as_ref().is_some_and(|t| expr_calls(t, name))
+}
+
+fn expr_calls(expr: &Expr, name: &str) -> bool {
+    match expr {
+        Expr::Call { callee, args, .. } => {
+            matches!(callee.as_ref(), Expr::Var(id) if id.text == name)
+                || expr_calls(callee, name)
+                || args.iter().any(|a| expr_calls(a, name))
+        }
+        // Passing the function as a value lets it be called elsewhere
+        Expr::Var(id) => id.text == name,
+        Expr::Lit(..) | Expr::PathExpr(_) | Expr::Hole(_) => false,
+        Expr::Unary { expr, .. }
+        | Expr::Paren { inner: expr, .. }
+        | Expr::Borrow(expr, _)
+        | Expr::Field { expr, .. } => expr_calls(expr, name),
+        Expr::Binary { lhs, rhs, .. } => expr_calls(lhs, name) || expr_calls(rhs, name),
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => block_calls(block, name),
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            expr_calls(cond, name)
+                || block_calls(then_, name)
+                || else_.as_ref().is_some_and(|e| expr_calls(e, name))
+        }
+        Expr::While { cond, body, .. }
+        | Expr::For {
+            iter: cond, body, ..
+        } => expr_calls(cond, name) || block_calls(body, name),
+        Expr::Match {
+            scrutinee, arms, ..
+        } => expr_calls(scrutinee, name) || arms.iter().any(|a| expr_calls(&a.body, name)),
+        Expr::Tuple { elems, .. } => elems.iter().any(|e| expr_calls(e, name)),
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            fields.iter().any(|f| expr_calls(&f.value, name))
+        }
+    }
+}
@@ -9,18 +9,18 @@
 
 use super::ty::{free_effect_vars, free_vars, Constraint, Scheme, Ty, TypeVarId};
 use crate::adt::AdtRegistry;
-use crate::effects::{EffectRow, EffectVarId};
+use crate::consts::ConstTable;
+use crate::effects::{Effect, EffectRow, EffectVarId};
 use crate::exhaustive::{self, ExhaustivenessError};
-use std::collections::{HashMap, HashSet};
-use strata_ast::ast::{BinOp, Block, Expr, FieldInit, Lit, MatchArm, Pat, Path, Stmt, UnOp};
+use crate::profile::Limits;
+use crate::resolve::{DefId, Resolution};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use strata_ast::ast::{
+    BinOp, Block, Expr, FieldInit, FnDecl, Lit, MatchArm, Pat, Path, Stmt, UnOp,
+};
 use strata_ast::span::Span;
 
-/// Maximum inference depth to prevent stack overflow from pathological input
-const MAX_INFER_DEPTH: u32 = 128;
-
-/// Maximum number of effect variables per module (DoS protection)
-const MAX_EFFECT_VARS: u32 = 4096;
-
 /// Errors that can occur during type inference
 #[derive(Debug, Clone)]
 pub enum InferError {
@@ -28,6 +28,8 @@ pub enum InferError {
     UnknownVariable { name: String, span: Span },
     /// Assignment to an immutable variable
     ImmutableAssignment { name: String, span: Span },
+    /// A `spawn` block reading or assigning a mutable variable from outside it
+    SpawnCapturesMutable { name: String, span: Span },
     /// Feature not yet implemented
     NotImplemented { msg: String, span: Span },
     /// Inference depth limit exceeded (pathological input)
@@ -36,6 +38,8 @@ pub enum InferError {
     DuplicateBinding { name: String, span: Span },
     /// Unknown type in pattern
     UnknownType { name: String, span: Span },
+    /// Unknown effect in a function type's annotation
+    UnknownEffect { name: String, span: Span },
     /// Unknown variant in pattern
     UnknownVariant {
         type_name: String,
@@ -69,13 +73,20 @@ pub enum InferError {
         span: Span,
     },
     /// Match is not exhaustive
-    NonExhaustiveMatch { witness: String, span: Span },
+    NonExhaustiveMatch {
+        witness: String,
+        /// Every uncovered pattern, as source for new arms
+        missing: Vec<String>,
+        span: Span,
+    },
     /// Pattern arm is unreachable
     UnreachablePattern { arm_index: usize, span: Span },
     /// Exhaustiveness checking limit exceeded (DoS protection)
     ExhaustivenessLimitExceeded { msg: String, span: Span },
     /// Refutable pattern in let binding (should use match instead)
     RefutablePattern { pat_desc: String, span: Span },
+    /// Range pattern that matches no values (e.g. `5..=1`, `3..3`)
+    EmptyRangePattern { span: Span },
     /// Effect variable limit exceeded (DoS protection)
     EffectVarLimitExceeded { limit: u32 },
     /// Cyclic effect variable substitution
@@ -84,6 +95,9 @@ pub enum InferError {
     RefEscape { ty: Ty, context: String, span: Span },
     /// Effect substitution chain too deep
     EffectChainTooDeep { depth: usize },
+    /// A partial application of something that is only ever called
+    /// directly: a builtin or a constructor
+    NotPartiallyApplicable { callee: String, span: Span },
     /// Scheme instantiation arity mismatch (internal invariant violation)
     InstantiationArityMismatch {
         expected_types: usize,
@@ -109,6 +123,13 @@ pub struct CheckContext {
     pub adt_registry: Option<AdtRegistry>,
     /// Effect row for the current function body (effects from calls accumulate here)
     pub body_effects: Option<EffectRow>,
+    /// Module consts; a pattern naming one matches its value
+    pub consts: Arc<ConstTable>,
+    /// Mutable variables from outside the enclosing `spawn`, which the task
+    /// may neither read nor assign
+    pub shared_mutable: HashSet<String>,
+    /// What the module's names refer to
+    pub resolution: Arc<Resolution>,
 }
 
 impl CheckContext {
@@ -120,6 +141,9 @@ impl CheckContext {
             expected_return: None,
             adt_registry: None,
             body_effects: None,
+            consts: Arc::default(),
+            shared_mutable: HashSet::new(),
+            resolution: Arc::default(),
         }
     }
 
@@ -131,6 +155,9 @@ impl CheckContext {
             expected_return: None,
             adt_registry: None,
             body_effects: None,
+            consts: Arc::default(),
+            shared_mutable: HashSet::new(),
+            resolution: Arc::default(),
         }
     }
 
@@ -142,10 +169,14 @@ impl CheckContext {
             expected_return: None,
             adt_registry: Some(registry),
             body_effects: None,
+            consts: Arc::default(),
+            shared_mutable: HashSet::new(),
+            resolution: Arc::default(),
         }
     }
 
-    /// Create a child context with the same expected_return, registry, and body_effects
+    /// Create a child context with the same expected_return, registry, body_effects,
+    /// consts, and resolution
     pub fn child(&self) -> Self {
         CheckContext {
             env: self.env.clone(),
@@ -153,11 +184,27 @@ impl CheckContext {
             expected_return: self.expected_return.clone(),
             adt_registry: self.adt_registry.clone(),
             body_effects: self.body_effects,
+            consts: self.consts.clone(),
+            shared_mutable: self.shared_mutable.clone(),
+            resolution: self.resolution.clone(),
         }
     }
 
+    /// Attach the module's evaluated consts
+    pub fn with_consts(mut self, consts: Arc<ConstTable>) -> Self {
+        self.consts = consts;
+        self
+    }
+
+    /// Attach the module's name resolution
+    pub fn with_resolution(mut self, resolution: Arc<Resolution>) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
     /// Add a binding to the context
     pub fn bind(&mut self, name: String, scheme: Scheme, mutable: bool) {
+        self.shared_mutable.remove(&name);
         self.env.insert(name.clone(), scheme);
         self.mutability.insert(name, mutable);
     }
@@ -174,7 +221,17 @@ impl Default for CheckContext {
     }
 }
 
+/// What each argument for a variadic `extern fn` parameter must be
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariadicBound {
+    /// `...Show`: any value `show` accepts
+    Show,
+    /// `...T`: a `T`
+    Ty(Ty),
+}
+
 /// Inference context for constraint generation
+#[derive(Clone)]
 pub struct InferCtx {
     /// Counter for generating fresh type variables
     fresh_counter: u32,
@@ -184,6 +241,52 @@ pub struct InferCtx {
     constraints: Vec<Constraint>,
     /// Current inference depth (for recursion limit)
     depth: u32,
+    /// Variables bound by block-level `let`s, with unsolved types
+    let_bindings: Vec<PatternBinding>,
+    /// Scheme of the `discriminant` builtin, to tell its calls apart from
+    /// calls to a user function of the same name
+    discriminant_scheme: Option<Scheme>,
+    /// Argument types of `discriminant(x)` calls, which must solve to enums
+    enum_args: Vec<(Ty, Span)>,
+    /// Scheme of the `show` builtin, like `discriminant_scheme`
+    show_scheme: Option<Scheme>,
+    /// Argument types of `show(x)` calls, and of the arguments for
+    /// `...Show` parameters, which must hold no capability; each with the
+    /// name of the function called
+    show_args: Vec<(Ty, String, Span)>,
+    /// Scheme of the `par` builtin, like `discriminant_scheme`
+    par_scheme: Option<Scheme>,
+    /// Scheme of the `iter` builtin, like `discriminant_scheme`
+    iter_scheme: Option<Scheme>,
+    /// Item types of `iter(first, step)` calls, which must not be single-use
+    iter_args: Vec<(Ty, Span)>,
+    /// Schemes of the `extern fn`s with default parameter values, like
+    /// `discriminant_scheme`, each with how many arguments a call must
+    /// pass; the defaulted parameters after those may be left off
+    defaulted_externs: HashMap<String, (Scheme, usize)>,
+    /// Schemes of the variadic `extern fn`s, like `defaulted_externs`, each
+    /// with what the arguments after its fixed parameters must be
+    variadic_externs: HashMap<String, (Scheme, VariadicBound)>,
+    /// Arguments passed by name, by span, each with the function called
+    /// and the parameter named, for type errors to report
+    named_args: HashMap<Span, (String, String)>,
+    /// Operand types of comparisons: `true` for an ordering (`<`, `<=`,
+    /// `>`, `>=`), `false` for `==`/`!=`. Checked once solved.
+    comparisons: Vec<(Ty, bool, Span)>,
+    /// Signatures of nested `fn`s, keyed by declaration span, computed by
+    /// the checker before the enclosing body is inferred
+    local_fn_sigs: HashMap<Span, Ty>,
+    /// Types of the arguments bound by partial applications, which must
+    /// hold no capability, each with the name of the function applied (if
+    /// it is a name)
+    partial_args: Vec<(Ty, Option<String>, Span)>,
+    /// Calls to a function or extern by name: the name, the span of the
+    /// call, and the effect row the call was given, for effect errors to
+    /// trace an effect back to the call it comes from
+    calls: Vec<(String, Span, EffectRow)>,
+    /// Inference depth, tuple arity, effect variable and exhaustiveness
+    /// limits (DoS protection)
+    limits: Limits,
 }
 
 impl InferCtx {
@@ -194,13 +297,40 @@ impl InferCtx {
             fresh_effect_counter: 0,
             constraints: vec![],
             depth: 0,
+            let_bindings: vec![],
+            discriminant_scheme: None,
+            enum_args: vec![],
+            show_scheme: None,
+            show_args: vec![],
+            partial_args: vec![],
+            par_scheme: None,
+            iter_scheme: None,
+            iter_args: vec![],
+            defaulted_externs: HashMap::new(),
+            variadic_externs: HashMap::new(),
+            named_args: HashMap::new(),
+            comparisons: vec![],
+            local_fn_sigs: HashMap::new(),
+            calls: vec![],
+            limits: Limits::default(),
         }
     }
 
+    /// Check under `limits` rather than the default ones
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The limits checking is under
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
     /// Enter a new level of inference depth
     fn enter_depth(&mut self, span: Span) -> Result<(), InferError> {
         self.depth += 1;
-        if self.depth > MAX_INFER_DEPTH {
+        if self.depth > self.limits.infer_depth {
             Err(InferError::DepthLimitExceeded { span })
         } else {
             Ok(())
@@ -224,13 +354,23 @@ impl InferCtx {
         id
     }
 
+    /// Id of the next fresh type variable
+    pub fn next_var(&self) -> u32 {
+        self.fresh_counter
+    }
+
+    /// Resume fresh type variables at `next`, past those a solver made
+    pub fn set_next_var(&mut self, next: u32) {
+        self.fresh_counter = self.fresh_counter.max(next);
+    }
+
     /// Generate a fresh EffectVarId (just the ID, not a full row)
     ///
     /// Returns an error if the effect variable limit is exceeded (DoS protection).
     pub fn fresh_effect_var_id(&mut self) -> Result<EffectVarId, InferError> {
-        if self.fresh_effect_counter >= MAX_EFFECT_VARS {
+        if self.fresh_effect_counter >= self.limits.effect_vars {
             return Err(InferError::EffectVarLimitExceeded {
-                limit: MAX_EFFECT_VARS,
+                limit: self.limits.effect_vars,
             });
         }
         let id = EffectVarId(self.fresh_effect_counter);
@@ -288,6 +428,95 @@ impl InferCtx {
         std::mem::take(&mut self.constraints)
     }
 
+    /// Take the bindings made by block-level `let`s since the last call.
+    /// Their types are unsolved; apply the substitution to resolve them.
+    pub fn take_let_bindings(&mut self) -> Vec<PatternBinding> {
+        std::mem::take(&mut self.let_bindings)
+    }
+
+    /// Record the scheme of the `discriminant` builtin
+    pub fn set_discriminant_scheme(&mut self, scheme: Scheme) {
+        self.discriminant_scheme = Some(scheme);
+    }
+
+    /// Record the scheme of `extern fn name`, whose parameters after the
+    /// first `required` have default values
+    pub fn set_extern_defaults(&mut self, name: &str, scheme: Scheme, required: usize) {
+        self.defaulted_externs
+            .insert(name.to_string(), (scheme, required));
+    }
+
+    /// Record the scheme of `extern fn name`, whose last parameter is
+    /// variadic with `bound`; it isn't one of the scheme's parameters
+    pub fn set_extern_variadic(&mut self, name: &str, scheme: Scheme, bound: VariadicBound) {
+        self.variadic_externs
+            .insert(name.to_string(), (scheme, bound));
+    }
+
+    /// The function called and the parameter named, if the argument at
+    /// `span` was passed by name
+    pub fn named_arg(&self, span: Span) -> Option<&(String, String)> {
+        self.named_args.get(&span)
+    }
+
+    /// Take the calls by name to a function or extern seen since the last
+    /// call, in the order they were inferred
+    pub fn take_calls(&mut self) -> Vec<(String, Span, EffectRow)> {
+        std::mem::take(&mut self.calls)
+    }
+
+    /// Take the argument types of `discriminant(x)` calls seen since the
+    /// last call, each with the span of the call's callee
+    pub fn take_enum_args(&mut self) -> Vec<(Ty, Span)> {
+        std::mem::take(&mut self.enum_args)
+    }
+
+    /// Record the scheme of the `show` builtin
+    pub fn set_show_scheme(&mut self, scheme: Scheme) {
+        self.show_scheme = Some(scheme);
+    }
+
+    /// Take the argument types of `show(x)` calls seen since the last
+    /// call, each with the function called and the span of the call's
+    /// callee (or of the argument, for a `...Show` parameter)
+    pub fn take_show_args(&mut self) -> Vec<(Ty, String, Span)> {
+        std::mem::take(&mut self.show_args)
+    }
+
+    /// Take the types of the arguments bound by partial applications seen
+    /// since the last call, each with the function applied and the span
+    /// of the argument
+    pub fn take_partial_args(&mut self) -> Vec<(Ty, Option<String>, Span)> {
+        std::mem::take(&mut self.partial_args)
+    }
+
+    /// Record the scheme of the `iter` builtin
+    pub fn set_iter_scheme(&mut self, scheme: Scheme) {
+        self.iter_scheme = Some(scheme);
+    }
+
+    /// Take the item types of `iter(first, step)` calls seen since the last
+    /// call, each with the span of the call's callee
+    pub fn take_iter_args(&mut self) -> Vec<(Ty, Span)> {
+        std::mem::take(&mut self.iter_args)
+    }
+
+    /// Record the scheme of the `par` builtin
+    pub fn set_par_scheme(&mut self, scheme: Scheme) {
+        self.par_scheme = Some(scheme);
+    }
+
+    /// Take the operand types of comparisons seen since the last call,
+    /// each with whether it is an ordering and the comparison's span
+    pub fn take_comparisons(&mut self) -> Vec<(Ty, bool, Span)> {
+        std::mem::take(&mut self.comparisons)
+    }
+
+    /// Record the signature of the nested `fn` declared at `span`
+    pub fn set_local_fn_sig(&mut self, span: Span, ty: Ty) {
+        self.local_fn_sigs.insert(span, ty);
+    }
+
     /// Generalize a type into a scheme
     ///
     /// Free variables in `ty` that are NOT in `env_vars` become ∀-bound.
@@ -358,6 +587,12 @@ impl InferCtx {
 
             // Variables: look up scheme and instantiate
             Expr::Var(ident) => {
+                if ctx.shared_mutable.contains(&ident.text) {
+                    return Err(InferError::SpawnCapturesMutable {
+                        name: ident.text.clone(),
+                        span: ident.span,
+                    });
+                }
                 if let Some(scheme) = ctx.env.get(&ident.text) {
                     self.instantiate_scheme(scheme)
                 } else {
@@ -371,6 +606,12 @@ impl InferCtx {
             // Parentheses: just infer the inner expression
             Expr::Paren { inner, .. } => self.infer_expr_ctx(ctx, inner),
 
+            // A hole is only a call argument; anywhere else `_` names nothing
+            Expr::Hole(span) => Err(InferError::UnknownVariable {
+                name: "_".to_string(),
+                span: *span,
+            }),
+
             // Unary operations
             Expr::Unary { op, expr, span } => self.infer_unary_ctx(ctx, *op, expr, *span),
 
@@ -378,28 +619,166 @@ impl InferCtx {
             Expr::Binary { lhs, op, rhs, span } => self.infer_binary_ctx(ctx, *op, lhs, rhs, *span),
 
             // Function calls
-            Expr::Call { callee, args, span } => {
+            Expr::Call {
+                callee,
+                args,
+                names,
+                span,
+            } => {
                 // Infer function type
                 let func_ty = self.infer_expr_ctx(ctx, callee)?;
 
-                // Infer argument types
+                // Infer argument types; a hole's is its parameter's
+                let partial = expr.is_partial_call();
+                if partial {
+                    check_partially_applicable(ctx, callee)?;
+                }
                 let arg_tys: Result<Vec<Ty>, InferError> = args
                     .iter()
-                    .map(|arg| self.infer_expr_ctx(ctx, arg))
+                    .map(|arg| match arg {
+                        Expr::Hole(_) => Ok(self.fresh_var()),
+                        _ => self.infer_expr_ctx(ctx, arg),
+                    })
                     .collect();
-                let arg_tys = arg_tys?;
+                let mut arg_tys = arg_tys?;
+                if partial {
+                    let name = match callee.as_ref() {
+                        Expr::Var(id) => Some(id.text.clone()),
+                        _ => None,
+                    };
+                    for (arg, ty) in args.iter().zip(&arg_tys) {
+                        if !matches!(arg, Expr::Hole(_)) {
+                            self.partial_args
+                                .push((ty.clone(), name.clone(), arg.span()));
+                        }
+                    }
+                }
+
+                // A call to an `extern fn` may leave off its defaulted
+                // parameters, which take the types declared for them
+                if let (Expr::Var(id), Ty::Arrow(params, _, _)) = (callee.as_ref(), &func_ty) {
+                    if let Some((scheme, required)) = self.defaulted_externs.get(&id.text) {
+                        if ctx.env.get(&id.text) == Some(scheme)
+                            && (*required..params.len()).contains(&arg_tys.len())
+                        {
+                            arg_tys.extend_from_slice(&params[arg_tys.len()..]);
+                        }
+                    }
+                }
+                // A call to a variadic `extern fn` passes any number of
+                // arguments after the fixed ones, each fitting the bound
+                if let (Expr::Var(id), Ty::Arrow(params, _, _)) = (callee.as_ref(), &func_ty) {
+                    if let Some((scheme, bound)) = self.variadic_externs.get(&id.text) {
+                        if ctx.env.get(&id.text) == Some(scheme) && arg_tys.len() > params.len() {
+                            let bound = bound.clone();
+                            let extra = arg_tys.split_off(params.len());
+                            for (ty, arg) in extra.into_iter().zip(&args[params.len()..]) {
+                                match &bound {
+                                    VariadicBound::Show => {
+                                        self.show_args.push((ty, id.text.clone(), arg.span()))
+                                    }
+                                    VariadicBound::Ty(expected) => self.add_constraint(
+                                        Constraint::Equal(expected.clone(), ty, arg.span()),
+                                    ),
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // An argument passed by name is checked against its
+                // parameter on its own, so a mismatch can name the parameter
+                if let (Expr::Var(id), Ty::Arrow(params, _, _)) = (callee.as_ref(), &func_ty) {
+                    let named = args.iter().zip(names).zip(params.iter().zip(&arg_tys));
+                    for ((arg, name), (param_ty, arg_ty)) in named {
+                        // Defaults written in by expansion carry the call's span
+                        if let Some(name) = name.as_ref().filter(|_| arg.span() != *span) {
+                            self.add_constraint(Constraint::Subsume(
+                                param_ty.clone(),
+                                arg_ty.clone(),
+                                arg.span(),
+                            ));
+                            self.named_args
+                                .insert(arg.span(), (id.text.clone(), name.text.clone()));
+                        }
+                    }
+                }
+
+                // `discriminant(x)` needs an enum, which is only known once
+                // `x`'s type is solved
+                if let (Expr::Var(id), [arg_ty]) = (callee.as_ref(), arg_tys.as_slice()) {
+                    if id.text == "discriminant"
+                        && self.discriminant_scheme.is_some()
+                        && ctx.env.get(&id.text) == self.discriminant_scheme.as_ref()
+                    {
+                        self.enum_args.push((arg_ty.clone(), id.span));
+                    }
+                    // Likewise `show(x)` needs `x` free of capabilities
+                    if id.text == "show"
+                        && self.show_scheme.is_some()
+                        && ctx.env.get(&id.text) == self.show_scheme.as_ref()
+                    {
+                        self.show_args
+                            .push((arg_ty.clone(), id.text.clone(), id.span));
+                    }
+                }
+                // `iter(first, step)` hands each item both to the caller and
+                // to `step`, so the items can't be single-use
+                if let (Expr::Var(id), [first_ty, _]) = (callee.as_ref(), arg_tys.as_slice()) {
+                    if id.text == "iter"
+                        && self.iter_scheme.is_some()
+                        && ctx.env.get(&id.text) == self.iter_scheme.as_ref()
+                    {
+                        self.iter_args.push((first_ty.clone(), id.span));
+                    }
+                }
+
+                // `par(f, g)` has the effects of both branches, which a
+                // single row variable can't express: each branch's row is
+                // constrained to fit within the call's
+                if let (Expr::Var(id), Ty::Arrow(params, _, par_eff)) = (callee.as_ref(), &func_ty)
+                {
+                    if id.text == "par"
+                        && self.par_scheme.is_some()
+                        && ctx.env.get(&id.text) == self.par_scheme.as_ref()
+                    {
+                        for param in params {
+                            if let Ty::Arrow(_, _, branch_eff) = param {
+                                self.add_constraint(Constraint::EffectSubset(
+                                    *branch_eff,
+                                    *par_eff,
+                                    *span,
+                                ));
+                            }
+                        }
+                    }
+                }
 
                 // Create fresh var for result
                 let result_ty = self.fresh_var();
 
+                // A callee returning Never (`todo`, `unreachable`) diverges.
+                // Never only unifies with itself, so the call's own type is
+                // left free to fit whatever its context expects.
+                let diverges = matches!(&func_ty, Ty::Arrow(_, ret, _) if **ret == Ty::Never);
+                let callee_ret = if diverges {
+                    Ty::Never
+                } else {
+                    result_ty.clone()
+                };
+
                 // Use fresh effect var for callee's effects (will be resolved by unification)
                 let callee_eff = self.fresh_effect_var()?;
-                let expected_fn_ty = Ty::arrow_eff(arg_tys, result_ty.clone(), callee_eff);
-
-                // Propagate callee effects to enclosing function body
-                if let Some(body_eff) = ctx.body_effects {
-                    self.add_constraint(Constraint::EffectSubset(callee_eff, body_eff, *span));
-                }
+                // A partial application is a function of its holes, with
+                // the callee's effects; they happen when it is called
+                let hole_tys: Vec<Ty> = args
+                    .iter()
+                    .zip(&arg_tys)
+                    .filter(|(arg, _)| matches!(arg, Expr::Hole(_)))
+                    .map(|(_, ty)| ty.clone())
+                    .collect();
+                let partial_ret = callee_ret.clone();
+                let expected_fn_ty = Ty::arrow_eff(arg_tys, callee_ret, callee_eff);
                 let call_span = match callee.as_ref() {
                     Expr::Var(id) => id.span,
                     Expr::Paren {
@@ -408,7 +787,23 @@ impl InferCtx {
                     Expr::PathExpr(path) => path.span,
                     _ => *span, // Use the call expression span as fallback
                 };
-                self.add_constraint(Constraint::Equal(func_ty, expected_fn_ty, call_span));
+                if partial {
+                    self.add_constraint(Constraint::Subsume(expected_fn_ty, func_ty, call_span));
+                    return Ok(Ty::arrow_eff(hole_tys, partial_ret, callee_eff));
+                }
+
+                // Propagate callee effects to enclosing function body
+                if let Some(body_eff) = ctx.body_effects {
+                    self.add_constraint(Constraint::EffectSubset(callee_eff, body_eff, *span));
+                }
+                if let Expr::Var(id) = callee.as_ref() {
+                    if let Some(DefId::Fn(_) | DefId::Extern(_)) = ctx.resolution.ident(id) {
+                        self.calls.push((id.text.clone(), *span, callee_eff));
+                    }
+                }
+                // Arguments are subsumed by their parameters: a function
+                // argument may do less than its parameter's type allows
+                self.add_constraint(Constraint::Subsume(expected_fn_ty, func_ty, call_span));
 
                 Ok(result_ty)
             }
@@ -427,6 +822,33 @@ impl InferCtx {
             // While loop
             Expr::While { cond, body, span } => self.infer_while(ctx, cond, body, *span),
 
+            // For loop
+            Expr::For {
+                pat,
+                iter,
+                body,
+                span,
+            } => self.infer_for(ctx, pat, iter, body, *span),
+
+            // Task: its effects are the spawning function's, and `return`
+            // leaves the task with its result
+            Expr::Spawn { body, span } => {
+                let result_ty = self.fresh_var();
+                let mut task_ctx = ctx.child();
+                task_ctx.shared_mutable.extend(
+                    ctx.mutability
+                        .iter()
+                        .filter(|(_, mutable)| **mutable)
+                        .map(|(name, _)| name.clone()),
+                );
+                task_ctx.expected_return = Some(result_ty.clone());
+                let body_ty = self.infer_block(&task_ctx, body)?;
+                if body_ty != Ty::Never {
+                    self.add_constraint(Constraint::Equal(body_ty, result_ty.clone(), *span));
+                }
+                Ok(Ty::adt("Task", vec![result_ty]))
+            }
+
             // Match expression
             Expr::Match {
                 scrutinee,
@@ -437,6 +859,35 @@ impl InferCtx {
             // Tuple expression
             Expr::Tuple { elems, span } => self.infer_tuple(ctx, elems, *span),
 
+            // Record expression: a closed record of the field types
+            Expr::Record { fields, .. } => {
+                let mut field_tys = BTreeMap::new();
+                for field in fields {
+                    let ty = self.infer_expr_ctx(ctx, &field.value)?;
+                    if field_tys.insert(field.name.text.clone(), ty).is_some() {
+                        return Err(InferError::DuplicateField {
+                            field: field.name.text.clone(),
+                            span: field.name.span,
+                        });
+                    }
+                }
+                Ok(Ty::Record(field_tys, None))
+            }
+
+            // Field access: the operand is any record with at least `field`
+            Expr::Field { expr, field, span } => {
+                let record_ty = self.infer_expr_ctx(ctx, expr)?;
+                let field_ty = self.fresh_var();
+                let rest = self.fresh_var_id();
+                let fields = BTreeMap::from([(field.text.clone(), field_ty.clone())]);
+                self.add_constraint(Constraint::Equal(
+                    record_ty,
+                    Ty::Record(fields, Some(rest)),
+                    *span,
+                ));
+                Ok(field_ty)
+            }
+
             // Struct expression
             Expr::StructExpr { path, fields, span } => {
                 self.infer_struct_expr(ctx, path, fields, *span)
@@ -460,8 +911,13 @@ impl InferCtx {
         // Create a child context for this block scope
         let mut block_ctx = ctx.child();
 
-        // Process each statement in order
-        for stmt in &block.stmts {
+        // Process each statement in order. A group of nested functions is
+        // bound before any of their bodies, so they can call each other.
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            for decl in block.fn_group_at(i) {
+                let fn_ty = self.local_fn_sig(decl)?;
+                block_ctx.bind(decl.name.text.clone(), Scheme::mono(fn_ty), false);
+            }
             self.infer_stmt(&mut block_ctx, stmt)?;
         }
 
@@ -508,12 +964,18 @@ impl InferCtx {
                     });
                 }
 
-                // If there's a type annotation, add constraint
+                // If there's a type annotation, the value must fit it, and
+                // the binding has the annotated type (which, for a function,
+                // may allow more effects than the value performs)
                 // (only allowed for simple identifier patterns)
-                if let Some(ann_ty) = ty {
-                    let expected = ty_from_type_expr(ann_ty)?;
-                    self.add_constraint(Constraint::Equal(value_ty.clone(), expected, *span));
-                }
+                let value_ty = match ty {
+                    Some(ann_ty) => {
+                        let expected = ty_from_type_expr(ann_ty)?;
+                        self.add_constraint(Constraint::Subsume(expected.clone(), value_ty, *span));
+                        expected
+                    }
+                    None => value_ty,
+                };
 
                 // Check that the pattern is irrefutable
                 if !is_irrefutable(ctx, pat) {
@@ -542,6 +1004,7 @@ impl InferCtx {
                 //   let id = fn(x) { x }; // id is polymorphic as a fn decl
                 //   let x = id(1);        // x: Int
                 //   let y = id(true);     // y: Bool
+                self.let_bindings.extend(bindings.iter().cloned());
                 for binding in bindings {
                     ctx.bind(binding.name, Scheme::mono(binding.ty), *mutable);
                 }
@@ -549,6 +1012,26 @@ impl InferCtx {
                 Ok(())
             }
 
+            Stmt::Declare { name, ty, span } => {
+                // No value to infer from: the annotation is the type. Reads
+                // before assignment are caught by the init_check pass.
+                let declared = ty_from_type_expr(ty)?;
+                if !declared.is_first_class() {
+                    return Err(InferError::RefEscape {
+                        ty: declared,
+                        context: "let binding".to_string(),
+                        span: *span,
+                    });
+                }
+                self.let_bindings.push(PatternBinding {
+                    name: name.text.clone(),
+                    ty: declared.clone(),
+                    span: name.span,
+                });
+                ctx.bind(name.text.clone(), Scheme::mono(declared), true);
+                Ok(())
+            }
+
             Stmt::Assign {
                 target,
                 value,
@@ -563,6 +1046,13 @@ impl InferCtx {
                             span: target.span,
                         })?;
 
+                if ctx.shared_mutable.contains(&target.text) {
+                    return Err(InferError::SpawnCapturesMutable {
+                        name: target.text.clone(),
+                        span: *span,
+                    });
+                }
+
                 // Check mutability
                 let is_mutable = ctx.is_mutable(&target.text).unwrap_or(false);
                 if !is_mutable {
@@ -575,9 +1065,9 @@ impl InferCtx {
                 // Infer value type
                 let value_ty = self.infer_expr_ctx(ctx, value)?;
 
-                // Constrain value type to match target type
+                // Constrain value type to fit the target type
                 let target_ty = self.instantiate_scheme(target_scheme)?;
-                self.add_constraint(Constraint::Equal(value_ty, target_ty, *span));
+                self.add_constraint(Constraint::Subsume(target_ty, value_ty, *span));
 
                 Ok(())
             }
@@ -595,7 +1085,7 @@ impl InferCtx {
                 if let Some(val_expr) = value {
                     // return expr; - infer expr type and constrain to expected return
                     let val_ty = self.infer_expr_ctx(ctx, val_expr)?;
-                    self.add_constraint(Constraint::Equal(val_ty, expected_ret, *span));
+                    self.add_constraint(Constraint::Subsume(expected_ret, val_ty, *span));
                 } else {
                     // return; - constrain Unit to expected return
                     self.add_constraint(Constraint::Equal(Ty::unit(), expected_ret, *span));
@@ -603,9 +1093,68 @@ impl InferCtx {
 
                 Ok(())
             }
+
+            // Bound with its group by `infer_block`
+            Stmt::Fn(decl) => {
+                let fn_ty = self.local_fn_sig(decl)?;
+                let Ty::Arrow(param_tys, ret_ty, declared_eff) = &fn_ty else {
+                    return Err(InferError::NotImplemented {
+                        msg: format!(
+                            "nested function '{}' has a non-function type",
+                            decl.name.text
+                        ),
+                        span: decl.span,
+                    });
+                };
+
+                // Like a top-level fn: its own return type and effect row,
+                // with captured variables readable but not assignable
+                let body_eff = self.fresh_effect_var()?;
+                let mut fn_ctx = ctx.child();
+                fn_ctx.mutability.values_mut().for_each(|m| *m = false);
+                fn_ctx.expected_return = Some(ret_ty.as_ref().clone());
+                fn_ctx.body_effects = Some(body_eff);
+                for (param, param_ty) in decl.params.iter().zip(param_tys) {
+                    fn_ctx.bind(
+                        param.name.text.clone(),
+                        Scheme::mono(param_ty.clone()),
+                        false,
+                    );
+                }
+
+                let body_ty = self.infer_block(&fn_ctx, &decl.body)?;
+                if body_ty != Ty::Never {
+                    self.add_constraint(Constraint::Subsume(
+                        ret_ty.as_ref().clone(),
+                        body_ty,
+                        decl.span,
+                    ));
+                }
+                self.add_constraint(Constraint::EffectSubset(body_eff, *declared_eff, decl.span));
+
+                // Monomorphic, like block-level lets: its type is not solved
+                // until the enclosing item is
+                self.let_bindings.push(PatternBinding {
+                    name: decl.name.text.clone(),
+                    ty: fn_ty.clone(),
+                    span: decl.name.span,
+                });
+                Ok(())
+            }
         }
     }
 
+    /// The signature the checker predeclared for a nested function
+    fn local_fn_sig(&self, decl: &FnDecl) -> Result<Ty, InferError> {
+        self.local_fn_sigs
+            .get(&decl.span)
+            .cloned()
+            .ok_or_else(|| InferError::NotImplemented {
+                msg: format!("nested function '{}' was not predeclared", decl.name.text),
+                span: decl.span,
+            })
+    }
+
     /// Infer the type of an if expression
     fn infer_if(
         &mut self,
@@ -669,6 +1218,46 @@ impl InferCtx {
         Ok(Ty::unit())
     }
 
+    /// Infer type of a for loop: `iter` is an `Iter<T>` and `pat` an
+    /// irrefutable pattern binding each `T`
+    fn infer_for(
+        &mut self,
+        ctx: &CheckContext,
+        pat: &Pat,
+        iter: &Expr,
+        body: &Block,
+        span: Span,
+    ) -> Result<Ty, InferError> {
+        let iter_ty = self.infer_expr_ctx(ctx, iter)?;
+        let item_ty = self.fresh_var();
+        self.add_constraint(Constraint::Equal(
+            iter_ty,
+            Ty::adt("Iter", vec![item_ty.clone()]),
+            span,
+        ));
+
+        if !is_irrefutable(ctx, pat) {
+            return Err(InferError::RefutablePattern {
+                pat_desc: Self::refutable_pattern_desc(pat),
+                span: pat.span(),
+            });
+        }
+        let bindings = self.check_pattern(ctx, pat, &item_ty)?;
+        self.check_duplicate_bindings(&bindings)?;
+
+        let mut body_ctx = ctx.child();
+        self.let_bindings.extend(bindings.iter().cloned());
+        for binding in bindings {
+            body_ctx.bind(binding.name, Scheme::mono(binding.ty), false);
+        }
+
+        // Infer body type (discarded)
+        let _ = self.infer_block(&body_ctx, body)?;
+
+        // Like while, a for loop returns Unit
+        Ok(Ty::unit())
+    }
+
     /// Infer type of a literal
     fn infer_lit(&self, lit: &Lit) -> Ty {
         match lit {
@@ -725,14 +1314,16 @@ impl InferCtx {
                 Ok(Ty::int())
             }
 
-            // Comparison: both Int or both Float, returns Bool
+            // Comparison: both the same orderable type, returns Bool
             BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                self.comparisons.push((lhs_ty.clone(), true, span));
                 self.add_constraint(Constraint::Equal(lhs_ty, rhs_ty, span));
                 Ok(Ty::bool_())
             }
 
-            // Equality: both same type, returns Bool
+            // Equality: both the same equatable type, returns Bool
             BinOp::Eq | BinOp::Ne => {
+                self.comparisons.push((lhs_ty.clone(), false, span));
                 self.add_constraint(Constraint::Equal(lhs_ty, rhs_ty, span));
                 Ok(Ty::bool_())
             }
@@ -756,8 +1347,11 @@ impl InferCtx {
         arms: &[MatchArm],
         span: Span,
     ) -> Result<Ty, InferError> {
-        // Infer scrutinee type
-        let scrutinee_ty = self.infer_expr_ctx(ctx, scrutinee)?;
+        // Infer scrutinee type: `match &x` matches what `x` holds
+        let scrutinee_ty = match scrutinee {
+            Expr::Borrow(inner, _) => self.infer_expr_ctx(ctx, inner)?,
+            _ => self.infer_expr_ctx(ctx, scrutinee)?,
+        };
 
         // Special case: matching on Never - empty match is valid, returns Never
         if scrutinee_ty == Ty::Never {
@@ -809,12 +1403,31 @@ impl InferCtx {
                 // Skip exhaustiveness check - scrutinee type not yet resolved
                 return Ok(result_ty.unwrap_or(Ty::Never));
             }
-            match exhaustive::check_match(arms, &scrutinee_ty, registry, span) {
+            match exhaustive::check_match(
+                arms,
+                &scrutinee_ty,
+                registry,
+                &ctx.consts,
+                &self.limits,
+                span,
+            ) {
                 Ok((witness_opt, redundant)) => {
                     // Check for non-exhaustive match
                     if let Some(witness) = witness_opt {
+                        // The full list is best-effort: hitting a limit while
+                        // collecting it still reports the first witness
+                        let missing = exhaustive::missing_patterns(
+                            arms,
+                            &scrutinee_ty,
+                            registry,
+                            &ctx.consts,
+                            &self.limits,
+                            span,
+                        )
+                        .unwrap_or_default();
                         return Err(InferError::NonExhaustiveMatch {
                             witness: format!("{}", witness),
+                            missing,
                             span,
                         });
                     }
@@ -843,6 +1456,7 @@ impl InferCtx {
                 Err(ExhaustivenessError::NonExhaustive { witness, span }) => {
                     return Err(InferError::NonExhaustiveMatch {
                         witness: format!("{}", witness),
+                        missing: Vec::new(),
                         span,
                     });
                 }
@@ -863,11 +1477,19 @@ impl InferCtx {
         match pat {
             Pat::Wildcard(_) => Ok(vec![]),
 
-            Pat::Ident(ident) => Ok(vec![PatternBinding {
-                name: ident.text.clone(),
-                ty: expected.clone(),
-                span: ident.span,
-            }]),
+            // A const name matches the const's value, like a literal
+            Pat::Ident(ident) => match ctx.consts.get(&ident.text) {
+                Some(value) => {
+                    let lit_ty = self.infer_lit(&value.to_lit());
+                    self.add_constraint(Constraint::Equal(lit_ty, expected.clone(), ident.span));
+                    Ok(vec![])
+                }
+                None => Ok(vec![PatternBinding {
+                    name: ident.text.clone(),
+                    ty: expected.clone(),
+                    span: ident.span,
+                }]),
+            },
 
             Pat::Literal(lit, span) => {
                 let lit_ty = self.infer_lit(lit);
@@ -875,6 +1497,46 @@ impl InferCtx {
                 Ok(vec![])
             }
 
+            Pat::Range { span, .. } => {
+                if pat.range_bounds().is_none() {
+                    return Err(InferError::EmptyRangePattern { span: *span });
+                }
+                self.add_constraint(Constraint::Equal(Ty::int(), expected.clone(), *span));
+                Ok(vec![])
+            }
+
+            // The remainder after the prefix is itself a String
+            Pat::StrPrefix { rest, span, .. } => {
+                self.add_constraint(Constraint::Equal(Ty::string(), expected.clone(), *span));
+                self.check_pattern(ctx, rest, &Ty::string())
+            }
+
+            // Fresh types for the named fields; `..` leaves the row open
+            Pat::Record { fields, rest, span } => {
+                let mut field_tys = BTreeMap::new();
+                let mut bindings = vec![];
+                for field in fields {
+                    let ty = self.fresh_var();
+                    if field_tys
+                        .insert(field.name.text.clone(), ty.clone())
+                        .is_some()
+                    {
+                        return Err(InferError::DuplicateField {
+                            field: field.name.text.clone(),
+                            span: field.name.span,
+                        });
+                    }
+                    bindings.extend(self.check_pattern(ctx, &field.pat, &ty)?);
+                }
+                let tail = rest.then(|| self.fresh_var_id());
+                self.add_constraint(Constraint::Equal(
+                    Ty::Record(field_tys, tail),
+                    expected.clone(),
+                    *span,
+                ));
+                Ok(bindings)
+            }
+
             Pat::Tuple(pats, span) => {
                 // Expected must be Tuple of same arity
                 match expected {
@@ -915,18 +1577,21 @@ impl InferCtx {
 
                 let scheme = ctx.env.get(&ctor_name).ok_or_else(|| {
                     // Try to provide a better error message
-                    let parts: Vec<&str> = ctor_name.split("::").collect();
-                    if parts.len() >= 2 {
-                        InferError::UnknownVariant {
-                            type_name: parts[..parts.len() - 1].join("::"),
-                            variant: parts[parts.len() - 1].to_string(),
-                            span: *span,
+                    match path.segments.split_last() {
+                        Some((variant, type_path)) if !type_path.is_empty() => {
+                            InferError::UnknownVariant {
+                                type_name: path_to_string(&Path {
+                                    segments: type_path.to_vec(),
+                                    span: path.span,
+                                }),
+                                variant: variant.text.clone(),
+                                span: *span,
+                            }
                         }
-                    } else {
-                        InferError::UnknownVariable {
+                        _ => InferError::UnknownVariable {
                             name: ctor_name.clone(),
                             span: *span,
-                        }
+                        },
                     }
                 })?;
 
@@ -1058,6 +1723,9 @@ impl InferCtx {
     fn refutable_pattern_desc(pat: &Pat) -> String {
         match pat {
             Pat::Literal(lit, _) => format!("literal pattern `{:?}`", lit),
+            Pat::Ident(ident) => format!("constant `{}`", ident.text),
+            Pat::Range { .. } => "range pattern".to_string(),
+            Pat::StrPrefix { prefix, .. } => format!("string prefix pattern `{:?} + ...`", prefix),
             Pat::Variant { path, .. } => {
                 let name = path
                     .segments
@@ -1090,11 +1758,9 @@ impl InferCtx {
         elems: &[Expr],
         span: Span,
     ) -> Result<Ty, InferError> {
-        const MAX_TUPLE_ARITY: usize = 8;
-
-        if elems.len() > MAX_TUPLE_ARITY {
+        if elems.len() > self.limits.tuple_arity {
             return Err(InferError::TupleArityLimit {
-                max: MAX_TUPLE_ARITY,
+                max: self.limits.tuple_arity,
                 found: elems.len(),
                 span,
             });
@@ -1191,9 +1857,10 @@ impl InferCtx {
             self.add_constraint(Constraint::Equal(value_ty, expected_ty, field.span));
         }
 
-        // Check all required fields are provided
+        // Check all required fields are provided; a defaulted one may be
+        // left off
         for field_def in struct_fields {
-            if !seen_fields.contains(&field_def.name) {
+            if !seen_fields.contains(&field_def.name) && field_def.default.is_none() {
                 return Err(InferError::MissingField {
                     struct_name: struct_name.clone(),
                     field: field_def.name.clone(),
@@ -1232,14 +1899,38 @@ pub struct PatternBinding {
 
 /// Check if a pattern is irrefutable (always matches).
 /// Irrefutable patterns are required for let bindings.
+/// Check that a partial application can hold on to `callee`: a function,
+/// or a binding or expression holding one. Builtins and constructors are
+/// only applied where they are called.
+fn check_partially_applicable(ctx: &CheckContext, callee: &Expr) -> Result<(), InferError> {
+    let (name, def, span) = match callee {
+        Expr::Var(id) => (id.text.clone(), ctx.resolution.ident(id), id.span),
+        Expr::PathExpr(path) => (path_to_string(path), ctx.resolution.path(path), path.span),
+        _ => return Ok(()),
+    };
+    match def {
+        Some(DefId::Fn(_) | DefId::Extern(_) | DefId::Local(_) | DefId::Global(_)) => Ok(()),
+        _ => Err(InferError::NotPartiallyApplicable { callee: name, span }),
+    }
+}
+
 fn is_irrefutable(ctx: &CheckContext, pat: &Pat) -> bool {
     match pat {
         // Wildcard and identifier patterns always match
-        Pat::Wildcard(_) | Pat::Ident(_) => true,
+        Pat::Wildcard(_) => true,
+
+        // ...unless the identifier names a const, which is a literal
+        Pat::Ident(ident) => !ctx.consts.contains_key(&ident.text),
 
         // Literals are always refutable (match specific values)
         Pat::Literal(_, _) => false,
 
+        // Ranges are refutable unless they span every Int
+        Pat::Range { .. } => pat.range_bounds() == Some((i64::MIN, i64::MAX)),
+
+        // Every string starts with ""
+        Pat::StrPrefix { prefix, rest, .. } => prefix.is_empty() && is_irrefutable(ctx, rest),
+
         // Tuple patterns are irrefutable if all sub-patterns are irrefutable
         Pat::Tuple(pats, _) => pats.iter().all(|p| is_irrefutable(ctx, p)),
 
@@ -1247,23 +1938,26 @@ fn is_irrefutable(ctx: &CheckContext, pat: &Pat) -> bool {
         // Sub-patterns must also be irrefutable
         Pat::Struct { fields, .. } => fields.iter().all(|f| is_irrefutable(ctx, &f.pat)),
 
-        // Variant patterns are only irrefutable if the enum has exactly one variant
+        // Likewise record patterns
+        Pat::Record { fields, .. } => fields.iter().all(|f| is_irrefutable(ctx, &f.pat)),
+
+        // Variant patterns are only irrefutable if the enum has exactly one
+        // variant, or they name a tuple struct
         Pat::Variant { path, fields, .. } => {
-            // Extract enum name from path (first segment)
-            if let Some(enum_name) = path.segments.first() {
-                if let Some(registry) = &ctx.adt_registry {
-                    if let Some(adt) = registry.get(&enum_name.text) {
-                        if let Some(variants) = adt.variants() {
-                            // Single-variant enum is irrefutable
-                            if variants.len() == 1 {
-                                // All sub-patterns must also be irrefutable
-                                return fields.iter().all(|p| is_irrefutable(ctx, p));
-                            }
-                        }
-                    }
-                }
-            }
-            false
+            let only_ctor = match ctx.resolution.path(path) {
+                Some(DefId::Ctor { variant: None, .. }) => true,
+                Some(DefId::Ctor {
+                    adt,
+                    variant: Some(_),
+                }) => ctx
+                    .adt_registry
+                    .as_ref()
+                    .and_then(|registry| registry.get(adt)?.variants())
+                    .is_some_and(|variants| variants.len() == 1),
+                _ => false,
+            };
+            // All sub-patterns must also be irrefutable
+            only_ctor && fields.iter().all(|p| is_irrefutable(ctx, p))
         }
     }
 }
@@ -1291,6 +1985,13 @@ fn substitute_type_vars(ty: &Ty, subst: &HashMap<TypeVarId, Ty>) -> Ty {
             *eff,
         ),
         Ty::Tuple(tys) => Ty::Tuple(tys.iter().map(|t| substitute_type_vars(t, subst)).collect()),
+        Ty::Record(fields, tail) => Ty::record_with_tail(
+            fields
+                .iter()
+                .map(|(name, t)| (name.clone(), substitute_type_vars(t, subst)))
+                .collect(),
+            tail.map(|v| substitute_type_vars(&Ty::Var(v), subst)),
+        ),
         Ty::List(t) => Ty::List(Box::new(substitute_type_vars(t, subst))),
         Ty::Adt { name, args } => Ty::Adt {
             name: name.clone(),
@@ -1328,12 +2029,27 @@ fn ty_from_type_expr(te: &strata_ast::ast::TypeExpr) -> Result<Ty, InferError> {
                 }
             }
         }
-        TypeExpr::Arrow { params, ret, .. } => {
+        TypeExpr::Arrow {
+            params,
+            ret,
+            effects,
+            ..
+        } => {
             let param_tys: Result<Vec<Ty>, InferError> =
                 params.iter().map(ty_from_type_expr).collect();
             let param_tys = param_tys?;
             let ret_ty = ty_from_type_expr(ret)?;
-            Ok(Ty::arrow(param_tys, ret_ty))
+            // An unannotated function type is pure
+            let mut eff = EffectRow::pure();
+            for ident in effects.iter().flatten() {
+                let effect =
+                    Effect::from_name(&ident.text).ok_or_else(|| InferError::UnknownEffect {
+                        name: ident.text.clone(),
+                        span: ident.span,
+                    })?;
+                eff.insert(effect);
+            }
+            Ok(Ty::arrow_eff(param_tys, ret_ty, eff))
         }
         // Generic type annotations in block-level let bindings not yet supported.
         // Workaround: rely on type inference. See Known Limitations in IMPLEMENTED.md.
@@ -1353,9 +2069,22 @@ fn ty_from_type_expr(te: &strata_ast::ast::TypeExpr) -> Result<Ty, InferError> {
             msg: "Tuple types not yet implemented".to_string(),
             span: *span,
         }),
-        // Reference types are only allowed in extern function parameters
+        TypeExpr::Record(fields, _) => {
+            let mut field_tys = BTreeMap::new();
+            for field in fields {
+                let ty = ty_from_type_expr(&field.ty)?;
+                if field_tys.insert(field.name.text.clone(), ty).is_some() {
+                    return Err(InferError::DuplicateField {
+                        field: field.name.text.clone(),
+                        span: field.name.span,
+                    });
+                }
+            }
+            Ok(Ty::Record(field_tys, None))
+        }
+        // Reference types are only allowed in function parameters
         TypeExpr::Ref(_, span) => Err(InferError::NotImplemented {
-            msg: "Reference types (&T) are only allowed in extern function parameters".to_string(),
+            msg: "Reference types (&T) are only allowed in function parameters".to_string(),
             span: *span,
         }),
     }
@@ -1391,7 +2120,7 @@ mod tests {
         let mut ctx = InferCtx::new();
         let t1 = Ty::int();
         let t2 = Ty::bool_();
-        let span = Span { start: 0, end: 0 };
+        let span = Span::default();
 
         ctx.add_constraint(Constraint::Equal(t1.clone(), t2.clone(), span));
         ctx.add_constraint(Constraint::Equal(t2.clone(), t1.clone(), span));
@@ -1447,7 +2176,14 @@ mod tests {
         let mut ctx = InferCtx::new();
         let env = HashMap::new();
 
-        let expr = Expr::Lit(Lit::Int(42), Span { start: 0, end: 2 });
+        let expr = Expr::Lit(
+            Lit::Int(42),
+            Span {
+                start: 0,
+                end: 2,
+                ..Span::default()
+            },
+        );
         let ty = ctx.infer_expr(&env, &expr).unwrap();
 
         assert_eq!(ty, Ty::int());
@@ -1460,10 +2196,28 @@ mod tests {
 
         // 1 + 2
         let expr = Expr::Binary {
-            lhs: Box::new(Expr::Lit(Lit::Int(1), Span { start: 0, end: 1 })),
+            lhs: Box::new(Expr::Lit(
+                Lit::Int(1),
+                Span {
+                    start: 0,
+                    end: 1,
+                    ..Span::default()
+                },
+            )),
             op: BinOp::Add,
-            rhs: Box::new(Expr::Lit(Lit::Int(2), Span { start: 4, end: 5 })),
-            span: Span { start: 0, end: 5 },
+            rhs: Box::new(Expr::Lit(
+                Lit::Int(2),
+                Span {
+                    start: 4,
+                    end: 5,
+                    ..Span::default()
+                },
+            )),
+            span: Span {
+                start: 0,
+                end: 5,
+                ..Span::default()
+            },
         };
 
         let ty = ctx.infer_expr(&env, &expr).unwrap();
@@ -1479,8 +2233,15 @@ mod tests {
         let env = HashMap::new();
 
         // Create a deeply nested unary expression: !!!!!...!true (200 levels)
-        // Must exceed MAX_INFER_DEPTH (128) but stay small enough for CI stacks
-        let mut expr = Expr::Lit(Lit::Bool(true), Span { start: 0, end: 4 });
+        // Must exceed the default inference depth limit (128) but stay small enough for CI stacks
+        let mut expr = Expr::Lit(
+            Lit::Bool(true),
+            Span {
+                start: 0,
+                end: 4,
+                ..Span::default()
+            },
+        );
         for i in 0..200 {
             expr = Expr::Unary {
                 op: strata_ast::ast::UnOp::Not,
@@ -1488,6 +2249,7 @@ mod tests {
                 span: Span {
                     start: i,
                     end: i + 1,
+                    ..Span::default()
                 },
             };
         }
@@ -1502,7 +2264,14 @@ mod tests {
         let env = HashMap::new();
 
         // Create moderately nested unary expression: !!!!!...!true (100 levels)
-        let mut expr = Expr::Lit(Lit::Bool(true), Span { start: 0, end: 4 });
+        let mut expr = Expr::Lit(
+            Lit::Bool(true),
+            Span {
+                start: 0,
+                end: 4,
+                ..Span::default()
+            },
+        );
         for i in 0..100 {
             expr = Expr::Unary {
                 op: strata_ast::ast::UnOp::Not,
@@ -1510,6 +2279,7 @@ mod tests {
                 span: Span {
                     start: i,
                     end: i + 1,
+                    ..Span::default()
                 },
             };
         }
@@ -15,16 +15,30 @@ pub struct SolveError {
     pub error: TypeError,
     /// The span of the constraint that failed
     pub span: Span,
+    /// For an effect row too small for another, the span of the constraint
+    /// that first brought the missing effect into it (the call, when the
+    /// row is a function body's), if it came in through another constraint
+    pub origin: Option<Span>,
 }
 
 impl std::fmt::Display for SolveError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} at {:?}", self.error, self.span)
+        write!(f, "{}", self.error)
     }
 }
 
 impl std::error::Error for SolveError {}
 
+/// What solving an item's constraints took
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolveStats {
+    /// Constraints inference generated
+    pub constraints: usize,
+    /// Steps the solver took over them: unifications, and visits of effect
+    /// constraints
+    pub steps: usize,
+}
+
 /// Constraint solver
 pub struct Solver {
     unifier: Unifier,
@@ -38,11 +52,46 @@ impl Solver {
         }
     }
 
+    /// A solver whose fresh variables start at `next_var`; see
+    /// [`Unifier::with_next_var`]
+    pub fn with_next_var(next_var: u32) -> Self {
+        Solver {
+            unifier: Unifier::with_next_var(next_var),
+        }
+    }
+
+    /// Fail with a step limit error once solving has taken more than
+    /// `limit` steps: unifications, and visits of effect constraints
+    pub fn with_step_limit(mut self, limit: usize) -> Self {
+        self.unifier = self.unifier.with_step_limit(limit);
+        self
+    }
+
+    /// Id of the next fresh variable solving would make
+    pub fn next_var(&self) -> u32 {
+        self.unifier.next_var()
+    }
+
+    /// Steps solving has taken so far
+    pub fn steps(&self) -> usize {
+        self.unifier.steps()
+    }
+
+    /// Count one step of effect solving against the budget
+    fn step(&mut self, span: Span) -> Result<(), SolveError> {
+        self.unifier.step().map_err(|error| SolveError {
+            error,
+            span,
+            origin: None,
+        })
+    }
+
     /// Helper: convert a SubstError to a SolveError with a span
     fn subst_err(&self, err: SubstError, span: Span) -> SolveError {
         SolveError {
             error: TypeError::from(err),
             span,
+            origin: None,
         }
     }
 
@@ -77,13 +126,13 @@ impl Solver {
     /// Phase B: resolve effect subset constraints by accumulating required effects
     ///          for each effect variable to a fixpoint, then checking all constraints.
     pub fn solve(&mut self, constraints: Vec<Constraint>) -> Result<Subst, SolveError> {
-        // Separate equality and effect constraints
+        // Separate equality (and subsumption) and effect constraints
         let mut equalities = Vec::new();
         let mut effect_subsets = Vec::new();
 
         for constraint in constraints {
             match constraint {
-                Constraint::Equal(..) => equalities.push(constraint),
+                Constraint::Equal(..) | Constraint::Subsume(..) => equalities.push(constraint),
                 Constraint::EffectSubset(..) => effect_subsets.push(constraint),
             }
         }
@@ -92,9 +141,20 @@ impl Solver {
         for constraint in equalities {
             match constraint {
                 Constraint::Equal(t1, t2, span) => {
+                    self.unifier.unify(&t1, &t2).map_err(|error| SolveError {
+                        error,
+                        span,
+                        origin: None,
+                    })?;
+                }
+                Constraint::Subsume(expected, actual, span) => {
                     self.unifier
-                        .unify(&t1, &t2)
-                        .map_err(|error| SolveError { error, span })?;
+                        .subsume(&expected, &actual)
+                        .map_err(|error| SolveError {
+                            error,
+                            span,
+                            origin: None,
+                        })?;
                 }
                 Constraint::EffectSubset(..) => {
                     // Filtered above; this arm satisfies exhaustiveness without unreachable!()
@@ -115,6 +175,7 @@ impl Solver {
         // Step 1: Bind open-open effect tails
         for constraint in &effect_subsets {
             if let Constraint::EffectSubset(sub, sup, span) = constraint {
+                self.step(*span)?;
                 let sub_resolved = self
                     .unifier
                     .subst()
@@ -136,6 +197,7 @@ impl Solver {
                                     found: sub_resolved,
                                 },
                                 span: *span,
+                                origin: None,
                             });
                         }
                         continue;
@@ -153,6 +215,7 @@ impl Solver {
                         return Err(SolveError {
                             error: TypeError::EffectCycle { var: sup_tail },
                             span: *span,
+                            origin: None,
                         });
                     }
                     self.unifier.subst_mut().insert_effect(sup_tail, target_row);
@@ -164,6 +227,9 @@ impl Solver {
         use std::collections::HashMap;
 
         let mut required: HashMap<EffectVarId, u64> = HashMap::new();
+        // Where each required effect of a variable came from: the constraint
+        // whose `sub` had it concretely, followed back through variables
+        let mut origins: HashMap<(EffectVarId, u64), Span> = HashMap::new();
 
         // Iterate to fixpoint: subset constraints may chain (a <= b <= c),
         // so resolving one may reveal requirements for another.
@@ -177,13 +243,14 @@ impl Solver {
                 Constraint::EffectSubset(_, _, span) => Some(*span),
                 _ => None,
             })
-            .unwrap_or(Span { start: 0, end: 0 });
+            .unwrap_or(Span::default());
 
         for _ in 0..MAX_EFFECT_ITERATIONS {
             let mut changed = false;
 
             for constraint in &effect_subsets {
                 if let Constraint::EffectSubset(sub, sup, span) = constraint {
+                    self.step(*span)?;
                     // Resolve sub through the unifier's substitution
                     let sub_resolved = self
                         .unifier
@@ -221,6 +288,15 @@ impl Solver {
 
                     if needed != 0 {
                         if let Some(sup_var) = sup_canonical_tail {
+                            for bit in bits(needed) {
+                                let from = match sub_canonical_tail {
+                                    Some(var) if sub_resolved.concrete & bit == 0 => {
+                                        origins.get(&(var, bit)).copied().unwrap_or(*span)
+                                    }
+                                    _ => *span,
+                                };
+                                origins.entry((sup_var, bit)).or_insert(from);
+                            }
                             let entry = required.entry(sup_var).or_insert(0);
                             let old = *entry;
                             *entry |= needed;
@@ -247,6 +323,7 @@ impl Solver {
                     depth: MAX_EFFECT_ITERATIONS,
                 },
                 span: first_effect_span,
+                origin: None,
             });
         }
 
@@ -276,12 +353,21 @@ impl Solver {
                 };
 
                 // Check: sub's effects must be a subset of sup's effects
-                if (sub_concrete & !sup_concrete) != 0 {
+                let missing = sub_concrete & !sup_concrete;
+                if missing != 0 {
                     let found = EffectRow::closed(sub_concrete);
                     let expected = EffectRow::closed(sup_concrete);
+                    let first = missing & missing.wrapping_neg();
+                    let origin = match sub_canonical_tail {
+                        Some(var) if sub_resolved.concrete & first == 0 => {
+                            origins.get(&(var, first)).copied()
+                        }
+                        _ => None,
+                    };
                     return Err(SolveError {
                         error: TypeError::EffectMismatch { expected, found },
                         span: *span,
+                        origin,
                     });
                 }
             }
@@ -301,6 +387,13 @@ impl Solver {
     }
 }
 
+/// Each set bit of `mask`, lowest first
+fn bits(mask: u64) -> impl Iterator<Item = u64> {
+    (0..u64::BITS)
+        .map(|i| 1u64 << i)
+        .filter(move |bit| mask & bit != 0)
+}
+
 impl Default for Solver {
     fn default() -> Self {
         Self::new()
@@ -321,7 +414,7 @@ mod tests {
         let constraints = vec![Constraint::Equal(
             Ty::Var(TypeVarId(0)),
             Ty::int(),
-            Span { start: 0, end: 0 },
+            Span::default(),
         )];
 
         let subst = solver.solve(constraints).unwrap();
@@ -340,9 +433,9 @@ mod tests {
             Constraint::Equal(
                 Ty::Var(TypeVarId(0)),
                 Ty::Var(TypeVarId(1)),
-                Span { start: 0, end: 0 },
+                Span::default(),
             ),
-            Constraint::Equal(Ty::Var(TypeVarId(1)), Ty::int(), Span { start: 0, end: 0 }),
+            Constraint::Equal(Ty::Var(TypeVarId(1)), Ty::int(), Span::default()),
         ];
 
         let subst = solver.solve(constraints).unwrap();
@@ -357,11 +450,7 @@ mod tests {
         let mut solver = Solver::new();
 
         // Constraint: Int = Bool (impossible!)
-        let constraints = vec![Constraint::Equal(
-            Ty::int(),
-            Ty::bool_(),
-            Span { start: 0, end: 0 },
-        )];
+        let constraints = vec![Constraint::Equal(Ty::int(), Ty::bool_(), Span::default())];
 
         let result = solver.solve(constraints);
         assert!(result.is_err());
@@ -1,6 +1,6 @@
 use super::ty::{Ty, TypeVarId};
 use crate::effects::{EffectRow, EffectVarId};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// Errors that can occur during substitution application
 #[derive(Debug, Clone)]
@@ -177,6 +177,15 @@ impl Subst {
                     xs.iter().map(|x| self.apply(x)).collect();
                 Ok(Ty::tuple(new_xs?))
             }
+            Ty::Record(fields, tail) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.clone(), self.apply(ty)?)))
+                    .collect::<Result<BTreeMap<_, _>, SubstError>>()?;
+                // A bound tail contributes the fields unification found for it
+                let tail = tail.map(|v| self.apply(&Ty::Var(v))).transpose()?;
+                Ok(Ty::record_with_tail(fields, tail))
+            }
             Ty::List(x) => Ok(Ty::list(self.apply(x)?)),
             Ty::Adt { name, args } => {
                 let new_args: Result<Vec<Ty>, SubstError> =
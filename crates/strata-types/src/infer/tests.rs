@@ -233,3 +233,70 @@ fn test_adt_nested() {
     u.unify(&nested1, &nested2).unwrap();
     assert_eq!(u.subst().apply(&v).unwrap(), Ty::int());
 }
+
+/// Two open rows share what they learn about each other through one tail
+#[test]
+fn test_open_records_merge_their_fields() {
+    let mut u = Unifier::new();
+    let (ra, rb) = (TypeVarId(0), TypeVarId(1));
+    let a = Ty::Record([("x".to_string(), Ty::int())].into(), Some(ra));
+    let b = Ty::Record([("y".to_string(), Ty::bool_())].into(), Some(rb));
+    u.unify(&a, &b).unwrap();
+
+    let merged_a = u.subst().apply(&a).unwrap();
+    let merged_b = u.subst().apply(&b).unwrap();
+    assert_eq!(merged_a, merged_b);
+    assert_eq!(merged_a.to_string(), "{x: Int, y: Bool, ..}");
+
+    // Closing the row fixes it to exactly these fields
+    let closed = Ty::record([("x", Ty::int()), ("y", Ty::bool_())]);
+    u.unify(&merged_a, &closed).unwrap();
+    assert_eq!(u.subst().apply(&b).unwrap(), closed);
+}
+
+/// A closed record can't gain a field an open one needs
+#[test]
+fn test_closed_record_lacks_field() {
+    let mut u = Unifier::new();
+    let closed = Ty::record([("x", Ty::int())]);
+    let open = Ty::Record([("y".to_string(), Ty::int())].into(), Some(TypeVarId(0)));
+    let err = u.unify(&closed, &open).unwrap_err();
+    assert!(matches!(err, TypeError::NoSuchField { ref field, .. } if field == "y"));
+    assert!(matches!(
+        u.unify(&closed, &Ty::record([("y", Ty::int())])),
+        Err(TypeError::Mismatch(..))
+    ));
+}
+
+/// A function may do less than the type it is used as allows, and its
+/// parameters are compared the other way round
+#[test]
+fn test_subsume_widens_closed_effect_rows() {
+    use crate::effects::{Effect, EffectRow};
+    let row = |effects: &[Effect]| {
+        let mut row = EffectRow::pure();
+        for e in effects {
+            row.insert(*e);
+        }
+        row
+    };
+    let f = |eff| Ty::arrow_eff(vec![Ty::int()], Ty::int(), eff);
+    let (pure, fs) = (f(row(&[])), f(row(&[Effect::Fs])));
+
+    let mut u = Unifier::new();
+    u.subsume(&fs, &pure).unwrap();
+    assert!(matches!(
+        u.subsume(&pure, &fs),
+        Err(TypeError::EffectMismatch { .. })
+    ));
+    assert!(u.unify(&fs, &pure).is_err());
+
+    // Contravariant: a taker of pure callbacks can't take effectful ones
+    let takes = |param: &Ty| Ty::arrow(vec![param.clone()], Ty::int());
+    u.subsume(&takes(&pure), &takes(&fs)).unwrap();
+    assert!(u.subsume(&takes(&fs), &takes(&pure)).is_err());
+
+    // Invariant elsewhere
+    let pair = |t: &Ty| Ty::tuple(vec![t.clone(), Ty::int()]);
+    assert!(u.subsume(&pair(&fs), &pair(&pure)).is_err());
+}
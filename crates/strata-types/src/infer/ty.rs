@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use strata_ast::span::Span;
@@ -52,7 +53,7 @@ pub enum TyConst {
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Ty {
     Var(TypeVarId),
     Const(TyConst),
@@ -62,6 +63,11 @@ pub enum Ty {
     Tuple(Vec<Ty>),
     /// Homogeneous list: [elem]
     List(Box<Ty>),
+    /// Structural record: `{x: Int, y: Int}`, fields sorted by name. With a
+    /// tail variable the row is open: the record has at least these fields,
+    /// and the tail (bound to another record as unification learns more)
+    /// stands for the rest.
+    Record(BTreeMap<String, Ty>, Option<TypeVarId>),
     /// Algebraic data type (struct or enum) with type arguments
     /// Examples: Option<Int>, Point, Result<T, E>
     Adt {
@@ -129,6 +135,28 @@ impl Ty {
     pub fn tuple(elems: impl Into<Vec<Ty>>) -> Self {
         Ty::Tuple(elems.into())
     }
+    /// Create a closed record type
+    pub fn record<S: Into<String>>(fields: impl IntoIterator<Item = (S, Ty)>) -> Self {
+        Ty::Record(
+            fields.into_iter().map(|(n, t)| (n.into(), t)).collect(),
+            None,
+        )
+    }
+    /// A record of `fields` whose tail variable now stands for `tail`: a
+    /// record tail contributes its fields, a variable stays the tail
+    pub fn record_with_tail(mut fields: BTreeMap<String, Ty>, tail: Option<Ty>) -> Self {
+        let tail = match tail {
+            Some(Ty::Var(v)) => Some(v),
+            Some(Ty::Record(rest, rest_tail)) => {
+                for (name, ty) in rest {
+                    fields.entry(name).or_insert(ty);
+                }
+                rest_tail
+            }
+            _ => None,
+        };
+        Ty::Record(fields, tail)
+    }
     #[inline]
     pub fn list(elem: Ty) -> Self {
         Ty::List(Box::new(elem))
@@ -171,12 +199,13 @@ impl Ty {
     /// Returns whether this type is first-class (can appear anywhere in programs).
     ///
     /// Non-first-class types like `Ty::Ref` are restricted to specific positions
-    /// (extern fn params only). This check runs AFTER solving/substitution to catch
+    /// (function parameters only). This check runs AFTER solving/substitution to catch
     /// cases where `&T` escapes through inference (e.g., `let r = &fs;`).
     pub fn is_first_class(&self) -> bool {
         match self {
             Ty::Ref(_) => false,
             Ty::Tuple(elems) => elems.iter().all(|e| e.is_first_class()),
+            Ty::Record(fields, _) => fields.values().all(|f| f.is_first_class()),
             Ty::List(inner) => inner.is_first_class(),
             Ty::Adt { args, .. } => args.iter().all(|a| a.is_first_class()),
             Ty::Arrow(params, ret, _) => {
@@ -219,6 +248,13 @@ impl Ty {
                     Kind::Unrestricted
                 }
             }
+            Ty::Record(fields, _) => {
+                if fields.values().any(|ty| ty.kind() == Kind::Affine) {
+                    Kind::Affine
+                } else {
+                    Kind::Unrestricted
+                }
+            }
             Ty::List(inner) => inner.kind(),
             // Refs are always unrestricted — borrowing doesn't consume
             Ty::Ref(_) => Kind::Unrestricted,
@@ -269,6 +305,19 @@ impl fmt::Display for Ty {
                 }
                 write!(f, ")")
             }
+            Ty::Record(fields, tail) => {
+                write!(f, "{{")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {ty}")?;
+                }
+                if tail.is_some() {
+                    write!(f, "{}..", if fields.is_empty() { "" } else { ", " })?;
+                }
+                write!(f, "}}")
+            }
             Ty::List(x) => write!(f, "[{}]", x),
             Ty::Adt { name, args } => {
                 if args.is_empty() {
@@ -366,11 +415,26 @@ pub enum Constraint {
     /// Type equality: t1 ~ t2
     /// Includes the span where this constraint was generated
     Equal(Ty, Ty, Span),
+    /// Subsumption: a value of the second type fits where the first is
+    /// expected. The same as `Equal`, except that a function value may
+    /// perform fewer effects than its expected type allows.
+    Subsume(Ty, Ty, Span),
     /// Effect subset: row1 ⊆ row2
     /// Used to enforce that a function body's effects fit within its declaration.
     EffectSubset(EffectRow, EffectRow, Span),
 }
 
+impl Constraint {
+    /// Where the constraint was generated
+    pub fn span(&self) -> Span {
+        match self {
+            Constraint::Equal(_, _, span)
+            | Constraint::Subsume(_, _, span)
+            | Constraint::EffectSubset(_, _, span) => *span,
+        }
+    }
+}
+
 /// Find free type variables in a type
 pub fn free_vars(ty: &Ty) -> HashSet<TypeVarId> {
     match ty {
@@ -395,6 +459,13 @@ pub fn free_vars(ty: &Ty) -> HashSet<TypeVarId> {
             }
             set
         }
+        Ty::Record(fields, tail) => {
+            let mut set: HashSet<TypeVarId> = tail.iter().copied().collect();
+            for ty in fields.values() {
+                set.extend(free_vars(ty));
+            }
+            set
+        }
         Ty::List(ty) => free_vars(ty),
         Ty::Adt { args, .. } => {
             let mut set = HashSet::new();
@@ -429,6 +500,13 @@ pub fn free_effect_vars(ty: &Ty) -> HashSet<EffectVarId> {
             }
             set
         }
+        Ty::Record(fields, _) => {
+            let mut set = HashSet::new();
+            for ty in fields.values() {
+                set.extend(free_effect_vars(ty));
+            }
+            set
+        }
         Ty::List(ty) => free_effect_vars(ty),
         Ty::Adt { args, .. } => {
             let mut set = HashSet::new();
@@ -441,6 +519,53 @@ pub fn free_effect_vars(ty: &Ty) -> HashSet<EffectVarId> {
     }
 }
 
+/// `ty` with its type variables and effect variables each renumbered from
+/// 0 in order of first appearance, so two types that differ only in the
+/// checker's variable counters print alike
+pub fn renumbered(ty: &Ty) -> Ty {
+    fn visit(ty: &Ty, types: &mut Vec<TypeVarId>, effects: &mut Vec<EffectVarId>) {
+        let ty_var = |v: TypeVarId, types: &mut Vec<TypeVarId>| {
+            if !types.contains(&v) {
+                types.push(v);
+            }
+        };
+        match ty {
+            Ty::Const(_) | Ty::Never | Ty::Cap(_) => {}
+            Ty::Var(v) => ty_var(*v, types),
+            Ty::Arrow(params, ret, eff) => {
+                params.iter().for_each(|p| visit(p, types, effects));
+                visit(ret, types, effects);
+                if let Some(tail) = eff.tail.filter(|tail| !effects.contains(tail)) {
+                    effects.push(tail);
+                }
+            }
+            Ty::Tuple(tys) | Ty::Adt { args: tys, .. } => {
+                tys.iter().for_each(|t| visit(t, types, effects))
+            }
+            Ty::Record(fields, tail) => {
+                fields.values().for_each(|t| visit(t, types, effects));
+                if let Some(tail) = tail {
+                    ty_var(*tail, types);
+                }
+            }
+            Ty::List(inner) | Ty::Ref(inner) => visit(inner, types, effects),
+        }
+    }
+    let (mut types, mut effects) = (Vec::new(), Vec::new());
+    visit(ty, &mut types, &mut effects);
+    let types = types
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| (v, TypeVarId(i as u32)))
+        .collect();
+    let effects = effects
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| (v, EffectVarId(i as u32)))
+        .collect();
+    crate::interface::rename(ty, &types, &effects).expect("every variable is renumbered")
+}
+
 /// Find free type variables in a type scheme
 ///
 /// Free variables are those in the type that are NOT ∀-bound.
@@ -487,6 +612,23 @@ pub fn free_effect_vars_env(
 mod tests {
     use super::*;
 
+    #[test]
+    fn renumbered_counts_variables_in_order_of_appearance() {
+        let row = EffectRow {
+            concrete: 0,
+            tail: Some(EffectVarId(9)),
+        };
+        let ty = Ty::Arrow(
+            vec![
+                Ty::Var(TypeVarId(7)),
+                Ty::List(Box::new(Ty::Var(TypeVarId(3)))),
+            ],
+            Box::new(Ty::Var(TypeVarId(7))),
+            row,
+        );
+        assert_eq!(renumbered(&ty).to_string(), "(t0, [t1]) -> t0 & {e0}");
+    }
+
     #[test]
     fn instantiate_monomorphic() {
         let scheme = Scheme::mono(Ty::int());
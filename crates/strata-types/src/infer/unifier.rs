@@ -12,6 +12,7 @@
 use super::subst::{Subst, SubstError};
 use super::ty::{Ty, TypeVarId};
 use crate::effects::{EffectRow, EffectVarId};
+use std::collections::BTreeMap;
 use std::fmt;
 
 pub type TypeResult<T> = Result<T, TypeError>;
@@ -41,6 +42,15 @@ pub enum TypeError {
     EffectChainTooDeep {
         depth: usize,
     },
+    /// A closed record lacks a field the other side needs
+    NoSuchField {
+        field: String,
+        record: Ty,
+    },
+    /// Unification took more steps than its budget allows
+    StepLimitExceeded {
+        limit: usize,
+    },
 }
 
 impl From<SubstError> for TypeError {
@@ -87,22 +97,87 @@ impl fmt::Display for TypeError {
                     depth
                 )
             }
+            TypeError::NoSuchField { field, record } => {
+                write!(f, "record {} has no field `{}`", record, field)
+            }
+            TypeError::StepLimitExceeded { limit } => {
+                write!(f, "unification step limit ({}) exceeded", limit)
+            }
         }
     }
 }
 impl std::error::Error for TypeError {}
 
-#[derive(Clone, Debug, Default)]
+/// Where a standalone unifier starts numbering the row variables it makes,
+/// above the ids of any hand-built or checker-made variable
+const FRESH_VAR_BASE: u32 = 1 << 31;
+
+#[derive(Clone, Debug)]
 pub struct Unifier {
     subst: Subst,
+    /// Id of the next fresh variable, for the tails of open record rows
+    next_var: u32,
+    /// Unification steps taken so far
+    steps: usize,
+    /// Most steps allowed before unifying fails
+    step_limit: usize,
+}
+
+impl Default for Unifier {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Unifier {
     pub fn new() -> Self {
+        Self::with_next_var(FRESH_VAR_BASE)
+    }
+
+    /// A unifier whose fresh variables start at `next_var`, so they don't
+    /// collide with those the caller has handed out
+    pub fn with_next_var(next_var: u32) -> Self {
         Self {
             subst: Subst::new(),
+            next_var,
+            steps: 0,
+            step_limit: usize::MAX,
         }
     }
+
+    /// Fail with [`TypeError::StepLimitExceeded`] once more than `limit`
+    /// steps have been taken
+    pub fn with_step_limit(mut self, limit: usize) -> Self {
+        self.step_limit = limit;
+        self
+    }
+
+    /// Unification steps taken so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Count one step against the budget
+    pub(crate) fn step(&mut self) -> Result<(), TypeError> {
+        self.steps += 1;
+        if self.steps > self.step_limit {
+            return Err(TypeError::StepLimitExceeded {
+                limit: self.step_limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// Id of the next fresh variable this unifier would make
+    pub fn next_var(&self) -> u32 {
+        self.next_var
+    }
+
+    fn fresh_var(&mut self) -> TypeVarId {
+        let id = TypeVarId(self.next_var);
+        self.next_var += 1;
+        id
+    }
     pub fn subst(&self) -> &Subst {
         &self.subst
     }
@@ -114,6 +189,7 @@ impl Unifier {
     }
 
     pub fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), TypeError> {
+        self.step()?;
         let a = self.subst.apply(a)?;
         let b = self.subst.apply(b)?;
         match (a, b) {
@@ -155,6 +231,8 @@ impl Unifier {
                 Ok(())
             }
 
+            (Ty::Record(f1, t1), Ty::Record(f2, t2)) => self.unify_records(f1, t1, f2, t2),
+
             (Ty::List(x), Ty::List(y)) => self.unify(&x, &y),
 
             // ADT unification: names must match, then unify type arguments
@@ -180,6 +258,47 @@ impl Unifier {
         }
     }
 
+    /// Check that a value of type `actual` can be used where `expected` is
+    /// wanted, unifying as it goes. This is [`unify`](Self::unify) but for
+    /// function types: a function whose effect row is closed fits an
+    /// expected function type with a larger closed row, and the check
+    /// recurses into the return type (covariant) and parameters
+    /// (contravariant). Everything else, including the contents of tuples,
+    /// lists, and ADTs, must be equal.
+    pub fn subsume(&mut self, expected: &Ty, actual: &Ty) -> Result<(), TypeError> {
+        self.step()?;
+        let expected = self.subst.apply(expected)?;
+        let actual = self.subst.apply(actual)?;
+        match (expected, actual) {
+            (Ty::Arrow(params1, ret1, eff1), Ty::Arrow(params2, ret2, eff2)) => {
+                // Parameters are compared the other way round, so the count
+                // expected is the actual function's
+                if params1.len() != params2.len() {
+                    return Err(TypeError::Arity {
+                        left: params2.len(),
+                        right: params1.len(),
+                    });
+                }
+                // The expected type's callers pass arguments of its
+                // parameter types, which must fit the actual parameters
+                for (p1, p2) in params1.iter().zip(params2.iter()) {
+                    self.subsume(p2, p1)?;
+                }
+                self.subsume(&ret1, &ret2)?;
+                let eff1 = self.subst.apply_effect_row(&eff1)?;
+                let eff2 = self.subst.apply_effect_row(&eff2)?;
+                match (eff1.tail, eff2.tail) {
+                    // Doing less than is allowed is fine. A row with a tail
+                    // is still being inferred, and is unified instead, so
+                    // it doesn't widen to whatever it first meets.
+                    (None, None) if eff2.concrete & !eff1.concrete == 0 => Ok(()),
+                    _ => self.unify_effect_rows(&eff1, &eff2),
+                }
+            }
+            (expected, actual) => self.unify(&expected, &actual),
+        }
+    }
+
     /// Unify two effect rows (Rémy-style row unification).
     ///
     /// Cases:
@@ -188,6 +307,7 @@ impl Unifier {
     /// - Open + Closed: symmetric
     /// - Open + Open: fresh tail, bind both
     fn unify_effect_rows(&mut self, a: &EffectRow, b: &EffectRow) -> Result<(), TypeError> {
+        self.step()?;
         let a = self.subst.apply_effect_row(a)?;
         let b = self.subst.apply_effect_row(b)?;
 
@@ -268,6 +388,79 @@ impl Unifier {
         }
     }
 
+    /// Unify two record rows (the same scheme as effect rows): shared fields
+    /// unify, and a field only one side has must be absorbed by the other
+    /// side's tail.
+    ///
+    /// - Closed + Closed: the same field names
+    /// - Closed + Open: bind the open tail to the closed side's extra fields
+    /// - Open + Open: bind each tail to the other side's extra fields plus
+    ///   one fresh tail they share
+    fn unify_records(
+        &mut self,
+        f1: BTreeMap<String, Ty>,
+        t1: Option<TypeVarId>,
+        f2: BTreeMap<String, Ty>,
+        t2: Option<TypeVarId>,
+    ) -> Result<(), TypeError> {
+        // A closed side missing a field the other side has is reported by
+        // name; two closed sides each missing some of the other's differ
+        let mismatch = |f1: &BTreeMap<String, Ty>, f2: &BTreeMap<String, Ty>| {
+            let missing = |extra: &BTreeMap<String, Ty>, record: &BTreeMap<String, Ty>| {
+                let field = extra.keys().find(|name| !record.contains_key(*name))?;
+                Some(TypeError::NoSuchField {
+                    field: field.clone(),
+                    record: Ty::Record(record.clone(), None),
+                })
+            };
+            match (t1, t2) {
+                (None, Some(_)) => missing(f2, f1),
+                (Some(_), None) => missing(f1, f2),
+                (None, None) if f1.keys().all(|name| f2.contains_key(name)) => missing(f2, f1),
+                (None, None) if f2.keys().all(|name| f1.contains_key(name)) => missing(f1, f2),
+                _ => None,
+            }
+            .unwrap_or_else(|| {
+                TypeError::Mismatch(Ty::Record(f1.clone(), t1), Ty::Record(f2.clone(), t2))
+            })
+        };
+        let only1: BTreeMap<String, Ty> = f1
+            .iter()
+            .filter(|(name, _)| !f2.contains_key(*name))
+            .map(|(name, ty)| (name.clone(), ty.clone()))
+            .collect();
+        let only2: BTreeMap<String, Ty> = f2
+            .iter()
+            .filter(|(name, _)| !f1.contains_key(*name))
+            .map(|(name, ty)| (name.clone(), ty.clone()))
+            .collect();
+
+        match (t1, t2) {
+            (None, None) if !only1.is_empty() || !only2.is_empty() => {
+                return Err(mismatch(&f1, &f2))
+            }
+            (None, Some(r2)) if only2.is_empty() => self.unify_var(r2, Ty::Record(only1, None))?,
+            (Some(r1), None) if only1.is_empty() => self.unify_var(r1, Ty::Record(only2, None))?,
+            (Some(r1), Some(r2)) if r1 != r2 => {
+                let rest = self.fresh_var();
+                self.unify_var(r1, Ty::Record(only2, Some(rest)))?;
+                self.unify_var(r2, Ty::Record(only1, Some(rest)))?;
+            }
+            (Some(_), Some(_)) if !only1.is_empty() || !only2.is_empty() => {
+                return Err(mismatch(&f1, &f2))
+            }
+            (None, None) | (Some(_), Some(_)) => {}
+            (None, Some(_)) | (Some(_), None) => return Err(mismatch(&f1, &f2)),
+        }
+
+        for (name, ty) in &f1 {
+            if let Some(other) = f2.get(name) {
+                self.unify(ty, other)?;
+            }
+        }
+        Ok(())
+    }
+
     fn unify_var(&mut self, v: TypeVarId, t: Ty) -> Result<(), TypeError> {
         if let Some(existing) = self.subst.get(&v).cloned() {
             return self.unify(&existing, &t);
@@ -300,6 +493,9 @@ fn occurs_in(v: TypeVarId, ty: &Ty, subst: &Subst) -> bool {
             params.iter().any(|p| occurs_in(v, p, subst)) || occurs_in(v, ret, subst)
         }
         Ty::Tuple(ref xs) => xs.iter().any(|x| occurs_in(v, x, subst)),
+        Ty::Record(ref fields, tail) => {
+            tail == Some(v) || fields.values().any(|x| occurs_in(v, x, subst))
+        }
         Ty::List(ref x) => occurs_in(v, x, subst),
         Ty::Adt { ref args, .. } => args.iter().any(|a| occurs_in(v, a, subst)),
         Ty::Ref(ref inner) => occurs_in(v, inner, subst),
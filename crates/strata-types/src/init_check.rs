@@ -0,0 +1,346 @@
+//! Definite-assignment analysis for `let mut x: T;`.
+//!
+//! Post-inference pass over a function body (or top-level `let` value). A
+//! declared variable starts unassigned; reading it is an error unless every
+//! path from the declaration to the read assigns it first. Paths that
+//! diverge (`return`, or a call to a `Never`-returning builtin such as
+//! `todo()`) never reach the read and so do not need to assign.
+//!
+//! The analysis is flow-sensitive but not value-sensitive: both arms of an
+//! `if` are assumed reachable whatever the condition, a `while` body may run
+//! zero times, and the right operand of `&&`/`||` may not run at all.
+
+use std::collections::HashMap;
+use strata_ast::ast::{BinOp, Block, Expr, Pat, Stmt};
+use strata_ast::span::Span;
+
+/// A read of a declared variable that some path leaves unassigned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitError {
+    pub name: String,
+    pub declared_at: Span,
+    pub used_at: Span,
+    /// The branch, or loop, through which the variable can reach the read
+    /// unassigned, if it is assigned on other paths
+    pub unassigned_on: Option<Span>,
+}
+
+/// Flow state at one program point.
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// Declarations not yet assigned on this path, each with the branch
+    /// that left it unassigned while others assigned it
+    unassigned: HashMap<usize, Option<Span>>,
+    /// No path reaches this point
+    diverged: bool,
+}
+
+struct InitChecker<'a> {
+    /// Lexical scopes mapping each visible name to its declaration index;
+    /// `None` marks an ordinary binding that hides any outer declaration
+    scopes: Vec<Vec<(String, Option<usize>)>>,
+    /// Name and span of each `let mut x: T;` seen so far
+    decls: Vec<(String, Span)>,
+    state: State,
+    /// Global functions whose calls never return
+    diverging: &'a [String],
+}
+
+impl<'a> InitChecker<'a> {
+    fn new(bound: &[String], diverging: &'a [String]) -> Self {
+        InitChecker {
+            scopes: vec![bound.iter().map(|n| (n.clone(), None)).collect()],
+            decls: Vec::new(),
+            state: State::default(),
+            diverging,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Option<usize>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.iter().rev().find(|(n, _)| n == name))
+            .map(|(_, decl)| *decl)
+    }
+
+    fn bind(&mut self, name: &str, decl: Option<usize>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), decl));
+        }
+    }
+
+    fn bind_pattern(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(ident) => self.bind(&ident.text, None),
+            Pat::Wildcard(_) | Pat::Literal(_, _) | Pat::Range { .. } => {}
+            Pat::StrPrefix { rest, .. } => self.bind_pattern(rest),
+            Pat::Tuple(pats, _) | Pat::Variant { fields: pats, .. } => {
+                for p in pats {
+                    self.bind_pattern(p);
+                }
+            }
+            Pat::Struct { fields, .. } | Pat::Record { fields, .. } => {
+                for f in fields {
+                    self.bind_pattern(&f.pat);
+                }
+            }
+        }
+    }
+
+    fn check_block(&mut self, block: &Block) -> Result<(), InitError> {
+        self.scopes.push(Vec::new());
+        let result = self.check_block_inner(block);
+        self.scopes.pop();
+        result
+    }
+
+    fn check_block_inner(&mut self, block: &Block) -> Result<(), InitError> {
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            for decl in block.fn_group_at(i) {
+                self.bind(&decl.name.text, None);
+            }
+            self.check_stmt(stmt)?;
+        }
+        match &block.tail {
+            Some(tail) => self.check_expr(tail),
+            None => Ok(()),
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) -> Result<(), InitError> {
+        match stmt {
+            Stmt::Let { pat, value, .. } => {
+                self.check_expr(value)?;
+                self.bind_pattern(pat);
+            }
+            Stmt::Declare { name, .. } => {
+                let decl = self.decls.len();
+                self.decls.push((name.text.clone(), name.span));
+                self.bind(&name.text, Some(decl));
+                self.state.unassigned.insert(decl, None);
+            }
+            Stmt::Assign { target, value, .. } => {
+                self.check_expr(value)?;
+                if let Some(Some(decl)) = self.lookup(&target.text) {
+                    self.state.unassigned.remove(&decl);
+                }
+            }
+            Stmt::Expr { expr, .. } => self.check_expr(expr)?,
+            Stmt::Return { value, .. } => {
+                if let Some(v) = value {
+                    self.check_expr(v)?;
+                }
+                self.state.diverged = true;
+            }
+            // Bound with its group by `check_block_inner`
+            Stmt::Fn(decl) => {
+                // The body reads captured variables as they are here, since
+                // it may be called straight away; its `return`s end only
+                // the call, not this path
+                let before = self.state.clone();
+                self.scopes.push(
+                    decl.params
+                        .iter()
+                        .map(|p| (p.name.text.clone(), None))
+                        .collect(),
+                );
+                let result = self.check_block(&decl.body);
+                self.scopes.pop();
+                self.state = before;
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    fn check_expr(&mut self, expr: &Expr) -> Result<(), InitError> {
+        match expr {
+            Expr::Lit(..) | Expr::PathExpr(_) | Expr::Hole(_) => Ok(()),
+            Expr::Var(id) => self.read(&id.text, id.span),
+            Expr::Paren { inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::Borrow(inner, _)
+            | Expr::Field { expr: inner, .. } => self.check_expr(inner),
+            Expr::Binary { op, lhs, rhs, span } => {
+                self.check_expr(lhs)?;
+                if matches!(op, BinOp::And | BinOp::Or) {
+                    // The right operand may not run
+                    let before = self.state.clone();
+                    self.check_expr(rhs)?;
+                    self.skippable(before, *span);
+                    Ok(())
+                } else {
+                    self.check_expr(rhs)
+                }
+            }
+            Expr::Call { callee, args, .. } => {
+                self.check_expr(callee)?;
+                for arg in args {
+                    self.check_expr(arg)?;
+                }
+                if let Expr::Var(id) = callee.as_ref() {
+                    if self.lookup(&id.text).is_none() && self.diverging.contains(&id.text) {
+                        self.state.diverged = true;
+                    }
+                }
+                Ok(())
+            }
+            Expr::Block(block) => self.check_block(block),
+            Expr::Spawn { body, .. } => {
+                // A task reads what it captures now, but what it assigns is
+                // its own, as in a nested function
+                let before = self.state.clone();
+                let result = self.check_block(body);
+                self.state = before;
+                result
+            }
+            Expr::If {
+                cond,
+                then_,
+                else_,
+                span,
+            } => {
+                self.check_expr(cond)?;
+                let before = self.state.clone();
+                self.check_block(then_)?;
+                let after_then = std::mem::replace(&mut self.state, before);
+                // A missing `else` is a path that assigns nothing
+                let else_span = match else_ {
+                    Some(e) => {
+                        self.check_expr(e)?;
+                        e.span()
+                    }
+                    None => *span,
+                };
+                let after_else = std::mem::take(&mut self.state);
+                self.join(vec![(after_then, then_.span), (after_else, else_span)]);
+                Ok(())
+            }
+            Expr::While { cond, body, span } => {
+                self.check_expr(cond)?;
+                let before = self.state.clone();
+                self.check_block(body)?;
+                // The body may run zero times
+                self.skippable(before, *span);
+                Ok(())
+            }
+            Expr::For {
+                pat,
+                iter,
+                body,
+                span,
+            } => {
+                self.check_expr(iter)?;
+                let before = self.state.clone();
+                self.scopes.push(Vec::new());
+                self.bind_pattern(pat);
+                let result = self.check_block(body);
+                self.scopes.pop();
+                result?;
+                // The body may run zero times
+                self.skippable(before, *span);
+                Ok(())
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.check_expr(scrutinee)?;
+                if arms.is_empty() {
+                    return Ok(());
+                }
+                let before = self.state.clone();
+                let mut branches = Vec::new();
+                for arm in arms {
+                    self.state = before.clone();
+                    self.scopes.push(Vec::new());
+                    self.bind_pattern(&arm.pat);
+                    let result = self.check_expr(&arm.body);
+                    self.scopes.pop();
+                    result?;
+                    branches.push((std::mem::take(&mut self.state), arm.span));
+                }
+                self.join(branches);
+                Ok(())
+            }
+            Expr::Tuple { elems, .. } => elems.iter().try_for_each(|e| self.check_expr(e)),
+            Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+                fields.iter().try_for_each(|f| self.check_expr(&f.value))
+            }
+        }
+    }
+
+    fn read(&self, name: &str, span: Span) -> Result<(), InitError> {
+        if self.state.diverged {
+            return Ok(());
+        }
+        let Some(Some(decl)) = self.lookup(name) else {
+            return Ok(());
+        };
+        match self.state.unassigned.get(&decl) {
+            Some(reason) => {
+                let (name, declared_at) = self.decls[decl].clone();
+                Err(InitError {
+                    name,
+                    declared_at,
+                    used_at: span,
+                    unassigned_on: *reason,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Roll back to `before` after code at `span` that may not run. Its
+    /// assignments don't count, so it is remembered as the path they missed.
+    fn skippable(&mut self, before: State, span: Span) {
+        let after = std::mem::replace(&mut self.state, before);
+        for (decl, reason) in self.state.unassigned.iter_mut() {
+            if reason.is_none() && !after.unassigned.contains_key(decl) {
+                *reason = Some(span);
+            }
+        }
+    }
+
+    /// Merge the states at the end of each branch. A declaration stays
+    /// unassigned if any reaching branch leaves it so; when another branch
+    /// does assign it, the first branch that doesn't is remembered.
+    fn join(&mut self, branches: Vec<(State, Span)>) {
+        let live: Vec<(State, Span)> = branches.into_iter().filter(|(s, _)| !s.diverged).collect();
+        if live.is_empty() {
+            self.state = State {
+                unassigned: HashMap::new(),
+                diverged: true,
+            };
+            return;
+        }
+        let mut unassigned: HashMap<usize, Option<Span>> = HashMap::new();
+        for (state, branch) in &live {
+            for (&decl, &reason) in &state.unassigned {
+                let assigned_elsewhere =
+                    live.iter().any(|(s, _)| !s.unassigned.contains_key(&decl));
+                let reason = reason.or(assigned_elsewhere.then_some(*branch));
+                unassigned.entry(decl).or_insert(reason);
+            }
+        }
+        self.state = State {
+            unassigned,
+            diverged: false,
+        };
+    }
+}
+
+/// Check a function body. `params` are the names bound on entry;
+/// `diverging` names the global functions whose calls never return.
+pub fn check_function_body(
+    params: &[String],
+    body: &Block,
+    diverging: &[String],
+) -> Result<(), InitError> {
+    InitChecker::new(params, diverging).check_block(body)
+}
+
+/// Check the value of a top-level `let`.
+pub fn check_expr(expr: &Expr, diverging: &[String]) -> Result<(), InitError> {
+    InitChecker::new(&[], diverging).check_expr(expr)
+}
@@ -0,0 +1,617 @@
+//! Interface files: the generalized schemes of a checked module's functions.
+//!
+//! Checking a module that calls another module's functions needs only
+//! their schemes (types with effect rows), not their bodies. An
+//! [`Interface`] records those schemes once, after
+//! [`TypeChecker::check_module`](crate::TypeChecker::check_module), so a
+//! downstream check can [`TypeChecker::import_interface`](crate::TypeChecker::import_interface)
+//! them instead of re-inferring and re-generalizing the upstream code.
+//!
+//! Only `pub fn`s are exported. The names of the module's other functions
+//! are recorded without their schemes, so a downstream reference to one
+//! can be reported as private rather than unknown. The definitions of
+//! `pub struct`s and `pub enum`s are recorded too, so two versions of an
+//! interface can be compared (see [`crate::compat`]).
+//!
+//! The binary format is stable: a `STIF` magic and a format version, then
+//! each function's name and scheme, then the private function names, then
+//! the ADT definitions, struct fields with their defaults. Bound variables
+//! are renumbered from 0 in order of appearance in the `∀` list, so the
+//! bytes depend only on the schemes, not on the checker's variable
+//! counters. All integers are little-endian; strings are a `u32` length
+//! and UTF-8 bytes.
+//!
+//! Functions whose types mention a struct or enum the module defines are
+//! left out, since importing an interface doesn't bring its ADTs into
+//! scope yet.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use strata_ast::ast::{Item, Module};
+
+use crate::adt::{AdtDef, AdtKind, AdtRegistry, FieldDef, VariantDef, VariantFields};
+use crate::consts::ConstValue;
+use crate::effects::{CapKind, EffectRow, EffectVarId, ALL_EFFECTS};
+use crate::infer::ty::{Scheme, Ty, TyConst, TypeVarId};
+
+const MAGIC: &[u8; 4] = b"STIF";
+
+/// Current interface format version.
+pub const FORMAT_VERSION: u16 = 4;
+
+/// Deepest type nesting accepted when decoding.
+const MAX_DEPTH: usize = 256;
+
+/// Generalized function schemes exported by one module, by name.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Interface {
+    pub fns: Vec<(String, Scheme)>,
+    /// Functions the module defines without `pub`
+    pub private: Vec<String>,
+    /// The module's `pub` structs and enums, in source order. A field type
+    /// refers to the ADT's `i`th type parameter as `Ty::Var(i)`.
+    pub adts: Vec<AdtDef>,
+}
+
+/// Why interface bytes couldn't be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceError {
+    /// Not an interface file
+    BadMagic,
+    /// Written by a different format version
+    UnsupportedVersion(u16),
+    /// The data ends early
+    Truncated,
+    /// Malformed contents
+    Invalid(String),
+}
+
+impl std::fmt::Display for InterfaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterfaceError::BadMagic => write!(f, "not a Strata interface file"),
+            InterfaceError::UnsupportedVersion(v) => write!(
+                f,
+                "interface format version {} is not supported (expected {})",
+                v, FORMAT_VERSION
+            ),
+            InterfaceError::Truncated => write!(f, "interface file is truncated"),
+            InterfaceError::Invalid(msg) => write!(f, "invalid interface file: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for InterfaceError {}
+
+impl Interface {
+    /// The schemes of `module`'s functions from the environment of the
+    /// checker that checked it.
+    pub(crate) fn from_env(
+        module: &Module,
+        env: &HashMap<String, Scheme>,
+        registry: &AdtRegistry,
+    ) -> Self {
+        let own_adts: HashSet<&str> = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(def) => Some(def.name.text.as_str()),
+                Item::Enum(def) => Some(def.name.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let fns = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(decl) if decl.public => Some(decl.name.text.as_str()),
+                _ => None,
+            })
+            .filter_map(|name| {
+                let scheme = env.get(name)?;
+                if mentions_adt(&scheme.ty, &own_adts) {
+                    return None;
+                }
+                Some((name.to_string(), canonical(scheme)?))
+            })
+            .collect();
+        let private = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Fn(decl) if !decl.public => Some(decl.name.text.clone()),
+                _ => None,
+            })
+            .collect();
+        let adts = module
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Struct(def) if def.public => registry.get(&def.name.text),
+                Item::Enum(def) if def.public => registry.get(&def.name.text),
+                _ => None,
+            })
+            .cloned()
+            .collect();
+        Interface { fns, private, adts }
+    }
+
+    /// Encode in the stable binary format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        put_u32(&mut out, self.fns.len());
+        for (name, scheme) in &self.fns {
+            put_str(&mut out, name);
+            put_u32(&mut out, scheme.type_vars.len());
+            put_u32(&mut out, scheme.effect_vars.len());
+            put_ty(&mut out, &scheme.ty);
+        }
+        put_u32(&mut out, self.private.len());
+        for name in &self.private {
+            put_str(&mut out, name);
+        }
+        put_u32(&mut out, self.adts.len());
+        for def in &self.adts {
+            put_adt(&mut out, def);
+        }
+        out
+    }
+
+    /// Decode bytes written by [`Interface::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InterfaceError> {
+        let mut r = Reader { bytes, pos: 0 };
+        if r.take(4)? != MAGIC {
+            return Err(InterfaceError::BadMagic);
+        }
+        let version = u16::from_le_bytes([r.u8()?, r.u8()?]);
+        if version != FORMAT_VERSION {
+            return Err(InterfaceError::UnsupportedVersion(version));
+        }
+        let count = r.u32()?;
+        let mut fns = Vec::new();
+        for _ in 0..count {
+            let name = r.str()?;
+            let type_vars = (0..r.u32()?).map(TypeVarId).collect();
+            let effect_vars = (0..r.u32()?).map(EffectVarId).collect();
+            let scheme = Scheme {
+                type_vars,
+                effect_vars,
+                ty: r.ty(0)?,
+            };
+            if canonical(&scheme).as_ref() != Some(&scheme) {
+                return Err(InterfaceError::Invalid(format!(
+                    "scheme of '{}' has an unbound variable",
+                    name
+                )));
+            }
+            fns.push((name, scheme));
+        }
+        let private = (0..r.u32()?).map(|_| r.str()).collect::<Result<_, _>>()?;
+        let adts = (0..r.u32()?).map(|_| r.adt()).collect::<Result<_, _>>()?;
+        if r.pos != bytes.len() {
+            return Err(InterfaceError::Invalid("trailing data".to_string()));
+        }
+        Ok(Interface { fns, private, adts })
+    }
+}
+
+/// Whether `ty` names any ADT in `names`.
+fn mentions_adt(ty: &Ty, names: &HashSet<&str>) -> bool {
+    match ty {
+        Ty::Var(_) | Ty::Const(_) | Ty::Cap(_) | Ty::Never => false,
+        Ty::Arrow(params, ret, _) => {
+            params.iter().any(|p| mentions_adt(p, names)) || mentions_adt(ret, names)
+        }
+        Ty::Tuple(elems) => elems.iter().any(|e| mentions_adt(e, names)),
+        Ty::Record(fields, _) => fields.values().any(|f| mentions_adt(f, names)),
+        Ty::List(elem) | Ty::Ref(elem) => mentions_adt(elem, names),
+        Ty::Adt { name, args } => {
+            names.contains(name.as_str()) || args.iter().any(|a| mentions_adt(a, names))
+        }
+    }
+}
+
+/// `scheme` with its bound variables renumbered from 0, or `None` if it
+/// has a free variable (so it isn't closed and can't be exported).
+fn canonical(scheme: &Scheme) -> Option<Scheme> {
+    let types: HashMap<TypeVarId, TypeVarId> = scheme
+        .type_vars
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (*v, TypeVarId(i as u32)))
+        .collect();
+    let effects: HashMap<EffectVarId, EffectVarId> = scheme
+        .effect_vars
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (*v, EffectVarId(i as u32)))
+        .collect();
+    Some(Scheme {
+        type_vars: (0..types.len() as u32).map(TypeVarId).collect(),
+        effect_vars: (0..effects.len() as u32).map(EffectVarId).collect(),
+        ty: rename(&scheme.ty, &types, &effects)?,
+    })
+}
+
+/// `ty` with variables renamed per the maps; `None` if one isn't mapped.
+pub(crate) fn rename(
+    ty: &Ty,
+    types: &HashMap<TypeVarId, TypeVarId>,
+    effects: &HashMap<EffectVarId, EffectVarId>,
+) -> Option<Ty> {
+    let all =
+        |tys: &[Ty]| -> Option<Vec<Ty>> { tys.iter().map(|t| rename(t, types, effects)).collect() };
+    Some(match ty {
+        Ty::Var(v) => Ty::Var(*types.get(v)?),
+        Ty::Const(_) | Ty::Cap(_) | Ty::Never => ty.clone(),
+        Ty::Arrow(params, ret, row) => {
+            let row = EffectRow {
+                concrete: row.concrete,
+                tail: match row.tail {
+                    Some(tail) => Some(*effects.get(&tail)?),
+                    None => None,
+                },
+            };
+            Ty::Arrow(all(params)?, Box::new(rename(ret, types, effects)?), row)
+        }
+        Ty::Tuple(elems) => Ty::Tuple(all(elems)?),
+        Ty::Record(fields, tail) => Ty::Record(
+            fields
+                .iter()
+                .map(|(name, f)| Some((name.clone(), rename(f, types, effects)?)))
+                .collect::<Option<_>>()?,
+            match tail {
+                Some(tail) => Some(*types.get(tail)?),
+                None => None,
+            },
+        ),
+        Ty::List(elem) => Ty::List(Box::new(rename(elem, types, effects)?)),
+        Ty::Ref(inner) => Ty::Ref(Box::new(rename(inner, types, effects)?)),
+        Ty::Adt { name, args } => Ty::Adt {
+            name: name.clone(),
+            args: all(args)?,
+        },
+    })
+}
+
+fn put_u32(out: &mut Vec<u8>, n: usize) {
+    out.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_u32(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn put_ty(out: &mut Vec<u8>, ty: &Ty) {
+    match ty {
+        Ty::Var(v) => {
+            out.push(0);
+            put_u32(out, v.0 as usize);
+        }
+        Ty::Const(c) => {
+            out.push(1);
+            out.push(match c {
+                TyConst::Unit => 0,
+                TyConst::Bool => 1,
+                TyConst::Int => 2,
+                TyConst::Float => 3,
+                TyConst::String => 4,
+            });
+        }
+        Ty::Arrow(params, ret, row) => {
+            out.push(2);
+            put_u32(out, params.len());
+            for param in params {
+                put_ty(out, param);
+            }
+            put_ty(out, ret);
+            out.extend_from_slice(&row.concrete.to_le_bytes());
+            match row.tail {
+                Some(tail) => {
+                    out.push(1);
+                    put_u32(out, tail.0 as usize);
+                }
+                None => out.push(0),
+            }
+        }
+        Ty::Tuple(elems) => {
+            out.push(3);
+            put_u32(out, elems.len());
+            for elem in elems {
+                put_ty(out, elem);
+            }
+        }
+        Ty::List(elem) => {
+            out.push(4);
+            put_ty(out, elem);
+        }
+        Ty::Adt { name, args } => {
+            out.push(5);
+            put_str(out, name);
+            put_u32(out, args.len());
+            for arg in args {
+                put_ty(out, arg);
+            }
+        }
+        Ty::Cap(kind) => {
+            out.push(6);
+            put_str(out, kind.type_name());
+        }
+        Ty::Never => out.push(7),
+        Ty::Ref(inner) => {
+            out.push(8);
+            put_ty(out, inner);
+        }
+        Ty::Record(fields, tail) => {
+            out.push(9);
+            put_u32(out, fields.len());
+            for (name, field) in fields {
+                put_str(out, name);
+                put_ty(out, field);
+            }
+            match tail {
+                Some(tail) => {
+                    out.push(1);
+                    put_u32(out, tail.0 as usize);
+                }
+                None => out.push(0),
+            }
+        }
+    }
+}
+
+fn put_adt(out: &mut Vec<u8>, def: &AdtDef) {
+    put_str(out, &def.name);
+    put_u32(out, def.type_params.len());
+    for param in &def.type_params {
+        put_str(out, param);
+    }
+    out.push(u8::from(def.linear));
+    out.push(u8::from(def.tuple));
+    match &def.kind {
+        AdtKind::Struct(fields) => {
+            out.push(0);
+            put_u32(out, fields.len());
+            for field in fields {
+                put_str(out, &field.name);
+                put_ty(out, &field.ty);
+                put_default(out, field.default.as_ref());
+            }
+        }
+        AdtKind::Enum(variants) => {
+            out.push(1);
+            put_u32(out, variants.len());
+            for variant in variants {
+                put_str(out, &variant.name);
+                out.extend_from_slice(&variant.discriminant.to_le_bytes());
+                match &variant.fields {
+                    VariantFields::Unit => out.push(0),
+                    VariantFields::Tuple(tys) => {
+                        out.push(1);
+                        put_u32(out, tys.len());
+                        for ty in tys {
+                            put_ty(out, ty);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn put_default(out: &mut Vec<u8>, default: Option<&ConstValue>) {
+    match default {
+        None => out.push(0),
+        Some(ConstValue::Int(n)) => {
+            out.push(1);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+        Some(ConstValue::Float(x)) => {
+            out.push(2);
+            out.extend_from_slice(&x.to_bits().to_le_bytes());
+        }
+        Some(ConstValue::Bool(b)) => {
+            out.push(3);
+            out.push(u8::from(*b));
+        }
+        Some(ConstValue::Str(s)) => {
+            out.push(4);
+            put_str(out, s);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], InterfaceError> {
+        let end = self.pos.checked_add(n).ok_or(InterfaceError::Truncated)?;
+        let slice = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(InterfaceError::Truncated)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, InterfaceError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, InterfaceError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, InterfaceError> {
+        let b = self.take(8)?;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(b);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn str(&mut self) -> Result<String, InterfaceError> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| InterfaceError::Invalid("string is not UTF-8".to_string()))
+    }
+
+    fn flag(&mut self) -> Result<bool, InterfaceError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(InterfaceError::Invalid(format!("flag {}", tag))),
+        }
+    }
+
+    fn default(&mut self) -> Result<Option<ConstValue>, InterfaceError> {
+        Ok(Some(match self.u8()? {
+            0 => return Ok(None),
+            1 => ConstValue::Int(self.u64()? as i64),
+            2 => ConstValue::Float(f64::from_bits(self.u64()?)),
+            3 => ConstValue::Bool(self.flag()?),
+            4 => ConstValue::Str(self.str()?),
+            tag => return Err(InterfaceError::Invalid(format!("default tag {}", tag))),
+        }))
+    }
+
+    fn adt(&mut self) -> Result<AdtDef, InterfaceError> {
+        let name = self.str()?;
+        let type_params: Vec<String> = (0..self.u32()?)
+            .map(|_| self.str())
+            .collect::<Result<_, _>>()?;
+        let linear = self.flag()?;
+        let tuple = self.flag()?;
+        let kind = match self.u8()? {
+            0 => {
+                let mut fields = Vec::new();
+                for _ in 0..self.u32()? {
+                    let name = self.str()?;
+                    let ty = self.ty(0)?;
+                    fields.push(FieldDef {
+                        name,
+                        ty,
+                        default: self.default()?,
+                    });
+                }
+                AdtKind::Struct(fields)
+            }
+            1 => {
+                let mut variants = Vec::new();
+                for _ in 0..self.u32()? {
+                    let name = self.str()?;
+                    let discriminant = self.u64()? as i64;
+                    let fields = match self.u8()? {
+                        0 => VariantFields::Unit,
+                        1 => VariantFields::Tuple(self.tys(0)?),
+                        tag => return Err(InterfaceError::Invalid(format!("variant tag {}", tag))),
+                    };
+                    variants.push(VariantDef {
+                        name,
+                        fields,
+                        discriminant,
+                    });
+                }
+                AdtKind::Enum(variants)
+            }
+            tag => return Err(InterfaceError::Invalid(format!("ADT tag {}", tag))),
+        };
+        let def = AdtDef {
+            name,
+            type_params,
+            kind,
+            linear,
+            tuple,
+        };
+        // Field types may only mention the ADT's own type parameters
+        let params: HashMap<TypeVarId, TypeVarId> = (0..def.type_params.len() as u32)
+            .map(|i| (TypeVarId(i), TypeVarId(i)))
+            .collect();
+        if def
+            .field_types()
+            .iter()
+            .any(|ty| rename(ty, &params, &HashMap::new()).is_none())
+        {
+            return Err(InterfaceError::Invalid(format!(
+                "a field of '{}' has an unbound variable",
+                def.name
+            )));
+        }
+        Ok(def)
+    }
+
+    fn tys(&mut self, depth: usize) -> Result<Vec<Ty>, InterfaceError> {
+        let len = self.u32()?;
+        (0..len).map(|_| self.ty(depth + 1)).collect()
+    }
+
+    fn ty(&mut self, depth: usize) -> Result<Ty, InterfaceError> {
+        if depth > MAX_DEPTH {
+            return Err(InterfaceError::Invalid("type nests too deeply".to_string()));
+        }
+        Ok(match self.u8()? {
+            0 => Ty::Var(TypeVarId(self.u32()?)),
+            1 => Ty::Const(match self.u8()? {
+                0 => TyConst::Unit,
+                1 => TyConst::Bool,
+                2 => TyConst::Int,
+                3 => TyConst::Float,
+                4 => TyConst::String,
+                tag => return Err(InterfaceError::Invalid(format!("primitive tag {}", tag))),
+            }),
+            2 => {
+                let params = self.tys(depth)?;
+                let ret = self.ty(depth + 1)?;
+                let concrete = self.u64()?;
+                let known = ALL_EFFECTS.iter().fold(0, |mask, e| mask | e.bit());
+                if concrete & !known != 0 {
+                    return Err(InterfaceError::Invalid("unknown effect".to_string()));
+                }
+                let tail = match self.u8()? {
+                    0 => None,
+                    1 => Some(EffectVarId(self.u32()?)),
+                    tag => return Err(InterfaceError::Invalid(format!("row tail tag {}", tag))),
+                };
+                Ty::Arrow(params, Box::new(ret), EffectRow { concrete, tail })
+            }
+            3 => Ty::Tuple(self.tys(depth)?),
+            4 => Ty::List(Box::new(self.ty(depth + 1)?)),
+            5 => {
+                let name = self.str()?;
+                Ty::Adt {
+                    name,
+                    args: self.tys(depth)?,
+                }
+            }
+            6 => {
+                let name = self.str()?;
+                Ty::Cap(CapKind::from_name(&name).ok_or_else(|| {
+                    InterfaceError::Invalid(format!("unknown capability '{}'", name))
+                })?)
+            }
+            7 => Ty::Never,
+            8 => Ty::Ref(Box::new(self.ty(depth + 1)?)),
+            9 => {
+                let len = self.u32()?;
+                let mut fields = BTreeMap::new();
+                for _ in 0..len {
+                    let name = self.str()?;
+                    fields.insert(name, self.ty(depth + 1)?);
+                }
+                let tail = match self.u8()? {
+                    0 => None,
+                    1 => Some(TypeVarId(self.u32()?)),
+                    tag => return Err(InterfaceError::Invalid(format!("row tail tag {}", tag))),
+                };
+                Ty::Record(fields, tail)
+            }
+            tag => return Err(InterfaceError::Invalid(format!("type tag {}", tag))),
+        })
+    }
+}
@@ -3,20 +3,36 @@
 #![forbid(unsafe_code)]
 
 pub mod adt;
+pub mod attrs;
 mod checker;
+pub mod compat;
+pub mod consts;
+mod deprecation;
+pub mod derive;
 mod effects;
 pub mod exhaustive;
+pub mod fixes;
+mod init_check;
+pub mod interface;
 pub mod move_check;
+pub mod named_args;
 mod profile;
+mod provenance;
+pub mod resolve;
+mod shadow_check;
+pub mod typed;
 mod types;
+pub mod warnings;
 
 #[cfg(test)]
 mod checker_tests;
 
-pub use checker::{TypeChecker, TypeError};
-pub use effects::{CapKind, Effect, EffectRow, EffectVarId};
-pub use profile::Profile;
+pub use checker::{CheckerSnapshot, TypeChecker, TypeError};
+pub use effects::{CapKind, Effect, EffectRow, EffectVarId, ALL_EFFECTS};
+pub use profile::{Limits, Profile};
+pub use typed::{TypedItem, TypedItemKind, TypedLocal};
 pub use types::{PrimType, Type};
+pub use warnings::{Lint, LintConfig, LintLevel, Warning};
 
 // inference lives in its own namespace (no collisions)
 pub mod infer {
@@ -28,9 +44,9 @@ pub mod infer {
     pub mod unifier;
 
     // Re-exports for convenience inside `infer`
-    pub use constraint::InferCtx;
+    pub use constraint::{InferCtx, VariadicBound};
     pub use ctx::TypeCtx;
-    pub use solver::Solver;
+    pub use solver::{SolveStats, Solver};
     pub use subst::Subst;
     pub use ty::{Kind, Ty, TyConst, TypeVarId};
     pub use unifier::{TypeError, Unifier};
@@ -4,14 +4,14 @@
 //! at most once. It operates on a fully-typed, fully-solved AST.
 //!
 //! The move checker does NOT modify unification, constraint solving, or type
-//! inference. It queries `Ty::kind()` on resolved types to determine which
-//! bindings need single-use tracking, and resolves polymorphic return types
+//! inference. It queries `AdtRegistry::kind()` on resolved types to determine
+//! which bindings need single-use tracking (so `linear` ADTs count as affine), and resolves polymorphic return types
 //! by manually instantiating callee schemes with known argument types.
 
 use crate::adt::AdtRegistry;
 use crate::infer::ty::{Kind, Scheme, Ty, TypeVarId};
-use std::collections::HashMap;
-use strata_ast::ast::{Block, Expr, Pat, Stmt};
+use std::collections::{HashMap, HashSet};
+use strata_ast::ast::{Block, Expr, FnDecl, Pat, Stmt};
 use strata_ast::span::Span;
 
 // ---------------------------------------------------------------------------
@@ -27,29 +27,76 @@ pub enum MoveError {
         used_at: Span,
         previous_use: Span,
     },
+    /// Task handle joined, or passed on, a second time.
+    TaskAlreadyJoined {
+        name: String,
+        used_at: Span,
+        previous_use: Span,
+    },
+    /// Capability was consumed on some, but not all, paths reaching this use.
+    MaybeUsed {
+        name: String,
+        used_at: Span,
+        moved_in_branch: Span,
+    },
     /// Capability used inside a loop (would be used multiple times).
     UsedInLoop { name: String, used_at: Span },
+    /// Capability of an enclosing function used inside a nested `fn`, which
+    /// could use it again on every call.
+    Captured {
+        name: String,
+        fn_name: String,
+        used_at: Span,
+    },
+    /// Capability bound by a `match &x` arm used by value: the match only
+    /// borrows `x`, so its arms may only borrow what they bind.
+    MovedFromBorrow {
+        name: String,
+        used_at: Span,
+        borrowed_at: Span,
+    },
 }
 
 impl std::fmt::Display for MoveError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            MoveError::AlreadyUsed {
-                name,
-                used_at,
-                previous_use,
-            } => write!(
+            MoveError::AlreadyUsed { name, .. } => write!(
                 f,
-                "capability '{}' has already been used at {:?}; \
-                 permission was transferred at {:?}; \
+                "capability '{}' has already been used; \
+                 permission was transferred earlier; \
                  '{}' is no longer available",
-                name, used_at, previous_use, name
+                name, name
+            ),
+            MoveError::TaskAlreadyJoined { name, .. } => write!(
+                f,
+                "task '{}' has already been joined or passed on; \
+                 a task can be joined only once",
+                name
+            ),
+            MoveError::MaybeUsed { name, .. } => write!(
+                f,
+                "capability '{}' may have already been used; \
+                 permission was transferred in a conditional branch; \
+                 '{}' is not available on every path",
+                name, name
             ),
-            MoveError::UsedInLoop { name, used_at } => write!(
+            MoveError::UsedInLoop { name, .. } => write!(
                 f,
-                "cannot use single-use capability '{}' inside loop at {:?}; \
+                "cannot use single-use capability '{}' inside loop; \
                  '{}' would be used on every iteration",
-                name, used_at, name
+                name, name
+            ),
+            MoveError::Captured { name, fn_name, .. } => write!(
+                f,
+                "cannot use single-use capability '{}' inside nested function '{}'; \
+                 pass it to '{}' as a parameter instead",
+                name, fn_name, fn_name
+            ),
+            MoveError::MovedFromBorrow { name, .. } => write!(
+                f,
+                "cannot transfer capability '{}'; \
+                 the match only borrows what it binds: use `&{}`",
+                name, name
             ),
         }
     }
@@ -74,8 +121,15 @@ struct BindingId {
 enum MoveState {
     Alive,
     Consumed(Span),
+    /// Consumed in at least one branch of an earlier `if`/`match` but not in
+    /// all of them. The span is the use inside the consuming branch.
+    MaybeConsumed(Span),
 }
 
+/// Move states at the end of one branch, and whether that branch diverged
+/// (ended in `return`) and therefore does not flow into the join.
+type BranchState = (HashMap<BindingId, TrackedBinding>, bool);
+
 /// Information about a tracked (affine) binding.
 #[derive(Clone, Debug)]
 struct TrackedBinding {
@@ -97,8 +151,15 @@ pub struct MoveChecker<'a> {
     generation: u32,
     /// Whether we're currently inside a loop body.
     in_loop: bool,
+    /// Whether the current path has diverged (hit a `return`).
+    diverged: bool,
     /// Collected errors.
     errors: Vec<MoveError>,
+    /// Every affine binding introduced, in order, with its definition span.
+    affine_defs: Vec<(BindingId, Span)>,
+    /// Affine bindings used (consumed or borrowed) on any path. Unlike
+    /// `tracked`, this is never rolled back at branch joins.
+    used: HashSet<BindingId>,
     /// Maps BindingId to resolved types (keyed by generation, not just name,
     /// so shadowing cannot corrupt type lookups).
     binding_types: HashMap<BindingId, Ty>,
@@ -107,22 +168,96 @@ pub struct MoveChecker<'a> {
     env: &'a HashMap<String, Scheme>,
     /// ADT registry for resolving generic field types in pattern destructuring.
     adt_registry: &'a AdtRegistry,
+    /// Solved signatures of nested `fn`s, keyed by declaration span.
+    local_fns: &'a HashMap<Span, Ty>,
+    /// When checking a nested `fn`: its name, and the affine bindings of the
+    /// enclosing functions it must not capture.
+    nested_in: Option<(String, HashSet<String>)>,
+    /// Affine bindings of `match &x` arms, each with the span of the borrow
+    /// and the binding borrowed from, if `x` is one. They may only be
+    /// borrowed, and only while `x` is alive.
+    borrowed: HashMap<BindingId, (Span, Option<BindingId>)>,
+    /// While introducing the bindings of a `match &x` arm: the borrow and
+    /// the binding borrowed from, for [`Self::borrowed`]
+    borrowing: Option<(Span, Option<BindingId>)>,
 }
 
 impl<'a> MoveChecker<'a> {
-    fn new(env: &'a HashMap<String, Scheme>, adt_registry: &'a AdtRegistry) -> Self {
+    fn new(
+        env: &'a HashMap<String, Scheme>,
+        adt_registry: &'a AdtRegistry,
+        local_fns: &'a HashMap<Span, Ty>,
+    ) -> Self {
         MoveChecker {
             name_to_id: HashMap::new(),
             tracked: HashMap::new(),
             generation: 0,
             in_loop: false,
+            diverged: false,
             errors: Vec::new(),
+            affine_defs: Vec::new(),
+            used: HashSet::new(),
             binding_types: HashMap::new(),
             env,
             adt_registry,
+            local_fns,
+            nested_in: None,
+            borrowed: HashMap::new(),
+            borrowing: None,
         }
     }
 
+    /// Report a use of `name` that reaches an affine binding of an
+    /// enclosing function from inside a nested `fn`.
+    fn check_capture(&mut self, name: &str, span: Span) {
+        if self.name_to_id.contains_key(name) {
+            return;
+        }
+        if let Some((fn_name, outer)) = &self.nested_in {
+            if outer.contains(name) {
+                self.errors.push(MoveError::Captured {
+                    name: name.to_string(),
+                    fn_name: fn_name.clone(),
+                    used_at: span,
+                });
+            }
+        }
+    }
+
+    /// Check a nested `fn` as a function of its own. Its body may not use
+    /// any affine binding visible here, alive or not.
+    fn check_nested_fn(&mut self, decl: &FnDecl, fn_ty: &Ty) {
+        let mut outer: HashSet<String> = self
+            .name_to_id
+            .iter()
+            .filter(|(_, id)| self.tracked.contains_key(id))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if let Some((_, enclosing)) = &self.nested_in {
+            outer.extend(enclosing.iter().cloned());
+        }
+
+        let mut nested = MoveChecker::new(self.env, self.adt_registry, self.local_fns);
+        nested.generation = self.generation;
+        nested.nested_in = Some((decl.name.text.clone(), outer));
+        nested.introduce_binding(&decl.name.text, fn_ty, decl.name.span);
+        let param_tys = match fn_ty {
+            Ty::Arrow(params, _, _) => params.clone(),
+            _ => vec![],
+        };
+        for (i, param) in decl.params.iter().enumerate() {
+            let ty = param_tys.get(i).cloned().unwrap_or_else(Ty::unit);
+            nested.introduce_binding(&param.name.text, &ty, param.name.span);
+        }
+        nested.check_block(&decl.body);
+
+        // Share generations so binding ids stay unique across both checkers
+        self.generation = nested.generation;
+        self.errors.extend(nested.errors);
+        self.affine_defs.extend(nested.affine_defs);
+        self.used.extend(nested.used);
+    }
+
     /// Introduce a new binding. If its type is affine, start tracking it.
     fn introduce_binding(&mut self, name: &str, ty: &Ty, span: Span) {
         self.generation += 1;
@@ -133,7 +268,15 @@ impl<'a> MoveChecker<'a> {
         self.name_to_id.insert(name.to_string(), id.clone());
         self.binding_types.insert(id.clone(), ty.clone());
 
-        if ty.kind() == Kind::Affine {
+        if self.adt_registry.kind(ty) == Kind::Affine {
+            // A binding of a `match &x` arm can't be dropped, only left
+            // alone, so it's never reported unused
+            match &self.borrowing {
+                Some(borrow) => {
+                    self.borrowed.insert(id.clone(), borrow.clone());
+                }
+                None => self.affine_defs.push((id.clone(), span)),
+            }
             self.tracked.insert(
                 id,
                 TrackedBinding {
@@ -150,6 +293,11 @@ impl<'a> MoveChecker<'a> {
         self.binding_types.get(id)
     }
 
+    /// Whether the binding `id` holds a `Task<T>` handle.
+    fn is_task(&self, id: &BindingId) -> bool {
+        matches!(self.binding_types.get(id), Some(Ty::Adt { name, .. }) if name == "Task")
+    }
+
     /// Check if a binding is tracked as affine.
     fn is_affine(&self, name: &str) -> bool {
         if let Some(id) = self.name_to_id.get(name) {
@@ -161,19 +309,31 @@ impl<'a> MoveChecker<'a> {
 
     /// Use (consume) an affine binding. Emits error if already consumed or in a loop.
     fn use_binding(&mut self, name: &str, use_span: Span) {
-        let id = match self.name_to_id.get(name) {
-            Some(id) => id.clone(),
-            None => return,
-        };
+        if let Some(id) = self.name_to_id.get(name) {
+            self.use_id(id.clone(), use_span);
+        }
+    }
 
+    /// Use (consume) the binding `id`, which a later one may have shadowed.
+    fn use_id(&mut self, id: BindingId, use_span: Span) {
         let tracked = match self.tracked.get(&id) {
             Some(t) => t.clone(),
             None => return,
         };
+        self.used.insert(id.clone());
+
+        if let Some((borrowed_at, _)) = self.borrowed.get(&id) {
+            self.errors.push(MoveError::MovedFromBorrow {
+                name: id.name,
+                used_at: use_span,
+                borrowed_at: *borrowed_at,
+            });
+            return;
+        }
 
         if self.in_loop {
             self.errors.push(MoveError::UsedInLoop {
-                name: name.to_string(),
+                name: id.name,
                 used_at: use_span,
             });
             return;
@@ -189,12 +349,10 @@ impl<'a> MoveChecker<'a> {
                     },
                 );
             }
-            MoveState::Consumed(previous_span) => {
-                self.errors.push(MoveError::AlreadyUsed {
-                    name: name.to_string(),
-                    used_at: use_span,
-                    previous_use: previous_span,
-                });
+            MoveState::Consumed(_) | MoveState::MaybeConsumed(_) => {
+                let task = self.is_task(&id);
+                self.errors
+                    .push(consumed_error(&id.name, use_span, &tracked.state, task));
             }
         }
     }
@@ -209,34 +367,67 @@ impl<'a> MoveChecker<'a> {
         self.tracked = snapshot;
     }
 
-    /// Pessimistic join: if consumed in ANY branch, mark as consumed.
-    fn pessimistic_join(
-        &mut self,
-        base: &HashMap<BindingId, TrackedBinding>,
-        branches: &[HashMap<BindingId, TrackedBinding>],
-    ) {
+    /// Run `check` once per branch from the same starting state, then join.
+    fn check_branches<T>(&mut self, branches: &[T], mut check: impl FnMut(&mut Self, &T)) {
+        let base = self.snapshot();
+        let base_diverged = self.diverged;
+        let mut states: Vec<BranchState> = Vec::new();
+
+        for branch in branches {
+            self.restore(base.clone());
+            self.diverged = base_diverged;
+            check(self, branch);
+            states.push((self.snapshot(), self.diverged));
+        }
+
+        self.restore(base.clone());
+        self.join(&base, &states);
+        self.diverged = base_diverged || states.iter().all(|(_, diverged)| *diverged);
+    }
+
+    /// Join branch states. Branches that diverged are ignored. A binding
+    /// consumed on every remaining branch is consumed; one consumed on only
+    /// some of them is maybe-consumed, remembering the consuming branch's span.
+    fn join(&mut self, base: &HashMap<BindingId, TrackedBinding>, branches: &[BranchState]) {
+        let live: Vec<_> = branches
+            .iter()
+            .filter(|(_, diverged)| !*diverged)
+            .map(|(state, _)| state)
+            .collect();
+        if live.is_empty() {
+            return;
+        }
+
         for (id, base_tracked) in base {
             let mut consumed_span: Option<Span> = None;
+            let mut all_consumed = true;
+            let mut any_maybe = false;
 
-            for branch in branches {
-                if let Some(branch_tracked) = branch.get(id) {
-                    if let MoveState::Consumed(span) = &branch_tracked.state {
-                        consumed_span = Some(*span);
+            for branch in &live {
+                match branch.get(id).map(|t| &t.state) {
+                    Some(MoveState::Consumed(span)) => {
+                        consumed_span.get_or_insert(*span);
+                    }
+                    Some(MoveState::MaybeConsumed(span)) => {
+                        consumed_span.get_or_insert(*span);
+                        any_maybe = true;
                     }
+                    Some(MoveState::Alive) | None => all_consumed = false,
                 }
             }
 
-            if let Some(span) = consumed_span {
-                self.tracked.insert(
-                    id.clone(),
-                    TrackedBinding {
-                        state: MoveState::Consumed(span),
-                        def_span: base_tracked.def_span,
-                    },
-                );
-            } else {
-                self.tracked.insert(id.clone(), base_tracked.clone());
-            }
+            let state = match consumed_span {
+                None => continue,
+                Some(span) if all_consumed && !any_maybe => MoveState::Consumed(span),
+                Some(span) => MoveState::MaybeConsumed(span),
+            };
+            self.tracked.insert(
+                id.clone(),
+                TrackedBinding {
+                    state,
+                    def_span: base_tracked.def_span,
+                },
+            );
         }
     }
 
@@ -250,12 +441,25 @@ impl<'a> MoveChecker<'a> {
     /// For example, if `Box<T>` has variant `Val(T)` and the scrutinee is
     /// `Box<FsCap>`, this returns `[FsCap]` for the variant fields.
     ///
+    /// A tuple struct pattern `Holder(c)` resolves its positional fields.
+    ///
     /// Returns None if the ADT or variant can't be resolved (fallback to unit).
     fn resolve_variant_field_types(
         &self,
         path: &strata_ast::ast::Path,
         scrutinee_ty: &Ty,
     ) -> Option<Vec<Ty>> {
+        if path.segments.len() == 1 {
+            let adt_def = self.adt_registry.get(&path.segments[0].text)?;
+            if !adt_def.is_tuple_struct() {
+                return None;
+            }
+            let mut fields = self.resolve_struct_field_types(path, scrutinee_ty)?;
+            return (0..fields.len())
+                .map(|i| fields.remove(&i.to_string()))
+                .collect();
+        }
+
         // Path for a variant is e.g. ["Box", "Val"] — first segment is ADT name
         if path.segments.len() < 2 {
             return None;
@@ -364,10 +568,23 @@ impl<'a> MoveChecker<'a> {
                     if let Some(ty) = self.get_binding_type(name) {
                         return ty.clone();
                     }
+                    return Ty::unit();
+                }
+                // Unit variant, e.g. `Token::Empty`
+                let qualified = path
+                    .segments
+                    .iter()
+                    .map(|s| s.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                match self.env.get(&qualified) {
+                    Some(scheme) => self.instantiate_return_type(scheme, &[]),
+                    None => Ty::unit(),
                 }
-                Ty::unit()
             }
 
+            Expr::StructExpr { path, fields, .. } => self.resolve_struct_expr_type(path, fields),
+
             Expr::Call { callee, args, .. } => self.resolve_call_return_type(callee, args),
 
             Expr::If { then_, else_, .. } => {
@@ -388,6 +605,15 @@ impl<'a> MoveChecker<'a> {
                 Ty::unit()
             }
 
+            // A task handle, which is linear whatever the task gives
+            Expr::Spawn { body, .. } => {
+                let result = match &body.tail {
+                    Some(tail) => self.resolve_expr_type(tail),
+                    None => Ty::unit(),
+                };
+                Ty::adt("Task", vec![result])
+            }
+
             Expr::Match { arms, .. } => {
                 // Resolve from first arm body
                 if let Some(arm) = arms.first() {
@@ -400,13 +626,63 @@ impl<'a> MoveChecker<'a> {
                 Ty::Tuple(elems.iter().map(|e| self.resolve_expr_type(e)).collect())
             }
 
+            Expr::Record { fields, .. } => Ty::Record(
+                fields
+                    .iter()
+                    .map(|f| (f.name.text.clone(), self.resolve_expr_type(&f.value)))
+                    .collect(),
+                None,
+            ),
+
+            Expr::Field { expr, field, .. } => match self.resolve_expr_type(expr) {
+                Ty::Record(mut fields, _) => fields.remove(&field.text).unwrap_or_else(Ty::unit),
+                _ => Ty::unit(),
+            },
+
             Expr::Borrow(inner, _) => Ty::Ref(Box::new(self.resolve_expr_type(inner))),
 
-            // Literals, binary, unary, struct exprs, etc. are always unrestricted
+            // Literals, binary, unary, etc. are always unrestricted
             _ => Ty::unit(),
         }
     }
 
+    /// Resolve the ADT type built by a struct literal, inferring generic
+    /// arguments from the field initializers.
+    fn resolve_struct_expr_type(
+        &self,
+        path: &strata_ast::ast::Path,
+        inits: &[strata_ast::ast::FieldInit],
+    ) -> Ty {
+        let Some(first) = path.segments.first() else {
+            return Ty::unit();
+        };
+        let name = first.text.clone();
+        let Some(adt_def) = self.adt_registry.get(&name) else {
+            return Ty::unit();
+        };
+        if adt_def.type_params.is_empty() {
+            return Ty::Adt { name, args: vec![] };
+        }
+
+        // ADT type params are registered with TypeVarId(0), TypeVarId(1), etc.
+        let bound: Vec<TypeVarId> = (0..adt_def.type_params.len() as u32)
+            .map(TypeVarId)
+            .collect();
+        let mut mapping: HashMap<TypeVarId, Ty> = HashMap::new();
+        for init in inits {
+            let fields = adt_def.fields().unwrap_or(&[]);
+            if let Some(field) = fields.iter().find(|f| f.name == init.name.text) {
+                let arg_ty = self.resolve_expr_type(&init.value);
+                collect_var_mapping(&field.ty, &arg_ty, &bound, &mut mapping);
+            }
+        }
+        let args = bound
+            .iter()
+            .map(|v| mapping.get(v).cloned().unwrap_or_else(Ty::unit))
+            .collect();
+        Ty::Adt { name, args }
+    }
+
     /// Resolve the return type of a function call.
     ///
     /// For polymorphic callees, instantiates the scheme with argument types.
@@ -468,9 +744,10 @@ impl<'a> MoveChecker<'a> {
     /// Check an expression for move violations.
     fn check_expr(&mut self, expr: &Expr) {
         match expr {
-            Expr::Lit(_, _) => {}
+            Expr::Lit(_, _) | Expr::Hole(_) => {}
 
             Expr::Var(ident) => {
+                self.check_capture(&ident.text, ident.span);
                 if self.is_affine(&ident.text) {
                     self.use_binding(&ident.text, ident.span);
                 }
@@ -510,23 +787,15 @@ impl<'a> MoveChecker<'a> {
                 // Condition is always unrestricted (Bool)
                 self.check_expr(cond);
 
-                // Save state before branches
-                let base = self.snapshot();
-
-                // Check then-branch
-                self.check_block(then_);
-                let then_state = self.snapshot();
-
-                // Restore and check else-branch
-                self.restore(base.clone());
-                if let Some(else_expr) = else_ {
-                    self.check_expr(else_expr);
-                }
-                let else_state = self.snapshot();
-
-                // Pessimistic join
-                self.restore(base.clone());
-                self.pessimistic_join(&base, &[then_state, else_state]);
+                // A missing else-branch is an empty branch that uses nothing
+                self.check_branches(&[Some(then_), None], |this, branch| match branch {
+                    Some(then_) => this.check_block(then_),
+                    None => {
+                        if let Some(else_expr) = else_ {
+                            this.check_expr(else_expr);
+                        }
+                    }
+                });
             }
 
             Expr::While { cond, body, .. } => {
@@ -538,33 +807,78 @@ impl<'a> MoveChecker<'a> {
                 self.in_loop = was_in_loop;
             }
 
+            Expr::For {
+                pat, iter, body, ..
+            } => {
+                let item_ty = match self.resolve_expr_type(iter) {
+                    Ty::Adt { name, mut args } if name == "Iter" && args.len() == 1 => {
+                        args.remove(0)
+                    }
+                    _ => Ty::unit(),
+                };
+                self.check_expr(iter);
+
+                // Each item is the body's own, but like a while body it runs
+                // repeatedly, so it can't consume one
+                let was_in_loop = self.in_loop;
+                self.in_loop = true;
+                self.introduce_pattern_bindings(pat, &item_ty);
+                self.check_block(body);
+                self.in_loop = was_in_loop;
+            }
+
+            Expr::Spawn { body, span } => {
+                // The task takes every single-use binding it mentions, even
+                // one it only borrows: it may run alongside what follows
+                let outer: HashSet<BindingId> = self.tracked.keys().cloned().collect();
+                let used_before = std::mem::take(&mut self.used);
+                self.check_block(body);
+                let mentioned = std::mem::replace(&mut self.used, used_before);
+                for id in &mentioned {
+                    let alive = self
+                        .tracked
+                        .get(id)
+                        .is_some_and(|t| matches!(t.state, MoveState::Alive));
+                    if outer.contains(id) && alive {
+                        self.use_id(id.clone(), *span);
+                    }
+                }
+                self.used.extend(mentioned);
+            }
+
             Expr::Match {
                 scrutinee, arms, ..
             } => {
+                // `match &x` borrows `x`, and its arms match what `x` holds
+                let borrow = match scrutinee.as_ref() {
+                    Expr::Borrow(inner, span) => {
+                        let root = match inner.as_ref() {
+                            Expr::Var(id) => self.name_to_id.get(&id.text).cloned(),
+                            _ => None,
+                        };
+                        Some((inner.as_ref(), (*span, root)))
+                    }
+                    _ => None,
+                };
+
                 // Resolve scrutinee type BEFORE checking (so we can type pattern bindings)
-                let scrut_ty = self.resolve_expr_type(scrutinee);
+                let scrut_ty = match &borrow {
+                    Some((inner, _)) => self.resolve_expr_type(inner),
+                    None => self.resolve_expr_type(scrutinee),
+                };
 
                 // Check scrutinee (may consume an affine binding)
                 self.check_expr(scrutinee);
 
-                let base = self.snapshot();
-                let mut arm_states = Vec::new();
-
-                for arm in arms {
-                    self.restore(base.clone());
-
+                let borrowing = borrow.map(|(_, borrowing)| borrowing);
+                self.check_branches(arms, |this, arm| {
                     // Introduce pattern bindings with the scrutinee's type
                     // so that capability bindings are correctly tracked as affine.
-                    self.introduce_pattern_bindings(&arm.pat, &scrut_ty);
-
-                    self.check_expr(&arm.body);
-                    arm_states.push(self.snapshot());
-                }
-
-                self.restore(base.clone());
-                if !arm_states.is_empty() {
-                    self.pessimistic_join(&base, &arm_states);
-                }
+                    this.borrowing = borrowing.clone();
+                    this.introduce_pattern_bindings(&arm.pat, &scrut_ty);
+                    this.borrowing = None;
+                    this.check_expr(&arm.body);
+                });
             }
 
             Expr::Tuple { elems, .. } => {
@@ -573,15 +887,21 @@ impl<'a> MoveChecker<'a> {
                 }
             }
 
-            Expr::StructExpr { fields, .. } => {
+            Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
                 for field in fields {
                     self.check_expr(&field.value);
                 }
             }
 
+            // Reading a field uses the whole record
+            Expr::Field { expr, .. } => {
+                self.check_expr(expr);
+            }
+
             Expr::PathExpr(path) => {
                 if path.segments.len() == 1 {
                     let name = &path.segments[0].text;
+                    self.check_capture(name, path.span);
                     if self.is_affine(name) {
                         self.use_binding(name, path.span);
                     }
@@ -592,17 +912,34 @@ impl<'a> MoveChecker<'a> {
                 // Borrow checks that the inner var is alive but does NOT consume it.
                 // No loop restriction — borrows are repeatable.
                 if let Expr::Var(ident) = inner.as_ref() {
+                    self.check_capture(&ident.text, ident.span);
                     if let Some(id) = self.name_to_id.get(&ident.text) {
                         if let Some(tracked) = self.tracked.get(id) {
-                            if let MoveState::Consumed(previous_span) = &tracked.state {
-                                self.errors.push(MoveError::AlreadyUsed {
-                                    name: ident.text.clone(),
-                                    used_at: *span,
-                                    previous_use: *previous_span,
-                                });
+                            self.used.insert(id.clone());
+                            if !matches!(tracked.state, MoveState::Alive) {
+                                self.errors.push(consumed_error(
+                                    &ident.text,
+                                    *span,
+                                    &tracked.state,
+                                    self.is_task(id),
+                                ));
                             }
                             // No state change — borrow doesn't consume
                         }
+                        // Bound by a `match &x` arm: `x` must still be held
+                        let root = self.borrowed.get(id).and_then(|(_, root)| root.as_ref());
+                        if let Some(root) = root {
+                            if let Some(tracked) = self.tracked.get(root) {
+                                if !matches!(tracked.state, MoveState::Alive) {
+                                    self.errors.push(consumed_error(
+                                        &root.name,
+                                        *span,
+                                        &tracked.state,
+                                        self.is_task(root),
+                                    ));
+                                }
+                            }
+                        }
                     }
                 } else {
                     // Complex borrow expression — recurse
@@ -614,7 +951,13 @@ impl<'a> MoveChecker<'a> {
 
     /// Check a block for move violations.
     fn check_block(&mut self, block: &Block) {
-        for stmt in &block.stmts {
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            // A group of nested functions is visible from its first body on,
+            // unrestricted
+            for decl in block.fn_group_at(i) {
+                let fn_ty = self.local_fn_ty(decl);
+                self.introduce_binding(&decl.name.text, &fn_ty, decl.name.span);
+            }
             self.check_stmt(stmt);
         }
 
@@ -641,12 +984,18 @@ impl<'a> MoveChecker<'a> {
                 let _ = span;
             }
 
+            // Nothing to own until the first assignment; the unrestricted
+            // placeholder still shadows any outer binding of the name
+            Stmt::Declare { name, .. } => {
+                self.introduce_binding(&name.text, &Ty::unit(), name.span);
+            }
+
             Stmt::Assign { target, value, .. } => {
                 let rhs_ty = self.resolve_expr_type(value);
                 self.check_expr(value);
 
                 // If the new value is affine, re-introduce the target as alive
-                if rhs_ty.kind() == Kind::Affine {
+                if self.adt_registry.kind(&rhs_ty) == Kind::Affine {
                     self.introduce_binding(&target.text, &rhs_ty, target.span);
                 }
             }
@@ -659,17 +1008,33 @@ impl<'a> MoveChecker<'a> {
                 if let Some(val_expr) = value {
                     self.check_expr(val_expr);
                 }
+                self.diverged = true;
+            }
+
+            // Bound with its group by `check_block`
+            Stmt::Fn(decl) => {
+                let fn_ty = self.local_fn_ty(decl);
+                self.check_nested_fn(decl, &fn_ty);
             }
         }
     }
 
+    /// The solved signature of a nested function
+    fn local_fn_ty(&self, decl: &FnDecl) -> Ty {
+        self.local_fns
+            .get(&decl.span)
+            .cloned()
+            .unwrap_or_else(Ty::unit)
+    }
+
     /// Introduce bindings from a pattern.
     fn introduce_pattern_bindings(&mut self, pat: &Pat, ty: &Ty) {
         match pat {
             Pat::Ident(ident) => {
                 self.introduce_binding(&ident.text, ty, ident.span);
             }
-            Pat::Wildcard(_) | Pat::Literal(_, _) => {}
+            Pat::Wildcard(_) | Pat::Literal(_, _) | Pat::Range { .. } => {}
+            Pat::StrPrefix { rest, .. } => self.introduce_pattern_bindings(rest, &Ty::string()),
             Pat::Tuple(pats, _) => {
                 if let Ty::Tuple(tys) = ty {
                     for (p, t) in pats.iter().zip(tys.iter()) {
@@ -711,10 +1076,43 @@ impl<'a> MoveChecker<'a> {
                     self.introduce_pattern_bindings(&f.pat, field_ty);
                 }
             }
+            Pat::Record { fields, .. } => {
+                let unit = Ty::unit();
+                for f in fields {
+                    let field_ty = match ty {
+                        Ty::Record(tys, _) => tys.get(&f.name.text).unwrap_or(&unit),
+                        _ => &unit,
+                    };
+                    self.introduce_pattern_bindings(&f.pat, field_ty);
+                }
+            }
         }
     }
 }
 
+/// Error for using a binding whose state is not `Alive`; `task` says the
+/// binding is a `Task<T>` handle.
+fn consumed_error(name: &str, used_at: Span, state: &MoveState, task: bool) -> MoveError {
+    match state {
+        MoveState::MaybeConsumed(moved_in_branch) => MoveError::MaybeUsed {
+            name: name.to_string(),
+            used_at,
+            moved_in_branch: *moved_in_branch,
+        },
+        MoveState::Consumed(previous_use) if task => MoveError::TaskAlreadyJoined {
+            name: name.to_string(),
+            used_at,
+            previous_use: *previous_use,
+        },
+        MoveState::Consumed(previous_use) => MoveError::AlreadyUsed {
+            name: name.to_string(),
+            used_at,
+            previous_use: *previous_use,
+        },
+        MoveState::Alive => unreachable!("using an alive binding is not an error"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Type mapping helpers (for polymorphic instantiation)
 // ---------------------------------------------------------------------------
@@ -745,6 +1143,15 @@ fn collect_var_mapping(
                 }
             }
         }
+        Ty::Record(fields, _) => {
+            if let Ty::Record(arg_fields, _) = arg {
+                for (name, p) in fields {
+                    if let Some(a) = arg_fields.get(name) {
+                        collect_var_mapping(p, a, bound_vars, mapping);
+                    }
+                }
+            }
+        }
         Ty::List(inner) => {
             if let Ty::List(arg_inner) = arg {
                 collect_var_mapping(inner, arg_inner, bound_vars, mapping);
@@ -783,6 +1190,13 @@ fn apply_type_mapping(ty: &Ty, mapping: &HashMap<TypeVarId, Ty>) -> Ty {
             *eff,
         ),
         Ty::Tuple(tys) => Ty::Tuple(tys.iter().map(|t| apply_type_mapping(t, mapping)).collect()),
+        Ty::Record(fields, tail) => Ty::record_with_tail(
+            fields
+                .iter()
+                .map(|(name, t)| (name.clone(), apply_type_mapping(t, mapping)))
+                .collect(),
+            tail.map(|v| apply_type_mapping(&Ty::Var(v), mapping)),
+        ),
         Ty::List(t) => Ty::List(Box::new(apply_type_mapping(t, mapping))),
         Ty::Adt { name, args } => Ty::Adt {
             name: name.clone(),
@@ -804,15 +1218,19 @@ fn apply_type_mapping(ty: &Ty, mapping: &HashMap<TypeVarId, Ty>) -> Ty {
 /// `params` is a list of (name, resolved_type, span) triples for parameters.
 /// `body` is the function body block.
 /// `env` is the type environment with generalized function schemes.
+/// `local_fns` holds the solved signatures of nested `fn`s by declaration span.
 ///
-/// Returns the first error found, or Ok(()).
+/// Returns the first error found, or the affine bindings that are never used
+/// (name, definition span, and whether it's a `Task<T>` handle). Names
+/// starting with `_` are never reported.
 pub fn check_function_body(
     params: &[(String, Ty, Span)],
     body: &Block,
     env: &HashMap<String, Scheme>,
     adt_registry: &AdtRegistry,
-) -> Result<(), MoveError> {
-    let mut checker = MoveChecker::new(env, adt_registry);
+    local_fns: &HashMap<Span, Ty>,
+) -> Result<Vec<(String, Span, bool)>, MoveError> {
+    let mut checker = MoveChecker::new(env, adt_registry, local_fns);
 
     // Introduce function parameters as alive bindings
     for (name, ty, span) in params {
@@ -823,9 +1241,18 @@ pub fn check_function_body(
     checker.check_block(body);
 
     // Return first error
-    if let Some(err) = checker.errors.into_iter().next() {
-        Err(err)
-    } else {
-        Ok(())
+    if let Some(err) = checker.errors.drain(..).next() {
+        return Err(err);
     }
+
+    let defs = std::mem::take(&mut checker.affine_defs);
+    let unused = defs
+        .into_iter()
+        .filter(|(id, _)| !checker.used.contains(id) && !id.name.starts_with('_'))
+        .map(|(id, span)| {
+            let task = checker.is_task(&id);
+            (id.name, span, task)
+        })
+        .collect();
+    Ok(unused)
 }
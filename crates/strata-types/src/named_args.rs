@@ -0,0 +1,372 @@
+//! Named arguments: `draw(x: 10, y: 20)`.
+//!
+//! A call may pass arguments by parameter name, after any positional ones
+//! and in any order, to a function the module declares with `fn` or
+//! `extern fn`. [`expand_named_args`] puts each such call's arguments in
+//! parameter order before checking and evaluation, so later passes read
+//! them by position; the names stay on the call for type errors to report.
+//! A defaulted `extern fn` parameter a named call skips gets its default
+//! value written in, unless every parameter after it is skipped too; those
+//! are left off, as in a positional call. Expanding twice changes nothing.
+
+use crate::checker::nested_fns_in_block;
+use crate::consts::{self, ConstValue};
+use crate::resolve::{self, DefId, Resolution};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use strata_ast::ast::{Block, Expr, FnDecl, Ident, Item, Module, Param, Stmt};
+use strata_ast::span::Span;
+
+/// A call whose named arguments don't fit the function called.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NamedArgError {
+    /// Named arguments to something other than a function the module
+    /// declares (a closure, a constructor, a builtin...); `callee` is the
+    /// name called, if it is a name
+    NotAFunction { callee: Option<String>, span: Span },
+    /// A positional argument after a named one
+    PositionalAfterNamed { callee: String, span: Span },
+    /// A name the function has no parameter for
+    UnknownParam {
+        callee: String,
+        name: String,
+        span: Span,
+    },
+    /// A parameter given a value twice, by name or by position and name
+    Duplicate {
+        callee: String,
+        name: String,
+        span: Span,
+    },
+    /// A variadic parameter passed by name
+    Variadic {
+        callee: String,
+        name: String,
+        span: Span,
+    },
+    /// A parameter without a default that no argument gives a value
+    Missing {
+        callee: String,
+        name: String,
+        span: Span,
+    },
+}
+
+impl NamedArgError {
+    pub fn span(&self) -> Span {
+        match self {
+            NamedArgError::NotAFunction { span, .. }
+            | NamedArgError::PositionalAfterNamed { span, .. }
+            | NamedArgError::UnknownParam { span, .. }
+            | NamedArgError::Duplicate { span, .. }
+            | NamedArgError::Variadic { span, .. }
+            | NamedArgError::Missing { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for NamedArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NamedArgError::NotAFunction {
+                callee: Some(callee),
+                ..
+            } => write!(
+                f,
+                "'{}' takes no named arguments; only functions declared with fn or extern fn do",
+                callee
+            ),
+            NamedArgError::NotAFunction { callee: None, .. } => {
+                write!(f, "Named arguments need a call to a function by name")
+            }
+            NamedArgError::PositionalAfterNamed { callee, .. } => write!(
+                f,
+                "Positional argument after a named one in a call to '{}'",
+                callee
+            ),
+            NamedArgError::UnknownParam { callee, name, .. } => {
+                write!(f, "'{}' has no parameter '{}'", callee, name)
+            }
+            NamedArgError::Duplicate { callee, name, .. } => write!(
+                f,
+                "Parameter '{}' of '{}' is given more than one argument",
+                name, callee
+            ),
+            NamedArgError::Variadic { callee, name, .. } => write!(
+                f,
+                "Variadic parameter '{}' of '{}' can't be passed by name",
+                name, callee
+            ),
+            NamedArgError::Missing { callee, name, .. } => write!(
+                f,
+                "Call to '{}' is missing an argument for parameter '{}'",
+                callee, name
+            ),
+        }
+    }
+}
+
+/// `module` with the arguments of every call passing any by name in
+/// parameter order. Borrowed when no call does.
+pub fn expand_named_args(module: &Module) -> Result<Cow<'_, Module>, NamedArgError> {
+    let mut expander = Expander {
+        res: resolve::resolve(module),
+        fns: HashMap::new(),
+        changed: false,
+    };
+    for item in &module.items {
+        match item {
+            Item::Fn(decl) => {
+                expander.declare(&decl.name, &decl.params, module);
+                for nested in nested_fns_in_block(&decl.body) {
+                    expander.declare(&nested.name, &nested.params, module);
+                }
+            }
+            Item::ExternFn(decl) => expander.declare(&decl.name, &decl.params, module),
+            _ => {}
+        }
+    }
+
+    let mut expanded = module.clone();
+    for item in &mut expanded.items {
+        match item {
+            Item::Let(decl) => expander.expr(&mut decl.value)?,
+            Item::Const(decl) => expander.expr(&mut decl.value)?,
+            Item::Fn(decl) => expander.fn_decl(decl)?,
+            _ => {}
+        }
+    }
+    Ok(match expander.changed {
+        true => Cow::Owned(expanded),
+        false => Cow::Borrowed(module),
+    })
+}
+
+/// A function's parameters, for matching names to positions
+struct Signature {
+    name: String,
+    params: Vec<Param>,
+    /// Each parameter's default value, if it has one that evaluates
+    defaults: Vec<Option<ConstValue>>,
+}
+
+struct Expander {
+    res: Resolution,
+    /// Functions and externs by the span of their name
+    fns: HashMap<Span, Signature>,
+    /// Whether any call was rewritten
+    changed: bool,
+}
+
+impl Expander {
+    fn declare(&mut self, name: &Ident, params: &[Param], module: &Module) {
+        // A default that doesn't evaluate is written in as it is, for the
+        // checker to report
+        let defaults =
+            consts::evaluate_defaults(module, params).unwrap_or_else(|_| vec![None; params.len()]);
+        self.fns.insert(
+            name.span,
+            Signature {
+                name: name.text.clone(),
+                params: params.to_vec(),
+                defaults,
+            },
+        );
+    }
+
+    fn fn_decl(&mut self, decl: &mut FnDecl) -> Result<(), NamedArgError> {
+        self.block(&mut decl.body)
+    }
+
+    fn block(&mut self, block: &mut Block) -> Result<(), NamedArgError> {
+        for stmt in &mut block.stmts {
+            match stmt {
+                Stmt::Let { value, .. } | Stmt::Assign { value, .. } => self.expr(value)?,
+                Stmt::Expr { expr, .. } => self.expr(expr)?,
+                Stmt::Return { value, .. } => {
+                    if let Some(value) = value {
+                        self.expr(value)?;
+                    }
+                }
+                Stmt::Fn(decl) => self.fn_decl(decl)?,
+                Stmt::Declare { .. } => {}
+            }
+        }
+        if let Some(tail) = &mut block.tail {
+            self.expr(tail)?;
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &mut Expr) -> Result<(), NamedArgError> {
+        match expr {
+            Expr::Lit(..) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+            Expr::Paren { inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::Borrow(inner, _)
+            | Expr::Field { expr: inner, .. } => self.expr(inner)?,
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs)?;
+                self.expr(rhs)?;
+            }
+            Expr::Call {
+                callee,
+                args,
+                names,
+                span,
+            } => {
+                self.expr(callee)?;
+                for arg in args.iter_mut() {
+                    self.expr(arg)?;
+                }
+                if !names.is_empty() {
+                    let given = std::mem::take(args);
+                    (*args, *names) =
+                        self.in_param_order(callee, given, std::mem::take(names), *span)?;
+                    self.changed = true;
+                }
+            }
+            Expr::Block(block) | Expr::Spawn { body: block, .. } => self.block(block)?,
+            Expr::If {
+                cond, then_, else_, ..
+            } => {
+                self.expr(cond)?;
+                self.block(then_)?;
+                if let Some(e) = else_ {
+                    self.expr(e)?;
+                }
+            }
+            Expr::While { cond, body, .. }
+            | Expr::For {
+                iter: cond, body, ..
+            } => {
+                self.expr(cond)?;
+                self.block(body)?;
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee)?;
+                for arm in arms {
+                    self.expr(&mut arm.body)?;
+                }
+            }
+            Expr::Tuple { elems, .. } => {
+                for e in elems {
+                    self.expr(e)?;
+                }
+            }
+            Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+                for f in fields {
+                    self.expr(&mut f.value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The arguments of a call to `callee`, passed as `args` with `names`,
+    /// in parameter order, with their names
+    fn in_param_order(
+        &self,
+        callee: &Expr,
+        args: Vec<Expr>,
+        names: Vec<Option<Ident>>,
+        span: Span,
+    ) -> Result<(Vec<Expr>, Vec<Option<Ident>>), NamedArgError> {
+        let Expr::Var(id) = callee else {
+            return Err(NamedArgError::NotAFunction {
+                callee: None,
+                span: callee.span(),
+            });
+        };
+        let sig = match self.res.ident(id) {
+            Some(DefId::Fn(def) | DefId::Extern(def)) => self.fns.get(def),
+            _ => None,
+        }
+        .ok_or_else(|| NamedArgError::NotAFunction {
+            callee: Some(id.text.clone()),
+            span: id.span,
+        })?;
+        let callee = || sig.name.clone();
+
+        let mut slots: Vec<Option<(Expr, Option<Ident>)>> = vec![None; sig.params.len()];
+        // Positional arguments past the fixed parameters, for a variadic one
+        let mut extra = Vec::new();
+        let mut named = false;
+        for (i, (arg, name)) in args.into_iter().zip(names).enumerate() {
+            let Some(name) = name else {
+                if named {
+                    return Err(NamedArgError::PositionalAfterNamed {
+                        callee: callee(),
+                        span: arg.span(),
+                    });
+                }
+                match sig.params.get(i) {
+                    Some(param) if !param.variadic => slots[i] = Some((arg, None)),
+                    _ => extra.push(arg),
+                }
+                continue;
+            };
+            named = true;
+            let Some(pos) = sig.params.iter().position(|p| p.name.text == name.text) else {
+                return Err(NamedArgError::UnknownParam {
+                    callee: callee(),
+                    name: name.text,
+                    span: name.span,
+                });
+            };
+            if sig.params[pos].variadic {
+                return Err(NamedArgError::Variadic {
+                    callee: callee(),
+                    name: name.text,
+                    span: name.span,
+                });
+            }
+            if slots[pos].is_some() {
+                return Err(NamedArgError::Duplicate {
+                    callee: callee(),
+                    name: name.text,
+                    span: name.span,
+                });
+            }
+            slots[pos] = Some((arg, Some(name)));
+        }
+
+        // Defaulted parameters after the last one given are left off
+        let given = slots.iter().rposition(Option::is_some).map_or(0, |i| i + 1);
+        let mut out = Vec::with_capacity(given + extra.len());
+        for (i, (slot, param)) in slots.into_iter().zip(&sig.params).enumerate() {
+            let missing = || NamedArgError::Missing {
+                callee: callee(),
+                name: param.name.text.clone(),
+                span,
+            };
+            // A default written in is named for its parameter, so the call
+            // stays named in parameter order
+            let default_name = || {
+                Some(Ident {
+                    text: param.name.text.clone(),
+                    span,
+                })
+            };
+            match slot {
+                Some(arg) => out.push(arg),
+                None if param.variadic => {}
+                None if i >= given => {
+                    if param.default.is_none() {
+                        return Err(missing());
+                    }
+                }
+                None => match (&sig.defaults[i], &param.default) {
+                    (Some(value), _) => out.push((Expr::Lit(value.to_lit(), span), default_name())),
+                    (None, Some(default)) => out.push((default.clone(), default_name())),
+                    (None, None) => return Err(missing()),
+                },
+            }
+        }
+        out.extend(extra.into_iter().map(|arg| (arg, None)));
+        Ok(out.into_iter().unzip())
+    }
+}
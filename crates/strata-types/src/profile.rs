@@ -1,18 +1,127 @@
-//! Build/profile awareness at the types layer (no enforcement yet).
+//! Checking profiles: named bundles of the checker's resource limits.
 //!
-//! This allows frontends or later phases to tag contexts with a
-//! profile. Enforcement will be added in later issues.
+//! The limits guard the checker against pathological input (deeply nested
+//! expressions, huge match matrices, items whose constraints take too long
+//! to solve). `Default` keeps the values the checker has always used;
+//! `Strict` tightens them for untrusted or CI-gated code, and `Permissive`
+//! loosens them for generated code that legitimately needs wide tuples or
+//! big matches.
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use std::fmt;
+
+/// A named bundle of [`Limits`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum Profile {
-    /// Safety-first, restricted effects.
-    Safe,
-    /// Full system capabilities available.
-    System,
-    /// Test builds; may allow extra effects/fixtures.
-    Test,
-    /// Constrained embedded environment.
-    Embedded,
-    /// No stdlib available; affects capability & effect availability.
-    NoStd,
+    /// Tight limits: small tuples, shallow inference, a small match budget
+    Strict,
+    /// The checker's standard limits
+    #[default]
+    Default,
+    /// Loose limits for large or generated programs
+    Permissive,
+}
+
+impl Profile {
+    /// Every profile, strictest first
+    pub const ALL: [Profile; 3] = [Profile::Strict, Profile::Default, Profile::Permissive];
+
+    /// The profile's name as written on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Strict => "strict",
+            Profile::Default => "default",
+            Profile::Permissive => "permissive",
+        }
+    }
+
+    /// The profile called `name`, if there is one
+    pub fn from_name(name: &str) -> Option<Profile> {
+        Profile::ALL.into_iter().find(|p| p.name() == name)
+    }
+
+    /// The limits this profile checks under
+    pub fn limits(self) -> Limits {
+        match self {
+            Profile::Strict => Limits {
+                infer_depth: 64,
+                tuple_arity: 16,
+                effect_vars: 1024,
+                pattern_matrix: 2_000,
+                exhaustiveness_depth: 250,
+                constraints: 20_000,
+                solve_steps: 200_000,
+            },
+            Profile::Default => Limits::default(),
+            Profile::Permissive => Limits {
+                infer_depth: 256,
+                tuple_arity: 1024,
+                effect_vars: 16_384,
+                pattern_matrix: 100_000,
+                exhaustiveness_depth: 4_000,
+                constraints: 1_000_000,
+                solve_steps: 10_000_000,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Resource limits the checker enforces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Deepest expression nesting type inference follows
+    pub infer_depth: u32,
+    /// Most elements a tuple may have
+    pub tuple_arity: usize,
+    /// Most effect variables one module may create
+    pub effect_vars: u32,
+    /// Largest pattern matrix (rows × columns) an exhaustiveness check builds
+    pub pattern_matrix: usize,
+    /// Most pattern nodes an exhaustiveness check visits down one path
+    pub exhaustiveness_depth: usize,
+    /// Most constraints inference may generate for one item
+    pub constraints: usize,
+    /// Most unification steps solving one item's constraints may take
+    pub solve_steps: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            infer_depth: 128,
+            tuple_arity: 64,
+            effect_vars: 4096,
+            pattern_matrix: 10_000,
+            exhaustiveness_depth: 1_000,
+            constraints: 100_000,
+            solve_steps: 1_000_000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_round_trip_by_name_and_order_their_limits() {
+        for profile in Profile::ALL {
+            assert_eq!(Profile::from_name(profile.name()), Some(profile));
+        }
+        assert_eq!(Profile::from_name("lenient"), None);
+
+        let [strict, default, permissive] = Profile::ALL.map(Profile::limits);
+        assert_eq!(default, Limits::default());
+        assert!(strict.tuple_arity < default.tuple_arity);
+        assert!(default.tuple_arity < permissive.tuple_arity);
+        assert!(strict.infer_depth < default.infer_depth);
+        assert!(strict.pattern_matrix < permissive.pattern_matrix);
+        assert!(strict.constraints < default.constraints);
+        assert!(default.solve_steps < permissive.solve_steps);
+    }
 }
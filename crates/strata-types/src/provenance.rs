@@ -0,0 +1,60 @@
+//! Where a function's effects come from.
+//!
+//! Inference records every call a function makes by name to a function or
+//! extern, and once the function's constraints are solved the checker
+//! keeps each call with the effects it was solved to have. Effect errors
+//! follow those calls down from a function to the extern that performs an
+//! effect: `load -> read_config -> read_file`.
+
+use crate::effects::EffectRow;
+use std::collections::HashMap;
+use strata_ast::span::Span;
+
+/// A call a checked function makes by name, with the effects the call was
+/// solved to have.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EffectCall {
+    pub callee: String,
+    pub span: Span,
+    pub effects: u64,
+}
+
+/// One call on the way to an effect: who is called, where, and the
+/// effects the callee has.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Link {
+    pub name: String,
+    pub span: Span,
+    pub effects: u64,
+}
+
+/// The calls through which the function `from` reaches any of `effects`,
+/// outermost first: at each function, its first call that has one. Ends at
+/// a function with no recorded calls, such as an extern. `row_of` gives a
+/// top-level function's or extern's final row by name, which counts too: a
+/// call to a function checked later was solved before that function's
+/// effects were known.
+pub(crate) fn chain(
+    calls: &HashMap<String, Vec<EffectCall>>,
+    from: &str,
+    effects: u64,
+    row_of: impl Fn(&str) -> Option<EffectRow>,
+) -> Vec<Link> {
+    let mut chain: Vec<Link> = Vec::new();
+    let mut current = from;
+    while let Some(made) = calls.get(current) {
+        let next = made.iter().find_map(|call| {
+            let row = row_of(&call.callee).map_or(0, |row| row.concrete);
+            let fresh = call.callee != from && !chain.iter().any(|link| link.name == call.callee);
+            ((call.effects | row) & effects != 0 && fresh).then_some((call, call.effects | row))
+        });
+        let Some((call, had)) = next else { break };
+        chain.push(Link {
+            name: call.callee.clone(),
+            span: call.span,
+            effects: had,
+        });
+        current = &call.callee;
+    }
+    chain
+}
@@ -0,0 +1,883 @@
+//! Name resolution.
+//!
+//! A pass over a parsed module, before type checking, that decides what
+//! every name use refers to: a local binding, a function, an extern, a
+//! top-level `let` or `const`, a constructor, or a type. The checker and
+//! the evaluator read the resulting [`Resolution`] instead of guessing from
+//! the shape of a path (`A::B` as a variant, `A` as a variable).
+//!
+//! Scoping follows the checker: parameters and pattern bindings are visible
+//! in the rest of their block, a group of adjacent nested `fn`s is visible
+//! in all of their bodies, a top-level `let` only in the items after it,
+//! and a pattern naming a `const` matches its value rather than binding.
+//! Names the module doesn't define (builtins, imported functions, unknown
+//! names, and associated functions such as `E::from_discriminant`) are left
+//! unresolved; the checker reports the unknown ones.
+//!
+//! `use Enum::*;` and `use Enum::Variant;` bring variants into scope
+//! unqualified. Locals and module definitions shadow them in expressions;
+//! in patterns an imported variant name always matches the variant. An
+//! explicit import wins over a glob, and a name two glob-imported enums
+//! both define is ambiguous where it is used. [`expand_variant_imports`]
+//! rewrites those uses to qualified paths, so later passes only ever see
+//! `Enum::Variant`.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use strata_ast::ast::{
+    Block, Expr, FnDecl, Ident, Item, Module, Pat, Path, Stmt, TypeExpr, UseDecl,
+};
+use strata_ast::span::{FileId, Span};
+
+/// What a name refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DefId {
+    /// A parameter or pattern binding, by the span of its name
+    Local(Span),
+    /// A top-level or nested `fn`, by the span of its name
+    Fn(Span),
+    /// An `extern fn`, by the span of its name
+    Extern(Span),
+    /// A top-level `let`, by the span of its name
+    Global(Span),
+    /// A `const`, by the span of its name
+    Const(Span),
+    /// An enum variant, or a tuple struct's constructor (no `variant`)
+    Ctor {
+        adt: String,
+        variant: Option<String>,
+    },
+    /// A struct or enum
+    Type(String),
+}
+
+/// The definition of each name use in a module, by the span of the use.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Resolution {
+    uses: HashMap<Span, (String, DefId)>,
+}
+
+impl Resolution {
+    /// Record that the use of `name` at `span` refers to `def`.
+    pub fn insert(&mut self, span: Span, name: impl Into<String>, def: DefId) {
+        self.uses.insert(span, (name.into(), def));
+    }
+
+    /// What the identifier `ident` refers to, if the module defines it.
+    pub fn ident(&self, ident: &Ident) -> Option<&DefId> {
+        self.lookup(ident.span, &ident.text)
+    }
+
+    /// What `path` refers to, if the module defines it.
+    pub fn path(&self, path: &Path) -> Option<&DefId> {
+        self.lookup(path.span, &path.as_str())
+    }
+
+    // Keyed by span, but only trusted for the same name: ASTs built by
+    // hand give every node the default span
+    fn lookup(&self, span: Span, name: &str) -> Option<&DefId> {
+        match self.uses.get(&span) {
+            Some((used, def)) if used == name => Some(def),
+            _ => None,
+        }
+    }
+
+    /// Every resolved use: its span, name, and definition.
+    pub fn iter(&self) -> impl Iterator<Item = (Span, &str, &DefId)> {
+        self.uses
+            .iter()
+            .map(|(span, (name, def))| (*span, name.as_str(), def))
+    }
+
+    /// Number of resolved uses.
+    pub fn len(&self) -> usize {
+        self.uses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uses.is_empty()
+    }
+}
+
+/// An invalid `use` item, or an ambiguous use of an imported variant.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveError {
+    /// `use` names a type the module doesn't define
+    UnknownType { name: String, span: Span },
+    /// `use` names a struct rather than an enum
+    NotAnEnum { name: String, span: Span },
+    /// `use Enum::Variant;` names a variant the enum doesn't have
+    UnknownVariant {
+        enum_name: String,
+        variant: String,
+        span: Span,
+    },
+    /// More than one imported enum has a variant called `name`
+    AmbiguousVariant {
+        name: String,
+        enums: Vec<String>,
+        span: Span,
+    },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::UnknownType { name, .. } => write!(f, "unknown type '{}'", name),
+            ResolveError::NotAnEnum { name, .. } => {
+                write!(
+                    f,
+                    "'{}' is not an enum; only enum variants can be imported",
+                    name
+                )
+            }
+            ResolveError::UnknownVariant {
+                enum_name, variant, ..
+            } => write!(f, "enum '{}' has no variant '{}'", enum_name, variant),
+            ResolveError::AmbiguousVariant { name, enums, .. } => write!(
+                f,
+                "'{}' is ambiguous: it is a variant of {}",
+                name,
+                enums.join(" and ")
+            ),
+        }
+    }
+}
+
+/// Resolve every name use in `module`.
+pub fn resolve(module: &Module) -> Resolution {
+    resolve_module(module).resolution
+}
+
+/// `module` with every variant used through a `use` import written as a
+/// qualified `Enum::Variant` path. Borrowed when the module has no imports.
+pub fn expand_variant_imports(module: &Module) -> Result<Cow<'_, Module>, ResolveError> {
+    if !module.items.iter().any(|item| matches!(item, Item::Use(_))) {
+        return Ok(Cow::Borrowed(module));
+    }
+    let resolver = resolve_module(module);
+    if let Some(err) = resolver.errors.into_iter().next() {
+        return Err(err);
+    }
+    let res = resolver.resolution;
+    let mut expanded = module.clone();
+    for item in &mut expanded.items {
+        match item {
+            Item::Let(decl) => expand_expr(&mut decl.value, &res),
+            Item::Const(decl) => expand_expr(&mut decl.value, &res),
+            Item::Fn(decl) => expand_block(&mut decl.body, &res),
+            _ => {}
+        }
+    }
+    Ok(Cow::Owned(expanded))
+}
+
+/// Every name in scope at byte `offset` of `file`, each once: locals and
+/// nested fns innermost first, then the module's functions, externs,
+/// consts, constructors, top-level `let`s defined before `offset`, and
+/// types, then imported variants, these three sorted by name. Names a
+/// local shadows appear only as the local.
+pub fn scope_at(module: &Module, file: FileId, offset: u32) -> Vec<(String, DefId)> {
+    let mut resolver = Resolver {
+        probe: Some((file, offset)),
+        ..Resolver::default()
+    };
+    resolver.walk_module(module);
+    match resolver.visible.take() {
+        Some(visible) => visible,
+        None => resolver.in_scope(),
+    }
+}
+
+fn resolve_module(module: &Module) -> Resolver {
+    let mut resolver = Resolver::default();
+    resolver.walk_module(module);
+    resolver
+}
+
+/// `Enum::Variant` for a use of an imported variant, spanning the use
+fn qualified(res: &Resolution, ident: &Ident) -> Option<Path> {
+    match res.ident(ident)? {
+        DefId::Ctor {
+            adt,
+            variant: Some(variant),
+        } => {
+            let segment = |text: &String| Ident {
+                text: text.clone(),
+                span: ident.span,
+            };
+            Some(Path {
+                segments: vec![segment(adt), segment(variant)],
+                span: ident.span,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn expand_block(block: &mut Block, res: &Resolution) {
+    for stmt in &mut block.stmts {
+        match stmt {
+            Stmt::Let { pat, value, .. } => {
+                expand_expr(value, res);
+                expand_pat(pat, res);
+            }
+            Stmt::Assign { value, .. } | Stmt::Expr { expr: value, .. } => expand_expr(value, res),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    expand_expr(value, res);
+                }
+            }
+            Stmt::Fn(decl) => expand_block(&mut decl.body, res),
+            Stmt::Declare { .. } => {}
+        }
+    }
+    if let Some(tail) = &mut block.tail {
+        expand_expr(tail, res);
+    }
+}
+
+fn expand_pat(pat: &mut Pat, res: &Resolution) {
+    match pat {
+        Pat::Ident(ident) => {
+            if let Some(path) = qualified(res, ident) {
+                let span = ident.span;
+                *pat = Pat::Variant {
+                    path,
+                    fields: Vec::new(),
+                    span,
+                };
+            }
+        }
+        Pat::Variant { path, fields, .. } => {
+            if let [name] = path.segments.as_slice() {
+                if let Some(full) = qualified(res, name) {
+                    *path = full;
+                }
+            }
+            for p in fields {
+                expand_pat(p, res);
+            }
+        }
+        Pat::StrPrefix { rest, .. } => expand_pat(rest, res),
+        Pat::Tuple(pats, _) => {
+            for p in pats {
+                expand_pat(p, res);
+            }
+        }
+        Pat::Struct { fields, .. } | Pat::Record { fields, .. } => {
+            for f in fields {
+                expand_pat(&mut f.pat, res);
+            }
+        }
+        Pat::Wildcard(_) | Pat::Literal(..) | Pat::Range { .. } => {}
+    }
+}
+
+fn expand_expr(expr: &mut Expr, res: &Resolution) {
+    match expr {
+        Expr::Var(ident) => {
+            if let Some(path) = qualified(res, ident) {
+                *expr = Expr::PathExpr(path);
+            }
+        }
+        Expr::PathExpr(path) => {
+            if let [name] = path.segments.as_slice() {
+                if let Some(full) = qualified(res, name) {
+                    *path = full;
+                }
+            }
+        }
+        Expr::Lit(..) | Expr::Hole(_) => {}
+        Expr::Paren { inner, .. }
+        | Expr::Unary { expr: inner, .. }
+        | Expr::Borrow(inner, _)
+        | Expr::Field { expr: inner, .. } => expand_expr(inner, res),
+        Expr::Binary { lhs, rhs, .. } => {
+            expand_expr(lhs, res);
+            expand_expr(rhs, res);
+        }
+        Expr::Call { callee, args, .. } => {
+            expand_expr(callee, res);
+            for arg in args {
+                expand_expr(arg, res);
+            }
+        }
+        Expr::Block(block) | Expr::Spawn { body: block, .. } => expand_block(block, res),
+        Expr::If {
+            cond, then_, else_, ..
+        } => {
+            expand_expr(cond, res);
+            expand_block(then_, res);
+            if let Some(e) = else_ {
+                expand_expr(e, res);
+            }
+        }
+        Expr::While { cond, body, .. } => {
+            expand_expr(cond, res);
+            expand_block(body, res);
+        }
+        Expr::For {
+            pat, iter, body, ..
+        } => {
+            expand_expr(iter, res);
+            expand_pat(pat, res);
+            expand_block(body, res);
+        }
+        Expr::Match {
+            scrutinee, arms, ..
+        } => {
+            expand_expr(scrutinee, res);
+            for arm in arms {
+                expand_pat(&mut arm.pat, res);
+                expand_expr(&mut arm.body, res);
+            }
+        }
+        Expr::Tuple { elems, .. } => {
+            for e in elems {
+                expand_expr(e, res);
+            }
+        }
+        Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+            for f in fields {
+                expand_expr(&mut f.value, res);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Resolver {
+    /// Module-level values: functions, externs, consts, constructors, and
+    /// the top-level `let`s seen so far
+    globals: HashMap<String, DefId>,
+    /// Names of the module's consts, which patterns match by value
+    consts: HashSet<String>,
+    /// Structs and enums, by name
+    types: HashSet<String>,
+    /// Enum variants, by `Enum::Variant`
+    variants: HashMap<String, DefId>,
+    /// Each enum's variant names
+    enums: HashMap<String, Vec<String>>,
+    /// Variants imported by name (`use E::V;`), to their enum
+    imports: HashMap<String, String>,
+    /// Enums whose variants are all imported (`use E::*;`)
+    globs: Vec<String>,
+    errors: Vec<ResolveError>,
+    /// Lexical scopes of local bindings and nested fns, innermost last
+    scopes: Vec<Vec<(String, DefId)>>,
+    resolution: Resolution,
+    /// Where [`scope_at`] asks what's in scope
+    probe: Option<(FileId, u32)>,
+    /// What was in scope there
+    visible: Option<Vec<(String, DefId)>>,
+}
+
+impl Resolver {
+    /// Predeclare everything but top-level `let`s, which are visible only
+    /// after their definition
+    fn declare_items(&mut self, module: &Module) {
+        for item in &module.items {
+            match item {
+                Item::Fn(decl) => self.global(&decl.name, DefId::Fn(decl.name.span)),
+                Item::ExternFn(decl) => self.global(&decl.name, DefId::Extern(decl.name.span)),
+                Item::Const(decl) => {
+                    self.consts.insert(decl.name.text.clone());
+                    self.global(&decl.name, DefId::Const(decl.name.span));
+                }
+                Item::Struct(def) => {
+                    self.types.insert(def.name.text.clone());
+                    if def.tuple {
+                        let ctor = DefId::Ctor {
+                            adt: def.name.text.clone(),
+                            variant: None,
+                        };
+                        self.global(&def.name, ctor);
+                    }
+                }
+                Item::Enum(def) => {
+                    self.types.insert(def.name.text.clone());
+                    for variant in &def.variants {
+                        self.variant(&def.name.text, &variant.name.text);
+                    }
+                }
+                Item::Let(_) | Item::Use(_) => {}
+            }
+        }
+        // The prelude `Option`, unless the module defines its own
+        if self.types.insert("Option".to_string()) {
+            self.variant("Option", "Some");
+            self.variant("Option", "None");
+        }
+        for item in &module.items {
+            if let Item::Use(decl) = item {
+                self.use_decl(decl);
+            }
+        }
+    }
+
+    /// Declare the module's items, then resolve each in order
+    fn walk_module(&mut self, module: &Module) {
+        self.declare_items(module);
+        for item in &module.items {
+            match item {
+                Item::Let(decl) => {
+                    self.probe(decl.span);
+                    self.type_expr_opt(decl.ty.as_ref());
+                    self.expr(&decl.value);
+                    self.globals
+                        .insert(decl.name.text.clone(), DefId::Global(decl.name.span));
+                }
+                Item::Const(decl) => {
+                    self.probe(decl.span);
+                    self.type_expr(&decl.ty);
+                    self.expr(&decl.value);
+                }
+                Item::Fn(decl) => self.fn_decl(decl),
+                Item::ExternFn(decl) => {
+                    for param in &decl.params {
+                        self.type_expr_opt(param.ty.as_ref());
+                    }
+                    self.type_expr_opt(decl.ret_ty.as_ref());
+                }
+                Item::Struct(def) => {
+                    for field in &def.fields {
+                        self.type_expr(&field.ty);
+                    }
+                }
+                Item::Enum(def) => {
+                    for variant in &def.variants {
+                        if let strata_ast::ast::VariantFields::Tuple(tys) = &variant.fields {
+                            for ty in tys {
+                                self.type_expr(ty);
+                            }
+                        }
+                    }
+                }
+                Item::Use(_) => {}
+            }
+        }
+    }
+
+    /// Save what's in scope, for [`scope_at`], if its offset is in `span`.
+    /// Called as the walk enters each construct, so the innermost one
+    /// containing the offset saves last.
+    fn probe(&mut self, span: Span) {
+        if let Some((file, offset)) = self.probe {
+            if span.file == file && span.start <= offset && offset <= span.end {
+                self.visible = Some(self.in_scope());
+            }
+        }
+    }
+
+    /// What [`scope_at`] returns, at this point of the walk
+    fn in_scope(&self) -> Vec<(String, DefId)> {
+        let mut seen = HashSet::new();
+        let mut names = Vec::new();
+        let locals = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev());
+        for (name, def) in locals {
+            if seen.insert(name.clone()) {
+                names.push((name.clone(), def.clone()));
+            }
+        }
+        let mut module: Vec<(String, DefId)> = self
+            .globals
+            .iter()
+            .map(|(name, def)| (name.clone(), def.clone()))
+            .chain(
+                self.types
+                    .iter()
+                    .map(|name| (name.clone(), DefId::Type(name.clone()))),
+            )
+            .collect();
+        module.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut imported: Vec<(String, DefId)> = self
+            .imports
+            .iter()
+            .map(|(variant, adt)| (variant.clone(), adt.clone()))
+            .chain(self.globs.iter().flat_map(|adt| {
+                self.enums[adt]
+                    .iter()
+                    .filter(|variant| !self.imports.contains_key(*variant))
+                    .map(move |variant| (variant.clone(), adt.clone()))
+            }))
+            .map(|(variant, adt)| {
+                let def = DefId::Ctor {
+                    adt,
+                    variant: Some(variant.clone()),
+                };
+                (variant, def)
+            })
+            .collect();
+        imported.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, def) in module.into_iter().chain(imported) {
+            if seen.insert(name.clone()) {
+                names.push((name, def));
+            }
+        }
+        names
+    }
+
+    fn use_decl(&mut self, decl: &UseDecl) {
+        let name = &decl.enum_name.text;
+        let Some(variants) = self.enums.get(name) else {
+            let span = decl.enum_name.span;
+            self.errors.push(if self.types.contains(name) {
+                ResolveError::NotAnEnum {
+                    name: name.clone(),
+                    span,
+                }
+            } else {
+                ResolveError::UnknownType {
+                    name: name.clone(),
+                    span,
+                }
+            });
+            return;
+        };
+        let Some(variant) = &decl.variant else {
+            if !self.globs.contains(name) {
+                self.globs.push(name.clone());
+            }
+            return;
+        };
+        if !variants.contains(&variant.text) {
+            self.errors.push(ResolveError::UnknownVariant {
+                enum_name: name.clone(),
+                variant: variant.text.clone(),
+                span: variant.span,
+            });
+            return;
+        }
+        match self.imports.get(&variant.text) {
+            Some(other) if other != name => self.errors.push(ResolveError::AmbiguousVariant {
+                name: variant.text.clone(),
+                enums: vec![other.clone(), name.clone()],
+                span: decl.span,
+            }),
+            _ => {
+                self.imports.insert(variant.text.clone(), name.clone());
+            }
+        }
+    }
+
+    /// The imported variant `name`, used at `span`
+    fn imported(&mut self, name: &str, span: Span) -> Option<DefId> {
+        let adt = match self.imports.get(name) {
+            Some(adt) => adt.clone(),
+            None => {
+                let enums: Vec<String> = self
+                    .globs
+                    .iter()
+                    .filter(|adt| self.enums[*adt].iter().any(|v| v == name))
+                    .cloned()
+                    .collect();
+                match enums.as_slice() {
+                    [] => return None,
+                    [adt] => adt.clone(),
+                    _ => {
+                        self.errors.push(ResolveError::AmbiguousVariant {
+                            name: name.to_string(),
+                            enums,
+                            span,
+                        });
+                        return None;
+                    }
+                }
+            }
+        };
+        Some(DefId::Ctor {
+            adt,
+            variant: Some(name.to_string()),
+        })
+    }
+
+    fn global(&mut self, name: &Ident, def: DefId) {
+        self.globals.insert(name.text.clone(), def);
+    }
+
+    fn variant(&mut self, adt: &str, variant: &str) {
+        self.enums
+            .entry(adt.to_string())
+            .or_default()
+            .push(variant.to_string());
+        self.variants.insert(
+            format!("{}::{}", adt, variant),
+            DefId::Ctor {
+                adt: adt.to_string(),
+                variant: Some(variant.to_string()),
+            },
+        );
+    }
+
+    fn bind(&mut self, name: &Ident) {
+        self.bind_as(name, DefId::Local(name.span));
+    }
+
+    fn bind_as(&mut self, name: &Ident, def: DefId) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.text.clone(), def));
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<DefId> {
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(n, _)| n == name);
+        match local {
+            Some((_, def)) => Some(def.clone()),
+            None => self.globals.get(name).cloned(),
+        }
+    }
+
+    fn use_ident(&mut self, ident: &Ident) {
+        let def = self
+            .lookup(&ident.text)
+            .or_else(|| self.imported(&ident.text, ident.span));
+        if let Some(def) = def {
+            self.resolution.insert(ident.span, ident.text.clone(), def);
+        }
+    }
+
+    /// A path in expression or pattern position: a name or a variant
+    fn use_path(&mut self, path: &Path) {
+        let def = match path.segments.as_slice() {
+            [name] => self
+                .lookup(&name.text)
+                .or_else(|| self.imported(&name.text, name.span)),
+            _ => self.variants.get(&path.as_str()).cloned(),
+        };
+        if let Some(def) = def {
+            self.resolution.insert(path.span, path.as_str(), def);
+        }
+    }
+
+    /// A path naming a struct, in a type or a struct expression or pattern
+    fn use_type_path(&mut self, segments: &[Ident], span: Span) {
+        if let [name] = segments {
+            if self.types.contains(&name.text) {
+                self.resolution
+                    .insert(span, name.text.clone(), DefId::Type(name.text.clone()));
+            }
+        }
+    }
+
+    fn type_expr_opt(&mut self, ty: Option<&TypeExpr>) {
+        if let Some(ty) = ty {
+            self.type_expr(ty);
+        }
+    }
+
+    fn type_expr(&mut self, ty: &TypeExpr) {
+        match ty {
+            TypeExpr::Path(segments, span) => self.use_type_path(segments, *span),
+            TypeExpr::App { base, args, span } => {
+                self.use_type_path(base, *span);
+                for arg in args {
+                    self.type_expr(arg);
+                }
+            }
+            TypeExpr::Arrow { params, ret, .. } => {
+                for param in params {
+                    self.type_expr(param);
+                }
+                self.type_expr(ret);
+            }
+            TypeExpr::Tuple(elems, _) => {
+                for elem in elems {
+                    self.type_expr(elem);
+                }
+            }
+            TypeExpr::Record(fields, _) => {
+                for field in fields {
+                    self.type_expr(&field.ty);
+                }
+            }
+            TypeExpr::Ref(inner, _) => self.type_expr(inner),
+        }
+    }
+
+    /// A function's signature and body; its parameters open a scope
+    fn fn_decl(&mut self, decl: &FnDecl) {
+        for param in &decl.params {
+            self.type_expr_opt(param.ty.as_ref());
+        }
+        self.type_expr_opt(decl.ret_ty.as_ref());
+        self.scopes.push(Vec::new());
+        for param in &decl.params {
+            self.bind(&param.name);
+        }
+        self.block(&decl.body);
+        self.scopes.pop();
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.scopes.push(Vec::new());
+        self.probe(block.span);
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            // A group of adjacent nested fns is visible in all their bodies
+            for decl in block.fn_group_at(i) {
+                self.bind_as(&decl.name, DefId::Fn(decl.name.span));
+            }
+            self.stmt(stmt);
+            // Past the statement, its bindings are in scope
+            if self
+                .probe
+                .is_some_and(|(_, offset)| stmt.span().end <= offset)
+            {
+                self.probe(block.span);
+            }
+        }
+        if let Some(tail) = &block.tail {
+            self.expr(tail);
+        }
+        self.scopes.pop();
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let { pat, ty, value, .. } => {
+                // The value is resolved before its bindings are in scope
+                self.type_expr_opt(ty.as_ref());
+                self.expr(value);
+                self.pattern(pat);
+            }
+            Stmt::Declare { name, ty, .. } => {
+                self.type_expr(ty);
+                self.bind(name);
+            }
+            Stmt::Assign { target, value, .. } => {
+                self.expr(value);
+                self.use_ident(target);
+            }
+            Stmt::Expr { expr, .. } => self.expr(expr),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::Fn(decl) => self.fn_decl(decl),
+        }
+    }
+
+    /// Resolve the paths in `pat` and bind its variables
+    fn pattern(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(ident) if self.consts.contains(&ident.text) => {
+                let def = self.globals.get(&ident.text).cloned();
+                if let Some(def) = def {
+                    self.resolution.insert(ident.span, ident.text.clone(), def);
+                }
+            }
+            Pat::Ident(ident) => match self.imported(&ident.text, ident.span) {
+                Some(def) => self.resolution.insert(ident.span, ident.text.clone(), def),
+                None => self.bind(ident),
+            },
+            Pat::Wildcard(_) | Pat::Literal(..) | Pat::Range { .. } => {}
+            Pat::StrPrefix { rest, .. } => self.pattern(rest),
+            Pat::Tuple(pats, _) => {
+                for p in pats {
+                    self.pattern(p);
+                }
+            }
+            Pat::Variant { path, fields, .. } => {
+                self.use_path(path);
+                for p in fields {
+                    self.pattern(p);
+                }
+            }
+            Pat::Struct { path, fields, .. } => {
+                self.use_type_path(&path.segments, path.span);
+                for f in fields {
+                    self.pattern(&f.pat);
+                }
+            }
+            Pat::Record { fields, .. } => {
+                for f in fields {
+                    self.pattern(&f.pat);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(..) | Expr::Hole(_) => {}
+            Expr::Var(ident) => self.use_ident(ident),
+            Expr::PathExpr(path) => self.use_path(path),
+            Expr::Paren { inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::Borrow(inner, _)
+            | Expr::Field { expr: inner, .. } => self.expr(inner),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.expr(lhs);
+                self.expr(rhs);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Block(block) | Expr::Spawn { body: block, .. } => self.block(block),
+            Expr::If {
+                cond, then_, else_, ..
+            } => {
+                self.expr(cond);
+                self.block(then_);
+                if let Some(e) = else_ {
+                    self.expr(e);
+                }
+            }
+            Expr::While { cond, body, .. } => {
+                self.expr(cond);
+                self.block(body);
+            }
+            Expr::For {
+                pat, iter, body, ..
+            } => {
+                self.expr(iter);
+                self.scopes.push(Vec::new());
+                self.pattern(pat);
+                self.block(body);
+                self.scopes.pop();
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.scopes.push(Vec::new());
+                    self.pattern(&arm.pat);
+                    self.probe(arm.body.span());
+                    self.expr(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            Expr::Tuple { elems, .. } => {
+                for e in elems {
+                    self.expr(e);
+                }
+            }
+            Expr::StructExpr { path, fields, .. } => {
+                self.use_type_path(&path.segments, path.span);
+                for f in fields {
+                    self.expr(&f.value);
+                }
+            }
+            Expr::Record { fields, .. } => {
+                for f in fields {
+                    self.expr(&f.value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Shadowed-binding lint.
+//!
+//! Post-inference pass that reports bindings which hide another binding of
+//! the same name that is still in scope. Shadowing is legal Strata, but
+//! silently shadowing a capability binding can hide which authority a call
+//! actually receives, so the lint is available as an opt-in check.
+//!
+//! Each warning carries the full chain of hidden definition spans, oldest
+//! first, so `let x = ...; let x = ...; let x = ...;` reports both earlier
+//! definitions for the third binding.
+
+use crate::warnings::Warning;
+use strata_ast::ast::{Block, Expr, Pat, Stmt};
+use strata_ast::span::Span;
+
+/// Walks a function body tracking lexical scopes.
+struct ShadowChecker {
+    /// Stack of lexical scopes; each maps a name to its definition spans.
+    scopes: Vec<Vec<(String, Span)>>,
+    warnings: Vec<Warning>,
+}
+
+impl ShadowChecker {
+    fn new() -> Self {
+        ShadowChecker {
+            scopes: vec![Vec::new()],
+            warnings: Vec::new(),
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Introduce a binding, reporting any visible bindings it hides.
+    fn introduce(&mut self, name: &str, span: Span) {
+        if name != "_" {
+            let previous: Vec<Span> = self
+                .scopes
+                .iter()
+                .flatten()
+                .filter(|(n, _)| n == name)
+                .map(|(_, s)| *s)
+                .collect();
+            if !previous.is_empty() {
+                self.warnings.push(Warning::ShadowedBinding {
+                    name: name.to_string(),
+                    shadow: span,
+                    previous,
+                });
+            }
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), span));
+        }
+    }
+
+    fn introduce_pattern(&mut self, pat: &Pat) {
+        match pat {
+            Pat::Ident(ident) => self.introduce(&ident.text, ident.span),
+            Pat::Wildcard(_) | Pat::Literal(_, _) | Pat::Range { .. } => {}
+            Pat::StrPrefix { rest, .. } => self.introduce_pattern(rest),
+            Pat::Tuple(pats, _) => {
+                for p in pats {
+                    self.introduce_pattern(p);
+                }
+            }
+            Pat::Variant { fields, .. } => {
+                for p in fields {
+                    self.introduce_pattern(p);
+                }
+            }
+            Pat::Struct { fields, .. } | Pat::Record { fields, .. } => {
+                for f in fields {
+                    self.introduce_pattern(&f.pat);
+                }
+            }
+        }
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        self.push_scope();
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            for decl in block.fn_group_at(i) {
+                self.introduce(&decl.name.text, decl.name.span);
+            }
+            self.check_stmt(stmt);
+        }
+        if let Some(ref tail) = block.tail {
+            self.check_expr(tail);
+        }
+        self.pop_scope();
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let { pat, value, .. } => {
+                // RHS is evaluated before the new binding is in scope
+                self.check_expr(value);
+                self.introduce_pattern(pat);
+            }
+            Stmt::Declare { name, .. } => self.introduce(&name.text, name.span),
+            Stmt::Assign { value, .. } => self.check_expr(value),
+            Stmt::Expr { expr, .. } => self.check_expr(expr),
+            Stmt::Return { value, .. } => {
+                if let Some(v) = value {
+                    self.check_expr(v);
+                }
+            }
+            // Introduced with its group by `check_block`; parameters open a
+            // scope of their own
+            Stmt::Fn(decl) => {
+                self.push_scope();
+                for p in &decl.params {
+                    self.introduce(&p.name.text, p.name.span);
+                }
+                self.check_block(&decl.body);
+                self.pop_scope();
+            }
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Lit(_, _) | Expr::Var(_) | Expr::PathExpr(_) | Expr::Hole(_) => {}
+            Expr::Paren { inner, .. } => self.check_expr(inner),
+            Expr::Unary { expr: inner, .. } => self.check_expr(inner),
+            Expr::Borrow(inner, _) => self.check_expr(inner),
+            Expr::Field { expr: inner, .. } => self.check_expr(inner),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.check_expr(lhs);
+                self.check_expr(rhs);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.check_expr(callee);
+                for arg in args {
+                    self.check_expr(arg);
+                }
+            }
+            Expr::Block(block) | Expr::Spawn { body: block, .. } => self.check_block(block),
+            Expr::If {
+                cond, then_, else_, ..
+            } => {
+                self.check_expr(cond);
+                self.check_block(then_);
+                if let Some(e) = else_ {
+                    self.check_expr(e);
+                }
+            }
+            Expr::While { cond, body, .. } => {
+                self.check_expr(cond);
+                self.check_block(body);
+            }
+            Expr::For {
+                pat, iter, body, ..
+            } => {
+                self.check_expr(iter);
+                self.push_scope();
+                self.introduce_pattern(pat);
+                self.check_block(body);
+                self.pop_scope();
+            }
+            Expr::Match {
+                scrutinee, arms, ..
+            } => {
+                self.check_expr(scrutinee);
+                for arm in arms {
+                    self.push_scope();
+                    self.introduce_pattern(&arm.pat);
+                    self.check_expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            Expr::Tuple { elems, .. } => {
+                for e in elems {
+                    self.check_expr(e);
+                }
+            }
+            Expr::StructExpr { fields, .. } | Expr::Record { fields, .. } => {
+                for f in fields {
+                    self.check_expr(&f.value);
+                }
+            }
+        }
+    }
+}
+
+/// Collect shadowed-binding warnings for a function body.
+///
+/// `params` are the function's parameter names and spans; they form the
+/// outermost scope of the body.
+pub fn check_function_body(params: &[(String, Span)], body: &Block) -> Vec<Warning> {
+    let mut checker = ShadowChecker::new();
+    for (name, span) in params {
+        checker.introduce(name, *span);
+    }
+    checker.check_block(body);
+    checker.warnings
+}
@@ -0,0 +1,39 @@
+//! Resolved types recorded by `check_module`, for tools that need semantic
+//! information rather than just syntax.
+//!
+//! Each checked top-level item yields a [`TypedItem`] with its fully solved
+//! type. Function types are `Ty::Arrow`s whose effect row is the resolved
+//! row, and functions also list every `let` binding in their body.
+
+use crate::infer::ty::Ty;
+use strata_ast::span::Span;
+
+/// Which kind of top-level item a [`TypedItem`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypedItemKind {
+    Fn,
+    ExternFn,
+    Let,
+    Const,
+}
+
+/// Resolved type of a checked top-level `fn`, `extern fn`, `let`, or `const`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedItem {
+    pub name: String,
+    pub kind: TypedItemKind,
+    pub span: Span,
+    /// Solved type, before generalization (free variables print as `tN`)
+    pub ty: Ty,
+    /// Variables bound by `let` statements in the item's body, in source order
+    pub locals: Vec<TypedLocal>,
+}
+
+/// Resolved type of a variable bound by a block-level `let`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedLocal {
+    pub name: String,
+    /// Span of the bound identifier
+    pub span: Span,
+    pub ty: Ty,
+}
@@ -0,0 +1,204 @@
+//! Warnings channel for non-fatal diagnostics.
+//!
+//! Lints are optional checks that run alongside type checking. Each lint has
+//! a configurable level: `Allow` suppresses it, `Warn` records a [`Warning`]
+//! on the checker, and `Deny` turns the first occurrence into a `TypeError`.
+
+use std::collections::HashMap;
+use strata_ast::span::Span;
+
+/// Identifies an individual lint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A binding shadows another binding of the same name that is still in scope.
+    ShadowedBinding,
+    /// A capability binding is never used, passed on, or dropped.
+    UnusedCapability,
+    /// A `Task<T>` handle is never joined or passed on.
+    UnjoinedTask,
+    /// An item carries an attribute the toolchain doesn't know.
+    UnknownAttribute,
+    /// An item marked `#[deprecated]` is used.
+    Deprecated,
+}
+
+impl Lint {
+    /// All known lints.
+    pub const ALL: &'static [Lint] = &[
+        Lint::ShadowedBinding,
+        Lint::UnusedCapability,
+        Lint::UnjoinedTask,
+        Lint::UnknownAttribute,
+        Lint::Deprecated,
+    ];
+
+    /// The snake_case name used on the command line and in diagnostics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::ShadowedBinding => "shadowed_binding",
+            Lint::UnusedCapability => "unused_capability",
+            Lint::UnjoinedTask => "unjoined_task",
+            Lint::UnknownAttribute => "unknown_attribute",
+            Lint::Deprecated => "deprecated",
+        }
+    }
+
+    /// Look up a lint by its snake_case name.
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().copied().find(|l| l.name() == name)
+    }
+
+    /// The level a lint runs at when not configured explicitly.
+    pub fn default_level(self) -> LintLevel {
+        match self {
+            Lint::ShadowedBinding => LintLevel::Allow,
+            Lint::UnusedCapability
+            | Lint::UnjoinedTask
+            | Lint::UnknownAttribute
+            | Lint::Deprecated => LintLevel::Warn,
+        }
+    }
+}
+
+/// Severity a lint is reported at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintLevel {
+    /// Lint is not run.
+    Allow,
+    /// Lint findings are collected as warnings.
+    Warn,
+    /// Lint findings are reported as type errors.
+    Deny,
+}
+
+/// Per-lint level overrides.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Effective level for a lint (override, else the lint's default).
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+
+    /// Override the level for a lint.
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+}
+
+/// A non-fatal diagnostic produced by a lint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// `name` at `shadow` hides earlier bindings that are still in scope.
+    /// `previous` is the span chain of those bindings, oldest first.
+    ShadowedBinding {
+        name: String,
+        shadow: Span,
+        previous: Vec<Span>,
+    },
+    /// Capability binding `name` defined at `span` is never used.
+    UnusedCapability { name: String, span: Span },
+    /// Task handle `name` defined at `span` is never joined or passed on.
+    UnjoinedTask { name: String, span: Span },
+    /// Attribute `#[name]` at `span` is not one the toolchain knows.
+    UnknownAttribute { name: String, span: Span },
+    /// Deprecated item `name` is used at `span`; `note` is the
+    /// `#[deprecated("...")]` text, which usually names the replacement.
+    Deprecated {
+        name: String,
+        note: Option<String>,
+        span: Span,
+    },
+}
+
+impl Warning {
+    /// The lint that produced this warning.
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::ShadowedBinding { .. } => Lint::ShadowedBinding,
+            Warning::UnusedCapability { .. } => Lint::UnusedCapability,
+            Warning::UnjoinedTask { .. } => Lint::UnjoinedTask,
+            Warning::UnknownAttribute { .. } => Lint::UnknownAttribute,
+            Warning::Deprecated { .. } => Lint::Deprecated,
+        }
+    }
+
+    /// Primary span of the warning.
+    pub fn span(&self) -> Span {
+        match self {
+            Warning::ShadowedBinding { shadow, .. } => *shadow,
+            Warning::UnusedCapability { span, .. }
+            | Warning::UnjoinedTask { span, .. }
+            | Warning::UnknownAttribute { span, .. }
+            | Warning::Deprecated { span, .. } => *span,
+        }
+    }
+
+    /// Other places the message refers to, each with what is there.
+    pub fn notes(&self) -> Vec<(String, Span)> {
+        match self {
+            Warning::ShadowedBinding { name, previous, .. } => previous
+                .iter()
+                .rev()
+                .map(|span| (format!("'{}' defined here", name), *span))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::ShadowedBinding { name, previous, .. } => {
+                write!(f, "binding '{}' shadows", name)?;
+                for i in 0..previous.len() {
+                    if i > 0 {
+                        write!(f, ", which shadows")?;
+                    }
+                    write!(f, " an earlier '{}'", name)?;
+                }
+                write!(f, " [{}]", self.lint().name())
+            }
+            Warning::UnusedCapability { name, .. } => write!(
+                f,
+                "capability '{}' is never used; pass it on or call `drop({})` \
+                 to discard it explicitly [{}]",
+                name,
+                name,
+                self.lint().name()
+            ),
+            Warning::UnjoinedTask { name, .. } => write!(
+                f,
+                "task '{}' is never joined; call `join({})` to wait for its \
+                 result, or it is joined when `main` returns [{}]",
+                name,
+                name,
+                self.lint().name()
+            ),
+            Warning::UnknownAttribute { name, .. } => write!(
+                f,
+                "unknown attribute `#[{}]` is ignored [{}]",
+                name,
+                self.lint().name()
+            ),
+            Warning::Deprecated { name, note, .. } => {
+                write!(f, "'{}' is deprecated", name)?;
+                if let Some(note) = note {
+                    write!(f, ": {}", note)?;
+                }
+                write!(f, " [{}]", self.lint().name())
+            }
+        }
+    }
+}
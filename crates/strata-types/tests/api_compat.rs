@@ -0,0 +1,129 @@
+//! Integration tests for comparing two versions of a module's interface.
+
+use strata_parse::parse_str;
+use strata_types::compat::compare;
+use strata_types::interface::Interface;
+use strata_types::TypeChecker;
+
+/// Helper: check `src` and export its interface
+fn export(src: &str) -> Interface {
+    let module = parse_str("<upstream>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.interface(&module)
+}
+
+/// Helper: the changes from `old` to `new`, as `strata api-check` prints them
+fn changes(old: &str, new: &str) -> Vec<String> {
+    compare(&export(old), &export(new))
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn unchanged_interfaces_have_no_changes() {
+    let src = "pub fn id(x) { x }\npub fn pair(a, b) { (a, b) }";
+    let reordered = "pub fn pair(p, q) { (p, q) }\npub fn id(y) { y }";
+    assert!(changes(src, reordered).is_empty());
+}
+
+#[test]
+fn adding_an_effect_breaks_and_dropping_one_does_not() {
+    let pure = "pub fn stamp(t: TimeCap) -> Int & {} { 0 }";
+    let timed = r#"
+        extern fn now(t: &TimeCap) -> Int & {Time};
+        pub fn stamp(t: TimeCap) -> Int & {Time} { now(&t) }
+    "#;
+    assert_eq!(
+        changes(pure, timed),
+        ["breaking: fn stamp: adds effects {Time}"]
+    );
+    assert_eq!(
+        changes(timed, pure),
+        ["compatible: fn stamp: no longer has effects {Time}"]
+    );
+}
+
+#[test]
+fn functions_removed_hidden_retyped_and_added() {
+    let old = "pub fn a() -> Int { 1 }\npub fn b() -> Int { 2 }\npub fn c() -> Int { 3 }";
+    let new = "pub fn a() -> Bool { true }\nfn b() -> Int { 2 }\npub fn d() -> Int { 4 }";
+    assert_eq!(
+        changes(old, new),
+        [
+            "breaking: fn a: type changed from () -> Int & {e0} to () -> Bool & {e0}",
+            "breaking: fn b: no longer `pub`",
+            "breaking: fn c: removed",
+            "compatible: fn d: added: () -> Int & {e0}",
+        ]
+    );
+}
+
+#[test]
+fn new_fields_and_variants_break() {
+    let old = r#"
+        pub struct Point { x: Int, y: Int }
+        pub enum Shape { Circle(Float), Square(Float) }
+    "#;
+    let new = r#"
+        pub struct Point { x: Int, y: Int, z: Int }
+        pub enum Shape { Circle(Float), Square(Float), Tri(Float) }
+        pub struct Extra { n: Int }
+    "#;
+    assert_eq!(
+        changes(old, new),
+        [
+            "breaking: struct Point: field z added",
+            "breaking: enum Shape: variant Tri added; matches without a wildcard arm are no longer exhaustive",
+            "compatible: struct Extra: added",
+        ]
+    );
+}
+
+#[test]
+fn fields_added_with_a_default_are_compatible() {
+    let old = r#"
+        pub struct Config { host: String, retries: Int = 3 }
+    "#;
+    let new = r#"
+        pub struct Config { host: String, retries: Int, timeout: Float = 1.5, verbose: Bool = false, name: String = "x" }
+    "#;
+    assert_eq!(
+        changes(old, new),
+        [
+            "breaking: struct Config: field retries no longer has a default",
+            "compatible: struct Config: field timeout added, with a default",
+            "compatible: struct Config: field verbose added, with a default",
+            "compatible: struct Config: field name added, with a default",
+        ]
+    );
+
+    // The defaults survive the binary format
+    let iface = export(new);
+    assert_eq!(Interface::from_bytes(&iface.to_bytes()), Ok(iface));
+}
+
+#[test]
+fn retyped_fields_renumbered_variants_and_lost_adts_break() {
+    let old = r#"
+        pub struct Wrapper { inner: Int }
+        pub enum Level { Low, High }
+        pub struct Gone { n: Int }
+    "#;
+    let new = r#"
+        pub struct Wrapper { inner: String }
+        pub enum Level { Low, High = 10 }
+        struct Gone { n: Int }
+    "#;
+    assert_eq!(
+        changes(old, new),
+        [
+            "breaking: struct Wrapper: field inner changed type from Int to String",
+            "breaking: enum Level: variant High renumbered from 1 to 10",
+            "breaking: struct Gone: removed, or no longer `pub`",
+        ]
+    );
+}
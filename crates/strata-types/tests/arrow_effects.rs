@@ -0,0 +1,86 @@
+//! Integration tests for effect rows on function types.
+//!
+//! `fn(Int) -> Int & {Fs}` states the effects a function value may perform,
+//! wherever a function type is written: parameters, `let` annotations,
+//! fields. Calling the value performs them; an unannotated function type is
+//! pure.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const TOUCH: &str = r#"
+fn twice(x: Int) -> Int { x * 2 }
+fn touch(fs: FsCap, x: Int) -> Int & {Fs} { drop(fs); x }
+"#;
+
+#[test]
+fn function_types_carry_their_effects() {
+    for decls in [
+        // Parameters
+        "fn run(f: fn(FsCap, Int) -> Int & {Fs}, fs: FsCap) -> Int & {Fs} { f(fs, 1) }
+         fn main(fs: FsCap) -> Int & {Fs} { run(touch, fs) }",
+        // Let annotations
+        "fn main(fs: FsCap) -> Int & {Fs} { let g: fn(FsCap, Int) -> Int & {Fs} = touch; g(fs, 1) }",
+        // An inferred function fits any row it is annotated with
+        "fn main(fs: &FsCap) -> Int & {Fs} { let h: fn(Int) -> Int & {Fs} = twice; h(1) }",
+        // Fields
+        "struct Job { step: fn(Int) -> Int & {Fs} }
+         fn go(j: Job, fs: &FsCap) -> Int & {Fs} { let Job { step } = j; step(1) }
+         fn main(fs: FsCap) -> Int & {Fs} { go(Job { step: twice }, &fs) }",
+    ] {
+        check(&format!("{TOUCH}{decls}")).unwrap_or_else(|e| panic!("{decls}: {e}"));
+    }
+}
+
+#[test]
+fn calls_through_a_function_type_perform_its_effects() {
+    let err =
+        check_err("fn run(f: fn(FsCap, Int) -> Int & {Fs}, fs: FsCap) -> Int & {} { f(fs, 1) }");
+    assert_eq!(err.code(), "EFFECT-UNDECLARED", "{err}");
+    assert!(
+        matches!(&err, TypeError::UndeclaredEffect { fn_name, .. } if fn_name == "run"),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn effects_must_match_the_annotation() {
+    for decls in [
+        // An unannotated function type is pure
+        "fn run(f: fn(FsCap, Int) -> Int, fs: FsCap) -> Int { f(fs, 1) }
+         fn main(fs: FsCap) -> Int & {Fs} { run(touch, fs) }",
+        "fn main(fs: FsCap) -> Int & {Fs} { let g: fn(FsCap, Int) -> Int & {} = touch; g(fs, 1) }",
+        "fn main(fs: FsCap) -> Int & {Fs} { let g: fn(FsCap, Int) -> Int & {Net} = touch; g(fs, 1) }",
+    ] {
+        let err = check_err(&format!("{TOUCH}{decls}"));
+        assert!(
+            matches!(err, TypeError::EffectMismatch { .. }),
+            "{decls}: {err:?}"
+        );
+    }
+
+    let err = check_err("fn run(f: fn(Int) -> Int & {Disk}) -> Int { f(1) }");
+    assert!(
+        matches!(&err, TypeError::UnknownEffect { name, .. } if name == "Disk"),
+        "got: {err:?}"
+    );
+    let err = check_err(&format!(
+        "{TOUCH}fn main() -> Int {{ let f: fn(Int) -> Int & {{Disk}} = twice; 1 }}"
+    ));
+    assert!(
+        matches!(err, TypeError::UnknownEffect { .. }),
+        "got: {err:?}"
+    );
+}
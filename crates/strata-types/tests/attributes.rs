@@ -0,0 +1,174 @@
+//! Integration tests for the item attribute registry.
+//!
+//! Known attributes are checked for where they may appear and what they
+//! take; unknown ones are reported by the `unknown_attribute` lint.
+
+use strata_parse::parse_str;
+use strata_types::attrs::KnownAttr;
+use strata_types::{Lint, LintLevel, TypeChecker, TypeError, Warning};
+
+/// Helper: parse and type-check, return warnings
+fn check_warnings(src: &str) -> Vec<Warning> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.take_warnings()
+}
+
+/// Helper: parse and type-check, expect failure
+fn check_err(src: &str) -> TypeError {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new()
+        .check_module(&module)
+        .expect_err("expected type error but got OK")
+}
+
+#[test]
+fn registry_round_trips_names() {
+    for attr in KnownAttr::ALL {
+        assert_eq!(KnownAttr::from_name(attr.name()), Some(*attr));
+    }
+    assert_eq!(KnownAttr::from_name("inline"), None);
+}
+
+#[test]
+fn known_attributes_check_quietly() {
+    let src = r#"
+        #[deprecated("use area")] fn size() -> Int { 1 }
+        #[deprecated] struct Old { x: Int }
+        #[allow(shadowed_binding)] fn f() -> Int { let x = 1; let x = 2; x }
+        #[test] fn f_is_one() -> Bool { f() == 1 }
+        #[transparent] struct Meters(Float);
+        #[cfg(all())] const LIMIT: Int = 3;
+        #[redact(key, output, mode = "mask")]
+        extern fn lookup(net: &NetCap, key: String) -> String & {Net};
+    "#;
+    assert_eq!(check_warnings(src), []);
+}
+
+#[test]
+fn unknown_attribute_warns() {
+    let src = "#[inline] fn f() -> Int { 1 }";
+    let warnings = check_warnings(src);
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|w| (w.lint(), w.span().start, w.span().end))
+            .collect::<Vec<_>>(),
+        [(Lint::UnknownAttribute, 0, 9)]
+    );
+    assert!(
+        warnings[0]
+            .to_string()
+            .contains("unknown attribute `#[inline]`"),
+        "got: {}",
+        warnings[0]
+    );
+}
+
+#[test]
+fn unknown_attribute_lint_levels() {
+    let module = parse_str("<test>", "#[inline] fn f() {}").expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::UnknownAttribute, LintLevel::Allow);
+    checker.check_module(&module).expect("check failed");
+    assert!(checker.warnings().is_empty());
+
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::UnknownAttribute, LintLevel::Deny);
+    let err = checker.check_module(&module).expect_err("denied");
+    assert_eq!(err.code(), "LINT-DENIED");
+}
+
+#[test]
+fn misused_known_attributes_are_errors() {
+    for (src, message) in [
+        (
+            "#[test] struct S { x: Int }",
+            "`#[test]` cannot be used on a struct",
+        ),
+        (
+            "#[test] fn t(x: Int) {}",
+            "test function 't' cannot take parameters",
+        ),
+        ("#[test(slow)] fn t() {}", "`#[test]` takes no arguments"),
+        (
+            "#[deprecated] let x = 1;",
+            "`#[deprecated]` cannot be used on a `let` item",
+        ),
+        (
+            "#[deprecated(\"a\", \"b\")] fn f() {}",
+            "`#[deprecated]` takes at most one note",
+        ),
+        ("#[deny] fn f() {}", "`#[deny]` names the lints it sets"),
+        (
+            "#[allow(no_such_lint)] fn f() {}",
+            "unknown lint `no_such_lint`",
+        ),
+        (
+            "#[allow(\"shadowed_binding\")] fn f() {}",
+            "expected a lint name",
+        ),
+        (
+            "#[redact] fn f() {}",
+            "`#[redact]` cannot be used on a function",
+        ),
+        (
+            "#[redact(path)] extern fn read(fs: &FsCap, p: String) -> String & {Fs};",
+            "'read' has no parameter `path`",
+        ),
+        (
+            "#[redact(output)] extern fn put(output: String);",
+            "it also has a parameter named `output`",
+        ),
+        (
+            "#[redact(mode = \"blur\")] extern fn put(s: String);",
+            "expected a parameter name, `output`, or `mode = \"hash\"`",
+        ),
+    ] {
+        let err = check_err(src);
+        assert_eq!(err.code(), "TYPE-INVALID-ATTRIBUTE", "{}", src);
+        assert!(err.to_string().contains(message), "{}: got {}", src, err);
+    }
+}
+
+#[test]
+fn redaction_reads_the_redact_attributes() {
+    use strata_ast::ast::Item;
+    use strata_types::attrs::{redaction, Redaction};
+
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        #[redact] extern fn secret(fs: &FsCap, name: String) -> String & {Fs};
+        #[redact(url)] #[redact(mode = "mask")]
+        extern fn fetch(net: &NetCap, url: String, body: String) -> String & {Net};
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    let found: Vec<Option<Redaction>> = module
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::ExternFn(decl) => redaction(decl),
+            _ => unreachable!(),
+        })
+        .collect();
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    assert_eq!(
+        found,
+        [
+            None,
+            Some(Redaction {
+                params: names(&["fs", "name"]),
+                output: true,
+                mask: false,
+            }),
+            Some(Redaction {
+                params: names(&["url"]),
+                output: false,
+                mask: true,
+            }),
+        ]
+    );
+}
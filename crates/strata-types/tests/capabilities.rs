@@ -108,8 +108,8 @@ fn extern_fn_pure_no_caps_needed() {
 }
 
 #[test]
-fn all_five_capability_types() {
-    // All five capability types are recognized as valid types
+fn all_six_capability_types() {
+    // All six capability types are recognized as valid types
     check_ok(
         r#"
         fn use_all(
@@ -118,6 +118,7 @@ fn all_five_capability_types() {
             time: TimeCap,
             rand: RandCap,
             ai: AiCap,
+            console: ConsoleCap,
             x: Int
         ) -> Int {
             x
@@ -308,7 +309,7 @@ fn hof_with_cap_and_own_effect() {
 
 #[test]
 fn capability_in_adt_field_rejected() {
-    // Capabilities cannot be stored in ADT fields (until linear types)
+    // Capabilities cannot be stored in fields of non-linear ADTs
     let err = check_err(
         r#"
         struct HasCap { cap: FsCap }
@@ -962,17 +963,13 @@ fn borrow_after_consume_error() {
 }
 
 #[test]
-fn ref_type_in_strata_fn_error() {
-    // &T in regular fn params is rejected
-    let err = check_err(
+fn ref_type_in_strata_fn_ok() {
+    // &T in regular fn params is a read-only borrow
+    check_ok(
         r#"
-        fn bad(fs: &FsCap) -> () & {Fs} { () }
+        fn fine(fs: &FsCap) -> () & {Fs} { () }
     "#,
     );
-    assert!(
-        err.contains("Reference types") || err.contains("only allowed in extern"),
-        "Expected ref-in-fn error, got: {err}"
-    );
 }
 
 #[test]
@@ -1060,18 +1057,18 @@ fn ref_in_return_type_error() {
 }
 
 #[test]
-fn ref_in_strata_fn_param_error() {
-    // &T is rejected in regular fn params (declared)
+fn ref_nested_in_strata_fn_param_error() {
+    // &T is only allowed as the whole parameter type, not nested inside one
     let err = check_err(
         r#"
-        fn bad(fs: &FsCap) -> () & {} {
+        fn bad(p: (&FsCap, Int)) -> () & {} {
             ()
         }
     "#,
     );
     assert!(
-        err.contains("only allowed in extern") || err.contains("Reference types"),
-        "Expected ref-in-param error, got: {err}"
+        err.contains("Reference types") && err.contains("whole parameter type"),
+        "Expected nested-ref-in-param error, got: {err}"
     );
 }
 
@@ -1152,3 +1149,279 @@ fn ref_in_enum_variant_error() {
         "Expected RefInAdtField error for enum, got: {err}"
     );
 }
+
+// ============================================================================
+// BORROWED PARAMETERS IN STRATA FUNCTIONS
+// ============================================================================
+
+#[test]
+fn borrowed_param_used_repeatedly() {
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn read_two(fs: &FsCap) -> String & {Fs} {
+            read_file(fs, "a");
+            read_file(fs, "b")
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrowed_param_used_in_loop() {
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn poll(fs: &FsCap) -> () & {Fs} {
+            let mut i = 0;
+            while i < 3 {
+                read_file(fs, "a");
+                i = i + 1;
+            }
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrow_into_strata_fn_does_not_consume() {
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn consume(fs: FsCap) -> () & {Fs} { () }
+        fn peek(fs: &FsCap) -> String & {Fs} { read_file(fs, "a") }
+        fn caller(fs: FsCap) -> () & {Fs} {
+            peek(&fs);
+            peek(&fs);
+            consume(fs)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrowed_param_passed_on_as_borrow() {
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn inner(fs: &FsCap) -> String & {Fs} { read_file(fs, "a") }
+        fn outer(fs: &FsCap) -> String & {Fs} {
+            inner(fs);
+            inner(fs)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrowed_data_param_ok() {
+    check_ok(
+        r#"
+        fn same(a: &String, b: &String) -> Int { 0 }
+        fn caller(s: String) -> Int {
+            same(&s, &s)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrowed_param_cannot_be_consumed() {
+    // A borrow grants read-only access; it cannot be passed where the
+    // capability itself is required
+    let err = check_err(
+        r#"
+        fn consume(fs: FsCap) -> () & {Fs} { () }
+        fn bad(fs: &FsCap) -> () & {Fs} {
+            consume(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("mismatch"), "got: {err}");
+}
+
+#[test]
+fn borrowed_param_cannot_be_returned() {
+    let err = check_err(
+        r#"
+        fn bad(fs: &FsCap) {
+            fs
+        }
+    "#,
+    );
+    assert!(
+        err.contains("reference type") && err.contains("cannot escape"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn borrowed_param_cannot_be_stored() {
+    let err = check_err(
+        r#"
+        fn bad(fs: &FsCap) -> () & {} {
+            let kept = (fs, 1);
+            ()
+        }
+    "#,
+    );
+    assert!(
+        err.contains("reference type") && err.contains("cannot escape"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn borrow_after_consume_into_strata_fn_error() {
+    let err = check_err(
+        r#"
+        fn consume(fs: FsCap) -> () & {Fs} { () }
+        fn peek(fs: &FsCap) -> () & {Fs} { () }
+        fn bad(fs: FsCap) -> () & {Fs} {
+            consume(fs);
+            peek(&fs)
+        }
+    "#,
+    );
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+// ============================================================================
+// LINEAR ADTS
+// ============================================================================
+
+const HANDLE: &str = r#"
+    extern fn read_file(fs: FsCap, path: String) -> String & {Fs};
+    linear struct Handle { fs: FsCap, path: String }
+"#;
+
+#[test]
+fn linear_struct_may_hold_capability() {
+    check_ok(&format!(
+        r#"{HANDLE}
+        fn open(fs: FsCap, path: String) -> Handle {{ Handle {{ fs: fs, path: path }} }}
+        fn read(h: Handle) -> String & {{Fs}} {{
+            match h {{ Handle {{ fs, path }} => read_file(fs, path) }}
+        }}
+    "#
+    ));
+}
+
+#[test]
+fn linear_enum_may_hold_capability() {
+    check_ok(
+        r#"
+        linear enum Slot { Full(FsCap), Empty }
+        fn fill(fs: FsCap) -> Slot { Slot::Full(fs) }
+    "#,
+    );
+}
+
+#[test]
+fn linear_struct_value_used_twice_error() {
+    let err = check_err(&format!(
+        r#"{HANDLE}
+        fn read(h: Handle) -> String & {{Fs}} {{
+            match h {{ Handle {{ fs, path }} => read_file(fs, path) }}
+        }}
+        fn twice(h: Handle) -> String & {{Fs}} {{
+            let a = read(h);
+            read(h)
+        }}
+    "#
+    ));
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+#[test]
+fn linear_struct_literal_is_tracked() {
+    let err = check_err(&format!(
+        r#"{HANDLE}
+        fn consume(h: Handle) -> () {{ drop(h) }}
+        fn bad(fs: FsCap) -> () {{
+            let h = Handle {{ fs: fs, path: "a" }};
+            consume(h);
+            consume(h)
+        }}
+    "#
+    ));
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+#[test]
+fn linear_without_capability_is_still_single_use() {
+    // Linearity is declared, not inferred from the fields
+    let err = check_err(
+        r#"
+        linear enum Token { Issued, Spent }
+        fn spend(t: Token) -> () { drop(t) }
+        fn bad() -> () {
+            let t = Token::Issued;
+            spend(t);
+            spend(t)
+        }
+    "#,
+    );
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+#[test]
+fn capability_from_linear_struct_single_use() {
+    let err = check_err(&format!(
+        r#"{HANDLE}
+        fn bad(h: Handle) -> String & {{Fs}} {{
+            match h {{
+                Handle {{ fs, path }} => {{
+                    let a = read_file(fs, path);
+                    read_file(fs, a)
+                }}
+            }}
+        }}
+    "#
+    ));
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+#[test]
+fn linear_type_in_plain_struct_rejected() {
+    let err = check_err(
+        r#"
+        linear struct Handle { fs: FsCap }
+        struct Wrapper { inner: Handle }
+    "#,
+    );
+    assert!(
+        err.contains("Handle") && err.contains("linear struct"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn linear_type_declared_later_in_plain_enum_rejected() {
+    let err = check_err(
+        r#"
+        enum Wrapper { Some(Handle), None }
+        linear struct Handle { fs: FsCap }
+    "#,
+    );
+    assert!(err.contains("Handle"), "got: {err}");
+}
+
+#[test]
+fn linear_type_nested_in_linear_struct_ok() {
+    check_ok(
+        r#"
+        linear struct Handle { fs: FsCap }
+        linear struct Pair { a: Handle, b: Handle }
+    "#,
+    );
+}
+
+#[test]
+fn main_parameters_must_be_capabilities() {
+    check_ok("fn main(fs: FsCap, t: &TimeCap) -> () { () }");
+    let msg = check_err("fn main(n: Int) -> Int { n }");
+    assert!(msg.contains("Parameter 'n' of main"), "got: {msg}");
+    // Other functions take anything
+    check_ok("fn run(n: Int) -> Int { n }");
+}
@@ -0,0 +1,80 @@
+//! Integration tests for `==` and ordering on composite types
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+#[test]
+fn equality_on_tuples_structs_and_enums() {
+    let src = r#"
+        struct Point { x: Int, y: Int }
+        enum Shape { Dot(Point), Segment(Point, Point), Empty }
+        fn main() -> Bool {
+            (1, "a") == (1, "a")
+                && Point { x: 1, y: 2 } != Point { x: 2, y: 1 }
+                && Option::Some(Point { x: 0, y: 0 }) == Option::None
+                && Shape::Empty == Shape::Dot(Point { x: 1, y: 1 })
+        }
+    "#;
+    if let Err(e) = check(src) {
+        panic!("composite equality should check, got: {e}");
+    }
+}
+
+#[test]
+fn ordering_on_numbers_strings_and_tuples() {
+    let src = r#"
+        fn main() -> Bool { 1 < 2 && 1.5 >= 0.5 && "a" < "b" && (1, ("x", 2.0)) <= (1, ("y", 0.0)) }
+    "#;
+    if let Err(e) = check(src) {
+        panic!("ordering should check, got: {e}");
+    }
+}
+
+#[test]
+fn generic_comparisons_still_check() {
+    // Unsolved operand types are let through
+    let src = "fn same(a, b) -> Bool { a == b } fn less(a, b) -> Bool { a < b }";
+    assert!(check(src).is_ok());
+}
+
+#[test]
+fn equality_without_definition_is_rejected() {
+    for src in [
+        // Closures
+        "fn f(x: Int) -> Int { x } fn main() -> Bool { f == f }",
+        "struct Handler { run: fn(Int) -> Int } \
+         fn id(x: Int) -> Int { x } \
+         fn main() -> Bool { Handler { run: id } == Handler { run: id } }",
+        // Capabilities, directly and inside a linear ADT
+        "fn main(fs: FsCap) -> Bool & {Fs} { fs == fs }",
+        "linear struct Ctx { fs: FsCap } \
+         fn same(a: Ctx, b: Ctx) -> Bool { a == b }",
+    ] {
+        match check(src) {
+            Err(TypeError::NotComparable { ordered: false, .. }) => {}
+            other => panic!("{src}: expected TYPE-NOT-COMPARABLE, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn ordering_without_definition_is_rejected() {
+    for src in [
+        "fn main() -> Bool { true < false }",
+        "struct Point { x: Int } fn main() -> Bool { Point { x: 1 } < Point { x: 2 } }",
+        "fn main() -> Bool { Option::Some(1) > Option::None }",
+        "fn main() -> Bool { (1, true) < (2, false) }",
+    ] {
+        match check(src) {
+            Err(e @ TypeError::NotComparable { ordered: true, .. }) => {
+                assert_eq!(e.code(), "TYPE-NOT-COMPARABLE")
+            }
+            other => panic!("{src}: expected TYPE-NOT-COMPARABLE, got {other:?}"),
+        }
+    }
+}
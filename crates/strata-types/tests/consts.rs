@@ -0,0 +1,149 @@
+//! Integration tests for `const` items.
+//!
+//! Consts are evaluated at check time, may refer to each other in any
+//! order, and act as literals when named in a pattern.
+
+use strata_parse::parse_str;
+use strata_types::consts::ConstValue;
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: parse and type-check, returning the checker for inspection
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+fn check_ok(src: &str) -> TypeChecker {
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"))
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(_) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+#[test]
+fn consts_are_evaluated_in_dependency_order() {
+    let checker = check_ok(
+        r#"
+        const AREA: Int = WIDTH * HEIGHT - 1;
+        const WIDTH: Int = 8;
+        const HEIGHT: Int = (WIDTH + 2) / 2;
+        const BIG: Bool = AREA >= 39 && !false;
+        const NEG: Int = -WIDTH;
+        const NAME: String = "grid";
+        const SAME: Bool = NAME == "grid";
+    "#,
+    );
+    let consts = checker.consts();
+    assert_eq!(consts["HEIGHT"], ConstValue::Int(5));
+    assert_eq!(consts["AREA"], ConstValue::Int(39));
+    assert_eq!(consts["BIG"], ConstValue::Bool(true));
+    assert_eq!(consts["NEG"], ConstValue::Int(-8));
+    assert_eq!(consts["SAME"], ConstValue::Bool(true));
+}
+
+#[test]
+fn consts_are_usable_in_functions() {
+    check_ok("const MAX: Int = 100;\nfn clamp(n: Int) -> Int { if n > MAX { MAX } else { n } }");
+}
+
+#[test]
+fn const_value_must_match_annotation() {
+    let err = check_err("const FLAG: Int = true;");
+    assert!(matches!(err, TypeError::Mismatch { .. }), "got: {err:?}");
+}
+
+#[test]
+fn const_type_must_be_a_literal_type() {
+    let err = check_err("const U: Unit = nil;");
+    assert!(
+        matches!(err, TypeError::InvalidConstType { ref name, .. } if name == "U"),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn runtime_values_are_not_constant() {
+    let src = "fn one() -> Int { 1 }\nconst ONE: Int = one();";
+    let err = check_err(src);
+    let TypeError::NotConstant { name, span } = err else {
+        panic!("expected NotConstant, got: {err:?}");
+    };
+    assert_eq!(name, "ONE");
+    assert_eq!(span.start as usize, src.rfind("one()").unwrap());
+}
+
+#[test]
+fn cycles_are_reported_with_their_path() {
+    let err = check_err("const A: Int = B + 1;\nconst B: Int = C;\nconst C: Int = A;");
+    let TypeError::ConstCycle { cycle, .. } = &err else {
+        panic!("expected ConstCycle, got: {err:?}");
+    };
+    assert_eq!(cycle, &["A", "B", "C"]);
+    assert!(err.to_string().contains("A -> B -> C -> A"), "got: {err}");
+
+    let err = check_err("const SELF: Int = SELF;");
+    assert!(matches!(err, TypeError::ConstCycle { .. }), "got: {err:?}");
+}
+
+#[test]
+fn overflow_and_division_by_zero_are_errors() {
+    let err = check_err("const BIG: Int = 9223372036854775807 + 1;");
+    assert!(err.to_string().contains("integer overflow"), "got: {err}");
+    let err = check_err("const ZERO: Int = 0;\nconst BAD: Int = 1 / ZERO;");
+    assert!(err.to_string().contains("division by zero"), "got: {err}");
+    // The right operand of a short-circuited `&&` is never evaluated
+    check_ok("const OK: Bool = false && 1 / 0 == 0;");
+}
+
+#[test]
+fn const_in_pattern_matches_its_value() {
+    check_ok(
+        r#"
+        const LIMIT: Int = 100;
+        fn f(n: Int) -> Int { match n { 0 => 0, LIMIT => 1, other => other } }
+    "#,
+    );
+    // A const of the wrong type is a mismatch, not a binding
+    let err = check_err(
+        "const NAME: String = \"x\";\nfn f(n: Int) -> Int { match n { NAME => 1, _ => 0 } }",
+    );
+    assert!(matches!(err, TypeError::Mismatch { .. }), "got: {err:?}");
+}
+
+#[test]
+fn const_patterns_count_for_exhaustiveness() {
+    let src = r#"
+        const YES: Bool = true;
+        const NO: Bool = !YES;
+        fn f(b: Bool) -> Int { match b { YES => 1, NO => 0 } }
+    "#;
+    check_ok(src);
+
+    let err = check_err("const YES: Bool = true;\nfn f(b: Bool) -> Int { match b { YES => 1 } }");
+    assert!(
+        matches!(err, TypeError::NonExhaustiveMatch { ref witness, .. } if witness == "false"),
+        "got: {err:?}"
+    );
+
+    let err =
+        check_err("const N: Int = 3;\nfn f(n: Int) -> Int { match n { 3 => 0, N => 1, _ => 2 } }");
+    assert!(
+        matches!(err, TypeError::UnreachablePattern { arm_index: 1, .. }),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn let_cannot_bind_a_const_name() {
+    let err = check_err("const N: Int = 3;\nfn f() -> Int { let N = 4; N }");
+    assert!(
+        matches!(err, TypeError::RefutablePattern { ref pat_desc, .. } if pat_desc == "constant `N`"),
+        "got: {err:?}"
+    );
+}
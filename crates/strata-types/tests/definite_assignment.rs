@@ -0,0 +1,168 @@
+//! Integration tests for `let mut x: T;` declarations.
+//!
+//! A declaration without a value must be assigned on every path before it
+//! is read; diverging paths (`return`, `unreachable()`) need not assign.
+
+use strata_ast::span::Span;
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: parse and type-check
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+/// Helper: the span of the `n`th occurrence (0-based) of `needle` in `src`
+fn nth(src: &str, needle: &str, n: usize) -> Span {
+    let start = src.match_indices(needle).nth(n).expect("needle present").0;
+    Span {
+        start: start as u32,
+        end: (start + needle.len()) as u32,
+        ..Span::default()
+    }
+}
+
+fn expect_uninit(src: &str) -> (Span, Option<Span>) {
+    match check(src) {
+        Err(TypeError::PossiblyUninitialized {
+            name,
+            used_at,
+            unassigned_on,
+            ..
+        }) => {
+            assert_eq!(name, "x");
+            (used_at, unassigned_on)
+        }
+        other => panic!("expected PossiblyUninitialized, got {other:?}"),
+    }
+}
+
+#[test]
+fn assigned_in_both_branches_is_ok() {
+    let src = r#"
+        fn f(c: Bool) -> Int {
+            let mut x: Int;
+            if c { x = 1; } else { x = 2; };
+            x
+        }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn accumulation_under_match_is_ok() {
+    let src = r#"
+        enum Op { Add(Int), Reset }
+        fn step(acc: Int, op: Op) -> Int {
+            let mut next: Int;
+            match op {
+                Op::Add(n) => { next = acc + n; },
+                Op::Reset => { next = 0; },
+            };
+            next
+        }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn read_before_any_assignment_is_error() {
+    let src = "fn f() -> Int { let mut x: Int; x }";
+    let (used_at, unassigned_on) = expect_uninit(src);
+    assert_eq!(used_at, nth(src, "x", 1));
+    assert_eq!(unassigned_on, None);
+}
+
+#[test]
+fn if_without_else_names_the_if() {
+    let src = "fn f(c: Bool) -> Int { let mut x: Int; if c { x = 1; }; x }";
+    let (used_at, unassigned_on) = expect_uninit(src);
+    assert_eq!(used_at, nth(src, "x", 2));
+    let if_start = src.find("if c").unwrap() as u32;
+    assert_eq!(unassigned_on.map(|s| s.start), Some(if_start));
+}
+
+#[test]
+fn unassigned_branch_is_reported() {
+    let src = r#"
+        enum E { A, B }
+        fn f(e: E) -> Int {
+            let mut x: Int;
+            match e { E::A => { x = 1; }, E::B => {} };
+            x
+        }
+    "#;
+    let (_, unassigned_on) = expect_uninit(src);
+    let arm_start = src.find("E::B =>").unwrap() as u32;
+    assert_eq!(unassigned_on.map(|s| s.start), Some(arm_start));
+}
+
+#[test]
+fn assignment_in_loop_body_does_not_count() {
+    let src = "fn f(c: Bool) -> Int { let mut x: Int; while c { x = 1; }; x }";
+    let (_, unassigned_on) = expect_uninit(src);
+    let while_start = src.find("while").unwrap() as u32;
+    assert_eq!(unassigned_on.map(|s| s.start), Some(while_start));
+}
+
+#[test]
+fn diverging_branches_need_not_assign() {
+    let src = r#"
+        fn early(c: Bool) -> Int {
+            let mut x: Int;
+            if c { x = 1; } else { return 0; };
+            x
+        }
+        fn never(c: Bool) -> Int {
+            let mut x: Int;
+            if c { x = 1; } else { unreachable() };
+            x
+        }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn user_fn_named_unreachable_does_not_diverge() {
+    let src = r#"
+        fn unreachable() -> Int { 0 }
+        fn f(c: Bool) -> Int {
+            let mut x: Int;
+            if c { x = 1; } else { unreachable(); };
+            x
+        }
+    "#;
+    expect_uninit(src);
+}
+
+#[test]
+fn shadowing_binding_is_not_the_declaration() {
+    let src = r#"
+        fn f(c: Bool) -> Int {
+            let mut x: Int;
+            let y = { let x = 2; x };
+            x = y;
+            x
+        }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn short_circuit_operand_may_not_run() {
+    let src = "fn f(c: Bool) -> Bool { let mut x: Bool; let ok = c && { x = true; x }; x }";
+    expect_uninit(src);
+}
+
+#[test]
+fn declared_type_is_enforced() {
+    let src = "fn f() -> Int { let mut x: Int; x = true; x }";
+    assert!(matches!(check(src), Err(TypeError::Mismatch { .. })));
+}
+
+#[test]
+fn top_level_let_blocks_are_checked() {
+    let src = "let k = { let mut x: Int; x + 1 };";
+    expect_uninit(src);
+}
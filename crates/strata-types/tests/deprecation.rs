@@ -0,0 +1,114 @@
+//! Integration tests for the deprecated-item lint.
+//!
+//! Every use of an item marked `#[deprecated]` is reported at the use,
+//! with the attribute's note.
+
+use strata_parse::parse_str;
+use strata_types::{Lint, LintLevel, TypeChecker, Warning};
+
+/// Helper: parse and type-check, return `(used text, note)` per warning
+fn deprecated_uses(src: &str) -> Vec<(String, Option<String>)> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker
+        .take_warnings()
+        .into_iter()
+        .map(|w| match w {
+            Warning::Deprecated { note, span, .. } => (
+                src[span.start as usize..span.end as usize].to_string(),
+                note,
+            ),
+            other => panic!("unexpected warning: {other}"),
+        })
+        .collect()
+}
+
+fn note(text: &str) -> Option<String> {
+    Some(text.to_string())
+}
+
+#[test]
+fn each_call_is_reported_with_the_note() {
+    let src = r#"
+        #[deprecated("use area")] fn size(w: Int) -> Int { w }
+        fn main() -> Int { size(1) + size(2) }
+    "#;
+    assert_eq!(
+        deprecated_uses(src),
+        [
+            ("size".to_string(), note("use area")),
+            ("size".to_string(), note("use area")),
+        ]
+    );
+}
+
+#[test]
+fn no_warnings_without_uses() {
+    let src = r#"#[deprecated] fn old() -> Int { 1 } fn main() -> Int { 2 }"#;
+    assert_eq!(deprecated_uses(src), []);
+}
+
+#[test]
+fn types_consts_and_externs() {
+    let src = r#"
+        #[deprecated("use Point")] struct Pos { x: Int }
+        #[deprecated] enum Dir { Up, Down }
+        #[deprecated("use MAX")] const LIMIT: Int = 3;
+        #[deprecated] extern fn clock() -> Int & {};
+        fn f(p: Pos) -> Int { LIMIT }
+        fn g() -> Dir { Dir::Up }
+        fn h() -> Int { clock() }
+    "#;
+    let uses: Vec<String> = deprecated_uses(src).into_iter().map(|(t, _)| t).collect();
+    assert_eq!(uses, ["Pos", "LIMIT", "Dir", "Dir::Up", "clock"]);
+}
+
+#[test]
+fn tuple_struct_constructor_is_deprecated_with_its_type() {
+    let src = r#"
+        #[deprecated("use Meters")] struct M(Int);
+        fn main() -> Int { let M(x) = M(1); x }
+    "#;
+    let uses: Vec<String> = deprecated_uses(src).into_iter().map(|(t, _)| t).collect();
+    assert_eq!(uses, ["M", "M"]);
+}
+
+#[test]
+fn uses_inside_deprecated_items_are_not_reported() {
+    let src = r#"
+        #[deprecated] fn old(n: Int) -> Int { if n == 0 { 0 } else { old(n - 1) } }
+        #[deprecated] fn older() -> Int { old(3) }
+    "#;
+    assert_eq!(deprecated_uses(src), []);
+}
+
+#[test]
+fn a_local_with_the_same_name_is_not_a_use() {
+    let src = r#"
+        #[deprecated] fn size() -> Int { 1 }
+        fn twice(size: Int) -> Int { size + size }
+    "#;
+    assert_eq!(deprecated_uses(src), []);
+}
+
+#[test]
+fn lint_can_be_allowed_or_denied() {
+    let src = "#[deprecated] fn old() -> Int { 1 } fn main() -> Int { old() }";
+    let module = parse_str("<test>", src).expect("parse failed");
+
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::Deprecated, LintLevel::Allow);
+    checker.check_module(&module).expect("check failed");
+    assert!(checker.warnings().is_empty());
+
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::Deprecated, LintLevel::Deny);
+    let err = checker.check_module(&module).expect_err("denied");
+    assert!(
+        err.to_string().contains("'old' is deprecated") && err.to_string().contains("(denied)"),
+        "got: {err}"
+    );
+}
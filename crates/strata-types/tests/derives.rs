@@ -0,0 +1,137 @@
+//! Integration tests for `#[derive(Show)]`.
+//!
+//! A derive on a struct or enum adds a `{type}_to_string` function for it
+//! before checking, formatting the way `show` does. Equality isn't derived,
+//! since `==` is structural already.
+
+use strata_ast::ast::Item;
+use strata_parse::parse_str;
+use strata_types::derive::{expand_derives, Derive};
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const TYPES: &str = r#"
+    #[derive(Show)]
+    struct Point { x: Int, y: Int }
+    #[derive(Show)]
+    struct Meters(Int);
+    #[derive(Show)]
+    enum Shape { Dot, Circle(Int), Line(Point, Point) }
+    #[derive(Show)]
+    enum Maybe<T> { Nothing, Just(T) }
+    #[derive(Show)]
+    enum Never {}
+"#;
+
+#[test]
+fn derived_functions_check() {
+    for body in [
+        r#"shape_to_string(Shape::Line(Point { x: 1, y: 2 }, Point { x: 3, y: 4 })) == """#,
+        r#"point_to_string(Point { x: 1, y: 2 }) == """#,
+        r#"maybe_to_string(Maybe::Just(true)) == meters_to_string(Meters(1))"#,
+    ] {
+        let src = format!("{TYPES}\nfn f() -> Bool {{ {body} }}");
+        check(&src).unwrap_or_else(|e| panic!("{body}: {e}"));
+    }
+
+    // A generic type's functions still take only that type
+    let err = check_err(&format!(
+        "{TYPES}\nfn f() -> Bool {{ maybe_to_string(Meters(1)) == \"\" }}"
+    ));
+    assert_eq!(err.code(), "TYPE-MISMATCH", "{err}");
+}
+
+#[test]
+fn equality_is_structural_rather_than_derived() {
+    let src = "#[derive(Show, Eq)]\nstruct P { x: Int }";
+    let err = check_err(src);
+    assert!(
+        matches!(&err, TypeError::InvalidAttribute { msg, .. }
+            if msg.contains("`==` already compares structs and enums")),
+        "got: {err:?}"
+    );
+    let at = src.find("Eq").unwrap() as u32;
+    assert_eq!(err.span().start, at);
+
+    // No derive needed to compare
+    check(&format!(
+        "{TYPES}\nfn f() -> Bool {{ Shape::Circle(1) == Shape::Circle(1) && Meters(1) != Meters(2) }}"
+    ))
+    .unwrap();
+
+    // Showing a type without equality is fine
+    check("#[derive(Show)]\nstruct Handler { run: fn(Int) -> Int }").unwrap();
+}
+
+#[test]
+fn invalid_derives_are_rejected() {
+    for (src, message) in [
+        (
+            "#[derive(Ord)] struct P { x: Int }",
+            "cannot derive `Ord`; expected one of Show",
+        ),
+        (
+            "#[derive(Show, Show)] struct P { x: Int }",
+            "`Show` is derived twice",
+        ),
+        (
+            "#[derive] struct P { x: Int }",
+            "`#[derive]` names what it derives",
+        ),
+        (
+            "#[derive(Show)] fn f() -> Int { 1 }",
+            "`#[derive]` cannot be used on a function",
+        ),
+    ] {
+        let err = check_err(src);
+        assert!(
+            matches!(&err, TypeError::InvalidAttribute { msg, .. } if msg.starts_with(message)),
+            "{src}: {err:?}"
+        );
+    }
+
+    // A derived function clashes with one written out
+    let err = check_err("#[derive(Show)] struct P { x: Int }\nfn p_to_string() -> Int { 1 }");
+    assert!(
+        matches!(&err, TypeError::DuplicateDefinition { .. }),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn functions_are_named_after_the_type() {
+    assert_eq!(Derive::Show.fn_name("Point"), "point_to_string");
+
+    let module = parse_str(
+        "<test>",
+        "#[derive(Show)]\npub struct HttpRequest { x: Int }",
+    )
+    .expect("parse failed");
+    let expanded = expand_derives(&module);
+    let fns: Vec<_> = expanded
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Fn(decl) => Some((decl.name.text.as_str(), decl.public)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fns, [("http_request_to_string", true)]);
+
+    // Expanding again changes nothing
+    assert!(matches!(
+        expand_derives(&expanded),
+        std::borrow::Cow::Borrowed(_)
+    ));
+}
@@ -0,0 +1,118 @@
+//! Integration tests for enum discriminants.
+//!
+//! Unit-only enums may give variants explicit Int discriminants, which must
+//! be unique; `discriminant(x)` reads one back and `E::from_discriminant(n)`
+//! returns the variant as an `Option`.
+
+use strata_parse::parse_str;
+use strata_types::adt::VariantFields;
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: parse and type-check
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut tc = TypeChecker::new();
+    tc.check_module(&module)?;
+    Ok(tc)
+}
+
+fn discriminants(tc: &TypeChecker, name: &str) -> Vec<i64> {
+    let def = tc.adt_registry().get(name).expect("enum registered");
+    def.variants()
+        .expect("an enum")
+        .iter()
+        .map(|v| v.discriminant)
+        .collect()
+}
+
+#[test]
+fn implicit_discriminants_count_up_from_the_previous() {
+    let tc = check("enum Color { Red, Green = 5, Blue, Black = -2, White }").unwrap();
+    assert_eq!(discriminants(&tc, "Color"), vec![0, 5, 6, -2, -1]);
+}
+
+#[test]
+fn duplicate_discriminant_is_error() {
+    let src = "enum Color { Red = 1, Green, Blue = 2 }";
+    match check(src).err() {
+        Some(TypeError::DuplicateDiscriminant {
+            value,
+            variant,
+            previous,
+            span,
+            ..
+        }) => {
+            assert_eq!(value, 2);
+            assert_eq!(variant, "Blue");
+            assert_eq!(previous, "Green");
+            assert_eq!(span.start as usize, src.find("2 }").unwrap());
+        }
+        other => panic!("expected DuplicateDiscriminant, got {other:?}"),
+    }
+}
+
+#[test]
+fn explicit_discriminant_on_data_enum_is_error() {
+    let src = "enum Shape { Dot = 1, Circle(Float) }";
+    assert!(matches!(
+        check(src),
+        Err(TypeError::InvalidDiscriminant { .. })
+    ));
+}
+
+#[test]
+fn discriminant_of_enum_is_int() {
+    let src = r#"
+        enum Color { Red = 1, Green = 2 }
+        enum Shape { Dot, Circle(Float) }
+        fn code(c: Color) -> Int { discriminant(c) }
+        fn kind(s: Shape) -> Int { discriminant(s) }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn discriminant_of_non_enum_is_error() {
+    let src = "fn f(n: Int) -> Int { discriminant(n) }";
+    match check(src).err() {
+        Some(TypeError::NotAnEnum { ty, .. }) => assert_eq!(ty.to_string(), "Int"),
+        other => panic!("expected NotAnEnum, got {other:?}"),
+    }
+}
+
+#[test]
+fn from_discriminant_returns_option() {
+    let src = r#"
+        enum Color { Red = 1, Green = 2 }
+        fn parse(n: Int) -> Color {
+            match Color::from_discriminant(n) {
+                Option::Some(c) => c,
+                Option::None => Color::Red,
+            }
+        }
+    "#;
+    let tc = check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+    // No user `Option`: the prelude one is used
+    let option = tc.adt_registry().get("Option").unwrap();
+    let some = &option.variants().unwrap()[0];
+    assert!(matches!(&some.fields, VariantFields::Tuple(tys) if tys.len() == 1));
+}
+
+#[test]
+fn from_discriminant_needs_unit_only_enum() {
+    let src = r#"
+        enum Shape { Dot, Circle(Float) }
+        fn f(n: Int) -> Option<Shape> { Shape::from_discriminant(n) }
+    "#;
+    assert!(check(src).is_err());
+}
+
+#[test]
+fn user_option_of_the_same_shape_is_used() {
+    let src = r#"
+        enum Option<T> { Some(T), None }
+        enum Color { Red, Green }
+        fn f(n: Int) -> Option<Color> { Color::from_discriminant(n) }
+    "#;
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
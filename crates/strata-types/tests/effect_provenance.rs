@@ -0,0 +1,102 @@
+//! Integration tests for tracing undeclared effects to their calls.
+//!
+//! When a function's body performs an effect its annotation leaves out,
+//! the error names the calls the effect comes through, `f -> g -> http_get`,
+//! each with the span of the call.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check_err(src: &str) -> TypeError {
+    let module = parse_str("<test>", src).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+/// Where the first `needle` in `src` starts and ends
+fn span_of(src: &str, needle: &str) -> (u32, u32) {
+    let start = src.find(needle).expect("needle in source");
+    (start as u32, (start + needle.len()) as u32)
+}
+
+const NET: &str = r#"
+extern fn http_get(n: &NetCap, url: String) -> String & {Net};
+extern fn now(t: &TimeCap) -> String & {Time};
+fn fetch(n: &NetCap) -> String { http_get(n, "a") }
+fn g(n: &NetCap) -> String { fetch(n) }
+"#;
+
+#[test]
+fn undeclared_effects_name_the_calls_they_come_through() {
+    let src = format!("{NET}fn f(n: &NetCap) -> String & {{}} {{ g(n) }}\n");
+    let err = check_err(&src);
+    assert_eq!(err.code(), "EFFECT-UNDECLARED", "{err}");
+    let TypeError::UndeclaredEffect {
+        effect,
+        fn_name,
+        chain,
+        ..
+    } = &err
+    else {
+        panic!("got: {err:?}");
+    };
+    assert_eq!((effect.as_str(), fn_name.as_str()), ("Net", "f"));
+    let links: Vec<_> = chain
+        .iter()
+        .map(|(name, span)| (name.as_str(), (span.start, span.end)))
+        .collect();
+    assert_eq!(
+        links,
+        [
+            ("g", span_of(&src, "g(n)")),
+            ("fetch", span_of(&src, "fetch(n)")),
+            ("http_get", span_of(&src, r#"http_get(n, "a")"#)),
+        ]
+    );
+    let message = err.to_string();
+    assert!(
+        message.contains(
+            "; add Net to the effect annotation. Net comes from f -> g -> fetch -> http_get"
+        ),
+        "{message}"
+    );
+    // Each call is a note at its own location
+    let notes: Vec<_> = err
+        .notes()
+        .into_iter()
+        .map(|(note, span)| (note, (span.start, span.end)))
+        .collect();
+    assert_eq!(
+        notes,
+        [
+            ("'f' calls 'g' here".to_string(), links[0].1),
+            ("'g' calls 'fetch' here".to_string(), links[1].1),
+            ("'fetch' calls 'http_get' here".to_string(), links[2].1),
+        ]
+    );
+}
+
+#[test]
+fn the_chain_follows_the_first_missing_effect() {
+    // Time is declared, so only the Net call is traced
+    let src = format!(
+        "{NET}fn f(n: &NetCap, t: &TimeCap) -> String & {{Time}} {{ let s = now(t); g(n) }}\n"
+    );
+    let err = check_err(&src);
+    let TypeError::UndeclaredEffect { chain, .. } = &err else {
+        panic!("got: {err:?}");
+    };
+    let names: Vec<_> = chain.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["g", "fetch", "http_get"]);
+
+    // Calling the extern directly is a chain of one
+    let src = format!("{NET}fn f(n: &NetCap) -> String & {{}} {{ http_get(n, \"b\") }}\n");
+    let err = check_err(&src);
+    let TypeError::UndeclaredEffect { chain, .. } = &err else {
+        panic!("got: {err:?}");
+    };
+    let names: Vec<_> = chain.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["http_get"]);
+}
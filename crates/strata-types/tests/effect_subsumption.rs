@@ -0,0 +1,100 @@
+//! Integration tests for effect-row subsumption between function types.
+//!
+//! A function value may perform fewer effects than the function type it is
+//! used as: a pure function passes for `fn(Int) -> Int & {Fs}`. Parameters
+//! are contravariant, so a function taking a pure callback doesn't pass for
+//! one that may be given an effectful callback. Inside tuples, lists, and
+//! ADTs function types must still match exactly.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const FNS: &str = r#"
+fn pure_inc(x: Int) -> Int & {} { x + 1 }
+fn fs_take(fs: FsCap, x: Int) -> Int & {Fs} { drop(fs); x }
+fn net_take(net: NetCap, x: Int) -> Int & {Net} { drop(net); x }
+fn with_fs(f: fn(Int) -> Int & {Fs}, fs: &FsCap) -> Int & {Fs} { f(1) }
+fn with_pure(f: fn(Int) -> Int) -> Int { f(1) }
+fn pick() -> fn(Int) -> Int & {Fs} { pure_inc }
+fn pick_early() -> fn(Int) -> Int & {Fs} { return pure_inc; }
+"#;
+
+#[test]
+fn functions_may_do_less_than_their_type_allows() {
+    for body in [
+        // Arguments
+        "with_fs(pure_inc, &fs)",
+        "with_fs(f: pure_inc, fs: &fs)",
+        // Let annotations and assignments
+        "let f: fn(Int) -> Int & {Fs} = pure_inc; f(1)",
+        "let mut f: fn(Int) -> Int & {Fs} = pure_inc; f = pure_inc; f(1)",
+        // Return values
+        "let f = pick(); let g = pick_early(); f(g(1))",
+        "fn nested() -> fn(Int) -> Int & {Fs} { pure_inc } 1",
+        // A callback taking an effectful function may be given a pure one
+        "fn run(g: fn(fn(Int) -> Int & {}) -> Int) -> Int { g(pure_inc) } run(with_pure)",
+    ] {
+        let src = format!("{FNS}fn main(fs: FsCap) -> Int & {{Fs}} {{ {body} }}");
+        check(&src).unwrap_or_else(|e| panic!("{body}: {e}"));
+    }
+}
+
+#[test]
+fn calling_a_widened_function_performs_its_type_s_effects() {
+    let err = check_err(&format!(
+        "{FNS}fn main() -> Int & {{}} {{ let f: fn(Int) -> Int & {{Fs}} = pure_inc; f(1) }}"
+    ));
+    assert_eq!(err.code(), "EFFECT-UNDECLARED", "{err}");
+}
+
+#[test]
+fn functions_may_not_do_more_than_their_type_allows() {
+    for body in [
+        "fn run(f: fn(FsCap, Int) -> Int, fs: FsCap) -> Int { f(fs, 1) } run(fs_take, fs)",
+        "let f: fn(NetCap, Int) -> Int & {Fs} = net_take; f(net, 1)",
+        "fn drop_fs(fs: FsCap, x: Int) -> Int & {} { drop(fs); x }
+         let mut f = drop_fs; f = fs_take; f(fs, 1)",
+        "fn pick() -> fn(FsCap, Int) -> Int & {} { fs_take } 1",
+    ] {
+        let src = format!("{FNS}fn main(fs: FsCap, net: NetCap) -> Int & {{Fs, Net}} {{ {body} }}");
+        let err = check_err(&src);
+        assert!(
+            matches!(err, TypeError::EffectMismatch { .. }),
+            "{body}: {err:?}"
+        );
+    }
+}
+
+#[test]
+fn parameters_of_function_types_are_contravariant() {
+    // `with_pure` calls its callback as if it were pure, so it can't be
+    // given to `run`, which passes it an effectful one
+    let src = format!(
+        "{FNS}fn run(g: fn(fn(Int) -> Int & {{Fs}}) -> Int, fs: &FsCap) -> Int & {{Fs}} {{ g(pure_inc) }}
+         fn main(fs: FsCap) -> Int & {{Fs}} {{ run(with_pure, &fs) }}"
+    );
+    let err = check_err(&src);
+    assert!(matches!(err, TypeError::EffectMismatch { .. }), "{err:?}");
+}
+
+#[test]
+fn function_types_inside_other_types_must_match() {
+    let src = format!(
+        "{FNS}fn first(p: (fn(Int) -> Int & {{Fs}}, Int), fs: &FsCap) -> Int & {{Fs}} {{ 1 }}
+         fn main(fs: FsCap) -> Int & {{Fs}} {{ first((pure_inc, 1), &fs) }}"
+    );
+    let err = check_err(&src);
+    assert!(matches!(err, TypeError::EffectMismatch { .. }), "{err:?}");
+}
@@ -0,0 +1,320 @@
+//! Integration tests for pattern exhaustiveness at scale.
+//!
+//! Wide enums and deeply nested generic patterns must be checked well within
+//! the DoS limits so real programs never see `ExhaustivenessLimitExceeded`.
+
+use strata_parse::parse_str;
+use strata_types::TypeChecker;
+
+/// Helper: parse and type-check, expect success
+fn check_ok(src: &str) {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+}
+
+/// Helper: parse and type-check, expect failure
+fn check_err(src: &str) -> String {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    let err = checker
+        .check_module(&module)
+        .expect_err("expected type error but got OK");
+    format!("{err}")
+}
+
+/// `enum Wide { V0, V1, ... }` with `n` unit variants
+fn wide_enum(n: usize) -> String {
+    let variants: Vec<String> = (0..n).map(|i| format!("V{i}")).collect();
+    format!("enum Wide {{ {} }}", variants.join(", "))
+}
+
+/// Match arms `Wide::Vi => i` for each `i` in `range`
+fn wide_arms(range: impl Iterator<Item = usize>) -> String {
+    range
+        .map(|i| format!("Wide::V{i} => {i},"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `Opt::Some(Opt::Some(... inner ...))` nested `depth` times
+fn nested_some(depth: usize, inner: &str) -> String {
+    let mut pat = inner.to_string();
+    for _ in 0..depth {
+        pat = format!("Opt::Some({pat})");
+    }
+    pat
+}
+
+const OPT: &str = "enum Opt<T> { Some(T), None }";
+
+// ============================================================================
+// WIDE ENUMS
+// ============================================================================
+
+#[test]
+fn wide_enum_all_variants_exhaustive() {
+    check_ok(&format!(
+        "{} fn f(w: Wide) -> Int {{ match w {{ {} }} }}",
+        wide_enum(150),
+        wide_arms(0..150)
+    ));
+}
+
+#[test]
+fn wide_enum_missing_variant_reports_it() {
+    let arms = wide_arms((0..150).filter(|&i| i != 97));
+    let err = check_err(&format!(
+        "{} fn f(w: Wide) -> Int {{ match w {{ {arms} }} }}",
+        wide_enum(150)
+    ));
+    assert!(err.contains("Wide::V97"), "got: {err}");
+}
+
+#[test]
+fn wide_enum_redundant_arm() {
+    let err = check_err(&format!(
+        "{} fn f(w: Wide) -> Int {{ match w {{ {} Wide::V3 => 0, }} }}",
+        wide_enum(150),
+        wide_arms(0..150)
+    ));
+    assert!(err.to_lowercase().contains("unreachable"), "got: {err}");
+}
+
+#[test]
+fn pair_of_wide_enums_with_wildcards() {
+    let arms: Vec<String> = (0..120)
+        .map(|i| format!("(Wide::V{i}, _) => {i},"))
+        .collect();
+    check_ok(&format!(
+        "{} fn f(a: Wide, b: Wide) -> Int {{ match (a, b) {{ {} }} }}",
+        wide_enum(120),
+        arms.join(" ")
+    ));
+}
+
+// ============================================================================
+// DEEPLY NESTED GENERIC PATTERNS
+// ============================================================================
+
+#[test]
+fn deeply_nested_generic_exhaustive() {
+    let depth = 40;
+    let mut arms = vec![format!("{} => 1,", nested_some(depth, "_"))];
+    for d in 0..depth {
+        arms.push(format!("{} => 0,", nested_some(d, "Opt::None")));
+    }
+    check_ok(&format!(
+        "{OPT} fn f(x: {}) -> Int {{ match x {{ {} }} }}",
+        nested_ty(depth),
+        arms.join(" ")
+    ));
+}
+
+#[test]
+fn deeply_nested_generic_missing_case() {
+    let depth = 40;
+    let mut arms = vec![format!("{} => 1,", nested_some(depth, "_"))];
+    for d in (0..depth).filter(|&d| d != 25) {
+        arms.push(format!("{} => 0,", nested_some(d, "Opt::None")));
+    }
+    let err = check_err(&format!(
+        "{OPT} fn f(x: {}) -> Int {{ match x {{ {} }} }}",
+        nested_ty(depth),
+        arms.join(" ")
+    ));
+    assert!(err.contains("Opt::None"), "got: {err}");
+}
+
+#[test]
+fn wide_tuple_of_nested_patterns() {
+    // Eight columns, each nested several levels deep
+    let elem = nested_some(8, "_");
+    let pat = [elem.as_str(); 8].join(", ");
+    let ty = vec![nested_ty(8); 8].join(", ");
+    check_ok(&format!(
+        "{OPT} fn f(t: ({ty})) -> Int {{ match t {{ ({pat}) => 1, _ => 0 }} }}"
+    ));
+}
+
+#[test]
+fn tuple_of_bools_all_combinations() {
+    // 2^6 arms over six Bool columns
+    let mut arms = vec![];
+    for bits in 0..64u32 {
+        let pats: Vec<&str> = (0..6)
+            .map(|i| {
+                if bits & (1 << i) != 0 {
+                    "true"
+                } else {
+                    "false"
+                }
+            })
+            .collect();
+        arms.push(format!("({}) => {bits},", pats.join(", ")));
+    }
+    let ty = ["Bool"; 6].join(", ");
+    check_ok(&format!(
+        "fn f(t: ({ty})) -> Int {{ match t {{ {} }} }}",
+        arms.join(" ")
+    ));
+}
+
+// ============================================================================
+// STRUCT PATTERNS
+// ============================================================================
+
+#[test]
+fn struct_fields_in_any_order() {
+    check_ok(
+        r#"
+        struct P { a: Bool, b: Bool }
+        fn f(p: P) -> Int {
+            match p {
+                P { b: true, a: true } => 0,
+                P { a: false, b: true } => 1,
+                P { b: false, a } => 2,
+            }
+        }
+    "#,
+    );
+}
+
+#[test]
+fn partial_struct_pattern_missing_case() {
+    let err = check_err(
+        r#"
+        struct P { a: Bool, b: Bool }
+        fn f(p: P) -> Int {
+            match p {
+                P { b: true } => 0,
+                P { a: true, b: false } => 1,
+            }
+        }
+    "#,
+    );
+    assert!(err.contains("P(false, false)"), "got: {err}");
+}
+
+// ============================================================================
+// INTEGER INTERVALS
+// ============================================================================
+
+#[test]
+fn int_ranges_cover_domain() {
+    check_ok("fn f(n: Int) -> Int { match n { ..=-1 => 0, 0 => 1, 1.. => 2 } }");
+}
+
+#[test]
+fn int_ranges_adjacent_exclusive_and_inclusive() {
+    check_ok("fn f(n: Int) -> Int { match n { ..0 => 0, 0..10 => 1, 10..=19 => 2, 20.. => 3 } }");
+}
+
+#[test]
+fn int_literals_without_wildcard_not_exhaustive() {
+    let err = check_err("fn f(n: Int) -> Int { match n { 0 => 0, 1 => 1 } }");
+    assert!(err.contains("..=-1"), "got: {err}");
+}
+
+#[test]
+fn int_range_gap_reports_interval() {
+    let err = check_err("fn f(n: Int) -> Int { match n { ..5 => 0, 10.. => 1 } }");
+    assert!(err.contains("5..=9"), "got: {err}");
+}
+
+#[test]
+fn int_literal_inside_range_is_redundant() {
+    let err = check_err("fn f(n: Int) -> Int { match n { 0..=9 => 0, 5 => 1, _ => 2 } }");
+    assert!(err.to_lowercase().contains("unreachable"), "got: {err}");
+}
+
+#[test]
+fn int_range_covered_by_earlier_ranges_is_redundant() {
+    let err =
+        check_err("fn f(n: Int) -> Int { match n { ..=0 => 0, 1..=5 => 1, 0..3 => 2, _ => 3 } }");
+    assert!(err.to_lowercase().contains("unreachable"), "got: {err}");
+}
+
+#[test]
+fn empty_int_range_is_error() {
+    let err = check_err("fn f(n: Int) -> Int { match n { 5..=1 => 0, _ => 1 } }");
+    assert!(err.contains("matches no values"), "got: {err}");
+    let err = check_err("fn f(n: Int) -> Int { match n { 3..3 => 0, _ => 1 } }");
+    assert!(err.contains("matches no values"), "got: {err}");
+}
+
+#[test]
+fn int_ranges_in_tuple_with_bool() {
+    check_ok(
+        r#"
+        fn f(t: (Int, Bool)) -> Int {
+            match t {
+                (..=0, true) => 0,
+                (1.., true) => 1,
+                (_, false) => 2,
+            }
+        }
+    "#,
+    );
+    let err = check_err(
+        r#"
+        fn f(t: (Int, Bool)) -> Int {
+            match t {
+                (..=0, _) => 0,
+                (1..=100, true) => 1,
+                (101.., _) => 2,
+            }
+        }
+    "#,
+    );
+    assert!(
+        err.contains("1..=100") && err.contains("false"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn string_patterns_need_a_wildcard() {
+    let err = check_err(r#"fn f(s: String) -> Int { match s { "a" => 0, "b" + _ => 1 } }"#);
+    assert!(err.contains("'_' not covered"), "got: {err}");
+    check_ok(r#"fn f(s: String) -> Int { match s { "a" => 0, "b" + rest => 1, _ => 2 } }"#);
+}
+
+#[test]
+fn string_covered_by_earlier_prefix_is_redundant() {
+    let err =
+        check_err(r#"fn f(s: String) -> Int { match s { "ge" + _ => 0, "get" => 1, _ => 2 } }"#);
+    assert!(err.to_lowercase().contains("unreachable"), "got: {err}");
+    let err = check_err(
+        r#"fn f(s: String) -> Int { match s { "get " + _ => 0, "get " + ("x" + _) => 1, _ => 2 } }"#,
+    );
+    assert!(err.to_lowercase().contains("unreachable"), "got: {err}");
+    // A longer prefix first leaves the shorter one useful
+    check_ok(r#"fn f(s: String) -> Int { match s { "get x" + _ => 0, "get " + _ => 1, _ => 2 } }"#);
+}
+
+#[test]
+fn string_prefixes_in_tuple_with_bool() {
+    let err = check_err(
+        r#"
+        fn f(t: (String, Bool)) -> Int {
+            match t {
+                ("-" + _, _) => 0,
+                (_, true) => 1,
+            }
+        }
+    "#,
+    );
+    assert!(err.contains("false"), "got: {err}");
+}
+
+/// `Opt<Opt<... Int ...>>` nested `depth` times
+fn nested_ty(depth: usize) -> String {
+    let mut ty = "Int".to_string();
+    for _ in 0..depth {
+        ty = format!("Opt<{ty}>");
+    }
+    ty
+}
@@ -0,0 +1,125 @@
+//! Integration tests for checking a single expression against an expected
+//! type, as an embedder validating a configured snippet would.
+
+use strata_ast::ast::{Expr, Item};
+use strata_parse::parse_str;
+use strata_types::infer::Ty;
+use strata_types::{EffectRow, TypeChecker, TypeError};
+
+/// Helper: the expression `src`, parsed as the value of a `let`
+fn expr(src: &str) -> Expr {
+    let module = parse_str("<snippet>", &format!("let snippet = {src};")).expect("parse failed");
+    match module.items.into_iter().next() {
+        Some(Item::Let(decl)) => decl.value,
+        other => panic!("expected a let, got {other:?}"),
+    }
+}
+
+/// Helper: a checker that has checked `src`
+fn checker_for(src: &str) -> TypeChecker {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker
+}
+
+fn int_to_bool() -> Ty {
+    Ty::arrow_eff(vec![Ty::int()], Ty::bool_(), EffectRow::pure())
+}
+
+#[test]
+fn snippet_of_the_expected_type_checks() {
+    let mut checker = TypeChecker::new();
+    checker
+        .check_expr_with_expected(&expr("1 + 2"), &Ty::int())
+        .unwrap();
+    checker
+        .check_expr_with_expected(
+            &expr("{ fn small(n: Int) -> Bool { n < 10 } small }"),
+            &int_to_bool(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn snippet_of_another_type_is_a_mismatch() {
+    let mut checker = TypeChecker::new();
+    let errors = checker
+        .check_expr_with_expected(&expr("\"yes\""), &Ty::bool_())
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(
+        matches!(errors[0], TypeError::Mismatch { .. }),
+        "{errors:?}"
+    );
+
+    // Right shape, wrong effects: the snippet would need a capability
+    let mut checker = checker_for(
+        "extern fn now(t: &TimeCap) -> Int & {Time};
+         fn late(t: &TimeCap, n: Int) -> Bool & {Time} { now(t) > n }",
+    );
+    let timed = Ty::arrow_eff(
+        vec![
+            Ty::Ref(Box::new(Ty::Cap(strata_types::CapKind::Time))),
+            Ty::int(),
+        ],
+        Ty::bool_(),
+        EffectRow::pure(),
+    );
+    assert!(checker
+        .check_expr_with_expected(&expr("late"), &timed)
+        .is_err());
+}
+
+#[test]
+fn snippet_sees_the_checked_module() {
+    let mut checker = checker_for(
+        "const LIMIT: Int = 10;
+         enum Level { Low, High }
+         fn level(n: Int) -> Level { if n > LIMIT { Level::High } else { Level::Low } }",
+    );
+    let level = Ty::adt("Level", vec![]);
+    checker
+        .check_expr_with_expected(&expr("level(LIMIT + 1)"), &level)
+        .unwrap();
+    let errors = checker
+        .check_expr_with_expected(&expr("missing(1)"), &level)
+        .unwrap_err();
+    assert!(
+        matches!(&errors[0], TypeError::UnknownVariable { name, .. } if name == "missing"),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn failed_checks_leave_the_checker_unchanged() {
+    let mut checker = checker_for("fn double(n: Int) -> Int { n * 2 }");
+    let typed_before = checker.typed_items().to_vec();
+    for _ in 0..3 {
+        assert!(checker
+            .check_expr_with_expected(&expr("double(true)"), &Ty::int())
+            .is_err());
+    }
+    // No constraint from the failed checks leaks into the next one
+    checker
+        .check_expr_with_expected(&expr("double(21)"), &Ty::int())
+        .unwrap();
+    assert_eq!(checker.typed_items(), typed_before.as_slice());
+    assert!(checker.warnings().is_empty());
+}
+
+#[test]
+fn every_error_is_reported() {
+    // A type error and a read of a declared but unassigned variable
+    let mut checker = TypeChecker::new();
+    let errors = checker
+        .check_expr_with_expected(&expr("{ let mut x: Int; x + 1 }"), &Ty::bool_())
+        .unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(
+        matches!(errors[0], TypeError::Mismatch { .. }),
+        "{errors:?}"
+    );
+}
@@ -0,0 +1,99 @@
+//! Integration tests for `extern fn` parameter default values.
+//!
+//! A default is a constant expression of its parameter's type; a call may
+//! leave off the defaulted parameters at the end of the list.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const FETCH: &str = r#"
+    const TIMEOUT: Int = 30;
+    extern fn fetch(net: &NetCap, url: String, timeout: Int = TIMEOUT * 2, retry: Bool = false) -> String & {Net};
+"#;
+
+#[test]
+fn calls_may_leave_off_defaulted_params() {
+    for call in [
+        r#"fetch(&net, "a")"#,
+        r#"fetch(&net, "a", 5)"#,
+        r#"fetch(&net, "a", 5, true)"#,
+    ] {
+        let src = format!("{FETCH}\nfn get(net: NetCap) -> String & {{Net}} {{ {call} }}");
+        check(&src).unwrap_or_else(|e| panic!("{call}: {e}"));
+    }
+}
+
+#[test]
+fn required_params_may_not_be_left_off() {
+    let src = format!("{FETCH}\nfn get(net: NetCap) -> String & {{Net}} {{ fetch(&net) }}");
+    let err = check_err(&src);
+    assert!(
+        matches!(err, TypeError::ArityMismatch { .. }),
+        "got: {err:?}"
+    );
+
+    let src = format!(
+        "{FETCH}\nfn get(net: NetCap) -> String & {{Net}} {{ fetch(&net, \"a\", 5, true, 1) }}"
+    );
+    let err = check_err(&src);
+    assert!(
+        matches!(err, TypeError::ArityMismatch { .. }),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn elided_args_still_take_the_declared_types() {
+    let src =
+        format!("{FETCH}\nfn get(net: NetCap) -> String & {{Net}} {{ fetch(&net, \"a\", true) }}");
+    let err = check_err(&src);
+    assert!(matches!(err, TypeError::Mismatch { .. }), "got: {err:?}");
+}
+
+#[test]
+fn a_local_of_the_same_name_does_not_elide() {
+    let src = format!(
+        "{FETCH}\nfn get(net: NetCap) -> String & {{Net}} {{ fn fetch(url: String, timeout: Int) -> String {{ url }} fetch(\"a\") }}"
+    );
+    let err = check_err(&src);
+    assert!(
+        matches!(err, TypeError::ArityMismatch { .. }),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn invalid_defaults_are_rejected() {
+    for src in [
+        // Only extern fns take defaults
+        "fn f(x: Int = 1) -> Int { x }",
+        // A required param can't follow a defaulted one
+        "extern fn f(t: &TimeCap, x: Int = 1, y: Int) -> Int & {Time};",
+        // Nor can a capability have a default
+        "extern fn f(t: TimeCap = 1) -> Int & {Time};",
+        // Nor a non-const type
+        "extern fn f(t: &TimeCap, x: Option<Int> = 1) -> Int & {Time};",
+        // Defaults are compile-time constants
+        "fn g() -> Int { 1 }\nextern fn f(t: &TimeCap, x: Int = g()) -> Int & {Time};",
+    ] {
+        let err = check_err(src);
+        assert_eq!(err.code(), "TYPE-INVALID-DEFAULT", "{src}: {err}");
+    }
+
+    let err = check_err("extern fn f(t: &TimeCap, x: Int = \"s\") -> Int & {Time};");
+    assert!(matches!(err, TypeError::Mismatch { .. }), "got: {err:?}");
+    let err = check_err("extern fn f(t: &TimeCap, x: Int = 1 / 0) -> Int & {Time};");
+    assert_eq!(err.code(), "TYPE-CONST-EVAL");
+}
@@ -0,0 +1,75 @@
+//! Integration tests for variadic `extern fn` parameters.
+//!
+//! `args: ...Show` (or `...T`) is the last parameter of an extern fn; a
+//! call passes any number of arguments for it, each checked against the
+//! bound.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const LOG: &str = r#"
+    struct Point { x: Int, y: Int }
+    extern fn log(console: &ConsoleCap, fmt: String, args: ...Show) -> () & {Console};
+    extern fn total(console: &ConsoleCap, xs: ...Int) -> Int & {Console};
+"#;
+
+fn with_main(body: &str) -> String {
+    format!("{LOG}\nfn main(c: ConsoleCap) -> () & {{Console}} {{ {body} }}")
+}
+
+#[test]
+fn variadic_calls_take_any_number_of_arguments() {
+    for body in [
+        r#"log(&c, "none")"#,
+        r#"log(&c, "{} {} {}", 1, "two", Point { x: 3, y: 4 })"#,
+        r#"let n = total(&c); let m = total(&c, 1, 2, n); ()"#,
+    ] {
+        check(&with_main(body)).unwrap_or_else(|e| panic!("{body}: {e}"));
+    }
+}
+
+#[test]
+fn each_argument_must_fit_the_bound() {
+    let err = check_err(&with_main(r#"log(&c, "{}", c)"#));
+    assert_eq!(err.code(), "TYPE-SHOW-CAPABILITY");
+    assert!(err.to_string().starts_with("log()"), "{err}");
+
+    let err = check_err(&with_main(r#"let n = total(&c, 1, true); ()"#));
+    assert!(matches!(err, TypeError::Mismatch { .. }), "got: {err:?}");
+
+    // The fixed parameters are still required
+    let err = check_err(&with_main("log(&c)"));
+    assert!(
+        matches!(err, TypeError::ArityMismatch { .. }),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn invalid_variadics_are_rejected() {
+    for src in [
+        // Only extern fns are variadic
+        "fn f(xs: ...Int) -> Int { 0 }",
+        // The variadic parameter comes last
+        "extern fn f(c: &ConsoleCap, xs: ...Int, y: Int) -> () & {Console};",
+        // No defaults alongside one
+        "extern fn f(c: &ConsoleCap, y: Int = 1, xs: ...Int) -> () & {Console};",
+        // Its bound holds no capability
+        "extern fn f(c: &ConsoleCap, xs: ...FsCap) -> () & {Console};",
+    ] {
+        let err = check_err(src);
+        assert_eq!(err.code(), "TYPE-INVALID-VARIADIC", "{src}: {err}");
+    }
+}
@@ -0,0 +1,94 @@
+//! Integration tests for struct field default values.
+//!
+//! `struct Config { retries: Int = 3, host: String }`: a default is a
+//! constant expression of its field's type, and a struct literal may leave
+//! the defaulted fields off.
+
+use strata_parse::parse_str;
+use strata_types::consts::ConstValue;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const CONFIG: &str = r#"
+    const BASE: Int = 2;
+    struct Config { retries: Int = BASE + 1, host: String, verbose: Bool = false }
+"#;
+
+#[test]
+fn literals_may_leave_off_defaulted_fields() {
+    for literal in [
+        r#"Config { host: "a" }"#,
+        r#"Config { host: "a", retries: 5 }"#,
+        r#"Config { verbose: true, host: "a", retries: 5 }"#,
+    ] {
+        let src = format!("{CONFIG}\nfn f() -> Config {{ {literal} }}");
+        check(&src).unwrap_or_else(|e| panic!("{literal}: {e}"));
+    }
+
+    // Fields without a default are still required
+    let err = check_err(&format!(
+        "{CONFIG}\nfn f() -> Config {{ Config {{ retries: 1 }} }}"
+    ));
+    assert!(
+        matches!(&err, TypeError::MissingField { field, .. } if field == "host"),
+        "got: {err:?}"
+    );
+}
+
+#[test]
+fn defaults_are_recorded_in_the_registry() {
+    let module = parse_str("<test>", CONFIG).unwrap();
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).unwrap();
+    let fields = checker
+        .adt_registry()
+        .get("Config")
+        .unwrap()
+        .fields()
+        .unwrap();
+    let defaults: Vec<_> = fields.iter().map(|f| f.default.clone()).collect();
+    assert_eq!(
+        defaults,
+        [
+            Some(ConstValue::Int(3)),
+            None,
+            Some(ConstValue::Bool(false))
+        ]
+    );
+}
+
+#[test]
+fn invalid_defaults_are_rejected() {
+    for src in [
+        // Of the field's type
+        r#"struct C { x: Int = "a" }"#,
+        // On a field of a type a const can't have
+        "struct C<T> { x: T = 1 }",
+        "struct P { x: Int } struct C { p: P = 1 }",
+        // Constant
+        "fn f() -> Int { 1 }\nstruct C { x: Int = f() }",
+    ] {
+        let err = check_err(src);
+        assert!(
+            matches!(
+                err,
+                TypeError::InvalidDefault { .. } | TypeError::Mismatch { .. }
+            ),
+            "{src}: {err:?}"
+        );
+    }
+    let err = check_err("fn f() -> Int { 1 }\nstruct C { x: Int = f() }");
+    assert_eq!(err.code(), "TYPE-INVALID-DEFAULT");
+    assert!(err.to_string().contains("field 'x'"), "{err}");
+}
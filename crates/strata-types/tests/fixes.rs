@@ -0,0 +1,233 @@
+//! Integration tests for the fixes suggested alongside type errors.
+//!
+//! Each test checks the edit a fix makes by applying it to the source, so
+//! the expectations read as before/after programs.
+
+use strata_ast::diag::{Applicability, Fix};
+use strata_parse::parse_str;
+use strata_types::fixes::{annotations, suggest_fixes};
+use strata_types::TypeChecker;
+
+/// Helper: parse and type-check `src`, which must fail, and return the
+/// fixes suggested for the error.
+fn fixes_for(src: &str) -> Vec<Fix> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let err = TypeChecker::new()
+        .check_module(&module)
+        .expect_err("expected a type error");
+    suggest_fixes(&err, &module, src)
+}
+
+/// Helper: apply the single suggested fix
+fn apply_only(src: &str) -> (String, Applicability) {
+    let fixes = fixes_for(src);
+    assert_eq!(fixes.len(), 1, "expected one fix, got {fixes:?}");
+    let (fixed, applied) = Fix::apply_all(src, &fixes);
+    assert_eq!(applied, 1);
+    (fixed, fixes[0].applicability)
+}
+
+#[test]
+fn effect_name_as_param_type_becomes_capability() {
+    let (fixed, applicability) = apply_only("fn f(fs: Fs) -> Int { 1 }");
+    assert_eq!(fixed, "fn f(fs: FsCap) -> Int { 1 }");
+    assert_eq!(applicability, Applicability::MachineApplicable);
+}
+
+#[test]
+fn capability_in_effect_row_becomes_effect() {
+    let (fixed, _) = apply_only("fn f(fs: FsCap) -> Int & {FsCap} { 1 }");
+    assert_eq!(fixed, "fn f(fs: FsCap) -> Int & {Fs} { 1 }");
+}
+
+#[test]
+fn missing_effect_added_to_annotation() {
+    let src = r#"extern fn now(t: &TimeCap) -> Int & {Time};
+fn f(fs: FsCap, t: TimeCap) -> Int & {Fs} { now(&t) }"#;
+    let (fixed, applicability) = apply_only(src);
+    assert!(
+        fixed.ends_with("-> Int & {Fs, Time} { now(&t) }"),
+        "got: {fixed}"
+    );
+    assert_eq!(applicability, Applicability::MachineApplicable);
+}
+
+#[test]
+fn missing_effect_added_to_empty_annotation() {
+    let src = r#"extern fn now(t: &TimeCap) -> Int & {Time};
+fn f(t: TimeCap) -> Int & {} { now(&t) }"#;
+    let (fixed, _) = apply_only(src);
+    assert!(
+        fixed.ends_with("-> Int & {Time} { now(&t) }"),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn missing_capability_param_added_to_uncalled_fn() {
+    let (fixed, applicability) = apply_only("fn f(x: Int) -> Int & {Net} { x }");
+    assert_eq!(fixed, "fn f(x: Int, net: NetCap) -> Int & {Net} { x }");
+    assert_eq!(applicability, Applicability::MachineApplicable);
+}
+
+#[test]
+fn missing_capability_param_on_extern_is_borrowed() {
+    let (fixed, _) = apply_only("extern fn now() -> Int & {Time};");
+    assert_eq!(fixed, "extern fn now(time: &TimeCap) -> Int & {Time};");
+}
+
+#[test]
+fn missing_capability_param_avoids_taken_names() {
+    let (fixed, _) = apply_only("extern fn f(time: Int) -> Int & {Time};");
+    assert_eq!(
+        fixed,
+        "extern fn f(time: Int, time2: &TimeCap) -> Int & {Time};"
+    );
+}
+
+#[test]
+fn missing_capability_param_goes_before_variadic() {
+    let (fixed, _) = apply_only("extern fn log(fmt: String, args: ...Show) -> () & {Console};");
+    assert_eq!(
+        fixed,
+        "extern fn log(fmt: String, console: &ConsoleCap, args: ...Show) -> () & {Console};"
+    );
+}
+
+#[test]
+fn missing_capability_param_unsafe_when_fn_is_called() {
+    let src = r#"extern fn now() -> Int & {Time};
+fn main() -> Int & {Time} { now() }"#;
+    let fixes = fixes_for(src);
+    assert_eq!(fixes.len(), 1);
+    assert_eq!(fixes[0].applicability, Applicability::HasPlaceholders);
+}
+
+#[test]
+fn non_exhaustive_match_gets_arm_skeleton() {
+    let src = "enum E { A, B }\nfn f(e: E) -> Int { match e { E::A => 1 } }";
+    let (fixed, applicability) = apply_only(src);
+    assert!(
+        fixed.ends_with("match e { E::A => 1, E::B => todo() } }"),
+        "got: {fixed}"
+    );
+    assert_eq!(applicability, Applicability::HasPlaceholders);
+}
+
+#[test]
+fn non_exhaustive_multiline_match_arm_is_indented() {
+    let src = "enum E { A, B }
+fn f(e: E) -> Int {
+    match e {
+        E::A => 1,
+    }
+}";
+    let (fixed, _) = apply_only(src);
+    assert!(
+        fixed.contains("        E::A => 1,\n        E::B => todo(),\n    }"),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn non_exhaustive_match_lists_every_missing_pattern() {
+    let src = "enum S { Circle(Int), Square(Int), Tri(Bool, Bool), Empty }
+fn f(s: S) -> Int { match s { S::Circle(r) => r, S::Tri(true, _) => 1 } }";
+    let (fixed, _) = apply_only(src);
+    assert!(
+        fixed.contains(
+            "S::Tri(true, _) => 1, S::Square(_) => todo(), S::Empty => todo(), \
+             S::Tri(false, _) => todo() }"
+        ),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn non_exhaustive_match_fix_names_each_arm() {
+    let src = "enum E { A, B, C }\nfn f(e: E) -> Int { match e { E::A => 1 } }";
+    let fixes = fixes_for(src);
+    assert_eq!(fixes.len(), 1);
+    assert_eq!(fixes[0].message, "add arms for `E::B`, `E::C`");
+}
+
+#[test]
+fn missing_patterns_use_tuple_and_struct_syntax() {
+    let src = "struct P { a: Bool, b: Int }
+fn f(p: P) -> Int { match p { P { a: true, b: 1 } => 1 } }";
+    let (fixed, _) = apply_only(src);
+    assert!(
+        fixed.contains("P { a: false, b: _ } => todo(), P { a: true, b: _ } => todo()"),
+        "got: {fixed}"
+    );
+
+    let src = "fn f(q: (Bool, Int)) -> Int { match q { (true, 0) => 0 } }";
+    let (fixed, _) = apply_only(src);
+    assert!(
+        fixed.contains("(true, 0) => 0, (false, _) => todo(), (true, _) => todo() }"),
+        "got: {fixed}"
+    );
+}
+
+#[test]
+fn unrelated_errors_have_no_fixes() {
+    assert!(fixes_for("fn f() -> Int { true }").is_empty());
+    assert!(fixes_for("fn f(x: Strin) -> Int { 1 }").is_empty());
+}
+
+/// Helper: type-check `src`, which must pass, and apply every annotation
+fn annotated(src: &str) -> String {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    let fixes = annotations(&module, checker.typed_items(), src);
+    assert!(fixes
+        .iter()
+        .all(|f| f.applicability == Applicability::MachineApplicable));
+    Fix::apply_all(src, &fixes).0
+}
+
+#[test]
+fn annotations_write_inferred_params_returns_and_effects() {
+    let src = r#"extern fn read(fs: &FsCap, path: String) -> String & {Fs};
+fn load(fs: &FsCap, p) { read(fs, p) }
+fn add(a, b) { (a + b, Option::Some(a)) }"#;
+    assert_eq!(
+        annotated(src),
+        r#"extern fn read(fs: &FsCap, path: String) -> String & {Fs};
+fn load(fs: &FsCap, p: String) -> String & {Fs} { read(fs, p) }
+fn add(a: Int, b: Int) -> (Int, Option<Int>) & {} { (a + b, Option::Some(a)) }"#
+    );
+}
+
+#[test]
+fn annotations_keep_what_is_written_and_are_idempotent() {
+    let src = "fn f(x: Int) -> Int & {} { x }\nfn g(x) -> Int { f(x) }";
+    let once = annotated(src);
+    assert_eq!(
+        once,
+        "fn f(x: Int) -> Int & {} { x }\nfn g(x: Int) -> Int & {} { f(x) }"
+    );
+    assert_eq!(annotated(&once), once);
+}
+
+#[test]
+fn annotations_skip_what_source_cannot_say() {
+    // Generic parameters and unit returns stay inferred
+    assert_eq!(
+        annotated("fn id(x) { x }\nfn nothing() { () }"),
+        "fn id(x) & {} { x }\nfn nothing() & {} { () }"
+    );
+    // Closing the row would make `apply` reject effectful callbacks
+    let src = "fn apply(f, x: Int) -> Int { f(x) }";
+    let module = parse_str("<test>", src).unwrap();
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).unwrap();
+    let fixes = annotations(&module, checker.typed_items(), src);
+    assert!(
+        fixes.iter().all(|f| !f.message.contains("effects")),
+        "{fixes:?}"
+    );
+}
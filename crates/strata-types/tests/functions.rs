@@ -1,7 +1,7 @@
 //! Integration tests for function type checking
 
 use strata_parse::parse_str;
-use strata_types::TypeChecker;
+use strata_types::{Lint, TypeChecker};
 
 #[test]
 fn simple_function_declaration() {
@@ -387,3 +387,351 @@ fn test_if_never_else_diverges_then_used() {
         "Diverging else with matching then should be OK"
     );
 }
+
+/// `todo()` and `unreachable()` diverge, so a call fits any expected type.
+#[test]
+fn test_diverging_builtins_fit_any_type() {
+    let src = r#"
+        enum E { A, B }
+        fn pick(e: E) -> Int {
+            match e { E::A => 1, E::B => unreachable() }
+        }
+        fn later(x: Int) -> String { todo() }
+        fn main() -> Int {
+            let n: Int = if true { 2 } else { todo() };
+            let b: Bool = unreachable();
+            todo() + n
+        }
+    "#;
+
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+
+    if let Err(e) = checker.check_module(&module) {
+        panic!("diverging builtins should check anywhere, got: {e}");
+    }
+}
+
+#[test]
+fn test_diverging_builtins_take_no_arguments() {
+    let src = r#"
+        fn f() -> Int { todo("later") }
+    "#;
+
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+
+    assert!(checker.check_module(&module).is_err());
+}
+
+/// A user definition named `todo` replaces the builtin.
+#[test]
+fn test_user_fn_shadows_diverging_builtin() {
+    let src = r#"
+        fn todo() -> Int { 0 }
+        fn f() -> String { todo() }
+    "#;
+
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+
+    assert!(checker.check_module(&module).is_err());
+}
+
+/// `arg_count()` and `arg(i)` expose the program's command-line arguments.
+#[test]
+fn test_arg_builtins() {
+    let src = r#"
+        fn first_or(default: String) -> String {
+            match arg(0) { Option::Some(a) => a, Option::None => default }
+        }
+        fn main() -> Int { arg_count() }
+    "#;
+
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+
+    if let Err(e) = checker.check_module(&module) {
+        panic!("arg builtins should check, got: {e}");
+    }
+
+    let src = r#"
+        fn f() -> String { arg(0) }
+    "#;
+
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+
+    assert!(checker.check_module(&module).is_err());
+}
+
+#[test]
+fn duplicate_top_level_definitions_are_rejected() {
+    // Each pair used to overwrite the first signature: an internal error,
+    // a confusing mismatch, or no error at all
+    for src in [
+        "let f = (); fn f() -> Int { 1 }",
+        "fn f() -> Int { 1 } fn f() -> Bool { true }",
+        "fn f() -> Int { 1 } let f = 2;",
+        "const f: Int = 1; fn f() -> Int { 2 }",
+    ] {
+        let module = parse_str("<test>", src).expect("parse failed");
+        let mut checker = TypeChecker::new();
+
+        match checker.check_module(&module) {
+            Err(e) => assert_eq!(e.code(), "TYPE-DUPLICATE-DEFINITION", "{src}: {e}"),
+            Ok(()) => panic!("{src}: expected a duplicate definition error"),
+        }
+    }
+
+    // Top-level lets may still shadow each other
+    let module = parse_str("<test>", "let x = 1; let x = true;").expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_ok());
+}
+
+#[test]
+fn show_formats_any_value_without_capabilities() {
+    let src = r#"
+        struct Point { x: Int, y: Int }
+        fn twice(x: Int) -> Int { x * 2 }
+        fn main() -> (String, String, String) {
+            (show(Point { x: 1, y: 2 }), show((1, Option::Some("a"))), show(twice))
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("show should check, got: {e}");
+    }
+
+    for src in [
+        "fn main(fs: FsCap) -> String { show(fs) }",
+        "fn main(fs: FsCap) -> String { show((1, fs)) }",
+        "linear struct Ctx { fs: FsCap } fn f(c: Ctx) -> String { show(c) }",
+    ] {
+        let module = parse_str("<test>", src).expect("parse failed");
+        match TypeChecker::new().check_module(&module) {
+            Err(e) => assert_eq!(e.code(), "TYPE-SHOW-CAPABILITY", "{src}: {e}"),
+            Ok(()) => panic!("{src}: expected show() to be rejected"),
+        }
+    }
+}
+
+#[test]
+fn par_has_the_effects_of_both_branches() {
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        extern fn random_int(r: &RandCap) -> Int & {Rand};
+        fn stamp(t: &TimeCap) -> String & {Time} { now(t) }
+        fn roll(r: RandCap) -> Int & {Rand} { random_int(&r) }
+        fn main(t: TimeCap, r: RandCap) -> (String, Int) & {Time, Rand} {
+            par(stamp, &t, roll, r)
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("par should check, got: {e}");
+    }
+
+    // Each branch's effects count against the caller
+    let missing = src.replace("(String, Int) & {Time, Rand}", "(String, Int) & {Time}");
+    let module = parse_str("<test>", &missing).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Err(e) => assert_eq!(e.code(), "EFFECT-UNDECLARED", "{e}"),
+        Ok(()) => panic!("expected the Rand branch to need {{Rand}}"),
+    }
+
+    // A capability passed to a branch is moved like any argument
+    let reused = src.replace(
+        "par(stamp, &t, roll, r)",
+        "let p = par(stamp, &t, roll, r); let n = roll(r); p",
+    );
+    let module = parse_str("<test>", &reused).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+
+    // A branch gets its argument's type
+    let module = parse_str(
+        "<test>",
+        "fn inc(n: Int) -> Int { n + 1 } fn main() -> (Int, Int) { par(inc, 1, inc, \"a\") }",
+    )
+    .expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+}
+
+#[test]
+fn for_loops_iterate_sequences_generically() {
+    let src = r#"
+        fn succ(n: Int) -> Option<Int> { Option::Some(n + 1) }
+        fn label(n: Int) -> (Int, String) { (n, show(n)) }
+        fn small(p: (Int, String)) -> Bool { let (n, _) = p; n < 3 }
+        fn main() -> Int {
+            let mut total = 0;
+            for (n, s) in take(filter(map(iter(0, succ), label), small), 5) {
+                total = total + n;
+            };
+            total
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("a for loop over adapted sequences should check, got: {e}");
+    }
+
+    // The pattern gets the item type
+    let wrong = src.replace("total = total + n;", "total = total + s;");
+    let module = parse_str("<test>", &wrong).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+
+    // Only an `Iter` can be looped over
+    let not_iter = src.replace(
+        "take(filter(map(iter(0, succ), label), small), 5)",
+        "(1, \"a\")",
+    );
+    let module = parse_str("<test>", &not_iter).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+
+    // The pattern must be irrefutable
+    let refutable = src.replace("for (n, s) in", "for (0, s) in");
+    let module = parse_str("<test>", &refutable).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+}
+
+#[test]
+fn iter_items_cannot_be_single_use() {
+    let src = r#"
+        fn keep(t: TimeCap) -> Option<TimeCap> { Option::None }
+        fn main(t: TimeCap) -> Int {
+            let s = iter(t, keep);
+            1
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Err(e) => assert_eq!(e.code(), "TYPE-ITER-SINGLE-USE", "{e}"),
+        Ok(()) => panic!("expected iter() over a capability to be rejected"),
+    }
+}
+
+#[test]
+fn join_gives_the_spawned_block_type() {
+    let src = r#"
+        fn main() -> (Int, String) {
+            let n = 20;
+            let a = spawn { n * 2 };
+            let b = spawn { if n > 0 { return "pos"; } else { "neg" } };
+            (join(a), join(b))
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("spawn/join should check, got: {e}");
+    }
+
+    let wrong = src.replace("(Int, String)", "(Int, Int)");
+    let module = parse_str("<test>", &wrong).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+
+    // A task is joined at most once
+    let twice = src.replace("(join(a), join(b))", "(join(a), join(b), join(a))");
+    let twice = twice.replace("(Int, String) {", "(Int, String, Int) {");
+    let module = parse_str("<test>", &twice).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+}
+
+#[test]
+fn task_handles_get_task_diagnostics() {
+    // Joined twice: reported as a task, pointing at the first join
+    let src = "fn main() -> Int { let t = spawn { 1 }; join(t) + join(t) }";
+    let module = parse_str("<test>", src).expect("parse failed");
+    let err = TypeChecker::new()
+        .check_module(&module)
+        .expect_err("expected the second join to be rejected");
+    assert_eq!(err.code(), "TASK-ALREADY-JOINED", "{err}");
+    let msg = err.to_string();
+    assert!(
+        msg.contains("task 't'") && !msg.contains("capability"),
+        "got: {msg}"
+    );
+    let first_join = src.find("join(t)").unwrap() + "join(".len();
+    let notes = err.notes();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].0, "'t' was joined or passed on here");
+    assert_eq!(notes[0].1.start as usize, first_join);
+
+    // Never joined: its own lint, suggesting `join` rather than `drop`
+    let src = "fn main() -> Int { let t = spawn { 1 }; 0 }";
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .expect("unjoined task should check");
+    let warnings = checker.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].lint(), Lint::UnjoinedTask);
+    let msg = warnings[0].to_string();
+    assert!(
+        msg.contains("task 't' is never joined") && msg.contains("join(t)"),
+        "got: {msg}"
+    );
+    assert!(!msg.contains("drop"), "got: {msg}");
+}
+
+#[test]
+fn spawn_cannot_share_mutable_variables() {
+    let src = r#"
+        fn main() -> Int {
+            let mut total = 1;
+            let t = spawn { total + 1 };
+            join(t)
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Err(e) => assert_eq!(e.code(), "TYPE-SPAWN-MUTABLE-CAPTURE", "{e}"),
+        Ok(()) => panic!("expected the task's read of `total` to be rejected"),
+    }
+
+    let assigned = src.replace("spawn { total + 1 }", "spawn { total = 2; 0 }");
+    let module = parse_str("<test>", &assigned).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Err(e) => assert_eq!(e.code(), "TYPE-SPAWN-MUTABLE-CAPTURE", "{e}"),
+        Ok(()) => panic!("expected the task's write to `total` to be rejected"),
+    }
+
+    // A task's own mutable variables, and immutable copies, are fine
+    let copied = src.replace(
+        "let t = spawn { total + 1 };",
+        "let snapshot = total; let t = spawn { let mut n = snapshot; n = n + 1; n };",
+    );
+    let module = parse_str("<test>", &copied).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("copying into an immutable binding should check, got: {e}");
+    }
+}
+
+#[test]
+fn spawn_moves_the_capabilities_it_uses() {
+    let src = r#"
+        extern fn now(t: &TimeCap) -> String & {Time};
+        fn main(t: TimeCap) -> String & {Time} {
+            let task = spawn { now(&t) };
+            join(task)
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    if let Err(e) = TypeChecker::new().check_module(&module) {
+        panic!("a task using a capability should check, got: {e}");
+    }
+
+    // Even borrowed inside the task, the capability is the task's now
+    let reused = src.replace("join(task)", "let mine = now(&t); join(task)");
+    let module = parse_str("<test>", &reused).expect("parse failed");
+    assert!(TypeChecker::new().check_module(&module).is_err());
+
+    // The task's effects count against the spawning function
+    let pure = src.replace("String & {Time} {", "String & {} {");
+    let module = parse_str("<test>", &pure).expect("parse failed");
+    match TypeChecker::new().check_module(&module) {
+        Err(e) => assert_eq!(e.code(), "EFFECT-UNDECLARED", "{e}"),
+        Ok(()) => panic!("expected the task's {{Time}} to count against main"),
+    }
+}
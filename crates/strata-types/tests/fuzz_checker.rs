@@ -0,0 +1,550 @@
+//! Fuzz harness for the type checker.
+//!
+//! Generates random, structurally valid modules straight as ASTs (no
+//! parser round trip, so shapes the parser never produces are covered
+//! too) and checks each one. Whatever the verdict, `check_module` must
+//! return: no panic, and within a time bound, so the depth, effect
+//! variable, and exhaustiveness limits are what stop pathological input.
+//!
+//! Names come from small pools so that references often resolve and the
+//! checker gets past name lookup into inference, effects, and matching.
+//!
+//! The generator is written by hand rather than derived with the
+//! `arbitrary` crate. A derive would have to sit on the `strata-ast` types,
+//! which would give that crate a fuzzing dependency. It would also build
+//! identifiers from raw bytes, so nearly every case would stop at the first
+//! unresolved name. It has no depth bound either. The local [`Arbitrary`]
+//! trait draws names from the pools below and takes a depth budget.
+//!
+//! `STRATA_FUZZ_CASES` and `STRATA_FUZZ_SEED` override the case count and
+//! starting seed, for longer local runs.
+
+use std::sync::mpsc;
+use std::time::Duration;
+use strata_ast::ast::*;
+use strata_ast::span::Span;
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+const DEFAULT_CASES: u64 = 400;
+const DEFAULT_SEED: u64 = 0x5eed_c4ec_0000_0001;
+
+/// Generous: a case normally checks in well under a millisecond
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Nesting budget for generated expressions, patterns, and types
+const MAX_DEPTH: u32 = 5;
+
+const VALUES: &[&str] = &["x", "y", "n", "s", "cap", "f", "g", "main", "Point", "_"];
+const FNS: &[&str] = &["f", "g", "h", "main", "read_file", "now", "print", "todo"];
+/// Types that resolve against [`PRELUDE`]; drawn most of the time
+const TYPES: &[&str] = &["Int", "Bool", "String", "Unit", "Point", "Color", "FsCap"];
+/// Types that are unknown, need arguments, or are otherwise rejected
+const ODD_TYPES: &[&str] = &["Float", "Opt", "T", "TimeCap", "NetCap", "Nope"];
+const EFFECTS: &[&str] = &["Fs", "Time", "Net", "Rand", "Bogus"];
+const VARIANTS: &[(&str, &str)] = &[
+    ("Opt", "Some"),
+    ("Opt", "None"),
+    ("Color", "Red"),
+    ("Color", "Green"),
+    ("Point", "Nope"),
+];
+const FIELDS: &[&str] = &["x", "y", "z"];
+
+/// Definitions most generated modules start with, so that type and
+/// variant names usually resolve
+const PRELUDE: &str = "
+    struct Point { x: Int, y: Int }
+    enum Opt<T> { Some(T), None }
+    enum Color { Red, Green }
+";
+
+/// Deterministic source of choices (xorshift64) that also hands out
+/// distinct, increasing spans.
+struct Gen {
+    state: u64,
+    offset: u32,
+}
+
+impl Gen {
+    fn new(seed: u64) -> Self {
+        Self {
+            state: seed | 1,
+            offset: 0,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    fn span(&mut self) -> Span {
+        let start = self.offset;
+        self.offset += 1 + self.below(8) as u32;
+        Span {
+            start,
+            end: self.offset,
+            ..Span::default()
+        }
+    }
+
+    fn ident(&mut self, pool: &[&str]) -> Ident {
+        Ident {
+            text: self.pick(pool).to_string(),
+            span: self.span(),
+        }
+    }
+
+    fn vec<T>(&mut self, max: usize, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        let n = self.below(max + 1);
+        (0..n).map(|_| item(self)).collect()
+    }
+
+    fn type_name(&mut self) -> Ident {
+        match self.chance(96) {
+            true => self.ident(TYPES),
+            false => self.ident(ODD_TYPES),
+        }
+    }
+
+    /// A fresh type name; occasionally one that's already taken
+    fn type_def_name(&mut self) -> Ident {
+        let text = match self.chance(90) {
+            true => format!("T{}", self.offset),
+            false => self.pick(&["Point", "Color", "Int"]).to_string(),
+        };
+        Ident {
+            text,
+            span: self.span(),
+        }
+    }
+
+    fn path(&mut self) -> Path {
+        let segments = match self.below(3) {
+            0 => vec![self.ident(VALUES)],
+            _ => {
+                let (ty, variant) = VARIANTS[self.below(VARIANTS.len())];
+                vec![
+                    Ident {
+                        text: ty.to_string(),
+                        span: self.span(),
+                    },
+                    Ident {
+                        text: variant.to_string(),
+                        span: self.span(),
+                    },
+                ]
+            }
+        };
+        Path {
+            segments,
+            span: self.span(),
+        }
+    }
+
+    fn effects(&mut self) -> Option<Vec<Ident>> {
+        self.chance(40).then(|| self.vec(2, |g| g.ident(EFFECTS)))
+    }
+}
+
+/// Random generation for AST nodes. `depth` is the remaining nesting
+/// budget; at zero only leaves are produced.
+trait Arbitrary: Sized {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self;
+}
+
+impl Arbitrary for Lit {
+    fn arbitrary(g: &mut Gen, _depth: u32) -> Self {
+        match g.below(6) {
+            0 => Lit::Int(g.next() as i64 % 100),
+            1 => Lit::Int([i64::MIN, i64::MAX, 0, -1][g.below(4)]),
+            2 => Lit::Float(g.below(10) as f64 / 4.0),
+            3 => Lit::Str(["", "get ", "a"][g.below(3)].to_string()),
+            4 => Lit::Bool(g.chance(50)),
+            _ => Lit::Nil,
+        }
+    }
+}
+
+impl Arbitrary for TypeExpr {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let choice = if depth == 0 { 0 } else { g.below(11) };
+        let d = depth.saturating_sub(1);
+        match choice {
+            0..=4 => TypeExpr::Path(vec![g.type_name()], g.span()),
+            5 => TypeExpr::Arrow {
+                params: g.vec(2, |g| TypeExpr::arbitrary(g, d)),
+                ret: Box::new(TypeExpr::arbitrary(g, d)),
+                effects: g.effects(),
+                span: g.span(),
+            },
+            6 | 7 => TypeExpr::App {
+                base: vec![Ident {
+                    text: "Opt".to_string(),
+                    span: g.span(),
+                }],
+                args: vec![TypeExpr::arbitrary(g, d)],
+                span: g.span(),
+            },
+            8 => TypeExpr::App {
+                base: vec![g.type_name()],
+                args: g.vec(2, |g| TypeExpr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            9 => TypeExpr::Tuple(g.vec(3, |g| TypeExpr::arbitrary(g, d)), g.span()),
+            _ => TypeExpr::Ref(Box::new(TypeExpr::arbitrary(g, d)), g.span()),
+        }
+    }
+}
+
+impl Arbitrary for Pat {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let choice = if depth == 0 { g.below(3) } else { g.below(8) };
+        let d = depth.saturating_sub(1);
+        match choice {
+            0 => Pat::Wildcard(g.span()),
+            1 => Pat::Ident(g.ident(VALUES)),
+            2 => Pat::Literal(Lit::arbitrary(g, d), g.span()),
+            3 => {
+                let lo = g.chance(70).then(|| g.next() as i64 % 20);
+                let hi = g.chance(70).then(|| g.next() as i64 % 20);
+                Pat::Range {
+                    lo,
+                    hi,
+                    inclusive: g.chance(50),
+                    span: g.span(),
+                }
+            }
+            4 => Pat::StrPrefix {
+                prefix: "get ".to_string(),
+                rest: Box::new(Pat::arbitrary(g, d)),
+                span: g.span(),
+            },
+            5 => Pat::Tuple(g.vec(3, |g| Pat::arbitrary(g, d)), g.span()),
+            6 => Pat::Struct {
+                path: g.path(),
+                fields: g.vec(2, |g| PatField {
+                    name: g.ident(FIELDS),
+                    pat: Pat::arbitrary(g, d),
+                    span: g.span(),
+                }),
+                span: g.span(),
+            },
+            _ => Pat::Variant {
+                path: g.path(),
+                fields: g.vec(2, |g| Pat::arbitrary(g, d)),
+                span: g.span(),
+            },
+        }
+    }
+}
+
+impl Arbitrary for Block {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let d = depth.saturating_sub(1);
+        Block {
+            stmts: g.vec(3, |g| Stmt::arbitrary(g, d)),
+            tail: g.chance(80).then(|| Box::new(Expr::arbitrary(g, d))),
+            span: g.span(),
+        }
+    }
+}
+
+impl Arbitrary for Stmt {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let d = depth.saturating_sub(1);
+        match g.below(7) {
+            0 | 1 => Stmt::Let {
+                mutable: g.chance(30),
+                pat: Pat::arbitrary(g, d.min(2)),
+                ty: g.chance(30).then(|| TypeExpr::arbitrary(g, d.min(2))),
+                value: Expr::arbitrary(g, d),
+                span: g.span(),
+            },
+            2 => Stmt::Declare {
+                name: g.ident(VALUES),
+                ty: TypeExpr::arbitrary(g, d.min(2)),
+                span: g.span(),
+            },
+            3 => Stmt::Assign {
+                target: g.ident(VALUES),
+                value: Expr::arbitrary(g, d),
+                span: g.span(),
+            },
+            4 => Stmt::Return {
+                value: g.chance(70).then(|| Expr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            5 if depth > 1 => Stmt::Fn(Box::new(FnDecl::arbitrary(g, d))),
+            _ => Stmt::Expr {
+                expr: Expr::arbitrary(g, d),
+                span: g.span(),
+            },
+        }
+    }
+}
+
+impl Arbitrary for BinOp {
+    fn arbitrary(g: &mut Gen, _depth: u32) -> Self {
+        use BinOp::*;
+        [Or, And, Eq, Ne, Lt, Le, Gt, Ge, Add, Sub, Mul, Div][g.below(12)]
+    }
+}
+
+impl Arbitrary for Expr {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let choice = if depth == 0 { g.below(3) } else { g.below(15) };
+        let d = depth.saturating_sub(1);
+        match choice {
+            0 => Expr::Lit(Lit::arbitrary(g, d), g.span()),
+            1 => Expr::Var(g.ident(VALUES)),
+            2 => Expr::PathExpr(g.path()),
+            3 => Expr::Unary {
+                op: if g.chance(50) { UnOp::Not } else { UnOp::Neg },
+                expr: Box::new(Expr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            4 | 5 => Expr::Call {
+                callee: Box::new(match g.chance(85) {
+                    true => Expr::Var(g.ident(FNS)),
+                    false => Expr::arbitrary(g, d),
+                }),
+                args: g.vec(3, |g| Expr::arbitrary(g, d)),
+                names: Vec::new(),
+                span: g.span(),
+            },
+            6 => Expr::Binary {
+                lhs: Box::new(Expr::arbitrary(g, d)),
+                op: BinOp::arbitrary(g, d),
+                rhs: Box::new(Expr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            7 => Expr::Paren {
+                inner: Box::new(Expr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            8 => Expr::Block(Block::arbitrary(g, d)),
+            9 => Expr::If {
+                cond: Box::new(Expr::arbitrary(g, d)),
+                then_: Block::arbitrary(g, d),
+                else_: g.chance(60).then(|| Box::new(Expr::arbitrary(g, d))),
+                span: g.span(),
+            },
+            10 => Expr::While {
+                cond: Box::new(Expr::arbitrary(g, d)),
+                body: Block::arbitrary(g, d),
+                span: g.span(),
+            },
+            11 => Expr::Match {
+                scrutinee: Box::new(Expr::arbitrary(g, d)),
+                arms: g.vec(4, |g| MatchArm {
+                    pat: Pat::arbitrary(g, d.min(3)),
+                    body: Expr::arbitrary(g, d),
+                    span: g.span(),
+                }),
+                span: g.span(),
+            },
+            12 => Expr::Tuple {
+                elems: g.vec(3, |g| Expr::arbitrary(g, d)),
+                span: g.span(),
+            },
+            13 => Expr::StructExpr {
+                path: g.path(),
+                fields: g.vec(3, |g| FieldInit {
+                    name: g.ident(FIELDS),
+                    value: Expr::arbitrary(g, d),
+                    span: g.span(),
+                }),
+                span: g.span(),
+            },
+            _ => Expr::Borrow(Box::new(Expr::arbitrary(g, d)), g.span()),
+        }
+    }
+}
+
+impl Arbitrary for Param {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        Param {
+            name: g.ident(VALUES),
+            ty: g.chance(80).then(|| TypeExpr::arbitrary(g, depth.min(2))),
+            default: None,
+            variadic: false,
+            span: g.span(),
+        }
+    }
+}
+
+impl Arbitrary for FnDecl {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        FnDecl {
+            name: g.ident(FNS),
+            params: g.vec(3, |g| Param::arbitrary(g, depth)),
+            ret_ty: g.chance(70).then(|| TypeExpr::arbitrary(g, depth.min(2))),
+            effects: g.effects(),
+            public: g.chance(20),
+            bench: false,
+            pure: false,
+            body: Block::arbitrary(g, depth),
+            span: g.span(),
+            attrs: Vec::new(),
+        }
+    }
+}
+
+impl Arbitrary for Item {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let d = depth.saturating_sub(1);
+        let type_params = |g: &mut Gen| g.vec(1, |g| g.ident(&["T"]));
+        match g.below(8) {
+            0 => Item::Let(LetDecl {
+                name: g.ident(VALUES),
+                ty: g.chance(30).then(|| TypeExpr::arbitrary(g, 2)),
+                value: Expr::arbitrary(g, d),
+                span: g.span(),
+                attrs: Vec::new(),
+            }),
+            1 => Item::Const(ConstDecl {
+                name: g.ident(VALUES),
+                ty: TypeExpr::arbitrary(g, 1),
+                value: Expr::arbitrary(g, d.min(3)),
+                span: g.span(),
+                attrs: Vec::new(),
+            }),
+            2 => Item::Struct(StructDef {
+                name: g.type_def_name(),
+                type_params: type_params(g),
+                fields: g.vec(3, |g| Field {
+                    name: g.ident(FIELDS),
+                    ty: TypeExpr::arbitrary(g, 2),
+                    default: None,
+                    span: g.span(),
+                }),
+                linear: g.chance(20),
+                tuple: false,
+                transparent: false,
+                public: g.chance(20),
+                span: g.span(),
+                attrs: Vec::new(),
+            }),
+            3 => Item::Enum(EnumDef {
+                name: g.type_def_name(),
+                type_params: type_params(g),
+                variants: g.vec(3, |g| Variant {
+                    name: g.ident(&["Dot", "Line", "Dot", "Arc"]),
+                    fields: match g.chance(50) {
+                        true => VariantFields::Unit,
+                        false => VariantFields::Tuple(g.vec(2, |g| TypeExpr::arbitrary(g, 2))),
+                    },
+                    discriminant: g.chance(20).then(|| (g.next() as i64 % 4, g.span())),
+                    span: g.span(),
+                }),
+                linear: g.chance(20),
+                public: g.chance(20),
+                span: g.span(),
+                attrs: Vec::new(),
+            }),
+            4 => Item::ExternFn(ExternFnDecl {
+                name: g.ident(FNS),
+                params: g.vec(2, |g| Param::arbitrary(g, 2)),
+                ret_ty: g.chance(70).then(|| TypeExpr::arbitrary(g, 2)),
+                effects: g.effects(),
+                span: g.span(),
+                attrs: Vec::new(),
+            }),
+            _ => Item::Fn(FnDecl::arbitrary(g, depth)),
+        }
+    }
+}
+
+impl Arbitrary for Module {
+    fn arbitrary(g: &mut Gen, depth: u32) -> Self {
+        let mut items = Vec::new();
+        if g.chance(90) {
+            let prelude = parse_str("<prelude>", PRELUDE).expect("prelude parses");
+            g.offset = PRELUDE.len() as u32;
+            items.extend(prelude.items);
+        }
+        items.extend((0..1 + g.below(5)).map(|_| Item::arbitrary(g, depth)));
+        Module {
+            items,
+            span: g.span(),
+        }
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    let value = std::env::var(name).ok()?;
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Check the module generated from `seed` on its own thread, so a panic
+/// or a hang is attributed to that seed.
+fn check_seed(seed: u64) {
+    let module = Module::arbitrary(&mut Gen::new(seed), MAX_DEPTH);
+    let (tx, rx) = mpsc::channel();
+    let handle = std::thread::Builder::new()
+        .name(format!("fuzz-{seed:#x}"))
+        .stack_size(64 * 1024 * 1024)
+        .spawn(move || {
+            let result = TypeChecker::new().check_module(&module);
+            let _ = tx.send(result.err());
+            module
+        })
+        .expect("spawn checker thread");
+
+    match rx.recv_timeout(TIMEOUT) {
+        Ok(err) => {
+            handle.join().expect("checker thread");
+            if let Some(TypeError::InvariantViolation { msg, .. }) = &err {
+                // Reported, not panicked: fine, but worth seeing when fuzzing
+                eprintln!("seed {seed:#x}: invariant violation: {msg}");
+            }
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            let payload = handle.join().expect_err("thread ended without a result");
+            let msg = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("<non-string panic>");
+            panic!("check_module panicked on seed {seed:#x}: {msg}");
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            panic!("check_module did not finish within {TIMEOUT:?} on seed {seed:#x}")
+        }
+    }
+}
+
+#[test]
+fn fuzz_check_module_never_panics_or_hangs() {
+    let cases = env_u64("STRATA_FUZZ_CASES").unwrap_or(DEFAULT_CASES);
+    let seed = env_u64("STRATA_FUZZ_SEED").unwrap_or(DEFAULT_SEED);
+    for i in 0..cases {
+        check_seed(seed.wrapping_add(i.wrapping_mul(0x9e37_79b9_7f4a_7c15)));
+    }
+}
+
+#[test]
+fn generator_is_deterministic_and_varied() {
+    let render = |seed| format!("{:?}", Module::arbitrary(&mut Gen::new(seed), MAX_DEPTH));
+    assert_eq!(render(7), render(7));
+    assert_ne!(render(7), render(8));
+}
@@ -0,0 +1,137 @@
+//! Integration tests for interface files: exporting a checked module's
+//! generalized function schemes and checking another module against them.
+
+use strata_parse::parse_str;
+use strata_types::interface::{Interface, InterfaceError, FORMAT_VERSION};
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: check `src` and export its interface
+fn export(src: &str) -> Interface {
+    let module = parse_str("<upstream>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.interface(&module)
+}
+
+/// Helper: check `src` with `interface` imported
+fn check_against(interface: &Interface, src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<downstream>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.import_interface(interface)?;
+    checker.check_module(&module)
+}
+
+const UPSTREAM: &str = r#"
+    extern fn now(t: &TimeCap) -> Int & {Time};
+
+    pub fn id(x) { x }
+    pub fn stamp(t: TimeCap) -> Int & {Time} { now(&t) }
+    fn helper() -> Int { 0 }
+"#;
+
+#[test]
+fn downstream_uses_imported_schemes() {
+    let interface = export(UPSTREAM);
+    let names: Vec<&str> = interface.fns.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["id", "stamp"]);
+
+    // `id` stays polymorphic
+    let src = "fn main() -> Bool { let n = id(1); id(true) }";
+    assert!(check_against(&interface, src).is_ok());
+    assert!(check_against(&Interface::default(), src).is_err());
+
+    // and `stamp` keeps its effect row
+    let src = "fn main(t: TimeCap) -> Int & {} { stamp(t) }";
+    assert!(check_against(&interface, src).is_err());
+    let src = "fn main(t: TimeCap) -> Int & {Time} { stamp(t) }";
+    assert!(check_against(&interface, src).is_ok());
+}
+
+#[test]
+fn bytes_round_trip_and_are_stable() {
+    let interface = export(UPSTREAM);
+    let bytes = interface.to_bytes();
+    assert_eq!(Interface::from_bytes(&bytes), Ok(interface));
+
+    // Checking other code first changes the checker's variable counters,
+    // but not the interface
+    let module = parse_str("<upstream>", UPSTREAM).unwrap();
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&parse_str("<warmup>", "fn f(a, b) { (a, b) }").unwrap())
+        .unwrap();
+    checker.check_module(&module).unwrap();
+    assert_eq!(checker.interface(&module).to_bytes(), bytes);
+}
+
+#[test]
+fn functions_over_own_adts_are_left_out() {
+    let interface = export(
+        r#"
+        struct Point { x: Int, y: Int }
+        pub fn origin() -> Point { Point { x: 0, y: 0 } }
+        pub fn zero() -> Int { 0 }
+        "#,
+    );
+    let names: Vec<&str> = interface.fns.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, ["zero"]);
+}
+
+#[test]
+fn pub_adts_are_recorded_and_round_trip() {
+    let interface = export(
+        r#"
+        pub struct Pair<A, B> { first: A, second: B }
+        pub enum Color { Red, Green = 5 }
+        struct Hidden { x: Int }
+        "#,
+    );
+    let names: Vec<&str> = interface.adts.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, ["Pair", "Color"]);
+    let bytes = interface.to_bytes();
+    assert_eq!(Interface::from_bytes(&bytes), Ok(interface));
+}
+
+#[test]
+fn only_pub_functions_are_exported() {
+    let interface = export(UPSTREAM);
+    assert_eq!(interface.private, ["helper"]);
+
+    let err = check_against(&interface, "fn main() -> Int { helper() }").unwrap_err();
+    assert_eq!(err.code(), "TYPE-PRIVATE-ITEM", "{err}");
+    assert!(err.to_string().contains("`pub fn helper`"), "{err}");
+
+    // A downstream definition of the same name is its own function
+    let src = "fn helper() -> Int { 1 } fn main() -> Int { helper() }";
+    assert!(check_against(&interface, src).is_ok());
+    // and names nobody defines are still just unknown
+    let err = check_against(&interface, "fn main() -> Int { nowhere() }").unwrap_err();
+    assert_eq!(err.code(), "TYPE-UNKNOWN-VARIABLE", "{err}");
+}
+
+#[test]
+fn malformed_bytes_are_rejected() {
+    let bytes = export(UPSTREAM).to_bytes();
+    assert_eq!(
+        Interface::from_bytes(b"nope"),
+        Err(InterfaceError::BadMagic)
+    );
+    assert_eq!(
+        Interface::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(InterfaceError::Truncated)
+    );
+    let mut newer = bytes.clone();
+    newer[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        Interface::from_bytes(&newer),
+        Err(InterfaceError::UnsupportedVersion(FORMAT_VERSION + 1))
+    );
+    let mut trailing = bytes;
+    trailing.push(0);
+    assert!(matches!(
+        Interface::from_bytes(&trailing),
+        Err(InterfaceError::Invalid(_))
+    ));
+}
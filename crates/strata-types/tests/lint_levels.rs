@@ -0,0 +1,97 @@
+//! Integration tests for item-level lint control.
+//!
+//! `#[allow(...)]`, `#[warn(...)]`, and `#[deny(...)]` set a lint's level
+//! for findings inside their item, over the checker's configuration.
+
+use strata_parse::parse_str;
+use strata_types::attrs::ItemLintLevels;
+use strata_types::{Lint, LintLevel, TypeChecker, Warning};
+
+const SHADOWS: &str = "{ let x = 1; let x = 2; x }";
+
+fn check(src: &str, configure: impl FnOnce(&mut TypeChecker)) -> Result<Vec<Warning>, String> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    configure(&mut checker);
+    checker.check_module(&module).map_err(|e| e.to_string())?;
+    Ok(checker.take_warnings())
+}
+
+fn lints(warnings: &[Warning]) -> Vec<Lint> {
+    warnings.iter().map(|w| w.lint()).collect()
+}
+
+#[test]
+fn warn_enables_an_allowed_lint_in_one_item() {
+    let src = format!("#[warn(shadowed_binding)] fn a() -> Int {SHADOWS} fn b() -> Int {SHADOWS}");
+    let warnings = check(&src, |_| {}).expect("check ok");
+    assert_eq!(lints(&warnings), [Lint::ShadowedBinding]);
+    // The finding is the one in `a`
+    assert!(warnings[0].span().start < src.find("fn b").unwrap() as u32);
+}
+
+#[test]
+fn allow_silences_a_configured_lint_in_one_item() {
+    let src = format!("#[allow(shadowed_binding)] fn a() -> Int {SHADOWS} fn b() -> Int {SHADOWS}");
+    let warnings = check(&src, |c| {
+        c.set_lint_level(Lint::ShadowedBinding, LintLevel::Deny)
+    });
+    assert!(warnings.is_err(), "b is still denied");
+
+    let src = format!("#[allow(shadowed_binding)] fn a() -> Int {SHADOWS}");
+    let warnings = check(&src, |c| {
+        c.set_lint_level(Lint::ShadowedBinding, LintLevel::Deny)
+    });
+    assert_eq!(warnings, Ok(vec![]));
+}
+
+#[test]
+fn deny_turns_a_warning_into_an_error() {
+    let src = "fn pure_work(fs: FsCap, n: Int) -> Int { n }";
+    assert_eq!(
+        lints(&check(src, |_| {}).expect("check ok")),
+        [Lint::UnusedCapability]
+    );
+    let denied = format!("#[deny(unused_capability)] {src}");
+    let err = check(&denied, |_| {}).expect_err("denied");
+    assert!(err.contains("(denied)"), "got: {err}");
+}
+
+#[test]
+fn levels_cover_the_items_own_attributes() {
+    let src = "#[allow(unknown_attribute)] #[inline] fn f() {} #[inline] fn g() {}";
+    let warnings = check(src, |_| {}).expect("check ok");
+    assert_eq!(lints(&warnings), [Lint::UnknownAttribute]);
+    assert_eq!(
+        warnings[0].span().start,
+        src.rfind("#[inline]").unwrap() as u32
+    );
+}
+
+#[test]
+fn deprecated_uses_follow_the_using_item() {
+    let src = r#"
+        #[deprecated] fn old() -> Int { 1 }
+        #[allow(deprecated)] fn quiet() -> Int { old() }
+        fn loud() -> Int { old() }
+    "#;
+    let warnings = check(src, |_| {}).expect("check ok");
+    assert_eq!(lints(&warnings), [Lint::Deprecated]);
+    assert!(warnings[0].span().start > src.find("fn loud").unwrap() as u32);
+}
+
+#[test]
+fn last_attribute_wins_within_an_item() {
+    let module = parse_str(
+        "<test>",
+        "#[deny(deprecated)] #[allow(deprecated, shadowed_binding)] fn f() {}",
+    )
+    .expect("parse failed");
+    let levels = ItemLintLevels::from_module(&module);
+    let inside = module.items[0].attrs()[0].span;
+    assert_eq!(
+        levels.level_at(Lint::Deprecated, inside),
+        Some(LintLevel::Allow)
+    );
+    assert_eq!(levels.level_at(Lint::UnusedCapability, inside), None);
+}
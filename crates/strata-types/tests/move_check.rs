@@ -423,6 +423,87 @@ fn match_tuple_destructure_double_use_error() {
     );
 }
 
+// ============================================================================
+// MATCH BY BORROW: `match &x` reads `x` without consuming it
+// ============================================================================
+
+#[test]
+fn borrowed_match_leaves_scrutinee_alive() {
+    // Arms reading non-capability fields, or borrowing the capability,
+    // don't use up the tuple
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        extern fn use_fs(fs: FsCap) -> () & {Fs};
+        fn f(fs: FsCap) -> () & {Fs} {
+            let pair = (fs, "a.txt");
+            let path = match &pair {
+                (_, p) => p,
+            };
+            let text = match &pair {
+                (f, p) => read_file(&f, p),
+            };
+            match pair {
+                (f, _) => use_fs(f),
+            }
+        }
+    "#,
+    );
+}
+
+#[test]
+fn borrowed_match_bindings_cannot_be_transferred() {
+    let err = check_err(
+        r#"
+        extern fn use_fs(fs: FsCap) -> () & {Fs};
+        fn f(fs: FsCap) -> () & {Fs} {
+            let pair = (fs, 1);
+            match &pair {
+                (f, _) => use_fs(f),
+            }
+        }
+    "#,
+    );
+    assert!(
+        err.contains("cannot transfer capability 'f'") && err.contains("use `&f`"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn borrowed_match_bindings_need_the_scrutinee_held() {
+    // Giving the tuple away inside the arm leaves nothing to borrow from
+    let err = check_err(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn take(p: (FsCap, String)) -> Int { 1 }
+        fn f(fs: FsCap) -> String & {Fs} {
+            let pair = (fs, "a.txt");
+            match &pair {
+                (f, p) => { let n = take(pair); read_file(&f, p) },
+            }
+        }
+    "#,
+    );
+    assert!(
+        err.contains("capability 'pair' has already been used"),
+        "got: {err}"
+    );
+
+    // And a consumed scrutinee can't be borrowed
+    let err = check_err(
+        r#"
+        fn take(p: (FsCap, Int)) -> Int { 1 }
+        fn f(fs: FsCap) -> Int {
+            let pair = (fs, 1);
+            let n = take(pair);
+            match &pair { (_, k) => k }
+        }
+    "#,
+    );
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
 // ============================================================================
 // EXPLOIT PROBE: Generic ADT capability laundering
 // ============================================================================
@@ -755,3 +836,219 @@ fn nested_if_double_use_error() {
         "Expected post-branch error, got: {err}"
     );
 }
+
+// ============================================================================
+// CONDITIONAL CONSUMPTION — caps moved on some paths are "maybe used"
+// ============================================================================
+
+/// Span of the `n`th occurrence of `needle` in `src`
+fn nth_span(src: &str, needle: &str, n: usize) -> strata_ast::span::Span {
+    let start = src
+        .match_indices(needle)
+        .nth(n)
+        .expect("needle not found")
+        .0;
+    strata_ast::span::Span {
+        start: start as u32,
+        end: (start + needle.len()) as u32,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn maybe_used_reports_branch_and_use_spans() {
+    let src = r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn branch_one(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { use_cap(fs) } else { () };
+            use_cap(fs)
+        }
+    "#;
+    let module = parse_str("<test>", src).expect("parse failed");
+    let err = TypeChecker::new()
+        .check_module(&module)
+        .expect_err("expected type error but got OK");
+    assert!(
+        err.to_string().contains("may have already been used"),
+        "got: {err}"
+    );
+    // Skip the extern and function parameter names
+    let moved = nth_span(src, "fs", 2);
+    let used = nth_span(src, "fs", 3);
+    assert_eq!(err.span(), used, "got: {err:?}");
+    let notes: Vec<_> = err.notes().into_iter().map(|(_, span)| span).collect();
+    assert_eq!(notes, [moved], "got: {err:?}");
+}
+
+#[test]
+fn if_without_else_consumes_maybe() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn maybe(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { use_cap(fs); };
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("may have already been used"), "got: {err}");
+}
+
+#[test]
+fn consumed_in_every_branch_is_definite() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn both(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { use_cap(fs) } else { use_cap(fs) };
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("has already been used"), "got: {err}");
+}
+
+#[test]
+fn nested_match_arm_consumption_is_maybe() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        enum Option<T> { Some(T), None }
+        fn nested(a: Option<Bool>, fs: FsCap) -> () & {Fs} {
+            match a {
+                Option::Some(b) => match b {
+                    true => use_cap(fs),
+                    false => (),
+                },
+                Option::None => (),
+            };
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("may have already been used"), "got: {err}");
+}
+
+#[test]
+fn nested_match_consumed_on_all_paths_is_definite() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        enum Option<T> { Some(T), None }
+        fn nested(a: Option<Bool>, fs: FsCap) -> () & {Fs} {
+            match a {
+                Option::Some(b) => match b {
+                    true => use_cap(fs),
+                    false => use_cap(fs),
+                },
+                Option::None => use_cap(fs),
+            };
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("has already been used"), "got: {err}");
+}
+
+#[test]
+fn borrow_after_maybe_consumed_error() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        extern fn peek(fs: &FsCap) -> () & {Fs};
+        fn borrow_bad(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { use_cap(fs) } else { () };
+            peek(&fs)
+        }
+    "#,
+    );
+    assert!(err.contains("may have already been used"), "got: {err}");
+}
+
+#[test]
+fn diverging_branch_does_not_flow_into_join() {
+    // The consuming branch returns, so fs is still alive after the `if`
+    check_ok(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn early(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c {
+                use_cap(fs);
+                return ();
+            } else {
+                ()
+            };
+            use_cap(fs)
+        }
+    "#,
+    );
+}
+
+// ============================================================================
+// DROP — explicitly discarding a capability consumes it
+// ============================================================================
+
+#[test]
+fn drop_consumes_capability() {
+    check_ok(
+        r#"
+        fn discard(fs: FsCap) -> () & {} {
+            drop(fs)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn use_after_drop_error() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn bad(fs: FsCap) -> () & {Fs} {
+            drop(fs);
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("already been used"), "got: {err}");
+}
+
+#[test]
+fn drop_in_one_branch_then_use_error() {
+    let err = check_err(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn bad(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { drop(fs) } else { () };
+            use_cap(fs)
+        }
+    "#,
+    );
+    assert!(err.contains("may have already been used"), "got: {err}");
+}
+
+#[test]
+fn drop_in_loop_error() {
+    let err = check_err(
+        r#"
+        fn bad(fs: FsCap) -> () & {} {
+            let mut i = 0;
+            while i < 2 {
+                drop(fs);
+                i = i + 1;
+            }
+        }
+    "#,
+    );
+    assert!(err.contains("inside loop"), "got: {err}");
+}
+
+#[test]
+fn user_fn_named_drop_replaces_intrinsic() {
+    check_ok(
+        r#"
+        fn drop(x: Int) -> Int { x + 1 }
+        fn f() -> Int { drop(1) }
+    "#,
+    );
+}
@@ -0,0 +1,149 @@
+//! Integration tests for named arguments at call sites.
+//!
+//! `draw(x: 10, y: 20)` passes arguments by parameter name, after any
+//! positional ones and in any order; the checker puts them in parameter
+//! order and reports calls whose names don't fit.
+
+use strata_ast::ast::{Expr, Item, Lit};
+use strata_parse::parse_str;
+use strata_types::named_args::{expand_named_args, NamedArgError};
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(()) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const DECLS: &str = r#"
+    fn draw(x: Int, y: Int, label: String) -> Int { x + y }
+    extern fn greet(c: &ConsoleCap, name: String, greeting: String = "hello", times: Int = 1) -> () & {Console};
+    extern fn log(c: &ConsoleCap, fmt: String, args: ...Show) -> () & {Console};
+"#;
+
+fn with_main(body: &str) -> String {
+    format!("{DECLS}\nfn main(c: ConsoleCap) -> () & {{Console}} {{ {body} }}")
+}
+
+#[test]
+fn named_arguments_may_come_in_any_order() {
+    for body in [
+        r#"let n = draw(y: 2, label: "p", x: 1); ()"#,
+        r#"let n = draw(1, label: "p", y: 2); ()"#,
+        r#"let n = draw(1, 2, "p"); ()"#,
+        r#"greet(&c, times: 2, name: "bob")"#,
+        r#"greet(&c, name: "al")"#,
+        r#"log(&c, fmt: "none")"#,
+        // Nested functions take named arguments too
+        "fn sub(a: Int, b: Int) -> Int { a - b } let n = sub(b: 1, a: 2); ()",
+    ] {
+        check(&with_main(body)).unwrap_or_else(|e| panic!("{body}: {e}"));
+    }
+}
+
+#[test]
+fn names_that_dont_fit_the_parameters_are_rejected() {
+    for (body, message) in [
+        (
+            r#"let n = draw(x: 1, y: 2, colour: "p"); ()"#,
+            "'draw' has no parameter 'colour'",
+        ),
+        (
+            r#"let n = draw(1, x: 1, y: 2, label: "p"); ()"#,
+            "Parameter 'x' of 'draw' is given more than one argument",
+        ),
+        (
+            r#"let n = draw(x: 1, 2, label: "p"); ()"#,
+            "Positional argument after a named one in a call to 'draw'",
+        ),
+        (
+            "let n = draw(y: 2, x: 1); ()",
+            "Call to 'draw' is missing an argument for parameter 'label'",
+        ),
+        (
+            r#"greet(&c, greeting: "hi")"#,
+            "Call to 'greet' is missing an argument for parameter 'name'",
+        ),
+        (
+            r#"log(&c, fmt: "{}", args: 1)"#,
+            "Variadic parameter 'args' of 'log' can't be passed by name",
+        ),
+        (
+            r#"let f = draw; let n = f(x: 1, y: 2, label: "p"); ()"#,
+            "'f' takes no named arguments",
+        ),
+    ] {
+        let err = check_err(&with_main(body));
+        assert_eq!(err.code(), "TYPE-NAMED-ARG", "{body}: {err}");
+        assert!(err.to_string().starts_with(message), "{body}: {err}");
+    }
+}
+
+#[test]
+fn type_errors_name_the_parameter() {
+    let err = check_err(&with_main("let n = draw(y: 2, label: 3, x: 1); ()"));
+    assert_eq!(err.code(), "TYPE-MISMATCH");
+    assert!(
+        matches!(&err, TypeError::ArgMismatch { callee, param, .. }
+            if callee == "draw" && param == "label"),
+        "got: {err:?}"
+    );
+    assert!(
+        err.to_string().contains("parameter 'label' of 'draw'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn skipped_defaults_before_a_named_argument_are_written_in() {
+    let src = with_main(r#"greet(&c, times: 3, name: "bob")"#);
+    let module = parse_str("<test>", &src).expect("parse failed");
+    let expanded = expand_named_args(&module).expect("expand");
+    let Some(Item::Fn(main)) = expanded.items.last() else {
+        panic!("expected main last");
+    };
+    let Some(Expr::Call { args, names, .. }) = main.body.tail.as_deref() else {
+        panic!("expected a call");
+    };
+    let args: Vec<_> = args
+        .iter()
+        .map(|arg| match arg {
+            Expr::Lit(Lit::Str(s), _) => s.clone(),
+            Expr::Lit(Lit::Int(n), _) => n.to_string(),
+            _ => "_".to_string(),
+        })
+        .collect();
+    assert_eq!(args, ["_", "bob", "hello", "3"]);
+    let names: Vec<_> = names
+        .iter()
+        .map(|name| name.as_ref().map(|n| n.text.as_str()))
+        .collect();
+    assert_eq!(names, [None, Some("name"), Some("greeting"), Some("times")]);
+
+    // Expanding again changes nothing
+    let again = expand_named_args(&expanded).expect("expand again");
+    assert_eq!(
+        format!("{:?}", again.items),
+        format!("{:?}", expanded.items)
+    );
+}
+
+#[test]
+fn modules_without_named_arguments_are_borrowed() {
+    let module = parse_str("<test>", &with_main(r#"greet(&c, "al")"#)).unwrap();
+    assert!(matches!(
+        expand_named_args(&module),
+        Ok(std::borrow::Cow::Borrowed(_))
+    ));
+    let module = parse_str("<test>", "fn f() -> () { g(a: 1) }").unwrap();
+    assert!(matches!(
+        expand_named_args(&module),
+        Err(NamedArgError::NotAFunction { .. })
+    ));
+}
@@ -0,0 +1,158 @@
+//! Integration tests for nested function definitions.
+//!
+//! A `fn` inside a block is visible in its own body and in the rest of the
+//! block. It reads the variables in scope where it is declared, cannot
+//! assign them, and cannot capture a capability.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: parse and type-check
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+fn check_ok(src: &str) {
+    check(src).unwrap_or_else(|e| panic!("expected OK, got: {e}"));
+}
+
+#[test]
+fn recursive_helper_reads_enclosing_params() {
+    check_ok(
+        r#"
+        fn sum_to(n: Int) -> Int {
+            fn go(i: Int) -> Int {
+                if i > n { 0 } else { i + go(i + 1) }
+            }
+            go(1)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn helper_is_not_visible_before_its_declaration() {
+    let src = r#"
+        fn f() -> Int {
+            let a = helper();
+            fn helper() -> Int { 1 }
+            a
+        }
+    "#;
+    assert!(matches!(check(src), Err(TypeError::UnknownVariable { name, .. }) if name == "helper"));
+}
+
+#[test]
+fn helper_is_not_visible_outside_its_block() {
+    let src = r#"
+        fn f() -> Int {
+            let a = { fn helper() -> Int { 1 } helper() };
+            a + helper()
+        }
+    "#;
+    assert!(matches!(check(src), Err(TypeError::UnknownVariable { name, .. }) if name == "helper"));
+}
+
+#[test]
+fn helper_cannot_assign_captured_variable() {
+    let src = r#"
+        fn f() -> Int {
+            let mut total = 0;
+            fn bump() -> () { total = total + 1; }
+            bump();
+            total
+        }
+    "#;
+    assert!(
+        matches!(check(src), Err(TypeError::ImmutableAssignment { name, .. }) if name == "total")
+    );
+}
+
+#[test]
+fn helper_cannot_capture_a_capability() {
+    let src = r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn load(fs: FsCap, p: String) -> String & {Fs} {
+            fn read() -> String & {Fs} { read_file(&fs, p) }
+            read()
+        }
+    "#;
+    match check(src) {
+        Err(TypeError::CapabilityCaptured { name, fn_name, .. }) => {
+            assert_eq!(name, "fs");
+            assert_eq!(fn_name, "read");
+        }
+        other => panic!("expected CapabilityCaptured, got {other:?}"),
+    }
+}
+
+#[test]
+fn helper_takes_capability_as_parameter() {
+    check_ok(
+        r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn load(fs: FsCap, p: String) -> String & {Fs} {
+            fn read(f: &FsCap) -> String & {Fs} { read_file(f, p) }
+            read(&fs)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn helper_effects_need_capability_parameter() {
+    let src = r#"
+        extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+        fn load(fs: FsCap, p: String) -> String & {Fs} {
+            fn read(path: String) -> String & {Fs} { "" }
+            read(p)
+        }
+    "#;
+    assert!(
+        matches!(check(src), Err(TypeError::MissingCapability { fn_name, .. }) if fn_name == "read")
+    );
+}
+
+#[test]
+fn helper_in_top_level_let() {
+    check_ok("let k = { fn twice(x: Int) -> Int { x * 2 } twice(21) };");
+}
+
+#[test]
+fn adjacent_helpers_are_mutually_recursive() {
+    check_ok(
+        r#"
+        fn parity(n: Int) -> Bool {
+            fn is_even(k: Int) -> Bool { if k == 0 { true } else { is_odd(k - 1) } }
+            fn is_odd(k: Int) -> Bool { if k == 0 { false } else { is_even(k - 1) } }
+            is_even(n)
+        }
+    "#,
+    );
+}
+
+#[test]
+fn mutually_recursive_helpers_share_inferred_types() {
+    let src = r#"
+        fn f() -> Int {
+            fn ping(k) { pong(k) }
+            fn pong(k) { if true { 1 } else { ping(k) } }
+            ping(true) + ping(1)
+        }
+    "#;
+    assert!(matches!(check(src), Err(TypeError::Mismatch { .. })));
+}
+
+#[test]
+fn statement_between_helpers_splits_the_group() {
+    let src = r#"
+        fn f() -> Int {
+            fn first() -> Int { second() }
+            let x = 1;
+            fn second() -> Int { x }
+            first()
+        }
+    "#;
+    assert!(matches!(check(src), Err(TypeError::UnknownVariable { name, .. }) if name == "second"));
+}
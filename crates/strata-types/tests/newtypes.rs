@@ -0,0 +1,115 @@
+//! Integration tests for tuple structs and `transparent` newtypes.
+//!
+//! `struct UserId(Int);` is a struct whose one field is named `0`: built by
+//! calling `UserId(1)`, taken apart by the pattern `UserId(n)`, and never
+//! interchangeable with the type it wraps, even when `transparent`.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+/// Helper: parse and type-check, expect success
+fn check_ok(src: &str) -> TypeChecker {
+    check(src).unwrap_or_else(|e| panic!("expected OK but got error: {e}"))
+}
+
+/// Helper: parse and type-check, expect failure
+fn check_err(src: &str) -> TypeError {
+    check(src).err().expect("expected type error but got OK")
+}
+
+#[test]
+fn tuple_structs_register_as_single_field_structs() {
+    let checker = check_ok("struct UserId(Int); struct Pair<T>(T, T);");
+    let def = checker.adt_registry().get("UserId").expect("registered");
+    assert!(def.is_tuple_struct());
+    let fields = def.fields().expect("a struct");
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].name, "0");
+    assert_eq!(checker.adt_registry().get("Pair").unwrap().arity(), 1);
+}
+
+#[test]
+fn constructor_calls_and_patterns_are_typed_by_the_fields() {
+    check_ok(
+        r#"
+        struct UserId(Int);
+        struct Pair<T>(T, T);
+        fn next(id: UserId) -> UserId {
+            let UserId(n) = id;
+            UserId(n + 1)
+        }
+        fn swap(p: Pair<String>) -> Pair<String> {
+            match p {
+                Pair(a, b) => Pair(b, a),
+            }
+        }
+        fn main() -> Int { let UserId(n) = next(UserId(1)); n }
+        "#,
+    );
+    let err = check_err("struct UserId(Int); fn main() -> UserId { UserId(true) }");
+    assert_eq!(err.code(), "TYPE-MISMATCH", "{err}");
+    let err = check_err("struct Pair(Int, Int); fn main() -> Pair { Pair(1) }");
+    assert_eq!(err.code(), "TYPE-ARITY", "{err}");
+}
+
+#[test]
+fn newtypes_stay_distinct_from_what_they_wrap() {
+    for src in [
+        "struct UserId(Int); fn f(id: UserId) -> Int { id }",
+        "struct UserId(Int); fn f(id: UserId) -> Int { 0 } fn main() -> Int { f(1) }",
+        "struct UserId(Int); struct OrderId(Int); fn f(id: UserId) -> OrderId { id }",
+        "transparent struct Path(String); fn f(p: Path) -> String { p }",
+        "transparent struct Path(String); fn f() -> Path { \"/tmp\" }",
+    ] {
+        let err = check_err(src);
+        assert_eq!(err.code(), "TYPE-MISMATCH", "{src}: {err}");
+    }
+}
+
+#[test]
+fn tuple_struct_matches_are_checked_for_exhaustiveness() {
+    let err = check_err(
+        r#"
+        struct Flag(Bool);
+        fn f(x: Flag) -> Int { match x { Flag(true) => 1 } }
+        "#,
+    );
+    let TypeError::NonExhaustiveMatch { missing, .. } = err else {
+        panic!("expected NonExhaustiveMatch, got {err}");
+    };
+    assert_eq!(missing, ["Flag(false)"]);
+
+    let err = check_err("struct Flag(Bool); fn f(x: Flag) -> Int { let Flag(true) = x; 1 }");
+    assert_eq!(err.code(), "TYPE-REFUTABLE-PATTERN", "{err}");
+}
+
+#[test]
+fn constructor_name_clashes_with_functions() {
+    let err = check_err("struct UserId(Int); fn UserId(n: Int) -> Int { n }");
+    assert_eq!(err.code(), "TYPE-DUPLICATE-DEFINITION", "{err}");
+    // A braced struct's name is free for a function
+    check_ok("struct Point { x: Int } fn Point() -> Int { 0 }");
+}
+
+#[test]
+fn linear_tuple_struct_fields_stay_affine_when_destructured() {
+    let err = check_err(
+        r#"
+        linear struct Holder(FsCap);
+        extern fn use_fs(fs: FsCap) -> () & {Fs};
+        fn f(h: Holder) -> () & {Fs} {
+            let Holder(fs) = h;
+            use_fs(fs);
+            use_fs(fs)
+        }
+        "#,
+    );
+    assert!(err.to_string().contains("fs"), "{err}");
+}
@@ -0,0 +1,110 @@
+//! Integration tests for partial application.
+//!
+//! `add(1, _)` leaves a call's `_` arguments to be passed later: it is a
+//! function of the holes' parameters, in order, with the callee's return
+//! type and effects. The other arguments are bound when it is made, and
+//! may not hold a capability.
+
+use strata_parse::parse_str;
+use strata_types::infer::ty::Ty;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(_) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const DECLS: &str = r#"
+    fn scale(k: Int, x: Int, label: String) -> Int { k * x }
+    extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+"#;
+
+#[test]
+fn holes_become_the_parameters_of_a_function() {
+    let src = format!(
+        r#"{DECLS}
+        fn apply(f, x: Int) -> Int {{ f(x) }}
+        fn by_two() -> Int {{ apply(scale(2, _, "p"), 21) }}
+        fn labelled() -> Int {{ let f = scale(_, 3, _); f(4, "q") }}
+        fn named() -> Int {{ let f = scale(label: "r", k: _, x: 5); f(2) }}
+        fn partial_of_partial() -> Int {{ let f = scale(_, _, "s"); let g = f(_, 2); g(3) }}
+        let tripled = scale(3, _, "t");
+    "#
+    );
+    let checker = check(&src).unwrap_or_else(|e| panic!("{e}"));
+    let tripled = &checker
+        .typed_items()
+        .iter()
+        .find(|item| item.name == "tripled")
+        .expect("tripled")
+        .ty;
+    assert!(
+        matches!(tripled, Ty::Arrow(params, ret, eff)
+            if params.len() == 1 && **ret == Ty::int() && eff.concrete == 0),
+        "got: {tripled:?}"
+    );
+
+    // Arguments in holes are still checked against their parameters
+    let err = check_err(&format!(
+        "{DECLS}\nfn f() -> Int {{ let g = scale(_, 3, \"a\"); g(true) }}"
+    ));
+    assert_eq!(err.code(), "TYPE-MISMATCH", "{err}");
+}
+
+#[test]
+fn effects_happen_when_the_result_is_called() {
+    // Making the partial application performs nothing
+    check(&format!(
+        r#"{DECLS}
+        fn reader() -> () & {{}} {{ let read = read_file(_, "a.txt"); () }}
+        fn load(fs: FsCap) -> String & {{Fs}} {{ let read = read_file(_, "a.txt"); read(&fs) }}
+    "#
+    ))
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    let err = check_err(&format!(
+        r#"{DECLS}
+        fn load(fs: FsCap) -> String & {{}} {{ let read = read_file(_, "a.txt"); read(&fs) }}
+    "#
+    ));
+    assert_eq!(err.code(), "EFFECT-UNDECLARED", "{err}");
+}
+
+#[test]
+fn bound_arguments_cannot_hold_a_capability() {
+    for body in [
+        r#"let read = read_file(&fs, _); read("a.txt")"#,
+        r#"fn take(c: FsCap, n: Int) -> Int { n } let f = take(fs, _); let n = f(1); "x""#,
+    ] {
+        let err = check_err(&format!(
+            "{DECLS}\nfn load(fs: FsCap) -> String & {{Fs}} {{ {body} }}"
+        ));
+        assert_eq!(err.code(), "CAP-CAPTURED", "{body}: {err}");
+        assert!(
+            matches!(&err, TypeError::PartialCapability { .. }),
+            "{body}: {err:?}"
+        );
+        assert!(err.to_string().contains("leave it as `_`"), "{err}");
+    }
+}
+
+#[test]
+fn builtins_and_constructors_cannot_be_applied_partially() {
+    for body in [
+        "let f = show(_); 1",
+        "let f = Option::Some(_); 1",
+        "let f = Wrap(_); 1",
+    ] {
+        let err = check_err(&format!("struct Wrap(Int);\nfn f() -> Int {{ {body} }}"));
+        assert_eq!(err.code(), "TYPE-PARTIAL", "{body}: {err}");
+    }
+}
@@ -0,0 +1,51 @@
+//! Integration tests for the pipe operator.
+//!
+//! `x |> f |> g(2)` is the call `g(f(x), 2)`, and checks like one.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new().check_module(&module)
+}
+
+const DECLS: &str = r#"
+    fn double(x: Int) -> Int { x * 2 }
+    fn clamp(x: Int, lo: Int, hi: Int) -> Int { if x < lo { lo } else { if x > hi { hi } else { x } } }
+    extern fn read_file(fs: &FsCap, path: String) -> String & {Fs};
+"#;
+
+#[test]
+fn pipelines_check_like_the_calls_they_stand_for() {
+    for body in [
+        "3 |> double |> clamp(0, 10)",
+        "3 |> clamp(0, _, 10) |> double",
+        "let f = 3 |> clamp(_, _, 10); f(1)",
+    ] {
+        check(&format!("{DECLS}\nfn f() -> Int {{ {body} }}"))
+            .unwrap_or_else(|e| panic!("{body}: {e}"));
+    }
+
+    // Errors are the call form's
+    for (piped, called) in [
+        ("true |> double", "double(true)"),
+        ("3 |> clamp(0)", "clamp(3, 0)"),
+    ] {
+        let err = |body: &str| {
+            check(&format!("{DECLS}\nfn f() -> Int {{ {body} }}"))
+                .expect_err(body)
+                .code()
+        };
+        assert_eq!(err(piped), err(called), "{piped}");
+    }
+}
+
+#[test]
+fn piped_effects_need_declaring() {
+    let src = format!(
+        "{DECLS}\nfn load(fs: FsCap) -> String & {{}} {{ \"a.txt\" |> read_file(&fs, _) }}"
+    );
+    let err = check(&src).expect_err("undeclared Fs");
+    assert_eq!(err.code(), "EFFECT-UNDECLARED", "{err}");
+}
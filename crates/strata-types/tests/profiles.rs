@@ -0,0 +1,156 @@
+//! Integration tests for checking profiles: the same program under the
+//! strict, default and permissive limits.
+
+use strata_parse::parse_str;
+use strata_types::{Profile, TypeChecker, TypeError};
+
+fn check(profile: Profile, src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    TypeChecker::new_with_profile(profile).check_module(&module)
+}
+
+/// `fn main() -> Int { let t = (0, 1, ...); 0 }` with an `n`-tuple
+fn tuple_of(n: usize) -> String {
+    let elems: Vec<String> = (0..n).map(|i| i.to_string()).collect();
+    format!("fn main() -> Int {{ let t = ({}); 0 }}", elems.join(", "))
+}
+
+#[test]
+fn default_profile_is_what_new_checks_under() {
+    let module = parse_str("<test>", &tuple_of(65)).expect("parse failed");
+    let plain = TypeChecker::new().check_module(&module);
+    let default = check(Profile::Default, &tuple_of(65));
+    assert_eq!(plain.map_err(|e| e.code()), default.map_err(|e| e.code()));
+}
+
+#[test]
+fn tuple_arity_cap_follows_the_profile() {
+    let cases = [
+        (Profile::Strict, 16, 17),
+        (Profile::Default, 64, 65),
+        (Profile::Permissive, 1024, 1025),
+    ];
+    for (profile, widest, too_wide) in cases {
+        if let Err(e) = check(profile, &tuple_of(widest)) {
+            panic!("{profile}: a {widest}-tuple should check, got: {e}");
+        }
+        let err = check(profile, &tuple_of(too_wide)).expect_err("tuple too wide");
+        assert_eq!(err.code(), "TYPE-TUPLE-ARITY", "{profile}: {err}");
+    }
+}
+
+#[test]
+fn widest_tuples_destructure_and_match_exhaustively() {
+    // A row as wide as the permissive profile allows, taken apart by a
+    // tuple pattern and matched on its first and last fields
+    let n = 1024;
+    let names: Vec<String> = (0..n).map(|i| format!("x{i}")).collect();
+    let blanks = vec!["_"; n - 2].join(", ");
+    let src = format!(
+        "fn f(row: ({ty})) -> Int {{ let ({names}) = row; match row {{ \
+         (true, {blanks}, false) => 1, (_, {blanks}, _) => 0, }} }}",
+        ty = vec!["Bool"; n].join(", "),
+        names = names.join(", "),
+    );
+    let partial = src.replace(&format!("(_, {blanks}, _) => 0, "), "");
+
+    // Finding the missing row recurses once per column, deeper than a test
+    // thread's default stack allows in debug builds
+    let checked = std::thread::Builder::new()
+        .stack_size(16 * 1024 * 1024)
+        .spawn(move || {
+            (
+                check(Profile::Permissive, &src),
+                check(Profile::Permissive, &partial),
+            )
+        })
+        .expect("spawn checker thread")
+        .join()
+        .expect("checker thread panicked");
+    if let Err(e) = checked.0 {
+        panic!("a {n}-wide row should check, got: {e}");
+    }
+    let err = checked.1.expect_err("missing rows");
+    assert_eq!(err.code(), "TYPE-NON-EXHAUSTIVE", "{err}");
+}
+
+#[test]
+fn inference_depth_follows_the_profile() {
+    // 100 nested negations: past the strict limit, within the default one
+    let src = format!("fn main() -> Bool {{ {}true }}", "!".repeat(100));
+    let err = check(Profile::Strict, &src).expect_err("too deep for strict");
+    assert_eq!(err.code(), "TYPE-DEPTH-LIMIT");
+    assert!(check(Profile::Default, &src).is_ok());
+}
+
+#[test]
+fn exhaustiveness_budget_follows_the_profile() {
+    // A 3000-arm match over a 3000-row matrix: past the strict budget only
+    let arms: Vec<String> = (0..3000).map(|i| format!("{i} => {i},")).collect();
+    let src = format!(
+        "fn f(n: Int) -> Int {{ match n {{ {} _ => 0, }} }}",
+        arms.join(" ")
+    );
+    let err = check(Profile::Strict, &src).expect_err("too big for strict");
+    assert_eq!(err.code(), "TYPE-EXHAUSTIVENESS-LIMIT", "{err}");
+    assert!(check(Profile::Permissive, &src).is_ok());
+}
+
+#[test]
+fn solve_budgets_follow_the_profile() {
+    // Two constraints a `let`: past the strict constraint budget only
+    let lets: Vec<String> = (1..10_500)
+        .map(|i| format!("let x{i} = x{} + 1;", i - 1))
+        .collect();
+    let src = format!(
+        "fn main() -> Int {{ let x0 = 0; {} x10499 }}",
+        lets.join(" ")
+    );
+    let err = check(Profile::Strict, &src).expect_err("too many for strict");
+    assert_eq!(err.code(), "TYPE-SOLVE-LIMIT", "{err}");
+    assert!(err.to_string().contains("20000 constraints"), "{err}");
+    assert!(check(Profile::Default, &src).is_ok());
+
+    // A type doubling with each `let`: comparing it unifies 2^17 tuples,
+    // from a handful of constraints
+    let lets: Vec<String> = (1..=17)
+        .map(|i| format!("let t{i} = (t{0}, t{0});", i - 1))
+        .collect();
+    let src = format!(
+        "fn main() -> Bool {{ let t0 = 1; {} t17 == t17 }}",
+        lets.join(" ")
+    );
+    let err = check(Profile::Strict, &src).expect_err("too slow for strict");
+    assert_eq!(err.code(), "TYPE-SOLVE-LIMIT", "{err}");
+    assert!(err.to_string().contains("solver steps"), "{err}");
+    assert!(check(Profile::Default, &src).is_ok());
+}
+
+#[test]
+fn solve_stats_are_kept_per_item() {
+    let src = "const N: Int = 2;
+               fn double(x: Int) -> Int { x * N }
+               extern fn now(t: &TimeCap) -> Int & {Time};
+               fn main() -> Int { double(1) + double(2) }";
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).expect("checks");
+    let names: Vec<_> = checker
+        .solve_stats()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    assert_eq!(names, ["N", "double", "main"]);
+    let (_, main) = checker.solve_stats()[2];
+    assert!(
+        main.constraints > 0 && main.steps >= main.constraints,
+        "{main:?}"
+    );
+
+    // The item that failed is last
+    let module = parse_str("<test>", "fn f() -> Int { 1 } fn g() -> Int { true }").unwrap();
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).expect_err("g is ill-typed");
+    let last = checker.solve_stats().last().map(|(name, _)| name.as_str());
+    assert_eq!(last, Some("g"));
+}
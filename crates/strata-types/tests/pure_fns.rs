@@ -0,0 +1,108 @@
+//! Integration tests for `pure fn`.
+//!
+//! A `pure fn` asserts that it performs no effects. The checker verifies
+//! it against every function's final effect row, names the calls an
+//! effect comes through when it finds one, and records the function as
+//! `& {}` when it finds none.
+
+use strata_parse::parse_str;
+use strata_types::infer::Ty;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+fn check_err(src: &str) -> TypeError {
+    match check(src) {
+        Ok(_) => panic!("expected a type error"),
+        Err(e) => e,
+    }
+}
+
+const WRITE: &str = r#"
+    extern fn write_file(c: &FsCap, path: String) -> () & {Fs};
+    fn save(c: FsCap) -> () & {Fs} { write_file(&c, "out") }
+"#;
+
+#[test]
+fn pure_functions_are_recorded_without_effects() {
+    let checker = check(
+        "pure fn double(n: Int) -> Int { n * 2 }\n\
+         pure fn quad(n: Int) -> Int { double(double(n)) }\n\
+         fn uses(n: Int) -> Int { quad(n) }",
+    )
+    .unwrap();
+    for name in ["double", "quad"] {
+        let item = checker
+            .typed_items()
+            .iter()
+            .find(|item| item.name == name)
+            .unwrap();
+        assert!(
+            matches!(&item.ty, Ty::Arrow(_, _, eff) if eff.is_empty()),
+            "{name}: {}",
+            item.ty
+        );
+    }
+}
+
+#[test]
+fn effects_are_reported_with_their_call_chain() {
+    let src = format!(
+        "{WRITE}\nfn helper(c: FsCap) -> () {{ save(c) }}\n\
+         pure fn report(c: FsCap) -> Int {{ helper(c); 1 }}"
+    );
+    let err = check_err(&src);
+    assert!(
+        matches!(&err, TypeError::ImpureFunction { fn_name, chain, .. }
+            if fn_name == "report" && chain == &["helper", "save", "write_file"]),
+        "got: {err:?}"
+    );
+    assert_eq!(err.code(), "EFFECT-IMPURE");
+    // At the first call
+    assert_eq!(err.span().start, src.rfind("helper(c)").unwrap() as u32);
+    let message = err.to_string();
+    assert!(message.contains("performs {Fs}"), "{message}");
+    assert!(
+        message.ends_with(": it calls 'helper', which calls 'save', which calls 'write_file'"),
+        "{message}"
+    );
+
+    // Through a function defined after the pure one
+    let src = format!(
+        "pure fn report(c: FsCap) -> Int {{ later(c) }}\n\
+         fn later(c: FsCap) -> Int {{ save(c); 1 }}\n{WRITE}"
+    );
+    assert!(
+        matches!(check_err(&src), TypeError::ImpureFunction { chain, .. }
+            if chain == ["later", "save", "write_file"]),
+    );
+
+    // Declaring the effects doesn't make them pure
+    let src = format!("{WRITE}\npure fn report(c: FsCap) -> () & {{Fs}} {{ save(c) }}");
+    assert!(matches!(
+        check_err(&src),
+        TypeError::ImpureFunction { chain, .. } if chain == ["save", "write_file"]
+    ));
+}
+
+#[test]
+fn effects_of_function_parameters_are_impure() {
+    let err = check_err("pure fn apply(f, n: Int) -> Int { f(n) }");
+    assert!(
+        matches!(&err, TypeError::ImpureFunction { chain, .. } if chain.is_empty()),
+        "got: {err:?}"
+    );
+    assert!(
+        err.to_string()
+            .contains("effects its function parameters do"),
+        "{err}"
+    );
+
+    // A parameter of function type has none
+    check("pure fn apply(f: fn(Int) -> Int, n: Int) -> Int { f(n) }").unwrap();
+}
@@ -0,0 +1,187 @@
+//! Integration tests for anonymous records: structural typing, field
+//! access over open rows, record patterns, and their exhaustiveness.
+
+use strata_parse::parse_str;
+use strata_types::interface::Interface;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+/// Helper: parse and type-check, expect success
+fn check_ok(src: &str) -> TypeChecker {
+    check(src).unwrap_or_else(|e| panic!("expected OK but got error: {e}"))
+}
+
+/// Helper: parse and type-check, expect failure
+fn check_err(src: &str) -> TypeError {
+    check(src).err().expect("expected type error but got OK")
+}
+
+#[test]
+fn records_are_typed_by_their_fields_in_any_order() {
+    check_ok(
+        r#"
+        fn norm(p: {x: Int, y: Int}) -> Int { p.x * p.x + p.y * p.y }
+        fn main() -> Int { norm({ y: 4, x: 3 }) }
+        "#,
+    );
+}
+
+#[test]
+fn field_access_is_polymorphic_over_wider_records() {
+    let checker = check_ok(
+        r#"
+        fn get_x(r) { r.x }
+        fn main() -> Int {
+            let a = get_x({ x: 1 });
+            let b = get_x({ x: 2, label: "b" });
+            let c = get_x({ x: 3, y: 4, z: true });
+            a + b + c
+        }
+        "#,
+    );
+    let get_x = checker
+        .typed_items()
+        .iter()
+        .find(|i| i.name == "get_x")
+        .expect("get_x recorded");
+    // An open row: any record with at least an `x`
+    let ty = get_x.ty.to_string();
+    assert!(ty.starts_with("{x: t") && ty.contains(", ..} -> t"), "{ty}");
+}
+
+#[test]
+fn record_arguments_must_match_exactly() {
+    let narrower =
+        check_err("fn f(p: {x: Int, y: Int}) -> Int { p.x } fn main() -> Int { f({ x: 1 }) }");
+    assert_eq!(narrower.code(), "TYPE-NO-SUCH-FIELD", "{narrower}");
+    assert!(narrower.to_string().contains("No field 'y'"), "{narrower}");
+
+    let wider =
+        check_err("fn f(p: {x: Int}) -> Int { p.x } fn main() -> Int { f({ x: 1, y: 2 }) }");
+    assert_eq!(wider.code(), "TYPE-NO-SUCH-FIELD", "{wider}");
+
+    let different = check_err("fn f(p: {x: Int}) -> Int { p.x } fn main() -> Int { f({ y: 1 }) }");
+    assert_eq!(different.code(), "TYPE-MISMATCH", "{different}");
+
+    let field_ty =
+        check_err("fn f(p: {x: Int}) -> Int { p.x } fn main() -> Int { f({ x: true }) }");
+    assert_eq!(field_ty.code(), "TYPE-MISMATCH", "{field_ty}");
+}
+
+#[test]
+fn reading_a_missing_field_names_it() {
+    let err = check_err("fn main() -> Int { let p = { x: 1 }; p.y }");
+    assert_eq!(err.code(), "TYPE-NO-SUCH-FIELD", "{err}");
+    assert!(err.to_string().contains("{x: Int}"), "{err}");
+
+    let err = check_err("fn main() -> Int { let n = 1; n.x }");
+    assert_eq!(err.code(), "TYPE-MISMATCH", "{err}");
+}
+
+#[test]
+fn two_open_rows_unify_into_one() {
+    // `r` needs both `x` and `y`, learned from two separate accesses
+    check_ok(
+        r#"
+        fn sum(r) { r.x + r.y }
+        fn main() -> Int { sum({ x: 1, y: 2, z: "extra" }) }
+        "#,
+    );
+    let err = check_err(
+        r#"
+        fn sum(r) { r.x + r.y }
+        fn main() -> Int { sum({ x: 1 }) }
+        "#,
+    );
+    assert_eq!(err.code(), "TYPE-NO-SUCH-FIELD", "{err}");
+}
+
+#[test]
+fn duplicate_fields_are_rejected() {
+    let expr = check_err("fn main() -> Int { let p = { x: 1, x: 2 }; 0 }");
+    assert_eq!(expr.code(), "TYPE-DUPLICATE-FIELD");
+    let ty = check_err("fn f(p: {x: Int, x: Bool}) -> Int { 0 }");
+    assert_eq!(ty.code(), "TYPE-DUPLICATE-FIELD");
+    let pat = check_err("fn f(p: {x: Int}) -> Int { let { x, x } = p; x }");
+    assert_eq!(pat.code(), "TYPE-DUPLICATE-FIELD");
+}
+
+#[test]
+fn record_patterns_name_every_field_unless_open() {
+    check_ok("fn f(p: {x: Int, y: Int}) -> Int { let { x, y } = p; x + y }");
+    check_ok("fn f(p: {x: Int, y: Int}) -> Int { let { y: b, .. } = p; b }");
+    let err = check_err("fn f(p: {x: Int, y: Int}) -> Int { let { x } = p; x }");
+    assert_eq!(err.code(), "TYPE-NO-SUCH-FIELD", "{err}");
+}
+
+#[test]
+fn record_matches_are_checked_for_exhaustiveness() {
+    check_ok(
+        r#"
+        fn f(p: {flag: Bool, n: Int}) -> Int {
+            match p {
+                { flag: true, n } => n,
+                { flag: false, .. } => 0,
+            }
+        }
+        "#,
+    );
+    let err = check_err(
+        r#"
+        fn f(p: {flag: Bool, n: Int}) -> Int {
+            match p {
+                { flag: true, n } => n,
+            }
+        }
+        "#,
+    );
+    let TypeError::NonExhaustiveMatch { missing, .. } = err else {
+        panic!("expected NonExhaustiveMatch, got {err}");
+    };
+    assert_eq!(missing, ["{ flag: false, n: _ }"]);
+
+    let err = check_err(
+        r#"
+        fn f(p: {flag: Bool, n: Int}) -> Int {
+            match p {
+                { n, .. } => n,
+                { flag: true, .. } => 1,
+            }
+        }
+        "#,
+    );
+    assert_eq!(err.code(), "TYPE-UNREACHABLE-PATTERN", "{err}");
+}
+
+#[test]
+fn records_compare_for_equality_but_not_order() {
+    check_ok("fn main() -> Bool { { x: 1, y: \"a\" } == { y: \"a\", x: 1 } }");
+    let err = check_err("fn main() -> Bool { { x: 1 } < { x: 2 } }");
+    assert_eq!(err.code(), "TYPE-NOT-COMPARABLE", "{err}");
+}
+
+#[test]
+fn open_record_schemes_round_trip_through_interfaces() {
+    let upstream = check_ok("pub fn get_x(r) { r.x }");
+    let module = parse_str("<upstream>", "pub fn get_x(r) { r.x }").unwrap();
+    let interface = upstream.interface(&module);
+    let bytes = interface.to_bytes();
+    assert_eq!(Interface::from_bytes(&bytes).unwrap(), interface);
+
+    let downstream = parse_str(
+        "<downstream>",
+        "fn main() -> Int { get_x({ x: 1, y: true }) }",
+    )
+    .unwrap();
+    let mut checker = TypeChecker::new();
+    checker.import_interface(&interface).unwrap();
+    checker
+        .check_module(&downstream)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+}
@@ -0,0 +1,239 @@
+//! Integration tests for name resolution: what each name use in a parsed
+//! module refers to, before any type checking.
+
+use strata_ast::span::FileId;
+use strata_ast::span::Span;
+use strata_parse::parse_str;
+use strata_types::resolve::{resolve, scope_at, DefId, Resolution};
+use strata_types::TypeChecker;
+
+/// Helper: parse and resolve `src`
+fn resolved(src: &str) -> Resolution {
+    resolve(&parse_str("<test>", src).expect("parse failed"))
+}
+
+/// Helper: the definitions of the uses of `name`, in source order
+fn uses_of<'a>(res: &'a Resolution, name: &str) -> Vec<&'a DefId> {
+    let mut uses: Vec<(Span, &DefId)> = res
+        .iter()
+        .filter(|(_, n, _)| *n == name)
+        .map(|(span, _, def)| (span, def))
+        .collect();
+    uses.sort_by_key(|(span, _)| span.start);
+    uses.into_iter().map(|(_, def)| def).collect()
+}
+
+/// Helper: the span of the `nth` occurrence of `needle` in `src`
+fn span_of(src: &str, needle: &str, nth: usize) -> Span {
+    let start = src
+        .match_indices(needle)
+        .nth(nth)
+        .unwrap_or_else(|| panic!("no occurrence {nth} of {needle}"))
+        .0;
+    Span {
+        start: start as u32,
+        end: (start + needle.len()) as u32,
+        ..Span::default()
+    }
+}
+
+/// Helper: the first `len` bytes of `span`
+fn narrow(span: Span, len: u32) -> Span {
+    Span {
+        end: span.start + len,
+        ..span
+    }
+}
+
+#[test]
+fn locals_shadow_module_definitions() {
+    let src = r#"
+        fn helper() -> Int { 1 }
+        fn main() -> Int {
+            let a = helper();
+            let helper = 2;
+            a + helper
+        }
+    "#;
+    let res = resolved(src);
+    assert_eq!(
+        uses_of(&res, "helper"),
+        [
+            &DefId::Fn(span_of(src, "helper", 0)),
+            &DefId::Local(span_of(src, "helper", 2)),
+        ]
+    );
+    assert_eq!(
+        uses_of(&res, "a"),
+        [&DefId::Local(narrow(span_of(src, "a =", 0), 1))]
+    );
+}
+
+#[test]
+fn paths_resolve_to_constructors_and_types() {
+    let src = r#"
+        enum Shape { Dot, Line(Int) }
+        struct UserId(Int);
+        struct Point { x: Int }
+        fn f(s: Shape, p: Point) -> UserId {
+            match s {
+                Shape::Dot => UserId(0),
+                Shape::Line(n) => UserId(n),
+            }
+        }
+        fn g() -> Option<Int> { Option::None }
+    "#;
+    let res = resolved(src);
+    let variant = |adt: &str, v: &str| DefId::Ctor {
+        adt: adt.to_string(),
+        variant: Some(v.to_string()),
+    };
+    assert_eq!(uses_of(&res, "Shape::Dot"), [&variant("Shape", "Dot")]);
+    assert_eq!(uses_of(&res, "Shape::Line"), [&variant("Shape", "Line")]);
+    assert_eq!(uses_of(&res, "Option::None"), [&variant("Option", "None")]);
+
+    // The tuple struct is both a type and a constructor
+    let user_id = uses_of(&res, "UserId");
+    assert_eq!(user_id[0], &DefId::Type("UserId".to_string()));
+    let ctor = DefId::Ctor {
+        adt: "UserId".to_string(),
+        variant: None,
+    };
+    assert_eq!(&user_id[1..], [&ctor, &ctor]);
+
+    assert_eq!(uses_of(&res, "Point"), [&DefId::Type("Point".to_string())]);
+    assert_eq!(
+        uses_of(&res, "Option"),
+        [&DefId::Type("Option".to_string())]
+    );
+}
+
+#[test]
+fn nested_fn_groups_see_each_other() {
+    let src = r#"
+        fn main() -> Bool {
+            fn even(n: Int) -> Bool { if n == 0 { true } else { odd(n - 1) } }
+            fn odd(n: Int) -> Bool { if n == 0 { false } else { even(n - 1) } }
+            even(4)
+        }
+    "#;
+    let res = resolved(src);
+    let even = DefId::Fn(narrow(span_of(src, "even(n: Int)", 0), 4));
+    let odd = DefId::Fn(narrow(span_of(src, "odd(n: Int)", 0), 3));
+    assert_eq!(uses_of(&res, "odd"), [&odd]);
+    assert_eq!(uses_of(&res, "even"), [&even, &even]);
+    // Each `n` is its own function's parameter
+    let n = uses_of(&res, "n");
+    assert_eq!(n.len(), 4);
+    assert_ne!(n[0], n[2]);
+}
+
+#[test]
+fn const_patterns_match_rather_than_bind() {
+    let src = r#"
+        const LIMIT: Int = 10;
+        fn f(x: Int) -> Int { match x { LIMIT => 0, other => other } }
+    "#;
+    let res = resolved(src);
+    assert_eq!(
+        uses_of(&res, "LIMIT"),
+        [&DefId::Const(span_of(src, "LIMIT", 0))]
+    );
+    assert_eq!(
+        uses_of(&res, "other"),
+        [&DefId::Local(span_of(src, "other", 0))]
+    );
+}
+
+#[test]
+fn top_level_lets_are_visible_after_their_definition() {
+    let src = "let base = 1; let next = base + 1; fn get() -> Int { next }";
+    let res = resolved(src);
+    assert_eq!(
+        uses_of(&res, "base"),
+        [&DefId::Global(span_of(src, "base", 0))]
+    );
+    assert_eq!(
+        uses_of(&res, "next"),
+        [&DefId::Global(span_of(src, "next", 0))]
+    );
+    assert!(uses_of(&resolved("let a = b; let b = 1;"), "b").is_empty());
+}
+
+#[test]
+fn builtins_and_unknown_names_are_unresolved() {
+    let src = r#"
+        enum Color { Red }
+        fn main() -> Int { print("hi"); discriminant(Color::Red) + missing }
+    "#;
+    let res = resolved(src);
+    for name in ["print", "discriminant", "missing"] {
+        assert!(uses_of(&res, name).is_empty(), "{name}");
+    }
+    assert!(uses_of(&resolved("enum C { A } fn f() -> Int { C::B }"), "C::B").is_empty());
+}
+
+#[test]
+fn checker_exposes_the_resolution() {
+    let src = "struct Flag(Bool); fn f(x: Flag) -> Bool { let Flag(b) = x; b }";
+    let module = parse_str("<test>", src).unwrap();
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    assert_eq!(checker.resolution(), &resolve(&module));
+    assert_eq!(
+        uses_of(checker.resolution(), "b"),
+        [&DefId::Local(narrow(span_of(src, "b)", 0), 1))]
+    );
+}
+
+#[test]
+fn imported_variants_resolve_to_their_enum() {
+    let src = r#"
+        use Option::*;
+        fn f(o: Option<Int>) -> Option<Int> { match o { None => Some(0), Some(n) => o } }
+    "#;
+    let res = resolved(src);
+    let variant = |v: &str| DefId::Ctor {
+        adt: "Option".to_string(),
+        variant: Some(v.to_string()),
+    };
+    assert_eq!(uses_of(&res, "None"), [&variant("None")]);
+    assert_eq!(uses_of(&res, "Some"), [&variant("Some"), &variant("Some")]);
+    assert!(uses_of(&res, "n").is_empty());
+}
+
+/// Names in scope at the `|` in `src`
+fn scope(src: &str) -> Vec<String> {
+    let offset = src.find('|').expect("no cursor");
+    let m = parse_str("<test>", &src.replacen('|', "", 1)).expect("parse failed");
+    scope_at(&m, FileId::default(), offset as u32)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[test]
+fn scope_at_lists_locals_innermost_first_then_module_names() {
+    let src = "enum E { A, B }\n\
+               use E::*;\n\
+               fn f(x: Int) -> Int {\n\
+                   let y = x;\n\
+                   match y { n => { let z = n; | } }\n\
+               }\n\
+               let later = 1;";
+    assert_eq!(
+        scope(src),
+        vec!["z", "n", "y", "x", "E", "Option", "f", "A", "B"]
+    );
+}
+
+#[test]
+fn scope_at_excludes_bindings_after_the_offset() {
+    let src = "fn f() -> Int { let a = 1; let b = |a; let c = 3; c }";
+    assert_eq!(scope(src), vec!["a", "Option", "f"]);
+    // A local shadows the global of the same name
+    let src = "fn f() -> Int { let f = 1; | f }";
+    assert_eq!(scope(src), vec!["f", "Option"]);
+}
@@ -0,0 +1,158 @@
+//! Integration tests for the shadowed-binding lint.
+//!
+//! Shadowing is legal; the lint is opt-in and reports every hidden
+//! definition span alongside the shadowing binding.
+
+use strata_ast::span::Span;
+use strata_parse::parse_str;
+use strata_types::{Lint, LintLevel, TypeChecker, Warning};
+
+/// Helper: parse and type-check with the lint at `level`, return warnings
+fn check_warnings(src: &str, level: LintLevel) -> Vec<Warning> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::ShadowedBinding, level);
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.take_warnings()
+}
+
+/// Helper: parse and type-check with the lint denied, expect failure
+fn check_denied(src: &str) -> String {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::ShadowedBinding, LintLevel::Deny);
+    let err = checker
+        .check_module(&module)
+        .expect_err("expected type error but got OK");
+    format!("{err}")
+}
+
+/// Span of the `n`th occurrence of `needle` in `src`
+fn nth_span(src: &str, needle: &str, n: usize) -> Span {
+    let start = src
+        .match_indices(needle)
+        .nth(n)
+        .expect("needle not found")
+        .0;
+    Span {
+        start: start as u32,
+        end: (start + needle.len()) as u32,
+        ..Span::default()
+    }
+}
+
+// ============================================================================
+// LEVELS
+// ============================================================================
+
+#[test]
+fn allowed_by_default() {
+    let module =
+        parse_str("<test>", "fn f() -> Int { let x = 1; { let x = 2; x } }").expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module).expect("check failed");
+    assert!(checker.warnings().is_empty());
+}
+
+#[test]
+fn warn_level_collects_warning() {
+    let warnings = check_warnings(
+        "fn f() -> Int { let x = 1; { let x = 2; x } }",
+        LintLevel::Warn,
+    );
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].lint(), Lint::ShadowedBinding);
+}
+
+#[test]
+fn deny_level_is_error() {
+    let err = check_denied("fn f() -> Int { let x = 1; let x = 2; x }");
+    assert!(err.contains("binding 'x'"), "got: {err}");
+    assert!(err.contains("shadowed_binding"), "got: {err}");
+}
+
+// ============================================================================
+// SPAN CHAINS
+// ============================================================================
+
+#[test]
+fn reports_original_and_shadow_spans() {
+    let src = "fn f() -> Int { let x = 1; let x = 2; x }";
+    let warnings = check_warnings(src, LintLevel::Warn);
+    assert_eq!(
+        warnings,
+        vec![Warning::ShadowedBinding {
+            name: "x".to_string(),
+            shadow: nth_span(src, "x", 1),
+            previous: vec![nth_span(src, "x", 0)],
+        }]
+    );
+}
+
+#[test]
+fn chain_lists_all_hidden_definitions() {
+    let src = "fn f() -> Int { let x = 1; let x = 2; let x = 3; x }";
+    let warnings = check_warnings(src, LintLevel::Warn);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        warnings[1],
+        Warning::ShadowedBinding {
+            name: "x".to_string(),
+            shadow: nth_span(src, "x", 2),
+            previous: vec![nth_span(src, "x", 0), nth_span(src, "x", 1)],
+        }
+    );
+}
+
+#[test]
+fn shadowing_capability_param() {
+    let src = r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn f(fs: FsCap, other: FsCap) -> () & {Fs} {
+            let fs = other;
+            use_cap(fs)
+        }
+    "#;
+    // The hidden `fs` is also never used, so filter to shadowing warnings
+    let names: Vec<String> = check_warnings(src, LintLevel::Warn)
+        .into_iter()
+        .filter_map(|w| match w {
+            Warning::ShadowedBinding { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, vec!["fs"]);
+}
+
+#[test]
+fn match_arm_binding_shadows_outer() {
+    let warnings = check_warnings(
+        "fn f(x: Int) -> Int { match x { x => x } }",
+        LintLevel::Warn,
+    );
+    assert_eq!(warnings.len(), 1);
+}
+
+// ============================================================================
+// NO WARNING
+// ============================================================================
+
+#[test]
+fn sibling_scopes_do_not_shadow() {
+    let warnings = check_warnings(
+        "fn f(b: Bool) -> Int { if b { let y = 1; y } else { let y = 2; y } }",
+        LintLevel::Warn,
+    );
+    assert!(warnings.is_empty(), "got: {warnings:?}");
+}
+
+#[test]
+fn binding_after_scope_ends_does_not_shadow() {
+    let warnings = check_warnings(
+        "fn f() -> Int { { let y = 1; y }; let y = 2; y }",
+        LintLevel::Warn,
+    );
+    assert!(warnings.is_empty(), "got: {warnings:?}");
+}
@@ -0,0 +1,51 @@
+//! Integration tests for checker snapshots, as a notebook frontend would
+//! use them: check cells one after another, rolling back speculative ones.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+/// Helper: check the cell `src` with `checker`
+fn check_cell(checker: &mut TypeChecker, src: &str) -> Result<(), TypeError> {
+    let module = parse_str("<cell>", src).expect("parse failed");
+    checker.check_module(&module)
+}
+
+#[test]
+fn restore_forgets_definitions_checked_since_the_snapshot() {
+    let mut checker = TypeChecker::new();
+    check_cell(&mut checker, "fn base() -> Int { 1 }").unwrap();
+    let saved = checker.snapshot();
+    let typed = checker.typed_items().len();
+
+    check_cell(&mut checker, "fn step() -> Int { base() + 1 }").unwrap();
+    check_cell(&mut checker, "fn later() -> Int { step() }").unwrap();
+    checker.restore(&saved);
+
+    assert_eq!(checker.typed_items().len(), typed);
+    let err = check_cell(&mut checker, "fn later() -> Int { step() }").unwrap_err();
+    assert!(
+        matches!(&err, TypeError::UnknownVariable { name, .. } if name == "step"),
+        "{err:?}"
+    );
+    // Earlier cells are still in scope
+    checker.restore(&saved);
+    check_cell(&mut checker, "fn other() -> Int { base() * 2 }").unwrap();
+}
+
+#[test]
+fn snapshots_are_independent_of_later_checks() {
+    let mut checker = TypeChecker::new();
+    let empty = checker.snapshot();
+    check_cell(&mut checker, "struct Point { x: Int }").unwrap();
+    assert!(checker.adt_registry().get("Point").is_some());
+
+    // A failed cell leaves nothing behind once rolled back
+    let with_point = checker.snapshot();
+    assert!(check_cell(&mut checker, "fn f(p: Point) -> Int { p }").is_err());
+    checker.restore(&with_point);
+    check_cell(&mut checker, "fn f(p: Point) -> Point { p }").unwrap();
+
+    checker.restore(&empty);
+    assert!(checker.adt_registry().get("Point").is_none());
+    assert!(checker.typed_items().is_empty());
+}
@@ -0,0 +1,99 @@
+//! Integration tests for the resolved types recorded by `check_module`.
+//!
+//! Every checked `fn`, `extern fn`, and top-level `let` yields a
+//! `TypedItem` in source order; functions also record the types of their
+//! local `let` bindings.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypedItem, TypedItemKind};
+
+/// Helper: parse and type-check, return the recorded items
+fn typed_items(src: &str) -> Vec<TypedItem> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.typed_items().to_vec()
+}
+
+#[test]
+fn items_recorded_in_source_order() {
+    let items = typed_items(
+        r#"
+        struct P { x: Int }
+        let k = 3;
+        extern fn now(t: &TimeCap) -> String & {Time};
+        fn one() -> Int & {} { 1 }
+        "#,
+    );
+    let summary: Vec<_> = items
+        .iter()
+        .map(|i| (i.name.as_str(), i.kind, i.ty.to_string()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("k", TypedItemKind::Let, "Int".to_string()),
+            (
+                "now",
+                TypedItemKind::ExternFn,
+                "&TimeCap -> String & {Time}".to_string()
+            ),
+            ("one", TypedItemKind::Fn, "() -> Int".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn fn_type_carries_inferred_effect_row() {
+    let items = typed_items(
+        r#"
+        extern fn read(fs: &FsCap, p: String) -> String & {Fs};
+        fn load(fs: FsCap) -> String & {Fs} { read(&fs, "a") }
+        "#,
+    );
+    let load = items.iter().find(|i| i.name == "load").unwrap();
+    assert_eq!(load.ty.to_string(), "FsCap -> String & {Fs}");
+}
+
+#[test]
+fn fn_records_resolved_local_let_types() {
+    let items = typed_items(
+        r#"
+        fn f() -> Int {
+            let (a, s) = (1, "x");
+            let mut b = a + 2;
+            b = b + 1;
+            b
+        }
+        "#,
+    );
+    let locals: Vec<_> = items[0]
+        .locals
+        .iter()
+        .map(|l| (l.name.as_str(), l.ty.to_string()))
+        .collect();
+    assert_eq!(
+        locals,
+        [
+            ("a", "Int".to_string()),
+            ("s", "String".to_string()),
+            ("b", "Int".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn locals_stay_with_their_item() {
+    let items = typed_items(
+        r#"
+        let top = { let inner = true; inner };
+        fn g() -> Int { let y = 2; y }
+        "#,
+    );
+    assert_eq!(items[0].locals.len(), 1);
+    assert_eq!(items[0].locals[0].name, "inner");
+    assert_eq!(items[1].locals.len(), 1);
+    assert_eq!(items[1].locals[0].name, "y");
+}
@@ -0,0 +1,155 @@
+//! Integration tests for the unused-capability lint.
+//!
+//! A capability that is never used, passed on, borrowed, or dropped is
+//! reported as a warning by default. Prefixing the name with `_` opts out.
+
+use strata_parse::parse_str;
+use strata_types::{Lint, LintLevel, TypeChecker, Warning};
+
+/// Helper: parse and type-check with default lint levels, return warnings
+fn check_warnings(src: &str) -> Vec<Warning> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker
+        .check_module(&module)
+        .unwrap_or_else(|e| panic!("expected OK but got error: {e}"));
+    checker.take_warnings()
+}
+
+/// Names reported by the unused-capability lint
+fn unused_names(src: &str) -> Vec<String> {
+    check_warnings(src)
+        .into_iter()
+        .filter_map(|w| match w {
+            Warning::UnusedCapability { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+// ============================================================================
+// REPORTED
+// ============================================================================
+
+#[test]
+fn unused_capability_param_warns_by_default() {
+    let warnings = check_warnings("fn f(fs: FsCap) -> () & {} { () }");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].lint(), Lint::UnusedCapability);
+    let msg = warnings[0].to_string();
+    assert!(
+        msg.contains("'fs'") && msg.contains("drop(fs)"),
+        "got: {msg}"
+    );
+    assert!(msg.contains("unused_capability"), "got: {msg}");
+}
+
+#[test]
+fn unused_let_bound_capability() {
+    assert_eq!(
+        unused_names("fn f(fs: FsCap) -> () & {} { let kept = fs; () }"),
+        vec!["kept"]
+    );
+}
+
+#[test]
+fn unused_pattern_bound_capability() {
+    assert_eq!(
+        unused_names("fn f(pair: (FsCap, NetCap)) -> () & {} { let (a, b) = pair; drop(a) }"),
+        vec!["b"]
+    );
+}
+
+#[test]
+fn deny_level_is_error() {
+    let module = parse_str("<test>", "fn f(fs: FsCap) -> () & {} { () }").expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::UnusedCapability, LintLevel::Deny);
+    let err = checker
+        .check_module(&module)
+        .expect_err("expected type error but got OK");
+    assert!(err.to_string().contains("(denied)"), "got: {err}");
+}
+
+#[test]
+fn allow_level_silences() {
+    let module = parse_str("<test>", "fn f(fs: FsCap) -> () & {} { () }").expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.set_lint_level(Lint::UnusedCapability, LintLevel::Allow);
+    checker.check_module(&module).expect("check failed");
+    assert!(checker.warnings().is_empty());
+}
+
+// ============================================================================
+// NOT REPORTED
+// ============================================================================
+
+#[test]
+fn dropped_capability_is_used() {
+    assert!(unused_names("fn f(fs: FsCap) -> () & {} { drop(fs) }").is_empty());
+}
+
+#[test]
+fn borrowed_capability_is_used() {
+    assert!(unused_names(
+        r#"
+        extern fn peek(fs: &FsCap) -> () & {Fs};
+        fn f(fs: FsCap) -> () & {Fs} { peek(&fs) }
+    "#
+    )
+    .is_empty());
+}
+
+#[test]
+fn capability_used_in_one_branch_is_used() {
+    assert!(unused_names(
+        r#"
+        extern fn use_cap(fs: FsCap) -> () & {Fs};
+        fn f(fs: FsCap, c: Bool) -> () & {Fs} {
+            if c { use_cap(fs) } else { () }
+        }
+    "#
+    )
+    .is_empty());
+}
+
+#[test]
+fn underscore_prefix_opts_out() {
+    assert!(unused_names("fn f(_fs: FsCap) -> () & {} { () }").is_empty());
+}
+
+#[test]
+fn borrowed_match_bindings_are_not_reported() {
+    // `drop(f)` would move out of a borrow, so there's nothing to suggest
+    assert!(unused_names(
+        "fn f(fs: FsCap) -> Int & {} { let pair = (fs, 1); \
+         let n = match &pair { (f, k) => k }; drop(pair); n }"
+    )
+    .is_empty());
+}
+
+#[test]
+fn suggested_drop_silences_the_warning() {
+    // Each program leaves one capability unused where `/*fix*/` is; doing
+    // as the warning says checks clean
+    for src in [
+        "fn f(fs: FsCap) -> () & {} { /*fix*/ () }",
+        "fn f(fs: FsCap) -> () & {} { let kept = fs; /*fix*/ () }",
+        "fn f(pair: (FsCap, NetCap)) -> () & {} { let (a, b) = pair; drop(a); /*fix*/ () }",
+        "fn f(pair: (FsCap, Int)) -> Int & {} { match pair { (c, k) => { /*fix*/ k } } }",
+    ] {
+        let warnings = check_warnings(src);
+        let [Warning::UnusedCapability { name, .. }] = warnings.as_slice() else {
+            panic!("{src}: expected one unused capability, got {warnings:?}");
+        };
+        let fix = format!("drop({name})");
+        assert!(warnings[0].to_string().contains(&fix), "{src}");
+        let fixed = src.replace("/*fix*/", &format!("{fix};"));
+        assert!(check_warnings(&fixed).is_empty(), "{fixed}");
+    }
+}
+
+#[test]
+fn unrestricted_values_not_reported() {
+    assert!(unused_names("fn f(x: Int) -> Int { 0 }").is_empty());
+}
@@ -0,0 +1,155 @@
+//! Integration tests for `use` imports of enum variants.
+//!
+//! `use Option::*;` lets `Some(x)` and `None` stand for `Option::Some(x)`
+//! and `Option::None`, in expressions and patterns alike. A variant name
+//! two imports could mean is an error wherever it is used.
+
+use strata_parse::parse_str;
+use strata_types::{TypeChecker, TypeError};
+
+fn check(src: &str) -> Result<TypeChecker, TypeError> {
+    let module = parse_str("<test>", src).expect("parse failed");
+    let mut checker = TypeChecker::new();
+    checker.check_module(&module)?;
+    Ok(checker)
+}
+
+/// Helper: parse and type-check, expect success
+fn check_ok(src: &str) -> TypeChecker {
+    check(src).unwrap_or_else(|e| panic!("expected OK but got error: {e}"))
+}
+
+/// Helper: parse and type-check, expect failure
+fn check_err(src: &str) -> TypeError {
+    check(src).err().expect("expected type error but got OK")
+}
+
+#[test]
+fn glob_import_allows_unqualified_option_variants() {
+    check_ok(
+        r#"
+        use Option::*;
+        fn first(o: Option<Int>) -> Int { match o { Some(n) => n, None => 0 } }
+        fn main() -> Int { first(Some(1)) + first(None) }
+        "#,
+    );
+}
+
+#[test]
+fn unqualified_variants_need_an_import() {
+    let err = check_err("fn main() -> Option<Int> { Some(1) }");
+    assert!(
+        matches!(&err, TypeError::UnknownVariable { name, .. } if name == "Some"),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn single_variant_import_brings_only_that_variant() {
+    let src = |body: &str| {
+        format!(
+            "enum Shape {{ Dot, Line(Int) }} use Shape::Line;
+             fn main() -> Shape {{ {body} }}"
+        )
+    };
+    check_ok(&src("Line(2)"));
+    assert!(matches!(
+        check_err(&src("Dot")),
+        TypeError::UnknownVariable { .. }
+    ));
+}
+
+#[test]
+fn imported_variants_are_checked_for_exhaustiveness() {
+    let err = check_err("use Option::*; fn f(o: Option<Int>) -> Int { match o { Some(n) => n } }");
+    assert!(
+        matches!(&err, TypeError::NonExhaustiveMatch { .. }),
+        "{err:?}"
+    );
+    assert!(err.to_string().contains("Option::None"), "{err}");
+}
+
+#[test]
+fn imported_unit_variant_in_a_pattern_matches_rather_than_binds() {
+    // `None` would otherwise bind anything and make `_` unreachable
+    check_ok(
+        "use Option::*;
+         fn f(o: Option<Int>) -> Int { match o { None => 0, _ => 1 } }",
+    );
+}
+
+#[test]
+fn locals_and_definitions_shadow_imported_variants() {
+    check_ok(
+        r#"
+        enum Level { Low, High }
+        use Level::*;
+        fn High() -> Int { 2 }
+        fn f(Low: Int) -> Int { Low + High() }
+        "#,
+    );
+}
+
+#[test]
+fn glob_imports_sharing_a_variant_are_ambiguous_where_used() {
+    let src = |body: &str| {
+        format!(
+            "enum A {{ X, Y }} enum B {{ X, Z }} use A::*; use B::*;
+             fn main() -> Int {{ {body} }}"
+        )
+    };
+    // Unused, the clash is harmless
+    check_ok(&src("let y = Y; let z = Z; 0"));
+    let err = check_err(&src("let x = X; 0"));
+    let TypeError::AmbiguousVariant { name, enums, .. } = &err else {
+        panic!("expected AmbiguousVariant, got {err:?}");
+    };
+    assert_eq!(name, "X");
+    assert_eq!(enums, &["A", "B"]);
+    assert_eq!(err.code(), "TYPE-AMBIGUOUS-VARIANT");
+    assert!(err.to_string().contains("`A::X` or `B::X`"), "{err}");
+}
+
+#[test]
+fn explicit_import_wins_over_a_glob() {
+    check_ok(
+        r#"
+        enum A { X, Y }
+        enum B { X, Z }
+        use A::*;
+        use B::X;
+        fn g(b: B) -> Int { match b { X => 10, Z => 20 } }
+        fn main() -> Int { g(X) }
+        "#,
+    );
+}
+
+#[test]
+fn conflicting_explicit_imports_are_ambiguous() {
+    let err = check_err("enum A { X } enum B { X } use A::X; use B::X;");
+    assert!(
+        matches!(&err, TypeError::AmbiguousVariant { name, .. } if name == "X"),
+        "{err:?}"
+    );
+    // The same import twice is fine
+    check_ok("enum A { X } use A::X; use A::X; use A::*;");
+}
+
+#[test]
+fn use_must_name_an_enum_and_its_variants() {
+    assert!(matches!(
+        check_err("use Missing::*;"),
+        TypeError::UnknownType { name, .. } if name == "Missing"
+    ));
+    let err = check_err("struct P { x: Int } use P::*;");
+    assert!(
+        matches!(&err, TypeError::UseNotEnum { name, .. } if name == "P"),
+        "{err:?}"
+    );
+    assert_eq!(err.code(), "TYPE-USE-NOT-ENUM");
+    assert!(matches!(
+        check_err("use Option::Maybe;"),
+        TypeError::UnknownVariant { type_name, variant, .. }
+            if type_name == "Option" && variant == "Maybe"
+    ));
+}